@@ -0,0 +1,78 @@
+/*
+ * C interface to the tree++ scanner (treepp_ffi.dll).
+ *
+ * Scans are configured with the same arguments as the command line, e.g.
+ * {"C:\\src", "/F", "/X", "target"}. All strings are UTF-8 and
+ * NUL-terminated. Strings returned by the library are freed with
+ * treepp_string_free.
+ *
+ * File: ffi/include/treepp.h
+ * Author: WaterRun
+ * Date: 2026-10-16
+ */
+
+#ifndef TREEPP_H
+#define TREEPP_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes; 1 to 3 match the exit codes of treepp. */
+#define TREEPP_OK 0              /* The call succeeded. */
+#define TREEPP_ERROR_ARGS 1      /* Invalid arguments, or a null pointer. */
+#define TREEPP_ERROR_SCAN 2      /* The scan failed, e.g. a missing root. */
+#define TREEPP_ERROR_OUTPUT 3    /* The tree could not be serialized. */
+#define TREEPP_ERROR_INTERNAL 70 /* The library panicked (a bug in tree++). */
+
+/* An entry passed to a treepp_entry_callback; the strings are only valid
+ * during the call. */
+typedef struct treepp_entry {
+    const char *path; /* Full path of the entry. */
+    const char *name; /* Name of the entry. */
+    uint32_t depth;   /* Depth below the root; children of the root are 0. */
+    uint8_t is_dir;   /* 1 for directories, 0 for files. */
+    uint64_t size;    /* File size in bytes (0 unless sizes are read, e.g. /S). */
+} treepp_entry;
+
+/* Called for each entry; a nonzero return value stops the scan. */
+typedef int32_t (*treepp_entry_callback)(const treepp_entry *entry, void *user_data);
+
+/*
+ * Scans a directory and returns the tree as JSON, the document
+ * `treepp /B /O tree.json` writes for the same arguments.
+ *
+ * On success *out receives the JSON, otherwise the error message. Either
+ * string must be freed with treepp_string_free.
+ */
+int32_t treepp_scan_json(const char *const *argv, size_t argc, char **out);
+
+/*
+ * Scans a directory and calls callback for each entry as it is found, in
+ * the order of the text tree. Stopping the scan from the callback is not
+ * an error.
+ *
+ * On failure *error receives the error message if error is not null; it
+ * must be freed with treepp_string_free.
+ */
+int32_t treepp_scan_callback(
+    const char *const *argv,
+    size_t argc,
+    treepp_entry_callback callback,
+    void *user_data,
+    char **error);
+
+/* Frees a string returned by treepp_scan_json or treepp_scan_callback. */
+void treepp_string_free(char *s);
+
+/* Returns the version of the library, e.g. "0.4.0"; must not be freed. */
+const char *treepp_version(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TREEPP_H */
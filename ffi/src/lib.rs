@@ -0,0 +1,420 @@
+//! C interface to the tree++ scanner.
+//!
+//! Builds `treepp_ffi.dll`, so that PowerShell, .NET and C++ tools can scan
+//! a directory without starting a `treepp` process. The functions are
+//! declared in `include/treepp.h`:
+//!
+//! - `treepp_scan_json`: scans and returns the tree as JSON (`/O tree.json`)
+//! - `treepp_scan_callback`: scans and calls back for each entry as it is found
+//! - `treepp_string_free`: frees the strings returned by the functions above
+//! - `treepp_version`: returns the version of the library
+//!
+//! Scans are configured with the same arguments as the command line, e.g.
+//! `{"C:\\src", "/F", "/X", "target"}`. All strings are UTF-8 and
+//! NUL-terminated. Panics are caught and reported as `TREEPP_ERROR_INTERNAL`.
+//!
+//! File: ffi/src/lib.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![deny(warnings)]
+#![deny(missing_docs)]
+
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::panic::{self, AssertUnwindSafe};
+
+use treepp::cli::{CliParser, ParseResult};
+use treepp::config::Config;
+use treepp::error::{ScanError, TreeppError};
+use treepp::output;
+use treepp::scan::{self, EntryKind, StreamEvent};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// The call succeeded.
+pub const TREEPP_OK: i32 = 0;
+
+/// The arguments are invalid, or a pointer argument is null.
+pub const TREEPP_ERROR_ARGS: i32 = 1;
+
+/// The scan failed, e.g. because the root directory does not exist.
+pub const TREEPP_ERROR_SCAN: i32 = 2;
+
+/// The tree could not be serialized.
+pub const TREEPP_ERROR_OUTPUT: i32 = 3;
+
+/// The library panicked; this is a bug in tree++.
+pub const TREEPP_ERROR_INTERNAL: i32 = 70;
+
+/// Version string returned by `treepp_version`, taken from the manifest.
+const VERSION: &CStr =
+    match CStr::from_bytes_with_nul(concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes()) {
+        Ok(version) => version,
+        Err(_) => panic!("the package version contains a NUL byte"),
+    };
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// An entry passed to a `treepp_entry_callback`.
+///
+/// The strings are only valid during the call.
+#[repr(C)]
+#[derive(Debug)]
+pub struct TreeppEntry {
+    /// Full path of the entry.
+    pub path: *const c_char,
+    /// Name of the entry.
+    pub name: *const c_char,
+    /// Depth below the root; children of the root have depth 0.
+    pub depth: u32,
+    /// 1 for directories, 0 for files.
+    pub is_dir: u8,
+    /// File size in bytes (0 unless sizes are read, e.g. with `/S`).
+    pub size: u64,
+}
+
+/// Called by `treepp_scan_callback` for each entry; a nonzero return value
+/// stops the scan.
+pub type TreeppEntryCallback =
+    Option<unsafe extern "C" fn(entry: *const TreeppEntry, user_data: *mut c_void) -> i32>;
+
+/// Error of an FFI call: the status code and the message for the caller.
+type FfiError = (i32, String);
+
+// ============================================================================
+// Exported Functions
+// ============================================================================
+
+/// Scans a directory and returns the tree as JSON.
+///
+/// The JSON is the document `treepp /B /O tree.json` writes for the same
+/// arguments. Output options such as `/O` are ignored.
+///
+/// # Returns
+///
+/// `TREEPP_OK` with the JSON in `*out`, or an error code with the error
+/// message in `*out`. Either string must be freed with `treepp_string_free`.
+///
+/// # Safety
+///
+/// `argv` must point to `argc` valid NUL-terminated strings (it may be null
+/// if `argc` is 0), and `out` must be a valid pointer to write to.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn treepp_scan_json(
+    argv: *const *const c_char,
+    argc: usize,
+    out: *mut *mut c_char,
+) -> i32 {
+    if out.is_null() {
+        return TREEPP_ERROR_ARGS;
+    }
+    // SAFETY: the caller guarantees that `argv` holds `argc` strings.
+    let result = unsafe { read_args(argv, argc) }.and_then(|args| guarded(|| scan_json(args)));
+    let (status, text) = match result {
+        Ok(json) => (TREEPP_OK, json),
+        Err(error) => error,
+    };
+    // SAFETY: `out` is not null and the caller guarantees it is writable.
+    unsafe { *out = into_c_string(text) };
+    status
+}
+
+/// Scans a directory and calls `callback` for each entry as it is found.
+///
+/// Entries come in the order the text tree lists them, with the same
+/// filters applied. A nonzero return value of the callback stops the scan;
+/// the call still returns `TREEPP_OK`.
+///
+/// # Returns
+///
+/// `TREEPP_OK`, or an error code with the error message in `*error` if
+/// `error` is not null. The message must be freed with `treepp_string_free`.
+///
+/// # Safety
+///
+/// `argv` must point to `argc` valid NUL-terminated strings (it may be null
+/// if `argc` is 0), `callback` must be safe to call with `user_data`, and
+/// `error` must be null or a valid pointer to write to.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn treepp_scan_callback(
+    argv: *const *const c_char,
+    argc: usize,
+    callback: TreeppEntryCallback,
+    user_data: *mut c_void,
+    error: *mut *mut c_char,
+) -> i32 {
+    let result = match callback {
+        None => Err((TREEPP_ERROR_ARGS, "callback is null".to_string())),
+        // SAFETY: the caller guarantees that `argv` holds `argc` strings.
+        Some(callback) => unsafe { read_args(argv, argc) }.and_then(|args| {
+            guarded(|| {
+                scan_entries(args, |entry| {
+                    // SAFETY: the caller guarantees that `callback` accepts
+                    // `user_data`; `entry` lives until the call returns.
+                    unsafe { callback(entry, user_data) }
+                })
+            })
+        }),
+    };
+    match result {
+        Ok(()) => TREEPP_OK,
+        Err((status, message)) => {
+            if !error.is_null() {
+                // SAFETY: `error` is not null and the caller guarantees it is writable.
+                unsafe { *error = into_c_string(message) };
+            }
+            status
+        }
+    }
+}
+
+/// Frees a string returned by this library.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by `treepp_scan_json` or
+/// `treepp_scan_callback` that has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn treepp_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: the caller guarantees that `s` came from `CString::into_raw`.
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Returns the version of the library, e.g. `0.4.0`.
+///
+/// The string is static and must not be freed.
+#[unsafe(no_mangle)]
+pub extern "C" fn treepp_version() -> *const c_char {
+    VERSION.as_ptr()
+}
+
+// ============================================================================
+// Internal Functions
+// ============================================================================
+
+/// Copies the C argument vector into owned strings.
+///
+/// # Errors
+///
+/// Returns `TREEPP_ERROR_ARGS` for a null vector or argument, or an
+/// argument that is not UTF-8.
+///
+/// # Safety
+///
+/// `argv` must point to `argc` valid NUL-terminated strings, or be null.
+unsafe fn read_args(argv: *const *const c_char, argc: usize) -> Result<Vec<String>, FfiError> {
+    if argc == 0 {
+        return Ok(Vec::new());
+    }
+    if argv.is_null() {
+        return Err((TREEPP_ERROR_ARGS, "argv is null".to_string()));
+    }
+    (0..argc)
+        .map(|i| {
+            // SAFETY: the caller guarantees `argc` readable elements.
+            let arg = unsafe { *argv.add(i) };
+            if arg.is_null() {
+                return Err((TREEPP_ERROR_ARGS, format!("argument {i} is null")));
+            }
+            // SAFETY: the caller guarantees NUL-terminated strings.
+            let arg = unsafe { CStr::from_ptr(arg) };
+            arg.to_str()
+                .map(str::to_string)
+                .map_err(|_| (TREEPP_ERROR_ARGS, format!("argument {i} is not UTF-8")))
+        })
+        .collect()
+}
+
+/// Runs `f`, turning a panic into `TREEPP_ERROR_INTERNAL`, since a panic
+/// must not unwind into the caller.
+fn guarded<T>(f: impl FnOnce() -> Result<T, FfiError>) -> Result<T, FfiError> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| Err((TREEPP_ERROR_INTERNAL, "tree++ panicked".to_string())))
+}
+
+/// Parses command-line style arguments into a validated configuration.
+fn parse_args(args: Vec<String>) -> Result<Config, FfiError> {
+    match CliParser::new(args).parse() {
+        Ok(ParseResult::Config(config)) => Ok(*config),
+        Ok(ParseResult::Help | ParseResult::Version | ParseResult::Settings(_)) => Err((
+            TREEPP_ERROR_ARGS,
+            "help, version and settings are not available".to_string(),
+        )),
+        Err(e) => Err(to_ffi_error(e.into())),
+    }
+}
+
+/// Scans the directory named by `args` and serializes the tree as JSON.
+fn scan_json(args: Vec<String>) -> Result<String, FfiError> {
+    let config = parse_args(args)?;
+    let stats = scan::scan(&config).map_err(to_ffi_error)?;
+    output::serialize_json(&stats.tree.to_tree(), &config)
+        .map_err(|e| to_ffi_error(TreeppError::Output(e)))
+}
+
+/// Streams the entries of the directory named by `args` to `callback`.
+fn scan_entries(
+    args: Vec<String>,
+    mut callback: impl FnMut(&TreeppEntry) -> i32,
+) -> Result<(), FfiError> {
+    let config = parse_args(args)?;
+    scan::scan_streaming(&config, |event| {
+        let StreamEvent::Entry(entry) = event else {
+            return Ok(());
+        };
+        let path = to_c_string(entry.path.to_string_lossy().into_owned());
+        let name = to_c_string(entry.name);
+        let c_entry = TreeppEntry {
+            path: path.as_ptr(),
+            name: name.as_ptr(),
+            depth: u32::try_from(entry.depth).unwrap_or(u32::MAX),
+            is_dir: u8::from(entry.kind == EntryKind::Directory),
+            size: entry.metadata.size,
+        };
+        if callback(&c_entry) == 0 {
+            Ok(())
+        } else {
+            // Ends the scan like a closed output, without an error.
+            Err(ScanError::OutputClosed)
+        }
+    })
+    .map(|_| ())
+    .map_err(to_ffi_error)
+}
+
+/// Maps an error to its status code, using the exit codes of the CLI.
+fn to_ffi_error(err: TreeppError) -> FfiError {
+    let status = match err {
+        TreeppError::Cli(_) | TreeppError::Config(_) => TREEPP_ERROR_ARGS,
+        TreeppError::Scan(_) | TreeppError::Match(_) => TREEPP_ERROR_SCAN,
+        TreeppError::Render(_) | TreeppError::Output(_) => TREEPP_ERROR_OUTPUT,
+    };
+    (status, err.to_string())
+}
+
+/// Converts a string to a C string, dropping interior NUL characters.
+fn to_c_string(s: String) -> CString {
+    CString::new(s).unwrap_or_else(|e| {
+        let mut bytes = e.into_vec();
+        bytes.retain(|&b| b != 0);
+        CString::new(bytes).expect("NUL characters were removed")
+    })
+}
+
+/// Hands a string to the caller, who frees it with `treepp_string_free`.
+fn into_c_string(s: String) -> *mut c_char {
+    to_c_string(s).into_raw()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::ptr;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Calls `treepp_scan_json` with Rust strings.
+    fn scan_json_with(args: &[&str]) -> (i32, String) {
+        let owned: Vec<CString> = args.iter().map(|a| CString::new(*a).unwrap()).collect();
+        let argv: Vec<*const c_char> = owned.iter().map(|a| a.as_ptr()).collect();
+        let mut out = ptr::null_mut();
+        let status = unsafe { treepp_scan_json(argv.as_ptr(), argv.len(), &mut out) };
+        let text = unsafe { CStr::from_ptr(out) }
+            .to_string_lossy()
+            .into_owned();
+        unsafe { treepp_string_free(out) };
+        (status, text)
+    }
+
+    /// Collects the names passed to the callback into a `Vec<String>`.
+    unsafe extern "C" fn collect_names(entry: *const TreeppEntry, user_data: *mut c_void) -> i32 {
+        let names = unsafe { &mut *user_data.cast::<Vec<String>>() };
+        let name = unsafe { CStr::from_ptr((*entry).name) };
+        names.push(name.to_string_lossy().into_owned());
+        0
+    }
+
+    /// Stops the scan at the first entry.
+    unsafe extern "C" fn stop_at_first(_entry: *const TreeppEntry, _user_data: *mut c_void) -> i32 {
+        1
+    }
+
+    fn setup_test_dir() -> TempDir {
+        let dir = TempDir::new().expect("创建临时目录失败");
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src").join("main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        dir
+    }
+
+    #[test]
+    fn should_return_json_tree() {
+        let dir = setup_test_dir();
+        let (status, json) = scan_json_with(&[dir.path().to_str().unwrap(), "/F"]);
+        assert_eq!(status, TREEPP_OK, "{json}");
+        assert!(json.contains("\"schema\": \"treepp.pretty.v1\""), "{json}");
+        assert!(json.contains("main.rs"), "{json}");
+    }
+
+    #[test]
+    fn should_report_invalid_arguments() {
+        let (status, message) = scan_json_with(&["/NO-SUCH-OPTION"]);
+        assert_eq!(status, TREEPP_ERROR_ARGS);
+        assert!(!message.is_empty());
+
+        let mut out = ptr::null_mut();
+        let status = unsafe { treepp_scan_json(ptr::null(), 1, &mut out) };
+        assert_eq!(status, TREEPP_ERROR_ARGS, "null argv 应被拒绝");
+        unsafe { treepp_string_free(out) };
+    }
+
+    #[test]
+    fn should_call_back_for_each_entry() {
+        let dir = setup_test_dir();
+        let root = dir.path().to_str().unwrap();
+        let owned = [CString::new(root).unwrap(), CString::new("/F").unwrap()];
+        let argv: Vec<*const c_char> = owned.iter().map(|a| a.as_ptr()).collect();
+
+        let mut names: Vec<String> = Vec::new();
+        let status = unsafe {
+            treepp_scan_callback(
+                argv.as_ptr(),
+                argv.len(),
+                Some(collect_names),
+                (&mut names as *mut Vec<String>).cast(),
+                ptr::null_mut(),
+            )
+        };
+        assert_eq!(status, TREEPP_OK);
+        assert_eq!(names, ["Cargo.toml", "src", "main.rs"]);
+
+        let status = unsafe {
+            treepp_scan_callback(
+                argv.as_ptr(),
+                argv.len(),
+                Some(stop_at_first),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        assert_eq!(status, TREEPP_OK, "回调停止扫描不是错误");
+    }
+
+    #[test]
+    fn should_return_static_version() {
+        let version = unsafe { CStr::from_ptr(treepp_version()) };
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+    }
+}
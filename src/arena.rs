@@ -0,0 +1,1689 @@
+//! Arena module: compact index-based storage for scanned trees.
+//!
+//! A `TreeNode` owns its name, its full path and a vector of child nodes,
+//! which adds up to several hundred bytes per entry and dominates memory on
+//! multi-million-entry scans. Batch scans therefore build a `TreeArena`:
+//!
+//! - **Index links**: nodes live in one vector and reference their parent
+//!   and children by `NodeId`
+//! - **Interned names**: each distinct entry name is stored once, so the
+//!   thousands of `index.js` or `mod.rs` files share a single allocation
+//! - **Relative paths**: no path is stored per node; `TreeArena::path`
+//!   rebuilds it from the root path and the names of the ancestors
+//!
+//! `TreeNode` remains the public tree type for building and inspecting
+//! small trees; `TreeArena::from_tree` and `TreeArena::to_tree` convert
+//! between the two representations.
+//!
+//! File: src/arena.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::SortKey;
+use crate::matching::FilterReason;
+use crate::scan::{EntryError, EntryKind, EntryMetadata, SortOrder, TreeNode, compare_entries};
+
+// ============================================================================
+// Identifiers
+// ============================================================================
+
+/// Index of a node inside a `TreeArena`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::arena::TreeArena;
+/// use treepp::scan::{EntryKind, EntryMetadata};
+///
+/// let mut arena = TreeArena::new(PathBuf::from("project"));
+/// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+/// let src = arena.add(Some(root), "src", EntryKind::Directory, EntryMetadata::default());
+/// assert_ne!(src, root);
+/// assert_eq!(arena.get(src).id(), src);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    /// Returns the position of the node in the arena.
+    #[must_use]
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Index of an interned name inside a `NameTable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NameId(u32);
+
+// ============================================================================
+// Name Interning
+// ============================================================================
+
+/// Interned entry names.
+///
+/// The lookup map and the name list share the same `Arc<str>` allocation.
+#[derive(Debug, Default)]
+struct NameTable {
+    names: Vec<Arc<str>>,
+    lookup: HashMap<Arc<str>, NameId>,
+}
+
+impl NameTable {
+    /// Returns the id of `name`, storing it on first use.
+    fn intern(&mut self, name: &str) -> NameId {
+        if let Some(&id) = self.lookup.get(name) {
+            return id;
+        }
+        let id = NameId(to_u32(self.names.len()));
+        let name: Arc<str> = Arc::from(name);
+        self.names.push(Arc::clone(&name));
+        self.lookup.insert(name, id);
+        id
+    }
+
+    /// Returns the name stored under `id`.
+    fn get(&self, id: NameId) -> &str {
+        &self.names[id.0 as usize]
+    }
+
+    /// Returns the number of distinct names.
+    fn len(&self) -> usize {
+        self.names.len()
+    }
+}
+
+/// Converts an arena position to its 32-bit form.
+///
+/// # Panics
+///
+/// Panics if the arena holds more than `u32::MAX` entries.
+fn to_u32(index: usize) -> u32 {
+    u32::try_from(index).expect("tree arena exceeds u32::MAX entries")
+}
+
+// ============================================================================
+// Arena
+// ============================================================================
+
+/// A node stored in a `TreeArena`.
+///
+/// Mirrors the fields of `TreeNode` without the owned name and path.
+#[derive(Debug)]
+struct ArenaNode {
+    name: NameId,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    kind: EntryKind,
+    metadata: EntryMetadata,
+    disk_usage: Option<u64>,
+    previous_usage: Option<u64>,
+    filtered: Option<Box<FilterReason>>,
+    omitted: usize,
+    small_dirs: usize,
+    collapsed: Option<usize>,
+    error: Option<EntryError>,
+    elided: bool,
+    annotations: Box<[String]>,
+}
+
+/// A directory tree stored as a flat vector of index-linked nodes.
+///
+/// The first node added is the root; its path is the arena's root path.
+/// Nodes detached from the tree (by pruning or truncation) keep their slot
+/// but are no longer reachable from the root.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::arena::TreeArena;
+/// use treepp::scan::{EntryKind, EntryMetadata};
+///
+/// let mut arena = TreeArena::new(PathBuf::from("project"));
+/// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+/// arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+///
+/// let file = arena.root().children().next().unwrap();
+/// assert_eq!(file.name(), "main.rs");
+/// assert_eq!(file.path(), PathBuf::from("project").join("main.rs"));
+/// ```
+#[derive(Debug)]
+pub struct TreeArena {
+    root_path: PathBuf,
+    nodes: Vec<ArenaNode>,
+    names: NameTable,
+}
+
+impl TreeArena {
+    /// Creates an empty arena for a tree rooted at `root_path`.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_path` - Path of the root node, used to rebuild entry paths.
+    ///
+    /// # Returns
+    ///
+    /// An arena without nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    /// use treepp::arena::TreeArena;
+    ///
+    /// let arena = TreeArena::new(PathBuf::from("project"));
+    /// assert!(arena.is_empty());
+    /// assert_eq!(arena.root_path(), Path::new("project"));
+    /// ```
+    #[must_use]
+    pub fn new(root_path: PathBuf) -> Self {
+        Self {
+            root_path,
+            nodes: Vec::new(),
+            names: NameTable::default(),
+        }
+    }
+
+    /// Adds a node and links it to its parent.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent` - The parent node, or `None` for the root.
+    /// * `name` - Entry name without path components.
+    /// * `kind` - Type of the entry.
+    /// * `metadata` - Entry metadata.
+    ///
+    /// # Returns
+    ///
+    /// The id of the new node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena already holds `u32::MAX` nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let src = arena.add(Some(root), "src", EntryKind::Directory, EntryMetadata::default());
+    /// arena.add(Some(src), "lib.rs", EntryKind::File, EntryMetadata::default());
+    /// assert_eq!(arena.len(), 3);
+    /// assert_eq!(arena.get(src).children().len(), 1);
+    /// ```
+    pub fn add(
+        &mut self,
+        parent: Option<NodeId>,
+        name: &str,
+        kind: EntryKind,
+        metadata: EntryMetadata,
+    ) -> NodeId {
+        let id = NodeId(to_u32(self.nodes.len()));
+        let name = self.names.intern(name);
+        self.nodes.push(ArenaNode {
+            name,
+            parent,
+            children: Vec::new(),
+            kind,
+            metadata,
+            disk_usage: None,
+            previous_usage: None,
+            filtered: None,
+            omitted: 0,
+            small_dirs: 0,
+            collapsed: None,
+            error: None,
+            elided: false,
+            annotations: Box::default(),
+        });
+        if let Some(parent) = parent {
+            self.nodes[parent.index()].children.push(id);
+        }
+        id
+    }
+
+    /// Marks a node as kept by `--show-filtered` for the given reason.
+    pub(crate) fn set_filtered(&mut self, id: NodeId, reason: Option<FilterReason>) {
+        self.nodes[id.index()].filtered = reason.map(Box::new);
+    }
+
+    /// Records the entry count of a directory collapsed by `--filelimit`.
+    pub(crate) fn set_collapsed(&mut self, id: NodeId, collapsed: Option<usize>) {
+        self.nodes[id.index()].collapsed = collapsed;
+    }
+
+    /// Records the cumulative size of a directory in the previous run (`--du-delta`).
+    pub(crate) fn set_previous_usage(&mut self, id: NodeId, usage: Option<u64>) {
+        self.nodes[id.index()].previous_usage = usage;
+    }
+
+    /// Records why the content of an unreadable directory is missing.
+    pub(crate) fn set_error(&mut self, id: NodeId, error: Option<EntryError>) {
+        self.nodes[id.index()].error = error;
+    }
+
+    /// Returns the root node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no node has been added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// assert_eq!(arena.root().id(), root);
+    /// assert_eq!(arena.root().name(), "project");
+    /// ```
+    #[must_use]
+    pub fn root(&self) -> NodeRef<'_> {
+        assert!(!self.nodes.is_empty(), "tree arena has no root node");
+        self.get(NodeId(0))
+    }
+
+    /// Returns a handle to the node with the given id.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let file = arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+    /// assert_eq!(arena.get(file).name(), "main.rs");
+    /// ```
+    #[must_use]
+    pub fn get(&self, id: NodeId) -> NodeRef<'_> {
+        NodeRef { arena: self, id }
+    }
+
+    /// Returns the root path of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    /// use treepp::arena::TreeArena;
+    ///
+    /// let arena = TreeArena::new(PathBuf::from("project"));
+    /// assert_eq!(arena.root_path(), Path::new("project"));
+    /// ```
+    #[must_use]
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+
+    /// Returns the number of stored nodes, including detached ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let file = arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+    /// assert_eq!(arena.len(), 2);
+    /// ```
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if no node has been added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// assert!(arena.is_empty());
+    /// arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// assert!(!arena.is_empty());
+    /// ```
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the number of distinct interned names.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// for dir in ["a", "b"] {
+    ///     let dir = arena.add(Some(root), dir, EntryKind::Directory, EntryMetadata::default());
+    ///     arena.add(Some(dir), "mod.rs", EntryKind::File, EntryMetadata::default());
+    /// }
+    /// assert_eq!(arena.len(), 5);
+    /// assert_eq!(arena.name_count(), 4);
+    /// ```
+    #[must_use]
+    pub fn name_count(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Rebuilds the full path of a node from the root path.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The node whose path to build.
+    ///
+    /// # Returns
+    ///
+    /// The root path joined with the names of all ancestors below the root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let src = arena.add(Some(root), "src", EntryKind::Directory, EntryMetadata::default());
+    /// let lib = arena.add(Some(src), "lib.rs", EntryKind::File, EntryMetadata::default());
+    /// assert_eq!(arena.path(lib), PathBuf::from("project").join("src").join("lib.rs"));
+    /// ```
+    #[must_use]
+    pub fn path(&self, id: NodeId) -> PathBuf {
+        let mut names = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.nodes[current.index()].parent {
+            names.push(self.names.get(self.nodes[current.index()].name));
+            current = parent;
+        }
+        let mut path = self.root_path.clone();
+        for name in names.iter().rev() {
+            path.push(name);
+        }
+        path
+    }
+
+    /// Copies a `TreeNode` tree into a new arena.
+    ///
+    /// Entry paths are rebuilt from the root node's path, so child paths are
+    /// expected to extend their parent's path by their name.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The root node of the tree to copy.
+    ///
+    /// # Returns
+    ///
+    /// An arena holding the same tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{TreeNode, EntryKind, EntryMetadata};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.children.push(TreeNode::new(
+    ///     PathBuf::from("project/main.rs"),
+    ///     EntryKind::File,
+    ///     EntryMetadata::default(),
+    /// ));
+    /// let arena = TreeArena::from_tree(&root);
+    /// assert_eq!(arena.root().children().len(), 1);
+    /// ```
+    #[must_use]
+    pub fn from_tree(tree: &TreeNode) -> Self {
+        let mut arena = Self::new(tree.path.clone());
+        arena.copy_node(tree, None);
+        arena
+    }
+
+    /// Copies `node` and its subtree below `parent`.
+    fn copy_node(&mut self, node: &TreeNode, parent: Option<NodeId>) {
+        let id = self.add(parent, &node.name, node.kind, node.metadata.clone());
+        let entry = &mut self.nodes[id.index()];
+        entry.disk_usage = node.disk_usage;
+        entry.filtered = node.filtered.clone().map(Box::new);
+        entry.omitted = node.omitted;
+        entry.small_dirs = node.small_dirs;
+        entry.collapsed = node.collapsed;
+        entry.error = node.error;
+        entry.annotations = node.annotations.clone().into_boxed_slice();
+        for child in &node.children {
+            self.copy_node(child, Some(id));
+        }
+    }
+
+    /// Builds a `TreeNode` tree from the arena.
+    ///
+    /// # Returns
+    ///
+    /// The root `TreeNode`, with full paths for every entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let file = arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+    /// let tree = arena.to_tree();
+    /// assert_eq!(tree.children[0].path, PathBuf::from("project").join("main.rs"));
+    /// ```
+    #[must_use]
+    pub fn to_tree(&self) -> TreeNode {
+        self.build_node(self.root(), self.root_path.clone())
+    }
+
+    /// Builds the `TreeNode` for `node`, whose path is `path`.
+    fn build_node(&self, node: NodeRef<'_>, path: PathBuf) -> TreeNode {
+        let children = node
+            .children()
+            .map(|child| self.build_node(child, path.join(child.name())))
+            .collect();
+        let mut tree =
+            TreeNode::with_children(path, node.kind(), node.metadata().clone(), children);
+        tree.name = node.name().to_string();
+        tree.disk_usage = node.disk_usage();
+        tree.filtered = node.filtered().cloned();
+        tree.omitted = node.omitted();
+        tree.small_dirs = node.small_dirs();
+        tree.collapsed = node.collapsed();
+        tree.error = node.error();
+        tree.annotations = node.annotations().to_vec();
+        tree
+    }
+
+    // ------------------------------------------------------------------------
+    // Tree Operations
+    // ------------------------------------------------------------------------
+
+    /// Sorts the children of every node in display order.
+    ///
+    /// Uses the same ordering as `scan::sort_tree_by`, except that
+    /// directories sorted by `SortKey::Share` are compared by their
+    /// cumulative size, which requires `compute_disk_usage` to have run.
+    /// Interleaved with files, they are compared with the size of the files.
+    pub(crate) fn sort(&mut self, order: SortOrder) {
+        for index in 0..self.nodes.len() {
+            if self.nodes[index].children.len() < 2 {
+                continue;
+            }
+            let mut children = std::mem::take(&mut self.nodes[index].children);
+            children.sort_by(|&a, &b| {
+                let (a, b) = (&self.nodes[a.index()], &self.nodes[b.index()]);
+                let by_usage = if order.key == SortKey::Share && order.interleave {
+                    let usage = |node: &ArenaNode| match node.kind {
+                        EntryKind::Directory => node.disk_usage.unwrap_or(0),
+                        _ => node.metadata.size,
+                    };
+                    let cmp = usage(a).cmp(&usage(b));
+                    if order.reverse { cmp.reverse() } else { cmp }
+                } else if order.key == SortKey::Share
+                    && a.kind == EntryKind::Directory
+                    && b.kind == EntryKind::Directory
+                {
+                    let cmp = a.disk_usage.cmp(&b.disk_usage);
+                    if order.reverse { cmp.reverse() } else { cmp }
+                } else {
+                    Ordering::Equal
+                };
+                by_usage.then_with(|| {
+                    compare_entries(
+                        a.kind,
+                        self.names.get(a.name),
+                        &a.metadata,
+                        b.kind,
+                        self.names.get(b.name),
+                        &b.metadata,
+                        order,
+                    )
+                })
+            });
+            self.nodes[index].children = children;
+        }
+    }
+
+    /// Computes cumulative directory sizes below the root.
+    ///
+    /// Behaves like `TreeNode::compute_disk_usage`.
+    ///
+    /// # Returns
+    ///
+    /// The cumulative size of the whole tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let src = arena.add(Some(root), "src", EntryKind::Directory, EntryMetadata::default());
+    /// let metadata = EntryMetadata { size: 100, ..Default::default() };
+    /// arena.add(Some(src), "lib.rs", EntryKind::File, metadata);
+    /// assert_eq!(arena.compute_disk_usage(), 100);
+    /// assert_eq!(arena.get(src).disk_usage(), Some(100));
+    /// ```
+    pub fn compute_disk_usage(&mut self) -> u64 {
+        self.disk_usage_of(NodeId(0))
+    }
+
+    /// Computes and stores the disk usage of the subtree at `id`.
+    fn disk_usage_of(&mut self, id: NodeId) -> u64 {
+        let node = &self.nodes[id.index()];
+        if node.filtered.is_some() {
+            return 0;
+        }
+        if node.kind == EntryKind::File {
+            return node.metadata.size;
+        }
+        let children = std::mem::take(&mut self.nodes[id.index()].children);
+        let total = children.iter().map(|&c| self.disk_usage_of(c)).sum();
+        let node = &mut self.nodes[id.index()];
+        node.children = children;
+        node.disk_usage = Some(total);
+        total
+    }
+
+    /// Removes directories that contain no files.
+    ///
+    /// Behaves like `TreeNode::prune_empty_directories`.
+    pub(crate) fn prune_empty_directories(&mut self) {
+        self.prune_node(NodeId(0));
+    }
+
+    /// Prunes the subtree at `id`, returning whether it has content.
+    fn prune_node(&mut self, id: NodeId) -> bool {
+        let node = &self.nodes[id.index()];
+        if node.filtered.is_some() {
+            return false;
+        }
+        if node.kind == EntryKind::File || node.collapsed.is_some() || node.error.is_some() {
+            return true;
+        }
+        let mut children = std::mem::take(&mut self.nodes[id.index()].children);
+        let mut has_content = false;
+        children.retain(|&c| {
+            let keep = self.prune_node(c);
+            has_content |= keep;
+            keep || self.nodes[c.index()].filtered.is_some()
+        });
+        self.nodes[id.index()].children = children;
+        has_content
+    }
+
+    /// Detaches directories whose cumulative size is below `threshold`.
+    ///
+    /// Requires `compute_disk_usage` to have run. Each directory records how
+    /// many of its subdirectories were detached. Entries kept by
+    /// `--show-filtered` are never detached.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - Smallest cumulative size, in bytes, that is kept.
+    pub(crate) fn hide_small_directories(&mut self, threshold: u64) {
+        for index in 0..self.nodes.len() {
+            let mut children = std::mem::take(&mut self.nodes[index].children);
+            let before = children.len();
+            children.retain(|&c| {
+                let child = &self.nodes[c.index()];
+                child.kind != EntryKind::Directory
+                    || child.filtered.is_some()
+                    || child.disk_usage.unwrap_or(0) >= threshold
+            });
+            self.nodes[index].small_dirs = before - children.len();
+            self.nodes[index].children = children;
+        }
+    }
+
+    /// Detaches all file nodes, keeping only directories.
+    pub(crate) fn remove_files(&mut self) {
+        for index in 0..self.nodes.len() {
+            let mut children = std::mem::take(&mut self.nodes[index].children);
+            children.retain(|&c| self.nodes[c.index()].kind == EntryKind::Directory);
+            self.nodes[index].children = children;
+        }
+    }
+
+    /// Drops the content of directories collapsed by `--filelimit`.
+    pub(crate) fn clear_collapsed(&mut self) {
+        for node in &mut self.nodes {
+            if node.collapsed.is_some() {
+                node.children.clear();
+            }
+        }
+    }
+
+    /// Keeps the first `limit` displayed children of every directory.
+    ///
+    /// Behaves like the `--max-entries` truncation of `TreeNode` trees: only
+    /// displayed entries count, and directories at or below `max_depth` are
+    /// left alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of displayed entries per directory.
+    /// * `show_files` - Whether files are displayed.
+    /// * `max_depth` - The display depth limit, if any.
+    pub(crate) fn truncate(&mut self, limit: usize, show_files: bool, max_depth: Option<usize>) {
+        self.truncate_node(NodeId(0), limit, show_files, max_depth, 0);
+    }
+
+    /// Truncates the children of `id` and recurses into its directories.
+    fn truncate_node(
+        &mut self,
+        id: NodeId,
+        limit: usize,
+        show_files: bool,
+        max_depth: Option<usize>,
+        depth: usize,
+    ) {
+        if max_depth.is_some_and(|max| depth >= max) {
+            return;
+        }
+
+        let mut children = std::mem::take(&mut self.nodes[id.index()].children);
+        let is_listed = |nodes: &[ArenaNode], c: NodeId| {
+            show_files || nodes[c.index()].kind == EntryKind::Directory
+        };
+        let listed = children
+            .iter()
+            .filter(|&&c| is_listed(&self.nodes, c))
+            .count();
+        if listed > limit {
+            let mut seen = 0;
+            children.retain(|&c| {
+                if !is_listed(&self.nodes, c) {
+                    return true;
+                }
+                seen += 1;
+                seen <= limit
+            });
+            self.nodes[id.index()].omitted = listed - limit;
+        }
+
+        for &child in &children {
+            if self.nodes[child.index()].kind == EntryKind::Directory {
+                self.truncate_node(child, limit, show_files, max_depth, depth + 1);
+            }
+        }
+        self.nodes[id.index()].children = children;
+    }
+
+    /// Keeps only the last `levels` levels of deep branches.
+    ///
+    /// A subtree spans one level per entry on its longest path, so a file
+    /// spans one level. Directories below the root whose subtree spans more
+    /// than `levels` levels are dropped from the display, and their shallow
+    /// descendants are attached to the root in their place, in display
+    /// order. Hoisted nodes are marked as elided and keep their parent, so
+    /// their paths are unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `levels` - Number of levels kept at the bottom of each branch.
+    pub(crate) fn keep_leaf_levels(&mut self, levels: usize) {
+        let mut spans = vec![0; self.nodes.len()];
+        self.span_of(NodeId(0), &mut spans);
+
+        let children = std::mem::take(&mut self.nodes[0].children);
+        let mut kept = Vec::with_capacity(children.len());
+        for child in children {
+            self.collect_leaf_levels(child, levels, &spans, false, &mut kept);
+        }
+        self.nodes[0].children = kept;
+    }
+
+    /// Records the number of levels spanned by the subtree at `id`.
+    fn span_of(&self, id: NodeId, spans: &mut [usize]) -> usize {
+        let deepest = self.nodes[id.index()]
+            .children
+            .iter()
+            .map(|&c| self.span_of(c, spans))
+            .max()
+            .unwrap_or(0);
+        spans[id.index()] = deepest + 1;
+        deepest + 1
+    }
+
+    /// Collects the nodes shown in place of `id` when it is too deep.
+    fn collect_leaf_levels(
+        &mut self,
+        id: NodeId,
+        levels: usize,
+        spans: &[usize],
+        hoisted: bool,
+        out: &mut Vec<NodeId>,
+    ) {
+        if spans[id.index()] <= levels {
+            self.nodes[id.index()].elided = hoisted;
+            out.push(id);
+            return;
+        }
+        let children = self.nodes[id.index()].children.clone();
+        for child in children {
+            self.collect_leaf_levels(child, levels, spans, true, out);
+        }
+    }
+
+    /// Counts the directories below the root.
+    ///
+    /// Behaves like `TreeNode::count_directories`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let src = arena.add(Some(root), "src", EntryKind::Directory, EntryMetadata::default());
+    /// arena.add(Some(src), "lib.rs", EntryKind::File, EntryMetadata::default());
+    /// assert_eq!(arena.count_directories(), 1);
+    /// ```
+    #[must_use]
+    pub fn count_directories(&self) -> usize {
+        self.root().count_directories()
+    }
+
+    /// Counts the files in the tree.
+    ///
+    /// Behaves like `TreeNode::count_files`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let src = arena.add(Some(root), "src", EntryKind::Directory, EntryMetadata::default());
+    /// arena.add(Some(src), "lib.rs", EntryKind::File, EntryMetadata::default());
+    /// assert_eq!(arena.count_files(), 1);
+    /// ```
+    #[must_use]
+    pub fn count_files(&self) -> usize {
+        self.root().count_files()
+    }
+
+    /// Counts the directories truncated by `--max-entries`.
+    ///
+    /// Behaves like `TreeNode::count_truncated`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata, TreeNode};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.omitted = 2;
+    /// let arena = TreeArena::from_tree(&root);
+    /// assert_eq!(arena.count_truncated(), 1);
+    /// ```
+    #[must_use]
+    pub fn count_truncated(&self) -> usize {
+        self.root().count_truncated()
+    }
+
+    /// Counts the directories left unread after the scan budget ran out.
+    ///
+    /// Behaves like `TreeNode::count_unread`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryError, EntryKind, EntryMetadata, TreeNode};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.error = Some(EntryError::BudgetExhausted);
+    /// let arena = TreeArena::from_tree(&root);
+    /// assert_eq!(arena.count_unread(), 1);
+    /// ```
+    #[must_use]
+    pub fn count_unread(&self) -> usize {
+        self.root().count_unread()
+    }
+}
+
+// ============================================================================
+// Node Handle
+// ============================================================================
+
+/// A read-only handle to a node in a `TreeArena`.
+///
+/// Provides the same information as the fields of `TreeNode`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::arena::TreeArena;
+/// use treepp::scan::{EntryKind, EntryMetadata};
+///
+/// let mut arena = TreeArena::new(PathBuf::from("project"));
+/// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+/// let file = arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+/// let node = arena.get(file);
+/// assert_eq!(node.kind(), EntryKind::File);
+/// assert_eq!(node.parent().unwrap().id(), root);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct NodeRef<'a> {
+    arena: &'a TreeArena,
+    id: NodeId,
+}
+
+impl<'a> NodeRef<'a> {
+    /// Returns the stored node.
+    fn node(&self) -> &'a ArenaNode {
+        &self.arena.nodes[self.id.index()]
+    }
+
+    /// Returns the id of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let file = arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+    /// assert_eq!(arena.get(file).id(), file);
+    /// ```
+    #[must_use]
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Returns the entry name without path components.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let file = arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+    /// assert_eq!(arena.get(file).name(), "main.rs");
+    /// ```
+    #[must_use]
+    pub fn name(&self) -> &'a str {
+        self.arena.names.get(self.node().name)
+    }
+
+    /// Returns the full path of the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let file = arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+    /// assert_eq!(arena.get(file).path(), PathBuf::from("project").join("main.rs"));
+    /// ```
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        self.arena.path(self.id)
+    }
+
+    /// Returns the type of the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let file = arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+    /// assert_eq!(arena.get(file).kind(), EntryKind::File);
+    /// assert_eq!(arena.root().kind(), EntryKind::Directory);
+    /// ```
+    #[must_use]
+    pub fn kind(&self) -> EntryKind {
+        self.node().kind
+    }
+
+    /// Returns the entry metadata.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let metadata = EntryMetadata { size: 42, ..Default::default() };
+    /// let file = arena.add(Some(root), "data.bin", EntryKind::File, metadata);
+    /// assert_eq!(arena.get(file).metadata().size, 42);
+    /// ```
+    #[must_use]
+    pub fn metadata(&self) -> &'a EntryMetadata {
+        &self.node().metadata
+    }
+
+    /// Returns the cumulative size for disk usage display.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata, TreeNode};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.disk_usage = Some(4096);
+    /// let arena = TreeArena::from_tree(&root);
+    /// assert_eq!(arena.root().disk_usage(), Some(4096));
+    /// ```
+    #[must_use]
+    pub fn disk_usage(&self) -> Option<u64> {
+        self.node().disk_usage
+    }
+
+    /// Returns the cumulative size in the previous run, set by `--du-delta`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// assert_eq!(arena.root().previous_usage(), None);
+    /// ```
+    #[must_use]
+    pub fn previous_usage(&self) -> Option<u64> {
+        self.node().previous_usage
+    }
+
+    /// Returns the cumulative size of the entry as a percentage of its parent's.
+    ///
+    /// Files count with their own size. Returns `None` for the root, when
+    /// disk usage has not been computed, or when the parent is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata, TreeNode};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.disk_usage = Some(200);
+    /// root.children.push(TreeNode::new(
+    ///     PathBuf::from("project/data.bin"),
+    ///     EntryKind::File,
+    ///     EntryMetadata { size: 50, ..Default::default() },
+    /// ));
+    /// let arena = TreeArena::from_tree(&root);
+    /// let file = arena.root().children().next().unwrap();
+    /// assert_eq!(file.share_of_parent(), Some(25.0));
+    /// assert_eq!(arena.root().share_of_parent(), None);
+    /// ```
+    #[must_use]
+    pub fn share_of_parent(&self) -> Option<f64> {
+        let usage = match self.kind() {
+            EntryKind::Directory => self.disk_usage()?,
+            EntryKind::File => self.metadata().size,
+        };
+        let total = self.parent()?.disk_usage().filter(|&total| total > 0)?;
+        Some(usage as f64 * 100.0 / total as f64)
+    }
+
+    /// Returns why the entry would be hidden, for entries kept by `--show-filtered`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::matching::FilterReason;
+    /// use treepp::scan::{EntryKind, EntryMetadata, TreeNode};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// let mut hidden = TreeNode::new(
+    ///     PathBuf::from("project/.env"),
+    ///     EntryKind::File,
+    ///     EntryMetadata::default(),
+    /// );
+    /// hidden.filtered = Some(FilterReason::Hidden);
+    /// root.children.push(hidden);
+    /// let arena = TreeArena::from_tree(&root);
+    /// let hidden = arena.root().children().next().unwrap();
+    /// assert_eq!(hidden.filtered(), Some(&FilterReason::Hidden));
+    /// assert_eq!(arena.root().filtered(), None);
+    /// ```
+    #[must_use]
+    pub fn filtered(&self) -> Option<&'a FilterReason> {
+        self.node().filtered.as_deref()
+    }
+
+    /// Returns the number of children left out by `--max-entries`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata, TreeNode};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.omitted = 3;
+    /// let arena = TreeArena::from_tree(&root);
+    /// assert_eq!(arena.root().omitted(), 3);
+    /// ```
+    #[must_use]
+    pub fn omitted(&self) -> usize {
+        self.node().omitted
+    }
+
+    /// Returns the number of subdirectories hidden by `--du-threshold`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata, TreeNode};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.small_dirs = 2;
+    /// let arena = TreeArena::from_tree(&root);
+    /// assert_eq!(arena.root().small_dirs(), 2);
+    /// ```
+    #[must_use]
+    pub fn small_dirs(&self) -> usize {
+        self.node().small_dirs
+    }
+
+    /// Returns the entry count of a directory collapsed by `--filelimit`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata, TreeNode};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.collapsed = Some(500);
+    /// let arena = TreeArena::from_tree(&root);
+    /// assert_eq!(arena.root().collapsed(), Some(500));
+    /// ```
+    #[must_use]
+    pub fn collapsed(&self) -> Option<usize> {
+        self.node().collapsed
+    }
+
+    /// Returns why the content of a directory is missing, for unreadable directories.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryError, EntryKind, EntryMetadata, TreeNode};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.error = Some(EntryError::ReadFailed);
+    /// let arena = TreeArena::from_tree(&root);
+    /// assert_eq!(arena.root().error(), Some(EntryError::ReadFailed));
+    /// ```
+    #[must_use]
+    pub fn error(&self) -> Option<EntryError> {
+        self.node().error
+    }
+
+    /// Returns the notes added to the node by a `TreeTransform`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata, TreeNode};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.annotations.push("workspace".to_string());
+    /// let arena = TreeArena::from_tree(&root);
+    /// assert_eq!(arena.root().annotations(), ["workspace"]);
+    /// ```
+    #[must_use]
+    pub fn annotations(&self) -> &'a [String] {
+        &self.node().annotations
+    }
+
+    /// Returns `true` if the node was hoisted to the root by `--leaf-depth`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let file = arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+    /// assert!(!arena.get(file).elided());
+    /// ```
+    #[must_use]
+    pub fn elided(&self) -> bool {
+        self.node().elided
+    }
+
+    /// Returns the parent node, or `None` for the root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let file = arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+    /// assert_eq!(arena.get(file).parent().unwrap().id(), root);
+    /// assert!(arena.root().parent().is_none());
+    /// ```
+    #[must_use]
+    pub fn parent(&self) -> Option<NodeRef<'a>> {
+        self.node().parent.map(|id| self.arena.get(id))
+    }
+
+    /// Returns `true` if the node has children.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let file = arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+    /// assert!(arena.root().has_children());
+    /// assert!(!arena.get(file).has_children());
+    /// ```
+    #[must_use]
+    pub fn has_children(&self) -> bool {
+        !self.node().children.is_empty()
+    }
+
+    /// Returns an iterator over the children of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// arena.add(Some(root), "src", EntryKind::Directory, EntryMetadata::default());
+    /// arena.add(Some(root), "main.rs", EntryKind::File, EntryMetadata::default());
+    /// let names: Vec<_> = arena.root().children().map(|child| child.name()).collect();
+    /// assert_eq!(names, ["src", "main.rs"]);
+    /// ```
+    pub fn children(&self) -> impl ExactSizeIterator<Item = NodeRef<'a>> + 'a {
+        let arena = self.arena;
+        self.node().children.iter().map(move |&id| arena.get(id))
+    }
+
+    /// Recursively counts the directories below this node.
+    fn count_directories(&self) -> usize {
+        self.children()
+            .map(|c| {
+                if c.kind() == EntryKind::Directory && c.filtered().is_none() {
+                    1 + c.count_directories()
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    /// Recursively counts the files in this subtree.
+    fn count_files(&self) -> usize {
+        if self.filtered().is_some() {
+            return 0;
+        }
+        let self_count = usize::from(self.kind() == EntryKind::File);
+        self_count + self.children().map(|c| c.count_files()).sum::<usize>()
+    }
+
+    /// Recursively counts the truncated directories in this subtree.
+    fn count_truncated(&self) -> usize {
+        let self_count = usize::from(self.omitted() > 0);
+        self_count + self.children().map(|c| c.count_truncated()).sum::<usize>()
+    }
+
+    /// Recursively counts the directories left unread in this subtree.
+    ///
+    /// Behaves like `TreeNode::count_unread`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryError, EntryKind, EntryMetadata, TreeNode};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("project"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.error = Some(EntryError::BudgetExhausted);
+    /// let arena = TreeArena::from_tree(&root);
+    /// assert_eq!(arena.root().count_unread(), 1);
+    /// ```
+    #[must_use]
+    pub fn count_unread(&self) -> usize {
+        let self_count = usize::from(self.error() == Some(EntryError::BudgetExhausted));
+        self_count + self.children().map(|c| c.count_unread()).sum::<usize>()
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size: u64) -> TreeNode {
+        TreeNode::new(
+            PathBuf::from(path),
+            EntryKind::File,
+            EntryMetadata {
+                size,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn dir(path: &str, children: Vec<TreeNode>) -> TreeNode {
+        TreeNode::with_children(
+            PathBuf::from(path),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+            children,
+        )
+    }
+
+    fn sample_tree() -> TreeNode {
+        dir(
+            "root",
+            vec![
+                dir(
+                    "root/src",
+                    vec![file("root/src/mod.rs", 10), file("root/src/main.rs", 20)],
+                ),
+                dir("root/empty", vec![]),
+                dir("root/tests", vec![file("root/tests/mod.rs", 5)]),
+                file("root/README.md", 1),
+            ],
+        )
+    }
+
+    fn child_names(node: NodeRef<'_>) -> Vec<&str> {
+        node.children().map(|c| c.name()).collect()
+    }
+
+    #[test]
+    fn test_from_tree_round_trip() {
+        let tree = sample_tree();
+        let arena = TreeArena::from_tree(&tree);
+        let back = arena.to_tree();
+
+        assert_eq!(back.name, "root");
+        assert_eq!(back.children.len(), 4);
+        assert_eq!(back.children[0].path, PathBuf::from("root").join("src"));
+        assert_eq!(
+            back.children[0].children[1].path,
+            PathBuf::from("root").join("src").join("main.rs")
+        );
+        assert_eq!(back.count_files(), tree.count_files());
+        assert_eq!(back.count_directories(), tree.count_directories());
+    }
+
+    #[test]
+    fn test_names_are_interned() {
+        let arena = TreeArena::from_tree(&sample_tree());
+        assert_eq!(arena.len(), 8);
+        assert_eq!(arena.name_count(), 7, "两个 mod.rs 应共享同一个名称");
+    }
+
+    #[test]
+    fn test_path_rebuilt_from_parents() {
+        let arena = TreeArena::from_tree(&sample_tree());
+        let tests = arena.root().children().nth(2).unwrap();
+        let module = tests.children().next().unwrap();
+
+        assert_eq!(
+            module.path(),
+            PathBuf::from("root").join("tests").join("mod.rs")
+        );
+        assert_eq!(module.parent().unwrap().name(), "tests");
+        assert_eq!(arena.root().path(), PathBuf::from("root"));
+        assert!(arena.root().parent().is_none());
+    }
+
+    #[test]
+    fn test_sort_matches_tree_sort() {
+        let mut tree = sample_tree();
+        let mut arena = TreeArena::from_tree(&tree);
+        crate::scan::sort_tree(&mut tree, false);
+        arena.sort(SortOrder::by_name(false));
+
+        let expected: Vec<_> = tree.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(child_names(arena.root()), expected);
+        assert_eq!(
+            child_names(arena.root().children().nth(2).unwrap()),
+            ["main.rs", "mod.rs"]
+        );
+    }
+
+    #[test]
+    fn test_compute_disk_usage() {
+        let mut arena = TreeArena::from_tree(&sample_tree());
+        assert_eq!(arena.compute_disk_usage(), 36);
+        assert_eq!(arena.root().disk_usage(), Some(36));
+        let src = arena.root().children().next().unwrap();
+        assert_eq!(src.disk_usage(), Some(30));
+    }
+
+    #[test]
+    fn test_sort_by_share_orders_directories_by_usage() {
+        let mut arena = TreeArena::from_tree(&sample_tree());
+        arena.compute_disk_usage();
+        arena.sort(SortOrder {
+            key: SortKey::Share,
+            reverse: true,
+            no_normalize: false,
+            interleave: false,
+        });
+
+        assert_eq!(
+            child_names(arena.root()),
+            ["README.md", "src", "tests", "empty"]
+        );
+        let src = arena.root().children().nth(1).unwrap();
+        assert_eq!(child_names(src), ["main.rs", "mod.rs"]);
+        let share = src.share_of_parent().unwrap();
+        assert!((share - 30.0 * 100.0 / 36.0).abs() < 1e-9);
+        assert_eq!(arena.root().share_of_parent(), None, "根节点没有上级");
+    }
+
+    #[test]
+    fn test_hide_small_directories() {
+        let mut arena = TreeArena::from_tree(&sample_tree());
+        arena.compute_disk_usage();
+        arena.hide_small_directories(10);
+
+        assert_eq!(child_names(arena.root()), ["src", "README.md"]);
+        assert_eq!(arena.root().small_dirs(), 2, "empty 与 tests 应被隐藏");
+        assert_eq!(arena.to_tree().small_dirs, 2);
+        assert_eq!(arena.root().children().next().unwrap().small_dirs(), 0);
+    }
+
+    #[test]
+    fn test_prune_and_remove_files() {
+        let mut arena = TreeArena::from_tree(&sample_tree());
+        arena.prune_empty_directories();
+        assert_eq!(child_names(arena.root()), ["src", "tests", "README.md"]);
+
+        arena.remove_files();
+        assert_eq!(child_names(arena.root()), ["src", "tests"]);
+        assert_eq!(arena.count_files(), 0);
+        assert_eq!(arena.count_directories(), 2);
+    }
+
+    #[test]
+    fn test_truncate_counts_listed_entries() {
+        let mut arena = TreeArena::from_tree(&sample_tree());
+        arena.truncate(2, true, None);
+
+        assert_eq!(arena.root().omitted(), 2);
+        assert_eq!(arena.root().children().len(), 2);
+        assert_eq!(arena.count_truncated(), 1);
+
+        let mut arena = TreeArena::from_tree(&sample_tree());
+        arena.truncate(3, false, None);
+        assert_eq!(arena.root().omitted(), 0, "未显示的文件不应计入限制");
+    }
+
+    #[test]
+    fn test_filtered_and_collapsed_flags() {
+        let mut arena = TreeArena::new(PathBuf::from("root"));
+        let root = arena.add(None, "root", EntryKind::Directory, EntryMetadata::default());
+        let hidden = arena.add(
+            Some(root),
+            "target",
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let big = arena.add(
+            Some(root),
+            "big",
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        arena.add(
+            Some(big),
+            "a.txt",
+            EntryKind::File,
+            EntryMetadata::default(),
+        );
+        arena.set_filtered(
+            hidden,
+            Some(FilterReason::Excluded {
+                pattern: "target".to_string(),
+            }),
+        );
+        arena.set_collapsed(big, Some(40));
+        arena.clear_collapsed();
+
+        assert!(arena.get(hidden).filtered().is_some());
+        assert_eq!(arena.get(big).collapsed(), Some(40));
+        assert!(!arena.get(big).has_children());
+        assert_eq!(arena.count_directories(), 1);
+        assert_eq!(
+            arena.to_tree().children[0].filtered,
+            arena.get(hidden).filtered().cloned()
+        );
+    }
+
+    #[test]
+    fn test_unreadable_directory_survives_prune() {
+        let mut arena = TreeArena::new(PathBuf::from("root"));
+        let root = arena.add(None, "root", EntryKind::Directory, EntryMetadata::default());
+        let locked = arena.add(
+            Some(root),
+            "locked",
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        arena.add(
+            Some(root),
+            "empty",
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        arena.set_error(locked, Some(EntryError::AccessDenied));
+        arena.prune_empty_directories();
+
+        assert_eq!(
+            child_names(arena.root()),
+            ["locked"],
+            "无法读取的目录不应被 /P 剪除"
+        );
+        assert_eq!(arena.get(locked).error(), Some(EntryError::AccessDenied));
+        let tree = arena.to_tree();
+        assert_eq!(tree.children[0].error, Some(EntryError::AccessDenied));
+        let copy = TreeArena::from_tree(&tree);
+        assert_eq!(
+            copy.root().children().next().unwrap().error(),
+            Some(EntryError::AccessDenied)
+        );
+    }
+
+    #[test]
+    fn test_keep_leaf_levels_hoists_shallow_descendants() {
+        let tree = dir(
+            "root",
+            vec![
+                file("root/top.txt", 1),
+                dir(
+                    "root/gen",
+                    vec![
+                        file("root/gen/mid.txt", 1),
+                        dir(
+                            "root/gen/a",
+                            vec![dir("root/gen/a/b", vec![file("root/gen/a/b/leaf.txt", 1)])],
+                        ),
+                    ],
+                ),
+                dir("root/shallow", vec![file("root/shallow/x.txt", 1)]),
+            ],
+        );
+        let mut arena = TreeArena::from_tree(&tree);
+        arena.keep_leaf_levels(2);
+
+        assert_eq!(
+            child_names(arena.root()),
+            ["top.txt", "mid.txt", "b", "shallow"]
+        );
+        let elided: Vec<bool> = arena.root().children().map(|c| c.elided()).collect();
+        assert_eq!(
+            elided,
+            [false, true, true, false],
+            "只有被提升的节点应标记为省略"
+        );
+
+        let b = arena.root().children().nth(2).unwrap();
+        assert_eq!(
+            b.path(),
+            PathBuf::from("root/gen/a/b"),
+            "提升后的节点路径应保持不变"
+        );
+        assert_eq!(child_names(b), ["leaf.txt"]);
+        assert_eq!(arena.count_directories(), 2);
+        assert_eq!(arena.count_files(), 4);
+    }
+}
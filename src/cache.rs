@@ -0,0 +1,817 @@
+//! Scan cache module: directory listings reused across runs (`--cache`).
+//!
+//! This module backs the `--cache` and `--no-cache` options. Each scan root
+//! has one cache file holding the listing of every directory scanned so far:
+//!
+//! - **Keying**: directories are keyed by their path relative to the root and
+//!   validated by their last modification time; a directory whose time is
+//!   unchanged is listed from the cache instead of being read again
+//! - **Change journal**: on NTFS, the cache file also holds the position of
+//!   the volume's change journal and the file ID of each directory. The
+//!   next run reads the journal from there, lists the directories it does
+//!   not report as changed without even checking their time, and reads the
+//!   ones it does report again
+//! - **Content**: only entry names, types, link targets and the hidden and
+//!   cloud-only attributes are stored, which is why the cache is not used
+//!   when sizes or dates are displayed
+//! - **Invalidation**: the whole file is discarded when its format version,
+//!   root path or root creation time do not match; `--no-cache` deletes it
+//! - **Location**: `%LOCALAPPDATA%\treepp\cache`, one JSON file per root
+//!
+//! Adding, removing or renaming an entry updates its parent directory's
+//! modification time on NTFS, so structural changes are always picked up.
+//! Changing only the attributes of an entry does not, for instance when a
+//! cloud file is downloaded or freed up again. The change journal records
+//! that case as well; without a journal (other file systems, or a journal
+//! that was turned off or has discarded the records since the last run) it
+//! is not seen until the parent directory changes or `--no-cache` is used.
+//!
+//! File: src/cache.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::winpath;
+use crate::winvol::{self, UsnChanges, UsnPosition};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Cache file format version; files written by other versions are discarded.
+const CACHE_FORMAT_VERSION: u32 = 3;
+
+/// Application directory below `%LOCALAPPDATA%`.
+const CACHE_APP_DIR: &str = "treepp";
+
+/// Subdirectory holding the cache files.
+const CACHE_SUBDIR: &str = "cache";
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A directory entry as stored in the cache.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::cache::CachedEntry;
+///
+/// let entry = CachedEntry {
+///     name: "src".to_string(),
+///     is_dir: true,
+///     hidden: false,
+///     cloud_only: false,
+///     link_target: None,
+/// };
+/// assert!(entry.is_dir);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedEntry {
+    /// File name of the entry.
+    pub name: String,
+    /// Whether the entry is a directory.
+    pub is_dir: bool,
+    /// Whether the entry has the Windows hidden attribute.
+    pub hidden: bool,
+    /// Whether the entry is a cloud-only placeholder.
+    pub cloud_only: bool,
+    /// Target of a symbolic link or junction.
+    pub link_target: Option<PathBuf>,
+}
+
+/// The cached listing of one directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedDir {
+    /// Modification time of the directory when it was listed (ns since the epoch).
+    modified: u64,
+    /// Index of the directory's file ID, if the listing was current at the
+    /// journal position of the cache file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file_id: Option<u64>,
+    /// Entries of the directory, in listing order.
+    entries: Vec<CachedEntry>,
+}
+
+/// On-disk layout of a cache file.
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    /// Format version (`CACHE_FORMAT_VERSION`).
+    version: u32,
+    /// Scan root the listings belong to.
+    root: PathBuf,
+    /// Creation time of the scan root (ns since the epoch).
+    root_created: Option<u64>,
+    /// Change-journal position the listings with a file ID were current at.
+    #[serde(default)]
+    journal: Option<UsnPosition>,
+    /// Directory listings keyed by `/`-separated path relative to the root.
+    dirs: HashMap<String, CachedDir>,
+}
+
+/// Directory listings of one scan root, loaded from and saved to disk.
+///
+/// Lookups are served from the listings loaded at startup; listings read
+/// during the scan are collected separately and merged in by `save`. Both
+/// sides can be used from the parallel scanner.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs;
+/// use std::path::Path;
+/// use treepp::cache::ScanCache;
+///
+/// let root = Path::new(".");
+/// let cache = ScanCache::open(root);
+/// let modified = fs::metadata(root).unwrap().modified().unwrap();
+/// if cache.lookup(root, modified).is_none() {
+///     cache.record(root, modified, Vec::new());
+/// }
+/// cache.save().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ScanCache {
+    /// Location of the cache file.
+    file: PathBuf,
+    /// Scan root the listings belong to.
+    root: PathBuf,
+    /// Creation time of the scan root, guarding against a replaced root.
+    root_created: Option<u64>,
+    /// Change-journal position when the cache was opened, saved with the
+    /// listings of this run.
+    journal: Option<UsnPosition>,
+    /// Change-journal position stored in the loaded cache file.
+    since: Option<UsnPosition>,
+    /// Listings loaded from the cache file.
+    previous: HashMap<String, CachedDir>,
+    /// Keys of loaded listings the change journal reports as changed.
+    changed: HashSet<String>,
+    /// Listings read during this run.
+    updates: Mutex<HashMap<String, CachedDir>>,
+    /// File IDs of loaded listings confirmed by their time during this run.
+    confirmed: Mutex<HashMap<String, u64>>,
+}
+
+impl ScanCache {
+    /// Opens the cache of a scan root from its default location.
+    ///
+    /// A missing, unreadable or stale cache file yields an empty cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The scan root.
+    ///
+    /// # Returns
+    ///
+    /// The loaded `ScanCache`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use treepp::cache::ScanCache;
+    ///
+    /// let cache = ScanCache::open(Path::new("D:\\src"));
+    /// assert!(cache.unchanged(Path::new("D:\\src\\docs")).is_none());
+    /// ```
+    #[must_use]
+    pub fn open(root: &Path) -> Self {
+        Self::load(cache_file_path(root), root)
+    }
+
+    /// Opens the cache of a scan root from a given cache file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Location of the cache file.
+    /// * `root` - The scan root.
+    ///
+    /// # Returns
+    ///
+    /// The loaded `ScanCache`; its listings are empty if `file` does not
+    /// exist, cannot be parsed, or belongs to another format version or root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use std::time::SystemTime;
+    /// use treepp::cache::ScanCache;
+    ///
+    /// // A cache file that does not exist yields an empty cache.
+    /// let file = std::env::temp_dir().join("treepp-missing-cache.json");
+    /// let cache = ScanCache::load(file, Path::new("."));
+    /// assert!(cache.lookup(Path::new("."), SystemTime::now()).is_none());
+    /// ```
+    #[must_use]
+    pub fn load(file: PathBuf, root: &Path) -> Self {
+        let extended_root = winpath::to_extended(root);
+        let root_created = fs::metadata(&extended_root)
+            .and_then(|meta| meta.created())
+            .ok()
+            .and_then(to_nanos);
+        let journal = winvol::usn_position(&extended_root);
+
+        let (mut previous, since) = fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CacheFile>(&bytes).ok())
+            .filter(|cached| {
+                cached.version == CACHE_FORMAT_VERSION
+                    && cached.root == root
+                    && cached.root_created == root_created
+            })
+            .map(|cached| (cached.dirs, cached.journal))
+            .unwrap_or_default();
+
+        let changes = since
+            .filter(|since| {
+                journal.is_some_and(|now| {
+                    now.volume == since.volume && now.journal_id == since.journal_id
+                })
+            })
+            .and_then(|since| winvol::usn_changes(&extended_root, since));
+        let changed = apply_journal(&mut previous, changes.as_ref());
+
+        Self {
+            file,
+            root: root.to_path_buf(),
+            root_created,
+            journal,
+            since,
+            previous,
+            changed,
+            updates: Mutex::new(HashMap::new()),
+            confirmed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Looks up the cached listing of a directory that the change journal
+    /// reports as unchanged since it was listed.
+    ///
+    /// Such a listing is current without checking the directory's
+    /// modification time.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory, below the scan root.
+    ///
+    /// # Returns
+    ///
+    /// The cached entries, or `None` if the directory is not cached or the
+    /// journal cannot vouch for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use treepp::cache::{CachedEntry, ScanCache};
+    ///
+    /// let root = Path::new(".");
+    /// let cache = ScanCache::load(std::env::temp_dir().join("treepp-missing-cache.json"), root);
+    ///
+    /// // Listings read during this run are only used by the next one.
+    /// cache.record(root, std::time::SystemTime::now(), Vec::<CachedEntry>::new());
+    /// assert!(cache.unchanged(root).is_none());
+    /// ```
+    #[must_use]
+    pub fn unchanged(&self, dir: &Path) -> Option<&[CachedEntry]> {
+        let cached = self.previous.get(&self.key(dir)?)?;
+        cached
+            .file_id
+            .is_some()
+            .then_some(cached.entries.as_slice())
+    }
+
+    /// Looks up the cached listing of a directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory, below the scan root.
+    /// * `modified` - The directory's current modification time.
+    ///
+    /// # Returns
+    ///
+    /// The cached entries, or `None` if the directory is not cached, has
+    /// been modified since it was listed, or is reported as changed by the
+    /// change journal. With a journal, a listing that is found is saved with
+    /// the directory's file ID, so that the next run can use `unchanged`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use std::time::SystemTime;
+    /// use treepp::cache::ScanCache;
+    ///
+    /// let file = std::env::temp_dir().join("treepp-missing-cache.json");
+    /// let cache = ScanCache::load(file, Path::new("."));
+    /// // Directories outside the root are never cached.
+    /// assert!(cache.lookup(Path::new("/elsewhere"), SystemTime::now()).is_none());
+    /// ```
+    #[must_use]
+    pub fn lookup(&self, dir: &Path, modified: SystemTime) -> Option<&[CachedEntry]> {
+        let key = self.key(dir)?;
+        let cached = self.previous.get(&key)?;
+        if Some(cached.modified) != to_nanos(modified) || self.changed.contains(&key) {
+            return None;
+        }
+        if let Some(file_id) = self.file_id(dir) {
+            self.confirmed.lock().unwrap().insert(key, file_id);
+        }
+        Some(cached.entries.as_slice())
+    }
+
+    /// Records the listing of a directory read during this run.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory, below the scan root.
+    /// * `modified` - The directory's modification time when it was read.
+    /// * `entries` - The directory's entries.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs;
+    /// use std::path::Path;
+    /// use treepp::cache::{CachedEntry, ScanCache};
+    ///
+    /// let root = Path::new(".");
+    /// let cache = ScanCache::open(root);
+    /// let modified = fs::metadata(root).unwrap().modified().unwrap();
+    /// let entries = vec![CachedEntry {
+    ///     name: "Cargo.toml".to_string(),
+    ///     is_dir: false,
+    ///     hidden: false,
+    ///     cloud_only: false,
+    ///     link_target: None,
+    /// }];
+    /// cache.record(root, modified, entries);
+    /// cache.save().unwrap();
+    /// ```
+    pub fn record(&self, dir: &Path, modified: SystemTime, entries: Vec<CachedEntry>) {
+        let (Some(key), Some(modified)) = (self.key(dir), to_nanos(modified)) else {
+            return;
+        };
+        let cached = CachedDir {
+            modified,
+            file_id: self.file_id(dir),
+            entries,
+        };
+        self.updates.lock().unwrap().insert(key, cached);
+    }
+
+    /// Writes the cache file if any listing was read or confirmed during
+    /// this run, or if the change-journal position moved.
+    ///
+    /// The position is saved even when every listing came from the cache,
+    /// so that the next run only reads the journal records added since.
+    ///
+    /// Listings of directories not visited by this run (for example below a
+    /// depth limit) are kept, since they are validated again on lookup,
+    /// except those the change journal reports as changed: their time
+    /// would not show a change of attributes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error if the cache directory or file cannot be written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use treepp::cache::ScanCache;
+    ///
+    /// let cache = ScanCache::open(Path::new("."));
+    /// // ... scan, calling `lookup` and `record` ...
+    /// cache.save().unwrap();
+    /// ```
+    pub fn save(&self) -> io::Result<()> {
+        let updates = std::mem::take(&mut *self.updates.lock().unwrap());
+        let confirmed = std::mem::take(&mut *self.confirmed.lock().unwrap());
+        if updates.is_empty()
+            && confirmed.is_empty()
+            && self.changed.is_empty()
+            && self.journal == self.since
+        {
+            return Ok(());
+        }
+
+        let mut dirs = self.previous.clone();
+        dirs.retain(|key, _| !self.changed.contains(key));
+        for (key, file_id) in confirmed {
+            if let Some(cached) = dirs.get_mut(&key) {
+                cached.file_id = Some(file_id);
+            }
+        }
+        dirs.extend(updates);
+        let cache = CacheFile {
+            version: CACHE_FORMAT_VERSION,
+            root: self.root.clone(),
+            root_created: self.root_created,
+            journal: self.journal,
+            dirs,
+        };
+        let bytes = serde_json::to_vec(&cache).map_err(io::Error::other)?;
+
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write to a sibling file first so that an interrupted run never
+        // leaves a truncated cache behind.
+        let partial = self.file.with_extension("tmp");
+        fs::write(&partial, bytes)?;
+        fs::rename(&partial, &self.file)
+    }
+
+    /// Returns the index of a directory's file ID, if the change journal is
+    /// used and the directory is on the journal's volume.
+    fn file_id(&self, dir: &Path) -> Option<u64> {
+        let journal = self.journal?;
+        winvol::file_id(&winpath::to_extended(dir))
+            .filter(|id| id.volume == journal.volume)
+            .map(|id| id.index)
+    }
+
+    /// Returns the cache key of a directory below the root.
+    fn key(&self, dir: &Path) -> Option<String> {
+        let relative = dir.strip_prefix(&self.root).ok()?;
+        let parts: Vec<_> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        Some(parts.join("/"))
+    }
+}
+
+// ============================================================================
+// Cache Location
+// ============================================================================
+
+/// Returns the cache file of a scan root.
+///
+/// Files live in `%LOCALAPPDATA%\treepp\cache` (or the temporary directory
+/// if `LOCALAPPDATA` is not set), named after a hash of the root path.
+///
+/// # Arguments
+///
+/// * `root` - The scan root.
+///
+/// # Returns
+///
+/// The path of the root's cache file.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::cache::cache_file_path;
+///
+/// let file = cache_file_path(Path::new("D:\\src"));
+/// assert_eq!(file, cache_file_path(Path::new("D:\\src")));
+/// assert_eq!(file.extension().unwrap(), "json");
+/// assert!(file.parent().unwrap().ends_with("treepp/cache"));
+/// ```
+#[must_use]
+pub fn cache_file_path(root: &Path) -> PathBuf {
+    let base = env::var_os("LOCALAPPDATA")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir);
+    let mut hasher = DefaultHasher::new();
+    root.hash(&mut hasher);
+    base.join(CACHE_APP_DIR)
+        .join(CACHE_SUBDIR)
+        .join(format!("{:016x}.json", hasher.finish()))
+}
+
+/// Deletes the cache file of a scan root (`--no-cache`).
+///
+/// # Arguments
+///
+/// * `root` - The scan root.
+///
+/// # Errors
+///
+/// Returns an I/O error if the file exists but cannot be removed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use treepp::cache;
+///
+/// cache::remove(Path::new("D:\\src")).unwrap();
+/// ```
+pub fn remove(root: &Path) -> io::Result<()> {
+    match fs::remove_file(cache_file_path(root)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Applies the changes the change journal reports to loaded listings.
+///
+/// Listings of changed directories, and of all directories below one that
+/// was itself moved, lose their file ID, and so does every listing if the
+/// journal cannot tell what changed.
+///
+/// # Arguments
+///
+/// * `dirs` - The loaded listings.
+/// * `changes` - The changes since the journal position of the cache file.
+///
+/// # Returns
+///
+/// The keys of the listings the journal reports as changed.
+fn apply_journal(
+    dirs: &mut HashMap<String, CachedDir>,
+    changes: Option<&UsnChanges>,
+) -> HashSet<String> {
+    let Some(changes) = changes else {
+        for cached in dirs.values_mut() {
+            cached.file_id = None;
+        }
+        return HashSet::new();
+    };
+
+    let moved: Vec<String> = dirs
+        .iter()
+        .filter(|(_, cached)| cached.file_id.is_some_and(|id| changes.moved.contains(&id)))
+        .map(|(key, _)| key.clone())
+        .collect();
+    let mut changed = HashSet::new();
+    for (key, cached) in dirs.iter_mut() {
+        let Some(id) = cached.file_id else {
+            continue;
+        };
+        let below_moved = moved.iter().any(|ancestor| {
+            ancestor.is_empty()
+                || key
+                    .strip_prefix(ancestor.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        });
+        if changes.listings.contains(&id) || changes.moved.contains(&id) || below_moved {
+            cached.file_id = None;
+            changed.insert(key.clone());
+        }
+    }
+    changed
+}
+
+/// Converts a timestamp to nanoseconds since the Unix epoch.
+fn to_nanos(time: SystemTime) -> Option<u64> {
+    let since_epoch = time.duration_since(UNIX_EPOCH).ok()?;
+    u64::try_from(since_epoch.as_nanos()).ok()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn entry(name: &str, is_dir: bool) -> CachedEntry {
+        CachedEntry {
+            name: name.to_string(),
+            is_dir,
+            hidden: false,
+            cloud_only: false,
+            link_target: None,
+        }
+    }
+
+    #[test]
+    fn lookup_misses_on_empty_cache() {
+        let dir = TempDir::new().unwrap();
+        let cache = ScanCache::load(dir.path().join("cache.json"), dir.path());
+        assert!(cache.lookup(dir.path(), SystemTime::now()).is_none());
+    }
+
+    #[test]
+    fn saved_listing_is_found_by_matching_time() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("cache.json");
+        let sub = dir.path().join("src");
+        let modified = SystemTime::now();
+
+        let cache = ScanCache::load(file.clone(), dir.path());
+        cache.record(
+            &sub,
+            modified,
+            vec![entry("main.rs", false), entry("bin", true)],
+        );
+        cache.save().expect("写入缓存失败");
+
+        let reloaded = ScanCache::load(file, dir.path());
+        let entries = reloaded.lookup(&sub, modified).expect("缓存未命中");
+        assert_eq!(entries, [entry("main.rs", false), entry("bin", true)]);
+    }
+
+    #[test]
+    fn modified_directory_is_not_served_from_cache() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("cache.json");
+        let modified = SystemTime::now();
+
+        let cache = ScanCache::load(file.clone(), dir.path());
+        cache.record(dir.path(), modified, vec![entry("a.txt", false)]);
+        cache.save().unwrap();
+
+        let reloaded = ScanCache::load(file, dir.path());
+        assert!(
+            reloaded
+                .lookup(dir.path(), modified + Duration::from_secs(1))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn cache_of_other_root_is_discarded() {
+        let dir = TempDir::new().unwrap();
+        let other = TempDir::new().unwrap();
+        let file = dir.path().join("cache.json");
+        let modified = SystemTime::now();
+
+        let cache = ScanCache::load(file.clone(), dir.path());
+        cache.record(dir.path(), modified, vec![entry("a.txt", false)]);
+        cache.save().unwrap();
+
+        let reloaded = ScanCache::load(file, other.path());
+        assert!(reloaded.lookup(other.path(), modified).is_none());
+    }
+
+    #[test]
+    fn corrupt_cache_file_is_ignored() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("cache.json");
+        fs::write(&file, b"{ not json").unwrap();
+
+        let cache = ScanCache::load(file, dir.path());
+        assert!(cache.lookup(dir.path(), SystemTime::now()).is_none());
+    }
+
+    #[test]
+    fn save_without_updates_writes_nothing() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("cache.json");
+
+        ScanCache::load(file.clone(), dir.path()).save().unwrap();
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn save_keeps_listings_not_visited_again() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("cache.json");
+        let deep = dir.path().join("a").join("b");
+        let modified = SystemTime::now();
+
+        let first = ScanCache::load(file.clone(), dir.path());
+        first.record(&deep, modified, vec![entry("deep.txt", false)]);
+        first.save().unwrap();
+
+        let second = ScanCache::load(file.clone(), dir.path());
+        second.record(dir.path(), modified, vec![entry("a", true)]);
+        second.save().unwrap();
+
+        let third = ScanCache::load(file, dir.path());
+        assert!(third.lookup(&deep, modified).is_some());
+        assert!(third.lookup(dir.path(), modified).is_some());
+    }
+
+    /// A loaded listing with a file ID and a time of 0.
+    fn current_dir(file_id: u64) -> CachedDir {
+        CachedDir {
+            modified: 0,
+            file_id: Some(file_id),
+            entries: vec![entry("a.txt", false)],
+        }
+    }
+
+    #[test]
+    fn journal_changes_invalidate_changed_and_moved_listings() {
+        let mut dirs: HashMap<String, CachedDir> = [
+            ("", 1),
+            ("src", 2),
+            ("src/bin", 3),
+            ("docs", 4),
+            ("docs/api", 5),
+        ]
+        .into_iter()
+        .map(|(key, id)| (key.to_string(), current_dir(id)))
+        .collect();
+        let changes = UsnChanges {
+            listings: HashSet::from([1, 2]),
+            moved: HashSet::from([4]),
+        };
+
+        let changed = apply_journal(&mut dirs, Some(&changes));
+        let expected: HashSet<String> = ["", "src", "docs", "docs/api"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(changed, expected, "被移动目录下的列表也应失效");
+        assert_eq!(
+            dirs["src/bin"].file_id,
+            Some(3),
+            "未变化的列表应保留文件 ID"
+        );
+        assert!(dirs["docs/api"].file_id.is_none());
+    }
+
+    #[test]
+    fn listings_lose_file_ids_without_journal_changes() {
+        let mut dirs = HashMap::from([("src".to_string(), current_dir(2))]);
+        assert!(apply_journal(&mut dirs, None).is_empty());
+        assert!(dirs["src"].file_id.is_none());
+    }
+
+    #[test]
+    fn journal_position_is_saved_when_no_listing_changed() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("cache.json");
+        let position = |next_usn| UsnPosition {
+            volume: 7,
+            journal_id: 1,
+            next_usn,
+        };
+
+        let mut cache = ScanCache::load(file.clone(), dir.path());
+        cache.journal = Some(position(100));
+        cache.record(dir.path(), SystemTime::now(), vec![entry("a.txt", false)]);
+        cache.save().unwrap();
+
+        // A second run served entirely from the journal reads and records nothing.
+        let mut second = ScanCache::load(file.clone(), dir.path());
+        assert_eq!(second.since, Some(position(100)));
+        second.journal = Some(position(250));
+        second.save().unwrap();
+        let saved: CacheFile = serde_json::from_slice(&fs::read(&file).unwrap()).unwrap();
+        assert_eq!(saved.journal, Some(position(250)), "日志位置应前移");
+        assert_eq!(saved.dirs.len(), 1, "列表应保留");
+
+        let mut third = ScanCache::load(file.clone(), dir.path());
+        third.journal = third.since;
+        fs::remove_file(&file).unwrap();
+        third.save().unwrap();
+        assert!(!file.exists(), "位置未变时不应重写缓存");
+    }
+
+    #[test]
+    fn changed_listings_are_not_served_and_not_saved() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("cache.json");
+        let sub = dir.path().join("src");
+        let modified = SystemTime::now();
+
+        let cache = ScanCache::load(file.clone(), dir.path());
+        cache.record(&sub, modified, vec![entry("main.rs", false)]);
+        cache.save().unwrap();
+
+        let mut reloaded = ScanCache::load(file.clone(), dir.path());
+        assert!(
+            reloaded.unchanged(&sub).is_none(),
+            "没有日志时不应跳过时间检查"
+        );
+        reloaded.changed.insert("src".to_string());
+        assert!(
+            reloaded.lookup(&sub, modified).is_none(),
+            "日志报告变化的目录应重新读取"
+        );
+
+        reloaded.record(dir.path(), modified, vec![entry("src", true)]);
+        reloaded.save().unwrap();
+        let third = ScanCache::load(file, dir.path());
+        assert!(
+            third.lookup(&sub, modified).is_none(),
+            "未重新读取的变化列表不应保存"
+        );
+        assert!(third.lookup(dir.path(), modified).is_some());
+    }
+
+    #[test]
+    fn cache_file_path_differs_per_root() {
+        let a = cache_file_path(Path::new(r"C:\a"));
+        let b = cache_file_path(Path::new(r"C:\b"));
+        assert_ne!(a, b);
+        assert_eq!(a, cache_file_path(Path::new(r"C:\a")));
+    }
+}
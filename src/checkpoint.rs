@@ -0,0 +1,684 @@
+//! Checkpoint module: resuming interrupted `--output` runs (`--checkpoint`).
+//!
+//! Listing a whole drive can take hours, and an interrupted run would
+//! otherwise have to start over. With `--checkpoint`, the streaming pipeline
+//! records its progress next to the output file, in `<output>.checkpoint`,
+//! each time it has written a directory completely, at most every
+//! `CHECKPOINT_INTERVAL`:
+//!
+//! - **Position**: the last directory written, relative to the root
+//! - **Output**: the length of the output file at that point
+//! - **Renderer**: the open levels of the tree, so that connectors continue
+//! - **Totals**: counts, unreadable directories, statistics and elapsed time
+//!
+//! A later run with the same options cuts the output file back to the
+//! recorded length and continues the scan after the recorded directory. The
+//! checkpoint is removed once a run completes. A checkpoint written with
+//! other options, for a changed output file, or for a directory that no
+//! longer exists is ignored, and the run starts over.
+//!
+//! File: src/checkpoint.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{Config, PagerMode};
+use crate::render::StreamRendererState;
+use crate::scan::{EntryError, StreamEvent, StreamStats};
+use crate::stats::StatsCollector;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Checkpoint file format version; files written by other versions are ignored.
+const CHECKPOINT_FORMAT_VERSION: u32 = 1;
+
+/// Suffix appended to the output path to name the checkpoint file.
+const CHECKPOINT_SUFFIX: &str = ".checkpoint";
+
+/// Minimum time between two checkpoints.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use treepp::checkpoint::CHECKPOINT_INTERVAL;
+///
+/// assert_eq!(CHECKPOINT_INTERVAL, Duration::from_secs(5));
+/// ```
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Progress of a `--checkpoint` run, as stored in the checkpoint file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use treepp::checkpoint::{load, LoadOutcome};
+/// use treepp::config::Config;
+///
+/// let mut config = Config::with_root(PathBuf::from("D:\\"));
+/// config.output.output_path = Some(PathBuf::from("d-drive.txt"));
+/// if let LoadOutcome::Resumable(checkpoint) = load(&config) {
+///     println!("resuming after {}", checkpoint.completed.display());
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// File format version.
+    version: u32,
+    /// Options of the run, compared when resuming (see `fingerprint`).
+    options: Value,
+    /// Last directory written completely, relative to the root.
+    pub completed: PathBuf,
+    /// Length of the output file after that directory.
+    pub output_len: u64,
+    /// Position of the renderer after that directory.
+    pub renderer: StreamRendererState,
+    /// Whether the root has subdirectories, for the "no subfolders" notice.
+    pub has_subdirs: bool,
+    /// Number of directories written.
+    pub directories: usize,
+    /// Number of files written.
+    pub files: usize,
+    /// Number of directories truncated by `--max-entries`.
+    pub truncated_dirs: usize,
+    /// Number of directories left unread after the scan budget ran out.
+    pub unread_dirs: usize,
+    /// Directories that could not be read.
+    pub unreadable_dirs: Vec<PathBuf>,
+    /// Statistics collected for `--report-extensions`, `--depth-histogram`
+    /// and `--stats-json`.
+    pub stats: Option<StatsCollector>,
+    /// Scan time of the interrupted runs.
+    pub elapsed: Duration,
+}
+
+/// What was found next to the output file when a `--checkpoint` run starts.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::checkpoint::{load, LoadOutcome};
+/// use treepp::config::Config;
+///
+/// // Without an output file there is nothing to resume.
+/// let config = Config::with_root(PathBuf::from("."));
+/// assert!(matches!(load(&config), LoadOutcome::Missing));
+/// ```
+#[derive(Debug)]
+pub enum LoadOutcome {
+    /// There is no checkpoint; the run starts over.
+    Missing,
+    /// The checkpoint does not belong to this run; the run starts over.
+    Stale,
+    /// The run continues from the checkpoint.
+    Resumable(Box<Checkpoint>),
+}
+
+/// Records the progress of a streaming scan and writes checkpoints.
+///
+/// Every event passed on by the scan goes through `record` before it is
+/// rendered. Once a directory has been written completely and the interval
+/// has passed, `record` asks for a checkpoint, which the caller writes with
+/// `save` after flushing the output file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use treepp::checkpoint::Recorder;
+/// use treepp::config::Config;
+/// use treepp::scan::StreamEvent;
+///
+/// let mut config = Config::with_root(PathBuf::from("C:\\"));
+/// config.output.output_path = Some(PathBuf::from("c-drive.txt"));
+/// let mut recorder = Recorder::new(&config, None);
+/// if recorder.record(&StreamEvent::LeaveDir) {
+///     // Flush the output file, then call `recorder.save`.
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Recorder {
+    /// Path of the checkpoint file.
+    file: PathBuf,
+    /// Root of the scan.
+    root: PathBuf,
+    /// Progress so far, including that of the resumed run.
+    progress: Checkpoint,
+    /// Directories entered but not yet left, relative to the root.
+    open_dirs: Vec<PathBuf>,
+    /// The directory of the last entry, entered by the next `EnterDir`.
+    last_dir: Option<PathBuf>,
+    /// Start of this run.
+    started: Instant,
+    /// Time of the last checkpoint, or the start of this run.
+    last_saved: Instant,
+}
+
+// ============================================================================
+// Public Functions
+// ============================================================================
+
+/// Returns the path of the checkpoint file for an output file.
+///
+/// # Arguments
+///
+/// * `output` - The output file path.
+///
+/// # Returns
+///
+/// The output path followed by `.checkpoint`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use treepp::checkpoint::checkpoint_path;
+///
+/// assert_eq!(checkpoint_path(Path::new("tree.txt")), PathBuf::from("tree.txt.checkpoint"));
+/// ```
+#[must_use]
+pub fn checkpoint_path(output: &Path) -> PathBuf {
+    let mut path: OsString = output.as_os_str().to_owned();
+    path.push(CHECKPOINT_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// Returns the options that must match for a checkpoint to be resumed.
+///
+/// This is the whole configuration, without the settings that only affect
+/// the console: the pager, `--silent`, warnings and the diagnostic log.
+///
+/// # Arguments
+///
+/// * `config` - The validated configuration.
+///
+/// # Returns
+///
+/// The configuration as a JSON value.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::checkpoint::fingerprint;
+/// use treepp::config::Config;
+///
+/// let config = Config::with_root(PathBuf::from("."));
+/// let mut silent = config.clone();
+/// silent.output.silent = true;
+/// assert_eq!(fingerprint(&config), fingerprint(&silent));
+/// ```
+#[must_use]
+pub fn fingerprint(config: &Config) -> Value {
+    let mut options = config.clone();
+    options.scan.resume_after = None;
+    options.output.silent = false;
+    options.output.pager = PagerMode::default();
+    options.output.page_after = None;
+    options.output.verbosity = 0;
+    options.output.log_file = None;
+    options.output.quiet_warnings = false;
+    serde_json::to_value(&options).unwrap_or_default()
+}
+
+/// Looks for a checkpoint of an interrupted run of the same command.
+///
+/// # Arguments
+///
+/// * `config` - The validated configuration, with an output path.
+///
+/// # Returns
+///
+/// `Resumable` if the checkpoint was written with the same options, the
+/// output file still holds what it recorded and its directory still exists.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use treepp::checkpoint::{load, LoadOutcome};
+/// use treepp::config::Config;
+///
+/// let mut config = Config::with_root(PathBuf::from("C:\\"));
+/// config.output.output_path = Some(PathBuf::from("c-drive.txt"));
+/// match load(&config) {
+///     LoadOutcome::Resumable(checkpoint) => println!("{} files so far", checkpoint.files),
+///     LoadOutcome::Stale | LoadOutcome::Missing => println!("starting over"),
+/// }
+/// ```
+#[must_use]
+pub fn load(config: &Config) -> LoadOutcome {
+    let Some(ref output) = config.output.output_path else {
+        return LoadOutcome::Missing;
+    };
+    let Ok(bytes) = fs::read(checkpoint_path(output)) else {
+        return LoadOutcome::Missing;
+    };
+
+    let output_len = fs::metadata(output).map_or(0, |meta| meta.len());
+    match serde_json::from_slice::<Checkpoint>(&bytes) {
+        Ok(checkpoint)
+            if checkpoint.version == CHECKPOINT_FORMAT_VERSION
+                && checkpoint.options == fingerprint(config)
+                && checkpoint.output_len <= output_len
+                && config.root_path.join(&checkpoint.completed).is_dir() =>
+        {
+            LoadOutcome::Resumable(Box::new(checkpoint))
+        }
+        _ => LoadOutcome::Stale,
+    }
+}
+
+// ============================================================================
+// Recorder
+// ============================================================================
+
+impl Recorder {
+    /// Creates a recorder for a new or resumed run.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The validated configuration, with an output path.
+    /// * `resumed` - The checkpoint the run continues from, if any.
+    ///
+    /// # Returns
+    ///
+    /// A recorder whose totals start at those of `resumed`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::checkpoint::Recorder;
+    /// use treepp::config::Config;
+    /// use treepp::scan::StreamEvent;
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("project"));
+    /// config.output.output_path = Some(PathBuf::from("tree.txt"));
+    /// let mut recorder = Recorder::new(&config, None);
+    /// // No directory was entered, so leaving one completes nothing.
+    /// assert!(!recorder.record(&StreamEvent::LeaveDir));
+    /// ```
+    #[must_use]
+    pub fn new(config: &Config, resumed: Option<&Checkpoint>) -> Self {
+        let output = config
+            .output
+            .output_path
+            .as_deref()
+            .unwrap_or(Path::new(""));
+        let progress = resumed.cloned().unwrap_or_else(|| Checkpoint {
+            version: CHECKPOINT_FORMAT_VERSION,
+            options: fingerprint(config),
+            completed: PathBuf::new(),
+            output_len: 0,
+            renderer: StreamRendererState::default(),
+            has_subdirs: false,
+            directories: 0,
+            files: 0,
+            truncated_dirs: 0,
+            unread_dirs: 0,
+            unreadable_dirs: Vec::new(),
+            stats: None,
+            elapsed: Duration::ZERO,
+        });
+        // The directories around the completed one are still open.
+        let mut open_dirs: Vec<PathBuf> = progress
+            .completed
+            .ancestors()
+            .skip(1)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .collect();
+        open_dirs.reverse();
+
+        let now = Instant::now();
+        Self {
+            file: checkpoint_path(output),
+            root: config.root_path.clone(),
+            progress,
+            open_dirs,
+            last_dir: None,
+            started: now,
+            last_saved: now,
+        }
+    }
+
+    /// Records an event passed on by the scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event, before it is rendered.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the event completes a directory and a checkpoint is due.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::checkpoint::Recorder;
+    /// use treepp::config::Config;
+    /// use treepp::scan::StreamEvent;
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("project"));
+    /// config.output.output_path = Some(PathBuf::from("tree.txt"));
+    /// let mut recorder = Recorder::new(&config, None);
+    /// assert!(!recorder.record(&StreamEvent::EnterDir { is_last: true }));
+    /// // The directory is complete, but the interval has not passed yet.
+    /// assert!(!recorder.record(&StreamEvent::LeaveDir));
+    /// ```
+    pub fn record(&mut self, event: &StreamEvent) -> bool {
+        match event {
+            StreamEvent::Entry(entry) => {
+                if entry.filtered.is_none() {
+                    if entry.is_file {
+                        self.progress.files += 1;
+                    } else {
+                        self.progress.directories += 1;
+                    }
+                }
+                if !entry.is_file {
+                    let relative = entry.path.strip_prefix(&self.root).unwrap_or(&entry.path);
+                    self.last_dir = Some(relative.to_path_buf());
+                }
+                match entry.error {
+                    Some(EntryError::OtherVolume) | None => {}
+                    Some(EntryError::BudgetExhausted) => self.progress.unread_dirs += 1,
+                    Some(_) => self.progress.unreadable_dirs.push(entry.path.clone()),
+                }
+                false
+            }
+            StreamEvent::Omitted { .. } => {
+                self.progress.truncated_dirs += 1;
+                false
+            }
+            StreamEvent::EnterDir { .. } => {
+                self.open_dirs
+                    .push(self.last_dir.take().unwrap_or_default());
+                false
+            }
+            StreamEvent::LeaveDir => match self.open_dirs.pop() {
+                Some(dir) => {
+                    self.progress.completed = dir;
+                    self.last_saved.elapsed() >= CHECKPOINT_INTERVAL
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Writes a checkpoint after the last directory recorded as completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_len` - Length of the flushed output file.
+    /// * `renderer` - Position of the renderer.
+    /// * `has_subdirs` - Whether the root has subdirectories.
+    /// * `stats` - Statistics collected so far, if any.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error if the checkpoint file cannot be written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::path::PathBuf;
+    /// use treepp::checkpoint::Recorder;
+    /// use treepp::config::Config;
+    /// use treepp::render::StreamRendererState;
+    /// use treepp::scan::StreamEvent;
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("C:\\"));
+    /// config.output.output_path = Some(PathBuf::from("c-drive.txt"));
+    /// let mut recorder = Recorder::new(&config, None);
+    /// if recorder.record(&StreamEvent::LeaveDir) {
+    ///     let written = std::fs::metadata("c-drive.txt").unwrap().len();
+    ///     recorder.save(written, StreamRendererState::default(), true, None).unwrap();
+    /// }
+    /// ```
+    pub fn save(
+        &mut self,
+        output_len: u64,
+        renderer: StreamRendererState,
+        has_subdirs: bool,
+        stats: Option<&StatsCollector>,
+    ) -> io::Result<()> {
+        let mut checkpoint = self.progress.clone();
+        checkpoint.output_len = output_len;
+        checkpoint.renderer = renderer;
+        checkpoint.has_subdirs = has_subdirs;
+        checkpoint.stats = stats.cloned();
+        checkpoint.elapsed += self.started.elapsed();
+        let bytes = serde_json::to_vec(&checkpoint).map_err(io::Error::other)?;
+
+        // Write to a sibling file first so that an interruption never
+        // leaves a truncated checkpoint behind.
+        let mut partial = self.file.as_os_str().to_owned();
+        partial.push(".tmp");
+        fs::write(&partial, bytes)?;
+        fs::rename(&partial, &self.file)?;
+        self.last_saved = Instant::now();
+        Ok(())
+    }
+
+    /// Adds the totals of the resumed runs to those of this run.
+    ///
+    /// # Arguments
+    ///
+    /// * `stats` - Statistics returned by the scan of this run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::checkpoint::Recorder;
+    /// use treepp::config::Config;
+    /// use std::time::Duration;
+    /// use treepp::matching::RejectionCounts;
+    /// use treepp::scan::StreamStats;
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("project"));
+    /// config.output.output_path = Some(PathBuf::from("tree.txt"));
+    /// let recorder = Recorder::new(&config, None);
+    /// let mut stats = StreamStats {
+    ///     duration: Duration::from_secs(1),
+    ///     directory_count: 0,
+    ///     file_count: 0,
+    ///     truncated_dirs: 0,
+    ///     unread_dirs: 0,
+    ///     output_truncated: false,
+    ///     unreadable_dirs: Vec::new(),
+    ///     vanished_entries: 0,
+    ///     rejected: RejectionCounts::default(),
+    /// };
+    /// recorder.complete(&mut stats);
+    /// assert_eq!(stats.duration, Duration::from_secs(1));
+    /// ```
+    pub fn complete(&self, stats: &mut StreamStats) {
+        stats.directory_count = self.progress.directories;
+        stats.file_count = self.progress.files;
+        stats.truncated_dirs = self.progress.truncated_dirs;
+        stats.unread_dirs = self.progress.unread_dirs;
+        stats.duration += self.progress.elapsed;
+        stats
+            .unreadable_dirs
+            .extend(self.progress.unreadable_dirs.iter().cloned());
+        stats.unreadable_dirs.sort();
+        stats.unreadable_dirs.dedup();
+    }
+
+    /// Removes the checkpoint file once the output file is complete.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, including when no checkpoint was written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error if an existing checkpoint cannot be removed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::path::PathBuf;
+    /// use treepp::checkpoint::Recorder;
+    /// use treepp::config::Config;
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("C:\\"));
+    /// config.output.output_path = Some(PathBuf::from("c-drive.txt"));
+    /// let recorder = Recorder::new(&config, None);
+    /// // ... scan and write the whole tree ...
+    /// recorder.discard().unwrap();
+    /// ```
+    pub fn discard(self) -> io::Result<()> {
+        match fs::remove_file(&self.file) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::{EntryKind, EntryMetadata, StreamEntry};
+    use tempfile::TempDir;
+
+    fn dir_entry(path: PathBuf) -> StreamEvent {
+        StreamEvent::Entry(Box::new(StreamEntry {
+            name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            path,
+            kind: EntryKind::Directory,
+            metadata: EntryMetadata::default(),
+            depth: 0,
+            is_last: false,
+            is_file: false,
+            has_more_dirs: true,
+            filtered: None,
+            collapsed: None,
+            error: None,
+        }))
+    }
+
+    fn setup() -> (TempDir, Config) {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("a").join("b")).unwrap();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.output.output_path = Some(dir.path().join("tree.txt"));
+        config.output.checkpoint = true;
+        (dir, config)
+    }
+
+    #[test]
+    fn should_track_completed_directory() {
+        let (dir, config) = setup();
+        let a = dir.path().join("a");
+        let mut recorder = Recorder::new(&config, None);
+
+        assert!(!recorder.record(&dir_entry(a.clone())));
+        assert!(!recorder.record(&StreamEvent::EnterDir { is_last: true }));
+        assert!(!recorder.record(&dir_entry(a.join("b"))));
+        assert!(!recorder.record(&StreamEvent::EnterDir { is_last: true }));
+        recorder.last_saved -= CHECKPOINT_INTERVAL;
+        assert!(
+            recorder.record(&StreamEvent::LeaveDir),
+            "间隔已过时应请求检查点"
+        );
+        assert_eq!(recorder.progress.completed, Path::new("a").join("b"));
+        assert_eq!(recorder.open_dirs, [PathBuf::from("a")]);
+        assert_eq!(recorder.progress.directories, 2);
+    }
+
+    #[test]
+    fn should_resume_saved_checkpoint() {
+        let (dir, config) = setup();
+        fs::write(dir.path().join("tree.txt"), "D:.\n└─a\n").unwrap();
+        let mut recorder = Recorder::new(&config, None);
+        recorder.record(&dir_entry(dir.path().join("a")));
+        recorder.record(&StreamEvent::EnterDir { is_last: true });
+        recorder.record(&dir_entry(dir.path().join("a").join("b")));
+        recorder.record(&StreamEvent::EnterDir { is_last: true });
+        recorder.record(&StreamEvent::LeaveDir);
+        recorder
+            .save(8, StreamRendererState::default(), true, None)
+            .expect("写入检查点失败");
+
+        let LoadOutcome::Resumable(checkpoint) = load(&config) else {
+            panic!("应能恢复检查点");
+        };
+        assert_eq!(checkpoint.completed, Path::new("a").join("b"));
+        assert_eq!(checkpoint.output_len, 8);
+        assert_eq!(checkpoint.directories, 2);
+
+        let resumed = Recorder::new(&config, Some(&checkpoint));
+        assert_eq!(
+            resumed.open_dirs,
+            [PathBuf::from("a")],
+            "外层目录应保持打开"
+        );
+
+        resumed.discard().expect("删除检查点失败");
+        assert!(matches!(load(&config), LoadOutcome::Missing));
+    }
+
+    #[test]
+    fn should_ignore_checkpoint_of_other_run() {
+        let (dir, config) = setup();
+        fs::write(dir.path().join("tree.txt"), "D:.\n└─a\n").unwrap();
+        let mut recorder = Recorder::new(&config, None);
+        recorder.record(&dir_entry(dir.path().join("a")));
+        recorder.record(&StreamEvent::EnterDir { is_last: true });
+        recorder.record(&StreamEvent::LeaveDir);
+        recorder
+            .save(8, StreamRendererState::default(), true, None)
+            .unwrap();
+
+        let mut quiet = config.clone();
+        quiet.output.silent = true;
+        assert!(
+            matches!(load(&quiet), LoadOutcome::Resumable(_)),
+            "控制台选项不应影响恢复"
+        );
+
+        let mut other = config.clone();
+        other.scan.show_files = true;
+        assert!(
+            matches!(load(&other), LoadOutcome::Stale),
+            "选项不同时不应恢复"
+        );
+
+        fs::write(dir.path().join("tree.txt"), "D:.\n").unwrap();
+        assert!(
+            matches!(load(&config), LoadOutcome::Stale),
+            "输出文件变短时不应恢复"
+        );
+    }
+}
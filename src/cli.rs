@@ -21,23 +21,37 @@
 //!     Ok(ParseResult::Config(config)) => println!("{:?}", config),
 //!     Ok(ParseResult::Help) => println!("Show help"),
 //!     Ok(ParseResult::Version) => println!("Show version"),
+//!     Ok(ParseResult::Settings(command)) => println!("Change settings: {:?}", command),
 //!     Err(e) => eprintln!("Error: {}", e),
 //! }
 //! ```
 //!
 //! File: src/cli.rs
 //! Author: WaterRun
-//! Date: 2026-02-06
+//! Date: 2026-10-16
 
 #![forbid(unsafe_code)]
 
 use std::collections::HashSet;
 use std::env;
+use std::fs;
+use std::io::{self, Write};
 use std::num::NonZeroUsize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::config::{CharsetMode, Config, PathMode};
+use chrono::format::{Item, StrftimeItems};
+
+use crate::config::{
+    CacheMode, CharsetMode, Config, ConfigWarning, EscapeMode, GroupBy, Language, LevelGuides,
+    LineEnding, MAX_THREAD_COUNT, MenuScope, NumberFormat, OutputEncoding, PagerMode,
+    PathListSource, PathMode, ScanBackend, SortKey, StatsTarget, auto_thread_count,
+};
 pub(crate) use crate::error::CliError;
+use crate::i18n;
+use crate::matching::{PatternRule, parse_pattern_file};
+use crate::redact::Redactor;
+use crate::settings::{Settings, SettingsCommand};
 
 // ============================================================================
 // Parse Result
@@ -45,13 +59,14 @@ pub(crate) use crate::error::CliError;
 
 /// Result of command-line parsing.
 ///
-/// Represents the three possible outcomes after parsing command-line arguments.
+/// Represents the possible outcomes after parsing command-line arguments.
 ///
 /// # Variants
 ///
 /// * `Config` - Normal configuration, scanning should be executed
 /// * `Help` - User requested help information display
 /// * `Version` - User requested version information display
+/// * `Settings` - User requested a change to the settings file (`treepp config`)
 ///
 /// # Examples
 ///
@@ -63,17 +78,20 @@ pub(crate) use crate::error::CliError;
 ///     Ok(ParseResult::Help) => println!("Show help"),
 ///     Ok(ParseResult::Version) => println!("Show version"),
 ///     Ok(ParseResult::Config(c)) => println!("Config: {:?}", c),
+///     Ok(ParseResult::Settings(s)) => println!("Settings: {:?}", s),
 ///     Err(e) => eprintln!("Error: {}", e),
 /// }
 /// ```
 #[derive(Debug)]
 pub enum ParseResult {
     /// Normal configuration, scanning should be executed.
-    Config(Config),
+    Config(Box<Config>),
     /// User requested help information display.
     Help,
     /// User requested version information display.
     Version,
+    /// User requested a change to the settings file.
+    Settings(SettingsCommand),
 }
 
 // ============================================================================
@@ -115,6 +133,27 @@ struct ArgDef {
     long_patterns: &'static [&'static str],
 }
 
+impl ArgDef {
+    /// Checks whether an argument is one of this definition's patterns.
+    fn matches(&self, arg: &str) -> bool {
+        self.cmd_patterns
+            .iter()
+            .any(|p| arg.eq_ignore_ascii_case(p))
+            || self.short_patterns.contains(&arg)
+            || self.long_patterns.contains(&arg)
+    }
+
+    /// Checks whether this is a boolean option that can be switched off.
+    ///
+    /// Boolean options accept `--no-<flag>` and `--<flag>=false`, and may be
+    /// repeated with the last occurrence winning. `help` and `version` are
+    /// actions rather than settings, and `plain` is a preset of other
+    /// options; they are excluded.
+    fn is_boolean(&self) -> bool {
+        self.kind == ArgKind::Flag && !matches!(self.canonical, "help" | "version" | "plain")
+    }
+}
+
 /// All supported argument definitions.
 ///
 /// Arguments are organized by category for maintainability.
@@ -149,6 +188,55 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-p"],
         long_patterns: &["--full-path"],
     },
+    ArgDef {
+        canonical: "relative-to",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/REL"],
+        short_patterns: &[],
+        long_patterns: &["--relative-to"],
+    },
+    ArgDef {
+        canonical: "redact",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/RED"],
+        short_patterns: &[],
+        long_patterns: &["--redact"],
+    },
+    ArgDef {
+        canonical: "redact-term",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/RDT"],
+        short_patterns: &[],
+        long_patterns: &["--redact-term"],
+    },
+    ArgDef {
+        canonical: "quote",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/Q"],
+        short_patterns: &["-Q"],
+        long_patterns: &["--quote"],
+    },
+    ArgDef {
+        canonical: "classify",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/CL"],
+        short_patterns: &[],
+        long_patterns: &["--classify"],
+    },
+    ArgDef {
+        canonical: "escape",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/ES"],
+        short_patterns: &[],
+        long_patterns: &["--escape"],
+    },
+    ArgDef {
+        canonical: "raw-names",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/RN"],
+        short_patterns: &[],
+        long_patterns: &["--raw-names"],
+    },
     ArgDef {
         canonical: "size",
         kind: ArgKind::Flag,
@@ -163,6 +251,20 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-H"],
         long_patterns: &["--human-readable"],
     },
+    ArgDef {
+        canonical: "si",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/SU"],
+        short_patterns: &[],
+        long_patterns: &["--si"],
+    },
+    ArgDef {
+        canonical: "number-format",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/NF"],
+        short_patterns: &[],
+        long_patterns: &["--number-format"],
+    },
     ArgDef {
         canonical: "date",
         kind: ArgKind::Flag,
@@ -170,6 +272,34 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-d"],
         long_patterns: &["--date"],
     },
+    ArgDef {
+        canonical: "created",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/CT"],
+        short_patterns: &[],
+        long_patterns: &["--created"],
+    },
+    ArgDef {
+        canonical: "accessed",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/AT"],
+        short_patterns: &[],
+        long_patterns: &["--accessed"],
+    },
+    ArgDef {
+        canonical: "relative-dates",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/RD"],
+        short_patterns: &[],
+        long_patterns: &["--relative-dates"],
+    },
+    ArgDef {
+        canonical: "date-format",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/DF"],
+        short_patterns: &[],
+        long_patterns: &["--date-format"],
+    },
     ArgDef {
         canonical: "disk-usage",
         kind: ArgKind::Flag,
@@ -177,6 +307,27 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-u"],
         long_patterns: &["--disk-usage"],
     },
+    ArgDef {
+        canonical: "du-threshold",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/DUT"],
+        short_patterns: &[],
+        long_patterns: &["--du-threshold"],
+    },
+    ArgDef {
+        canonical: "du-percent",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/DUP"],
+        short_patterns: &[],
+        long_patterns: &["--du-percent"],
+    },
+    ArgDef {
+        canonical: "du-delta",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/DUD"],
+        short_patterns: &[],
+        long_patterns: &["--du-delta"],
+    },
     // Rendering style
     ArgDef {
         canonical: "ascii",
@@ -185,6 +336,13 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-a"],
         long_patterns: &["--ascii"],
     },
+    ArgDef {
+        canonical: "charset",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/CS"],
+        short_patterns: &[],
+        long_patterns: &["--charset"],
+    },
     ArgDef {
         canonical: "no-indent",
         kind: ArgKind::Flag,
@@ -192,6 +350,41 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-i"],
         long_patterns: &["--no-indent"],
     },
+    ArgDef {
+        canonical: "level-guides",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/LGD"],
+        short_patterns: &[],
+        long_patterns: &["--level-guides"],
+    },
+    ArgDef {
+        canonical: "flat",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/FLAT"],
+        short_patterns: &[],
+        long_patterns: &["--flat"],
+    },
+    ArgDef {
+        canonical: "no-align",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/NA"],
+        short_patterns: &[],
+        long_patterns: &["--no-align"],
+    },
+    ArgDef {
+        canonical: "truncate",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/TR"],
+        short_patterns: &[],
+        long_patterns: &["--truncate"],
+    },
+    ArgDef {
+        canonical: "sort",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/SO"],
+        short_patterns: &[],
+        long_patterns: &["--sort"],
+    },
     ArgDef {
         canonical: "reverse",
         kind: ArgKind::Flag,
@@ -199,6 +392,27 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-r"],
         long_patterns: &["--reverse"],
     },
+    ArgDef {
+        canonical: "interleave",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/IL"],
+        short_patterns: &[],
+        long_patterns: &["--interleave", "--no-dirs-first"],
+    },
+    ArgDef {
+        canonical: "group-by",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/GB"],
+        short_patterns: &[],
+        long_patterns: &["--group-by"],
+    },
+    ArgDef {
+        canonical: "columns",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/COL"],
+        short_patterns: &["-C"],
+        long_patterns: &["--columns"],
+    },
     // Filtering
     ArgDef {
         canonical: "level",
@@ -207,6 +421,27 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-L"],
         long_patterns: &["--level"],
     },
+    ArgDef {
+        canonical: "filelimit",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/FL"],
+        short_patterns: &[],
+        long_patterns: &["--filelimit"],
+    },
+    ArgDef {
+        canonical: "one-file-system",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/OFS"],
+        short_patterns: &["-x"],
+        long_patterns: &["--one-file-system"],
+    },
+    ArgDef {
+        canonical: "leaf-depth",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/LD"],
+        short_patterns: &[],
+        long_patterns: &["--leaf-depth"],
+    },
     ArgDef {
         canonical: "include",
         kind: ArgKind::Value,
@@ -214,6 +449,13 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-m"],
         long_patterns: &["--include"],
     },
+    ArgDef {
+        canonical: "include-override",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/IO"],
+        short_patterns: &[],
+        long_patterns: &["--include-override"],
+    },
     ArgDef {
         canonical: "exclude",
         kind: ArgKind::Value,
@@ -221,6 +463,41 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-I"],
         long_patterns: &["--exclude"],
     },
+    ArgDef {
+        canonical: "patterns-from",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/XF"],
+        short_patterns: &[],
+        long_patterns: &["--patterns-from"],
+    },
+    ArgDef {
+        canonical: "highlight",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/HL"],
+        short_patterns: &[],
+        long_patterns: &["--highlight"],
+    },
+    ArgDef {
+        canonical: "smart",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/SM"],
+        short_patterns: &[],
+        long_patterns: &["--smart"],
+    },
+    ArgDef {
+        canonical: "no-normalize",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/NN"],
+        short_patterns: &[],
+        long_patterns: &["--no-normalize"],
+    },
+    ArgDef {
+        canonical: "prune",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/P"],
+        short_patterns: &["-P"],
+        long_patterns: &["--prune"],
+    },
     ArgDef {
         canonical: "gitignore",
         kind: ArgKind::Flag,
@@ -235,6 +512,113 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-k"],
         long_patterns: &["--all"],
     },
+    ArgDef {
+        canonical: "mark-cloud",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/MC"],
+        short_patterns: &[],
+        long_patterns: &["--mark-cloud"],
+    },
+    ArgDef {
+        canonical: "skip-cloud-only",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/SCO"],
+        short_patterns: &[],
+        long_patterns: &["--skip-cloud-only"],
+    },
+    ArgDef {
+        canonical: "mark-empty-files",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/MEF"],
+        short_patterns: &[],
+        long_patterns: &["--mark-empty-files"],
+    },
+    ArgDef {
+        canonical: "prune-empty-files",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/PEF"],
+        short_patterns: &[],
+        long_patterns: &["--prune-empty-files"],
+    },
+    ArgDef {
+        canonical: "git-tracked",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/GT"],
+        short_patterns: &[],
+        long_patterns: &["--git-tracked"],
+    },
+    // Input
+    ArgDef {
+        canonical: "paths-from",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/PF"],
+        short_patterns: &[],
+        long_patterns: &["--paths-from"],
+    },
+    // Diagnostics
+    ArgDef {
+        canonical: "explain",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/EX"],
+        short_patterns: &[],
+        long_patterns: &["--explain"],
+    },
+    ArgDef {
+        canonical: "serve",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/SRV"],
+        short_patterns: &[],
+        long_patterns: &["--serve"],
+    },
+    ArgDef {
+        canonical: "port",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/PRT"],
+        short_patterns: &[],
+        long_patterns: &["--port"],
+    },
+    ArgDef {
+        canonical: "pipe",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/PIP"],
+        short_patterns: &[],
+        long_patterns: &["--pipe"],
+    },
+    ArgDef {
+        canonical: "print-config",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/PC"],
+        short_patterns: &[],
+        long_patterns: &["--print-config"],
+    },
+    ArgDef {
+        canonical: "print-schema",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/PS"],
+        short_patterns: &[],
+        long_patterns: &["--print-schema"],
+    },
+    ArgDef {
+        canonical: "install-context-menu",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/ICM"],
+        short_patterns: &[],
+        long_patterns: &["--install-context-menu"],
+    },
+    ArgDef {
+        canonical: "uninstall-context-menu",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/UCM"],
+        short_patterns: &[],
+        long_patterns: &["--uninstall-context-menu"],
+    },
+    ArgDef {
+        canonical: "show-filtered",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/SF"],
+        short_patterns: &[],
+        long_patterns: &["--show-filtered"],
+    },
     // Output control
     ArgDef {
         canonical: "report",
@@ -243,6 +627,34 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-e"],
         long_patterns: &["--report"],
     },
+    ArgDef {
+        canonical: "report-extensions",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/RE"],
+        short_patterns: &[],
+        long_patterns: &["--report-extensions"],
+    },
+    ArgDef {
+        canonical: "depth-histogram",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/DH"],
+        short_patterns: &[],
+        long_patterns: &["--depth-histogram"],
+    },
+    ArgDef {
+        canonical: "report-errors",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/ER"],
+        short_patterns: &[],
+        long_patterns: &["--report-errors"],
+    },
+    ArgDef {
+        canonical: "count",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/CNT"],
+        short_patterns: &[],
+        long_patterns: &["--count"],
+    },
     ArgDef {
         canonical: "no-win-banner",
         kind: ArgKind::Flag,
@@ -250,6 +662,34 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-N"],
         long_patterns: &["--no-win-banner"],
     },
+    ArgDef {
+        canonical: "plain",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/PL"],
+        short_patterns: &[],
+        long_patterns: &["--plain"],
+    },
+    ArgDef {
+        canonical: "lang",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/LG"],
+        short_patterns: &[],
+        long_patterns: &["--lang"],
+    },
+    ArgDef {
+        canonical: "json-errors",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/JE"],
+        short_patterns: &[],
+        long_patterns: &["--json-errors"],
+    },
+    ArgDef {
+        canonical: "quiet-warnings",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/QW"],
+        short_patterns: &[],
+        long_patterns: &["--quiet-warnings"],
+    },
     ArgDef {
         canonical: "silent",
         kind: ArgKind::Flag,
@@ -264,6 +704,125 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-o"],
         long_patterns: &["--output"],
     },
+    ArgDef {
+        canonical: "output-encoding",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/OE"],
+        short_patterns: &[],
+        long_patterns: &["--output-encoding"],
+    },
+    ArgDef {
+        canonical: "bom",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/BOM"],
+        short_patterns: &[],
+        long_patterns: &["--bom"],
+    },
+    ArgDef {
+        canonical: "crlf",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/CRLF"],
+        short_patterns: &[],
+        long_patterns: &["--crlf"],
+    },
+    ArgDef {
+        canonical: "lf",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/LF"],
+        short_patterns: &[],
+        long_patterns: &["--lf"],
+    },
+    ArgDef {
+        canonical: "append",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/AP"],
+        short_patterns: &[],
+        long_patterns: &["--append"],
+    },
+    ArgDef {
+        canonical: "no-clobber",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/NCL"],
+        short_patterns: &[],
+        long_patterns: &["--no-clobber"],
+    },
+    ArgDef {
+        canonical: "compress",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/GZ"],
+        short_patterns: &[],
+        long_patterns: &["--compress"],
+    },
+    ArgDef {
+        canonical: "report-dir",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/RPD"],
+        short_patterns: &[],
+        long_patterns: &["--report-dir"],
+    },
+    ArgDef {
+        canonical: "rotate",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/ROT"],
+        short_patterns: &[],
+        long_patterns: &["--rotate"],
+    },
+    ArgDef {
+        canonical: "checkpoint",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/CK"],
+        short_patterns: &[],
+        long_patterns: &["--checkpoint"],
+    },
+    ArgDef {
+        canonical: "node-ids",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/ID"],
+        short_patterns: &[],
+        long_patterns: &["--node-ids"],
+    },
+    ArgDef {
+        canonical: "max-entries",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/ME"],
+        short_patterns: &[],
+        long_patterns: &["--max-entries"],
+    },
+    ArgDef {
+        canonical: "max-output-size",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/MS"],
+        short_patterns: &[],
+        long_patterns: &["--max-output-size"],
+    },
+    ArgDef {
+        canonical: "stats-json",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/SJ"],
+        short_patterns: &[],
+        long_patterns: &["--stats-json"],
+    },
+    ArgDef {
+        canonical: "pager",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/PG"],
+        short_patterns: &[],
+        long_patterns: &["--pager"],
+    },
+    ArgDef {
+        canonical: "verbose",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/VB"],
+        short_patterns: &[],
+        long_patterns: &["--verbose"],
+    },
+    ArgDef {
+        canonical: "log-file",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/LOG"],
+        short_patterns: &[],
+        long_patterns: &["--log-file"],
+    },
     // Mode
     ArgDef {
         canonical: "batch",
@@ -280,10 +839,65 @@ const ARG_DEFINITIONS: &[ArgDef] = &[
         short_patterns: &["-t"],
         long_patterns: &["--thread"],
     },
+    ArgDef {
+        canonical: "backend",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/BK"],
+        short_patterns: &[],
+        long_patterns: &["--backend"],
+    },
+    ArgDef {
+        canonical: "cache",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/CA"],
+        short_patterns: &[],
+        long_patterns: &["--cache"],
+    },
+    ArgDef {
+        canonical: "no-cache",
+        kind: ArgKind::Flag,
+        cmd_patterns: &["/NC"],
+        short_patterns: &[],
+        long_patterns: &["--no-cache"],
+    },
+    ArgDef {
+        canonical: "read-timeout",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/RT"],
+        short_patterns: &[],
+        long_patterns: &["--read-timeout"],
+    },
+    ArgDef {
+        canonical: "timeout",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/TO"],
+        short_patterns: &[],
+        long_patterns: &["--timeout"],
+    },
+    ArgDef {
+        canonical: "budget-time",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/BT"],
+        short_patterns: &[],
+        long_patterns: &["--budget-time"],
+    },
+    ArgDef {
+        canonical: "budget-entries",
+        kind: ArgKind::Value,
+        cmd_patterns: &["/BE"],
+        short_patterns: &[],
+        long_patterns: &["--budget-entries"],
+    },
 ];
 
 /// Arguments that can be specified multiple times.
-const ACCUMULATIVE_OPTIONS: &[&str] = &["include", "exclude"];
+const ACCUMULATIVE_OPTIONS: &[&str] = &[
+    "include",
+    "exclude",
+    "patterns-from",
+    "include-override",
+    "redact-term",
+];
 
 // ============================================================================
 // Matched Argument
@@ -291,10 +905,20 @@ const ACCUMULATIVE_OPTIONS: &[&str] = &["include", "exclude"];
 
 /// Result of matching an argument to a definition.
 ///
-/// Contains the matched definition and optionally the parsed value.
+/// Contains the matched definition, optionally the parsed value, and for
+/// boolean options whether the option is switched on.
 struct MatchedArg {
     definition: &'static ArgDef,
     value: Option<String>,
+    enabled: bool,
+}
+
+/// What an `@<NAME>` argument resolved to.
+enum AtArgument {
+    /// A preset, whose arguments replaced it.
+    Expanded,
+    /// A path, from a bookmark or named like the argument.
+    Path(String),
 }
 
 // ============================================================================
@@ -328,6 +952,9 @@ pub struct CliParser {
     position: usize,
     seen_canonical_names: HashSet<String>,
     thread_explicitly_set: bool,
+    settings: Settings,
+    /// Presets being expanded, with the position after their arguments.
+    active_presets: Vec<(String, usize)>,
 }
 
 impl CliParser {
@@ -355,14 +982,49 @@ impl CliParser {
             position: 0,
             seen_canonical_names: HashSet::new(),
             thread_explicitly_set: false,
+            settings: Settings::default(),
+            active_presets: Vec::new(),
         }
     }
 
-    /// Creates a parser from environment arguments.
+    /// Applies the user's settings to the configurations this parser
+    /// produces.
     ///
-    /// Automatically skips the program name (first argument).
+    /// Default excludes from the settings come before any `--exclude` on
+    /// the command line, and `@<NAME>` arguments are expanded to the
+    /// presets of the settings.
     ///
-    /// # Returns
+    /// # Arguments
+    ///
+    /// * `settings` - Settings loaded from the settings file.
+    ///
+    /// # Returns
+    ///
+    /// The parser, for chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::cli::{CliParser, ParseResult};
+    /// use treepp::settings::Settings;
+    ///
+    /// let mut settings = Settings::default();
+    /// settings.add_exclude("node_modules");
+    /// let parser = CliParser::new(vec![]).with_settings(settings);
+    /// let Ok(ParseResult::Config(config)) = parser.parse() else { panic!() };
+    /// assert_eq!(config.matching.exclude_patterns, ["node_modules"]);
+    /// ```
+    #[must_use]
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Creates a parser from environment arguments.
+    ///
+    /// Automatically skips the program name (first argument).
+    ///
+    /// # Returns
     ///
     /// A new `CliParser` instance initialized with environment arguments.
     ///
@@ -380,6 +1042,64 @@ impl CliParser {
         Self::new(args)
     }
 
+    /// Determines the language of help, errors and the report.
+    ///
+    /// Looks for `--lang` ahead of parsing, so errors raised while parsing
+    /// are already reported in the requested language. Without a valid
+    /// `--lang`, the system UI language is used.
+    ///
+    /// # Returns
+    ///
+    /// The language to use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::cli::CliParser;
+    /// use treepp::config::Language;
+    ///
+    /// let parser = CliParser::new(vec!["/LG".to_string(), "zh".to_string()]);
+    /// assert_eq!(parser.language(), Language::Chinese);
+    /// ```
+    #[must_use]
+    pub fn language(&self) -> Language {
+        let lang = ARG_DEFINITIONS
+            .iter()
+            .find(|def| def.canonical == "lang")
+            .expect("lang option is defined");
+        self.args
+            .windows(2)
+            .find(|pair| lang.matches(&pair[0]))
+            .and_then(|pair| Language::from_arg(&pair[1]))
+            .unwrap_or_else(i18n::system_language)
+    }
+
+    /// Determines whether errors are reported as JSON (`--json-errors`).
+    ///
+    /// Like `language`, this looks ahead of parsing, so errors raised while
+    /// parsing are already reported as JSON.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `--json-errors` appears among the arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::cli::CliParser;
+    ///
+    /// let parser = CliParser::new(vec!["/ZZ".to_string(), "/JE".to_string()]);
+    /// assert!(parser.json_errors());
+    /// ```
+    #[must_use]
+    pub fn json_errors(&self) -> bool {
+        let json_errors = ARG_DEFINITIONS
+            .iter()
+            .find(|def| def.canonical == "json-errors")
+            .expect("json-errors option is defined");
+        self.args.iter().any(|arg| json_errors.matches(arg))
+    }
+
     /// Parses command-line arguments.
     ///
     /// After parsing, calls `Config::validate()` to verify configuration validity.
@@ -391,6 +1111,10 @@ impl CliParser {
     /// - `treepp /F C:\dir` ✓
     /// - `treepp /F C:\dir --ascii` ✓
     ///
+    /// An `@<NAME>` argument in place of a path is replaced by the arguments
+    /// of the preset `NAME` from the settings, which are then parsed like
+    /// the rest of the command line, or by the path of the bookmark `NAME`.
+    ///
     /// # Returns
     ///
     /// * `Ok(ParseResult)` - Successfully parsed result
@@ -399,11 +1123,13 @@ impl CliParser {
     /// # Errors
     ///
     /// * `CliError::UnknownOption` - Encountered unknown argument
+    /// * `CliError::ValueOptionInCluster` - Value-taking option inside a short option cluster
     /// * `CliError::MissingValue` - Value-requiring argument missing its value
     /// * `CliError::InvalidValue` - Invalid argument value
-    /// * `CliError::DuplicateOption` - Duplicate argument
+    /// * `CliError::DuplicateOption` - Duplicate value argument (boolean options: last one wins)
     /// * `CliError::MultiplePaths` - Multiple paths specified
     /// * `CliError::ConflictingOptions` - Conflicting arguments (e.g., `--thread` without `--batch`)
+    /// * `CliError::RecursivePreset` - A preset that expands to itself
     ///
     /// # Examples
     ///
@@ -419,26 +1145,40 @@ impl CliParser {
     /// }
     /// ```
     pub fn parse(mut self) -> Result<ParseResult, CliError> {
+        if let Some(command) = self.settings_command()? {
+            return Ok(ParseResult::Settings(command));
+        }
+
         let mut config = Config::default();
+        config.render.language = self.language();
+        config.matching.exclude_patterns = self.settings.exclude.clone();
         let mut collected_paths: Vec<String> = Vec::new();
 
         while self.position < self.args.len() {
             let current_arg = self.args[self.position].clone();
 
             if Self::is_option_like(&current_arg) {
-                let matched = self.try_match_argument(&current_arg)?;
-
-                if !ACCUMULATIVE_OPTIONS.contains(&matched.definition.canonical) {
-                    self.register_canonical_name(matched.definition.canonical)?;
-                }
-
-                self.apply_to_config(&mut config, &matched)?;
-
-                if matched.definition.canonical == "help" {
-                    return Ok(ParseResult::Help);
+                for matched in self.match_argument(&current_arg)? {
+                    if !ACCUMULATIVE_OPTIONS.contains(&matched.definition.canonical)
+                        && !matched.definition.is_boolean()
+                    {
+                        self.register_canonical_name(matched.definition.canonical)?;
+                    }
+
+                    self.apply_to_config(&mut config, &matched)?;
+
+                    if matched.definition.canonical == "help" {
+                        return Ok(ParseResult::Help);
+                    }
+                    if matched.definition.canonical == "version" {
+                        return Ok(ParseResult::Version);
+                    }
                 }
-                if matched.definition.canonical == "version" {
-                    return Ok(ParseResult::Version);
+            } else if let Some(name) = current_arg.strip_prefix('@') {
+                match self.resolve_at_argument(name)? {
+                    // The preset's arguments now start at the current position.
+                    AtArgument::Expanded => continue,
+                    AtArgument::Path(path) => collected_paths.push(path),
                 }
             } else {
                 collected_paths.push(current_arg);
@@ -456,11 +1196,140 @@ impl CliParser {
             });
         }
 
-        let validated_config = config.validate().map_err(|e| CliError::ParseError {
-            message: e.to_string(),
-        })?;
+        let validated_config = config.validate()?;
+
+        Ok(ParseResult::Config(Box::new(validated_config)))
+    }
+
+    /// Recognizes a `treepp config` or `treepp bookmark` command.
+    ///
+    /// The arguments form a command when the first is `config` or
+    /// `bookmark` and the second is not an option, so `treepp config /F`
+    /// still shows the tree of a directory named `config`.
+    ///
+    /// # Returns
+    ///
+    /// The command, or `None` if the arguments describe a scan.
+    ///
+    /// # Errors
+    ///
+    /// * `CliError::InvalidValue` - Unknown action after `config` or `bookmark`
+    /// * `CliError::MissingValue` - An action without its pattern, name or path
+    /// * `CliError::UnknownOption` - Extra argument after an action
+    fn settings_command(&self) -> Result<Option<SettingsCommand>, CliError> {
+        let [first, action, operands @ ..] = self.args.as_slice() else {
+            return Ok(None);
+        };
+        if !matches!(first.as_str(), "config" | "bookmark") || Self::is_option_like(action) {
+            return Ok(None);
+        }
+
+        let (min, max) = match (first.as_str(), action.as_str()) {
+            ("config", "add-exclude" | "remove") => (1, usize::MAX),
+            ("config" | "bookmark", "list") => (0, 0),
+            ("bookmark", "add") => (2, 2),
+            ("bookmark", "remove") => (1, 1),
+            ("config", _) => {
+                return Err(CliError::InvalidValue {
+                    option: first.clone(),
+                    value: action.clone(),
+                    reason: "must be add-exclude, list or remove".to_string(),
+                    suggestion: closest_value(action, SettingsCommand::CONFIG_ACTIONS),
+                });
+            }
+            _ => {
+                return Err(CliError::InvalidValue {
+                    option: first.clone(),
+                    value: action.clone(),
+                    reason: "must be add, list or remove".to_string(),
+                    suggestion: closest_value(action, SettingsCommand::BOOKMARK_ACTIONS),
+                });
+            }
+        };
+        if operands.len() < min {
+            return Err(CliError::MissingValue {
+                option: format!("{} {}", first, action),
+            });
+        }
+        if let Some(extra) = operands.get(max) {
+            return Err(CliError::UnknownOption {
+                option: extra.clone(),
+            });
+        }
+
+        // `@work` names the bookmark `work`, as when it is used.
+        let bookmark_name = || operands[0].trim_start_matches('@').to_string();
+        let command = match (first.as_str(), action.as_str()) {
+            ("config", "add-exclude") => SettingsCommand::AddExclude(operands.to_vec()),
+            ("config", "remove") => SettingsCommand::RemoveExclude(operands.to_vec()),
+            ("config", _) => SettingsCommand::ListExcludes,
+            (_, "add") => SettingsCommand::AddBookmark {
+                name: bookmark_name(),
+                path: PathBuf::from(&operands[1]),
+            },
+            (_, "remove") => SettingsCommand::RemoveBookmark(bookmark_name()),
+            _ => SettingsCommand::ListBookmarks,
+        };
+        Ok(Some(command))
+    }
+
+    /// Resolves the `@<NAME>` argument at the current position.
+    ///
+    /// A preset `NAME` is expanded in place; otherwise a bookmark `NAME`
+    /// gives the path, and failing that an existing file or directory
+    /// named like the argument.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The argument without `@`
+    ///
+    /// # Returns
+    ///
+    /// Whether the argument was expanded or stands for a path.
+    ///
+    /// # Errors
+    ///
+    /// * `CliError::RecursivePreset` - The preset is already being expanded
+    /// * `CliError::InvalidValue` - No such preset or bookmark, and no such
+    ///   path either
+    fn resolve_at_argument(&mut self, name: &str) -> Result<AtArgument, CliError> {
+        let Some(preset) = self.settings.preset.get(name) else {
+            if let Some(path) = self.settings.bookmark.get(name) {
+                return Ok(AtArgument::Path(path.to_string_lossy().into_owned()));
+            }
+            let arg = &self.args[self.position];
+            if Path::new(arg).exists() {
+                return Ok(AtArgument::Path(arg.clone()));
+            }
+            let names: Vec<&str> = (self.settings.preset.keys())
+                .chain(self.settings.bookmark.keys())
+                .map(String::as_str)
+                .collect();
+            return Err(CliError::InvalidValue {
+                option: "@<NAME>".to_string(),
+                value: name.to_string(),
+                reason: "no preset or bookmark of this name in the settings file".to_string(),
+                suggestion: closest_value(name, &names),
+            });
+        };
+
+        let position = self.position;
+        self.active_presets.retain(|(_, end)| *end > position);
+        if self.active_presets.iter().any(|(active, _)| active == name) {
+            return Err(CliError::RecursivePreset {
+                name: name.to_string(),
+            });
+        }
 
-        Ok(ParseResult::Config(validated_config))
+        let expansion = split_arguments(preset);
+        let count = expansion.len();
+        for (_, end) in &mut self.active_presets {
+            *end = *end + count - 1;
+        }
+        self.args.splice(position..=position, expansion);
+        self.active_presets
+            .push((name.to_string(), position + count));
+        Ok(AtArgument::Expanded)
     }
 
     /// Determines if a string looks like an option argument.
@@ -487,9 +1356,78 @@ impl CliParser {
         arg.starts_with('-') || arg.starts_with('/')
     }
 
+    /// Matches an option argument, expanding clustered short options.
+    ///
+    /// `-fa` is read as `-f -a` when it is not an option by itself. A
+    /// value-taking option may only end a cluster, and then takes the next
+    /// argument as its value (`-fL 2`).
+    ///
+    /// # Arguments
+    ///
+    /// * `arg` - The argument string to match
+    ///
+    /// # Returns
+    ///
+    /// The matched arguments, in command-line order.
+    ///
+    /// # Errors
+    ///
+    /// * `CliError::UnknownOption` - The argument, or a letter of the cluster, is unknown
+    /// * `CliError::ValueOptionInCluster` - A value-taking option is not last in the cluster
+    /// * `CliError::MissingValue` - The value of the last option is missing
+    fn match_argument(&mut self, arg: &str) -> Result<Vec<MatchedArg>, CliError> {
+        match self.try_match_argument(arg) {
+            Err(CliError::UnknownOption { .. }) if Self::is_short_cluster(arg) => {
+                self.match_short_cluster(arg)
+            }
+            result => result.map(|matched| vec![matched]),
+        }
+    }
+
+    /// Checks whether an argument looks like clustered short options (`-fa`).
+    fn is_short_cluster(arg: &str) -> bool {
+        arg.strip_prefix('-').is_some_and(|letters| {
+            letters.len() > 1 && letters.chars().all(|c| c.is_ascii_alphabetic())
+        })
+    }
+
+    /// Splits a short option cluster into its options.
+    fn match_short_cluster(&mut self, cluster: &str) -> Result<Vec<MatchedArg>, CliError> {
+        let letters: Vec<char> = cluster[1..].chars().collect();
+        let mut matches = Vec::with_capacity(letters.len());
+
+        for (i, letter) in letters.iter().enumerate() {
+            let option = format!("-{}", letter);
+            let definition = ARG_DEFINITIONS
+                .iter()
+                .find(|def| def.short_patterns.contains(&option.as_str()))
+                .ok_or_else(|| CliError::UnknownOption {
+                    option: option.clone(),
+                })?;
+
+            if definition.kind == ArgKind::Value && i + 1 < letters.len() {
+                return Err(CliError::ValueOptionInCluster {
+                    option,
+                    cluster: cluster.to_string(),
+                });
+            }
+
+            let value = self.consume_value_if_required(definition, &option)?;
+            matches.push(MatchedArg {
+                definition,
+                value,
+                enabled: true,
+            });
+        }
+
+        Ok(matches)
+    }
+
     /// Attempts to match an argument to a known definition.
     ///
-    /// Iterates through all argument definitions and attempts to match.
+    /// Iterates through all argument definitions and attempts to match. Only
+    /// if none matches is the argument tried as a negated boolean option, so
+    /// options whose own name starts with `--no-` keep their meaning.
     ///
     /// # Arguments
     ///
@@ -505,11 +1443,38 @@ impl CliParser {
                 return Ok(matched);
             }
         }
-        Err(CliError::UnknownOption {
+        Self::match_negated(arg).ok_or_else(|| CliError::UnknownOption {
             option: arg.to_string(),
         })
     }
 
+    /// Matches a negated boolean option (`--no-files`).
+    ///
+    /// # Arguments
+    ///
+    /// * `arg` - The argument string to match
+    ///
+    /// # Returns
+    ///
+    /// The matched option switched off, or `None` if `arg` is not the
+    /// negation of a boolean option.
+    fn match_negated(arg: &str) -> Option<MatchedArg> {
+        let name = arg.strip_prefix("--no-")?;
+        ARG_DEFINITIONS
+            .iter()
+            .filter(|def| def.is_boolean())
+            .find(|def| {
+                def.long_patterns
+                    .iter()
+                    .any(|p| p.strip_prefix("--") == Some(name))
+            })
+            .map(|definition| MatchedArg {
+                definition,
+                value: None,
+                enabled: false,
+            })
+    }
+
     /// Attempts to match an argument against a specific definition.
     ///
     /// Checks all three styles: CMD (case-insensitive), Unix short, and GNU long (both case-sensitive).
@@ -537,6 +1502,7 @@ impl CliParser {
                 return Ok(Some(MatchedArg {
                     definition: def,
                     value,
+                    enabled: true,
                 }));
             }
         }
@@ -547,6 +1513,7 @@ impl CliParser {
                 return Ok(Some(MatchedArg {
                     definition: def,
                     value,
+                    enabled: true,
                 }));
             }
         }
@@ -557,6 +1524,7 @@ impl CliParser {
                 return Ok(Some(MatchedArg {
                     definition: def,
                     value,
+                    enabled: true,
                 }));
             }
 
@@ -566,6 +1534,23 @@ impl CliParser {
                 return Ok(Some(MatchedArg {
                     definition: def,
                     value: Some(value),
+                    enabled: true,
+                }));
+            }
+
+            if let Some(value) = arg.strip_prefix(&equals_prefix)
+                && def.is_boolean()
+            {
+                let enabled = parse_bool(value).ok_or_else(|| CliError::InvalidValue {
+                    option: def.canonical.to_string(),
+                    value: value.to_string(),
+                    reason: "must be true or false".to_string(),
+                    suggestion: closest_value(value, BOOL_NAMES),
+                })?;
+                return Ok(Some(MatchedArg {
+                    definition: def,
+                    value: None,
+                    enabled,
                 }));
             }
         }
@@ -576,7 +1561,8 @@ impl CliParser {
     /// Consumes the next argument as a value if required.
     ///
     /// For value-type arguments, reads the next argument as the value.
-    /// For flag-type arguments, returns `None`.
+    /// For flag-type arguments, returns `None`. A lone `-` is accepted as a
+    /// value (conventionally meaning standard input).
     ///
     /// # Arguments
     ///
@@ -605,7 +1591,7 @@ impl CliParser {
         }
 
         let next_arg = &self.args[next_position];
-        if Self::is_option_like(next_arg) {
+        if Self::is_option_like(next_arg) && next_arg != "-" {
             return Err(CliError::MissingValue {
                 option: arg.to_string(),
             });
@@ -637,6 +1623,8 @@ impl CliParser {
     /// Applies a matched argument to the configuration.
     ///
     /// Updates the appropriate field in the config based on the matched argument.
+    /// Boolean options set their field to `matched.enabled`, so a later
+    /// occurrence overrides an earlier one.
     ///
     /// # Arguments
     ///
@@ -653,37 +1641,81 @@ impl CliParser {
         matched: &MatchedArg,
     ) -> Result<(), CliError> {
         let canonical = matched.definition.canonical;
+        let enabled = matched.enabled;
 
         match canonical {
             "help" => config.show_help = true,
             "version" => config.show_version = true,
-            "batch" => config.batch_mode = true,
-            "files" => config.scan.show_files = true,
-            "gitignore" => config.scan.respect_gitignore = true,
-            "all" => config.scan.show_hidden = true,
+            "batch" => config.batch_mode = enabled,
+            "files" => config.scan.show_files = enabled,
+            "gitignore" => config.scan.respect_gitignore = enabled,
+            "all" => config.scan.show_hidden = enabled,
+            "mark-cloud" => config.render.mark_cloud = enabled,
+            "skip-cloud-only" => config.scan.skip_cloud_only = enabled,
+            "mark-empty-files" => config.render.mark_empty_files = enabled,
+            "prune-empty-files" => config.matching.prune_empty_files = enabled,
             "level" => {
                 let value = matched.value.as_ref().expect("level requires a value");
                 let depth: usize = value.parse().map_err(|_| CliError::InvalidValue {
                     option: canonical.to_string(),
                     value: value.clone(),
                     reason: "must be a positive integer".to_string(),
+                    suggestion: None,
                 })?;
                 config.scan.max_depth = Some(depth);
             }
-            "thread" => {
-                let value = matched.value.as_ref().expect("thread requires a value");
-                let count: usize = value.parse().map_err(|_| CliError::InvalidValue {
+            "filelimit" => {
+                let value = matched.value.as_ref().expect("filelimit requires a value");
+                let limit: usize = value.parse().map_err(|_| CliError::InvalidValue {
                     option: canonical.to_string(),
                     value: value.clone(),
                     reason: "must be a positive integer".to_string(),
+                    suggestion: None,
                 })?;
-                config.scan.thread_count =
-                    NonZeroUsize::new(count).ok_or_else(|| CliError::InvalidValue {
+                config.scan.file_limit = Some(limit);
+            }
+            "leaf-depth" => {
+                let value = matched.value.as_ref().expect("leaf-depth requires a value");
+                let levels = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| CliError::InvalidValue {
                         option: canonical.to_string(),
                         value: value.clone(),
-                        reason: "thread count must be greater than 0".to_string(),
+                        reason: "must be a positive integer".to_string(),
+                        suggestion: None,
                     })?;
+                config.scan.leaf_depth = Some(levels);
+            }
+            "thread" => {
+                let value = matched.value.as_ref().expect("thread requires a value");
                 self.thread_explicitly_set = true;
+                if value.eq_ignore_ascii_case("auto") {
+                    config.scan.thread_count = auto_thread_count();
+                    return Ok(());
+                }
+                let count: usize = value.parse().map_err(|_| CliError::InvalidValue {
+                    option: canonical.to_string(),
+                    value: value.clone(),
+                    reason: "must be a positive integer or auto".to_string(),
+                    suggestion: closest_value(value, &["auto"]),
+                })?;
+                let count = NonZeroUsize::new(count).ok_or_else(|| CliError::InvalidValue {
+                    option: canonical.to_string(),
+                    value: value.clone(),
+                    reason: "thread count must be greater than 0".to_string(),
+                    suggestion: None,
+                })?;
+                config.scan.thread_count = if count.get() > MAX_THREAD_COUNT {
+                    config.warnings.push(ConfigWarning::ThreadCountClamped {
+                        requested: count.get(),
+                        used: MAX_THREAD_COUNT,
+                    });
+                    NonZeroUsize::new(MAX_THREAD_COUNT).expect("MAX_THREAD_COUNT is non-zero")
+                } else {
+                    count
+                };
             }
             "include" => {
                 if let Some(ref value) = matched.value {
@@ -695,113 +1727,1003 @@ impl CliParser {
                     config.matching.exclude_patterns.push(value.clone());
                 }
             }
-            "ascii" => config.render.charset = CharsetMode::Ascii,
-            "full-path" => config.render.path_mode = PathMode::Full,
-            "size" => config.render.show_size = true,
-            "human-readable" => config.render.human_readable = true,
-            "date" => config.render.show_date = true,
-            "disk-usage" => config.render.show_disk_usage = true,
-            "no-indent" => config.render.no_indent = true,
-            "reverse" => config.render.reverse_sort = true,
-            "report" => config.render.show_report = true,
-            "no-win-banner" => config.render.no_win_banner = true,
+            "patterns-from" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("patterns-from requires a value");
+                let text = fs::read_to_string(value).map_err(|e| CliError::InvalidValue {
+                    option: canonical.to_string(),
+                    value: value.clone(),
+                    reason: format!("cannot read pattern file: {e}"),
+                    suggestion: None,
+                })?;
+                for rule in parse_pattern_file(&text) {
+                    match rule {
+                        PatternRule::Include(p) => config.matching.include_patterns.push(p),
+                        PatternRule::Exclude(p) => config.matching.exclude_patterns.push(p),
+                    }
+                }
+            }
+            "include-override" => {
+                if let Some(ref value) = matched.value {
+                    config.matching.include_overrides.push(value.clone());
+                }
+            }
+            "ascii" => {
+                config.render.charset = if enabled {
+                    CharsetMode::Ascii
+                } else {
+                    CharsetMode::Unicode
+                };
+            }
+            "charset" => {
+                let value = matched.value.as_ref().expect("charset requires a value");
+                config.render.charset =
+                    CharsetMode::from_arg(value).ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be auto, unicode or ascii".to_string(),
+                        suggestion: closest_value(value, CharsetMode::NAMES),
+                    })?;
+            }
+            "full-path" => {
+                config.render.path_mode = if enabled {
+                    PathMode::Full
+                } else {
+                    PathMode::Relative
+                };
+            }
+            "relative-to" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("relative-to requires a value");
+                config.render.relative_to = Some(PathBuf::from(value));
+            }
+            "redact" => {
+                if !enabled {
+                    config.render.redaction = None;
+                } else if config.render.redaction.is_none() {
+                    config.render.redaction = Some(Redactor::default());
+                }
+            }
+            "redact-term" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("redact-term requires a value");
+                config
+                    .render
+                    .redaction
+                    .get_or_insert_with(Redactor::default)
+                    .add_term(value.as_str());
+            }
+            "quote" => config.render.quote_names = enabled,
+            "classify" => config.render.classify = enabled,
+            "highlight" => config.render.highlight = enabled,
+            "escape" => {
+                let value = matched.value.as_ref().expect("escape requires a value");
+                config.render.escape =
+                    EscapeMode::from_arg(value).ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be raw, question or c".to_string(),
+                        suggestion: closest_value(value, EscapeMode::NAMES),
+                    })?;
+            }
+            "raw-names" => config.render.raw_names = enabled,
+            "size" => config.render.show_size = enabled,
+            "human-readable" => config.render.human_readable = enabled,
+            "si" => config.render.si_units = enabled,
+            "number-format" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("number-format requires a value");
+                config.render.number_format =
+                    NumberFormat::from_arg(value).ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be plain, locale, comma, period, space or apostrophe"
+                            .to_string(),
+                        suggestion: closest_value(value, NumberFormat::NAMES),
+                    })?;
+            }
+            "date" => config.render.show_date = enabled,
+            "created" => config.render.show_created = enabled,
+            "accessed" => config.render.show_accessed = enabled,
+            "relative-dates" => config.render.relative_dates = enabled,
+            "date-format" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("date-format requires a value");
+                if value.is_empty() || StrftimeItems::new(value).any(|item| item == Item::Error) {
+                    return Err(CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be a strftime pattern such as %Y-%m-%d".to_string(),
+                        suggestion: None,
+                    });
+                }
+                config.render.date_format = Some(value.clone());
+            }
+            "disk-usage" => config.render.show_disk_usage = enabled,
+            "no-indent" => config.render.no_indent = enabled,
+            "flat" => config.render.flat = enabled,
+            "no-align" => config.render.no_align = enabled,
+            "truncate" => config.render.truncate = enabled,
+            "sort" => {
+                let value = matched.value.as_ref().expect("sort requires a value");
+                config.render.sort_key =
+                    SortKey::from_arg(value).ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be name, size, mtime, ctime, atime or share".to_string(),
+                        suggestion: closest_value(value, SortKey::NAMES),
+                    })?;
+            }
+            "reverse" => config.render.reverse_sort = enabled,
+            "interleave" => config.render.interleave = enabled,
+            "columns" => config.render.columns = enabled,
+            "level-guides" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("level-guides requires a value");
+                config.render.level_guides =
+                    LevelGuides::from_arg(value).ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be none, number or color".to_string(),
+                        suggestion: closest_value(value, LevelGuides::NAMES),
+                    })?;
+            }
+            "group-by" => {
+                let value = matched.value.as_ref().expect("group-by requires a value");
+                config.render.group_by =
+                    GroupBy::from_arg(value).ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be none or type".to_string(),
+                        suggestion: closest_value(value, GroupBy::NAMES),
+                    })?;
+            }
+            "du-percent" => config.render.show_du_percent = enabled,
+            "du-delta" => config.render.show_du_delta = enabled,
+            "report" => config.render.show_report = enabled,
+            "report-extensions" => config.render.report_extensions = enabled,
+            "depth-histogram" => config.render.depth_histogram = enabled,
+            "report-errors" => config.render.report_errors = enabled,
+            "count" => config.render.count_only = enabled,
+            "no-win-banner" => config.render.no_win_banner = enabled,
+            "plain" => {
+                // A preset: options after it can still override each setting.
+                config.render.no_win_banner = true;
+                config.render.charset = CharsetMode::Ascii;
+                config.render.show_report = false;
+                config.output.trim_trailing_whitespace = true;
+                config.output.line_ending = LineEnding::Lf;
+            }
+            "lang" => {
+                let value = matched.value.as_ref().expect("lang requires a value");
+                config.render.language =
+                    Language::from_arg(value).ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be en or zh".to_string(),
+                        suggestion: closest_value(value, Language::NAMES),
+                    })?;
+            }
             "output" => {
                 if let Some(ref value) = matched.value {
                     config.output.output_path = Some(PathBuf::from(value));
                 }
             }
-            "silent" => config.output.silent = true,
-            _ => {}
-        }
-
-        Ok(())
-    }
-
-    /// Validates path arguments.
-    ///
-    /// Ensures at most one path is specified and updates the config accordingly.
-    ///
-    /// # Arguments
-    ///
-    /// * `paths` - Collected path arguments
-    /// * `config` - The configuration to update
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(())` - Valid paths
-    /// * `Err(CliError::MultiplePaths)` - More than one path specified
-    fn validate_paths(&self, paths: &[String], config: &mut Config) -> Result<(), CliError> {
-        match paths.len() {
-            0 => {
-                config.path_explicitly_set = false;
-                Ok(())
+            "output-encoding" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("output-encoding requires a value");
+                config.output.encoding =
+                    OutputEncoding::from_arg(value).ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be utf8, utf16le or gbk".to_string(),
+                        suggestion: closest_value(value, OutputEncoding::NAMES),
+                    })?;
             }
-            1 => {
-                config.root_path = PathBuf::from(&paths[0]);
-                config.path_explicitly_set = true;
-                Ok(())
+            "bom" => config.output.bom = enabled,
+            "crlf" | "lf" => {
+                config.output.line_ending = if enabled == (canonical == "crlf") {
+                    LineEnding::Crlf
+                } else {
+                    LineEnding::Lf
+                };
             }
-            _ => Err(CliError::MultiplePaths {
-                paths: paths.to_vec(),
-            }),
-        }
-    }
-}
-
-// ============================================================================
-// Help and Version Text
-// ============================================================================
-
-/// Returns the help information string.
-///
-/// # Returns
-///
-/// A static string containing the complete help text.
-///
-/// # Examples
-///
-/// ```
-/// use treepp::cli::help_text;
-///
-/// let help = help_text();
-/// assert!(help.contains("tree++"));
-/// assert!(help.contains("--help"));
-/// ```
-#[must_use]
-pub fn help_text() -> &'static str {
+            "append" => config.output.append = enabled,
+            "no-clobber" => config.output.no_clobber = enabled,
+            "compress" => config.output.compress = enabled,
+            "report-dir" => {
+                let value = matched.value.as_ref().expect("report-dir requires a value");
+                config.output.report_dir = Some(PathBuf::from(value));
+            }
+            "rotate" => {
+                let value = matched.value.as_ref().expect("rotate requires a value");
+                let keep = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be a positive integer".to_string(),
+                        suggestion: None,
+                    })?;
+                config.output.rotate = Some(keep);
+            }
+            "checkpoint" => config.output.checkpoint = enabled,
+            "node-ids" => config.output.node_ids = enabled,
+            "silent" => config.output.silent = enabled,
+            "stats-json" => {
+                if let Some(ref value) = matched.value {
+                    config.output.stats_json = Some(StatsTarget::from_arg(value));
+                }
+            }
+            "verbose" => {
+                // Each repetition raises the level; `--no-verbose` resets it.
+                config.output.verbosity = if enabled {
+                    config.output.verbosity.saturating_add(1)
+                } else {
+                    0
+                };
+            }
+            "log-file" => {
+                if let Some(ref value) = matched.value {
+                    config.output.log_file = Some(PathBuf::from(value));
+                }
+            }
+            "pager" => {
+                let value = matched.value.as_ref().expect("pager requires a value");
+                config.output.pager =
+                    PagerMode::from_arg(value).ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be auto, never or always".to_string(),
+                        suggestion: closest_value(value, PagerMode::NAMES),
+                    })?;
+            }
+            "backend" => {
+                let value = matched.value.as_ref().expect("backend requires a value");
+                config.scan.backend =
+                    ScanBackend::from_arg(value).ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be auto, walk, parallel or channel".to_string(),
+                        suggestion: closest_value(value, ScanBackend::NAMES),
+                    })?;
+            }
+            "cache" => {
+                config.scan.cache = if enabled {
+                    CacheMode::Enabled
+                } else {
+                    CacheMode::Disabled
+                };
+            }
+            "no-cache" => {
+                config.scan.cache = if enabled {
+                    CacheMode::Discard
+                } else {
+                    CacheMode::Disabled
+                };
+            }
+            "max-entries" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("max-entries requires a value");
+                let limit = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be a positive integer".to_string(),
+                        suggestion: None,
+                    })?;
+                config.scan.max_entries = Some(limit);
+            }
+            "du-threshold" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("du-threshold requires a value");
+                let threshold = parse_byte_size(value).filter(|&n| n > 0).ok_or_else(|| {
+                    CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be a positive size such as 4096, 512K, 100M or 1G"
+                            .to_string(),
+                        suggestion: None,
+                    }
+                })?;
+                config.scan.du_threshold = Some(threshold);
+            }
+            "max-output-size" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("max-output-size requires a value");
+                let limit = parse_byte_size(value).filter(|&n| n > 0).ok_or_else(|| {
+                    CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be a positive size such as 4096, 512K, 10M or 1G".to_string(),
+                        suggestion: None,
+                    }
+                })?;
+                config.output.max_output_size = Some(limit);
+            }
+            "read-timeout" | "timeout" | "budget-time" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("timeout options require a value");
+                let timeout = parse_duration(value)
+                    .filter(|d| !d.is_zero())
+                    .ok_or_else(|| CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be a positive duration such as 500ms, 30s or 2m".to_string(),
+                        suggestion: None,
+                    })?;
+                match canonical {
+                    "timeout" => config.scan.timeout = Some(timeout),
+                    "budget-time" => config.scan.budget_time = Some(timeout),
+                    _ => config.scan.read_timeout = Some(timeout),
+                }
+            }
+            "budget-entries" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("budget-entries requires a value");
+                let budget = parse_entry_count(value).filter(|&n| n > 0).ok_or_else(|| {
+                    CliError::InvalidValue {
+                        option: canonical.to_string(),
+                        value: value.clone(),
+                        reason: "must be a positive count such as 50000, 500K or 1M".to_string(),
+                        suggestion: None,
+                    }
+                })?;
+                config.scan.budget_entries = Some(budget);
+            }
+            "git-tracked" => config.scan.git_tracked = enabled,
+            "one-file-system" => config.scan.one_file_system = enabled,
+            "prune" => config.matching.prune_empty = enabled,
+            "smart" => config.matching.smart_excludes = enabled,
+            "no-normalize" => config.matching.no_normalize = enabled,
+            "show-filtered" => config.scan.show_filtered = enabled,
+            "explain" => {
+                if let Some(ref value) = matched.value {
+                    config.explain_target = Some(PathBuf::from(value));
+                }
+            }
+            "serve" => config.serve = enabled,
+            "port" => {
+                let value = matched.value.as_ref().expect("port requires a value");
+                let port: u16 = value.parse().map_err(|_| CliError::InvalidValue {
+                    option: canonical.to_string(),
+                    value: value.clone(),
+                    reason: "must be a port number from 0 to 65535".to_string(),
+                    suggestion: None,
+                })?;
+                config.serve_port = Some(port);
+            }
+            "pipe" => {
+                let value = matched.value.as_ref().expect("pipe requires a value");
+                config.serve_pipe = Some(value.clone());
+            }
+            "print-config" => config.print_config = enabled,
+            "print-schema" => config.print_schema = enabled,
+            "json-errors" => config.json_errors = enabled,
+            "quiet-warnings" => config.output.quiet_warnings = enabled,
+            "install-context-menu" | "uninstall-context-menu" => {
+                let value = matched
+                    .value
+                    .as_ref()
+                    .expect("context menu requires a scope");
+                let scope = MenuScope::from_arg(value).ok_or_else(|| CliError::InvalidValue {
+                    option: canonical.to_string(),
+                    value: value.clone(),
+                    reason: "must be user or machine".to_string(),
+                    suggestion: closest_value(value, MenuScope::NAMES),
+                })?;
+                if canonical == "install-context-menu" {
+                    config.install_menu = Some(scope);
+                } else {
+                    config.uninstall_menu = Some(scope);
+                }
+            }
+            "paths-from" => {
+                if let Some(ref value) = matched.value {
+                    config.scan.paths_from = Some(PathListSource::from_arg(value));
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Validates path arguments.
+    ///
+    /// Ensures at most one path is specified and updates the config accordingly.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Collected path arguments
+    /// * `config` - The configuration to update
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Valid paths
+    /// * `Err(CliError::MultiplePaths)` - More than one path specified
+    fn validate_paths(&self, paths: &[String], config: &mut Config) -> Result<(), CliError> {
+        match paths.len() {
+            0 => {
+                config.path_explicitly_set = false;
+                Ok(())
+            }
+            1 => {
+                config.root_path = PathBuf::from(&paths[0]);
+                config.path_explicitly_set = true;
+                Ok(())
+            }
+            _ => Err(CliError::MultiplePaths {
+                paths: paths.to_vec(),
+            }),
+        }
+    }
+}
+
+// ============================================================================
+// Value Parsing
+// ============================================================================
+
+/// Canonical values accepted by `parse_bool`, used in error suggestions.
+const BOOL_NAMES: &[&str] = &["true", "false"];
+
+/// Splits a preset into arguments.
+///
+/// Arguments are separated by whitespace; double quotes group an argument
+/// that contains spaces and are removed, as on the command line.
+///
+/// # Arguments
+///
+/// * `value` - The preset, e.g. `/F /X "My Files"`.
+///
+/// # Returns
+///
+/// The arguments, in order.
+fn split_arguments(value: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// Computes the Levenshtein edit distance between two strings.
+///
+/// # Arguments
+///
+/// * `a` - First string
+/// * `b` - Second string
+///
+/// # Returns
+///
+/// The minimum number of single-character insertions, deletions and
+/// substitutions turning `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Finds the accepted value closest to a rejected one.
+///
+/// Comparison is case-insensitive. A candidate is only suggested when it is
+/// within a third of its length (at least one edit) and shares at least one
+/// character position, so unrelated input does not produce a misleading
+/// suggestion.
+///
+/// # Arguments
+///
+/// * `value` - The rejected value
+/// * `accepted` - The accepted values
+///
+/// # Returns
+///
+/// The closest accepted value, or `None` if none is near enough.
+fn closest_value(value: &str, accepted: &[&str]) -> Option<String> {
+    let value = value.to_ascii_lowercase();
+    accepted
+        .iter()
+        .map(|candidate| (edit_distance(&value, candidate), *candidate))
+        .filter(|(distance, candidate)| {
+            *distance <= (candidate.len() / 3).max(1) && *distance < candidate.len()
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Parses the value of a boolean option given as `--<flag>=<value>`.
+///
+/// Accepts `true`/`false`, `yes`/`no`, `on`/`off` and `1`/`0`, case-insensitively.
+///
+/// # Arguments
+///
+/// * `value` - The text after `=`
+///
+/// # Returns
+///
+/// The boolean value, or `None` if the text is not recognized.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parses a byte size with an optional binary unit suffix.
+///
+/// Accepts plain byte counts and the suffixes `K`, `M` and `G` (optionally
+/// followed by `B`), case-insensitively. Units are powers of 1024.
+///
+/// # Arguments
+///
+/// * `value` - The size string, e.g. `4096`, `512K` or `10MB`.
+///
+/// # Returns
+///
+/// The size in bytes, or `None` if the value is malformed or overflows.
+fn parse_byte_size(value: &str) -> Option<u64> {
+    let upper = value.trim().to_ascii_uppercase();
+    let digits = upper.strip_suffix('B').unwrap_or(&upper);
+    let (number, multiplier) = match digits.chars().last()? {
+        'K' => (&digits[..digits.len() - 1], 1024),
+        'M' => (&digits[..digits.len() - 1], 1024 * 1024),
+        'G' => (&digits[..digits.len() - 1], 1024 * 1024 * 1024),
+        _ => (digits, 1),
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Parses an entry count with an optional decimal unit suffix.
+///
+/// Accepts plain counts and the suffixes `K` and `M`, case-insensitively.
+/// Unlike byte sizes, units are powers of 1000: `1M` is one million entries.
+///
+/// # Arguments
+///
+/// * `value` - The count string, e.g. `50000`, `500K` or `1M`.
+///
+/// # Returns
+///
+/// The count, or `None` if the value is malformed or overflows.
+fn parse_entry_count(value: &str) -> Option<usize> {
+    let upper = value.trim().to_ascii_uppercase();
+    let (number, multiplier) = match upper.chars().last()? {
+        'K' => (&upper[..upper.len() - 1], 1_000),
+        'M' => (&upper[..upper.len() - 1], 1_000_000),
+        _ => (upper.as_str(), 1),
+    };
+    number.parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+/// Parses a duration with an optional unit suffix.
+///
+/// Accepts whole numbers followed by `ms`, `s` or `m`, case-insensitively;
+/// a number without a suffix is in seconds.
+///
+/// # Arguments
+///
+/// * `value` - The duration string, e.g. `500ms`, `30` or `2m`.
+///
+/// # Returns
+///
+/// The duration, or `None` if the value is malformed or overflows.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let lower = value.trim().to_ascii_lowercase();
+    let (number, millis_per_unit) = if let Some(number) = lower.strip_suffix("ms") {
+        (number, 1)
+    } else if let Some(number) = lower.strip_suffix('s') {
+        (number, 1000)
+    } else if let Some(number) = lower.strip_suffix('m') {
+        (number, 60 * 1000)
+    } else {
+        (lower.as_str(), 1000)
+    };
+    let millis = number.parse::<u64>().ok()?.checked_mul(millis_per_unit)?;
+    Some(Duration::from_millis(millis))
+}
+
+// ============================================================================
+// Help and Version Text
+// ============================================================================
+
+/// Returns the help information string.
+///
+/// # Returns
+///
+/// A static string containing the complete help text.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::cli::help_text;
+///
+/// let help = help_text();
+/// assert!(help.contains("tree++"));
+/// assert!(help.contains("--help"));
+/// ```
+#[must_use]
+pub fn help_text() -> &'static str {
     r#"tree++: A much better Windows tree command.
 
 Usage:
   treepp [<PATH>] [<OPTIONS>...]
+  treepp config <ACTION> [<PATTERN>...]
+  treepp bookmark <ACTION> [<NAME>] [<PATH>]
 
 Options:
   --help, -h, /?              Show help information
   --version, -v, /V           Show version information
   --batch, -b, /B             Use batch processing mode
   --ascii, -a, /A             Draw the tree using ASCII characters
+  --charset, /CS <MODE>       Tree characters (auto, unicode, ascii); auto uses ASCII
+                              on consoles that cannot show Unicode
   --files, -f, /F             Show files
   --full-path, -p, /FP        Show full paths
+  --relative-to, /REL <PATH>  Show full paths relative to PATH, taken from the root
+                              (. for the root itself); implies /FP
+  --redact, /RED              Replace the user profile and user name in names and paths
+  --redact-term, /RDT <TEXT>  Also replace TEXT wherever it appears; implies /RED
+  --quote, -Q, /Q             Wrap names in double quotes
+  --classify, /CL             Append / to directories, * to executables, @ to links
+  --escape, /ES <MODE>        Escape non-printable characters in names (raw, question, c)
+  --raw-names, /RN            Print control characters in names to the console unchanged
   --human-readable, -H, /HR   Show file sizes in human-readable format
+  --si, /SU                   Use decimal size units (1 kB = 1000 B); implies /HR
+  --number-format, /NF <FMT>  Group size digits (plain, locale, comma, period, space, apostrophe)
   --no-indent, -i, /NI        Do not display tree connector lines
+  --level-guides, /LGD <MODE> Mark connector lines by level (none, number, color)
+  --flat, /FLAT               List full paths one per line instead of a tree
+  --no-align, /NA             Do not align sizes and dates in a column
+  --truncate, /TR             Shorten long names to fit the console width
+  --sort, /SO <KEY>           Sort by name, size, mtime, ctime, atime or share
   --reverse, -r, /R           Sort in reverse order
+  --interleave, /IL           Sort files and directories together, as GNU tree; implies /B
+  --group-by, /GB <MODE>      List files in sections by kind (none, type); implies /B
+  --columns, -C, /COL         Lay files out in columns that fit the console; implies /B
   --size, -s, /S              Show file size (bytes)
   --date, -d, /DT             Show last modified date
-  --exclude, -I, /X <PATTERN> Exclude files matching the pattern
+  --created, /CT              Show creation date
+  --accessed, /AT             Show last access date
+  --relative-dates, /RD       Show dates as "3 days ago"; implies /DT
+  --date-format, /DF <FMT>    Date pattern in strftime syntax; implies /DT
+  --exclude, -I, /X <PATTERN> Exclude files matching the pattern; !PATTERN re-includes
+                              files excluded by an earlier pattern
+  --patterns-from, /XF <FILE> Read exclude patterns (and +include patterns) from a file,
+                              one per line; # starts a comment
+  --include-override, /IO <PATTERN>
+                              Always show files matching the pattern, despite
+                              .gitignore, --smart, --exclude and --include
+  --smart, /SM                Exclude target, node_modules, build and .git in detected projects
+  --no-normalize, /NN         Match and sort names without Unicode (NFC) normalization
   --level, -L, /L <N>         Limit recursion depth
+  --filelimit, /FL <N>        Do not descend directories with more than N entries
+  --one-file-system, -x, /OFS
+                              Do not descend into directories on other volumes
+  --leaf-depth, /LD <N>       Show only the last N levels of deep branches
   --include, -m, /M <PATTERN> Show only files matching the pattern
+  --highlight, /HL            Highlight the parts of file names matched by --include
+  --prune, -P, /P             Prune directories that contain no files after filtering
   --disk-usage, -u, /DU       Show cumulative directory sizes (requires --batch)
+  --du-threshold, /DUT <SIZE> Hide directories smaller than SIZE (requires --disk-usage)
+  --du-percent, /DUP          Show each directory's share of its parent (requires --disk-usage)
+  --du-delta, /DUD            Show each directory's growth since the last --du-delta run
   --report, -e, /RP           Show summary statistics at the end
+  --report-extensions, /RE    Show the most common file extensions by count and size
+  --depth-histogram, /DH      Show entry counts and file sizes per depth level
+  --report-errors, /ER        List the directories that could not be read
+  --count, /CNT               Print only totals and per-filter counts, without the tree
   --no-win-banner, -N, /NB    Do not show the Windows native tree banner/header
+  --plain, /PL                Stable plain text: no banner, ASCII, no report,
+                              LF line ends, no trailing whitespace
+  --lang, /LG <LANG>          Language of help, errors and report (en, zh)
+  --json-errors, /JE          Write errors to stderr as a JSON object with a stable code
+  --quiet-warnings, /QW       Do not write warnings to stderr
   --silent, -l, /SI           Silent mode (requires --output)
   --output, -o, /O <FILE>     Write output to a file (.txt, .json, .yml, .toml)
                               Note: JSON/YAML/TOML formats require --batch
-  --thread, -t, /T <N>        Number of scanning threads (requires --batch, default: 8)
+  --output-encoding, /OE <ENC>
+                              Encoding of the file and redirected stdout (utf8, utf16le, gbk)
+  --bom, /BOM                 Start the output with a byte order mark
+  --crlf, /CRLF               End lines with CRLF
+  --lf, /LF                   End lines with LF (default)
+  --append, /AP               Append to the output file instead of replacing it
+  --no-clobber, /NCL          Fail if the output file already exists
+  --compress, /GZ             Gzip the output file (implied by a .gz output path)
+  --report-dir, /RPD <DIR>    Write each run to a new timestamped file in DIR (--output names it)
+  --rotate, /ROT <N>          Keep only the newest N files in --report-dir
+  --checkpoint, /CK           Record progress so an interrupted /O run can resume
+  --node-ids, /ID             Add stable id and parent_id fields to JSON/YAML output
+  --max-entries, /ME <N>      List at most N entries per directory
+  --max-output-size, /MS <SIZE>
+                              Stop the tree once it reaches SIZE (e.g. 512K, 10M)
+  --stats-json, /SJ <FILE>    Write scan statistics as JSON (FILE, or - for stderr)
+  --pager, /PG <WHEN>         Page long output with %PAGER% or more (auto, never, always)
+  --verbose, /VB              Log scan details to stderr; repeat for more detail
+                              (-v is --version)
+  --log-file, /LOG <FILE>     Write the log to FILE instead of stderr (implies --verbose)
+  --thread, -t, /T <N>        Number of scanning threads, or auto (requires --batch, default: auto)
+  --backend, /BK <NAME>       Directory traversal: auto, walk, parallel, channel
+                              (requires --batch, default: auto)
+  --cache, /CA                Reuse cached listings of unchanged directories
+  --no-cache, /NC             Delete the cache of this directory and scan without it
+  --read-timeout, /RT <TIME>  Give up on a directory after TIME (e.g. 500ms, 30s, 2m)
+  --timeout, /TO <TIME>       Stop reading directories once the scan has run for TIME
+  --budget-time, /BT <TIME>   End the scan after TIME and report the tree as truncated
+  --budget-entries, /BE <N>   End the scan after N entries (e.g. 500K, 1M), as truncated
   --gitignore, -g, /G         Respect .gitignore
   --all, -k, /AL              Show hidden files (Windows hidden attribute)
+  --mark-cloud, /MC           Mark cloud-only files (OneDrive, Dropbox) with [cloud]
+  --skip-cloud-only, /SCO     Leave out cloud-only files that are not stored locally
+  --mark-empty-files, /MEF    Mark zero-byte files with [empty]
+  --prune-empty-files, /PEF   Leave out zero-byte files (with /P, also folders left empty)
+  --git-tracked, /GT          Show only files tracked by the git index
+  --paths-from, /PF <FILE>    Build the tree from a path list (FILE, or - for stdin)
+  --explain, /EX <PATH>       Explain why PATH is shown or hidden, then exit
+  --serve, /SRV               Answer JSON-RPC queries about the tree over local HTTP
+  --port, /PRT <N>            Port for --serve (default 7878; 0 picks a free port)
+  --pipe, /PIP <NAME>         Serve on \\.\pipe\NAME as well; without --port, on the pipe only
+  --print-config, /PC         Print the resolved options as TOML, then exit
+  --print-schema, /PS         Print the JSON Schema of JSON/YAML output, then exit
+  --install-context-menu, /ICM <SCOPE>
+                              Add "Open tree++ here" to the folder context menu (user, machine)
+  --uninstall-context-menu, /UCM <SCOPE>
+                              Remove the folder context-menu entry (user, machine)
+  --show-filtered, /SF        List filtered entries annotated with the reason
+
+Settings (%APPDATA%\treepp\config.toml):
+  config add-exclude <PATTERN>...
+                              Exclude PATTERN from every scan, as with --exclude
+  config remove <PATTERN>...  Stop excluding PATTERN from every scan
+  config list                 List the patterns excluded from every scan
+  bookmark add <NAME> <PATH>  Bookmark the directory PATH as @NAME
+  bookmark remove <NAME>      Remove the bookmark @NAME
+  bookmark list               List the bookmarks
+  @<NAME>                     Use the options of preset NAME from [preset] in the settings,
+                              or else the directory bookmarked as NAME
+
+Boolean options can be switched off with --no-<option> or --<option>=false
+(e.g. --no-files, --files=false); when repeated, the last one wins.
 
 More info: https://github.com/Water-Run/treepp"#
 }
 
+/// Returns the help text in the given language.
+///
+/// # Arguments
+///
+/// * `language` - Language of the help text
+///
+/// # Returns
+///
+/// A static string containing the complete help text.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::cli::localized_help_text;
+/// use treepp::config::Language;
+///
+/// let help = localized_help_text(Language::Chinese);
+/// assert!(help.contains("--files"));
+/// ```
+#[must_use]
+pub fn localized_help_text(language: Language) -> &'static str {
+    match language {
+        Language::English => help_text(),
+        Language::Chinese => help_text_zh(),
+    }
+}
+
+/// Returns the Chinese help text.
+///
+/// Mirrors `help_text` line by line; option names are not translated.
+#[must_use]
+fn help_text_zh() -> &'static str {
+    r#"tree++：更好的 Windows tree 命令。
+
+用法：
+  treepp [<PATH>] [<OPTIONS>...]
+  treepp config <ACTION> [<PATTERN>...]
+  treepp bookmark <ACTION> [<NAME>] [<PATH>]
+
+选项：
+  --help, -h, /?              显示帮助信息
+  --version, -v, /V           显示版本信息
+  --batch, -b, /B             使用批处理模式
+  --ascii, -a, /A             使用 ASCII 字符绘制树
+  --charset, /CS <MODE>       树形字符（auto、unicode、ascii）；auto 在无法显示 Unicode
+                              的控制台上使用 ASCII
+  --files, -f, /F             显示文件
+  --full-path, -p, /FP        显示完整路径
+  --relative-to, /REL <PATH>  显示相对于 PATH 的完整路径，PATH 从根目录起算
+                              （. 即根目录本身）；隐含 /FP
+  --redact, /RED              将名称和路径中的用户配置文件目录与用户名替换为占位符
+  --redact-term, /RDT <TEXT>  同时替换所有出现的 TEXT；隐含 /RED
+  --quote, -Q, /Q             用双引号包裹名称
+  --classify, /CL             为目录附加 /、可执行文件附加 *、链接附加 @
+  --escape, /ES <MODE>        转义名称中的不可打印字符（raw、question、c）
+  --raw-names, /RN            向控制台原样输出名称中的控制字符
+  --human-readable, -H, /HR   以易读格式显示文件大小
+  --si, /SU                   使用十进制大小单位（1 kB = 1000 B）；隐含 /HR
+  --number-format, /NF <FMT>  大小数字分组（plain、locale、comma、period、space、apostrophe）
+  --no-indent, -i, /NI        不显示树形连接线
+  --level-guides, /LGD <MODE> 按层级标记连接线（none、number、color）
+  --flat, /FLAT               逐行列出完整路径而不显示树形结构
+  --no-align, /NA             不按列对齐大小与日期
+  --truncate, /TR             缩短过长的名称以适应控制台宽度
+  --sort, /SO <KEY>           按 name、size、mtime、ctime、atime 或 share 排序
+  --reverse, -r, /R           逆序排序
+  --interleave, /IL           文件与目录混合排序（与 GNU tree 相同）；隐含 /B
+  --group-by, /GB <MODE>      按类型分节列出文件（none、type）；隐含 /B
+  --columns, -C, /COL         按控制台宽度分栏列出文件；隐含 /B
+  --size, -s, /S              显示文件大小（字节）
+  --date, -d, /DT             显示最后修改日期
+  --created, /CT              显示创建日期
+  --accessed, /AT             显示最后访问日期
+  --relative-dates, /RD       以“3 天前”的形式显示日期；隐含 /DT
+  --date-format, /DF <FMT>    strftime 语法的日期格式；隐含 /DT
+  --exclude, -I, /X <PATTERN> 排除匹配模式的文件；!PATTERN 重新包含被之前的模式排除的文件
+  --patterns-from, /XF <FILE> 从文件读取排除模式（以及 + 开头的包含模式），每行一个；
+                              # 开头为注释
+  --include-override, /IO <PATTERN>
+                              始终显示匹配模式的文件，不受 .gitignore、--smart、
+                              --exclude 与 --include 影响
+  --smart, /SM                在识别出的项目中排除 target、node_modules、build 与 .git
+  --no-normalize, /NN         匹配与排序名称时不进行 Unicode (NFC) 规范化
+  --level, -L, /L <N>         限制递归深度
+  --filelimit, /FL <N>        不展开条目数超过 N 的目录
+  --one-file-system, -x, /OFS
+                              不进入其他卷上的目录
+  --leaf-depth, /LD <N>       仅显示深层分支的最后 N 层
+  --include, -m, /M <PATTERN> 仅显示匹配模式的文件
+  --highlight, /HL            高亮文件名中与 --include 匹配的部分
+  --prune, -P, /P             修剪过滤后不含文件的目录
+  --disk-usage, -u, /DU       显示目录累计大小（需要 --batch）
+  --du-threshold, /DUT <SIZE> 隐藏累计大小小于 SIZE 的目录（需要 --disk-usage）
+  --du-percent, /DUP          显示每个目录占上级目录大小的百分比（需要 --disk-usage）
+  --du-delta, /DUD            显示每个目录自上次 --du-delta 运行以来的增长
+  --report, -e, /RP           在末尾显示统计信息
+  --report-extensions, /RE    按数量与大小显示最常见的文件扩展名
+  --depth-histogram, /DH      按深度层级显示条目数与文件大小
+  --report-errors, /ER        列出无法读取的目录
+  --count, /CNT               仅输出总计与各过滤器的排除数，不显示树
+  --no-win-banner, -N, /NB    不显示 Windows 原生 tree 的样板信息
+  --plain, /PL                稳定的纯文本：无样板信息、ASCII、无统计报告、
+                              LF 换行、无行尾空白
+  --lang, /LG <LANG>          帮助、错误与统计信息的语言（en、zh）
+  --json-errors, /JE          以带有稳定错误码的 JSON 对象向标准错误写出错误
+  --quiet-warnings, /QW       不向标准错误写出警告
+  --silent, -l, /SI           静默模式（需要 --output）
+  --output, -o, /O <FILE>     将结果输出到文件（.txt、.json、.yml、.toml）
+                              注意：JSON/YAML/TOML 格式需要 --batch
+  --output-encoding, /OE <ENC>
+                              文件与重定向标准输出的编码（utf8、utf16le、gbk）
+  --bom, /BOM                 在输出开头写入字节顺序标记
+  --crlf, /CRLF               以 CRLF 结束每行
+  --lf, /LF                   以 LF 结束每行（默认）
+  --append, /AP               追加到输出文件而不是替换它
+  --no-clobber, /NCL          输出文件已存在时报错
+  --compress, /GZ             以 gzip 压缩输出文件（输出路径以 .gz 结尾时自动启用）
+  --report-dir, /RPD <DIR>    每次运行在 DIR 中写入带时间戳的新文件（--output 给出文件名）
+  --rotate, /ROT <N>          仅保留 --report-dir 中最新的 N 个文件
+  --checkpoint, /CK           记录进度，使中断的 /O 运行可以继续
+  --node-ids, /ID             为 JSON/YAML 输出添加稳定的 id 与 parent_id 字段
+  --max-entries, /ME <N>      每个目录最多列出 N 个条目
+  --max-output-size, /MS <SIZE>
+                              树达到 SIZE 时停止（如 512K、10M）
+  --stats-json, /SJ <FILE>    以 JSON 写出扫描统计（FILE，或 - 表示 stderr）
+  --pager, /PG <WHEN>         用 %PAGER% 或 more 分页显示长输出（auto、never、always）
+  --verbose, /VB              将扫描细节记录到 stderr；重复以获得更多细节
+                              （-v 为 --version）
+  --log-file, /LOG <FILE>     将日志写入 FILE 而不是 stderr（隐含 --verbose）
+  --thread, -t, /T <N>        扫描线程数或 auto（需要 --batch，默认：auto）
+  --backend, /BK <NAME>       目录遍历方式：auto、walk、parallel、channel
+                              （需要 --batch，默认：auto）
+  --cache, /CA                复用未变化目录的缓存列表
+  --no-cache, /NC             删除此目录的缓存并在不使用缓存的情况下扫描
+  --read-timeout, /RT <TIME>  读取单个目录超过 TIME 后放弃（如 500ms、30s、2m）
+  --timeout, /TO <TIME>       扫描运行 TIME 后不再读取目录
+  --budget-time, /BT <TIME>   扫描 TIME 后结束，并将树报告为已截断
+  --budget-entries, /BE <N>   扫描 N 个条目后结束（如 500K、1M），并报告为已截断
+  --gitignore, -g, /G         遵循 .gitignore
+  --all, -k, /AL              显示隐藏文件（Windows 隐藏属性）
+  --mark-cloud, /MC           以 [cloud] 标记仅云端文件（OneDrive、Dropbox）
+  --skip-cloud-only, /SCO     省略未存储在本地的仅云端文件
+  --mark-empty-files, /MEF    以 [empty] 标记零字节文件
+  --prune-empty-files, /PEF   省略零字节文件（配合 /P 时也省略因此变空的目录）
+  --git-tracked, /GT          仅显示 git 索引跟踪的文件
+  --paths-from, /PF <FILE>    从路径列表构建树（FILE，或 - 表示 stdin）
+  --explain, /EX <PATH>       解释 PATH 被显示或隐藏的原因，然后退出
+  --serve, /SRV               通过本地 HTTP 以 JSON-RPC 回答关于目录树的查询
+  --port, /PRT <N>            --serve 的端口（默认 7878；0 表示任选空闲端口）
+  --pipe, /PIP <NAME>         也在 \\.\pipe\NAME 上提供服务；未指定 --port 时仅用管道
+  --print-config, /PC         以 TOML 输出解析后的全部选项，然后退出
+  --print-schema, /PS         输出 JSON/YAML 结果的 JSON Schema，然后退出
+  --install-context-menu, /ICM <SCOPE>
+                              在文件夹右键菜单中添加“在此处打开 tree++”（user、machine）
+  --uninstall-context-menu, /UCM <SCOPE>
+                              移除文件夹右键菜单项（user、machine）
+  --show-filtered, /SF        列出被过滤的条目并标注原因
+
+设置（%APPDATA%\treepp\config.toml）：
+  config add-exclude <PATTERN>...
+                              在每次扫描中排除 PATTERN，与 --exclude 相同
+  config remove <PATTERN>...  不再在每次扫描中排除 PATTERN
+  config list                 列出每次扫描都排除的模式
+  bookmark add <NAME> <PATH>  将目录 PATH 添加为书签 @NAME
+  bookmark remove <NAME>      移除书签 @NAME
+  bookmark list               列出全部书签
+  @<NAME>                     使用设置文件 [preset] 中预设 NAME 的选项，
+                              否则使用书签 NAME 对应的目录
+
+布尔选项可以用 --no-<option> 或 --<option>=false 关闭
+（如 --no-files、--files=false）；重复指定时以最后一次为准。
+
+更多信息：https://github.com/Water-Run/treepp"#
+}
+
 /// Returns the version information string.
 ///
 /// # Returns
@@ -814,7 +2736,7 @@ More info: https://github.com/Water-Run/treepp"#
 /// use treepp::cli::version_text;
 ///
 /// let version = version_text();
-/// assert!(version.contains("0.1.0"));
+/// assert!(version.contains("0.4.0"));
 /// ```
 #[must_use]
 pub fn version_text() -> &'static str {
@@ -828,18 +2750,22 @@ link: https://github.com/Water-Run/treepp"#
 
 /// Prints help information to standard output.
 ///
+/// A reader that stops early, such as `Select-Object -First 5`, is not an
+/// error, so write failures are ignored.
+///
 /// # Examples
 ///
 /// ```no_run
 /// use treepp::cli::print_help;
+/// use treepp::config::Language;
 ///
-/// print_help();
+/// print_help(Language::English);
 /// ```
-pub fn print_help() {
-    println!("{}", help_text());
+pub fn print_help(language: Language) {
+    let _ = writeln!(io::stdout(), "{}", localized_help_text(language));
 }
 
-/// Prints version information to standard output.
+/// Prints version information to standard output, ignoring write failures.
 ///
 /// # Examples
 ///
@@ -849,7 +2775,7 @@ pub fn print_help() {
 /// print_version();
 /// ```
 pub fn print_version() {
-    println!("{}", version_text());
+    let _ = writeln!(io::stdout(), "{}", version_text());
 }
 
 // ============================================================================
@@ -859,7 +2785,7 @@ pub fn print_version() {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::OutputFormat;
+    use crate::config::{ConfigError, OutputFormat};
     use tempfile::TempDir;
 
     fn create_temp_dir() -> TempDir {
@@ -886,12 +2812,12 @@ mod tests {
         if let Ok(ParseResult::Config(config)) = result {
             assert!(config.root_path.is_absolute());
             assert!(!config.scan.show_files);
-            assert_eq!(config.scan.thread_count.get(), 8);
+            assert_eq!(config.scan.thread_count, auto_thread_count());
             assert_eq!(config.scan.max_depth, None);
             assert!(!config.scan.respect_gitignore);
             assert!(config.matching.include_patterns.is_empty());
             assert!(config.matching.exclude_patterns.is_empty());
-            assert_eq!(config.render.charset, CharsetMode::Unicode);
+            assert_eq!(config.render.charset, CharsetMode::Auto);
             assert_eq!(config.render.path_mode, PathMode::Relative);
             assert!(!config.render.show_size);
             assert!(!config.render.human_readable);
@@ -1134,6 +3060,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_charset_values() {
+        for arg in ["--charset", "/CS", "/cs"] {
+            for (value, mode) in [
+                ("auto", CharsetMode::Auto),
+                ("Unicode", CharsetMode::Unicode),
+                ("ascii", CharsetMode::Ascii),
+            ] {
+                assert_eq!(
+                    parse_config(&[arg, value]).render.charset,
+                    mode,
+                    "测试 {arg} {value}"
+                );
+            }
+        }
+        assert_eq!(
+            parse_config(&["/A", "/CS", "auto"]).render.charset,
+            CharsetMode::Auto
+        );
+
+        let parser = CliParser::new(vec!["/CS".to_string(), "asci".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::InvalidValue { suggestion: Some(ref s), .. }) if s == "ascii"
+        ));
+    }
+
     #[test]
     fn map_equivalent_level_options() {
         for (flag, value) in &[("/L", "5"), ("-L", "5"), ("--level", "5")] {
@@ -1256,23 +3209,38 @@ mod tests {
     // ========================================================================
 
     #[test]
-    fn parse_duplicate_option_different_styles_fails() {
+    fn parse_duplicate_flag_different_styles_succeeds() {
         let parser = CliParser::new(vec!["/F".to_string(), "--files".to_string()]);
-        let result = parser.parse();
-        assert!(matches!(result, Err(CliError::DuplicateOption { .. })));
+        if let Ok(ParseResult::Config(config)) = parser.parse() {
+            assert!(config.scan.show_files);
+        } else {
+            panic!("解析失败");
+        }
     }
 
     #[test]
-    fn parse_duplicate_option_same_style_fails() {
+    fn parse_duplicate_flag_same_style_succeeds() {
         let parser = CliParser::new(vec!["/F".to_string(), "/F".to_string()]);
         let result = parser.parse();
-        assert!(matches!(result, Err(CliError::DuplicateOption { .. })));
+        assert!(result.is_ok(), "重复的布尔选项应以最后一次为准");
     }
 
     #[test]
-    fn parse_duplicate_option_different_case_cmd_fails() {
+    fn parse_duplicate_flag_different_case_cmd_succeeds() {
         let parser = CliParser::new(vec!["/F".to_string(), "/f".to_string()]);
         let result = parser.parse();
+        assert!(result.is_ok(), "重复的布尔选项应以最后一次为准");
+    }
+
+    #[test]
+    fn parse_duplicate_value_option_different_styles_fails() {
+        let parser = CliParser::new(vec![
+            "/SO".to_string(),
+            "size".to_string(),
+            "--sort".to_string(),
+            "name".to_string(),
+        ]);
+        let result = parser.parse();
         assert!(matches!(result, Err(CliError::DuplicateOption { .. })));
     }
 
@@ -1385,8 +3353,7 @@ mod tests {
 
     #[test]
     fn parse_thread_with_cmd_style() {
-        let parser =
-            CliParser::new(vec!["/B".to_string(), "/T".to_string(), "4".to_string()]);
+        let parser = CliParser::new(vec!["/B".to_string(), "/T".to_string(), "4".to_string()]);
 
         if let Ok(ParseResult::Config(config)) = parser.parse() {
             assert_eq!(config.scan.thread_count.get(), 4);
@@ -1526,8 +3493,7 @@ mod tests {
 
     #[test]
     fn parse_exclude_pattern() {
-        let parser =
-            CliParser::new(vec!["--exclude".to_string(), "node_modules".to_string()]);
+        let parser = CliParser::new(vec!["--exclude".to_string(), "node_modules".to_string()]);
 
         if let Ok(ParseResult::Config(config)) = parser.parse() {
             assert_eq!(
@@ -1577,6 +3543,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_root_anchored_exclude_with_equals_syntax() {
+        let config = parse_config(&["--exclude=/target"]);
+        assert_eq!(config.matching.exclude_patterns, ["/target"]);
+
+        let parser = CliParser::new(vec!["/X".to_string(), "/target".to_string()]);
+        assert!(
+            matches!(parser.parse(), Err(CliError::MissingValue { .. })),
+            "独立的 /target 参数应被视为开关"
+        );
+    }
+
+    #[test]
+    fn parse_exclude_negation_and_include_override() {
+        let config = parse_config(&["/X", "*.log", "/X", "!keep.log", "/X", "keep-old.log"]);
+        assert_eq!(
+            config.matching.exclude_patterns,
+            ["*.log", "!keep.log", "keep-old.log"],
+            "应保留顺序"
+        );
+
+        let config = parse_config(&["/IO", "*.md", "/io", "LICENSE"]);
+        assert_eq!(config.matching.include_overrides, ["*.md", "LICENSE"]);
+    }
+
+    #[test]
+    fn parse_patterns_from_keeps_declaration_order() {
+        let temp_dir = create_temp_dir();
+        let file = temp_dir.path().join("patterns.txt");
+        std::fs::write(&file, "# CI\n*.log\n+*.rs\n!keep.log\n").expect("写入模式文件失败");
+        let option = format!("--patterns-from={}", file.display());
+
+        let config = parse_config(&["/X", "*.tmp", &option, "--exclude", "keep.log"]);
+        assert_eq!(
+            config.matching.exclude_patterns,
+            ["*.tmp", "*.log", "!keep.log", "keep.log"],
+            "文件中的模式应插入到选项所在位置"
+        );
+        assert_eq!(config.matching.include_patterns, ["*.rs"]);
+
+        let missing = temp_dir.path().join("missing.txt");
+        let parser = CliParser::new(vec![format!("--patterns-from={}", missing.display())]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidValue { .. })));
+    }
+
     #[test]
     fn parse_gitignore_all_styles() {
         for flag in &["--gitignore", "-g", "/G", "/g"] {
@@ -1591,10 +3602,7 @@ mod tests {
 
     #[test]
     fn parse_include_with_complex_pattern() {
-        let parser = CliParser::new(vec![
-            "--include".to_string(),
-            "src/**/*.rs".to_string(),
-        ]);
+        let parser = CliParser::new(vec!["--include".to_string(), "src/**/*.rs".to_string()]);
 
         if let Ok(ParseResult::Config(config)) = parser.parse() {
             assert_eq!(
@@ -1641,9 +3649,43 @@ mod tests {
     }
 
     #[test]
-    fn parse_size_all_styles() {
-        for flag in &["--size", "-s", "/S"] {
-            let parser = CliParser::new(vec![flag.to_string()]);
+    fn parse_relative_to_all_styles() {
+        for flag in ["--relative-to", "/REL", "/rel"] {
+            let config = parse_config(&[flag, "src"]);
+            let base = config.render.relative_to.expect("应设置基准目录");
+            assert!(base.is_absolute() && base.ends_with("src"), "测试 {flag}");
+            assert_eq!(
+                config.render.path_mode,
+                PathMode::Full,
+                "--relative-to 应隐含 /FP"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_redact_terms_accumulate() {
+        for flag in ["--redact", "/RED", "/red"] {
+            let config = parse_config(&[flag]);
+            let redaction = config.render.redaction.expect("应启用脱敏");
+            assert!(redaction.terms().is_empty(), "测试 {flag}");
+        }
+
+        let config = parse_config(&["--redact-term", "falcon", "/RDT", "Atlas-Internal"]);
+        let redaction = config.render.redaction.expect("--redact-term 应隐含 /RED");
+        assert_eq!(redaction.terms(), ["Atlas-Internal", "falcon"]);
+
+        assert!(
+            parse_config(&["/RDT", "falcon", "--no-redact"])
+                .render
+                .redaction
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn parse_size_all_styles() {
+        for flag in &["--size", "-s", "/S"] {
+            let parser = CliParser::new(vec![flag.to_string()]);
             if let Ok(ParseResult::Config(config)) = parser.parse() {
                 assert!(config.render.show_size, "测试 {flag}");
             } else {
@@ -1689,6 +3731,118 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_du_threshold_all_styles() {
+        for arg in ["--du-threshold", "/DUT", "/dut"] {
+            let parser = CliParser::new(vec![
+                "/B".to_string(),
+                "/DU".to_string(),
+                arg.to_string(),
+                "100M".to_string(),
+            ]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(
+                    config.scan.du_threshold,
+                    Some(100 * 1024 * 1024),
+                    "测试 {arg} 失败"
+                );
+            } else {
+                panic!("解析 {arg} 失败");
+            }
+        }
+    }
+
+    #[test]
+    fn parse_du_threshold_without_disk_usage_fails() {
+        let parser = CliParser::new(vec!["/B".to_string(), "/DUT".to_string(), "1M".to_string()]);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn parse_invalid_du_threshold_fails() {
+        for value in ["0", "M", "big"] {
+            let parser = CliParser::new(vec![
+                "/B".to_string(),
+                "/DU".to_string(),
+                "/DUT".to_string(),
+                value.to_string(),
+            ]);
+            assert!(
+                matches!(parser.parse(), Err(CliError::InvalidValue { .. })),
+                "{value} 应被拒绝"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_du_percent_all_styles() {
+        for flag in ["--du-percent", "/DUP", "/dup"] {
+            let config = parse_config(&["/B", "/DU", flag]);
+            assert!(config.render.show_du_percent, "测试 {flag} 失败");
+        }
+        let parser = CliParser::new(vec!["/B".to_string(), "/DUP".to_string()]);
+        assert!(parser.parse().is_err(), "/DUP 需要 /DU");
+    }
+
+    #[test]
+    fn parse_du_delta_all_styles() {
+        for flag in ["--du-delta", "/DUD", "/dud"] {
+            let config = parse_config(&["/B", "/DU", flag]);
+            assert!(config.render.show_du_delta, "测试 {flag} 失败");
+        }
+        let parser = CliParser::new(vec!["/B".to_string(), "/DUD".to_string()]);
+        assert!(parser.parse().is_err(), "/DUD 需要 /DU");
+    }
+
+    #[test]
+    fn parse_one_file_system_all_styles() {
+        for flag in ["--one-file-system", "-x", "/OFS", "/ofs"] {
+            let config = parse_config(&[flag]);
+            assert!(config.scan.one_file_system, "测试 {flag} 失败");
+        }
+        assert!(!parse_config(&[]).scan.one_file_system);
+    }
+
+    #[test]
+    fn parse_cloud_options_all_styles() {
+        for flag in ["--mark-cloud", "/MC", "/mc"] {
+            let config = parse_config(&[flag]);
+            assert!(config.render.mark_cloud, "测试 {flag} 失败");
+        }
+        for flag in ["--skip-cloud-only", "/SCO", "/sco"] {
+            let config = parse_config(&[flag]);
+            assert!(config.scan.skip_cloud_only, "测试 {flag} 失败");
+        }
+        let config = parse_config(&[]);
+        assert!(!config.render.mark_cloud);
+        assert!(!config.scan.skip_cloud_only);
+    }
+
+    #[test]
+    fn parse_empty_file_options_all_styles() {
+        for flag in ["--mark-empty-files", "/MEF", "/mef"] {
+            let config = parse_config(&[flag]);
+            assert!(config.render.mark_empty_files, "测试 {flag} 失败");
+        }
+        for flag in ["--prune-empty-files", "/PEF", "/pef"] {
+            let config = parse_config(&[flag]);
+            assert!(config.matching.prune_empty_files, "测试 {flag} 失败");
+            assert!(!config.matching.prune_empty, "不应隐含 /P");
+        }
+        let config = parse_config(&[]);
+        assert!(!config.render.mark_empty_files);
+        assert!(!config.matching.prune_empty_files);
+    }
+
+    #[test]
+    fn parse_classify_all_styles() {
+        for flag in ["--classify", "/CL", "/cl"] {
+            let config = parse_config(&[flag]);
+            assert!(config.render.classify, "测试 {flag} 失败");
+        }
+        assert!(!parse_config(&[]).render.classify);
+    }
+
     #[test]
     fn parse_no_indent_all_styles() {
         for flag in &["--no-indent", "-i", "/NI", "/ni"] {
@@ -1827,6 +3981,52 @@ mod tests {
         assert!(result.is_ok(), "只有选项无路径应该成功（使用当前目录）");
     }
 
+    #[test]
+    fn parse_path_at_any_position_gives_same_config() {
+        let path = ".".to_string();
+        let parse = |args: Vec<String>| match CliParser::new(args).parse() {
+            Ok(ParseResult::Config(config)) => config,
+            other => panic!("解析失败: {:?}", other.err()),
+        };
+
+        let first = parse(vec![
+            path.clone(),
+            "/F".to_string(),
+            "/L".to_string(),
+            "2".to_string(),
+        ]);
+        let middle = parse(vec![
+            "/F".to_string(),
+            path.clone(),
+            "/L".to_string(),
+            "2".to_string(),
+        ]);
+        let last = parse(vec![
+            "/F".to_string(),
+            "/L".to_string(),
+            "2".to_string(),
+            path,
+        ]);
+
+        assert_eq!(first, middle);
+        assert_eq!(first, last);
+        assert!(first.path_explicitly_set);
+        assert_eq!(first.scan.max_depth, Some(2), "选项的值不应被视为路径");
+    }
+
+    #[test]
+    fn parse_paths_separated_by_options_fails() {
+        let parser = CliParser::new(vec![
+            "path1".to_string(),
+            "/F".to_string(),
+            "path2".to_string(),
+        ]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::MultiplePaths { ref paths }) if paths == &["path1", "path2"]
+        ));
+    }
+
     #[test]
     fn parse_path_between_options() {
         let temp_dir = create_temp_dir();
@@ -1916,8 +4116,23 @@ mod tests {
         let parser = parser_with_temp_dir(
             &temp_dir,
             vec![
-                "/B", "/F", "-a", "--level", "5", "-s", "-H", "-r", "--include", "*.rs",
-                "--exclude", "target", "-g", "--report", "-N", "--thread", "4",
+                "/B",
+                "/F",
+                "-a",
+                "--level",
+                "5",
+                "-s",
+                "-H",
+                "-r",
+                "--include",
+                "*.rs",
+                "--exclude",
+                "target",
+                "-g",
+                "--report",
+                "-N",
+                "--thread",
+                "4",
                 "--disk-usage",
             ],
         );
@@ -1949,7 +4164,13 @@ mod tests {
 
         let parser = parser_with_temp_dir(
             &temp_dir,
-            vec!["--batch", "--output", output_file.to_str().unwrap(), "--silent", "/F"],
+            vec![
+                "--batch",
+                "--output",
+                output_file.to_str().unwrap(),
+                "--silent",
+                "/F",
+            ],
         );
 
         if let Ok(ParseResult::Config(config)) = parser.parse() {
@@ -2199,8 +4420,10 @@ mod tests {
     #[test]
     fn parse_batch_with_multiple_options() {
         let temp_dir = create_temp_dir();
-        let parser =
-            parser_with_temp_dir(&temp_dir, vec!["/B", "/F", "/DU", "-t", "16", "-o", "tree.json"]);
+        let parser = parser_with_temp_dir(
+            &temp_dir,
+            vec!["/B", "/F", "/DU", "-t", "16", "-o", "tree.json"],
+        );
 
         if let Ok(ParseResult::Config(config)) = parser.parse() {
             assert!(config.batch_mode);
@@ -2273,10 +4496,30 @@ mod tests {
         if let Ok(ParseResult::Config(config)) = parser.parse() {
             assert_eq!(config.matching.include_patterns.len(), 2);
             assert_eq!(config.matching.exclude_patterns.len(), 2);
-            assert!(config.matching.include_patterns.contains(&"*.rs".to_string()));
-            assert!(config.matching.include_patterns.contains(&"*.toml".to_string()));
-            assert!(config.matching.exclude_patterns.contains(&"target".to_string()));
-            assert!(config.matching.exclude_patterns.contains(&".git".to_string()));
+            assert!(
+                config
+                    .matching
+                    .include_patterns
+                    .contains(&"*.rs".to_string())
+            );
+            assert!(
+                config
+                    .matching
+                    .include_patterns
+                    .contains(&"*.toml".to_string())
+            );
+            assert!(
+                config
+                    .matching
+                    .exclude_patterns
+                    .contains(&"target".to_string())
+            );
+            assert!(
+                config
+                    .matching
+                    .exclude_patterns
+                    .contains(&".git".to_string())
+            );
         } else {
             panic!("解析失败");
         }
@@ -2328,10 +4571,7 @@ mod tests {
 
     #[test]
     fn parse_size_and_human_readable_combined() {
-        let parser = CliParser::new(vec![
-            "--size".to_string(),
-            "--human-readable".to_string(),
-        ]);
+        let parser = CliParser::new(vec!["--size".to_string(), "--human-readable".to_string()]);
 
         if let Ok(ParseResult::Config(config)) = parser.parse() {
             assert!(config.render.show_size);
@@ -2343,11 +4583,7 @@ mod tests {
 
     #[test]
     fn parse_cmd_style_thread_lowercase() {
-        let parser = CliParser::new(vec![
-            "/b".to_string(),
-            "/t".to_string(),
-            "8".to_string(),
-        ]);
+        let parser = CliParser::new(vec!["/b".to_string(), "/t".to_string(), "8".to_string()]);
 
         if let Ok(ParseResult::Config(config)) = parser.parse() {
             assert!(config.batch_mode);
@@ -2449,4 +4685,2098 @@ mod tests {
             panic!("解析失败");
         }
     }
+
+    // ========================================================================
+    // Paths From Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_paths_from_stdin_dash() {
+        let parser = CliParser::new(vec!["--paths-from".to_string(), "-".to_string()]);
+        if let Ok(ParseResult::Config(config)) = parser.parse() {
+            assert_eq!(config.scan.paths_from, Some(PathListSource::Stdin));
+            assert!(config.batch_mode, "路径列表应隐式启用批处理模式");
+        } else {
+            panic!("解析失败");
+        }
+    }
+
+    #[test]
+    fn parse_paths_from_file_cmd_style() {
+        let parser = CliParser::new(vec!["/pf".to_string(), "files.txt".to_string()]);
+        if let Ok(ParseResult::Config(config)) = parser.parse() {
+            assert_eq!(
+                config.scan.paths_from,
+                Some(PathListSource::File(PathBuf::from("files.txt")))
+            );
+        } else {
+            panic!("解析失败");
+        }
+    }
+
+    #[test]
+    fn parse_paths_from_equals_syntax() {
+        let parser = CliParser::new(vec!["--paths-from=-".to_string()]);
+        if let Ok(ParseResult::Config(config)) = parser.parse() {
+            assert_eq!(config.scan.paths_from, Some(PathListSource::Stdin));
+        } else {
+            panic!("解析失败");
+        }
+    }
+
+    #[test]
+    fn parse_paths_from_missing_value_fails() {
+        let parser = CliParser::new(vec!["--paths-from".to_string()]);
+        let result = parser.parse();
+        assert!(matches!(result, Err(CliError::MissingValue { .. })));
+    }
+
+    #[test]
+    fn parse_paths_from_with_structured_output_succeeds() {
+        let parser = CliParser::new(vec![
+            "--paths-from".to_string(),
+            "-".to_string(),
+            "--output".to_string(),
+            "tree.json".to_string(),
+        ]);
+        let result = parser.parse();
+        assert!(result.is_ok(), "路径列表模式应允许结构化输出: {:?}", result);
+    }
+
+    #[test]
+    fn parse_dash_value_for_other_option_is_invalid_value() {
+        let parser = CliParser::new(vec!["--level".to_string(), "-".to_string()]);
+        let result = parser.parse();
+        assert!(matches!(result, Err(CliError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn help_text_contains_paths_from() {
+        let help = help_text();
+        assert!(help.contains("--paths-from"));
+        assert!(help.contains("/PF"));
+    }
+
+    // ========================================================================
+    // Git Tracked Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_git_tracked_all_styles() {
+        for arg in ["--git-tracked", "/GT", "/gt"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.scan.git_tracked, "{} 应启用 git 跟踪过滤", arg);
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_git_tracked_with_paths_from_fails() {
+        let parser = CliParser::new(vec![
+            "--git-tracked".to_string(),
+            "--paths-from".to_string(),
+            "-".to_string(),
+        ]);
+        let result = parser.parse();
+        assert!(
+            matches!(result, Err(CliError::InvalidConfig(_))),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn help_text_contains_git_tracked() {
+        let help = help_text();
+        assert!(help.contains("--git-tracked"));
+        assert!(help.contains("/GT"));
+    }
+
+    // ========================================================================
+    // Prune Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_prune_all_styles() {
+        for arg in ["--prune", "-P", "/P", "/p"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.matching.prune_empty, "{} 应启用空目录修剪", arg);
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_prune_does_not_require_batch() {
+        let parser = CliParser::new(vec!["/P".to_string(), "/M".to_string(), "*.rs".to_string()]);
+        if let Ok(ParseResult::Config(config)) = parser.parse() {
+            assert!(config.matching.prune_empty);
+            assert!(!config.batch_mode, "修剪不应隐式启用批处理模式");
+        } else {
+            panic!("解析失败");
+        }
+    }
+
+    #[test]
+    fn help_text_contains_prune() {
+        let help = help_text();
+        assert!(help.contains("--prune"));
+        assert!(help.contains("/P "));
+    }
+
+    // ========================================================================
+    // Explain Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_explain_sets_target() {
+        let parser = CliParser::new(vec!["--explain".to_string(), "src\\main.rs".to_string()]);
+        if let Ok(ParseResult::Config(config)) = parser.parse() {
+            assert_eq!(config.explain_target, Some(PathBuf::from("src\\main.rs")));
+        } else {
+            panic!("解析失败");
+        }
+    }
+
+    #[test]
+    fn parse_explain_cmd_style_with_filters() {
+        let parser = CliParser::new(vec![
+            "/x".to_string(),
+            "target".to_string(),
+            "/ex".to_string(),
+            "target".to_string(),
+        ]);
+        if let Ok(ParseResult::Config(config)) = parser.parse() {
+            assert_eq!(config.explain_target, Some(PathBuf::from("target")));
+            assert_eq!(config.matching.exclude_patterns, vec!["target".to_string()]);
+        } else {
+            panic!("解析失败");
+        }
+    }
+
+    #[test]
+    fn parse_explain_missing_value_fails() {
+        let parser = CliParser::new(vec!["--explain".to_string()]);
+        assert!(matches!(parser.parse(), Err(CliError::MissingValue { .. })));
+    }
+
+    #[test]
+    fn help_text_contains_explain() {
+        let help = help_text();
+        assert!(help.contains("--explain"));
+        assert!(help.contains("/EX"));
+    }
+
+    // ========================================================================
+    // Serve Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_serve_with_port() {
+        let config = parse_config(&["/SRV", "/PRT", "9000"]);
+        assert!(config.serve);
+        assert_eq!(config.serve_port, Some(9000));
+        assert!(config.batch_mode, "--serve 应启用批处理模式");
+
+        let config = parse_config(&["--serve"]);
+        assert_eq!(config.serve_port, None);
+    }
+
+    #[test]
+    fn parse_serve_with_pipe() {
+        let config = parse_config(&["/SRV", "/PIP", "treepp"]);
+        assert_eq!(config.serve_pipe.as_deref(), Some("treepp"));
+        assert_eq!(config.serve_port, None);
+
+        let config = parse_config(&["--serve", "--pipe=ide", "--port=0"]);
+        assert_eq!(config.serve_pipe.as_deref(), Some("ide"));
+        assert_eq!(config.serve_port, Some(0));
+    }
+
+    #[test]
+    fn parse_port_out_of_range_fails() {
+        let parser = CliParser::new(vec!["--serve".into(), "--port".into(), "70000".into()]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidValue { .. })));
+    }
+
+    // ========================================================================
+    // Show Filtered Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_show_filtered_all_styles() {
+        for arg in ["--show-filtered", "/SF", "/sf"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.scan.show_filtered, "{} 应启用过滤项显示", arg);
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_show_filtered_with_json_output_fails() {
+        let parser = CliParser::new(vec![
+            "/SF".to_string(),
+            "/B".to_string(),
+            "/O".to_string(),
+            "tree.json".to_string(),
+        ]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn help_text_contains_show_filtered() {
+        let help = help_text();
+        assert!(help.contains("--show-filtered"));
+        assert!(help.contains("/SF"));
+    }
+
+    // ========================================================================
+    // Output Guard Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_max_entries_all_styles() {
+        for arg in ["--max-entries", "/ME", "/me"] {
+            let parser = CliParser::new(vec![arg.to_string(), "100".to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(config.scan.max_entries, Some(100), "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_invalid_max_entries_fails() {
+        for value in ["0", "1.5", "abc"] {
+            let parser = CliParser::new(vec!["/ME".to_string(), value.to_string()]);
+            assert!(
+                matches!(parser.parse(), Err(CliError::InvalidValue { .. })),
+                "{value} 应被拒绝"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_max_output_size_with_units() {
+        let cases = [
+            ("4096", 4096),
+            ("512K", 512 * 1024),
+            ("10m", 10 * 1024 * 1024),
+            ("2MB", 2 * 1024 * 1024),
+            ("1G", 1024 * 1024 * 1024),
+        ];
+        for (value, expected) in cases {
+            let parser = CliParser::new(vec!["--max-output-size".to_string(), value.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(
+                    config.output.max_output_size,
+                    Some(expected),
+                    "测试 {value} 失败"
+                );
+            } else {
+                panic!("解析失败: {}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_invalid_max_output_size_fails() {
+        for value in ["0", "K", "10X", "5.5M", "99999999999G"] {
+            let parser = CliParser::new(vec!["/MS".to_string(), value.to_string()]);
+            assert!(
+                matches!(parser.parse(), Err(CliError::InvalidValue { .. })),
+                "{value} 应被拒绝"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_max_entries_with_json_output_fails() {
+        let parser = CliParser::new(vec![
+            "/ME".to_string(),
+            "10".to_string(),
+            "/B".to_string(),
+            "/O".to_string(),
+            "tree.json".to_string(),
+        ]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn help_text_contains_output_guards() {
+        let help = help_text();
+        assert!(help.contains("--max-entries"));
+        assert!(help.contains("/ME"));
+        assert!(help.contains("--max-output-size"));
+        assert!(help.contains("/MS"));
+    }
+
+    // ========================================================================
+    // File Limit Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_filelimit_all_styles() {
+        for arg in ["--filelimit", "/FL", "/fl"] {
+            let parser = CliParser::new(vec![arg.to_string(), "500".to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(config.scan.file_limit, Some(500), "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_invalid_filelimit_fails() {
+        let parser = CliParser::new(vec!["/FL".to_string(), "many".to_string()]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn help_text_contains_filelimit() {
+        let help = help_text();
+        assert!(help.contains("--filelimit"));
+        assert!(help.contains("/FL"));
+    }
+
+    // ========================================================================
+    // Scan Cache Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_cache_all_styles() {
+        for arg in ["--cache", "/CA", "/ca"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(config.scan.cache, CacheMode::Enabled, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_no_cache_all_styles() {
+        for arg in ["--no-cache", "/NC", "/nc"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(config.scan.cache, CacheMode::Discard, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_cache_with_size_fails() {
+        let parser = CliParser::new(vec!["/CA".to_string(), "/S".to_string()]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn help_text_contains_cache_options() {
+        let help = help_text();
+        assert!(help.contains("--cache"));
+        assert!(help.contains("/CA"));
+        assert!(help.contains("--no-cache"));
+        assert!(help.contains("/NC"));
+    }
+
+    // ========================================================================
+    // Stats JSON Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_stats_json_all_styles() {
+        for arg in ["--stats-json", "/SJ", "/sj"] {
+            let parser = CliParser::new(vec![arg.to_string(), "stats.json".to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(
+                    config.output.stats_json,
+                    Some(StatsTarget::File(PathBuf::from("stats.json"))),
+                    "测试 {arg} 失败"
+                );
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_stats_json_stderr_dash() {
+        let parser = CliParser::new(vec!["--stats-json".to_string(), "-".to_string()]);
+        if let Ok(ParseResult::Config(config)) = parser.parse() {
+            assert_eq!(config.output.stats_json, Some(StatsTarget::Stderr));
+        } else {
+            panic!("解析失败");
+        }
+    }
+
+    #[test]
+    fn parse_stats_json_without_value_fails() {
+        let parser = CliParser::new(vec!["/SJ".to_string()]);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn help_text_contains_stats_json() {
+        let help = help_text();
+        assert!(help.contains("--stats-json"));
+        assert!(help.contains("/SJ"));
+    }
+
+    // ========================================================================
+    // Extension Report Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_report_extensions_all_styles() {
+        for arg in ["--report-extensions", "/RE", "/re"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.render.report_extensions, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn help_text_contains_report_extensions() {
+        let help = help_text();
+        assert!(help.contains("--report-extensions"));
+        assert!(help.contains("/RE"));
+    }
+
+    // ========================================================================
+    // Name Escaping Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_quote_all_styles() {
+        for arg in ["--quote", "-Q", "/Q", "/q"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.render.quote_names, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_escape_all_styles() {
+        for arg in ["--escape", "/ES", "/es"] {
+            let parser = CliParser::new(vec![arg.to_string(), "c".to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(config.render.escape, EscapeMode::C, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_escape_modes() {
+        for (value, mode) in [
+            ("raw", EscapeMode::Raw),
+            ("question", EscapeMode::Question),
+            ("C", EscapeMode::C),
+        ] {
+            let parser = CliParser::new(vec!["/ES".to_string(), value.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(config.render.escape, mode, "测试 {value} 失败");
+            } else {
+                panic!("解析失败: {}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_raw_names() {
+        for flag in ["--raw-names", "/RN", "/rn"] {
+            let config = parse_config(&[flag]);
+            assert!(config.render.raw_names, "测试 {flag} 失败");
+        }
+        assert!(!parse_config(&[]).render.raw_names, "默认应清理控制字符");
+    }
+
+    #[test]
+    fn parse_escape_invalid_mode_fails() {
+        let parser = CliParser::new(vec!["--escape".to_string(), "octal".to_string()]);
+        let result = parser.parse();
+        assert!(matches!(result, Err(CliError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn help_text_contains_escape_options() {
+        let help = help_text();
+        assert!(help.contains("--quote"));
+        assert!(help.contains("--escape"));
+        assert!(help.contains("/ES"));
+        assert!(help.contains("--raw-names"));
+    }
+
+    // ========================================================================
+    // Output Encoding Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_output_encoding_all_styles() {
+        for arg in ["--output-encoding", "/OE", "/oe"] {
+            let parser = CliParser::new(vec![arg.to_string(), "utf16le".to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(
+                    config.output.encoding,
+                    OutputEncoding::Utf16Le,
+                    "测试 {arg} 失败"
+                );
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_output_encoding_invalid_fails() {
+        let parser = CliParser::new(vec!["/OE".to_string(), "latin1".to_string()]);
+        let result = parser.parse();
+        assert!(matches!(result, Err(CliError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn parse_bom_all_styles() {
+        for arg in ["--bom", "/BOM", "/bom"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.output.bom, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_bom_with_gbk_fails() {
+        let parser = CliParser::new(vec![
+            "/OE".to_string(),
+            "gbk".to_string(),
+            "/BOM".to_string(),
+        ]);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn help_text_contains_output_encoding() {
+        let help = help_text();
+        assert!(help.contains("--output-encoding"));
+        assert!(help.contains("/BOM"));
+    }
+
+    // ========================================================================
+    // Append and No-Clobber Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_append_all_styles() {
+        for arg in ["--append", "/AP", "/ap"] {
+            let parser = CliParser::new(vec![
+                arg.to_string(),
+                "/O".to_string(),
+                "tree.txt".to_string(),
+            ]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.output.append, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_no_clobber_all_styles() {
+        for arg in ["--no-clobber", "/NCL", "/ncl"] {
+            let parser = CliParser::new(vec![
+                arg.to_string(),
+                "/O".to_string(),
+                "tree.txt".to_string(),
+            ]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.output.no_clobber, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_report_dir_with_rotate() {
+        let config = parse_config(&["/RPD", "reports", "/ROT", "7", "/O", "tree.txt"]);
+        assert_eq!(config.output.report_dir, Some(PathBuf::from("reports")));
+        assert_eq!(config.output.rotate, Some(7));
+        let path = config.output.output_path.expect("应生成报告路径");
+        assert!(path.starts_with("reports"), "{}", path.display());
+
+        let parser = CliParser::new(vec!["--report-dir=reports".into(), "--rotate=0".into()]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn parse_append_without_output_fails() {
+        let parser = CliParser::new(vec!["/AP".to_string()]);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn help_text_contains_append_and_no_clobber() {
+        let help = help_text();
+        assert!(help.contains("--append"));
+        assert!(help.contains("--no-clobber"));
+        assert!(help.contains("--compress"));
+        assert!(help.contains("--checkpoint"));
+    }
+
+    #[test]
+    fn parse_compress() {
+        for flag in ["--compress", "/GZ", "/gz"] {
+            let config = parse_config(&[flag, "/O", "tree.txt"]);
+            assert!(config.output.compress, "测试 {flag} 失败");
+        }
+        let config = parse_config(&["/B", "/O", "tree.json.gz"]);
+        assert!(config.output.compress, ".gz 路径应启用压缩");
+        assert_eq!(config.output.format, OutputFormat::Json);
+        assert!(!parse_config(&["/O", "tree.txt"]).output.compress);
+    }
+
+    #[test]
+    fn parse_node_ids() {
+        for flag in ["--node-ids", "/ID", "/id"] {
+            let config = parse_config(&[flag, "/B", "/O", "tree.json"]);
+            assert!(config.output.node_ids, "测试 {flag} 失败");
+        }
+        assert!(!parse_config(&["/B", "/O", "tree.json"]).output.node_ids);
+    }
+
+    #[test]
+    fn parse_checkpoint() {
+        for flag in ["--checkpoint", "/CK", "/ck"] {
+            let config = parse_config(&[flag, "/F", "/O", "tree.txt"]);
+            assert!(config.output.checkpoint, "测试 {flag} 失败");
+        }
+        assert!(!parse_config(&["/O", "tree.txt"]).output.checkpoint);
+    }
+
+    // ========================================================================
+    // Metadata Alignment Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_no_align_all_styles() {
+        for arg in ["--no-align", "/NA", "/na"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.render.no_align, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn help_text_contains_no_align() {
+        let help = help_text();
+        assert!(help.contains("--no-align"));
+        assert!(help.contains("/NA"));
+    }
+
+    // ========================================================================
+    // Number Format Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_si_all_styles() {
+        for arg in ["--si", "/SU", "/su"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.render.si_units, "测试 {arg} 失败");
+                assert!(config.render.human_readable, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_number_format_all_styles() {
+        for arg in ["--number-format", "/NF", "/nf"] {
+            let parser = CliParser::new(vec![arg.to_string(), "comma".to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(
+                    config.render.number_format,
+                    NumberFormat::Comma,
+                    "测试 {arg} 失败"
+                );
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_number_formats() {
+        for (value, format) in [
+            ("plain", NumberFormat::Plain),
+            ("Locale", NumberFormat::Locale),
+            ("period", NumberFormat::Period),
+            ("space", NumberFormat::Space),
+            ("apostrophe", NumberFormat::Apostrophe),
+        ] {
+            let parser = CliParser::new(vec!["/NF".to_string(), value.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(config.render.number_format, format, "测试 {value} 失败");
+            } else {
+                panic!("解析失败: {}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_number_format_invalid_value_fails() {
+        let parser = CliParser::new(vec!["--number-format".to_string(), "hex".to_string()]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn help_text_contains_number_format() {
+        let help = help_text();
+        assert!(help.contains("--si"));
+        assert!(help.contains("--number-format"));
+        assert!(help.contains("/NF"));
+    }
+
+    // ========================================================================
+    // Date Format Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_relative_dates_all_styles() {
+        for arg in ["--relative-dates", "/RD", "/rd"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.render.relative_dates, "测试 {arg} 失败");
+                assert!(config.render.show_date, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_date_format_all_styles() {
+        for arg in ["--date-format", "/DF", "/df"] {
+            let parser = CliParser::new(vec![arg.to_string(), "%d.%m.%Y".to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(
+                    config.render.date_format.as_deref(),
+                    Some("%d.%m.%Y"),
+                    "测试 {arg} 失败"
+                );
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_invalid_date_format_fails() {
+        for value in ["%Q", "%", ""] {
+            let parser = CliParser::new(vec!["/DF".to_string(), value.to_string()]);
+            assert!(
+                matches!(parser.parse(), Err(CliError::InvalidValue { .. })),
+                "{value} 应被拒绝"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_relative_dates_with_date_format_fails() {
+        let parser = CliParser::new(vec!["/RD".to_string(), "/DF".to_string(), "%Y".to_string()]);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn help_text_contains_date_options() {
+        let help = help_text();
+        assert!(help.contains("--relative-dates"));
+        assert!(help.contains("--date-format"));
+        assert!(help.contains("/DF"));
+    }
+
+    // ========================================================================
+    // Timestamp and Sort Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_created_and_accessed_all_styles() {
+        for (created, accessed) in [("--created", "--accessed"), ("/CT", "/AT"), ("/ct", "/at")] {
+            let parser = CliParser::new(vec![created.to_string(), accessed.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.render.show_created, "测试 {created} 失败");
+                assert!(config.render.show_accessed, "测试 {accessed} 失败");
+                assert!(!config.render.show_date, "测试 {created} 失败");
+            } else {
+                panic!("解析失败: {}", created);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_sort_keys() {
+        for (value, key) in [
+            ("name", SortKey::Name),
+            ("size", SortKey::Size),
+            ("mtime", SortKey::Modified),
+            ("CTIME", SortKey::Created),
+            ("atime", SortKey::Accessed),
+        ] {
+            for arg in ["--sort", "/SO", "/so"] {
+                let parser = CliParser::new(vec![arg.to_string(), value.to_string()]);
+                if let Ok(ParseResult::Config(config)) = parser.parse() {
+                    assert_eq!(config.render.sort_key, key, "测试 {arg} {value} 失败");
+                } else {
+                    panic!("解析失败: {} {}", arg, value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_sort_by_share_with_disk_usage() {
+        let config = parse_config(&["/B", "/DU", "/SO", "share"]);
+        assert_eq!(config.render.sort_key, SortKey::Share);
+
+        let parser = CliParser::new(vec!["/SO".to_string(), "share".to_string()]);
+        assert!(parser.parse().is_err(), "share 排序需要 /DU");
+    }
+
+    #[test]
+    fn parse_sort_invalid_key_fails() {
+        let parser = CliParser::new(vec!["--sort".to_string(), "owner".to_string()]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn parse_smart() {
+        for flag in ["--smart", "/SM", "/sm"] {
+            let config = parse_config(&[flag]);
+            assert!(config.matching.smart_excludes, "测试 {flag} 失败");
+        }
+        assert!(!parse_config(&[]).matching.smart_excludes);
+    }
+
+    #[test]
+    fn parse_no_normalize() {
+        for flag in ["--no-normalize", "/NN", "/nn"] {
+            let config = parse_config(&[flag]);
+            assert!(config.matching.no_normalize, "测试 {flag} 失败");
+        }
+        assert!(!parse_config(&[]).matching.no_normalize, "默认应进行规范化");
+    }
+
+    #[test]
+    fn parse_print_config() {
+        for flag in ["--print-config", "/PC", "/pc"] {
+            assert!(parse_config(&[flag]).print_config, "测试 {flag} 失败");
+        }
+        assert!(!parse_config(&[]).print_config);
+    }
+
+    #[test]
+    fn parse_print_schema() {
+        for flag in ["--print-schema", "/PS", "/ps"] {
+            assert!(parse_config(&[flag]).print_schema, "测试 {flag} 失败");
+        }
+        assert!(!parse_config(&[]).print_schema);
+    }
+
+    #[test]
+    fn parse_context_menu_scopes() {
+        assert_eq!(
+            parse_config(&["/ICM", "user"]).install_menu,
+            Some(MenuScope::User)
+        );
+        assert_eq!(
+            parse_config(&["--uninstall-context-menu", "Machine"]).uninstall_menu,
+            Some(MenuScope::Machine)
+        );
+        let parser = CliParser::new(vec!["/icm".to_string(), "usr".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::InvalidValue { suggestion: Some(ref s), .. }) if s == "user"
+        ));
+    }
+
+    #[test]
+    fn parse_verbose_counts_repetitions() {
+        assert_eq!(parse_config(&[]).output.verbosity, 0);
+        assert_eq!(parse_config(&["/VB"]).output.verbosity, 1);
+        assert_eq!(
+            parse_config(&["--verbose", "/vb"]).output.verbosity,
+            2,
+            "重复应提高级别"
+        );
+        assert_eq!(
+            parse_config(&["/VB", "/VB", "--no-verbose"])
+                .output
+                .verbosity,
+            0
+        );
+    }
+
+    #[test]
+    fn parse_log_file_implies_verbose() {
+        for arg in ["--log-file", "/LOG", "/log"] {
+            let config = parse_config(&[arg, "scan.log"]);
+            assert_eq!(
+                config.output.log_file,
+                Some(PathBuf::from("scan.log")),
+                "测试 {arg} 失败"
+            );
+            assert_eq!(config.output.verbosity, 1, "日志文件应启用 info 级别");
+        }
+    }
+
+    #[test]
+    fn parse_group_by_type() {
+        let config = parse_config(&["/F", "/GB", "TYPE"]);
+        assert_eq!(config.render.group_by, GroupBy::Type);
+        assert!(config.batch_mode, "分组应启用批处理模式");
+
+        let config = parse_config(&["--group-by", "none"]);
+        assert_eq!(config.render.group_by, GroupBy::None);
+    }
+
+    #[test]
+    fn parse_interleave_all_styles() {
+        for flag in ["--interleave", "--no-dirs-first", "/IL", "/il"] {
+            let config = parse_config(&[flag]);
+            assert!(config.render.interleave, "测试 {flag} 失败");
+            assert!(config.batch_mode, "交错排序应启用批处理模式");
+        }
+        let config = parse_config(&["/IL", "--no-interleave"]);
+        assert!(!config.render.interleave);
+    }
+
+    #[test]
+    fn parse_backend_all_styles() {
+        let styles: [&[&str]; 3] = [
+            &["/B", "--backend", "walk"],
+            &["/B", "/BK", "Walk"],
+            &["-b", "--backend=walk"],
+        ];
+        for args in styles {
+            let config = parse_config(args);
+            assert_eq!(config.scan.backend, ScanBackend::Walk, "测试 {args:?} 失败");
+        }
+
+        let args = ["/B", "--backend", "paralel"].map(String::from).to_vec();
+        let err = CliParser::new(args).parse().unwrap_err();
+        assert!(
+            matches!(err, CliError::InvalidValue { ref suggestion, .. }
+                if suggestion.as_deref() == Some("parallel")),
+            "应提示最接近的后端：{err:?}"
+        );
+    }
+
+    #[test]
+    fn parse_columns_all_styles() {
+        for flag in ["--columns", "-C", "/COL", "/col"] {
+            let config = parse_config(&["/F", flag]);
+            assert!(config.render.columns, "测试 {flag} 失败");
+            assert!(config.batch_mode, "分栏应启用批处理模式");
+        }
+        let parser = CliParser::new(vec!["/COL".to_string()]);
+        assert!(parser.parse().is_err(), "/COL 需要 /F");
+    }
+
+    #[test]
+    fn parse_level_guides_all_styles() {
+        for arg in ["--level-guides", "/LGD", "/lgd"] {
+            let config = parse_config(&[arg, "number"]);
+            assert_eq!(
+                config.render.level_guides,
+                LevelGuides::Number,
+                "测试 {arg} 失败"
+            );
+        }
+        let config = parse_config(&["/LGD", "COLOR"]);
+        assert_eq!(config.render.level_guides, LevelGuides::Color);
+
+        let parser = CliParser::new(vec!["/LGD".to_string(), "colour".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::InvalidValue { suggestion: Some(ref s), .. }) if s == "color"
+        ));
+    }
+
+    #[test]
+    fn parse_group_by_invalid_value_suggests_closest() {
+        let parser = CliParser::new(vec!["--group-by".to_string(), "typ".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::InvalidValue { suggestion: Some(ref s), .. }) if s == "type"
+        ));
+    }
+
+    #[test]
+    fn help_text_contains_timestamp_and_sort_options() {
+        let help = help_text();
+        assert!(help.contains("--created"));
+        assert!(help.contains("--accessed"));
+        assert!(help.contains("--sort"));
+    }
+
+    // ========================================================================
+    // Language Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_lang_all_styles() {
+        for (value, language) in [("en", Language::English), ("ZH", Language::Chinese)] {
+            for arg in ["--lang", "/LG", "/lg"] {
+                let parser = CliParser::new(vec![arg.to_string(), value.to_string()]);
+                if let Ok(ParseResult::Config(config)) = parser.parse() {
+                    assert_eq!(config.render.language, language, "测试 {arg} {value} 失败");
+                } else {
+                    panic!("解析失败: {} {}", arg, value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_lang_invalid_value_fails() {
+        let parser = CliParser::new(vec!["--lang".to_string(), "fr".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::InvalidValue { ref option, .. }) if option == "lang"
+        ));
+    }
+
+    #[test]
+    fn language_is_found_before_parsing() {
+        let args = vec!["/ZZ".to_string(), "/lg".to_string(), "zh".to_string()];
+        assert_eq!(CliParser::new(args).language(), Language::Chinese);
+
+        let args = vec!["/lg".to_string(), "fr".to_string()];
+        assert_eq!(CliParser::new(args).language(), i18n::system_language());
+    }
+
+    #[test]
+    fn json_errors_is_found_before_parsing() {
+        for flag in ["--json-errors", "/JE", "/je"] {
+            let args = vec!["/ZZ".to_string(), flag.to_string()];
+            assert!(CliParser::new(args).json_errors(), "测试 {flag} 失败");
+            assert!(parse_config(&[flag]).json_errors, "测试 {flag} 失败");
+        }
+        assert!(!CliParser::new(vec!["/F".to_string()]).json_errors());
+    }
+
+    fn parse_args(args: &[&str]) -> Result<ParseResult, CliError> {
+        CliParser::new(args.iter().map(|s| s.to_string()).collect()).parse()
+    }
+
+    #[test]
+    fn parse_settings_commands() {
+        let result = parse_args(&["config", "add-exclude", "node_modules", "*.log"]);
+        assert!(matches!(
+            result,
+            Ok(ParseResult::Settings(SettingsCommand::AddExclude(ref p)))
+                if p == &["node_modules", "*.log"]
+        ));
+        let result = parse_args(&["config", "remove", "target"]);
+        assert!(matches!(
+            result,
+            Ok(ParseResult::Settings(SettingsCommand::RemoveExclude(ref p))) if p == &["target"]
+        ));
+        let result = parse_args(&["config", "list"]);
+        assert!(matches!(
+            result,
+            Ok(ParseResult::Settings(SettingsCommand::ListExcludes))
+        ));
+    }
+
+    #[test]
+    fn parse_settings_command_errors() {
+        assert!(matches!(
+            parse_args(&["config", "add-exclude"]),
+            Err(CliError::MissingValue { .. })
+        ));
+        assert!(matches!(
+            parse_args(&["config", "list", "extra"]),
+            Err(CliError::UnknownOption { ref option }) if option == "extra"
+        ));
+        match parse_args(&["config", "lst"]) {
+            Err(CliError::InvalidValue { suggestion, .. }) => {
+                assert_eq!(suggestion.as_deref(), Some("list"));
+            }
+            other => panic!("应拒绝未知操作：{:?}", other),
+        }
+    }
+
+    #[test]
+    fn config_followed_by_option_is_a_path() {
+        let result = parse_args(&["config", "/F"]);
+        assert!(
+            matches!(result, Err(CliError::InvalidConfig(ConfigError::InvalidPath { ref path, .. }))
+                if path.ends_with("config")),
+            "应把 config 当作路径：{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn settings_excludes_come_before_command_line_excludes() {
+        let mut settings = Settings::default();
+        settings.add_exclude("node_modules");
+        let args = vec!["/X".to_string(), "target".to_string()];
+        let Ok(ParseResult::Config(config)) = CliParser::new(args).with_settings(settings).parse()
+        else {
+            panic!("解析失败");
+        };
+        assert_eq!(config.matching.exclude_patterns, ["node_modules", "target"]);
+    }
+
+    #[test]
+    fn parse_bookmark_commands() {
+        let result = parse_args(&["bookmark", "add", "@work", r"D:\repos\big-project"]);
+        assert!(matches!(
+            result,
+            Ok(ParseResult::Settings(SettingsCommand::AddBookmark { ref name, ref path }))
+                if name == "work" && path == Path::new(r"D:\repos\big-project")
+        ));
+        let result = parse_args(&["bookmark", "remove", "work"]);
+        assert!(matches!(
+            result,
+            Ok(ParseResult::Settings(SettingsCommand::RemoveBookmark(ref name))) if name == "work"
+        ));
+        assert!(matches!(
+            parse_args(&["bookmark", "add", "work"]),
+            Err(CliError::MissingValue { ref option }) if option == "bookmark add"
+        ));
+        assert!(matches!(
+            parse_args(&["bookmark", "remove", "a", "b"]),
+            Err(CliError::UnknownOption { ref option }) if option == "b"
+        ));
+    }
+
+    #[test]
+    fn parse_resolves_bookmarks() {
+        let temp_dir = create_temp_dir();
+        let mut settings = Settings::default();
+        settings.add_bookmark("work", temp_dir.path()).unwrap();
+        settings
+            .preset
+            .insert("files".to_string(), "/F @work".to_string());
+        let args = vec!["@files".to_string()];
+        let Ok(ParseResult::Config(config)) = CliParser::new(args).with_settings(settings).parse()
+        else {
+            panic!("解析失败");
+        };
+        assert!(config.scan.show_files);
+        assert_eq!(
+            config.root_path,
+            dunce::canonicalize(temp_dir.path()).unwrap(),
+            "书签应解析为根路径"
+        );
+    }
+
+    fn parse_with_presets(presets: &[(&str, &str)], args: &[&str]) -> Result<Config, CliError> {
+        let mut settings = Settings::default();
+        for (name, value) in presets {
+            settings.preset.insert(name.to_string(), value.to_string());
+        }
+        let args = args.iter().map(|s| s.to_string()).collect();
+        match CliParser::new(args).with_settings(settings).parse()? {
+            ParseResult::Config(config) => Ok(*config),
+            other => panic!("解析结果不是配置：{:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_expands_presets_in_place() {
+        let presets = [("docs", "-f -L 3 --smart"), ("all", "@docs /AL")];
+        let config = parse_with_presets(&presets, &["@all", "/X", "target"]).unwrap();
+        assert!(config.scan.show_files);
+        assert_eq!(config.scan.max_depth, Some(3));
+        assert!(config.matching.smart_excludes);
+        assert!(config.scan.show_hidden);
+        assert_eq!(config.matching.exclude_patterns, ["target"]);
+
+        let config = parse_with_presets(&presets, &["@docs", "@docs"]);
+        assert!(
+            matches!(config, Err(CliError::DuplicateOption { .. })),
+            "预设重复展开时值选项应冲突"
+        );
+    }
+
+    #[test]
+    fn parse_rejects_recursive_presets() {
+        let presets = [("a", "/F @b"), ("b", "@a")];
+        assert_eq!(
+            parse_with_presets(&presets, &["@b"]).unwrap_err(),
+            CliError::RecursivePreset {
+                name: "b".to_string()
+            }
+        );
+        let err = parse_with_presets(&[("self", "@self")], &["@self"]).unwrap_err();
+        assert!(matches!(err, CliError::RecursivePreset { .. }));
+    }
+
+    #[test]
+    fn parse_suggests_closest_preset() {
+        match parse_with_presets(&[("docs", "/F")], &["@doc"]) {
+            Err(CliError::InvalidValue {
+                option, suggestion, ..
+            }) => {
+                assert_eq!(option, "@<NAME>");
+                assert_eq!(suggestion.as_deref(), Some("docs"));
+            }
+            other => panic!("应拒绝未知预设：{:?}", other),
+        }
+    }
+
+    #[test]
+    fn split_arguments_honors_quotes() {
+        assert_eq!(split_arguments("  /F  -L 3 "), ["/F", "-L", "3"]);
+        assert_eq!(
+            split_arguments(r#"/X "My Files" /M "#),
+            ["/X", "My Files", "/M"]
+        );
+        assert_eq!(split_arguments(r#"/X """#), ["/X", ""]);
+        assert!(split_arguments("").is_empty());
+    }
+
+    #[test]
+    fn parse_quiet_warnings() {
+        for flag in ["--quiet-warnings", "/QW", "/qw"] {
+            assert!(
+                parse_config(&[flag]).output.quiet_warnings,
+                "测试 {flag} 失败"
+            );
+        }
+        assert!(!parse_config(&[]).output.quiet_warnings);
+    }
+
+    #[test]
+    fn chinese_help_lists_every_option() {
+        let zh = localized_help_text(Language::Chinese);
+        for line in help_text().lines().filter(|l| l.starts_with("  -")) {
+            let options = line.split_whitespace().next().unwrap();
+            assert!(zh.contains(options), "中文帮助缺少 {options}");
+        }
+        assert_eq!(localized_help_text(Language::English), help_text());
+    }
+
+    #[test]
+    fn help_text_contains_lang() {
+        assert!(help_text().contains("--lang"));
+        assert!(help_text().contains("/LG"));
+    }
+
+    // ========================================================================
+    // Short Option Cluster Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_short_cluster_sets_every_flag() {
+        let parser = CliParser::new(vec!["-fa".to_string()]);
+        if let Ok(ParseResult::Config(config)) = parser.parse() {
+            assert!(config.scan.show_files);
+            assert_eq!(config.render.charset, CharsetMode::Ascii);
+        } else {
+            panic!("解析失败: -fa");
+        }
+    }
+
+    #[test]
+    fn parse_short_cluster_matches_separate_flags() {
+        let parse =
+            |args: &[&str]| match CliParser::new(args.iter().map(|a| a.to_string()).collect())
+                .parse()
+            {
+                Ok(ParseResult::Config(config)) => config,
+                other => panic!("解析失败: {:?}: {:?}", args, other.err()),
+            };
+        assert_eq!(parse(&["-fsHr"]), parse(&["-f", "-s", "-H", "-r"]));
+        assert_eq!(parse(&["-bgk"]), parse(&["-b", "-g", "-k"]));
+    }
+
+    #[test]
+    fn parse_short_cluster_with_trailing_value_option() {
+        let parser = CliParser::new(vec!["-fL".to_string(), "2".to_string()]);
+        if let Ok(ParseResult::Config(config)) = parser.parse() {
+            assert!(config.scan.show_files);
+            assert_eq!(config.scan.max_depth, Some(2));
+        } else {
+            panic!("解析失败: -fL 2");
+        }
+    }
+
+    #[test]
+    fn parse_short_cluster_rejects_value_option_before_end() {
+        let parser = CliParser::new(vec!["-Lf".to_string(), "2".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::ValueOptionInCluster { ref option, ref cluster }) if option == "-L" && cluster == "-Lf"
+        ));
+    }
+
+    #[test]
+    fn parse_short_cluster_rejects_unknown_letter() {
+        let parser = CliParser::new(vec!["-fz".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::UnknownOption { ref option }) if option == "-z"
+        ));
+    }
+
+    #[test]
+    fn parse_short_cluster_accepts_repeated_flag_letter() {
+        let parser = CliParser::new(vec!["-ff".to_string()]);
+        assert!(parser.parse().is_ok(), "重复的布尔选项应以最后一次为准");
+    }
+
+    #[test]
+    fn parse_short_cluster_requires_value_for_last_option() {
+        let parser = CliParser::new(vec!["-fL".to_string()]);
+        assert!(matches!(parser.parse(), Err(CliError::MissingValue { .. })));
+    }
+
+    #[test]
+    fn parse_short_cluster_stops_at_help() {
+        let parser = CliParser::new(vec!["-fh".to_string()]);
+        assert!(matches!(parser.parse(), Ok(ParseResult::Help)));
+    }
+
+    #[test]
+    fn long_and_cmd_options_are_not_split() {
+        for arg in ["--fa", "/fa", "-f1"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            assert!(
+                matches!(parser.parse(), Err(CliError::UnknownOption { ref option }) if option == arg),
+                "{arg} 应作为整体被拒绝"
+            );
+        }
+    }
+
+    // ========================================================================
+    // Negatable Flag Tests
+    // ========================================================================
+
+    fn parse_config(args: &[&str]) -> Config {
+        let args = args.iter().map(|s| s.to_string()).collect();
+        match CliParser::new(args).parse() {
+            Ok(ParseResult::Config(config)) => *config,
+            other => panic!("解析失败: {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn parse_no_prefix_switches_flag_off() {
+        let config = parse_config(&["/F", "--no-files"]);
+        assert!(!config.scan.show_files);
+    }
+
+    #[test]
+    fn parse_last_flag_occurrence_wins() {
+        let config = parse_config(&["--no-files", "/F"]);
+        assert!(config.scan.show_files);
+
+        let config = parse_config(&["-a", "--no-ascii", "--ascii=false", "/a"]);
+        assert_eq!(config.render.charset, CharsetMode::Ascii);
+    }
+
+    #[test]
+    fn parse_flag_equals_boolean_value() {
+        for (value, expected) in [
+            ("true", true),
+            ("FALSE", false),
+            ("yes", true),
+            ("no", false),
+            ("on", true),
+            ("off", false),
+            ("1", true),
+            ("0", false),
+        ] {
+            let arg = format!("--files={}", value);
+            let config = parse_config(&[&arg]);
+            assert_eq!(config.scan.show_files, expected, "测试 {} 失败", arg);
+        }
+    }
+
+    #[test]
+    fn parse_flag_equals_invalid_value_fails() {
+        let parser = CliParser::new(vec!["--files=maybe".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::InvalidValue { ref option, ref value, .. })
+                if option == "files" && value == "maybe"
+        ));
+    }
+
+    #[test]
+    fn parse_negated_flag_restores_default_mode() {
+        let config = parse_config(&["/FP", "--no-full-path"]);
+        assert_eq!(config.render.path_mode, PathMode::Relative);
+
+        let config = parse_config(&["/CA", "--cache=false"]);
+        assert_eq!(config.scan.cache, CacheMode::Disabled);
+    }
+
+    #[test]
+    fn parse_existing_no_options_keep_their_meaning() {
+        let config = parse_config(&["--no-cache"]);
+        assert_eq!(config.scan.cache, CacheMode::Discard);
+
+        let config = parse_config(&["--no-indent", "--no-indent=false"]);
+        assert!(!config.render.no_indent);
+    }
+
+    #[test]
+    fn parse_negated_batch_still_rejects_thread() {
+        let parser = CliParser::new(vec![
+            "/B".to_string(),
+            "--no-batch".to_string(),
+            "/T".to_string(),
+            "4".to_string(),
+        ]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::ConflictingOptions { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_value_and_action_options_are_not_negatable() {
+        for arg in ["--no-level", "--no-help", "--no-version", "--help=false"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            assert!(
+                matches!(parser.parse(), Err(CliError::UnknownOption { .. })),
+                "测试 {} 失败",
+                arg
+            );
+        }
+    }
+
+    #[test]
+    fn help_text_mentions_negation() {
+        assert!(help_text().contains("--no-<option>"));
+        assert!(localized_help_text(Language::Chinese).contains("--no-<option>"));
+    }
+
+    // ========================================================================
+    // Value Suggestion Tests
+    // ========================================================================
+
+    #[test]
+    fn edit_distance_counts_single_edits() {
+        assert_eq!(edit_distance("size", "size"), 0);
+        assert_eq!(edit_distance("sise", "size"), 1);
+        assert_eq!(edit_distance("siz", "size"), 1);
+        assert_eq!(edit_distance("sizes", "size"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn closest_value_suggests_near_match() {
+        assert_eq!(
+            closest_value("sise", SortKey::NAMES),
+            Some("size".to_string())
+        );
+        assert_eq!(
+            closest_value("MTIM", SortKey::NAMES),
+            Some("mtime".to_string())
+        );
+        assert_eq!(
+            closest_value("utf16", OutputEncoding::NAMES),
+            Some("utf16le".to_string())
+        );
+    }
+
+    #[test]
+    fn closest_value_ignores_unrelated_input() {
+        assert_eq!(closest_value("banana", SortKey::NAMES), None);
+        assert_eq!(closest_value("x", EscapeMode::NAMES), None);
+    }
+
+    #[test]
+    fn parse_invalid_sort_key_carries_suggestion() {
+        let parser = CliParser::new(vec!["--sort".to_string(), "sise".to_string()]);
+        match parser.parse() {
+            Err(CliError::InvalidValue {
+                option,
+                value,
+                reason,
+                suggestion,
+            }) => {
+                assert_eq!(option, "sort");
+                assert_eq!(value, "sise");
+                assert!(reason.contains("name, size, mtime, ctime, atime or share"));
+                assert_eq!(suggestion.as_deref(), Some("size"));
+            }
+            other => panic!("应返回 InvalidValue: {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn parse_invalid_boolean_value_carries_suggestion() {
+        let parser = CliParser::new(vec!["--files=fals".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::InvalidValue { suggestion: Some(ref s), .. }) if s == "false"
+        ));
+    }
+
+    #[test]
+    fn parse_invalid_integer_has_no_suggestion() {
+        let parser = CliParser::new(vec!["/L".to_string(), "abc".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::InvalidValue {
+                suggestion: None,
+                ..
+            })
+        ));
+    }
+
+    // ========================================================================
+    // Thread Count Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_thread_auto_uses_auto_thread_count() {
+        for value in ["auto", "AUTO"] {
+            let config = parse_config(&["/B", "/T", value]);
+            assert_eq!(
+                config.scan.thread_count,
+                auto_thread_count(),
+                "测试 {} 失败",
+                value
+            );
+            assert!(config.warnings.is_empty());
+        }
+    }
+
+    #[test]
+    fn parse_thread_auto_requires_batch() {
+        let parser = CliParser::new(vec!["--thread".to_string(), "auto".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::ConflictingOptions { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_thread_count_is_clamped_with_warning() {
+        let config = parse_config(&["/B", "/T", "10000"]);
+        assert_eq!(config.scan.thread_count.get(), MAX_THREAD_COUNT);
+        assert_eq!(
+            config.warnings,
+            vec![ConfigWarning::ThreadCountClamped {
+                requested: 10000,
+                used: MAX_THREAD_COUNT,
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_thread_count_at_limit_is_kept() {
+        let config = parse_config(&["/B", "/T", "256"]);
+        assert_eq!(config.scan.thread_count.get(), 256);
+        assert!(config.warnings.is_empty());
+    }
+
+    #[test]
+    fn parse_thread_typo_suggests_auto() {
+        let parser = CliParser::new(vec!["/B".to_string(), "/T".to_string(), "aut".to_string()]);
+        assert!(matches!(
+            parser.parse(),
+            Err(CliError::InvalidValue { suggestion: Some(ref s), .. }) if s == "auto"
+        ));
+    }
+
+    // ========================================================================
+    // Error Report Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_report_errors_all_styles() {
+        for arg in ["--report-errors", "/ER", "/er"] {
+            let parser = CliParser::new(vec![arg.to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert!(config.render.report_errors, "测试 {arg} 失败");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn help_text_contains_report_errors() {
+        for language in [Language::English, Language::Chinese] {
+            let help = localized_help_text(language);
+            assert!(help.contains("--report-errors"));
+            assert!(help.contains("/ER"));
+        }
+    }
+
+    // ========================================================================
+    // Timeout Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_read_timeout_and_timeout() {
+        let config = parse_config(&["/RT", "500ms", "--timeout", "2m"]);
+        assert_eq!(config.scan.read_timeout, Some(Duration::from_millis(500)));
+        assert_eq!(config.scan.timeout, Some(Duration::from_secs(120)));
+
+        let config = parse_config(&["--read-timeout=30"]);
+        assert_eq!(
+            config.scan.read_timeout,
+            Some(Duration::from_secs(30)),
+            "无单位时按秒计"
+        );
+        assert_eq!(config.scan.timeout, None);
+    }
+
+    #[test]
+    fn parse_duration_accepts_units() {
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("10S"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_duration("3m"), Some(Duration::from_secs(180)));
+        assert_eq!(parse_duration("7"), Some(Duration::from_secs(7)));
+        assert_eq!(parse_duration("1.5s"), None);
+        assert_eq!(parse_duration("-1"), None);
+        assert_eq!(parse_duration("s"), None);
+    }
+
+    #[test]
+    fn parse_zero_timeout_fails() {
+        for args in [["/TO", "0"], ["/RT", "0ms"], ["/RT", "soon"]] {
+            let parser = CliParser::new(args.iter().map(|s| s.to_string()).collect());
+            assert!(
+                matches!(parser.parse(), Err(CliError::InvalidValue { .. })),
+                "{:?} 应被拒绝",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn help_text_contains_timeouts() {
+        for language in [Language::English, Language::Chinese] {
+            let help = localized_help_text(language);
+            assert!(help.contains("--read-timeout, /RT <TIME>"));
+            assert!(help.contains("--timeout, /TO <TIME>"));
+        }
+    }
+
+    // ========================================================================
+    // Scan Budget Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_budget_time_and_entries() {
+        let config = parse_config(&["/BT", "30s", "--budget-entries", "1M"]);
+        assert_eq!(config.scan.budget_time, Some(Duration::from_secs(30)));
+        assert_eq!(config.scan.budget_entries, Some(1_000_000));
+        assert_eq!(config.scan.timeout, None, "预算不应设置 --timeout");
+
+        let config = parse_config(&["--budget-entries=500k"]);
+        assert_eq!(config.scan.budget_entries, Some(500_000));
+        assert_eq!(config.scan.budget_time, None);
+    }
+
+    #[test]
+    fn parse_entry_count_accepts_decimal_units() {
+        assert_eq!(parse_entry_count("50000"), Some(50_000));
+        assert_eq!(parse_entry_count("2K"), Some(2_000));
+        assert_eq!(parse_entry_count("3m"), Some(3_000_000));
+        assert_eq!(parse_entry_count("1MB"), None);
+        assert_eq!(parse_entry_count("1.5M"), None);
+        assert_eq!(parse_entry_count("M"), None);
+    }
+
+    #[test]
+    fn parse_zero_budget_fails() {
+        for args in [["/BE", "0"], ["/BT", "0s"], ["/BE", "lots"]] {
+            let parser = CliParser::new(args.iter().map(|s| s.to_string()).collect());
+            assert!(
+                matches!(parser.parse(), Err(CliError::InvalidValue { .. })),
+                "{:?} 应被拒绝",
+                args
+            );
+        }
+    }
+
+    // ========================================================================
+    // Plain Preset Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_plain_applies_preset() {
+        for arg in ["--plain", "/PL", "/pl"] {
+            let config = parse_config(&["/RP", arg]);
+            assert!(config.render.no_win_banner, "测试 {arg} 失败");
+            assert_eq!(config.render.charset, CharsetMode::Ascii);
+            assert!(!config.render.show_report, "--plain 应关闭统计报告");
+            assert!(config.output.trim_trailing_whitespace);
+        }
+    }
+
+    #[test]
+    fn parse_options_after_plain_override_preset() {
+        let config = parse_config(&["--plain", "--no-ascii", "/RP"]);
+        assert_eq!(config.render.charset, CharsetMode::Unicode);
+        assert!(config.render.show_report);
+        assert!(config.render.no_win_banner);
+    }
+
+    #[test]
+    fn parse_plain_is_not_negatable() {
+        for args in [
+            vec!["--no-plain"],
+            vec!["--plain=false"],
+            vec!["/PL", "/PL"],
+        ] {
+            let parser = CliParser::new(args.iter().map(|s| s.to_string()).collect());
+            assert!(parser.parse().is_err(), "{:?} 应被拒绝", args);
+        }
+    }
+
+    // ========================================================================
+    // Line Ending Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_line_ending_last_one_wins() {
+        assert_eq!(parse_config(&[]).output.line_ending, LineEnding::Lf);
+        assert_eq!(
+            parse_config(&["--crlf"]).output.line_ending,
+            LineEnding::Crlf
+        );
+        assert_eq!(
+            parse_config(&["/crlf", "/LF"]).output.line_ending,
+            LineEnding::Lf
+        );
+        assert_eq!(
+            parse_config(&["--lf", "--crlf"]).output.line_ending,
+            LineEnding::Crlf
+        );
+        assert_eq!(
+            parse_config(&["--no-lf"]).output.line_ending,
+            LineEnding::Crlf
+        );
+        assert_eq!(
+            parse_config(&["--crlf=false"]).output.line_ending,
+            LineEnding::Lf
+        );
+    }
+
+    #[test]
+    fn parse_plain_resets_line_ending_unless_overridden() {
+        assert_eq!(
+            parse_config(&["--crlf", "--plain"]).output.line_ending,
+            LineEnding::Lf
+        );
+        assert_eq!(
+            parse_config(&["--plain", "--crlf"]).output.line_ending,
+            LineEnding::Crlf
+        );
+    }
+
+    // ========================================================================
+    // Leaf Depth Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_leaf_depth_all_styles() {
+        for arg in ["--leaf-depth", "/LD", "/ld"] {
+            let parser = CliParser::new(vec![arg.to_string(), "2".to_string()]);
+            if let Ok(ParseResult::Config(config)) = parser.parse() {
+                assert_eq!(config.scan.leaf_depth, Some(2), "测试 {arg} 失败");
+                assert!(config.batch_mode, "测试 {arg} 应启用 batch 模式");
+            } else {
+                panic!("解析失败: {}", arg);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_invalid_leaf_depth_fails() {
+        for value in ["0", "1.5", "abc"] {
+            let parser = CliParser::new(vec!["--leaf-depth".to_string(), value.to_string()]);
+            assert!(
+                matches!(parser.parse(), Err(CliError::InvalidValue { .. })),
+                "{value} 应被拒绝"
+            );
+        }
+    }
+
+    #[test]
+    fn help_text_contains_leaf_depth() {
+        assert!(help_text().contains("--leaf-depth"));
+        assert!(help_text_zh().contains("--leaf-depth"));
+    }
+
+    // ========================================================================
+    // Flat Listing Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_flat_all_styles() {
+        for arg in ["--flat", "/FLAT", "/flat"] {
+            let config = parse_config(&[arg]);
+            assert!(config.render.flat, "测试 {arg} 失败");
+            assert_eq!(
+                config.render.path_mode,
+                PathMode::Full,
+                "--flat 应显示完整路径"
+            );
+            assert!(config.render.no_indent);
+            assert!(config.render.no_win_banner);
+        }
+    }
+
+    #[test]
+    fn parse_flat_with_structured_output_fails() {
+        let parser = CliParser::new(vec![
+            "/FLAT".to_string(),
+            "/B".to_string(),
+            "/O".to_string(),
+            "tree.json".to_string(),
+        ]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn help_text_contains_flat() {
+        assert!(help_text().contains("--flat"));
+        assert!(help_text_zh().contains("--flat"));
+    }
+
+    // ========================================================================
+    // Highlight Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_highlight_all_styles() {
+        for arg in ["--highlight", "/HL", "/hl"] {
+            let config = parse_config(&[arg, "/M", "*.rs"]);
+            assert!(config.render.highlight, "测试 {arg} 失败");
+        }
+    }
+
+    #[test]
+    fn parse_highlight_without_include_fails() {
+        let parser = CliParser::new(vec!["--highlight".to_string()]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn help_text_contains_highlight() {
+        assert!(help_text().contains("--highlight"));
+        assert!(help_text_zh().contains("--highlight"));
+    }
+
+    // ========================================================================
+    // Count Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_count_all_styles() {
+        for arg in ["--count", "/CNT", "/cnt"] {
+            let config = parse_config(&[arg]);
+            assert!(config.render.count_only, "测试 {arg} 失败");
+            assert!(config.scan.show_files, "--count 应统计文件");
+        }
+    }
+
+    #[test]
+    fn parse_count_with_batch_fails() {
+        let parser = CliParser::new(vec!["/CNT".to_string(), "/B".to_string()]);
+        assert!(matches!(parser.parse(), Err(CliError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn help_text_contains_count() {
+        assert!(help_text().contains("--count"));
+        assert!(help_text_zh().contains("--count"));
+    }
+
+    // ========================================================================
+    // Truncate Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_truncate_all_styles() {
+        for arg in ["--truncate", "/TR", "/tr"] {
+            let config = parse_config(&[arg]);
+            assert!(config.render.truncate, "测试 {arg} 失败");
+            assert_eq!(config.render.line_width, None, "控制台宽度在启动时检测");
+        }
+    }
+
+    #[test]
+    fn help_text_contains_truncate() {
+        assert!(help_text().contains("--truncate"));
+        assert!(help_text_zh().contains("--truncate"));
+    }
+
+    // ========================================================================
+    // Pager Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_pager_all_styles() {
+        for arg in ["--pager", "/PG", "/pg"] {
+            let config = parse_config(&[arg, "auto"]);
+            assert_eq!(config.output.pager, PagerMode::Auto, "测试 {arg} 失败");
+            assert_eq!(config.output.page_after, None, "分页阈值在启动时确定");
+        }
+    }
+
+    #[test]
+    fn parse_pager_modes() {
+        assert_eq!(parse_config(&[]).output.pager, PagerMode::Never);
+        for (value, mode) in [
+            ("auto", PagerMode::Auto),
+            ("never", PagerMode::Never),
+            ("ALWAYS", PagerMode::Always),
+        ] {
+            assert_eq!(
+                parse_config(&["--pager", value]).output.pager,
+                mode,
+                "测试 {value} 失败"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_pager_invalid_mode_fails() {
+        let parser = CliParser::new(vec!["--pager".to_string(), "alway".to_string()]);
+        match parser.parse() {
+            Err(CliError::InvalidValue { suggestion, .. }) => {
+                assert_eq!(suggestion.as_deref(), Some("always"));
+            }
+            other => panic!("应返回 InvalidValue 错误: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn help_text_contains_pager() {
+        assert!(help_text().contains("--pager"));
+        assert!(help_text_zh().contains("--pager"));
+    }
+
+    // ========================================================================
+    // Depth Histogram Tests
+    // ========================================================================
+
+    #[test]
+    fn parse_depth_histogram_all_styles() {
+        for arg in ["--depth-histogram", "/DH", "/dh"] {
+            let config = parse_config(&[arg]);
+            assert!(config.render.depth_histogram, "测试 {arg} 失败");
+        }
+        assert!(!parse_config(&[]).render.depth_histogram);
+    }
+
+    #[test]
+    fn help_text_contains_depth_histogram() {
+        assert!(help_text().contains("--depth-histogram"));
+        assert!(help_text_zh().contains("--depth-histogram"));
+    }
+
+    #[test]
+    fn help_text_contains_du_threshold() {
+        assert!(help_text().contains("--du-threshold"));
+        assert!(help_text_zh().contains("--du-threshold"));
+    }
+
+    #[test]
+    fn help_text_contains_du_percent() {
+        assert!(help_text().contains("--du-percent"));
+        assert!(help_text_zh().contains("--du-percent"));
+    }
+
+    #[test]
+    fn help_text_contains_one_file_system() {
+        assert!(help_text().contains("--one-file-system"));
+        assert!(help_text_zh().contains("--one-file-system"));
+    }
+
+    #[test]
+    fn help_text_contains_cloud_options() {
+        assert!(help_text().contains("--mark-cloud"));
+        assert!(help_text().contains("--skip-cloud-only"));
+        assert!(help_text_zh().contains("--skip-cloud-only"));
+    }
+
+    #[test]
+    fn help_text_contains_classify() {
+        assert!(help_text().contains("--classify"));
+        assert!(help_text_zh().contains("--classify"));
+    }
+
+    #[test]
+    fn help_text_contains_group_by() {
+        assert!(help_text().contains("--group-by"));
+        assert!(help_text_zh().contains("--group-by"));
+    }
+
+    #[test]
+    fn help_text_contains_smart() {
+        assert!(help_text().contains("--smart"));
+        assert!(help_text_zh().contains("--smart"));
+    }
+
+    #[test]
+    fn help_text_contains_no_normalize() {
+        assert!(help_text().contains("--no-normalize"));
+        assert!(help_text_zh().contains("--no-normalize"));
+    }
+
+    #[test]
+    fn help_text_contains_verbose() {
+        assert!(help_text().contains("--verbose"));
+        assert!(help_text().contains("--log-file"));
+        assert!(help_text_zh().contains("--log-file"));
+    }
+
+    #[test]
+    fn help_text_contains_print_config() {
+        assert!(help_text().contains("--print-config"));
+        assert!(help_text_zh().contains("--print-config"));
+        assert!(help_text().contains("--print-schema"));
+        assert!(help_text_zh().contains("--print-schema"));
+    }
+
+    #[test]
+    fn help_text_contains_context_menu() {
+        assert!(help_text().contains("--install-context-menu"));
+        assert!(help_text_zh().contains("--uninstall-context-menu"));
+    }
 }
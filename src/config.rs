@@ -7,15 +7,23 @@
 //!
 //! File: src/config.rs
 //! Author: WaterRun
-//! Date: 2026-01-22
+//! Date: 2026-10-16
 
 #![forbid(unsafe_code)]
 
+use std::fmt;
 use std::num::NonZeroUsize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
+use serde::{Serialize, Serializer};
 use thiserror::Error;
 
+use crate::redact::Redactor;
+use crate::report;
+use crate::winpath;
+
 // ============================================================================
 // Error Types
 // ============================================================================
@@ -79,11 +87,61 @@ pub enum ConfigError {
         /// The output file path with unrecognized extension.
         path: PathBuf,
     },
+
+    /// The settings file cannot be read or is not valid.
+    #[error("Invalid settings file: {path} ({reason})")]
+    InvalidSettings {
+        /// The settings file.
+        path: PathBuf,
+        /// Reason why the file is invalid.
+        reason: String,
+    },
 }
 
 /// Result type for configuration validation.
 pub type ConfigResult<T> = Result<T, ConfigError>;
 
+// ============================================================================
+// Warning Types
+// ============================================================================
+
+/// Non-fatal problem with the user's arguments.
+///
+/// The configuration is still usable; the warning is printed to stderr
+/// before the tree is produced.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::ConfigWarning;
+///
+/// let warning = ConfigWarning::ThreadCountClamped { requested: 10000, used: 256 };
+/// assert!(warning.to_string().contains("10000"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigWarning {
+    /// The requested thread count exceeded `MAX_THREAD_COUNT`.
+    ThreadCountClamped {
+        /// Thread count given on the command line.
+        requested: usize,
+        /// Thread count actually used.
+        used: usize,
+    },
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ThreadCountClamped { requested, used } => {
+                write!(
+                    f,
+                    "Thread count {requested} is too large; using {used} threads"
+                )
+            }
+        }
+    }
+}
+
 // ============================================================================
 // Output Format
 // ============================================================================
@@ -104,7 +162,8 @@ pub type ConfigResult<T> = Result<T, ConfigError>;
 /// let format = OutputFormat::from_extension(Path::new("tree.unknown"));
 /// assert_eq!(format, None);
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
     /// Plain text format (default).
     #[default]
@@ -120,6 +179,8 @@ pub enum OutputFormat {
 impl OutputFormat {
     /// Infers output format from file extension.
     ///
+    /// A trailing `.gz` is skipped, so `tree.json.gz` is JSON.
+    ///
     /// # Arguments
     ///
     /// * `path` - The file path to extract extension from.
@@ -139,11 +200,17 @@ impl OutputFormat {
     /// assert_eq!(OutputFormat::from_extension(Path::new("out.yml")), Some(OutputFormat::Yaml));
     /// assert_eq!(OutputFormat::from_extension(Path::new("out.yaml")), Some(OutputFormat::Yaml));
     /// assert_eq!(OutputFormat::from_extension(Path::new("out.toml")), Some(OutputFormat::Toml));
+    /// assert_eq!(OutputFormat::from_extension(Path::new("out.txt.gz")), Some(OutputFormat::Txt));
     /// assert_eq!(OutputFormat::from_extension(Path::new("out.unknown")), None);
     /// assert_eq!(OutputFormat::from_extension(Path::new("noext")), None);
     /// ```
     #[must_use]
     pub fn from_extension(path: &std::path::Path) -> Option<Self> {
+        let path = if is_gzip_path(path) {
+            std::path::Path::new(path.file_stem()?)
+        } else {
+            path
+        };
         path.extension()
             .and_then(|ext| ext.to_str())
             .and_then(|ext| match ext.to_ascii_lowercase().as_str() {
@@ -182,13 +249,198 @@ impl OutputFormat {
     }
 }
 
+/// Determines whether an output path names a gzip file.
+///
+/// # Arguments
+///
+/// * `path` - The output file path.
+///
+/// # Returns
+///
+/// `true` if the extension is `.gz`, in any case.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::config::is_gzip_path;
+///
+/// assert!(is_gzip_path(Path::new("tree.txt.gz")));
+/// assert!(!is_gzip_path(Path::new("tree.txt")));
+/// ```
+#[must_use]
+pub fn is_gzip_path(path: &std::path::Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+// ============================================================================
+// Output Encoding
+// ============================================================================
+
+/// Text encoding of written output.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::OutputEncoding;
+///
+/// assert_eq!(OutputEncoding::default(), OutputEncoding::Utf8);
+/// assert_eq!(OutputEncoding::from_arg("UTF-16LE"), Some(OutputEncoding::Utf16Le));
+/// assert_eq!(OutputEncoding::from_arg("gbk"), Some(OutputEncoding::Gbk));
+/// assert_eq!(OutputEncoding::from_arg("latin1"), None);
+/// assert_eq!(OutputEncoding::Utf16Le.bom(), &[0xFF, 0xFE]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputEncoding {
+    /// UTF-8 (default).
+    #[default]
+    Utf8,
+    /// UTF-16 little endian, as used by Windows "Unicode" text files.
+    Utf16Le,
+    /// GBK (code page 936), the ANSI code page of Simplified Chinese Windows.
+    Gbk,
+}
+
+impl OutputEncoding {
+    /// Canonical encoding names accepted by `from_arg`, used in error suggestions.
+    pub const NAMES: &'static [&'static str] = &["utf8", "utf16le", "gbk"];
+
+    /// Parses an encoding from its command-line name.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Encoding name (`utf8`, `utf16le` or `gbk`, case-insensitive,
+    ///   with an optional `-` as in `utf-8`)
+    ///
+    /// # Returns
+    ///
+    /// The matching encoding, or `None` for an unknown name.
+    #[must_use]
+    pub fn from_arg(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().replace('-', "").as_str() {
+            "utf8" => Some(Self::Utf8),
+            "utf16le" => Some(Self::Utf16Le),
+            "gbk" | "cp936" => Some(Self::Gbk),
+            _ => None,
+        }
+    }
+
+    /// Returns the byte order mark of this encoding.
+    ///
+    /// # Returns
+    ///
+    /// The BOM bytes, or an empty slice for encodings without one.
+    #[must_use]
+    pub const fn bom(&self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Self::Utf16Le => &[0xFF, 0xFE],
+            Self::Gbk => &[],
+        }
+    }
+}
+
+// ============================================================================
+// Line Ending
+// ============================================================================
+
+/// Line ending of written output.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::LineEnding;
+///
+/// assert_eq!(LineEnding::default(), LineEnding::Lf);
+/// assert_eq!(LineEnding::Crlf.as_str(), "\r\n");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// `\n` only (default).
+    #[default]
+    Lf,
+    /// `\r\n`, as written by Windows tools.
+    Crlf,
+}
+
+impl LineEnding {
+    /// Returns the characters that end a line.
+    ///
+    /// # Returns
+    ///
+    /// `"\n"` or `"\r\n"`.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+        }
+    }
+}
+
+// ============================================================================
+// Pager Mode
+// ============================================================================
+
+/// When terminal output is piped through a pager (`--pager`).
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::PagerMode;
+///
+/// assert_eq!(PagerMode::default(), PagerMode::Never);
+/// assert_eq!(PagerMode::from_arg("Auto"), Some(PagerMode::Auto));
+/// assert_eq!(PagerMode::from_arg("sometimes"), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PagerMode {
+    /// Never start a pager (default).
+    #[default]
+    Never,
+    /// Start a pager once the output no longer fits in the console.
+    Auto,
+    /// Always start a pager, even for short or redirected output.
+    Always,
+}
+
+impl PagerMode {
+    /// Canonical pager mode names accepted by `from_arg`, used in error suggestions.
+    pub const NAMES: &'static [&'static str] = &["auto", "never", "always"];
+
+    /// Parses a pager mode from its command-line name.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Mode name (`auto`, `never` or `always`, case-insensitive)
+    ///
+    /// # Returns
+    ///
+    /// The matching mode, or `None` for an unknown name.
+    #[must_use]
+    pub fn from_arg(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "never" => Some(Self::Never),
+            "always" => Some(Self::Always),
+            _ => None,
+        }
+    }
+}
+
 // ============================================================================
 // Charset Mode
 // ============================================================================
 
 /// Character set mode for tree rendering.
 ///
-/// Controls whether tree symbols use ASCII or Unicode characters.
+/// Controls whether tree symbols use ASCII or Unicode characters. `Auto`
+/// is resolved at startup from the console (see `winvol`); until then, and
+/// when rendering without a console, it draws like `Unicode`.
 ///
 /// # Examples
 ///
@@ -203,16 +455,51 @@ impl OutputFormat {
 /// assert_eq!(ascii.branch(), "+---");
 /// assert_eq!(ascii.last_branch(), "\\---");
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CharsetMode {
-    /// Use Unicode characters for tree rendering (default).
+    /// Use Unicode characters, or ASCII on a console that cannot show them
+    /// (default).
     #[default]
+    Auto,
+    /// Use Unicode characters for tree rendering.
     Unicode,
     /// Use ASCII characters for tree rendering (compatible with `tree /A`).
     Ascii,
 }
 
 impl CharsetMode {
+    /// Canonical charset names accepted by `from_arg`, used in error suggestions.
+    pub const NAMES: &'static [&'static str] = &["auto", "unicode", "ascii"];
+
+    /// Parses a charset mode from its command-line name.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Mode name (`auto`, `unicode` or `ascii`, case-insensitive)
+    ///
+    /// # Returns
+    ///
+    /// The matching mode, or `None` for an unknown name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::CharsetMode;
+    ///
+    /// assert_eq!(CharsetMode::from_arg("ASCII"), Some(CharsetMode::Ascii));
+    /// assert_eq!(CharsetMode::from_arg("utf8"), None);
+    /// ```
+    #[must_use]
+    pub fn from_arg(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "unicode" => Some(Self::Unicode),
+            "ascii" => Some(Self::Ascii),
+            _ => None,
+        }
+    }
+
     /// Returns the branch symbol for non-last siblings.
     ///
     /// # Returns
@@ -230,7 +517,7 @@ impl CharsetMode {
     #[must_use]
     pub const fn branch(&self) -> &'static str {
         match self {
-            Self::Unicode => "├─",
+            Self::Unicode | Self::Auto => "├─",
             Self::Ascii => "+---",
         }
     }
@@ -252,7 +539,7 @@ impl CharsetMode {
     #[must_use]
     pub const fn last_branch(&self) -> &'static str {
         match self {
-            Self::Unicode => "└─",
+            Self::Unicode | Self::Auto => "└─",
             Self::Ascii => "\\---",
         }
     }
@@ -274,7 +561,7 @@ impl CharsetMode {
     #[must_use]
     pub const fn vertical(&self) -> &'static str {
         match self {
-            Self::Unicode => "│  ",
+            Self::Unicode | Self::Auto => "│  ",
             Self::Ascii => "|   ",
         }
     }
@@ -296,7 +583,7 @@ impl CharsetMode {
     #[must_use]
     pub const fn indent(&self) -> &'static str {
         match self {
-            Self::Unicode => "   ",
+            Self::Unicode | Self::Auto => "   ",
             Self::Ascii => "    ",
         }
     }
@@ -318,7 +605,8 @@ impl CharsetMode {
 /// let mode = PathMode::default();
 /// assert_eq!(mode, PathMode::Relative);
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PathMode {
     /// Display only the name (default).
     #[default]
@@ -328,1092 +616,3730 @@ pub enum PathMode {
 }
 
 // ============================================================================
-// Sub-Configuration Structures
+// Escape Mode
 // ============================================================================
 
-/// Scan options.
+/// Escaping policy for entry names in text output.
 ///
-/// Configuration controlling directory traversal behavior.
+/// Names containing newlines, control characters or trailing spaces can
+/// break the tree layout and confuse tools reading the output.
 ///
 /// # Examples
 ///
 /// ```
-/// use treepp::config::ScanOptions;
+/// use treepp::config::EscapeMode;
 ///
-/// let opts = ScanOptions::default();
-/// assert_eq!(opts.max_depth, None);
-/// assert!(!opts.show_files);
-/// assert_eq!(opts.thread_count.get(), 8);
-/// assert!(!opts.respect_gitignore);
-/// assert!(!opts.show_hidden);
+/// assert_eq!(EscapeMode::default(), EscapeMode::Raw);
+/// assert_eq!(EscapeMode::from_arg("c"), Some(EscapeMode::C));
+/// assert_eq!(EscapeMode::from_arg("Question"), Some(EscapeMode::Question));
+/// assert_eq!(EscapeMode::from_arg("octal"), None);
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct ScanOptions {
-    /// Maximum recursion depth (`None` means unlimited).
-    pub max_depth: Option<usize>,
-    /// Whether to show files (corresponds to `/F`).
-    pub show_files: bool,
-    /// Number of scanning threads.
-    pub thread_count: NonZeroUsize,
-    /// Whether to respect `.gitignore` rules.
-    pub respect_gitignore: bool,
-    /// Whether to show hidden files (Windows hidden attribute).
-    pub show_hidden: bool,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EscapeMode {
+    /// Print names unchanged (default).
+    #[default]
+    Raw,
+    /// Replace non-printable characters with `?`.
+    Question,
+    /// Use C-style escapes such as `\n`, `\t` and `\x1B`.
+    C,
 }
 
-impl Default for ScanOptions {
-    /// Creates default scan options with 8 threads and no file display.
+impl EscapeMode {
+    /// Canonical escape mode names accepted by `from_arg`, used in error suggestions.
+    pub const NAMES: &'static [&'static str] = &["raw", "question", "c"];
+
+    /// Parses an escape mode from its command-line name.
     ///
-    /// # Examples
+    /// # Arguments
     ///
-    /// ```
-    /// use treepp::config::ScanOptions;
+    /// * `value` - Mode name (`raw`, `question` or `c`, case-insensitive)
     ///
-    /// let opts = ScanOptions::default();
-    /// assert_eq!(opts.thread_count.get(), 8);
-    /// assert!(!opts.show_hidden);
-    /// ```
-    fn default() -> Self {
-        Self {
-            max_depth: None,
-            show_files: false,
-            thread_count: NonZeroUsize::new(8).expect("8 is non-zero"),
-            respect_gitignore: false,
-            show_hidden: false,
+    /// # Returns
+    ///
+    /// The matching mode, or `None` for an unknown name.
+    #[must_use]
+    pub fn from_arg(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "raw" => Some(Self::Raw),
+            "question" => Some(Self::Question),
+            "c" => Some(Self::C),
+            _ => None,
         }
     }
 }
 
-/// Match options.
+// ============================================================================
+// Sort Key
+// ============================================================================
+
+/// Key used to order entries of the same kind.
 ///
-/// Configuration controlling file/directory filtering behavior.
+/// Files are always listed before directories, as in the native `tree`.
 ///
 /// # Examples
 ///
 /// ```
-/// use treepp::config::MatchOptions;
+/// use treepp::config::SortKey;
 ///
-/// let opts = MatchOptions::default();
-/// assert!(opts.include_patterns.is_empty());
-/// assert!(opts.exclude_patterns.is_empty());
-/// assert!(!opts.prune_empty);
+/// assert_eq!(SortKey::default(), SortKey::Name);
+/// assert_eq!(SortKey::from_arg("ctime"), Some(SortKey::Created));
+/// assert_eq!(SortKey::from_arg("Size"), Some(SortKey::Size));
+/// assert_eq!(SortKey::from_arg("owner"), None);
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct MatchOptions {
-    /// Include patterns (only show matching items).
-    pub include_patterns: Vec<String>,
-    /// Exclude patterns (ignore matching items).
-    pub exclude_patterns: Vec<String>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// Windows-style name order (default).
+    #[default]
+    Name,
+    /// File size, smallest first.
+    Size,
+    /// Last modification time, oldest first.
+    #[serde(rename = "mtime")]
+    Modified,
+    /// Creation time, oldest first.
+    #[serde(rename = "ctime")]
+    Created,
+    /// Last access time, oldest first.
+    #[serde(rename = "atime")]
+    Accessed,
+    /// Share of the parent's cumulative size, smallest first (requires disk usage).
+    Share,
 }
 
-/// Render options.
-///
-/// Configuration controlling tree output appearance.
+impl SortKey {
+    /// Canonical sort key names accepted by `from_arg`, used in error suggestions.
+    pub const NAMES: &'static [&'static str] =
+        &["name", "size", "mtime", "ctime", "atime", "share"];
+
+    /// Parses a sort key from its command-line name.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Key name (`name`, `size`, `mtime`, `ctime`, `atime` or
+    ///   `share`, case-insensitive)
+    ///
+    /// # Returns
+    ///
+    /// The matching key, or `None` for an unknown name.
+    #[must_use]
+    pub fn from_arg(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "name" => Some(Self::Name),
+            "size" => Some(Self::Size),
+            "mtime" => Some(Self::Modified),
+            "ctime" => Some(Self::Created),
+            "atime" => Some(Self::Accessed),
+            "share" => Some(Self::Share),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// Grouping
+// ============================================================================
+
+/// How the files of a directory are divided into headed sections (`--group-by`).
 ///
 /// # Examples
 ///
 /// ```
-/// use treepp::config::{RenderOptions, CharsetMode, PathMode};
+/// use treepp::config::GroupBy;
 ///
-/// let opts = RenderOptions::default();
-/// assert_eq!(opts.charset, CharsetMode::Unicode);
-/// assert_eq!(opts.path_mode, PathMode::Relative);
-/// assert!(!opts.show_size);
-/// assert!(!opts.human_readable);
+/// assert_eq!(GroupBy::default(), GroupBy::None);
+/// assert_eq!(GroupBy::from_arg("Type"), Some(GroupBy::Type));
+/// assert_eq!(GroupBy::from_arg("owner"), None);
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct RenderOptions {
-    /// Character set mode.
-    pub charset: CharsetMode,
-    /// Path display mode.
-    pub path_mode: PathMode,
-    /// Whether to show file size.
-    pub show_size: bool,
-    /// Whether to display size in human-readable format.
-    pub human_readable: bool,
-    /// Whether to show last modification date.
-    pub show_date: bool,
-    /// Whether to show cumulative directory size.
-    pub show_disk_usage: bool,
-    /// Whether to hide tree connectors (indent only).
-    pub no_indent: bool,
-    /// Whether to reverse sort order.
-    pub reverse_sort: bool,
-    /// Whether to show summary report at the end.
-    pub show_report: bool,
-    /// Whether to hide Windows native banner.
-    pub no_win_banner: bool,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    /// Files are listed in one block (default).
+    #[default]
+    None,
+    /// Files are grouped by kind (code, images, archives, other), based on
+    /// their extension.
+    Type,
 }
 
-/// Output options.
-///
-/// Configuration controlling result output destination and format.
+impl GroupBy {
+    /// Canonical grouping names accepted by `from_arg`, used in error suggestions.
+    pub const NAMES: &'static [&'static str] = &["none", "type"];
+
+    /// Parses a grouping from its command-line name.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Grouping name (`none` or `type`, case-insensitive)
+    ///
+    /// # Returns
+    ///
+    /// The matching grouping, or `None` for an unknown name.
+    #[must_use]
+    pub fn from_arg(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "type" => Some(Self::Type),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// Level Guides
+// ============================================================================
+
+/// Decoration of the tree connectors that marks their level (`--level-guides`).
 ///
 /// # Examples
 ///
 /// ```
-/// use treepp::config::OutputOptions;
+/// use treepp::config::LevelGuides;
 ///
-/// let opts = OutputOptions::default();
-/// assert!(opts.output_path.is_none());
-/// assert!(!opts.silent);
+/// assert_eq!(LevelGuides::default(), LevelGuides::None);
+/// assert_eq!(LevelGuides::from_arg("Color"), Some(LevelGuides::Color));
+/// assert_eq!(LevelGuides::from_arg("dots"), None);
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct OutputOptions {
-    /// Output file path (`None` means terminal output only).
-    pub output_path: Option<PathBuf>,
-    /// Output format (inferred from `output_path` extension, or default `Txt`).
-    pub format: OutputFormat,
-    /// Whether to suppress terminal output.
-    pub silent: bool,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LevelGuides {
+    /// Connectors are drawn plain (default).
+    #[default]
+    None,
+    /// Each vertical line carries the number of its level, e.g. `│3 `.
+    Number,
+    /// Connectors are colored by level, cycling through six colors.
+    Color,
+}
+
+impl LevelGuides {
+    /// Canonical decoration names accepted by `from_arg`, used in error suggestions.
+    pub const NAMES: &'static [&'static str] = &["none", "number", "color"];
+
+    /// Parses a decoration from its command-line name.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Decoration name (`none`, `number` or `color`, case-insensitive)
+    ///
+    /// # Returns
+    ///
+    /// The matching decoration, or `None` for an unknown name.
+    #[must_use]
+    pub fn from_arg(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "number" => Some(Self::Number),
+            "color" => Some(Self::Color),
+            _ => None,
+        }
+    }
 }
 
 // ============================================================================
-// Main Configuration Structure
+// Number Format
 // ============================================================================
 
-/// Full configuration.
-///
-/// The single source of truth for user intent. Generated by CLI parsing,
-/// and all subsequent modules (scan, match, render, output) depend on this configuration.
+/// Digit grouping used for sizes in text output.
 ///
 /// # Examples
 ///
 /// ```
-/// use std::path::PathBuf;
-/// use treepp::config::Config;
-///
-/// let config = Config::default();
-/// assert_eq!(config.root_path, PathBuf::from("."));
-/// assert!(!config.scan.show_files);
-/// assert!(!config.batch_mode);
-/// ```
-///
-/// ```
-/// use std::path::PathBuf;
-/// use std::num::NonZeroUsize;
-/// use treepp::config::{Config, OutputFormat};
-///
-/// let mut config = Config::default();
-/// config.scan.show_files = true;
-/// config.batch_mode = true;
-/// config.scan.thread_count = NonZeroUsize::new(16).unwrap();
-/// config.output.output_path = Some(PathBuf::from("tree.json"));
+/// use treepp::config::NumberFormat;
 ///
-/// let validated = config.validate().expect("validation should pass");
-/// assert_eq!(validated.output.format, OutputFormat::Json);
+/// assert_eq!(NumberFormat::default(), NumberFormat::Plain);
+/// assert_eq!(NumberFormat::from_arg("Locale"), Some(NumberFormat::Locale));
+/// assert_eq!(NumberFormat::from_arg("comma"), Some(NumberFormat::Comma));
+/// assert_eq!(NumberFormat::from_arg("hex"), None);
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Config {
-    /// Root path (starting directory).
-    pub root_path: PathBuf,
-    /// Whether the user explicitly specified a path.
-    pub path_explicitly_set: bool,
-    /// Whether to show help information.
-    pub show_help: bool,
-    /// Whether to show version information.
-    pub show_version: bool,
-    /// Whether to use batch mode (default `false`, uses streaming mode).
-    pub batch_mode: bool,
-    /// Scan options.
-    pub scan: ScanOptions,
-    /// Match options.
-    pub matching: MatchOptions,
-    /// Render options.
-    pub render: RenderOptions,
-    /// Output options.
-    pub output: OutputOptions,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    /// Raw digits without grouping (default).
+    #[default]
+    Plain,
+    /// Group separator and decimal point from the user's regional settings.
+    Locale,
+    /// `1,234,567.8`
+    Comma,
+    /// `1.234.567,8`
+    Period,
+    /// `1 234 567.8`
+    Space,
+    /// `1'234'567.8`
+    Apostrophe,
 }
 
-impl Default for Config {
-    /// Creates a default configuration with current directory as root.
+impl NumberFormat {
+    /// Canonical number format names accepted by `from_arg`, used in error suggestions.
+    pub const NAMES: &'static [&'static str] =
+        &["plain", "locale", "comma", "period", "space", "apostrophe"];
+
+    /// Parses a number format from its command-line name.
     ///
-    /// # Examples
+    /// # Arguments
     ///
-    /// ```
-    /// use std::path::PathBuf;
-    /// use treepp::config::Config;
+    /// * `value` - Format name (`plain`, `locale`, `comma`, `period`,
+    ///   `space` or `apostrophe`, case-insensitive)
     ///
-    /// let config = Config::default();
-    /// assert_eq!(config.root_path, PathBuf::from("."));
-    /// ```
-    fn default() -> Self {
-        Self {
-            root_path: PathBuf::from("."),
-            path_explicitly_set: false,
-            show_help: false,
-            show_version: false,
-            batch_mode: false,
-            scan: ScanOptions::default(),
-            matching: MatchOptions::default(),
-            render: RenderOptions::default(),
-            output: OutputOptions::default(),
+    /// # Returns
+    ///
+    /// The matching format, or `None` for an unknown name.
+    #[must_use]
+    pub fn from_arg(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "plain" => Some(Self::Plain),
+            "locale" => Some(Self::Locale),
+            "comma" => Some(Self::Comma),
+            "period" | "dot" => Some(Self::Period),
+            "space" => Some(Self::Space),
+            "apostrophe" => Some(Self::Apostrophe),
+            _ => None,
         }
     }
 }
 
-impl Config {
-    /// Creates a configuration with the specified root path.
+// ============================================================================
+// Language
+// ============================================================================
+
+/// Language of help, error messages and the statistics report.
+///
+/// The banner is unaffected; it always follows the system like the native
+/// `tree`.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+///
+/// assert_eq!(Language::default(), Language::English);
+/// assert_eq!(Language::from_arg("ZH"), Some(Language::Chinese));
+/// assert_eq!(Language::from_langid(0x0804), Language::Chinese);
+/// assert_eq!(Language::from_arg("fr"), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    /// English (default).
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    /// Simplified Chinese.
+    #[serde(rename = "zh")]
+    Chinese,
+}
+
+impl Language {
+    /// Canonical language names accepted by `from_arg`, used in error suggestions.
+    pub const NAMES: &'static [&'static str] = &["en", "zh"];
+
+    /// Parses a language from its command-line name.
     ///
     /// # Arguments
     ///
-    /// * `root_path` - The starting directory for tree traversal.
+    /// * `value` - Language name (`en` or `zh`, case-insensitive)
     ///
     /// # Returns
     ///
-    /// A new `Config` with the specified root path and `path_explicitly_set` set to `true`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use std::path::PathBuf;
-    /// use treepp::config::Config;
-    ///
-    /// let config = Config::with_root(PathBuf::from("C:\\Users"));
-    /// assert_eq!(config.root_path, PathBuf::from("C:\\Users"));
-    /// assert!(config.path_explicitly_set);
-    /// ```
+    /// The matching language, or `None` for an unknown name.
     #[must_use]
-    pub fn with_root(root_path: PathBuf) -> Self {
-        Self {
-            root_path,
-            path_explicitly_set: true,
-            ..Self::default()
+    pub fn from_arg(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "en" | "english" => Some(Self::English),
+            "zh" | "chinese" => Some(Self::Chinese),
+            _ => None,
         }
     }
 
-    /// Validates the configuration and populates derived fields.
-    ///
-    /// Performs the following operations:
-    /// - Validates root path existence and canonicalizes it
-    /// - Infers output format from file extension
-    /// - Checks for option conflicts
-    /// - Applies implicit dependencies
-    ///
-    /// # Returns
-    ///
-    /// The validated and normalized `Config`.
-    ///
-    /// # Errors
-    ///
-    /// Returns `ConfigError` if:
-    /// - Options have irreconcilable conflicts
-    /// - Root path does not exist or is not a directory
-    /// - Output path extension is unrecognized
-    ///
-    /// # Examples
+    /// Maps a Windows `LANGID` to a language.
     ///
-    /// ```
-    /// use std::path::PathBuf;
-    /// use treepp::config::{Config, OutputFormat};
-    ///
-    /// let mut config = Config::default();
-    /// config.batch_mode = true;
-    /// config.output.output_path = Some(PathBuf::from("result.json"));
+    /// Every Chinese variant maps to `Chinese`; anything else falls back
+    /// to `English`.
     ///
-    /// let validated = config.validate().unwrap();
-    /// assert_eq!(validated.output.format, OutputFormat::Json);
-    /// ```
+    /// # Arguments
     ///
-    /// ```
-    /// use std::path::PathBuf;
-    /// use treepp::config::{Config, ConfigError};
+    /// * `langid` - Language identifier, e.g. from `GetUserDefaultUILanguage`
     ///
-    /// let mut config = Config::default();
-    /// config.output.output_path = Some(PathBuf::from("result.xyz"));
+    /// # Returns
     ///
-    /// let err = config.validate().unwrap_err();
-    /// assert!(matches!(err, ConfigError::UnknownOutputFormat { .. }));
-    /// ```
-    pub fn validate(mut self) -> ConfigResult<Self> {
-        self.validate_and_canonicalize_root_path()?;
-        self.infer_output_format()?;
-        self.check_conflicts()?;
-        self.apply_implicit_dependencies();
-        Ok(self)
+    /// The language to use.
+    #[must_use]
+    pub fn from_langid(langid: u16) -> Self {
+        if langid & 0x03FF == 0x04 {
+            Self::Chinese
+        } else {
+            Self::English
+        }
     }
+}
 
-    /// Determines whether this is an "info-only" mode (help or version).
-    ///
-    /// # Returns
-    ///
-    /// `true` if either `show_help` or `show_version` is enabled.
+// ============================================================================
+// Path List Source
+// ============================================================================
+
+/// Source of an explicit path list (`--paths-from`).
+///
+/// When set, the tree is built from the listed paths instead of traversing
+/// the filesystem.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::config::PathListSource;
+///
+/// assert_eq!(PathListSource::from_arg("-"), PathListSource::Stdin);
+/// assert_eq!(
+///     PathListSource::from_arg("files.txt"),
+///     PathListSource::File(PathBuf::from("files.txt"))
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathListSource {
+    /// Read the list from standard input (`-`).
+    Stdin,
+    /// Read the list from a file.
+    File(PathBuf),
+}
+
+impl PathListSource {
+    /// Creates a path list source from a command-line value.
     ///
-    /// # Examples
+    /// # Arguments
     ///
-    /// ```
-    /// use treepp::config::Config;
+    /// * `value` - The argument value; `-` selects standard input.
     ///
-    /// let mut config = Config::default();
-    /// assert!(!config.is_info_only());
+    /// # Returns
     ///
-    /// config.show_help = true;
-    /// assert!(config.is_info_only());
-    /// ```
+    /// The corresponding `PathListSource`.
     #[must_use]
-    pub const fn is_info_only(&self) -> bool {
-        self.show_help || self.show_version
+    pub fn from_arg(value: &str) -> Self {
+        if value == "-" {
+            Self::Stdin
+        } else {
+            Self::File(PathBuf::from(value))
+        }
     }
+}
 
-    /// Determines whether file size information is needed.
-    ///
-    /// Returns `true` when any of `show_size`, `human_readable`, or `show_disk_usage` is enabled.
-    ///
-    /// # Returns
-    ///
-    /// `true` if size information is required.
+impl Serialize for PathListSource {
+    /// Serializes the source as its command-line value: `-` or the path.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Stdin => serializer.serialize_str("-"),
+            Self::File(path) => path.serialize(serializer),
+        }
+    }
+}
+
+// ============================================================================
+// Stats Target
+// ============================================================================
+
+/// Destination of the `--stats-json` summary.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::config::StatsTarget;
+///
+/// assert_eq!(StatsTarget::from_arg("-"), StatsTarget::Stderr);
+/// assert_eq!(
+///     StatsTarget::from_arg("stats.json"),
+///     StatsTarget::File(PathBuf::from("stats.json"))
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatsTarget {
+    /// Write the summary to standard error (`-`).
+    Stderr,
+    /// Write the summary to a file.
+    File(PathBuf),
+}
+
+impl StatsTarget {
+    /// Creates a stats target from a command-line value.
     ///
-    /// # Examples
+    /// # Arguments
     ///
-    /// ```
-    /// use treepp::config::Config;
+    /// * `value` - The argument value; `-` selects standard error.
     ///
-    /// let mut config = Config::default();
-    /// assert!(!config.needs_size_info());
+    /// # Returns
     ///
-    /// config.render.show_size = true;
-    /// assert!(config.needs_size_info());
-    /// ```
+    /// The corresponding `StatsTarget`.
     #[must_use]
-    pub const fn needs_size_info(&self) -> bool {
-        self.render.show_size || self.render.human_readable || self.render.show_disk_usage
+    pub fn from_arg(value: &str) -> Self {
+        if value == "-" {
+            Self::Stderr
+        } else {
+            Self::File(PathBuf::from(value))
+        }
     }
+}
 
-    /// Determines whether time information is needed.
-    ///
-    /// Returns `true` when `show_date` is enabled.
-    ///
-    /// # Returns
-    ///
-    /// `true` if time information is required.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use treepp::config::Config;
-    ///
-    /// let mut config = Config::default();
-    /// assert!(!config.needs_time_info());
+impl Serialize for StatsTarget {
+    /// Serializes the target as its command-line value: `-` or the path.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Stderr => serializer.serialize_str("-"),
+            Self::File(path) => path.serialize(serializer),
+        }
+    }
+}
+
+// ============================================================================
+// Cache Mode
+// ============================================================================
+
+/// Use of the on-disk scan cache (`--cache` / `--no-cache`).
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::CacheMode;
+///
+/// let mode = CacheMode::default();
+/// assert_eq!(mode, CacheMode::Disabled);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheMode {
+    /// Neither read nor write the cache (default).
+    #[default]
+    Disabled,
+    /// List unchanged directories from the cache and update it (`--cache`).
+    Enabled,
+    /// Delete the cache of the scan root and scan without it (`--no-cache`).
+    Discard,
+}
+
+// ============================================================================
+// Context Menu Scope
+// ============================================================================
+
+/// Whose Explorer context menu `--install-context-menu` changes.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::MenuScope;
+///
+/// assert_eq!(MenuScope::from_arg("User"), Some(MenuScope::User));
+/// assert_eq!(MenuScope::from_arg("everyone"), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuScope {
+    /// The current user only; needs no elevation.
+    User,
+    /// All users of the machine; needs an elevated prompt.
+    Machine,
+}
+
+impl MenuScope {
+    /// Canonical scope names accepted by `from_arg`, used in error suggestions.
+    pub const NAMES: &'static [&'static str] = &["user", "machine"];
+
+    /// Parses a scope from its command-line name.
     ///
-    /// config.render.show_date = true;
-    /// assert!(config.needs_time_info());
-    /// ```
+    /// # Arguments
+    ///
+    /// * `value` - Scope name (`user` or `machine`, case-insensitive)
+    ///
+    /// # Returns
+    ///
+    /// The matching scope, or `None` for an unknown name.
     #[must_use]
-    pub const fn needs_time_info(&self) -> bool {
-        self.render.show_date
+    pub fn from_arg(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "user" => Some(Self::User),
+            "machine" => Some(Self::Machine),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// Scan Backend
+// ============================================================================
+
+/// Directory traversal used by the batch pipeline (`--backend`).
+///
+/// Every backend applies the same filters and yields the same tree; they
+/// differ only in how directories are read and the tree is assembled.
+///
+/// # Examples
+///
+/// ```
+/// use std::num::NonZeroUsize;
+/// use treepp::config::ScanBackend;
+///
+/// let one = NonZeroUsize::new(1).unwrap();
+/// let eight = NonZeroUsize::new(8).unwrap();
+/// assert_eq!(ScanBackend::Auto.resolve(eight), ScanBackend::Parallel);
+/// assert_eq!(ScanBackend::Auto.resolve(one), ScanBackend::Walk);
+/// assert_eq!(ScanBackend::Channel.resolve(one), ScanBackend::Channel);
+/// assert_eq!(ScanBackend::from_arg("Channel"), Some(ScanBackend::Channel));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanBackend {
+    /// `parallel`, or `walk` with a single thread (default).
+    #[default]
+    Auto,
+    /// One directory at a time on the calling thread.
+    Walk,
+    /// Subdirectories read on a thread pool, added to a shared tree.
+    Parallel,
+    /// Subdirectories read on a thread pool, sent to a single tree builder.
+    Channel,
+}
+
+impl ScanBackend {
+    /// Canonical backend names accepted by `from_arg`, used in error suggestions.
+    pub const NAMES: &'static [&'static str] = &["auto", "walk", "parallel", "channel"];
+
+    /// Parses a backend from its command-line name.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Backend name (`auto`, `walk`, `parallel` or `channel`,
+    ///   case-insensitive)
+    ///
+    /// # Returns
+    ///
+    /// The matching backend, or `None` for an unknown name.
+    #[must_use]
+    pub fn from_arg(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "walk" => Some(Self::Walk),
+            "parallel" => Some(Self::Parallel),
+            "channel" => Some(Self::Channel),
+            _ => None,
+        }
+    }
+
+    /// Returns the command-line name of the backend.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Walk => "walk",
+            Self::Parallel => "parallel",
+            Self::Channel => "channel",
+        }
+    }
+
+    /// Picks the backend a scan runs with.
+    ///
+    /// `auto` picks `parallel`, which needs more than one thread, and
+    /// `walk` otherwise. Other backends are returned unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `threads` - The configured thread count.
+    ///
+    /// # Returns
+    ///
+    /// The backend to scan with; never `Auto`.
+    #[must_use]
+    pub const fn resolve(self, threads: NonZeroUsize) -> Self {
+        match self {
+            Self::Auto if threads.get() > 1 => Self::Parallel,
+            Self::Auto => Self::Walk,
+            other => other,
+        }
+    }
+}
+
+// ============================================================================
+// Thread Count
+// ============================================================================
+
+/// Upper bound for an explicit `--thread` value.
+///
+/// More threads than this only add contention on the directory handles.
+pub const MAX_THREAD_COUNT: usize = 256;
+
+/// Threads per logical CPU used by `--thread auto`.
+///
+/// Scanning mostly waits on the file system, so more threads than CPUs
+/// keep the disk queue busy.
+const AUTO_THREADS_PER_CPU: usize = 2;
+
+/// Lower bound of the `--thread auto` thread count.
+const MIN_AUTO_THREADS: usize = 4;
+
+/// Upper bound of the `--thread auto` thread count.
+///
+/// Beyond this, a single volume rarely serves directory listings faster.
+const MAX_AUTO_THREADS: usize = 32;
+
+/// Returns the thread count used by `--thread auto` (the default).
+///
+/// Uses twice the available parallelism, bounded to
+/// `MIN_AUTO_THREADS..=MAX_AUTO_THREADS`. If the parallelism cannot be
+/// determined, a single CPU is assumed.
+///
+/// # Returns
+///
+/// The thread count for batch scanning.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::auto_thread_count;
+///
+/// let threads = auto_thread_count().get();
+/// assert!((4..=32).contains(&threads));
+/// ```
+#[must_use]
+pub fn auto_thread_count() -> NonZeroUsize {
+    let cpus = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let threads = cpus
+        .saturating_mul(AUTO_THREADS_PER_CPU)
+        .clamp(MIN_AUTO_THREADS, MAX_AUTO_THREADS);
+    NonZeroUsize::new(threads).expect("auto thread count is at least MIN_AUTO_THREADS")
+}
+
+// ============================================================================
+// Serialization Helpers
+// ============================================================================
+
+/// Serializes a time limit the way `--read-timeout` and `--timeout` accept
+/// it, in milliseconds (e.g. `"1500ms"`); no limit is left out.
+fn serialize_duration<S: Serializer>(
+    value: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(duration) => serializer.collect_str(&format_args!("{}ms", duration.as_millis())),
+        None => serializer.serialize_none(),
+    }
+}
+
+// ============================================================================
+// Sub-Configuration Structures
+// ============================================================================
+
+/// Scan options.
+///
+/// Configuration controlling directory traversal behavior.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::ScanOptions;
+///
+/// let opts = ScanOptions::default();
+/// assert_eq!(opts.max_depth, None);
+/// assert!(!opts.show_files);
+/// assert_eq!(opts.thread_count, treepp::config::auto_thread_count());
+/// assert!(!opts.respect_gitignore);
+/// assert!(!opts.show_hidden);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScanOptions {
+    /// Maximum recursion depth (`None` means unlimited).
+    pub max_depth: Option<usize>,
+    /// Whether to show files (corresponds to `/F`).
+    pub show_files: bool,
+    /// Number of scanning threads (defaults to `auto_thread_count`).
+    pub thread_count: NonZeroUsize,
+    /// Directory traversal of the batch pipeline.
+    pub backend: ScanBackend,
+    /// Whether to respect `.gitignore` rules.
+    pub respect_gitignore: bool,
+    /// Whether to show hidden files (Windows hidden attribute).
+    pub show_hidden: bool,
+    /// Explicit path list to build the tree from (`None` means traverse the filesystem).
+    pub paths_from: Option<PathListSource>,
+    /// Whether to limit the tree to files tracked by the git index.
+    pub git_tracked: bool,
+    /// Whether to list filtered entries annotated with the reason instead of hiding them.
+    pub show_filtered: bool,
+    /// Maximum number of entries listed per directory (`None` means unlimited).
+    pub max_entries: Option<usize>,
+    /// Directories with more entries than this are not descended into (`None` means unlimited).
+    pub file_limit: Option<usize>,
+    /// Use of the on-disk scan cache.
+    pub cache: CacheMode,
+    /// Time limit for reading a single directory (`None` means no limit).
+    #[serde(serialize_with = "serialize_duration")]
+    pub read_timeout: Option<Duration>,
+    /// Time limit for the whole scan (`None` means no limit).
+    #[serde(serialize_with = "serialize_duration")]
+    pub timeout: Option<Duration>,
+    /// Time after which no further directories are read (`None` means no budget).
+    #[serde(serialize_with = "serialize_duration")]
+    pub budget_time: Option<Duration>,
+    /// Number of entries after which no further directories are read (`None` means no budget).
+    pub budget_entries: Option<usize>,
+    /// Number of levels kept at the bottom of deep branches (`None` shows all levels).
+    pub leaf_depth: Option<usize>,
+    /// Directories with a smaller cumulative size in bytes are hidden (`None` shows all).
+    pub du_threshold: Option<u64>,
+    /// Whether to stay on the root's volume instead of descending into other volumes.
+    pub one_file_system: bool,
+    /// Whether to leave out cloud-only placeholders (files not stored locally).
+    pub skip_cloud_only: bool,
+    /// Last directory, relative to the root, that an interrupted `--checkpoint`
+    /// run wrote; the streaming scan continues after it. Set at startup from
+    /// the checkpoint file, `None` otherwise.
+    #[serde(skip)]
+    pub resume_after: Option<PathBuf>,
+}
+
+impl Default for ScanOptions {
+    /// Creates default scan options with automatic threads and no file display.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::{ScanOptions, auto_thread_count};
+    ///
+    /// let opts = ScanOptions::default();
+    /// assert_eq!(opts.thread_count, auto_thread_count());
+    /// assert!(!opts.show_hidden);
+    /// ```
+    fn default() -> Self {
+        Self {
+            max_depth: None,
+            show_files: false,
+            thread_count: auto_thread_count(),
+            backend: ScanBackend::Auto,
+            respect_gitignore: false,
+            show_hidden: false,
+            paths_from: None,
+            git_tracked: false,
+            show_filtered: false,
+            max_entries: None,
+            file_limit: None,
+            cache: CacheMode::Disabled,
+            read_timeout: None,
+            timeout: None,
+            budget_time: None,
+            budget_entries: None,
+            leaf_depth: None,
+            du_threshold: None,
+            one_file_system: false,
+            skip_cloud_only: false,
+            resume_after: None,
+        }
+    }
+}
+
+/// Match options.
+///
+/// Configuration controlling file/directory filtering behavior.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::MatchOptions;
+///
+/// let opts = MatchOptions::default();
+/// assert!(opts.include_patterns.is_empty());
+/// assert!(opts.exclude_patterns.is_empty());
+/// assert!(!opts.prune_empty);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct MatchOptions {
+    /// Include patterns (only show matching items).
+    pub include_patterns: Vec<String>,
+    /// Exclude patterns (ignore matching items); `!pattern` re-includes
+    /// items excluded by earlier patterns.
+    pub exclude_patterns: Vec<String>,
+    /// Include overrides: matching items are kept despite `.gitignore`,
+    /// `--smart`, exclude and include patterns (`--include-override`).
+    pub include_overrides: Vec<String>,
+    /// Whether to prune directories that contain no files after filtering.
+    pub prune_empty: bool,
+    /// Whether to leave out zero-byte files (`--prune-empty-files`).
+    pub prune_empty_files: bool,
+    /// Whether to exclude the build output of detected projects (`--smart`).
+    pub smart_excludes: bool,
+    /// Whether to match and sort names as stored, without composing them to
+    /// NFC first (`--no-normalize`).
+    pub no_normalize: bool,
+}
+
+/// Render options.
+///
+/// Configuration controlling tree output appearance.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::{RenderOptions, CharsetMode, PathMode};
+///
+/// let opts = RenderOptions::default();
+/// assert_eq!(opts.charset, CharsetMode::Auto);
+/// assert_eq!(opts.path_mode, PathMode::Relative);
+/// assert!(!opts.show_size);
+/// assert!(!opts.human_readable);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct RenderOptions {
+    /// Character set mode.
+    pub charset: CharsetMode,
+    /// Path display mode.
+    pub path_mode: PathMode,
+    /// Directory full paths are shown relative to (`--relative-to`); a
+    /// relative path is taken from the root, and validation canonicalizes it.
+    pub relative_to: Option<PathBuf>,
+    /// Redaction of the user profile and chosen terms (`--redact`).
+    pub redaction: Option<Redactor>,
+    /// Escaping policy for entry names.
+    pub escape: EscapeMode,
+    /// Whether to print control characters in names to a console unchanged
+    /// (`--raw-names`).
+    pub raw_names: bool,
+    /// Whether to wrap entry names in double quotes.
+    pub quote_names: bool,
+    /// Whether to append a type indicator to names (`/` directories, `*` executables, `@` links).
+    pub classify: bool,
+    /// Whether to highlight the parts of file names matched by include patterns.
+    pub highlight: bool,
+    /// Whether to show file size.
+    pub show_size: bool,
+    /// Whether to display size in human-readable format.
+    pub human_readable: bool,
+    /// Whether human-readable sizes use decimal units (1 kB = 1000 B).
+    pub si_units: bool,
+    /// Digit grouping for sizes.
+    pub number_format: NumberFormat,
+    /// Whether to show last modification date.
+    pub show_date: bool,
+    /// Whether to show dates relative to now (e.g. `3 days ago`).
+    pub relative_dates: bool,
+    /// strftime pattern for dates (`None` means `%Y-%m-%d %H:%M:%S`).
+    pub date_format: Option<String>,
+    /// Whether to show creation date.
+    pub show_created: bool,
+    /// Whether to show last access date.
+    pub show_accessed: bool,
+    /// Whether to show cumulative directory size.
+    pub show_disk_usage: bool,
+    /// Whether to follow the cumulative size with the share of the parent's.
+    pub show_du_percent: bool,
+    /// Whether to follow the cumulative size with its change since the
+    /// previous `--du-delta` run of the root.
+    pub show_du_delta: bool,
+    /// Whether to mark cloud-only placeholders with `[cloud]`.
+    pub mark_cloud: bool,
+    /// Whether to mark zero-byte files with `[empty]`.
+    pub mark_empty_files: bool,
+    /// Whether to hide tree connectors (indent only).
+    pub no_indent: bool,
+    /// Decoration of the tree connectors by level.
+    pub level_guides: LevelGuides,
+    /// Whether to list full paths one per line instead of a tree.
+    pub flat: bool,
+    /// Whether to keep the fixed separator before metadata instead of aligning it.
+    pub no_align: bool,
+    /// Whether to shorten long names so that lines fit in the console.
+    pub truncate: bool,
+    /// Console width lines are fitted into; set at startup when `truncate`
+    /// applies to a console, `None` otherwise.
+    pub line_width: Option<usize>,
+    /// Key used to order entries of the same kind.
+    pub sort_key: SortKey,
+    /// Whether to reverse sort order.
+    pub reverse_sort: bool,
+    /// Whether to sort files and directories together instead of listing
+    /// files first.
+    pub interleave: bool,
+    /// How files are divided into headed sections.
+    pub group_by: GroupBy,
+    /// Whether to lay the files of a directory out in columns.
+    pub columns: bool,
+    /// Console width `--columns` lays files out in; set at startup when
+    /// the output goes to a console, `None` otherwise.
+    pub columns_width: Option<usize>,
+    /// Whether to show summary report at the end.
+    pub show_report: bool,
+    /// Whether to print only the totals instead of the tree.
+    pub count_only: bool,
+    /// Whether to append the most common extensions by count and size.
+    pub report_extensions: bool,
+    /// Whether to append the entry counts and file sizes per level.
+    pub depth_histogram: bool,
+    /// Whether to list the directories that could not be read.
+    pub report_errors: bool,
+    /// Whether to hide Windows native banner.
+    pub no_win_banner: bool,
+    /// Language of the statistics report.
+    pub language: Language,
+}
+
+/// Output options.
+///
+/// Configuration controlling result output destination and format.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::OutputOptions;
+///
+/// let opts = OutputOptions::default();
+/// assert!(opts.output_path.is_none());
+/// assert!(!opts.silent);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct OutputOptions {
+    /// Output file path (`None` means terminal output only).
+    pub output_path: Option<PathBuf>,
+    /// Output format (inferred from `output_path` extension, or default `Txt`).
+    pub format: OutputFormat,
+    /// Whether to suppress terminal output.
+    pub silent: bool,
+    /// Maximum size of the rendered tree in bytes (`None` means unlimited).
+    pub max_output_size: Option<u64>,
+    /// Destination of the machine-readable scan summary (`None` means not written).
+    pub stats_json: Option<StatsTarget>,
+    /// Encoding of the output file and of redirected stdout.
+    pub encoding: OutputEncoding,
+    /// Whether to start the output with a byte order mark.
+    pub bom: bool,
+    /// Whether to append to the output file instead of replacing it.
+    pub append: bool,
+    /// Whether to refuse to replace an existing output file.
+    pub no_clobber: bool,
+    /// Whether to gzip the output file (`--compress`, or an output path
+    /// ending in `.gz`).
+    pub compress: bool,
+    /// Directory receiving a timestamped output file per run
+    /// (`--report-dir`); `output_path` then only gives the file name.
+    pub report_dir: Option<PathBuf>,
+    /// Number of reports of the same name kept in `report_dir` (`--rotate`,
+    /// `None` means all).
+    pub rotate: Option<usize>,
+    /// Whether to record progress next to the output file, so that an
+    /// interrupted run can be resumed (`--checkpoint`).
+    pub checkpoint: bool,
+    /// Whether to add stable `id` and `parent_id` fields to the entries of
+    /// JSON and YAML output (`--node-ids`).
+    pub node_ids: bool,
+    /// Whether to remove whitespace at the end of output lines.
+    pub trim_trailing_whitespace: bool,
+    /// Line ending of stdout and the output file.
+    pub line_ending: LineEnding,
+    /// When stdout is piped through a pager.
+    pub pager: PagerMode,
+    /// Lines written to stdout before the pager starts (`None` means no
+    /// pager); resolved at startup from `pager` and the console height.
+    pub page_after: Option<usize>,
+    /// Detail of the diagnostic log (0 = off, 1 = info, 2 or more = debug).
+    pub verbosity: u8,
+    /// File the diagnostic log is written to instead of stderr.
+    pub log_file: Option<PathBuf>,
+    /// Whether to suppress warnings on stderr (`--quiet-warnings`).
+    pub quiet_warnings: bool,
+}
+
+// ============================================================================
+// Main Configuration Structure
+// ============================================================================
+
+/// Full configuration.
+///
+/// The single source of truth for user intent. Generated by CLI parsing,
+/// and all subsequent modules (scan, match, render, output) depend on this configuration.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::config::Config;
+///
+/// let config = Config::default();
+/// assert_eq!(config.root_path, PathBuf::from("."));
+/// assert!(!config.scan.show_files);
+/// assert!(!config.batch_mode);
+/// ```
+///
+/// ```
+/// use std::path::PathBuf;
+/// use std::num::NonZeroUsize;
+/// use treepp::config::{Config, OutputFormat};
+///
+/// let mut config = Config::default();
+/// config.scan.show_files = true;
+/// config.batch_mode = true;
+/// config.scan.thread_count = NonZeroUsize::new(16).unwrap();
+/// config.output.output_path = Some(PathBuf::from("tree.json"));
+///
+/// let validated = config.validate().expect("validation should pass");
+/// assert_eq!(validated.output.format, OutputFormat::Json);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Config {
+    /// Root path (starting directory).
+    pub root_path: PathBuf,
+    /// Whether the user explicitly specified a path.
+    pub path_explicitly_set: bool,
+    /// Whether to show help information.
+    #[serde(skip)]
+    pub show_help: bool,
+    /// Whether to show version information.
+    #[serde(skip)]
+    pub show_version: bool,
+    /// Whether to print this configuration instead of rendering a tree (`--print-config`).
+    #[serde(skip)]
+    pub print_config: bool,
+    /// Whether to print the JSON Schema of the structured output instead of
+    /// rendering a tree (`--print-schema`).
+    #[serde(skip)]
+    pub print_schema: bool,
+    /// Whether errors are written to stderr as a JSON object (`--json-errors`).
+    #[serde(skip)]
+    pub json_errors: bool,
+    /// Scope in which to add the Explorer context-menu entry (`--install-context-menu`).
+    #[serde(skip)]
+    pub install_menu: Option<MenuScope>,
+    /// Scope from which to remove the Explorer context-menu entry (`--uninstall-context-menu`).
+    #[serde(skip)]
+    pub uninstall_menu: Option<MenuScope>,
+    /// Whether to use batch mode (default `false`, uses streaming mode).
+    pub batch_mode: bool,
+    /// Path whose filtering should be explained instead of rendering a tree (`--explain`).
+    pub explain_target: Option<PathBuf>,
+    /// Whether to answer queries over a local HTTP API instead of rendering
+    /// a tree (`--serve`).
+    #[serde(skip)]
+    pub serve: bool,
+    /// Port the server listens on (`--port`, default `server::DEFAULT_PORT`).
+    #[serde(skip)]
+    pub serve_port: Option<u16>,
+    /// Named pipe the server listens on (`--pipe`); without `--port`, the
+    /// server then listens on the pipe only.
+    #[serde(skip)]
+    pub serve_pipe: Option<String>,
+    /// Scan options.
+    pub scan: ScanOptions,
+    /// Match options.
+    pub matching: MatchOptions,
+    /// Render options.
+    pub render: RenderOptions,
+    /// Output options.
+    pub output: OutputOptions,
+    /// Non-fatal problems found while parsing, printed before running.
+    #[serde(skip)]
+    pub warnings: Vec<ConfigWarning>,
+}
+
+impl Default for Config {
+    /// Creates a default configuration with current directory as root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::config::Config;
+    ///
+    /// let config = Config::default();
+    /// assert_eq!(config.root_path, PathBuf::from("."));
+    /// ```
+    fn default() -> Self {
+        Self {
+            root_path: PathBuf::from("."),
+            path_explicitly_set: false,
+            show_help: false,
+            show_version: false,
+            print_config: false,
+            print_schema: false,
+            json_errors: false,
+            install_menu: None,
+            uninstall_menu: None,
+            batch_mode: false,
+            explain_target: None,
+            serve: false,
+            serve_port: None,
+            serve_pipe: None,
+            scan: ScanOptions::default(),
+            matching: MatchOptions::default(),
+            render: RenderOptions::default(),
+            output: OutputOptions::default(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Creates a configuration with the specified root path.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_path` - The starting directory for tree traversal.
+    ///
+    /// # Returns
+    ///
+    /// A new `Config` with the specified root path and `path_explicitly_set` set to `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::config::Config;
+    ///
+    /// let config = Config::with_root(PathBuf::from("C:\\Users"));
+    /// assert_eq!(config.root_path, PathBuf::from("C:\\Users"));
+    /// assert!(config.path_explicitly_set);
+    /// ```
+    #[must_use]
+    pub fn with_root(root_path: PathBuf) -> Self {
+        Self {
+            root_path,
+            path_explicitly_set: true,
+            ..Self::default()
+        }
+    }
+
+    /// Validates the configuration and populates derived fields.
+    ///
+    /// Performs the following operations:
+    /// - Validates root path existence and canonicalizes it
+    /// - Resolves the `--relative-to` base and reads the user profile for `--redact`
+    /// - Infers output format from file extension
+    /// - Checks for option conflicts
+    /// - Applies implicit dependencies
+    ///
+    /// # Returns
+    ///
+    /// The validated and normalized `Config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError` if:
+    /// - Options have irreconcilable conflicts
+    /// - Root path does not exist or is not a directory
+    /// - Output path extension is unrecognized
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::config::{Config, OutputFormat};
+    ///
+    /// let mut config = Config::default();
+    /// config.batch_mode = true;
+    /// config.output.output_path = Some(PathBuf::from("result.json"));
+    ///
+    /// let validated = config.validate().unwrap();
+    /// assert_eq!(validated.output.format, OutputFormat::Json);
+    /// ```
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::config::{Config, ConfigError};
+    ///
+    /// let mut config = Config::default();
+    /// config.output.output_path = Some(PathBuf::from("result.xyz"));
+    ///
+    /// let err = config.validate().unwrap_err();
+    /// assert!(matches!(err, ConfigError::UnknownOutputFormat { .. }));
+    /// ```
+    pub fn validate(mut self) -> ConfigResult<Self> {
+        self.validate_and_canonicalize_root_path()?;
+        self.resolve_relative_base()?;
+        self.render.redaction = self
+            .render
+            .redaction
+            .take()
+            .map(Redactor::with_current_user);
+        self.resolve_report_path()?;
+        self.infer_output_format()?;
+        self.check_conflicts()?;
+        self.apply_implicit_dependencies();
+        Ok(self)
+    }
+
+    /// Determines whether this is an "info-only" mode (help or version).
+    ///
+    /// # Returns
+    ///
+    /// `true` if either `show_help` or `show_version` is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::Config;
+    ///
+    /// let mut config = Config::default();
+    /// assert!(!config.is_info_only());
+    ///
+    /// config.show_help = true;
+    /// assert!(config.is_info_only());
+    /// ```
+    #[must_use]
+    pub const fn is_info_only(&self) -> bool {
+        self.show_help || self.show_version
+    }
+
+    /// Determines whether names shown in a console need their control
+    /// characters replaced.
+    ///
+    /// A name such as `ESC]0;title BEL` would otherwise be interpreted by the
+    /// console instead of printed. Applies while names are written raw and
+    /// stdout is not silenced, unless `--raw-names` is given.
+    ///
+    /// # Returns
+    ///
+    /// `true` if names written to a console should be escaped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::{Config, EscapeMode};
+    ///
+    /// let mut config = Config::default();
+    /// assert!(config.sanitizes_console_names());
+    ///
+    /// config.render.escape = EscapeMode::C;
+    /// assert!(!config.sanitizes_console_names());
+    /// ```
+    #[must_use]
+    pub const fn sanitizes_console_names(&self) -> bool {
+        matches!(self.render.escape, EscapeMode::Raw)
+            && !self.render.raw_names
+            && !self.output.silent
+    }
+
+    /// Determines whether file size information is needed.
+    ///
+    /// Returns `true` when any of `show_size`, `human_readable`, `si_units`,
+    /// or `show_disk_usage` is enabled, entries are sorted by size,
+    /// `--count` totals the file sizes, or empty files are left out or
+    /// marked.
+    ///
+    /// # Returns
+    ///
+    /// `true` if size information is required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::Config;
+    ///
+    /// let mut config = Config::default();
+    /// assert!(!config.needs_size_info());
+    ///
+    /// config.render.show_size = true;
+    /// assert!(config.needs_size_info());
+    /// ```
+    #[must_use]
+    pub const fn needs_size_info(&self) -> bool {
+        self.render.show_size
+            || self.render.human_readable
+            || self.render.si_units
+            || self.render.show_disk_usage
+            || self.render.count_only
+            || matches!(self.render.sort_key, SortKey::Size | SortKey::Share)
+            || self.matching.prune_empty_files
+            || self.render.mark_empty_files
+    }
+
+    /// Determines whether time information is needed.
+    ///
+    /// Returns `true` when any date is shown, `relative_dates` or a
+    /// `date_format` is set, or entries are sorted by a date.
+    ///
+    /// # Returns
+    ///
+    /// `true` if time information is required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::Config;
+    ///
+    /// let mut config = Config::default();
+    /// assert!(!config.needs_time_info());
+    ///
+    /// config.render.show_date = true;
+    /// assert!(config.needs_time_info());
+    /// ```
+    #[must_use]
+    pub const fn needs_time_info(&self) -> bool {
+        self.render.show_date
+            || self.render.show_created
+            || self.render.show_accessed
+            || self.render.relative_dates
+            || self.render.date_format.is_some()
+            || matches!(
+                self.render.sort_key,
+                SortKey::Modified | SortKey::Created | SortKey::Accessed
+            )
+    }
+
+    /// Determines whether per-extension statistics are collected.
+    ///
+    /// Returns `true` when `--report-extensions` or `--stats-json` is set.
+    ///
+    /// # Returns
+    ///
+    /// `true` if extension statistics are required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::Config;
+    ///
+    /// let mut config = Config::default();
+    /// assert!(!config.needs_extension_stats());
+    ///
+    /// config.render.report_extensions = true;
+    /// assert!(config.needs_extension_stats());
+    /// ```
+    #[must_use]
+    pub const fn needs_extension_stats(&self) -> bool {
+        self.render.report_extensions || self.output.stats_json.is_some()
+    }
+
+    /// Determines whether statistics are collected while scanning.
+    ///
+    /// Returns `true` when extension statistics are needed or
+    /// `--depth-histogram` is set.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a `StatsCollector` must record the scanned entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::Config;
+    ///
+    /// let mut config = Config::default();
+    /// assert!(!config.needs_collected_stats());
+    ///
+    /// config.render.depth_histogram = true;
+    /// assert!(config.needs_collected_stats());
+    /// assert!(!config.needs_extension_stats());
+    /// ```
+    #[must_use]
+    pub const fn needs_collected_stats(&self) -> bool {
+        self.needs_extension_stats() || self.render.depth_histogram
+    }
+
+    /// Determines whether per-entry filesystem metadata is needed.
+    ///
+    /// Returns `true` when sizes or dates are needed (including the
+    /// per-extension and per-level sizes), or when hidden entries are
+    /// filtered out or cloud placeholders are marked or skipped, since these
+    /// attributes are part of the metadata.
+    /// Otherwise the scanner takes the entry type from the directory listing
+    /// and skips the per-entry metadata call.
+    ///
+    /// # Returns
+    ///
+    /// `true` if entry metadata must be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::config::Config;
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("."));
+    /// assert!(config.needs_entry_metadata());
+    ///
+    /// config.scan.show_hidden = true;
+    /// assert!(!config.needs_entry_metadata());
+    ///
+    /// config.render.show_date = true;
+    /// assert!(config.needs_entry_metadata());
+    /// ```
+    #[must_use]
+    pub const fn needs_entry_metadata(&self) -> bool {
+        self.needs_size_info()
+            || self.needs_time_info()
+            || self.needs_collected_stats()
+            || !self.scan.show_hidden
+            || self.render.mark_cloud
+            || self.scan.skip_cloud_only
+    }
+
+    /// Determines whether the batch pipeline will be used.
+    ///
+    /// Returns `true` when batch mode is explicitly enabled, when the tree
+    /// is built from a path list (`--paths-from`), which is inherently batch,
+    /// when `--leaf-depth`, `--group-by`, `--columns` or `--interleave`
+    /// needs the full tree before rendering, or when `--serve` keeps the
+    /// tree for queries.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the batch pipeline is required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::{Config, PathListSource};
+    ///
+    /// let mut config = Config::default();
+    /// assert!(!config.uses_batch_pipeline());
+    ///
+    /// config.scan.paths_from = Some(PathListSource::Stdin);
+    /// assert!(config.uses_batch_pipeline());
+    /// ```
+    #[must_use]
+    pub const fn uses_batch_pipeline(&self) -> bool {
+        self.batch_mode
+            || self.scan.paths_from.is_some()
+            || self.scan.leaf_depth.is_some()
+            || !matches!(self.render.group_by, GroupBy::None)
+            || self.render.columns
+            || self.render.interleave
+            || self.serve
+    }
+
+    fn validate_and_canonicalize_root_path(&mut self) -> ConfigResult<()> {
+        self.root_path = winpath::normalize_root(&self.root_path);
+
+        if !self.root_path.exists() {
+            return Err(ConfigError::InvalidPath {
+                path: self.root_path.clone(),
+                reason: missing_root_reason(&self.root_path),
+            });
+        }
+
+        if !self.root_path.is_dir() {
+            return Err(ConfigError::InvalidPath {
+                path: self.root_path.clone(),
+                reason: "Path is not a directory".to_string(),
+            });
+        }
+
+        match dunce::canonicalize(&self.root_path) {
+            Ok(canonical) => {
+                self.root_path = winpath::to_display(&canonical).into_owned();
+                Ok(())
+            }
+            Err(e) => Err(ConfigError::InvalidPath {
+                path: self.root_path.clone(),
+                reason: format!("Failed to canonicalize path: {}", e),
+            }),
+        }
+    }
+
+    fn resolve_relative_base(&mut self) -> ConfigResult<()> {
+        let Some(ref base) = self.render.relative_to else {
+            return Ok(());
+        };
+        let base = self.root_path.join(winpath::normalize_root(base));
+        if !base.is_dir() {
+            return Err(ConfigError::InvalidPath {
+                reason: if base.exists() {
+                    "--relative-to must name a directory".to_string()
+                } else {
+                    missing_root_reason(&base)
+                },
+                path: base,
+            });
+        }
+        let canonical = dunce::canonicalize(&base).map_err(|e| ConfigError::InvalidPath {
+            path: base.clone(),
+            reason: format!("Failed to canonicalize path: {}", e),
+        })?;
+        self.render.relative_to = Some(winpath::to_display(&canonical).into_owned());
+        Ok(())
+    }
+
+    /// Replaces the output path with the timestamped report path in
+    /// `--report-dir`.
+    fn resolve_report_path(&mut self) -> ConfigResult<()> {
+        let Some(ref dir) = self.output.report_dir else {
+            return Ok(());
+        };
+        let name = match self.output.output_path {
+            None => report::DEFAULT_REPORT_NAME.to_string(),
+            Some(ref path) => match (path.parent(), path.file_name()) {
+                (Some(parent), Some(name)) if parent.as_os_str().is_empty() => {
+                    name.to_string_lossy().into_owned()
+                }
+                _ => {
+                    return Err(ConfigError::ConflictingOptions {
+                        opt_a: "--report-dir".to_string(),
+                        opt_b: "--output".to_string(),
+                        reason: "With --report-dir, --output only names the reports.".to_string(),
+                    });
+                }
+            },
+        };
+        let time = chrono::Local::now().naive_local();
+        self.output.output_path = Some(report::report_path(dir, &name, time));
+        Ok(())
+    }
+
+    fn infer_output_format(&mut self) -> ConfigResult<()> {
+        if let Some(ref path) = self.output.output_path {
+            if is_gzip_path(path) {
+                self.output.compress = true;
+            }
+            if let Some(format) = OutputFormat::from_extension(path) {
+                self.output.format = format;
+            } else {
+                return Err(ConfigError::UnknownOutputFormat { path: path.clone() });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_conflicts(&self) -> ConfigResult<()> {
+        if self.output.silent && self.output.output_path.is_none() {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--silent".to_string(),
+                opt_b: "(no --output)".to_string(),
+                reason:
+                    "Silent mode requires an output file; otherwise no output will be produced."
+                        .to_string(),
+            });
+        }
+
+        if self.render.show_disk_usage && !self.uses_batch_pipeline() {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--disk-usage".to_string(),
+                opt_b: "(no --batch)".to_string(),
+                reason: "Disk usage calculation requires batch mode (--batch).".to_string(),
+            });
+        }
+
+        if self.scan.backend != ScanBackend::Auto {
+            if !self.uses_batch_pipeline() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--backend".to_string(),
+                    opt_b: "(no --batch)".to_string(),
+                    reason: "The streaming pipeline has a traversal of its own.".to_string(),
+                });
+            }
+            if self.scan.paths_from.is_some() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--backend".to_string(),
+                    opt_b: "--paths-from".to_string(),
+                    reason: "No directories are read when the tree is built from a path list."
+                        .to_string(),
+                });
+            }
+        }
+
+        if self.render.show_du_percent && !self.render.show_disk_usage {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--du-percent".to_string(),
+                opt_b: "(no --disk-usage)".to_string(),
+                reason: "Percentages are computed from cumulative directory sizes (--disk-usage)."
+                    .to_string(),
+            });
+        }
+
+        if self.render.show_du_delta && !self.render.show_disk_usage {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--du-delta".to_string(),
+                opt_b: "(no --disk-usage)".to_string(),
+                reason: "Changes are computed from cumulative directory sizes (--disk-usage)."
+                    .to_string(),
+            });
+        }
+
+        if self.render.show_du_delta && self.scan.paths_from.is_some() {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--du-delta".to_string(),
+                opt_b: "--paths-from".to_string(),
+                reason: "Size snapshots are taken of scanned directories.".to_string(),
+            });
+        }
+
+        if self.render.sort_key == SortKey::Share && !self.render.show_disk_usage {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--sort share".to_string(),
+                opt_b: "(no --disk-usage)".to_string(),
+                reason: "Directories are ordered by cumulative size (--disk-usage).".to_string(),
+            });
+        }
+
+        if self.scan.du_threshold.is_some() && !self.render.show_disk_usage {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--du-threshold".to_string(),
+                opt_b: "(no --disk-usage)".to_string(),
+                reason: "The size threshold applies to cumulative directory sizes (--disk-usage)."
+                    .to_string(),
+            });
+        }
+
+        if self.output.output_path.is_some() {
+            let format = &self.output.format;
+            let requires_batch = matches!(
+                format,
+                OutputFormat::Json | OutputFormat::Yaml | OutputFormat::Toml
+            );
+            if requires_batch && !self.uses_batch_pipeline() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: format!("--output (format: {:?})", format),
+                    opt_b: "(no --batch)".to_string(),
+                    reason:
+                        "Structured output formats (JSON/YAML/TOML) require batch mode (--batch)."
+                            .to_string(),
+                });
+            }
+        }
+
+        if self.scan.show_filtered {
+            if self.scan.paths_from.is_some() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--show-filtered".to_string(),
+                    opt_b: "--paths-from".to_string(),
+                    reason: "A path list is not filtered while scanning, so there is nothing to annotate."
+                        .to_string(),
+                });
+            }
+            if self.output.output_path.is_some() && self.output.format != OutputFormat::Txt {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--show-filtered".to_string(),
+                    opt_b: format!("--output (format: {:?})", self.output.format),
+                    reason: "Filter annotations are only available in text output.".to_string(),
+                });
+            }
+        }
+
+        if self.output.node_ids {
+            let opt_b = match self.output.format {
+                _ if self.output.output_path.is_none() => {
+                    Some("(no structured --output)".to_string())
+                }
+                OutputFormat::Json | OutputFormat::Yaml => None,
+                format => Some(format!("--output (format: {:?})", format)),
+            };
+            if let Some(opt_b) = opt_b {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--node-ids".to_string(),
+                    opt_b,
+                    reason: "Node ids are only written to JSON and YAML output.".to_string(),
+                });
+            }
+        }
+
+        if self.output.output_path.is_some() && self.output.format != OutputFormat::Txt {
+            let format = format!("--output (format: {:?})", self.output.format);
+            if self.scan.max_entries.is_some() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--max-entries".to_string(),
+                    opt_b: format,
+                    reason: "Truncation markers are only available in text output.".to_string(),
+                });
+            }
+            if self.scan.du_threshold.is_some() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--du-threshold".to_string(),
+                    opt_b: format,
+                    reason: "Markers for hidden directories are only available in text output."
+                        .to_string(),
+                });
+            }
+            if self.render.mark_cloud {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--mark-cloud".to_string(),
+                    opt_b: format,
+                    reason: "Cloud placeholder markers are only available in text output."
+                        .to_string(),
+                });
+            }
+            if self.render.mark_empty_files {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--mark-empty-files".to_string(),
+                    opt_b: format,
+                    reason: "Empty file markers are only available in text output.".to_string(),
+                });
+            }
+            if self.scan.file_limit.is_some() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--filelimit".to_string(),
+                    opt_b: format,
+                    reason:
+                        "Entry counts of collapsed directories are only available in text output."
+                            .to_string(),
+                });
+            }
+            if self.output.max_output_size.is_some() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--max-output-size".to_string(),
+                    opt_b: format,
+                    reason:
+                        "Structured output cannot be cut off without producing an invalid document."
+                            .to_string(),
+                });
+            }
+            if self.scan.leaf_depth.is_some() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--leaf-depth".to_string(),
+                    opt_b: format,
+                    reason: "Collapsed upper levels are only available in text output.".to_string(),
+                });
+            }
+            if self.render.flat {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--flat".to_string(),
+                    opt_b: format,
+                    reason: "A flat path list is only available in text output.".to_string(),
+                });
+            }
+            if self.render.group_by != GroupBy::None {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--group-by".to_string(),
+                    opt_b: format,
+                    reason: "Section headings are only available in text output.".to_string(),
+                });
+            }
+            if self.render.columns {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--columns".to_string(),
+                    opt_b: format,
+                    reason: "File columns are only available in text output.".to_string(),
+                });
+            }
+            if self.render.interleave {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--interleave".to_string(),
+                    opt_b: format,
+                    reason: "Structured output keeps files and directories in separate lists."
+                        .to_string(),
+                });
+            }
+            if self.output.append {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--append".to_string(),
+                    opt_b: format,
+                    reason: "Appending a second document to a structured file makes it invalid."
+                        .to_string(),
+                });
+            }
+        }
+
+        if self.serve_port.is_some() && !self.serve {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--port".to_string(),
+                opt_b: "(no --serve)".to_string(),
+                reason: "The port is where --serve listens.".to_string(),
+            });
+        }
+
+        if self.serve_pipe.is_some() && !self.serve {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--pipe".to_string(),
+                opt_b: "(no --serve)".to_string(),
+                reason: "The pipe is where --serve listens.".to_string(),
+            });
+        }
+
+        if self.serve {
+            let opt_b = if self.output.report_dir.is_some() {
+                Some((
+                    "--report-dir",
+                    "Query results are sent to the client, not to a file.",
+                ))
+            } else if self.output.output_path.is_some() {
+                Some((
+                    "--output",
+                    "Query results are sent to the client, not to a file.",
+                ))
+            } else if self.scan.paths_from.is_some() {
+                Some((
+                    "--paths-from",
+                    "The server refreshes its tree by scanning the directory.",
+                ))
+            } else if self.explain_target.is_some() {
+                Some((
+                    "--explain",
+                    "An explanation is printed instead of serving the tree.",
+                ))
+            } else {
+                None
+            };
+            if let Some((opt_b, reason)) = opt_b {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--serve".to_string(),
+                    opt_b: opt_b.to_string(),
+                    reason: reason.to_string(),
+                });
+            }
+        }
+
+        if self.install_menu.is_some() && self.uninstall_menu.is_some() {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--install-context-menu".to_string(),
+                opt_b: "--uninstall-context-menu".to_string(),
+                reason: "The context-menu entry can be either added or removed.".to_string(),
+            });
+        }
+
+        if self.explain_target.is_some() && self.scan.paths_from.is_some() {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--explain".to_string(),
+                opt_b: "--paths-from".to_string(),
+                reason:
+                    "Explanations describe filesystem filtering and do not apply to a path list."
+                        .to_string(),
+            });
+        }
+
+        if self.scan.git_tracked && self.scan.paths_from.is_some() {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--git-tracked".to_string(),
+                opt_b: "--paths-from".to_string(),
+                reason: "A path list is not read from the filesystem, so git tracking cannot be applied."
+                    .to_string(),
+            });
+        }
+
+        if (self.output.append || self.output.no_clobber) && self.output.output_path.is_none() {
+            let opt_a = if self.output.append {
+                "--append"
+            } else {
+                "--no-clobber"
+            };
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: opt_a.to_string(),
+                opt_b: "(no --output)".to_string(),
+                reason: "There is no output file to protect or append to.".to_string(),
+            });
+        }
+
+        if self.output.rotate.is_some() && self.output.report_dir.is_none() {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--rotate".to_string(),
+                opt_b: "(no --report-dir)".to_string(),
+                reason: "Only the reports in --report-dir are rotated.".to_string(),
+            });
+        }
+
+        if self.output.report_dir.is_some() {
+            let opt_b = if self.output.append {
+                Some(("--append", "Each run writes a report of its own."))
+            } else if self.output.checkpoint {
+                Some((
+                    "--checkpoint",
+                    "Each run writes to a new file, so none can be resumed.",
+                ))
+            } else {
+                None
+            };
+            if let Some((opt_b, reason)) = opt_b {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--report-dir".to_string(),
+                    opt_b: opt_b.to_string(),
+                    reason: reason.to_string(),
+                });
+            }
+        }
+
+        if self.output.compress && self.output.output_path.is_none() {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--compress".to_string(),
+                opt_b: "(no --output)".to_string(),
+                reason: "Only the output file is compressed; the console always gets text."
+                    .to_string(),
+            });
+        }
+
+        if self.output.checkpoint {
+            if self.output.output_path.is_none() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--checkpoint".to_string(),
+                    opt_b: "(no --output)".to_string(),
+                    reason: "Progress is recorded next to the output file.".to_string(),
+                });
+            }
+            let conflict = if self.uses_batch_pipeline() {
+                Some((
+                    "--batch",
+                    "Only the streaming mode writes the tree while scanning.",
+                ))
+            } else if self.output.append {
+                Some(("--append", "A resumed run continues the file it started."))
+            } else if self.output.compress {
+                Some((
+                    "--compress",
+                    "A gzip stream cannot be cut back to the last checkpoint.",
+                ))
+            } else if self.render.count_only {
+                Some(("--count", "Nothing is written before the scan ends."))
+            } else if self.matching.prune_empty {
+                Some((
+                    "--prune",
+                    "Pruning holds directories back until their content is known.",
+                ))
+            } else if self.output.max_output_size.is_some() {
+                Some((
+                    "--max-output-size",
+                    "The size limit only counts what one run writes.",
+                ))
+            } else {
+                None
+            };
+            if let Some((opt_b, reason)) = conflict {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--checkpoint".to_string(),
+                    opt_b: opt_b.to_string(),
+                    reason: reason.to_string(),
+                });
+            }
+        }
+
+        if self.output.append && self.output.no_clobber {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--append".to_string(),
+                opt_b: "--no-clobber".to_string(),
+                reason: "Appending always writes to an existing file.".to_string(),
+            });
+        }
+
+        if self.render.group_by != GroupBy::None && self.render.flat {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--group-by".to_string(),
+                opt_b: "--flat".to_string(),
+                reason: "A flat path list has no directory levels to divide into sections."
+                    .to_string(),
+            });
+        }
+
+        if self.render.relative_dates && self.render.date_format.is_some() {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--relative-dates".to_string(),
+                opt_b: "--date-format".to_string(),
+                reason: "Relative dates are not formatted with a pattern.".to_string(),
+            });
+        }
+
+        if self.render.interleave {
+            let opt_b = if self.render.group_by != GroupBy::None {
+                Some("--group-by")
+            } else if self.render.columns {
+                Some("--columns")
+            } else {
+                None
+            };
+            if let Some(opt_b) = opt_b {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--interleave".to_string(),
+                    opt_b: opt_b.to_string(),
+                    reason: "Files are listed apart from directories there.".to_string(),
+                });
+            }
+        }
+
+        if self.render.columns {
+            let metadata = [
+                (self.render.show_size, "--size"),
+                (self.render.show_date, "--date"),
+                (self.render.show_created, "--created"),
+                (self.render.show_accessed, "--accessed"),
+            ];
+            let conflict = if !self.scan.show_files {
+                Some(("(no --files)", "Only files are laid out in columns."))
+            } else if self.render.flat {
+                Some(("--flat", "A flat path list has one path per line."))
+            } else {
+                metadata
+                    .iter()
+                    .find(|(shown, _)| *shown)
+                    .map(|&(_, option)| {
+                        (
+                            option,
+                            "Columns hold file names only, without their metadata.",
+                        )
+                    })
+            };
+            if let Some((opt_b, reason)) = conflict {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--columns".to_string(),
+                    opt_b: opt_b.to_string(),
+                    reason: reason.to_string(),
+                });
+            }
+        }
+
+        if self.render.level_guides != LevelGuides::None {
+            let opt_b = if self.render.flat {
+                Some("--flat")
+            } else if self.render.no_indent {
+                Some("--no-indent")
+            } else {
+                None
+            };
+            if let Some(opt_b) = opt_b {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--level-guides".to_string(),
+                    opt_b: opt_b.to_string(),
+                    reason: "Level guides decorate tree connectors, which are not drawn."
+                        .to_string(),
+                });
+            }
+            if self.output.output_path.is_some() && self.output.format != OutputFormat::Txt {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--level-guides".to_string(),
+                    opt_b: format!("--output (format: {:?})", self.output.format),
+                    reason: "Level guides are only available in text output.".to_string(),
+                });
+            }
+            if self.render.level_guides == LevelGuides::Color && self.output.output_path.is_some() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--level-guides color".to_string(),
+                    opt_b: "--output".to_string(),
+                    reason: "Colors use terminal escape sequences, which do not belong in a file."
+                        .to_string(),
+                });
+            }
+        }
+
+        if self.render.highlight {
+            if self.matching.include_patterns.is_empty() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--highlight".to_string(),
+                    opt_b: "(no --include)".to_string(),
+                    reason: "Only matches of include patterns are highlighted.".to_string(),
+                });
+            }
+            if self.output.output_path.is_some() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--highlight".to_string(),
+                    opt_b: "--output".to_string(),
+                    reason: "Highlighting uses terminal escape sequences, which do not belong in a file."
+                        .to_string(),
+                });
+            }
+        }
+
+        if self.render.count_only {
+            if self.uses_batch_pipeline() {
+                let opt_b = if self.scan.paths_from.is_some() {
+                    "--paths-from"
+                } else if self.scan.leaf_depth.is_some() {
+                    "--leaf-depth"
+                } else if self.render.group_by != GroupBy::None {
+                    "--group-by"
+                } else if self.serve {
+                    "--serve"
+                } else {
+                    "--batch"
+                };
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--count".to_string(),
+                    opt_b: opt_b.to_string(),
+                    reason: "Totals are counted during a streaming scan of the filesystem."
+                        .to_string(),
+                });
+            }
+            if self.explain_target.is_some() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--count".to_string(),
+                    opt_b: "--explain".to_string(),
+                    reason: "An explanation is printed instead of the tree, so there is nothing to count."
+                        .to_string(),
+                });
+            }
+        }
+
+        if self.output.bom && self.output.encoding == OutputEncoding::Gbk {
+            return Err(ConfigError::ConflictingOptions {
+                opt_a: "--bom".to_string(),
+                opt_b: "--output-encoding gbk".to_string(),
+                reason: "GBK has no byte order mark.".to_string(),
+            });
+        }
+
+        if self.scan.cache == CacheMode::Enabled {
+            if self.scan.paths_from.is_some() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--cache".to_string(),
+                    opt_b: "--paths-from".to_string(),
+                    reason:
+                        "A path list is not read from the filesystem, so there is nothing to cache."
+                            .to_string(),
+                });
+            }
+            if self.needs_size_info() || self.needs_time_info() {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--cache".to_string(),
+                    opt_b: "(size or date display)".to_string(),
+                    reason: "Cached listings do not record sizes or dates, which change without touching the directory."
+                        .to_string(),
+                });
+            }
+            if self.needs_extension_stats() {
+                let opt_b = if self.render.report_extensions {
+                    "--report-extensions"
+                } else {
+                    "--stats-json"
+                };
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--cache".to_string(),
+                    opt_b: opt_b.to_string(),
+                    reason: "Cached listings do not record the file sizes summed per extension."
+                        .to_string(),
+                });
+            }
+            if self.render.depth_histogram {
+                return Err(ConfigError::ConflictingOptions {
+                    opt_a: "--cache".to_string(),
+                    opt_b: "--depth-histogram".to_string(),
+                    reason: "Cached listings do not record the file sizes summed per level."
+                        .to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_implicit_dependencies(&mut self) {
+        if self.scan.paths_from.is_some()
+            || self.scan.leaf_depth.is_some()
+            || self.render.group_by != GroupBy::None
+            || self.render.columns
+            || self.render.interleave
+            || self.serve
+        {
+            self.batch_mode = true;
+        }
+        if self.render.count_only {
+            self.scan.show_files = true;
+        }
+        if self.output.log_file.is_some() && self.output.verbosity == 0 {
+            self.output.verbosity = 1;
+        }
+        if self.render.relative_to.is_some() {
+            self.render.path_mode = PathMode::Full;
+        }
+        if self.render.flat {
+            self.render.path_mode = PathMode::Full;
+            self.render.no_indent = true;
+            self.render.no_win_banner = true;
+        }
+        if self.render.si_units {
+            self.render.human_readable = true;
+        }
+        if (self.render.relative_dates || self.render.date_format.is_some())
+            && !self.render.show_created
+            && !self.render.show_accessed
+        {
+            self.render.show_date = true;
+        }
+        if self.render.human_readable {
+            self.render.show_size = true;
+        }
+        if self.render.show_disk_usage {
+            self.render.show_size = true;
+        }
+    }
+}
+
+// ============================================================================
+// Root Path
+// ============================================================================
+
+/// Explains why a root path does not exist.
+///
+/// Names the deepest ancestor that does exist, which usually points at the
+/// mistyped component, or the missing drive if there is none.
+fn missing_root_reason(path: &Path) -> String {
+    let nearest = path
+        .ancestors()
+        .skip(1)
+        .find(|dir| !dir.as_os_str().is_empty() && dir.is_dir());
+    match (nearest, winpath::drive_letter(path)) {
+        (Some(dir), _) => format!(
+            "Path does not exist; the nearest existing directory is {}",
+            winpath::to_display(dir).display()
+        ),
+        (None, Some(drive)) => format!("Path does not exist; drive {}: is not available", drive),
+        (None, None) => "Path does not exist".to_string(),
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    mod output_format_tests {
+        use super::*;
+
+        #[test]
+        fn from_extension_recognizes_txt() {
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("file.txt")),
+                Some(OutputFormat::Txt)
+            );
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("file.TXT")),
+                Some(OutputFormat::Txt)
+            );
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("FILE.Txt")),
+                Some(OutputFormat::Txt)
+            );
+        }
+
+        #[test]
+        fn from_extension_recognizes_json() {
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("file.json")),
+                Some(OutputFormat::Json)
+            );
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("file.JSON")),
+                Some(OutputFormat::Json)
+            );
+        }
+
+        #[test]
+        fn from_extension_recognizes_yaml_variants() {
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("file.yml")),
+                Some(OutputFormat::Yaml)
+            );
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("file.yaml")),
+                Some(OutputFormat::Yaml)
+            );
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("file.YAML")),
+                Some(OutputFormat::Yaml)
+            );
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("file.YML")),
+                Some(OutputFormat::Yaml)
+            );
+        }
+
+        #[test]
+        fn from_extension_recognizes_toml() {
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("file.toml")),
+                Some(OutputFormat::Toml)
+            );
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("file.TOML")),
+                Some(OutputFormat::Toml)
+            );
+        }
+
+        #[test]
+        fn from_extension_returns_none_for_unknown() {
+            assert_eq!(OutputFormat::from_extension(Path::new("file.xyz")), None);
+            assert_eq!(OutputFormat::from_extension(Path::new("file")), None);
+            assert_eq!(OutputFormat::from_extension(Path::new("")), None);
+            assert_eq!(OutputFormat::from_extension(Path::new("file.md")), None);
+            assert_eq!(OutputFormat::from_extension(Path::new("file.rs")), None);
+            assert_eq!(OutputFormat::from_extension(Path::new(".gitignore")), None);
+        }
+
+        #[test]
+        fn from_extension_skips_gzip_suffix() {
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("tree.json.gz")),
+                Some(OutputFormat::Json)
+            );
+            assert_eq!(
+                OutputFormat::from_extension(Path::new("tree.TXT.GZ")),
+                Some(OutputFormat::Txt)
+            );
+            assert_eq!(OutputFormat::from_extension(Path::new("tree.gz")), None);
+            assert!(is_gzip_path(Path::new("C:\\out\\tree.txt.Gz")));
+            assert!(!is_gzip_path(Path::new("tree.txt")));
+        }
+
+        #[test]
+        fn extension_returns_correct_string() {
+            assert_eq!(OutputFormat::Txt.extension(), "txt");
+            assert_eq!(OutputFormat::Json.extension(), "json");
+            assert_eq!(OutputFormat::Yaml.extension(), "yml");
+            assert_eq!(OutputFormat::Toml.extension(), "toml");
+        }
+
+        #[test]
+        fn default_is_txt() {
+            assert_eq!(OutputFormat::default(), OutputFormat::Txt);
+        }
+
+        #[test]
+        fn formats_are_distinct() {
+            let formats = [
+                OutputFormat::Txt,
+                OutputFormat::Json,
+                OutputFormat::Yaml,
+                OutputFormat::Toml,
+            ];
+            for (i, a) in formats.iter().enumerate() {
+                for (j, b) in formats.iter().enumerate() {
+                    if i == j {
+                        assert_eq!(a, b);
+                    } else {
+                        assert_ne!(a, b);
+                    }
+                }
+            }
+        }
+    }
+
+    mod charset_mode_tests {
+        use super::*;
+
+        #[test]
+        fn unicode_returns_unicode_symbols() {
+            let mode = CharsetMode::Unicode;
+            assert_eq!(mode.branch(), "├─");
+            assert_eq!(mode.last_branch(), "└─");
+            assert_eq!(mode.vertical(), "│  ");
+            assert_eq!(mode.indent(), "   ");
+        }
+
+        #[test]
+        fn ascii_returns_ascii_symbols() {
+            let mode = CharsetMode::Ascii;
+            assert_eq!(mode.branch(), "+---");
+            assert_eq!(mode.last_branch(), "\\---");
+            assert_eq!(mode.vertical(), "|   ");
+            assert_eq!(mode.indent(), "    ");
+        }
+
+        #[test]
+        fn default_is_auto() {
+            assert_eq!(CharsetMode::default(), CharsetMode::Auto);
+            assert_eq!(CharsetMode::Auto.branch(), CharsetMode::Unicode.branch());
+        }
+
+        #[test]
+        fn vertical_and_indent_have_matching_widths() {
+            let unicode = CharsetMode::Unicode;
+            assert_eq!(
+                unicode.vertical().chars().count(),
+                unicode.indent().chars().count()
+            );
+
+            let ascii = CharsetMode::Ascii;
+            assert_eq!(ascii.vertical().len(), ascii.indent().len());
+        }
+
+        #[test]
+        fn branch_and_last_branch_have_same_visual_width() {
+            let ascii = CharsetMode::Ascii;
+            assert_eq!(ascii.branch().len(), ascii.last_branch().len());
+        }
     }
 
-    fn validate_and_canonicalize_root_path(&mut self) -> ConfigResult<()> {
-        if !self.root_path.exists() {
-            return Err(ConfigError::InvalidPath {
-                path: self.root_path.clone(),
-                reason: "Path does not exist".to_string(),
-            });
+    mod path_mode_tests {
+        use super::*;
+
+        #[test]
+        fn default_is_relative() {
+            assert_eq!(PathMode::default(), PathMode::Relative);
         }
 
-        if !self.root_path.is_dir() {
-            return Err(ConfigError::InvalidPath {
-                path: self.root_path.clone(),
-                reason: "Path is not a directory".to_string(),
-            });
+        #[test]
+        fn modes_are_distinct() {
+            assert_ne!(PathMode::Relative, PathMode::Full);
         }
+    }
 
-        match dunce::canonicalize(&self.root_path) {
-            Ok(canonical) => {
-                self.root_path = canonical;
-                Ok(())
-            }
-            Err(e) => Err(ConfigError::InvalidPath {
-                path: self.root_path.clone(),
-                reason: format!("Failed to canonicalize path: {}", e),
-            }),
+    mod scan_options_tests {
+        use super::*;
+
+        #[test]
+        fn default_has_expected_values() {
+            let opts = ScanOptions::default();
+            assert_eq!(opts.max_depth, None);
+            assert!(!opts.show_files);
+            assert_eq!(opts.thread_count, auto_thread_count());
+            assert!(!opts.respect_gitignore);
+            assert!(opts.paths_from.is_none());
+            assert!(!opts.git_tracked);
+            assert!(!opts.show_filtered);
+            assert_eq!(opts.max_entries, None);
+            assert_eq!(opts.file_limit, None);
+            assert_eq!(opts.read_timeout, None);
+            assert_eq!(opts.timeout, None);
+            assert_eq!(opts.budget_time, None);
+            assert_eq!(opts.budget_entries, None);
+        }
+
+        #[test]
+        fn thread_count_is_always_non_zero() {
+            let opts = ScanOptions::default();
+            assert!(opts.thread_count.get() > 0);
+        }
+
+        #[test]
+        fn auto_thread_count_stays_within_io_bounds() {
+            let threads = auto_thread_count().get();
+            assert!((MIN_AUTO_THREADS..=MAX_AUTO_THREADS).contains(&threads));
+            assert!(threads <= MAX_THREAD_COUNT);
+        }
+
+        #[test]
+        fn thread_clamp_warning_names_both_counts() {
+            let warning = ConfigWarning::ThreadCountClamped {
+                requested: 10000,
+                used: MAX_THREAD_COUNT,
+            };
+            let text = warning.to_string();
+            assert!(text.contains("10000"));
+            assert!(text.contains("256"));
+        }
+
+        #[test]
+        fn clone_produces_equal_copy() {
+            let opts = ScanOptions {
+                max_depth: Some(5),
+                show_files: true,
+                thread_count: NonZeroUsize::new(4).unwrap(),
+                backend: ScanBackend::Channel,
+                respect_gitignore: true,
+                show_hidden: false,
+                paths_from: Some(PathListSource::Stdin),
+                git_tracked: true,
+                show_filtered: true,
+                max_entries: Some(100),
+                file_limit: Some(500),
+                cache: CacheMode::Enabled,
+                read_timeout: Some(Duration::from_secs(5)),
+                timeout: Some(Duration::from_secs(60)),
+                budget_time: Some(Duration::from_secs(30)),
+                budget_entries: Some(1_000_000),
+                leaf_depth: Some(3),
+                du_threshold: Some(100 * 1024 * 1024),
+                one_file_system: true,
+                skip_cloud_only: false,
+                resume_after: Some(PathBuf::from("src")),
+            };
+            let cloned = opts.clone();
+            assert_eq!(opts, cloned);
         }
     }
 
-    fn infer_output_format(&mut self) -> ConfigResult<()> {
-        if let Some(ref path) = self.output.output_path {
-            if let Some(format) = OutputFormat::from_extension(path) {
-                self.output.format = format;
-            } else {
-                return Err(ConfigError::UnknownOutputFormat { path: path.clone() });
-            }
+    mod match_options_tests {
+        use super::*;
+
+        #[test]
+        fn default_does_not_prune() {
+            let opts = MatchOptions::default();
+            assert!(!opts.prune_empty);
+        }
+
+        #[test]
+        fn clone_produces_equal_copy() {
+            let opts = MatchOptions {
+                include_patterns: vec!["*.rs".to_string()],
+                exclude_patterns: vec!["target".to_string()],
+                include_overrides: vec!["*.md".to_string()],
+                prune_empty: true,
+                prune_empty_files: true,
+                smart_excludes: false,
+                no_normalize: false,
+            };
+            let cloned = opts.clone();
+            assert_eq!(opts, cloned);
         }
-        Ok(())
     }
 
-    fn check_conflicts(&self) -> ConfigResult<()> {
-        if self.output.silent && self.output.output_path.is_none() {
-            return Err(ConfigError::ConflictingOptions {
-                opt_a: "--silent".to_string(),
-                opt_b: "(no --output)".to_string(),
-                reason: "Silent mode requires an output file; otherwise no output will be produced."
-                    .to_string(),
-            });
+    mod render_options_tests {
+        use super::*;
+
+        #[test]
+        fn default_has_expected_values() {
+            let opts = RenderOptions::default();
+            assert_eq!(opts.charset, CharsetMode::Auto);
+            assert_eq!(opts.path_mode, PathMode::Relative);
+            assert!(!opts.show_size);
+            assert!(!opts.human_readable);
+            assert!(!opts.show_date);
+            assert!(!opts.show_disk_usage);
+            assert!(!opts.no_indent);
+            assert!(!opts.reverse_sort);
+            assert!(!opts.show_report);
+            assert!(!opts.report_extensions);
+            assert!(!opts.no_win_banner);
         }
+    }
 
-        if self.render.show_disk_usage && !self.batch_mode {
-            return Err(ConfigError::ConflictingOptions {
-                opt_a: "--disk-usage".to_string(),
-                opt_b: "(no --batch)".to_string(),
-                reason: "Disk usage calculation requires batch mode (--batch).".to_string(),
-            });
+    mod output_options_tests {
+        use super::*;
+
+        #[test]
+        fn default_has_expected_values() {
+            let opts = OutputOptions::default();
+            assert!(opts.output_path.is_none());
+            assert_eq!(opts.format, OutputFormat::Txt);
+            assert!(!opts.silent);
+        }
+    }
+
+    mod config_basic_tests {
+        use super::*;
+
+        #[test]
+        fn default_has_expected_values() {
+            let config = Config::default();
+            assert_eq!(config.root_path, PathBuf::from("."));
+            assert!(!config.path_explicitly_set);
+            assert!(!config.show_help);
+            assert!(!config.show_version);
+            assert!(!config.batch_mode);
+            assert!(config.explain_target.is_none());
         }
 
-        if self.output.output_path.is_some() {
-            let format = &self.output.format;
-            let requires_batch = matches!(
-                format,
-                OutputFormat::Json | OutputFormat::Yaml | OutputFormat::Toml
+        #[test]
+        fn with_root_sets_root_path_and_flag() {
+            let config = Config::with_root(PathBuf::from("/some/path"));
+            assert_eq!(config.root_path, PathBuf::from("/some/path"));
+            assert!(config.path_explicitly_set);
+            assert!(!config.show_help);
+            assert!(!config.show_version);
+        }
+
+        #[test]
+        fn clone_produces_equal_copy() {
+            let mut config = Config::default();
+            config.scan.show_files = true;
+            config.render.show_size = true;
+
+            let cloned = config.clone();
+            assert_eq!(config, cloned);
+        }
+
+        #[test]
+        fn debug_is_implemented() {
+            let config = Config::default();
+            let debug_str = format!("{:?}", config);
+            assert!(debug_str.contains("Config"));
+            assert!(debug_str.contains("root_path"));
+        }
+    }
+
+    mod config_is_info_only_tests {
+        use super::*;
+
+        #[test]
+        fn returns_false_by_default() {
+            let config = Config::default();
+            assert!(!config.is_info_only());
+        }
+
+        #[test]
+        fn returns_true_for_help() {
+            let config = Config {
+                show_help: true,
+                ..Default::default()
+            };
+            assert!(config.is_info_only());
+        }
+
+        #[test]
+        fn returns_true_for_version() {
+            let config = Config {
+                show_version: true,
+                ..Default::default()
+            };
+            assert!(config.is_info_only());
+        }
+
+        #[test]
+        fn returns_true_for_both() {
+            let config = Config {
+                show_help: true,
+                show_version: true,
+                ..Default::default()
+            };
+            assert!(config.is_info_only());
+        }
+    }
+
+    mod config_sanitizes_console_names_tests {
+        use super::*;
+
+        #[test]
+        fn returns_true_by_default() {
+            assert!(Config::default().sanitizes_console_names());
+        }
+
+        #[test]
+        fn returns_false_with_raw_names_or_silent() {
+            let mut config = Config::default();
+            config.render.raw_names = true;
+            assert!(
+                !config.sanitizes_console_names(),
+                "--raw-names 应保留控制字符"
+            );
+
+            let mut config = Config::default();
+            config.output.silent = true;
+            assert!(
+                !config.sanitizes_console_names(),
+                "不输出到控制台时无需清理"
             );
-            if requires_batch && !self.batch_mode {
-                return Err(ConfigError::ConflictingOptions {
-                    opt_a: format!("--output (format: {:?})", format),
-                    opt_b: "(no --batch)".to_string(),
-                    reason:
-                    "Structured output formats (JSON/YAML/TOML) require batch mode (--batch)."
-                        .to_string(),
-                });
-            }
         }
 
-        Ok(())
+        #[test]
+        fn returns_false_with_explicit_escape() {
+            let mut config = Config::default();
+            config.render.escape = EscapeMode::Question;
+            assert!(
+                !config.sanitizes_console_names(),
+                "已指定 /ES 时应沿用其模式"
+            );
+        }
     }
 
-    fn apply_implicit_dependencies(&mut self) {
-        if self.render.human_readable {
-            self.render.show_size = true;
+    mod config_needs_size_info_tests {
+        use super::*;
+
+        #[test]
+        fn returns_false_by_default() {
+            let config = Config::default();
+            assert!(!config.needs_size_info());
+        }
+
+        #[test]
+        fn returns_true_when_show_size() {
+            let mut config = Config::default();
+            config.render.show_size = true;
+            assert!(config.needs_size_info());
+        }
+
+        #[test]
+        fn returns_true_when_human_readable() {
+            let mut config = Config::default();
+            config.render.human_readable = true;
+            assert!(config.needs_size_info());
+        }
+
+        #[test]
+        fn returns_true_when_show_disk_usage() {
+            let mut config = Config::default();
+            config.render.show_disk_usage = true;
+            assert!(config.needs_size_info());
         }
-        if self.render.show_disk_usage {
-            self.render.show_size = true;
+
+        #[test]
+        fn returns_true_when_multiple_size_options() {
+            let mut config = Config::default();
+            config.render.show_size = true;
+            config.render.human_readable = true;
+            config.render.show_disk_usage = true;
+            assert!(config.needs_size_info());
         }
     }
-}
-
-// ============================================================================
-// Unit Tests
-// ============================================================================
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::path::Path;
 
-    mod output_format_tests {
+    mod config_needs_time_info_tests {
         use super::*;
 
         #[test]
-        fn from_extension_recognizes_txt() {
-            assert_eq!(
-                OutputFormat::from_extension(Path::new("file.txt")),
-                Some(OutputFormat::Txt)
-            );
-            assert_eq!(
-                OutputFormat::from_extension(Path::new("file.TXT")),
-                Some(OutputFormat::Txt)
-            );
-            assert_eq!(
-                OutputFormat::from_extension(Path::new("FILE.Txt")),
-                Some(OutputFormat::Txt)
-            );
+        fn returns_false_by_default() {
+            let config = Config::default();
+            assert!(!config.needs_time_info());
         }
 
         #[test]
-        fn from_extension_recognizes_json() {
-            assert_eq!(
-                OutputFormat::from_extension(Path::new("file.json")),
-                Some(OutputFormat::Json)
-            );
-            assert_eq!(
-                OutputFormat::from_extension(Path::new("file.JSON")),
-                Some(OutputFormat::Json)
-            );
+        fn returns_true_when_show_date() {
+            let mut config = Config::default();
+            config.render.show_date = true;
+            assert!(config.needs_time_info());
         }
 
         #[test]
-        fn from_extension_recognizes_yaml_variants() {
-            assert_eq!(
-                OutputFormat::from_extension(Path::new("file.yml")),
-                Some(OutputFormat::Yaml)
-            );
-            assert_eq!(
-                OutputFormat::from_extension(Path::new("file.yaml")),
-                Some(OutputFormat::Yaml)
-            );
-            assert_eq!(
-                OutputFormat::from_extension(Path::new("file.YAML")),
-                Some(OutputFormat::Yaml)
-            );
-            assert_eq!(
-                OutputFormat::from_extension(Path::new("file.YML")),
-                Some(OutputFormat::Yaml)
-            );
+        fn returns_true_when_sorting_by_date() {
+            let mut config = Config::default();
+            config.render.sort_key = SortKey::Accessed;
+            assert!(config.needs_time_info());
+            assert!(!config.needs_size_info());
         }
+    }
 
-        #[test]
-        fn from_extension_recognizes_toml() {
-            assert_eq!(
-                OutputFormat::from_extension(Path::new("file.toml")),
-                Some(OutputFormat::Toml)
-            );
-            assert_eq!(
-                OutputFormat::from_extension(Path::new("file.TOML")),
-                Some(OutputFormat::Toml)
-            );
-        }
+    mod config_needs_entry_metadata_tests {
+        use super::*;
 
         #[test]
-        fn from_extension_returns_none_for_unknown() {
-            assert_eq!(OutputFormat::from_extension(Path::new("file.xyz")), None);
-            assert_eq!(OutputFormat::from_extension(Path::new("file")), None);
-            assert_eq!(OutputFormat::from_extension(Path::new("")), None);
-            assert_eq!(OutputFormat::from_extension(Path::new("file.md")), None);
-            assert_eq!(OutputFormat::from_extension(Path::new("file.rs")), None);
-            assert_eq!(OutputFormat::from_extension(Path::new(".gitignore")), None);
+        fn returns_true_by_default_for_hidden_check() {
+            let config = Config::with_root(PathBuf::from("."));
+            assert!(config.needs_entry_metadata());
         }
 
         #[test]
-        fn extension_returns_correct_string() {
-            assert_eq!(OutputFormat::Txt.extension(), "txt");
-            assert_eq!(OutputFormat::Json.extension(), "json");
-            assert_eq!(OutputFormat::Yaml.extension(), "yml");
-            assert_eq!(OutputFormat::Toml.extension(), "toml");
+        fn returns_false_when_showing_hidden_without_size_or_date() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.show_hidden = true;
+            assert!(!config.needs_entry_metadata());
         }
 
         #[test]
-        fn default_is_txt() {
-            assert_eq!(OutputFormat::default(), OutputFormat::Txt);
+        fn returns_true_when_size_or_date_needed() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.show_hidden = true;
+            config.render.show_size = true;
+            assert!(config.needs_entry_metadata());
+
+            config.render.show_size = false;
+            config.render.show_disk_usage = true;
+            assert!(config.needs_entry_metadata());
+
+            config.render.show_disk_usage = false;
+            config.render.show_date = true;
+            assert!(config.needs_entry_metadata());
         }
 
         #[test]
-        fn formats_are_distinct() {
-            let formats = [
-                OutputFormat::Txt,
-                OutputFormat::Json,
-                OutputFormat::Yaml,
-                OutputFormat::Toml,
-            ];
-            for (i, a) in formats.iter().enumerate() {
-                for (j, b) in formats.iter().enumerate() {
-                    if i == j {
-                        assert_eq!(a, b);
-                    } else {
-                        assert_ne!(a, b);
-                    }
-                }
-            }
+        fn returns_true_for_extension_stats() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.show_hidden = true;
+            config.output.stats_json = Some(StatsTarget::Stderr);
+            assert!(config.needs_entry_metadata());
+
+            config.output.stats_json = None;
+            config.render.report_extensions = true;
+            assert!(config.needs_entry_metadata());
         }
     }
 
-    mod charset_mode_tests {
+    mod config_validate_path_tests {
         use super::*;
 
         #[test]
-        fn unicode_returns_unicode_symbols() {
-            let mode = CharsetMode::Unicode;
-            assert_eq!(mode.branch(), "├─");
-            assert_eq!(mode.last_branch(), "└─");
-            assert_eq!(mode.vertical(), "│  ");
-            assert_eq!(mode.indent(), "   ");
+        fn fails_for_nonexistent_path() {
+            let config = Config::with_root(PathBuf::from("/nonexistent/path/that/does/not/exist"));
+            let result = config.validate();
+            assert!(result.is_err());
+
+            let err = result.unwrap_err();
+            assert!(matches!(err, ConfigError::InvalidPath { .. }));
+            if let ConfigError::InvalidPath { reason, .. } = err {
+                assert!(reason.contains("Path does not exist"));
+            }
         }
 
         #[test]
-        fn ascii_returns_ascii_symbols() {
-            let mode = CharsetMode::Ascii;
-            assert_eq!(mode.branch(), "+---");
-            assert_eq!(mode.last_branch(), "\\---");
-            assert_eq!(mode.vertical(), "|   ");
-            assert_eq!(mode.indent(), "    ");
+        fn fails_for_file_as_root() {
+            let config = Config::with_root(PathBuf::from("Cargo.toml"));
+            let result = config.validate();
+            assert!(result.is_err());
+
+            let err = result.unwrap_err();
+            if let ConfigError::InvalidPath { reason, .. } = err {
+                assert!(reason.contains("Path is not a directory"));
+            } else {
+                panic!("Expected InvalidPath error");
+            }
+        }
+
+        #[test]
+        fn succeeds_for_current_directory() {
+            let config = Config::default();
+            let result = config.validate();
+            assert!(result.is_ok());
         }
 
         #[test]
-        fn default_is_unicode() {
-            assert_eq!(CharsetMode::default(), CharsetMode::Unicode);
+        fn canonicalizes_path() {
+            let config = Config::with_root(PathBuf::from("."));
+            let validated = config.validate().unwrap();
+            assert!(validated.root_path.is_absolute());
         }
 
         #[test]
-        fn vertical_and_indent_have_matching_widths() {
-            let unicode = CharsetMode::Unicode;
-            assert_eq!(unicode.vertical().chars().count(), unicode.indent().chars().count());
+        fn names_nearest_existing_directory_for_missing_root() {
+            let dir = tempfile::TempDir::new().unwrap();
+            let config = Config::with_root(dir.path().join("typo").join("deeper"));
 
-            let ascii = CharsetMode::Ascii;
-            assert_eq!(ascii.vertical().len(), ascii.indent().len());
+            let err = config.validate().unwrap_err();
+            if let ConfigError::InvalidPath { reason, .. } = err {
+                assert!(reason.starts_with("Path does not exist"));
+                assert!(
+                    reason.contains(&dir.path().display().to_string()),
+                    "应指出最近的存在目录"
+                );
+            } else {
+                panic!("Expected InvalidPath error");
+            }
         }
 
         #[test]
-        fn branch_and_last_branch_have_same_visual_width() {
-            let ascii = CharsetMode::Ascii;
-            assert_eq!(ascii.branch().len(), ascii.last_branch().len());
+        fn normalizes_quoted_root_with_trailing_separator() {
+            let dir = tempfile::TempDir::new().unwrap();
+            let typed = format!("{}/\"", dir.path().display());
+
+            let validated = Config::with_root(PathBuf::from(typed)).validate().unwrap();
+            let expected = Config::with_root(dir.path().to_path_buf())
+                .validate()
+                .unwrap();
+            assert_eq!(
+                validated.root_path, expected.root_path,
+                "引号和末尾分隔符应被去除"
+            );
         }
-    }
 
-    mod path_mode_tests {
-        use super::*;
+        #[test]
+        fn resolves_relative_base_from_root() {
+            let dir = tempfile::TempDir::new().unwrap();
+            std::fs::create_dir(dir.path().join("repo")).unwrap();
+            let mut config = Config::with_root(dir.path().join("repo"));
+            config.render.relative_to = Some(PathBuf::from(".."));
+
+            let validated = config.validate().unwrap();
+            let root_parent = validated.root_path.parent().map(Path::to_path_buf);
+            assert_eq!(validated.render.relative_to, root_parent, "应从根目录起算");
+            assert_eq!(validated.render.path_mode, PathMode::Full);
+        }
 
         #[test]
-        fn default_is_relative() {
-            assert_eq!(PathMode::default(), PathMode::Relative);
+        fn fails_for_missing_relative_base() {
+            let mut config = Config::default();
+            config.render.relative_to = Some(PathBuf::from("no-such-base"));
+
+            let err = config.validate().unwrap_err();
+            assert!(matches!(err, ConfigError::InvalidPath { .. }));
         }
 
         #[test]
-        fn modes_are_distinct() {
-            assert_ne!(PathMode::Relative, PathMode::Full);
+        fn canonicalizes_relative_path() {
+            let config = Config::with_root(PathBuf::from("src"));
+            let result = config.validate();
+            if let Ok(validated) = result {
+                assert!(validated.root_path.is_absolute());
+                assert!(validated.root_path.ends_with("src"));
+            }
         }
     }
 
-    mod scan_options_tests {
+    mod config_validate_format_inference_tests {
         use super::*;
 
         #[test]
-        fn default_has_expected_values() {
-            let opts = ScanOptions::default();
-            assert_eq!(opts.max_depth, None);
-            assert!(!opts.show_files);
-            assert_eq!(opts.thread_count.get(), 8);
-            assert!(!opts.respect_gitignore);
+        fn infers_json_format() {
+            let mut config = Config {
+                batch_mode: true,
+                ..Default::default()
+            };
+            config.output.output_path = Some(PathBuf::from("tree.json"));
+            let validated = config.validate().unwrap();
+            assert_eq!(validated.output.format, OutputFormat::Json);
         }
 
         #[test]
-        fn thread_count_is_always_non_zero() {
-            let opts = ScanOptions::default();
-            assert!(opts.thread_count.get() > 0);
+        fn infers_yaml_from_yml() {
+            let mut config = Config {
+                batch_mode: true,
+                ..Default::default()
+            };
+            config.output.output_path = Some(PathBuf::from("tree.yml"));
+            let validated = config.validate().unwrap();
+            assert_eq!(validated.output.format, OutputFormat::Yaml);
         }
 
         #[test]
-        fn clone_produces_equal_copy() {
-            let opts = ScanOptions {
-                max_depth: Some(5),
-                show_files: true,
-                thread_count: NonZeroUsize::new(4).unwrap(),
-                respect_gitignore: true,
-                show_hidden: false,
+        fn infers_yaml_from_yaml() {
+            let mut config = Config {
+                batch_mode: true,
+                ..Default::default()
             };
-            let cloned = opts.clone();
-            assert_eq!(opts, cloned);
+            config.output.output_path = Some(PathBuf::from("tree.yaml"));
+            let validated = config.validate().unwrap();
+            assert_eq!(validated.output.format, OutputFormat::Yaml);
         }
-    }
 
-    mod match_options_tests {
-        use super::*;
+        #[test]
+        fn infers_toml_format() {
+            let mut config = Config {
+                batch_mode: true,
+                ..Default::default()
+            };
+            config.output.output_path = Some(PathBuf::from("tree.toml"));
+            let validated = config.validate().unwrap();
+            assert_eq!(validated.output.format, OutputFormat::Toml);
+        }
+
+        #[test]
+        fn infers_txt_format() {
+            let mut config = Config::default();
+            config.output.output_path = Some(PathBuf::from("tree.txt"));
+            let validated = config.validate().unwrap();
+            assert_eq!(validated.output.format, OutputFormat::Txt);
+        }
+
+        #[test]
+        fn fails_for_unknown_extension() {
+            let mut config = Config::default();
+            config.output.output_path = Some(PathBuf::from("tree.xyz"));
+            let result = config.validate();
+            assert!(result.is_err());
+
+            let err = result.unwrap_err();
+            if let ConfigError::UnknownOutputFormat { path } = err {
+                assert_eq!(path, PathBuf::from("tree.xyz"));
+            } else {
+                panic!("Expected UnknownOutputFormat error");
+            }
+        }
 
+        #[test]
+        fn fails_for_no_extension() {
+            let mut config = Config::default();
+            config.output.output_path = Some(PathBuf::from("tree_output"));
+            let result = config.validate();
+            assert!(result.is_err());
+            assert!(matches!(
+                result.unwrap_err(),
+                ConfigError::UnknownOutputFormat { .. }
+            ));
+        }
 
         #[test]
-        fn clone_produces_equal_copy() {
-            let opts = MatchOptions {
-                include_patterns: vec!["*.rs".to_string()],
-                exclude_patterns: vec!["target".to_string()],
+        fn report_dir_names_timestamped_output() {
+            let mut config = Config {
+                batch_mode: true,
+                ..Default::default()
             };
-            let cloned = opts.clone();
-            assert_eq!(opts, cloned);
+            config.output.report_dir = Some(PathBuf::from("reports"));
+            config.output.output_path = Some(PathBuf::from("tree.json.gz"));
+            let validated = config.validate().unwrap();
+            assert_eq!(validated.output.format, OutputFormat::Json);
+            assert!(validated.output.compress);
+
+            let path = validated.output.output_path.unwrap();
+            assert_eq!(path.parent(), Some(Path::new("reports")));
+            let name = path.file_name().unwrap().to_str().unwrap();
+            assert!(
+                name.starts_with("tree-") && name.ends_with(".json.gz"),
+                "{name}"
+            );
+            assert_eq!(name.len(), "tree-20261016-020000.json.gz".len());
+
+            let mut config = Config::default();
+            config.output.report_dir = Some(PathBuf::from("reports"));
+            let path = config.validate().unwrap().output.output_path.unwrap();
+            assert!(
+                path.to_string_lossy().ends_with(".txt"),
+                "默认应写出文本报告"
+            );
         }
     }
 
-    mod render_options_tests {
+    mod config_validate_conflict_tests {
         use super::*;
 
         #[test]
-        fn default_has_expected_values() {
-            let opts = RenderOptions::default();
-            assert_eq!(opts.charset, CharsetMode::Unicode);
-            assert_eq!(opts.path_mode, PathMode::Relative);
-            assert!(!opts.show_size);
-            assert!(!opts.human_readable);
-            assert!(!opts.show_date);
-            assert!(!opts.show_disk_usage);
-            assert!(!opts.no_indent);
-            assert!(!opts.reverse_sort);
-            assert!(!opts.show_report);
-            assert!(!opts.no_win_banner);
-        }
-    }
+        fn fails_silent_without_output() {
+            let mut config = Config::default();
+            config.output.silent = true;
+            let result = config.validate();
+            assert!(result.is_err());
 
-    mod output_options_tests {
-        use super::*;
+            let err = result.unwrap_err();
+            if let ConfigError::ConflictingOptions { opt_a, opt_b, .. } = err {
+                assert!(opt_a.contains("silent"));
+                assert!(opt_b.contains("output"));
+            } else {
+                panic!("Expected ConflictingOptions error");
+            }
+        }
 
         #[test]
-        fn default_has_expected_values() {
-            let opts = OutputOptions::default();
-            assert!(opts.output_path.is_none());
-            assert_eq!(opts.format, OutputFormat::Txt);
-            assert!(!opts.silent);
+        fn succeeds_silent_with_output() {
+            let mut config = Config::default();
+            config.output.silent = true;
+            config.output.output_path = Some(PathBuf::from("tree.txt"));
+            let result = config.validate();
+            assert!(result.is_ok());
         }
-    }
-
-    mod config_basic_tests {
-        use super::*;
 
         #[test]
-        fn default_has_expected_values() {
-            let config = Config::default();
-            assert_eq!(config.root_path, PathBuf::from("."));
-            assert!(!config.path_explicitly_set);
-            assert!(!config.show_help);
-            assert!(!config.show_version);
-            assert!(!config.batch_mode);
+        fn fails_disk_usage_without_batch() {
+            let mut config = Config::default();
+            config.render.show_disk_usage = true;
+            let result = config.validate();
+            assert!(result.is_err());
+
+            let err = result.unwrap_err();
+            if let ConfigError::ConflictingOptions { opt_a, opt_b, .. } = err {
+                assert!(opt_a.contains("disk-usage"));
+                assert!(opt_b.contains("batch"));
+            } else {
+                panic!("Expected ConflictingOptions error");
+            }
         }
 
         #[test]
-        fn with_root_sets_root_path_and_flag() {
-            let config = Config::with_root(PathBuf::from("/some/path"));
-            assert_eq!(config.root_path, PathBuf::from("/some/path"));
-            assert!(config.path_explicitly_set);
-            assert!(!config.show_help);
-            assert!(!config.show_version);
+        fn succeeds_disk_usage_with_batch() {
+            let mut config = Config::default();
+            config.render.show_disk_usage = true;
+            config.batch_mode = true;
+            let result = config.validate();
+            assert!(result.is_ok());
         }
 
         #[test]
-        fn clone_produces_equal_copy() {
+        fn fails_du_threshold_without_disk_usage() {
             let mut config = Config::default();
-            config.scan.show_files = true;
-            config.render.show_size = true;
+            config.scan.du_threshold = Some(1024);
+            config.batch_mode = true;
+            let err = config.clone().validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--du-threshold" && opt_b.contains("disk-usage")
+            ));
 
-            let cloned = config.clone();
-            assert_eq!(config, cloned);
+            config.render.show_disk_usage = true;
+            assert!(config.validate().is_ok());
         }
 
         #[test]
-        fn debug_is_implemented() {
-            let config = Config::default();
-            let debug_str = format!("{:?}", config);
-            assert!(debug_str.contains("Config"));
-            assert!(debug_str.contains("root_path"));
-        }
-    }
+        fn fails_du_percent_without_disk_usage() {
+            let mut config = Config::default();
+            config.render.show_du_percent = true;
+            config.batch_mode = true;
+            let err = config.clone().validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--du-percent"
+            ));
 
-    mod config_is_info_only_tests {
-        use super::*;
+            config.render.show_disk_usage = true;
+            assert!(config.validate().is_ok());
+        }
 
         #[test]
-        fn returns_false_by_default() {
-            let config = Config::default();
-            assert!(!config.is_info_only());
+        fn fails_du_delta_without_disk_usage() {
+            let mut config = Config::default();
+            config.render.show_du_delta = true;
+            config.batch_mode = true;
+            let err = config.clone().validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--du-delta"
+            ));
+
+            config.render.show_disk_usage = true;
+            assert!(config.validate().is_ok());
         }
 
         #[test]
-        fn returns_true_for_help() {
+        fn fails_share_sort_without_disk_usage() {
             let mut config = Config::default();
-            config.show_help = true;
-            assert!(config.is_info_only());
+            config.render.sort_key = SortKey::Share;
+            config.batch_mode = true;
+            let err = config.clone().validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--sort share"
+            ));
+
+            config.render.show_disk_usage = true;
+            assert!(config.validate().is_ok());
         }
 
         #[test]
-        fn returns_true_for_version() {
+        fn fails_json_output_without_batch() {
             let mut config = Config::default();
-            config.show_version = true;
-            assert!(config.is_info_only());
+            config.output.output_path = Some(PathBuf::from("tree.json"));
+            let result = config.validate();
+            assert!(result.is_err());
+
+            let err = result.unwrap_err();
+            if let ConfigError::ConflictingOptions { opt_a, reason, .. } = err {
+                assert!(opt_a.contains("Json") || opt_a.contains("json"));
+                assert!(reason.contains("batch"));
+            } else {
+                panic!("Expected ConflictingOptions error");
+            }
         }
 
         #[test]
-        fn returns_true_for_both() {
+        fn fails_yaml_output_without_batch() {
             let mut config = Config::default();
-            config.show_help = true;
-            config.show_version = true;
-            assert!(config.is_info_only());
+            config.output.output_path = Some(PathBuf::from("tree.yml"));
+            let result = config.validate();
+            assert!(result.is_err());
         }
-    }
-
-    mod config_needs_size_info_tests {
-        use super::*;
 
         #[test]
-        fn returns_false_by_default() {
-            let config = Config::default();
-            assert!(!config.needs_size_info());
+        fn fails_toml_output_without_batch() {
+            let mut config = Config::default();
+            config.output.output_path = Some(PathBuf::from("tree.toml"));
+            let result = config.validate();
+            assert!(result.is_err());
         }
 
         #[test]
-        fn returns_true_when_show_size() {
+        fn succeeds_txt_output_without_batch() {
             let mut config = Config::default();
-            config.render.show_size = true;
-            assert!(config.needs_size_info());
+            config.output.output_path = Some(PathBuf::from("tree.txt"));
+            let result = config.validate();
+            assert!(result.is_ok());
         }
 
         #[test]
-        fn returns_true_when_human_readable() {
+        fn succeeds_json_output_with_batch() {
             let mut config = Config::default();
-            config.render.human_readable = true;
-            assert!(config.needs_size_info());
+            config.output.output_path = Some(PathBuf::from("tree.json"));
+            config.batch_mode = true;
+            let validated = config.validate().unwrap();
+            assert_eq!(validated.output.format, OutputFormat::Json);
         }
 
         #[test]
-        fn returns_true_when_show_disk_usage() {
+        fn succeeds_yaml_output_with_batch() {
             let mut config = Config::default();
-            config.render.show_disk_usage = true;
-            assert!(config.needs_size_info());
+            config.output.output_path = Some(PathBuf::from("tree.yaml"));
+            config.batch_mode = true;
+            let result = config.validate();
+            assert!(result.is_ok());
         }
 
         #[test]
-        fn returns_true_when_multiple_size_options() {
+        fn succeeds_toml_output_with_batch() {
             let mut config = Config::default();
-            config.render.show_size = true;
-            config.render.human_readable = true;
-            config.render.show_disk_usage = true;
-            assert!(config.needs_size_info());
+            config.output.output_path = Some(PathBuf::from("tree.toml"));
+            config.batch_mode = true;
+            let result = config.validate();
+            assert!(result.is_ok());
         }
-    }
 
-    mod config_needs_time_info_tests {
-        use super::*;
+        #[test]
+        fn fails_show_filtered_with_structured_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.show_filtered = true;
+            config.batch_mode = true;
+            config.output.output_path = Some(PathBuf::from("tree.json"));
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--show-filtered"
+            ));
+        }
 
         #[test]
-        fn returns_false_by_default() {
-            let config = Config::default();
-            assert!(!config.needs_time_info());
+        fn succeeds_show_filtered_with_txt_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.show_filtered = true;
+            config.output.output_path = Some(PathBuf::from("tree.txt"));
+            assert!(config.validate().is_ok());
         }
 
         #[test]
-        fn returns_true_when_show_date() {
-            let mut config = Config::default();
-            config.render.show_date = true;
-            assert!(config.needs_time_info());
+        fn fails_max_entries_with_structured_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.max_entries = Some(10);
+            config.batch_mode = true;
+            config.output.output_path = Some(PathBuf::from("tree.yml"));
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--max-entries"
+            ));
         }
-    }
 
-    mod config_validate_path_tests {
-        use super::*;
+        #[test]
+        fn fails_max_output_size_with_structured_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.max_output_size = Some(4096);
+            config.batch_mode = true;
+            config.output.output_path = Some(PathBuf::from("tree.json"));
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--max-output-size"
+            ));
+        }
 
         #[test]
-        fn fails_for_nonexistent_path() {
-            let config = Config::with_root(PathBuf::from("/nonexistent/path/that/does/not/exist"));
-            let result = config.validate();
-            assert!(result.is_err());
+        fn fails_du_threshold_with_structured_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.du_threshold = Some(1024);
+            config.render.show_disk_usage = true;
+            config.batch_mode = true;
+            config.output.output_path = Some(PathBuf::from("tree.json"));
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--du-threshold"
+            ));
+        }
 
-            let err = result.unwrap_err();
-            assert!(matches!(err, ConfigError::InvalidPath { .. }));
-            if let ConfigError::InvalidPath { reason, .. } = err {
-                assert!(reason.contains("Path does not exist"));
-            }
+        #[test]
+        fn fails_mark_cloud_with_structured_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.mark_cloud = true;
+            config.batch_mode = true;
+            config.output.output_path = Some(PathBuf::from("tree.json"));
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--mark-cloud"
+            ));
         }
 
         #[test]
-        fn fails_for_file_as_root() {
-            let config = Config::with_root(PathBuf::from("Cargo.toml"));
-            let result = config.validate();
-            assert!(result.is_err());
+        fn fails_mark_empty_files_with_structured_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.mark_empty_files = true;
+            config.batch_mode = true;
+            config.output.output_path = Some(PathBuf::from("tree.json"));
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--mark-empty-files"
+            ));
+        }
 
-            let err = result.unwrap_err();
-            if let ConfigError::InvalidPath { reason, .. } = err {
-                assert!(reason.contains("Path is not a directory"));
-            } else {
-                panic!("Expected InvalidPath error");
+        #[test]
+        fn fails_node_ids_without_json_or_yaml_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.node_ids = true;
+            assert!(matches!(
+                config.validate().unwrap_err(),
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--node-ids" && opt_b == "(no structured --output)"
+            ));
+
+            for path in ["tree.txt", "tree.toml"] {
+                let mut config = Config::with_root(PathBuf::from("."));
+                config.output.node_ids = true;
+                config.batch_mode = true;
+                config.output.output_path = Some(PathBuf::from(path));
+                let err = config.validate().unwrap_err();
+                assert!(
+                    matches!(err, ConfigError::ConflictingOptions { ref opt_a, .. }
+                        if opt_a == "--node-ids"),
+                    "{path} 不应接受 --node-ids"
+                );
             }
+
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.node_ids = true;
+            config.batch_mode = true;
+            config.output.output_path = Some(PathBuf::from("tree.yaml"));
+            assert!(config.validate().is_ok(), "YAML 输出应接受 --node-ids");
         }
 
         #[test]
-        fn succeeds_for_current_directory() {
-            let config = Config::default();
-            let result = config.validate();
-            assert!(result.is_ok());
+        fn fails_file_limit_with_structured_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.file_limit = Some(100);
+            config.batch_mode = true;
+            config.output.output_path = Some(PathBuf::from("tree.toml"));
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--filelimit"
+            ));
         }
 
         #[test]
-        fn canonicalizes_path() {
-            let config = Config::with_root(PathBuf::from("."));
-            let validated = config.validate().unwrap();
-            assert!(validated.root_path.is_absolute());
+        fn fails_leaf_depth_with_structured_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.leaf_depth = Some(2);
+            config.output.output_path = Some(PathBuf::from("tree.json"));
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--leaf-depth"
+            ));
         }
 
         #[test]
-        fn canonicalizes_relative_path() {
-            let config = Config::with_root(PathBuf::from("src"));
-            let result = config.validate();
-            if result.is_ok() {
-                let validated = result.unwrap();
-                assert!(validated.root_path.is_absolute());
-                assert!(validated.root_path.ends_with("src"));
-            }
+        fn fails_highlight_without_include() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.highlight = true;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_b, .. } if opt_b == "(no --include)"
+            ));
         }
-    }
 
-    mod config_validate_format_inference_tests {
-        use super::*;
+        #[test]
+        fn fails_highlight_with_output_file() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.highlight = true;
+            config.matching.include_patterns = vec!["*.rs".to_string()];
+            config.output.output_path = Some(PathBuf::from("tree.txt"));
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--highlight" && opt_b == "--output"
+            ));
+        }
 
         #[test]
-        fn infers_json_format() {
-            let mut config = Config::default();
+        fn fails_backend_outside_batch_pipeline() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.backend = ScanBackend::Walk;
+            let err = config.clone().validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--backend" && opt_b == "(no --batch)"
+            ));
+
             config.batch_mode = true;
-            config.output.output_path = Some(PathBuf::from("tree.json"));
-            let validated = config.validate().unwrap();
-            assert_eq!(validated.output.format, OutputFormat::Json);
+            assert!(config.validate().is_ok());
         }
 
         #[test]
-        fn infers_yaml_from_yml() {
-            let mut config = Config::default();
-            config.batch_mode = true;
-            config.output.output_path = Some(PathBuf::from("tree.yml"));
-            let validated = config.validate().unwrap();
-            assert_eq!(validated.output.format, OutputFormat::Yaml);
+        fn fails_interleave_with_file_sections() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.interleave = true;
+            config.render.group_by = GroupBy::Type;
+            let err = config.clone().validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--interleave" && opt_b == "--group-by"
+            ));
+
+            config.render.group_by = GroupBy::None;
+            let config = config.validate().unwrap();
+            assert!(config.batch_mode, "交错排序应启用批处理模式");
         }
 
         #[test]
-        fn infers_yaml_from_yaml() {
-            let mut config = Config::default();
-            config.batch_mode = true;
-            config.output.output_path = Some(PathBuf::from("tree.yaml"));
-            let validated = config.validate().unwrap();
-            assert_eq!(validated.output.format, OutputFormat::Yaml);
+        fn fails_columns_with_file_metadata() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.columns = true;
+            let err = config.clone().validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_b, .. } if opt_b == "(no --files)"
+            ));
+
+            config.scan.show_files = true;
+            config.render.show_date = true;
+            let err = config.clone().validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--columns" && opt_b == "--date"
+            ));
+
+            config.render.show_date = false;
+            let config = config.validate().unwrap();
+            assert!(config.batch_mode, "分栏应启用批处理模式");
         }
 
         #[test]
-        fn infers_toml_format() {
-            let mut config = Config::default();
-            config.batch_mode = true;
-            config.output.output_path = Some(PathBuf::from("tree.toml"));
-            let validated = config.validate().unwrap();
-            assert_eq!(validated.output.format, OutputFormat::Toml);
+        fn fails_level_guides_without_connectors() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.level_guides = LevelGuides::Number;
+            config.render.no_indent = true;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--level-guides" && opt_b == "--no-indent"
+            ));
         }
 
         #[test]
-        fn infers_txt_format() {
-            let mut config = Config::default();
+        fn fails_level_guide_colors_with_output_file() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.level_guides = LevelGuides::Number;
             config.output.output_path = Some(PathBuf::from("tree.txt"));
-            let validated = config.validate().unwrap();
-            assert_eq!(validated.output.format, OutputFormat::Txt);
+            assert!(config.clone().validate().is_ok(), "编号可以写入文本文件");
+
+            config.render.level_guides = LevelGuides::Color;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_b, .. } if opt_b == "--output"
+            ));
         }
 
         #[test]
-        fn fails_for_unknown_extension() {
-            let mut config = Config::default();
-            config.output.output_path = Some(PathBuf::from("tree.xyz"));
-            let result = config.validate();
-            assert!(result.is_err());
+        fn fails_count_with_batch_pipeline() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.count_only = true;
+            config.scan.leaf_depth = Some(2);
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--count" && opt_b == "--leaf-depth"
+            ));
+        }
 
-            let err = result.unwrap_err();
-            if let ConfigError::UnknownOutputFormat { path } = err {
-                assert_eq!(path, PathBuf::from("tree.xyz"));
-            } else {
-                panic!("Expected UnknownOutputFormat error");
-            }
+        #[test]
+        fn fails_count_with_explain() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.count_only = true;
+            config.explain_target = Some(PathBuf::from("src"));
+            assert!(config.validate().is_err());
         }
 
         #[test]
-        fn fails_for_no_extension() {
-            let mut config = Config::default();
-            config.output.output_path = Some(PathBuf::from("tree_output"));
-            let result = config.validate();
-            assert!(result.is_err());
-            assert!(matches!(result.unwrap_err(), ConfigError::UnknownOutputFormat { .. }));
+        fn succeeds_output_guards_with_txt_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.max_entries = Some(10);
+            config.output.max_output_size = Some(4096);
+            config.output.output_path = Some(PathBuf::from("tree.txt"));
+            assert!(config.validate().is_ok());
         }
-    }
 
-    mod config_validate_conflict_tests {
-        use super::*;
+        #[test]
+        fn fails_explain_with_paths_from() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.explain_target = Some(PathBuf::from("src"));
+            config.scan.paths_from = Some(PathListSource::Stdin);
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--explain"
+            ));
+        }
 
         #[test]
-        fn fails_silent_without_output() {
-            let mut config = Config::default();
-            config.output.silent = true;
-            let result = config.validate();
-            assert!(result.is_err());
+        fn serve_implies_batch_and_rejects_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.serve = true;
+            config.serve_port = Some(0);
+            let validated = config.clone().validate().unwrap();
+            assert!(validated.batch_mode, "--serve 应启用批处理模式");
 
-            let err = result.unwrap_err();
+            config.output.output_path = Some(PathBuf::from("tree.json"));
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--serve" && opt_b == "--output"
+            ));
+        }
+
+        #[test]
+        fn fails_port_without_serve() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.serve_port = Some(8080);
+            assert!(config.validate().is_err());
+
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.serve_pipe = Some("treepp".to_string());
+            assert!(config.validate().is_err(), "--pipe 也需要 --serve");
+        }
+
+        #[test]
+        fn fails_git_tracked_with_paths_from() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.git_tracked = true;
+            config.scan.paths_from = Some(PathListSource::Stdin);
+            let err = config.validate().unwrap_err();
             if let ConfigError::ConflictingOptions { opt_a, opt_b, .. } = err {
-                assert!(opt_a.contains("silent"));
-                assert!(opt_b.contains("output"));
+                assert_eq!(opt_a, "--git-tracked");
+                assert_eq!(opt_b, "--paths-from");
             } else {
                 panic!("Expected ConflictingOptions error");
             }
         }
 
         #[test]
-        fn succeeds_silent_with_output() {
-            let mut config = Config::default();
-            config.output.silent = true;
-            config.output.output_path = Some(PathBuf::from("tree.txt"));
-            let result = config.validate();
-            assert!(result.is_ok());
+        fn fails_cache_with_paths_from() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.cache = CacheMode::Enabled;
+            config.scan.paths_from = Some(PathListSource::Stdin);
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--cache" && opt_b == "--paths-from"
+            ));
         }
 
         #[test]
-        fn fails_disk_usage_without_batch() {
-            let mut config = Config::default();
-            config.render.show_disk_usage = true;
-            let result = config.validate();
-            assert!(result.is_err());
+        fn fails_cache_with_size_display() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.cache = CacheMode::Enabled;
+            config.render.show_size = true;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--cache"
+            ));
+        }
 
-            let err = result.unwrap_err();
-            if let ConfigError::ConflictingOptions { opt_a, opt_b, .. } = err {
-                assert!(opt_a.contains("disk-usage"));
-                assert!(opt_b.contains("batch"));
-            } else {
-                panic!("Expected ConflictingOptions error");
-            }
+        #[test]
+        fn fails_cache_with_stats_json() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.cache = CacheMode::Enabled;
+            config.output.stats_json = Some(StatsTarget::Stderr);
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_b, .. } if opt_b == "--stats-json"
+            ));
         }
 
         #[test]
-        fn succeeds_disk_usage_with_batch() {
-            let mut config = Config::default();
-            config.render.show_disk_usage = true;
-            config.batch_mode = true;
-            let result = config.validate();
-            assert!(result.is_ok());
+        fn fails_cache_with_depth_histogram() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.cache = CacheMode::Enabled;
+            config.render.depth_histogram = true;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_b, .. } if opt_b == "--depth-histogram"
+            ));
         }
 
         #[test]
-        fn fails_json_output_without_batch() {
-            let mut config = Config::default();
-            config.output.output_path = Some(PathBuf::from("tree.json"));
-            let result = config.validate();
-            assert!(result.is_err());
+        fn succeeds_no_cache_with_size_display() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.cache = CacheMode::Discard;
+            config.render.show_size = true;
+            assert!(config.validate().is_ok());
+        }
 
-            let err = result.unwrap_err();
-            if let ConfigError::ConflictingOptions { opt_a, reason, .. } = err {
-                assert!(opt_a.contains("Json") || opt_a.contains("json"));
-                assert!(reason.contains("batch"));
-            } else {
-                panic!("Expected ConflictingOptions error");
-            }
+        #[test]
+        fn fails_append_without_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.append = true;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--append" && opt_b == "(no --output)"
+            ));
         }
 
         #[test]
-        fn fails_yaml_output_without_batch() {
-            let mut config = Config::default();
-            config.output.output_path = Some(PathBuf::from("tree.yml"));
-            let result = config.validate();
-            assert!(result.is_err());
+        fn fails_report_dir_misuse() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.rotate = Some(7);
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--rotate"
+            ));
+
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.report_dir = Some(PathBuf::from("reports"));
+            config.output.output_path = Some(PathBuf::from("out").join("tree.txt"));
+            assert!(config.clone().validate().is_err(), "--output 只能是文件名");
+
+            config.output.output_path = None;
+            config.output.append = true;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--report-dir" && opt_b == "--append"
+            ));
         }
 
         #[test]
-        fn fails_toml_output_without_batch() {
-            let mut config = Config::default();
-            config.output.output_path = Some(PathBuf::from("tree.toml"));
-            let result = config.validate();
-            assert!(result.is_err());
+        fn fails_compress_without_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.compress = true;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--compress" && opt_b == "(no --output)"
+            ));
         }
 
         #[test]
-        fn succeeds_txt_output_without_batch() {
-            let mut config = Config::default();
+        fn fails_checkpoint_without_streamed_text_file() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.checkpoint = true;
+            assert!(matches!(
+                config.validate().unwrap_err(),
+                ConfigError::ConflictingOptions { ref opt_b, .. } if opt_b == "(no --output)"
+            ));
+
+            for (opt_b, set) in [
+                (
+                    "--batch",
+                    (|c: &mut Config| c.batch_mode = true) as fn(&mut Config),
+                ),
+                ("--append", |c| c.output.append = true),
+                ("--compress", |c| c.output.compress = true),
+                ("--count", |c| c.render.count_only = true),
+                ("--prune", |c| c.matching.prune_empty = true),
+            ] {
+                let mut config = Config::with_root(PathBuf::from("."));
+                config.output.checkpoint = true;
+                config.output.output_path = Some(PathBuf::from("tree.txt"));
+                set(&mut config);
+                let err = config.validate().unwrap_err();
+                assert!(
+                    matches!(err, ConfigError::ConflictingOptions { ref opt_a, opt_b: ref b, .. }
+                        if opt_a == "--checkpoint" && b == opt_b),
+                    "{opt_b} 应与 --checkpoint 冲突"
+                );
+            }
+        }
+
+        #[test]
+        fn fails_append_with_no_clobber() {
+            let mut config = Config::with_root(PathBuf::from("."));
             config.output.output_path = Some(PathBuf::from("tree.txt"));
-            let result = config.validate();
-            assert!(result.is_ok());
+            config.output.append = true;
+            config.output.no_clobber = true;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_b, .. } if opt_b == "--no-clobber"
+            ));
         }
 
         #[test]
-        fn succeeds_json_output_with_batch() {
-            let mut config = Config::default();
+        fn fails_append_with_structured_output() {
+            let mut config = Config::with_root(PathBuf::from("."));
             config.output.output_path = Some(PathBuf::from("tree.json"));
             config.batch_mode = true;
-            let validated = config.validate().unwrap();
-            assert_eq!(validated.output.format, OutputFormat::Json);
+            config.output.append = true;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--append"
+            ));
         }
 
         #[test]
-        fn succeeds_yaml_output_with_batch() {
-            let mut config = Config::default();
-            config.output.output_path = Some(PathBuf::from("tree.yaml"));
-            config.batch_mode = true;
-            let result = config.validate();
-            assert!(result.is_ok());
+        fn fails_bom_with_gbk() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.encoding = OutputEncoding::Gbk;
+            config.output.bom = true;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, .. } if opt_a == "--bom"
+            ));
         }
 
         #[test]
-        fn succeeds_toml_output_with_batch() {
-            let mut config = Config::default();
-            config.output.output_path = Some(PathBuf::from("tree.toml"));
-            config.batch_mode = true;
-            let result = config.validate();
-            assert!(result.is_ok());
+        fn succeeds_bom_with_utf16le() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.encoding = OutputEncoding::Utf16Le;
+            config.output.bom = true;
+            assert!(config.validate().is_ok());
+        }
+
+        #[test]
+        fn fails_relative_dates_with_date_format() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.relative_dates = true;
+            config.render.date_format = Some("%Y".to_string());
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_b, .. } if opt_b == "--date-format"
+            ));
         }
     }
 
@@ -1429,6 +4355,43 @@ mod tests {
             assert!(validated.render.show_size);
         }
 
+        #[test]
+        fn si_units_enable_human_readable_sizes() {
+            let mut config = Config::default();
+            config.render.si_units = true;
+            let validated = config.validate().unwrap();
+            assert!(validated.render.human_readable);
+            assert!(validated.render.show_size);
+        }
+
+        #[test]
+        fn date_options_enable_show_date() {
+            let mut config = Config::default();
+            config.render.relative_dates = true;
+            assert!(config.validate().unwrap().render.show_date);
+
+            let mut config = Config::default();
+            config.render.date_format = Some("%d.%m.%Y".to_string());
+            assert!(config.validate().unwrap().render.show_date);
+        }
+
+        #[test]
+        fn count_enables_files_and_sizes() {
+            let mut config = Config::default();
+            config.render.count_only = true;
+            let validated = config.validate().unwrap();
+            assert!(validated.scan.show_files, "--count 应统计文件");
+            assert!(validated.needs_size_info());
+        }
+
+        #[test]
+        fn date_options_keep_other_dates_alone() {
+            let mut config = Config::default();
+            config.render.relative_dates = true;
+            config.render.show_created = true;
+            assert!(!config.validate().unwrap().render.show_date);
+        }
+
         #[test]
         fn disk_usage_enables_show_size() {
             let mut config = Config::default();
@@ -1447,6 +4410,125 @@ mod tests {
             let validated = config.validate().unwrap();
             assert!(validated.render.show_size);
         }
+
+        #[test]
+        fn paths_from_enables_batch_mode() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.paths_from = Some(PathListSource::Stdin);
+            let validated = config.validate().unwrap();
+            assert!(validated.batch_mode);
+        }
+
+        #[test]
+        fn leaf_depth_enables_batch_mode() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.leaf_depth = Some(3);
+            let validated = config.validate().unwrap();
+            assert!(validated.batch_mode);
+        }
+
+        #[test]
+        fn group_by_enables_batch_mode() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.group_by = GroupBy::Type;
+            let validated = config.validate().unwrap();
+            assert!(validated.batch_mode);
+        }
+
+        #[test]
+        fn serializes_resolved_values_as_toml() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.sort_key = SortKey::Modified;
+            config.render.language = Language::Chinese;
+            config.scan.read_timeout = Some(Duration::from_millis(1500));
+            config.scan.paths_from = Some(PathListSource::Stdin);
+            let validated = config.validate().unwrap();
+
+            let text = toml::to_string_pretty(&validated).unwrap();
+            assert!(
+                text.contains("sort_key = \"mtime\""),
+                "应使用命令行取值: {text}"
+            );
+            assert!(text.contains("language = \"zh\""), "{text}");
+            assert!(text.contains("read_timeout = \"1500ms\""), "{text}");
+            assert!(text.contains("paths_from = \"-\""), "{text}");
+            assert!(
+                text.contains("batch_mode = true"),
+                "应包含推导出的选项: {text}"
+            );
+            assert!(!text.contains("\ntimeout"), "未设置的选项应省略: {text}");
+            assert!(!text.contains("\nwarnings"), "{text}");
+        }
+
+        #[test]
+        fn fails_install_with_uninstall_context_menu() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.install_menu = Some(MenuScope::User);
+            config.uninstall_menu = Some(MenuScope::Machine);
+            let err = config.validate().unwrap_err();
+            assert!(matches!(err, ConfigError::ConflictingOptions { .. }));
+        }
+
+        #[test]
+        fn log_file_implies_info_verbosity() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.log_file = Some(PathBuf::from("scan.log"));
+            let validated = config.validate().unwrap();
+            assert_eq!(
+                validated.output.verbosity, 1,
+                "日志文件应默认启用 info 级别"
+            );
+
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.output.log_file = Some(PathBuf::from("scan.log"));
+            config.output.verbosity = 2;
+            let validated = config.validate().unwrap();
+            assert_eq!(validated.output.verbosity, 2, "显式指定的级别应保留");
+        }
+
+        #[test]
+        fn fails_group_by_with_flat() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.render.group_by = GroupBy::Type;
+            config.render.flat = true;
+            let err = config.validate().unwrap_err();
+            assert!(matches!(
+                err,
+                ConfigError::ConflictingOptions { ref opt_a, ref opt_b, .. }
+                    if opt_a == "--group-by" && opt_b == "--flat"
+            ));
+        }
+
+        #[test]
+        fn paths_from_allows_structured_output_without_batch() {
+            let mut config = Config::with_root(PathBuf::from("."));
+            config.scan.paths_from = Some(PathListSource::File(PathBuf::from("list.txt")));
+            config.output.output_path = Some(PathBuf::from("tree.json"));
+            let validated = config.validate().unwrap();
+            assert_eq!(validated.output.format, OutputFormat::Json);
+            assert!(validated.batch_mode);
+        }
+    }
+
+    mod path_list_source_tests {
+        use super::*;
+
+        #[test]
+        fn dash_selects_stdin() {
+            assert_eq!(PathListSource::from_arg("-"), PathListSource::Stdin);
+        }
+
+        #[test]
+        fn other_values_select_file() {
+            assert_eq!(
+                PathListSource::from_arg("list.txt"),
+                PathListSource::File(PathBuf::from("list.txt"))
+            );
+            assert_eq!(
+                PathListSource::from_arg("--"),
+                PathListSource::File(PathBuf::from("--"))
+            );
+        }
     }
 
     mod config_batch_mode_tests {
@@ -1460,8 +4542,10 @@ mod tests {
 
         #[test]
         fn can_be_enabled() {
-            let mut config = Config::default();
-            config.batch_mode = true;
+            let config = Config {
+                batch_mode: true,
+                ..Default::default()
+            };
             assert!(config.batch_mode);
         }
     }
@@ -1471,8 +4555,10 @@ mod tests {
 
         #[test]
         fn all_options_enabled_validates() {
-            let mut config = Config::default();
-            config.batch_mode = true;
+            let mut config = Config {
+                batch_mode: true,
+                ..Default::default()
+            };
             config.scan.show_files = true;
             config.scan.max_depth = Some(10);
             config.scan.respect_gitignore = true;
@@ -1648,11 +4734,8 @@ mod tests {
         #[test]
         fn multiple_patterns_are_valid() {
             let mut config = Config::default();
-            config.matching.include_patterns = vec![
-                "*.rs".to_string(),
-                "*.toml".to_string(),
-                "*.md".to_string(),
-            ];
+            config.matching.include_patterns =
+                vec!["*.rs".to_string(), "*.toml".to_string(), "*.md".to_string()];
             config.matching.exclude_patterns = vec![
                 "target".to_string(),
                 "node_modules".to_string(),
@@ -0,0 +1,214 @@
+//! Explorer context-menu module: `--install-context-menu`.
+//!
+//! Adds an "Open tree++ here" entry to the right-click menu of folders, and
+//! of the background of an open folder, which opens a console showing the
+//! tree of that folder. The entry is a `shell` verb below `Software\Classes`:
+//!
+//! | Key                                 | Shown when right-clicking   |
+//! |-------------------------------------|-----------------------------|
+//! | `Directory\shell\treepp`            | A folder                    |
+//! | `Directory\Background\shell\treepp` | The background of a folder  |
+//!
+//! The `user` scope writes below `HKEY_CURRENT_USER` and needs no
+//! elevation; the `machine` scope writes below `HKEY_LOCAL_MACHINE` for all
+//! users and must be run from an elevated prompt. The command refers to the
+//! executable by its current path, so the entry has to be installed again
+//! after tree++ is moved.
+//!
+//! File: src/contextmenu.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::io;
+use std::path::Path;
+
+use crate::config::MenuScope;
+use crate::error::OutputError;
+use crate::winvol::{self, RegistryHive};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Verb keys of the entry, relative to the hive of the scope.
+pub const VERB_KEYS: [&str; 2] = [
+    r"Software\Classes\Directory\shell\treepp",
+    r"Software\Classes\Directory\Background\shell\treepp",
+];
+
+// ============================================================================
+// Public Functions
+// ============================================================================
+
+/// Builds the command run by the entry.
+///
+/// `%V` is replaced by Explorer with the clicked folder. `cmd /K` keeps the
+/// console open once the tree is printed; it strips the outermost pair of
+/// quotes, which leaves the program and the folder quoted.
+///
+/// # Arguments
+///
+/// * `exe` - Path of the tree++ executable
+///
+/// # Returns
+///
+/// The command line stored as the default value of the `command` key.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::contextmenu::menu_command;
+///
+/// assert_eq!(
+///     menu_command(Path::new(r"C:\Tools\treepp.exe")),
+///     r#"cmd.exe /K ""C:\Tools\treepp.exe" "%V"""#
+/// );
+/// ```
+#[must_use]
+pub fn menu_command(exe: &Path) -> String {
+    format!(r#"cmd.exe /K ""{}" "%V"""#, exe.display())
+}
+
+/// Adds the entry to the folder context menu, replacing an earlier one.
+///
+/// # Arguments
+///
+/// * `scope` - Whose menu to change
+/// * `label` - Text of the entry
+/// * `exe` - Path of the tree++ executable
+///
+/// # Errors
+///
+/// Returns `OutputError::RegistryFailed` if a key cannot be written, e.g.
+/// for the `machine` scope without elevation.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use treepp::config::MenuScope;
+/// use treepp::contextmenu::install;
+///
+/// install(MenuScope::User, "Open tree++ here", Path::new(r"C:\Tools\treepp.exe")).unwrap();
+/// ```
+pub fn install(scope: MenuScope, label: &str, exe: &Path) -> Result<(), OutputError> {
+    let hive = hive_of(scope);
+    let command = menu_command(exe);
+    let icon = exe.display().to_string();
+
+    for key in VERB_KEYS {
+        let command_key = format!(r"{key}\command");
+        winvol::set_registry_string(hive, key, None, label)
+            .and_then(|()| winvol::set_registry_string(hive, key, Some("Icon"), &icon))
+            .map_err(|source| registry_error(hive, key, source))?;
+        winvol::set_registry_string(hive, &command_key, None, &command)
+            .map_err(|source| registry_error(hive, &command_key, source))?;
+    }
+    Ok(())
+}
+
+/// Removes the entry from the folder context menu.
+///
+/// # Arguments
+///
+/// * `scope` - Whose menu to change
+///
+/// # Returns
+///
+/// `true` if an entry was removed, `false` if none was installed.
+///
+/// # Errors
+///
+/// Returns `OutputError::RegistryFailed` if an existing key cannot be
+/// deleted.
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::config::MenuScope;
+/// use treepp::contextmenu::uninstall;
+///
+/// if !uninstall(MenuScope::User).unwrap() {
+///     println!("nothing to remove");
+/// }
+/// ```
+pub fn uninstall(scope: MenuScope) -> Result<bool, OutputError> {
+    let hive = hive_of(scope);
+    let mut removed = false;
+
+    for key in VERB_KEYS {
+        match winvol::delete_registry_tree(hive, key) {
+            Ok(()) => removed = true,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(source) => return Err(registry_error(hive, key, source)),
+        }
+    }
+    Ok(removed)
+}
+
+// ============================================================================
+// Internal Functions
+// ============================================================================
+
+/// Returns the hive written for a scope.
+const fn hive_of(scope: MenuScope) -> RegistryHive {
+    match scope {
+        MenuScope::User => RegistryHive::CurrentUser,
+        MenuScope::Machine => RegistryHive::LocalMachine,
+    }
+}
+
+/// Returns the full path of a key as shown by `regedit`.
+fn key_path(hive: RegistryHive, key: &str) -> String {
+    let root = match hive {
+        RegistryHive::CurrentUser => "HKEY_CURRENT_USER",
+        RegistryHive::LocalMachine => "HKEY_LOCAL_MACHINE",
+    };
+    format!(r"{root}\{key}")
+}
+
+/// Wraps a system error with the key it occurred on.
+fn registry_error(hive: RegistryHive, key: &str, source: io::Error) -> OutputError {
+    OutputError::RegistryFailed {
+        key: key_path(hive, key),
+        source,
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_quote_program_and_folder_in_command() {
+        let command = menu_command(Path::new(r"C:\Program Files\tree++\treepp.exe"));
+        assert_eq!(
+            command,
+            r#"cmd.exe /K ""C:\Program Files\tree++\treepp.exe" "%V"""#
+        );
+    }
+
+    #[test]
+    fn should_write_scope_to_matching_hive() {
+        assert_eq!(hive_of(MenuScope::User), RegistryHive::CurrentUser);
+        assert_eq!(hive_of(MenuScope::Machine), RegistryHive::LocalMachine);
+    }
+
+    #[test]
+    fn should_report_full_key_path() {
+        let err = registry_error(
+            RegistryHive::LocalMachine,
+            VERB_KEYS[1],
+            io::Error::from(io::ErrorKind::PermissionDenied),
+        );
+        assert!(matches!(err, OutputError::RegistryFailed { ref key, .. }
+            if key == r"HKEY_LOCAL_MACHINE\Software\Classes\Directory\Background\shell\treepp"));
+    }
+}
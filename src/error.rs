@@ -13,12 +13,12 @@
 //!
 //! File: src/error.rs
 //! Author: WaterRun
-//! Date: 2026-01-13
+//! Date: 2026-10-16
 
 #![forbid(unsafe_code)]
 
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 pub use crate::config::ConfigError;
@@ -81,6 +81,175 @@ pub enum TreeppError {
     Output(#[from] OutputError),
 }
 
+impl TreeppError {
+    /// Returns the stable code of the error, reported by `--json-errors`.
+    ///
+    /// Codes never change meaning between releases. The first digit is the
+    /// exit code the error leads to: `E1xx` for arguments and configuration,
+    /// `E2xx` for scanning and matching, `E3xx` for rendering and output.
+    ///
+    /// # Returns
+    ///
+    /// The code, e.g. `E101` for an unknown option.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::error::{ScanError, TreeppError};
+    ///
+    /// let err: TreeppError = ScanError::PathNotFound { path: PathBuf::from("X:\\") }.into();
+    /// assert_eq!(err.code(), "E201");
+    /// ```
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::Cli(e) => match e {
+                CliError::UnknownOption { .. } => "E101",
+                CliError::MissingValue { .. } => "E102",
+                CliError::InvalidValue { .. } => "E103",
+                CliError::DuplicateOption { .. } => "E104",
+                CliError::ConflictingOptions { .. } => "E105",
+                CliError::MultiplePaths { .. } => "E106",
+                CliError::InvalidPath { .. } => "E107",
+                CliError::ValueOptionInCluster { .. } => "E108",
+                CliError::ParseError { .. } => "E109",
+                CliError::RecursivePreset { .. } => "E110",
+                CliError::InvalidConfig(e) => config_code(e),
+            },
+            Self::Config(e) => config_code(e),
+            Self::Scan(e) => match e {
+                ScanError::PathNotFound { .. } => "E201",
+                ScanError::NotADirectory { .. } => "E202",
+                ScanError::PermissionDenied { .. } => "E203",
+                ScanError::ReadDirFailed { .. } => "E204",
+                ScanError::MetadataFailed { .. } => "E205",
+                ScanError::CanonicalizeFailed { .. } => "E206",
+                ScanError::WalkError { .. } => "E207",
+                ScanError::PathListReadFailed { .. } => "E208",
+                ScanError::PathOutsideRoot { .. } => "E209",
+                ScanError::GitIndexFailed { .. } => "E210",
+                ScanError::OutputLimitReached { .. } => "E211",
+                ScanError::OutputClosed => "E212",
+            },
+            Self::Match(e) => match e {
+                MatchError::InvalidPattern { .. } => "E251",
+                MatchError::GitignoreParseError { .. } => "E252",
+                MatchError::GitignoreBuildError { .. } => "E253",
+            },
+            Self::Render(e) => match e {
+                RenderError::FormatError { .. } => "E301",
+                RenderError::InvalidUtf8Path { .. } => "E302",
+                RenderError::BannerFetchFailed { .. } => "E303",
+                RenderError::InvalidPath { .. } => "E304",
+            },
+            Self::Output(e) => match e {
+                OutputError::FileCreateFailed { .. } => "E351",
+                OutputError::WriteFailed { .. } => "E352",
+                OutputError::SerializationFailed { .. } => "E353",
+                OutputError::StdoutFailed { .. } => "E354",
+                OutputError::InvalidOutputPath { .. } => "E355",
+                OutputError::FileExists { .. } => "E356",
+                OutputError::RegistryFailed { .. } => "E357",
+                OutputError::ListenFailed { .. } => "E358",
+            },
+        }
+    }
+
+    /// Returns the category of the error, reported by `--json-errors`.
+    ///
+    /// Unlike the category printed to the console, this does not depend on
+    /// `--lang`.
+    ///
+    /// # Returns
+    ///
+    /// One of `cli`, `config`, `scan`, `match`, `render` and `output`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::error::{CliError, TreeppError};
+    ///
+    /// let err: TreeppError = CliError::UnknownOption { option: "/Z".to_string() }.into();
+    /// assert_eq!(err.category(), "cli");
+    /// ```
+    #[must_use]
+    pub const fn category(&self) -> &'static str {
+        match self {
+            Self::Cli(CliError::InvalidConfig(_)) | Self::Config(_) => "config",
+            Self::Cli(_) => "cli",
+            Self::Scan(_) => "scan",
+            Self::Match(_) => "match",
+            Self::Render(_) => "render",
+            Self::Output(_) => "output",
+        }
+    }
+
+    /// Returns the file or directory the error is about, if any.
+    ///
+    /// # Returns
+    ///
+    /// The path, or `None` for errors that do not concern a single path,
+    /// such as an unknown option.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    /// use treepp::error::{OutputError, TreeppError};
+    ///
+    /// let err: TreeppError = OutputError::FileExists { path: PathBuf::from("tree.txt") }.into();
+    /// assert_eq!(err.path(), Some(Path::new("tree.txt")));
+    /// ```
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Cli(CliError::InvalidPath { arg }) => Some(Path::new(arg)),
+            Self::Cli(CliError::InvalidConfig(
+                ConfigError::InvalidPath { path, .. }
+                | ConfigError::UnknownOutputFormat { path }
+                | ConfigError::InvalidSettings { path, .. },
+            ))
+            | Self::Config(
+                ConfigError::InvalidPath { path, .. }
+                | ConfigError::UnknownOutputFormat { path }
+                | ConfigError::InvalidSettings { path, .. },
+            )
+            | Self::Scan(
+                ScanError::PathNotFound { path }
+                | ScanError::NotADirectory { path }
+                | ScanError::PermissionDenied { path }
+                | ScanError::ReadDirFailed { path, .. }
+                | ScanError::MetadataFailed { path, .. }
+                | ScanError::CanonicalizeFailed { path, .. }
+                | ScanError::PathOutsideRoot { path, .. }
+                | ScanError::GitIndexFailed { path, .. },
+            )
+            | Self::Match(MatchError::GitignoreParseError { path, .. })
+            | Self::Render(RenderError::InvalidPath { path, .. })
+            | Self::Output(
+                OutputError::FileCreateFailed { path, .. }
+                | OutputError::WriteFailed { path, .. }
+                | OutputError::InvalidOutputPath { path, .. }
+                | OutputError::FileExists { path },
+            ) => Some(path),
+            Self::Scan(ScanError::WalkError { path, .. }) => path.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the stable code of a configuration error (see `TreeppError::code`).
+const fn config_code(err: &ConfigError) -> &'static str {
+    match err {
+        ConfigError::ConflictingOptions { .. } => "E151",
+        ConfigError::InvalidValue { .. } => "E152",
+        ConfigError::InvalidPath { .. } => "E153",
+        ConfigError::UnknownOutputFormat { .. } => "E154",
+        ConfigError::InvalidSettings { .. } => "E155",
+    }
+}
+
 /// Result type alias for tree++ operations.
 ///
 /// A convenience alias for `Result<T, TreeppError>` used throughout the crate.
@@ -150,6 +319,8 @@ pub enum CliError {
         value: String,
         /// The reason for invalidity.
         reason: String,
+        /// The closest accepted value, if one is near enough to suggest.
+        suggestion: Option<String>,
     },
 
     /// Option was specified more than once.
@@ -182,12 +353,32 @@ pub enum CliError {
         arg: String,
     },
 
+    /// A value-taking option appeared before the end of a short option cluster.
+    #[error("Option {option} takes a value and must come last in {cluster}")]
+    ValueOptionInCluster {
+        /// The value-taking short option.
+        option: String,
+        /// The whole cluster as given.
+        cluster: String,
+    },
+
     /// Generic parsing error.
     #[error("Argument parsing failed: {message}")]
     ParseError {
         /// Error message.
         message: String,
     },
+
+    /// A preset expands, directly or through other presets, to itself.
+    #[error("Preset @{name} refers to itself")]
+    RecursivePreset {
+        /// Name of the preset, without `@`.
+        name: String,
+    },
+
+    /// The parsed options failed validation, e.g. two that conflict.
+    #[error(transparent)]
+    InvalidConfig(#[from] ConfigError),
 }
 
 /// Directory scanning errors.
@@ -278,6 +469,51 @@ pub enum ScanError {
         /// Related path, if available.
         path: Option<PathBuf>,
     },
+
+    /// Failed to read a path list (`--paths-from`).
+    #[error("Failed to read path list: {origin}")]
+    PathListReadFailed {
+        /// Where the list was read from (file path or `stdin`).
+        origin: String,
+        /// The underlying IO error.
+        #[source]
+        source: io::Error,
+    },
+
+    /// A listed path lies outside the root directory.
+    #[error("Listed path is outside the root directory: {path} (root: {root})")]
+    PathOutsideRoot {
+        /// The offending listed path.
+        path: PathBuf,
+        /// The root directory.
+        root: PathBuf,
+    },
+
+    /// Failed to load the git index (`--git-tracked`).
+    #[error("Failed to load git index: {path} ({reason})")]
+    GitIndexFailed {
+        /// The index file or scan root involved.
+        path: PathBuf,
+        /// Description of the failure.
+        reason: String,
+    },
+
+    /// The `--max-output-size` limit was reached.
+    ///
+    /// Returned by the streaming output callback to stop the scan; the
+    /// scanner treats it as a truncation rather than a failure.
+    #[error("Output size limit of {limit} bytes reached")]
+    OutputLimitReached {
+        /// The configured limit in bytes.
+        limit: u64,
+    },
+
+    /// The reader of stdout went away, for example a pager that was quit.
+    ///
+    /// Returned by the streaming output callback to stop the scan; the
+    /// scanner treats it as the end of the output rather than a failure.
+    #[error("Output was closed by its reader")]
+    OutputClosed,
 }
 
 impl ScanError {
@@ -321,7 +557,7 @@ impl ScanError {
     /// use std::path::PathBuf;
     /// use treepp::error::ScanError;
     ///
-    /// let io_err = io::Error::new(ErrorKind::Other, "other error");
+    /// let io_err = io::Error::other("other error");
     /// let scan_err = ScanError::from_io_error(io_err, PathBuf::from("/path"));
     /// assert!(matches!(scan_err, ScanError::ReadDirFailed { .. }));
     /// ```
@@ -605,6 +841,35 @@ pub enum OutputError {
         /// The reason.
         reason: String,
     },
+
+    /// Output file exists and `--no-clobber` forbids replacing it.
+    #[error(
+        "Output file already exists: {path} (remove it, or use --append instead of --no-clobber)"
+    )]
+    FileExists {
+        /// Output path.
+        path: PathBuf,
+    },
+
+    /// Failed to change a registry key (`--install-context-menu`).
+    #[error("Failed to change registry key: {key}")]
+    RegistryFailed {
+        /// Full path of the key, including the hive.
+        key: String,
+        /// The underlying system error.
+        #[source]
+        source: io::Error,
+    },
+
+    /// Failed to listen for connections (`--serve`).
+    #[error("Failed to listen on {address}")]
+    ListenFailed {
+        /// The local address, e.g. `127.0.0.1:7878`.
+        address: String,
+        /// The underlying IO error.
+        #[source]
+        source: io::Error,
+    },
 }
 
 impl OutputError {
@@ -874,6 +1139,7 @@ mod tests {
             option: "--thread".to_string(),
             value: "abc".to_string(),
             reason: "必须是正整数".to_string(),
+            suggestion: None,
         };
         let msg = err.to_string();
         assert!(msg.contains("--thread"));
@@ -921,6 +1187,14 @@ mod tests {
         assert!(err.to_string().contains("unexpected token"));
     }
 
+    #[test]
+    fn cli_error_recursive_preset_names_preset() {
+        let err = CliError::RecursivePreset {
+            name: "docs".to_string(),
+        };
+        assert_eq!(err.to_string(), "Preset @docs refers to itself");
+    }
+
     #[test]
     fn cli_errors_compare_equal_when_identical() {
         let err1 = CliError::UnknownOption {
@@ -957,7 +1231,7 @@ mod tests {
 
     #[test]
     fn scan_error_from_io_other_creates_read_dir_failed() {
-        let io_err = io::Error::new(ErrorKind::Other, "some error");
+        let io_err = io::Error::other("some error");
         let path = PathBuf::from("/some/path");
         let scan_err = ScanError::from_io_error(io_err, path.clone());
 
@@ -992,6 +1266,55 @@ mod tests {
         assert!(msg.contains("not a directory"));
     }
 
+    #[test]
+    fn scan_error_path_list_read_failed_formats_correctly() {
+        let err = ScanError::PathListReadFailed {
+            origin: "stdin".to_string(),
+            source: io::Error::new(ErrorKind::BrokenPipe, "closed"),
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("path list"));
+        assert!(msg.contains("stdin"));
+    }
+
+    #[test]
+    fn scan_error_path_outside_root_formats_correctly() {
+        let err = ScanError::PathOutsideRoot {
+            path: PathBuf::from(r"D:\other\file.txt"),
+            root: PathBuf::from(r"C:\project"),
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("outside the root"));
+        assert!(msg.contains(r"D:\other\file.txt"));
+        assert!(msg.contains(r"C:\project"));
+    }
+
+    #[test]
+    fn scan_error_git_index_failed_formats_correctly() {
+        let err = ScanError::GitIndexFailed {
+            path: PathBuf::from(r"C:\project\.git\index"),
+            reason: "missing DIRC signature".to_string(),
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("git index"));
+        assert!(msg.contains(r"C:\project\.git\index"));
+        assert!(msg.contains("missing DIRC signature"));
+    }
+
+    #[test]
+    fn scan_error_output_limit_reached_formats_correctly() {
+        let err = ScanError::OutputLimitReached { limit: 1024 };
+        let msg = err.to_string();
+        assert!(msg.contains("limit"));
+        assert!(msg.contains("1024"));
+    }
+
+    #[test]
+    fn scan_error_output_closed_formats_correctly() {
+        let msg = ScanError::OutputClosed.to_string();
+        assert!(msg.contains("closed"));
+    }
+
     #[test]
     fn scan_error_canonicalize_failed_formats_correctly() {
         let err = ScanError::CanonicalizeFailed {
@@ -1179,7 +1502,7 @@ mod tests {
     fn output_error_write_failed_formats_correctly() {
         let err = OutputError::WriteFailed {
             path: PathBuf::from("output.txt"),
-            source: io::Error::new(ErrorKind::Other, "disk full"),
+            source: io::Error::other("disk full"),
         };
         let msg = err.to_string();
         assert!(msg.contains("output.txt"));
@@ -1196,6 +1519,37 @@ mod tests {
         assert!(msg.contains("Invalid output path"));
     }
 
+    #[test]
+    fn output_error_file_exists_formats_correctly() {
+        let err = OutputError::FileExists {
+            path: PathBuf::from("tree.txt"),
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("tree.txt"));
+        assert!(msg.contains("--no-clobber"));
+    }
+
+    #[test]
+    fn output_error_registry_failed_formats_correctly() {
+        let err = OutputError::RegistryFailed {
+            key: r"HKEY_LOCAL_MACHINE\Software\Classes\Directory\shell\treepp".to_string(),
+            source: io::Error::from(io::ErrorKind::PermissionDenied),
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("registry key"));
+        assert!(msg.contains(r"Directory\shell\treepp"));
+    }
+
+    #[test]
+    fn output_error_listen_failed_formats_correctly() {
+        let err = OutputError::ListenFailed {
+            address: "127.0.0.1:7878".to_string(),
+            source: io::Error::from(io::ErrorKind::AddrInUse),
+        };
+        assert!(err.to_string().contains("127.0.0.1:7878"));
+        assert_eq!(TreeppError::from(err).code(), "E358");
+    }
+
     #[test]
     fn path_display_handles_valid_utf8_path() {
         let path = std::path::Path::new("C:\\Users\\test\\file.txt");
@@ -1223,7 +1577,7 @@ mod tests {
     fn is_recoverable_returns_true_for_metadata_failed() {
         let err = ScanError::MetadataFailed {
             path: PathBuf::from("/test"),
-            source: io::Error::new(ErrorKind::Other, "test"),
+            source: io::Error::other("test"),
         };
         assert!(is_recoverable(&err));
     }
@@ -1257,7 +1611,7 @@ mod tests {
     fn is_recoverable_returns_false_for_read_dir_failed() {
         let err = ScanError::ReadDirFailed {
             path: PathBuf::from("/test"),
-            source: io::Error::new(ErrorKind::Other, "test"),
+            source: io::Error::other("test"),
         };
         assert!(!is_recoverable(&err));
     }
@@ -1266,14 +1620,14 @@ mod tests {
     fn is_recoverable_returns_false_for_canonicalize_failed() {
         let err = ScanError::CanonicalizeFailed {
             path: PathBuf::from("/test"),
-            source: io::Error::new(ErrorKind::Other, "test"),
+            source: io::Error::other("test"),
         };
         assert!(!is_recoverable(&err));
     }
 
     #[test]
     fn scan_error_read_dir_failed_preserves_source() {
-        let io_err = io::Error::new(ErrorKind::Other, "underlying error");
+        let io_err = io::Error::other("underlying error");
         let err = ScanError::ReadDirFailed {
             path: PathBuf::from("/test"),
             source: io_err,
@@ -1285,7 +1639,7 @@ mod tests {
 
     #[test]
     fn scan_error_metadata_failed_preserves_source() {
-        let io_err = io::Error::new(ErrorKind::Other, "metadata error");
+        let io_err = io::Error::other("metadata error");
         let err = ScanError::MetadataFailed {
             path: PathBuf::from("/test"),
             source: io_err,
@@ -1321,7 +1675,7 @@ mod tests {
 
     #[test]
     fn output_error_write_failed_preserves_source() {
-        let io_err = io::Error::new(ErrorKind::Other, "write error");
+        let io_err = io::Error::other("write error");
         let err = OutputError::WriteFailed {
             path: PathBuf::from("test.txt"),
             source: io_err,
@@ -1350,7 +1704,7 @@ mod tests {
             Err(CliError::UnknownOption {
                 option: "/X".to_string(),
             }
-                .into())
+            .into())
         }
 
         assert_eq!(test_ok().unwrap(), 42);
@@ -1366,6 +1720,18 @@ mod tests {
         assert!(msg.contains(":::invalid:::"));
     }
 
+    #[test]
+    fn cli_error_value_option_in_cluster_formats_correctly() {
+        let err = CliError::ValueOptionInCluster {
+            option: "-L".to_string(),
+            cluster: "-Lf".to_string(),
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("-L"));
+        assert!(msg.contains("-Lf"));
+        assert!(msg.contains("last"));
+    }
+
     #[test]
     fn cli_error_clone_produces_equal_value() {
         let err = CliError::UnknownOption {
@@ -1419,4 +1785,77 @@ mod tests {
             _ => panic!("Expected WalkError variant"),
         }
     }
+
+    #[test]
+    fn error_code_starts_with_exit_code_digit() {
+        let cases: Vec<(TreeppError, &str, &str)> = vec![
+            (
+                CliError::UnknownOption {
+                    option: "/Z".to_string(),
+                }
+                .into(),
+                "E101",
+                "cli",
+            ),
+            (
+                ConfigError::UnknownOutputFormat {
+                    path: PathBuf::from("a.xml"),
+                }
+                .into(),
+                "E154",
+                "config",
+            ),
+            (
+                CliError::from(ConfigError::InvalidPath {
+                    path: PathBuf::from("x"),
+                    reason: "missing".to_string(),
+                })
+                .into(),
+                "E153",
+                "config",
+            ),
+            (ScanError::OutputClosed.into(), "E212", "scan"),
+            (
+                MatchError::from_glob_error("[", "unclosed").into(),
+                "E251",
+                "match",
+            ),
+            (
+                RenderError::BannerFetchFailed {
+                    reason: "x".to_string(),
+                }
+                .into(),
+                "E303",
+                "render",
+            ),
+            (OutputError::json_error("x").into(), "E353", "output"),
+        ];
+        for (err, code, category) in cases {
+            assert_eq!(err.code(), code, "{err} 的错误码不正确");
+            assert_eq!(err.category(), category, "{err} 的类别不正确");
+        }
+    }
+
+    #[test]
+    fn error_path_is_reported_when_known() {
+        let err: TreeppError = ScanError::ReadDirFailed {
+            path: PathBuf::from("/data"),
+            source: io::Error::other("x"),
+        }
+        .into();
+        assert_eq!(err.path(), Some(Path::new("/data")));
+
+        let err: TreeppError = ScanError::WalkError {
+            message: "x".to_string(),
+            path: None,
+        }
+        .into();
+        assert_eq!(err.path(), None, "无路径的遍历错误不应报告路径");
+
+        let err: TreeppError = CliError::MissingValue {
+            option: "/L".to_string(),
+        }
+        .into();
+        assert_eq!(err.path(), None);
+    }
 }
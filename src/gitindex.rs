@@ -0,0 +1,630 @@
+//! Git index module: reads the set of tracked files from `.git/index`.
+//!
+//! This module backs the `--git-tracked` option. The index is parsed directly
+//! (no `git` executable is required) and loaded exactly once per run:
+//!
+//! - **Repository discovery**: walks up from the scan root to the nearest
+//!   `.git` directory or `.git` file (worktrees and submodules use
+//!   `gitdir: <path>` indirection files)
+//! - **Index formats**: versions 2, 3 and 4 (path prefix compression), with
+//!   SHA-1 or SHA-256 object ids; the directory entries of a sparse index
+//!   are skipped, as nothing below them is checked out
+//! - **Lookup**: tracked files plus all of their ancestor directories, keyed
+//!   by `/`-separated paths relative to the work tree
+//!
+//! Parsing the on-disk format avoids adding a full git implementation as a
+//! dependency; only the entry table is read and extensions are ignored.
+//!
+//! File: src/gitindex.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::ScanError;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Index file signature ("DIRC", directory cache).
+const INDEX_SIGNATURE: &[u8; 4] = b"DIRC";
+
+/// Size of the index header (signature, version, entry count).
+const HEADER_LEN: usize = 12;
+
+/// Size of the fixed stat fields preceding the object id in each entry.
+const STAT_FIELDS_LEN: usize = 40;
+
+/// Object id length for SHA-1 repositories.
+const SHA1_LEN: usize = 20;
+
+/// Object id length for SHA-256 repositories.
+const SHA256_LEN: usize = 32;
+
+/// Flag bit marking an entry that carries an additional 16-bit flags field.
+const EXTENDED_FLAG: u16 = 0x4000;
+
+/// Offset of the mode field within the stat fields of an entry.
+const MODE_OFFSET: usize = 24;
+
+/// Mode of a sparse-index entry standing for a whole directory that is not
+/// checked out.
+const SPARSE_DIRECTORY_MODE: u32 = 0o040000;
+
+/// Size of the smallest possible entry: stat fields, a SHA-1 object id and
+/// the flags, before the name.
+const MIN_ENTRY_LEN: usize = STAT_FIELDS_LEN + SHA1_LEN + 2;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Set of paths tracked by a git repository's index.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use treepp::gitindex::GitIndex;
+///
+/// let index = GitIndex::discover(Path::new(".")).unwrap();
+/// assert!(index.is_tracked(&index.work_tree().join("Cargo.toml"), false));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GitIndex {
+    /// Work tree root that index paths are relative to.
+    work_tree: PathBuf,
+    /// Tracked file paths (`/`-separated, relative to the work tree).
+    files: HashSet<String>,
+    /// Ancestor directories of tracked files (`/`-separated, relative).
+    directories: HashSet<String>,
+}
+
+impl GitIndex {
+    /// Locates the repository containing `start` and loads its index.
+    ///
+    /// A repository without an index file (no commits or staged files yet)
+    /// yields an empty set.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Directory to start the upward `.git` search from.
+    ///
+    /// # Returns
+    ///
+    /// The loaded `GitIndex`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScanError::GitIndexFailed` if no repository is found, or if
+    /// the index cannot be read or parsed.
+    pub fn discover(start: &Path) -> Result<Self, ScanError> {
+        let (work_tree, git_dir) =
+            find_repository(start).ok_or_else(|| ScanError::GitIndexFailed {
+                path: start.to_path_buf(),
+                reason: "not inside a git repository".to_string(),
+            })?;
+
+        let index_path = git_dir.join("index");
+        if !index_path.exists() {
+            return Ok(Self::from_paths(work_tree, Vec::new()));
+        }
+
+        let bytes = fs::read(&index_path).map_err(|e| ScanError::GitIndexFailed {
+            path: index_path.clone(),
+            reason: e.to_string(),
+        })?;
+        let oid_len = object_id_len(&git_dir);
+        let paths = parse_index(&bytes, oid_len).map_err(|reason| ScanError::GitIndexFailed {
+            path: index_path,
+            reason,
+        })?;
+
+        Ok(Self::from_paths(work_tree, paths))
+    }
+
+    /// Builds an index from `/`-separated paths relative to `work_tree`.
+    ///
+    /// # Arguments
+    ///
+    /// * `work_tree` - The repository work tree root.
+    /// * `paths` - Tracked file paths.
+    ///
+    /// # Returns
+    ///
+    /// A `GitIndex` containing the files and their ancestor directories.
+    #[must_use]
+    pub fn from_paths(work_tree: PathBuf, paths: Vec<String>) -> Self {
+        let mut files = HashSet::with_capacity(paths.len());
+        let mut directories = HashSet::new();
+
+        for path in paths {
+            let key = normalize_key(&path);
+            let mut end = key.len();
+            while let Some(pos) = key[..end].rfind('/') {
+                if !directories.insert(key[..pos].to_string()) {
+                    break;
+                }
+                end = pos;
+            }
+            files.insert(key);
+        }
+
+        Self {
+            work_tree,
+            files,
+            directories,
+        }
+    }
+
+    /// Returns the work tree root.
+    #[must_use]
+    pub fn work_tree(&self) -> &Path {
+        &self.work_tree
+    }
+
+    /// Returns the number of tracked files.
+    #[must_use]
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Checks whether a path is tracked.
+    ///
+    /// Files must appear in the index; directories are tracked when they
+    /// contain at least one tracked file. Paths outside the work tree are
+    /// never tracked, while the work tree itself always is.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Absolute path of the entry.
+    /// * `is_dir` - Whether the entry is a directory.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the entry should be kept by `--git-tracked`.
+    #[must_use]
+    pub fn is_tracked(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.work_tree) else {
+            return false;
+        };
+        let key = normalize_key(&relative.to_string_lossy());
+        if key.is_empty() {
+            return true;
+        }
+        if is_dir {
+            self.directories.contains(&key)
+        } else {
+            self.files.contains(&key)
+        }
+    }
+}
+
+// ============================================================================
+// Repository Discovery
+// ============================================================================
+
+/// Finds the work tree and git directory for `start` by walking upwards.
+fn find_repository(start: &Path) -> Option<(PathBuf, PathBuf)> {
+    start.ancestors().find_map(|dir| {
+        let dot_git = dir.join(".git");
+        if dot_git.is_dir() {
+            Some((dir.to_path_buf(), dot_git))
+        } else if dot_git.is_file() {
+            resolve_gitdir_file(&dot_git).map(|git_dir| (dir.to_path_buf(), git_dir))
+        } else {
+            None
+        }
+    })
+}
+
+/// Resolves a `.git` file containing `gitdir: <path>`.
+fn resolve_gitdir_file(dot_git: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(dot_git).ok()?;
+    let target = content
+        .lines()
+        .find_map(|line| line.strip_prefix("gitdir:"))?
+        .trim();
+    let target = Path::new(target);
+    if target.is_absolute() {
+        Some(target.to_path_buf())
+    } else {
+        Some(dot_git.parent()?.join(target))
+    }
+}
+
+/// Determines the object id length from the repository configuration.
+fn object_id_len(git_dir: &Path) -> usize {
+    let config = fs::read_to_string(git_dir.join("config")).unwrap_or_default();
+    let is_sha256 = config.lines().any(|line| {
+        let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+        compact.eq_ignore_ascii_case("objectformat=sha256")
+    });
+    if is_sha256 { SHA256_LEN } else { SHA1_LEN }
+}
+
+// ============================================================================
+// Index Parsing
+// ============================================================================
+
+/// Parses the entry table of a git index file.
+///
+/// # Arguments
+///
+/// * `bytes` - Raw index file contents.
+/// * `oid_len` - Object id length in bytes (20 or 32).
+///
+/// # Returns
+///
+/// The `/`-separated file paths, in index order. Directory entries of a
+/// sparse index are skipped, as their files are not checked out.
+///
+/// # Errors
+///
+/// Returns a description of the problem if the data is not a valid
+/// version 2, 3 or 4 index.
+fn parse_index(bytes: &[u8], oid_len: usize) -> Result<Vec<String>, String> {
+    if bytes.len() < HEADER_LEN || &bytes[..4] != INDEX_SIGNATURE {
+        return Err("missing DIRC signature".to_string());
+    }
+    let version = read_u32(bytes, 4)?;
+    if !(2..=4).contains(&version) {
+        return Err(format!("unsupported index version {}", version));
+    }
+    let count = read_u32(bytes, 8)? as usize;
+
+    // The count is not trusted for the allocation; each entry takes at
+    // least `MIN_ENTRY_LEN` bytes.
+    let mut paths = Vec::with_capacity(count.min(bytes.len() / MIN_ENTRY_LEN));
+    let mut offset = HEADER_LEN;
+    let mut previous: Vec<u8> = Vec::new();
+
+    for _ in 0..count {
+        let entry_start = offset;
+        let mode = read_u32(bytes, entry_start + MODE_OFFSET)?;
+        let flags_offset = entry_start + STAT_FIELDS_LEN + oid_len;
+        let flags = read_u16(bytes, flags_offset)?;
+        let mut name_offset = flags_offset + 2;
+        if version >= 3 && flags & EXTENDED_FLAG != 0 {
+            name_offset += 2;
+        }
+
+        let name = if version == 4 {
+            let (strip, varint_len) = read_varint(bytes, name_offset)?;
+            let suffix_start = name_offset + varint_len;
+            let suffix_end = find_nul(bytes, suffix_start)?;
+            let keep = previous
+                .len()
+                .checked_sub(strip)
+                .ok_or_else(|| "invalid path prefix length".to_string())?;
+            let mut name = previous[..keep].to_vec();
+            name.extend_from_slice(&bytes[suffix_start..suffix_end]);
+            offset = suffix_end + 1;
+            name
+        } else {
+            let name_end = find_nul(bytes, name_offset)?;
+            let entry_len = name_end - entry_start;
+            offset = entry_start + (entry_len + 8) / 8 * 8;
+            bytes[name_offset..name_end].to_vec()
+        };
+
+        if mode != SPARSE_DIRECTORY_MODE {
+            paths.push(String::from_utf8_lossy(&name).into_owned());
+        }
+        previous = name;
+    }
+
+    Ok(paths)
+}
+
+/// Reads a big-endian `u32` at `offset`.
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String> {
+    bytes
+        .get(offset..offset + 4)
+        .and_then(|b| b.try_into().ok())
+        .map(u32::from_be_bytes)
+        .ok_or_else(|| "unexpected end of index".to_string())
+}
+
+/// Reads a big-endian `u16` at `offset`.
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, String> {
+    bytes
+        .get(offset..offset + 2)
+        .and_then(|b| b.try_into().ok())
+        .map(u16::from_be_bytes)
+        .ok_or_else(|| "unexpected end of index".to_string())
+}
+
+/// Reads the offset-encoded varint used by index version 4.
+///
+/// # Returns
+///
+/// The decoded value and the number of bytes consumed.
+fn read_varint(bytes: &[u8], offset: usize) -> Result<(usize, usize), String> {
+    let mut value: usize = 0;
+    let mut consumed = 0;
+    loop {
+        let byte = *bytes
+            .get(offset + consumed)
+            .ok_or_else(|| "unexpected end of index".to_string())?;
+        consumed += 1;
+        value = (value << 7) | usize::from(byte & 0x7F);
+        if byte & 0x80 == 0 {
+            return Ok((value, consumed));
+        }
+        value += 1;
+    }
+}
+
+/// Finds the next NUL byte at or after `start`.
+fn find_nul(bytes: &[u8], start: usize) -> Result<usize, String> {
+    bytes
+        .get(start..)
+        .and_then(|rest| rest.iter().position(|&b| b == 0))
+        .map(|pos| start + pos)
+        .ok_or_else(|| "unterminated entry path".to_string())
+}
+
+/// Normalizes a relative path into a lookup key.
+///
+/// Separators become `/`; on Windows the key is lowercased because the
+/// filesystem is case-insensitive.
+fn normalize_key(path: &str) -> String {
+    let key = path.replace('\\', "/");
+    let key = key.trim_matches('/');
+    if cfg!(windows) {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Encodes an index entry for versions 2 and 3.
+    fn entry_v2(name: &str, oid_len: usize) -> Vec<u8> {
+        let mut entry = vec![0u8; STAT_FIELDS_LEN + oid_len];
+        let flags = name.len().min(0xFFF) as u16;
+        entry.extend_from_slice(&flags.to_be_bytes());
+        entry.extend_from_slice(name.as_bytes());
+        let padded = (entry.len() + 8) / 8 * 8;
+        entry.resize(padded, 0);
+        entry
+    }
+
+    /// Encodes the offset varint used by version 4.
+    fn varint(mut value: usize) -> Vec<u8> {
+        let mut out = vec![(value & 0x7F) as u8];
+        while value > 0x7F {
+            value = (value >> 7) - 1;
+            out.insert(0, 0x80 | (value & 0x7F) as u8);
+        }
+        out
+    }
+
+    /// Encodes an index entry for version 4.
+    fn entry_v4(strip: usize, suffix: &str) -> Vec<u8> {
+        let mut entry = vec![0u8; STAT_FIELDS_LEN + SHA1_LEN];
+        entry.extend_from_slice(&(suffix.len() as u16).to_be_bytes());
+        entry.extend_from_slice(&varint(strip));
+        entry.extend_from_slice(suffix.as_bytes());
+        entry.push(0);
+        entry
+    }
+
+    fn index_bytes(version: u32, entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = INDEX_SIGNATURE.to_vec();
+        bytes.extend_from_slice(&version.to_be_bytes());
+        bytes.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        for entry in entries {
+            bytes.extend_from_slice(entry);
+        }
+        bytes.extend_from_slice(&[0u8; SHA1_LEN]);
+        bytes
+    }
+
+    #[test]
+    fn should_parse_version_2_index() {
+        let bytes = index_bytes(
+            2,
+            &[
+                entry_v2("Cargo.toml", SHA1_LEN),
+                entry_v2("src/main.rs", SHA1_LEN),
+            ],
+        );
+        let paths = parse_index(&bytes, SHA1_LEN).unwrap();
+        assert_eq!(paths, vec!["Cargo.toml", "src/main.rs"]);
+    }
+
+    #[test]
+    fn should_parse_name_with_exact_padding_boundary() {
+        // 62 fixed bytes + 2-byte name = 64, which needs a full 8-byte NUL pad.
+        let bytes = index_bytes(2, &[entry_v2("ab", SHA1_LEN), entry_v2("c", SHA1_LEN)]);
+        let paths = parse_index(&bytes, SHA1_LEN).unwrap();
+        assert_eq!(paths, vec!["ab", "c"]);
+    }
+
+    #[test]
+    fn should_parse_version_3_extended_entry() {
+        let mut extended = vec![0u8; STAT_FIELDS_LEN + SHA1_LEN];
+        let name = "docs/readme.md";
+        extended.extend_from_slice(&(EXTENDED_FLAG | name.len() as u16).to_be_bytes());
+        extended.extend_from_slice(&[0, 0]);
+        extended.extend_from_slice(name.as_bytes());
+        let padded = (extended.len() + 8) / 8 * 8;
+        extended.resize(padded, 0);
+
+        let bytes = index_bytes(3, &[extended, entry_v2("z.txt", SHA1_LEN)]);
+        let paths = parse_index(&bytes, SHA1_LEN).unwrap();
+        assert_eq!(paths, vec!["docs/readme.md", "z.txt"]);
+    }
+
+    #[test]
+    fn should_parse_version_4_prefix_compression() {
+        let bytes = index_bytes(
+            4,
+            &[
+                entry_v4(0, "src/cli.rs"),
+                entry_v4(6, "config.rs"),
+                entry_v4(9, "main.rs"),
+            ],
+        );
+        let paths = parse_index(&bytes, SHA1_LEN).unwrap();
+        assert_eq!(paths, vec!["src/cli.rs", "src/config.rs", "src/main.rs"]);
+    }
+
+    #[test]
+    fn should_skip_sparse_directory_entries() {
+        let mut sparse = entry_v4(10, "docs/");
+        sparse[MODE_OFFSET..MODE_OFFSET + 4].copy_from_slice(&SPARSE_DIRECTORY_MODE.to_be_bytes());
+        let bytes = index_bytes(
+            4,
+            &[entry_v4(0, "Cargo.toml"), sparse, entry_v4(0, "index.md")],
+        );
+        let paths = parse_index(&bytes, SHA1_LEN).unwrap();
+        assert_eq!(paths, vec!["Cargo.toml", "docs/index.md"]);
+    }
+
+    #[test]
+    fn should_reject_entry_count_beyond_data() {
+        let mut bytes = index_bytes(2, &[entry_v2("a.txt", SHA1_LEN)]);
+        bytes[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(parse_index(&bytes, SHA1_LEN).is_err());
+    }
+
+    #[test]
+    fn should_round_trip_multi_byte_varint() {
+        for value in [0, 1, 127, 128, 300, 16_511, 16_512, 1_000_000] {
+            let encoded = varint(value);
+            assert_eq!(read_varint(&encoded, 0).unwrap(), (value, encoded.len()));
+        }
+    }
+
+    #[test]
+    fn should_parse_sha256_index() {
+        let bytes = index_bytes(2, &[entry_v2("a.txt", SHA256_LEN)]);
+        let paths = parse_index(&bytes, SHA256_LEN).unwrap();
+        assert_eq!(paths, vec!["a.txt"]);
+    }
+
+    #[test]
+    fn should_reject_bad_signature() {
+        let mut bytes = index_bytes(2, &[]);
+        bytes[0] = b'X';
+        assert!(parse_index(&bytes, SHA1_LEN).unwrap_err().contains("DIRC"));
+    }
+
+    #[test]
+    fn should_reject_unsupported_version() {
+        let bytes = index_bytes(5, &[]);
+        assert!(
+            parse_index(&bytes, SHA1_LEN)
+                .unwrap_err()
+                .contains("version")
+        );
+    }
+
+    #[test]
+    fn should_reject_truncated_index() {
+        let mut bytes = index_bytes(2, &[entry_v2("file.txt", SHA1_LEN)]);
+        bytes.truncate(HEADER_LEN + 30);
+        assert!(parse_index(&bytes, SHA1_LEN).is_err());
+    }
+
+    #[test]
+    fn should_track_files_and_ancestor_directories() {
+        let root = PathBuf::from("repo");
+        let index = GitIndex::from_paths(root.clone(), vec!["a/b/c.txt".to_string()]);
+
+        assert!(index.is_tracked(&root.join("a"), true));
+        assert!(index.is_tracked(&root.join("a").join("b"), true));
+        assert!(index.is_tracked(&root.join("a").join("b").join("c.txt"), false));
+        assert!(!index.is_tracked(&root.join("a").join("other.txt"), false));
+        assert!(!index.is_tracked(&root.join("x"), true));
+        assert_eq!(index.file_count(), 1);
+    }
+
+    #[test]
+    fn should_treat_work_tree_as_tracked_and_outside_as_untracked() {
+        let root = PathBuf::from("repo");
+        let index = GitIndex::from_paths(root.clone(), vec!["f.txt".to_string()]);
+        assert!(index.is_tracked(&root, true));
+        assert!(!index.is_tracked(Path::new("elsewhere/f.txt"), false));
+    }
+
+    #[test]
+    fn should_not_treat_file_as_directory() {
+        let root = PathBuf::from("repo");
+        let index = GitIndex::from_paths(root.clone(), vec!["src".to_string()]);
+        assert!(!index.is_tracked(&root.join("src"), true));
+        assert!(index.is_tracked(&root.join("src"), false));
+    }
+
+    #[test]
+    fn should_discover_repository_from_subdirectory() {
+        let dir = TempDir::new().unwrap();
+        let git_dir = dir.path().join(".git");
+        fs::create_dir_all(&git_dir).unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(
+            git_dir.join("index"),
+            index_bytes(2, &[entry_v2("src/lib.rs", SHA1_LEN)]),
+        )
+        .unwrap();
+
+        let index = GitIndex::discover(&dir.path().join("src")).unwrap();
+        assert_eq!(index.work_tree(), dir.path());
+        assert!(index.is_tracked(&dir.path().join("src").join("lib.rs"), false));
+    }
+
+    #[test]
+    fn should_follow_gitdir_file() {
+        let dir = TempDir::new().unwrap();
+        let real_git = dir.path().join("modules").join("sub");
+        fs::create_dir_all(&real_git).unwrap();
+        fs::write(
+            real_git.join("index"),
+            index_bytes(2, &[entry_v2("x.rs", SHA1_LEN)]),
+        )
+        .unwrap();
+
+        let work_tree = dir.path().join("sub");
+        fs::create_dir_all(&work_tree).unwrap();
+        fs::write(work_tree.join(".git"), "gitdir: ../modules/sub\n").unwrap();
+
+        let index = GitIndex::discover(&work_tree).unwrap();
+        assert!(index.is_tracked(&work_tree.join("x.rs"), false));
+    }
+
+    #[test]
+    fn should_return_empty_index_when_index_file_missing() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        let index = GitIndex::discover(dir.path()).unwrap();
+        assert_eq!(index.file_count(), 0);
+    }
+
+    #[test]
+    fn should_detect_sha256_object_format() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("config"),
+            "[extensions]\n\tobjectformat = sha256\n",
+        )
+        .unwrap();
+        assert_eq!(object_id_len(dir.path()), SHA256_LEN);
+        assert_eq!(object_id_len(&dir.path().join("missing")), SHA1_LEN);
+    }
+}
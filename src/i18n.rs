@@ -0,0 +1,1337 @@
+//! Localization module: translated text for help, errors and the report.
+//!
+//! tree++ keeps its messages in a small embedded table rather than external
+//! resource files. The language is chosen by:
+//!
+//! - **`--lang`**: explicit override (`en`, `zh`)
+//! - **System UI language**: `GetUserDefaultUILanguage`, when no override is given
+//!
+//! English text is the source of truth: English error messages are the
+//! `Display` output of the error types, and this module only adds the
+//! translations.
+//!
+//! File: src/i18n.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::config::{ConfigError, ConfigWarning, Language, MenuScope};
+use crate::error::{CliError, MatchError, OutputError, RenderError, ScanError, TreeppError};
+use crate::matching::FilterKind;
+use crate::render::Section;
+use crate::winvol;
+
+// ============================================================================
+// Language Detection
+// ============================================================================
+
+/// Returns the language matching the user's Windows display language.
+///
+/// # Returns
+///
+/// `Language::Chinese` for Chinese UI languages, `Language::English` otherwise.
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::i18n::system_language;
+///
+/// println!("{:?}", system_language());
+/// ```
+#[must_use]
+pub fn system_language() -> Language {
+    Language::from_langid(winvol::ui_language())
+}
+
+// ============================================================================
+// Statistics Report
+// ============================================================================
+
+/// Formats the summary line printed by `--report`.
+///
+/// # Arguments
+///
+/// * `language` - Output language
+/// * `directory_count` - Number of directories
+/// * `file_count` - Number of files, or `None` when files are not shown
+/// * `duration` - Scan duration
+///
+/// # Returns
+///
+/// The summary line without a trailing newline.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use treepp::config::Language;
+/// use treepp::i18n::report_line;
+///
+/// let line = report_line(Language::English, 5, Some(20), Duration::from_millis(100));
+/// assert_eq!(line, "5 directory, 20 files in 0.100s");
+/// ```
+#[must_use]
+pub fn report_line(
+    language: Language,
+    directory_count: usize,
+    file_count: Option<usize>,
+    duration: Duration,
+) -> String {
+    let seconds = duration.as_secs_f64();
+    match (language, file_count) {
+        (Language::English, Some(files)) => {
+            format!(
+                "{} directory, {} files in {:.3}s",
+                directory_count, files, seconds
+            )
+        }
+        (Language::English, None) => format!("{} directory in {:.3}s", directory_count, seconds),
+        (Language::Chinese, Some(files)) => {
+            format!(
+                "{} 个目录，{} 个文件，用时 {:.3}s",
+                directory_count, files, seconds
+            )
+        }
+        (Language::Chinese, None) => format!("{} 个目录，用时 {:.3}s", directory_count, seconds),
+    }
+}
+
+/// Returns the heading of the `--report-extensions` table.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::extensions_heading;
+///
+/// assert_eq!(extensions_heading(Language::English), "Extensions:");
+/// ```
+#[must_use]
+pub fn extensions_heading(language: Language) -> &'static str {
+    match language {
+        Language::English => "Extensions:",
+        Language::Chinese => "扩展名：",
+    }
+}
+
+/// Returns the line printed by `--report-extensions` when no files were listed.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::no_files_listed;
+///
+/// assert_eq!(no_files_listed(Language::English), "Extensions: no files listed");
+/// ```
+#[must_use]
+pub fn no_files_listed(language: Language) -> &'static str {
+    match language {
+        Language::English => "Extensions: no files listed",
+        Language::Chinese => "扩展名：未列出文件",
+    }
+}
+
+/// Returns the heading of the `--depth-histogram` table.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::depth_histogram_heading;
+///
+/// assert_eq!(depth_histogram_heading(Language::English), "Depth histogram:");
+/// ```
+#[must_use]
+pub fn depth_histogram_heading(language: Language) -> &'static str {
+    match language {
+        Language::English => "Depth histogram:",
+        Language::Chinese => "深度分布：",
+    }
+}
+
+/// Returns the line printed by `--depth-histogram` when no entries were listed.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::no_entries_listed;
+///
+/// assert_eq!(no_entries_listed(Language::English), "Depth histogram: no entries listed");
+/// ```
+#[must_use]
+pub fn no_entries_listed(language: Language) -> &'static str {
+    match language {
+        Language::English => "Depth histogram: no entries listed",
+        Language::Chinese => "深度分布：未列出条目",
+    }
+}
+
+/// Returns the column headers of the `--depth-histogram` table.
+///
+/// # Returns
+///
+/// The headers of the level, directory count, file count and size
+/// columns, in that order.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::depth_histogram_columns;
+///
+/// assert_eq!(depth_histogram_columns(Language::English), ["Level", "Dirs", "Files", "Size"]);
+/// ```
+#[must_use]
+pub fn depth_histogram_columns(language: Language) -> [&'static str; 4] {
+    match language {
+        Language::English => ["Level", "Dirs", "Files", "Size"],
+        Language::Chinese => ["层级", "目录", "文件", "大小"],
+    }
+}
+
+/// Returns the labels of the `--count` totals.
+///
+/// # Returns
+///
+/// The labels of the directory count, file count, total size, maximum depth
+/// and filtered-out count, in that order.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::count_labels;
+///
+/// assert_eq!(count_labels(Language::English)[0], "Directories:");
+/// assert_eq!(count_labels(Language::Chinese)[4], "已过滤：");
+/// ```
+#[must_use]
+pub fn count_labels(language: Language) -> [&'static str; 5] {
+    match language {
+        Language::English => [
+            "Directories:",
+            "Files:",
+            "Total size:",
+            "Max depth:",
+            "Filtered out:",
+        ],
+        Language::Chinese => ["目录：", "文件：", "总大小：", "最大深度：", "已过滤："],
+    }
+}
+
+/// Returns the label of a filter in the `--count` totals.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::filter_label;
+/// use treepp::matching::FilterKind;
+///
+/// assert_eq!(filter_label(Language::English, FilterKind::Excluded), "excluded (/X)");
+/// assert_eq!(filter_label(Language::Chinese, FilterKind::Pruned), "已修剪 (/P)");
+/// ```
+#[must_use]
+pub fn filter_label(language: Language, kind: FilterKind) -> &'static str {
+    match (language, kind) {
+        (Language::English, FilterKind::Hidden) => "hidden",
+        (Language::English, FilterKind::CloudOnly) => "cloud-only (/SCO)",
+        (Language::English, FilterKind::EmptyFile) => "empty file (/PEF)",
+        (Language::English, FilterKind::Excluded) => "excluded (/X)",
+        (Language::English, FilterKind::Smart) => "project output (/SM)",
+        (Language::English, FilterKind::NotIncluded) => "not included (/M)",
+        (Language::English, FilterKind::Gitignored) => ".gitignore (/G)",
+        (Language::English, FilterKind::Untracked) => "untracked (/GT)",
+        (Language::English, FilterKind::Pruned) => "pruned (/P)",
+        (Language::Chinese, FilterKind::Hidden) => "隐藏",
+        (Language::Chinese, FilterKind::CloudOnly) => "仅云端 (/SCO)",
+        (Language::Chinese, FilterKind::EmptyFile) => "空文件 (/PEF)",
+        (Language::Chinese, FilterKind::Excluded) => "排除 (/X)",
+        (Language::Chinese, FilterKind::Smart) => "项目输出 (/SM)",
+        (Language::Chinese, FilterKind::NotIncluded) => "未包含 (/M)",
+        (Language::Chinese, FilterKind::Gitignored) => ".gitignore (/G)",
+        (Language::Chinese, FilterKind::Untracked) => "未跟踪 (/GT)",
+        (Language::Chinese, FilterKind::Pruned) => "已修剪 (/P)",
+    }
+}
+
+/// Formats the line printed when some directories could not be read.
+///
+/// # Arguments
+///
+/// * `language` - Output language
+/// * `count` - Number of unreadable directories
+///
+/// # Returns
+///
+/// The summary line without a trailing newline.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::unreadable_summary;
+///
+/// assert_eq!(unreadable_summary(Language::English, 1), "1 directory could not be read");
+/// assert_eq!(unreadable_summary(Language::Chinese, 3), "3 个目录无法读取");
+/// ```
+#[must_use]
+pub fn unreadable_summary(language: Language, count: usize) -> String {
+    match language {
+        Language::English if count == 1 => "1 directory could not be read".to_string(),
+        Language::English => format!("{} directories could not be read", count),
+        Language::Chinese => format!("{} 个目录无法读取", count),
+    }
+}
+
+/// Formats the line printed when `--budget-time` or `--budget-entries` ran out.
+///
+/// # Arguments
+///
+/// * `language` - Output language
+/// * `count` - Number of directories left unread
+///
+/// # Returns
+///
+/// The summary line without a trailing newline.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::budget_summary;
+///
+/// assert_eq!(
+///     budget_summary(Language::English, 1),
+///     "scan budget ran out; 1 directory was not read"
+/// );
+/// assert_eq!(budget_summary(Language::Chinese, 3), "扫描预算已用完；3 个目录未读取");
+/// ```
+#[must_use]
+pub fn budget_summary(language: Language, count: usize) -> String {
+    match language {
+        Language::English if count == 1 => {
+            "scan budget ran out; 1 directory was not read".to_string()
+        }
+        Language::English => format!("scan budget ran out; {} directories were not read", count),
+        Language::Chinese => format!("扫描预算已用完；{} 个目录未读取", count),
+    }
+}
+
+/// Formats the warning for entries deleted or renamed during the scan.
+///
+/// # Arguments
+///
+/// * `language` - Output language
+/// * `count` - Number of entries left out
+///
+/// # Returns
+///
+/// The warning line, including its category.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::vanished_warning;
+///
+/// assert_eq!(
+///     vanished_warning(Language::English, 1),
+///     "Warning: 1 entry was deleted or renamed during the scan and is not shown"
+/// );
+/// assert_eq!(
+///     vanished_warning(Language::Chinese, 3),
+///     "警告：3 个条目在扫描期间被删除或重命名，未显示"
+/// );
+/// ```
+#[must_use]
+pub fn vanished_warning(language: Language, count: usize) -> String {
+    match language {
+        Language::English if count == 1 => {
+            "Warning: 1 entry was deleted or renamed during the scan and is not shown".to_string()
+        }
+        Language::English => format!(
+            "Warning: {} entries were deleted or renamed during the scan and are not shown",
+            count
+        ),
+        Language::Chinese => format!("警告：{} 个条目在扫描期间被删除或重命名，未显示", count),
+    }
+}
+
+/// Returns the warning shown when old reports cannot be deleted (`--rotate`).
+///
+/// The new report was written, so the run still succeeds.
+///
+/// # Arguments
+///
+/// * `language` - Output language
+/// * `reason` - The error, naming the report that was kept
+///
+/// # Returns
+///
+/// The warning line, including its category.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::rotation_warning;
+///
+/// assert_eq!(
+///     rotation_warning(Language::English, "tree-20261016-020000.txt: Access is denied."),
+///     "Warning: old reports were kept: tree-20261016-020000.txt: Access is denied."
+/// );
+/// ```
+#[must_use]
+pub fn rotation_warning(language: Language, reason: &str) -> String {
+    match language {
+        Language::English => format!("Warning: old reports were kept: {}", reason),
+        Language::Chinese => format!("警告：未能删除旧报告：{}", reason),
+    }
+}
+
+/// Returns the noun following a file count in the extension table.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::file_noun;
+///
+/// assert_eq!(file_noun(Language::English, 1), "file");
+/// assert_eq!(file_noun(Language::English, 3), "files");
+/// ```
+#[must_use]
+pub fn file_noun(language: Language, count: usize) -> &'static str {
+    match language {
+        Language::English if count == 1 => "file",
+        Language::English => "files",
+        Language::Chinese => "个文件",
+    }
+}
+
+// ============================================================================
+// Listing
+// ============================================================================
+
+/// Returns the heading of a `--group-by type` section.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::section_label;
+/// use treepp::render::Section;
+///
+/// assert_eq!(section_label(Language::English, Section::Images), "Images");
+/// assert_eq!(section_label(Language::Chinese, Section::Directories), "目录");
+/// ```
+#[must_use]
+pub fn section_label(language: Language, section: Section) -> &'static str {
+    match (language, section) {
+        (Language::English, Section::Code) => "Code",
+        (Language::English, Section::Images) => "Images",
+        (Language::English, Section::Archives) => "Archives",
+        (Language::English, Section::Other) => "Other",
+        (Language::English, Section::Directories) => "Directories",
+        (Language::Chinese, Section::Code) => "代码",
+        (Language::Chinese, Section::Images) => "图片",
+        (Language::Chinese, Section::Archives) => "压缩包",
+        (Language::Chinese, Section::Other) => "其他",
+        (Language::Chinese, Section::Directories) => "目录",
+    }
+}
+
+// ============================================================================
+// Context Menu
+// ============================================================================
+
+/// Returns the text of the Explorer context-menu entry.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::context_menu_label;
+///
+/// assert_eq!(context_menu_label(Language::English), "Open tree++ here");
+/// ```
+#[must_use]
+pub fn context_menu_label(language: Language) -> &'static str {
+    match language {
+        Language::English => "Open tree++ here",
+        Language::Chinese => "在此处打开 tree++",
+    }
+}
+
+/// Describes the users whose context menu a scope changes.
+fn menu_scope_text(language: Language, scope: MenuScope) -> &'static str {
+    match (language, scope) {
+        (Language::English, MenuScope::User) => "the current user",
+        (Language::English, MenuScope::Machine) => "all users",
+        (Language::Chinese, MenuScope::User) => "当前用户",
+        (Language::Chinese, MenuScope::Machine) => "所有用户",
+    }
+}
+
+/// Returns the message printed after the context-menu entry was added.
+///
+/// # Arguments
+///
+/// * `language` - Language of the message
+/// * `scope` - Scope the entry was added in
+///
+/// # Returns
+///
+/// One line naming the entry and the users who see it.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::{Language, MenuScope};
+/// use treepp::i18n::context_menu_installed;
+///
+/// assert_eq!(
+///     context_menu_installed(Language::English, MenuScope::User),
+///     "Added \"Open tree++ here\" to the folder context menu for the current user"
+/// );
+/// ```
+#[must_use]
+pub fn context_menu_installed(language: Language, scope: MenuScope) -> String {
+    let label = context_menu_label(language);
+    let users = menu_scope_text(language, scope);
+    match language {
+        Language::English => {
+            format!(
+                "Added \"{}\" to the folder context menu for {}",
+                label, users
+            )
+        }
+        Language::Chinese => format!("已为{}在文件夹右键菜单中添加“{}”", users, label),
+    }
+}
+
+/// Returns the message printed after removing the context-menu entry.
+///
+/// # Arguments
+///
+/// * `language` - Language of the message
+/// * `scope` - Scope the entry was removed from
+/// * `removed` - Whether an entry existed
+///
+/// # Returns
+///
+/// One line stating whether the entry was removed.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::{Language, MenuScope};
+/// use treepp::i18n::context_menu_removed;
+///
+/// assert_eq!(
+///     context_menu_removed(Language::English, MenuScope::Machine, false),
+///     "No folder context-menu entry was installed for all users"
+/// );
+/// ```
+#[must_use]
+pub fn context_menu_removed(language: Language, scope: MenuScope, removed: bool) -> String {
+    let users = menu_scope_text(language, scope);
+    match (language, removed) {
+        (Language::English, true) => format!("Removed the folder context-menu entry for {}", users),
+        (Language::English, false) => {
+            format!("No folder context-menu entry was installed for {}", users)
+        }
+        (Language::Chinese, true) => format!("已为{}移除文件夹右键菜单项", users),
+        (Language::Chinese, false) => format!("{}未安装文件夹右键菜单项", users),
+    }
+}
+
+// ============================================================================
+// Settings
+// ============================================================================
+
+/// Returns the message printed after `treepp config add-exclude`.
+///
+/// # Arguments
+///
+/// * `language` - Language of the message
+/// * `pattern` - The pattern
+/// * `added` - Whether the pattern was new
+///
+/// # Returns
+///
+/// One line stating whether the pattern was added.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::exclude_added;
+///
+/// let message = exclude_added(Language::English, "node_modules", true);
+/// assert_eq!(message, "Added \"node_modules\" to the default excludes");
+/// ```
+#[must_use]
+pub fn exclude_added(language: Language, pattern: &str, added: bool) -> String {
+    match (language, added) {
+        (Language::English, true) => format!("Added \"{}\" to the default excludes", pattern),
+        (Language::English, false) => format!("\"{}\" is already a default exclude", pattern),
+        (Language::Chinese, true) => format!("已将“{}”加入默认排除项", pattern),
+        (Language::Chinese, false) => format!("“{}”已是默认排除项", pattern),
+    }
+}
+
+/// Returns the message printed after `treepp config remove`.
+///
+/// # Arguments
+///
+/// * `language` - Language of the message
+/// * `pattern` - The pattern
+/// * `removed` - Whether the pattern was a default exclude
+///
+/// # Returns
+///
+/// One line stating whether the pattern was removed.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::exclude_removed;
+///
+/// let message = exclude_removed(Language::English, "dist", false);
+/// assert_eq!(message, "\"dist\" is not a default exclude");
+/// ```
+#[must_use]
+pub fn exclude_removed(language: Language, pattern: &str, removed: bool) -> String {
+    match (language, removed) {
+        (Language::English, true) => {
+            format!("Removed \"{}\" from the default excludes", pattern)
+        }
+        (Language::English, false) => format!("\"{}\" is not a default exclude", pattern),
+        (Language::Chinese, true) => format!("已从默认排除项中移除“{}”", pattern),
+        (Language::Chinese, false) => format!("“{}”不是默认排除项", pattern),
+    }
+}
+
+/// Returns the message printed after `treepp bookmark add`.
+///
+/// # Arguments
+///
+/// * `language` - Language of the message
+/// * `name` - Name of the bookmark
+/// * `path` - The bookmarked directory
+///
+/// # Returns
+///
+/// One line naming the bookmark and its directory.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::config::Language;
+/// use treepp::i18n::bookmark_added;
+///
+/// let message = bookmark_added(Language::English, "src", Path::new("work"));
+/// assert_eq!(message, "@src now points to work");
+/// ```
+#[must_use]
+pub fn bookmark_added(language: Language, name: &str, path: &Path) -> String {
+    match language {
+        Language::English => format!("@{} now points to {}", name, path.display()),
+        Language::Chinese => format!("@{} 现指向 {}", name, path.display()),
+    }
+}
+
+/// Returns the message printed after `treepp bookmark remove`.
+///
+/// # Arguments
+///
+/// * `language` - Language of the message
+/// * `name` - Name of the bookmark
+/// * `removed` - Whether the bookmark existed
+///
+/// # Returns
+///
+/// One line stating whether the bookmark was removed.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::bookmark_removed;
+///
+/// assert_eq!(bookmark_removed(Language::English, "src", true), "Removed the bookmark @src");
+/// ```
+#[must_use]
+pub fn bookmark_removed(language: Language, name: &str, removed: bool) -> String {
+    match (language, removed) {
+        (Language::English, true) => format!("Removed the bookmark @{}", name),
+        (Language::English, false) => format!("There is no bookmark @{}", name),
+        (Language::Chinese, true) => format!("已移除书签 @{}", name),
+        (Language::Chinese, false) => format!("不存在书签 @{}", name),
+    }
+}
+
+// ============================================================================
+// Checkpoint
+// ============================================================================
+
+/// Returns the notice printed when a `--checkpoint` run is resumed.
+///
+/// # Arguments
+///
+/// * `language` - Language of the message
+/// * `completed` - Last directory written by the interrupted run
+///
+/// # Returns
+///
+/// One line naming the directory after which the run continues.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::config::Language;
+/// use treepp::i18n::checkpoint_resumed;
+///
+/// let notice = checkpoint_resumed(Language::English, Path::new(r"C:\Windows"));
+/// assert_eq!(notice, r"resuming the output file after C:\Windows");
+/// ```
+#[must_use]
+pub fn checkpoint_resumed(language: Language, completed: &Path) -> String {
+    match language {
+        Language::English => format!("resuming the output file after {}", completed.display()),
+        Language::Chinese => format!("从 {} 之后继续写入输出文件", completed.display()),
+    }
+}
+
+/// Returns the notice printed when the server is ready (`--serve`).
+///
+/// # Arguments
+///
+/// * `language` - Language of the message
+/// * `endpoint` - URL or pipe path the server listens on
+/// * `root` - The scanned root
+///
+/// # Returns
+///
+/// One line naming the root and where to send requests; printed once per
+/// transport.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::config::Language;
+/// use treepp::i18n::server_listening;
+///
+/// let url = "http://127.0.0.1:7878/";
+/// let notice = server_listening(Language::English, url, Path::new(r"D:\src"));
+/// assert_eq!(
+///     notice,
+///     r#"serving D:\src at http://127.0.0.1:7878/ (send "shutdown" to stop)"#
+/// );
+/// ```
+#[must_use]
+pub fn server_listening(language: Language, endpoint: &str, root: &Path) -> String {
+    match language {
+        Language::English => format!(
+            "serving {} at {} (send \"shutdown\" to stop)",
+            root.display(),
+            endpoint
+        ),
+        Language::Chinese => format!(
+            "正在 {} 提供 {} 的目录树（发送 \"shutdown\" 以停止）",
+            endpoint,
+            root.display()
+        ),
+    }
+}
+
+/// Returns the notice printed when a checkpoint cannot be resumed.
+///
+/// A checkpoint is ignored if it was written with other options, if the
+/// output file was changed since, or if its directory no longer exists.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::checkpoint_ignored;
+///
+/// assert_eq!(
+///     checkpoint_ignored(Language::English),
+///     "the checkpoint does not match this run; starting over"
+/// );
+/// ```
+#[must_use]
+pub fn checkpoint_ignored(language: Language) -> &'static str {
+    match language {
+        Language::English => "the checkpoint does not match this run; starting over",
+        Language::Chinese => "检查点与本次运行不符，将重新开始",
+    }
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// Returns the category label printed before an error message.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::error::{CliError, TreeppError};
+/// use treepp::i18n::error_category;
+///
+/// let err: TreeppError = CliError::UnknownOption { option: "/Z".to_string() }.into();
+/// assert_eq!(error_category(&err, Language::Chinese), "参数错误");
+/// ```
+#[must_use]
+pub fn error_category(err: &TreeppError, language: Language) -> &'static str {
+    match (language, err) {
+        (Language::English, TreeppError::Cli(_)) => "CLI error",
+        (Language::English, TreeppError::Config(_)) => "Config error",
+        (Language::English, TreeppError::Scan(_)) => "Scan error",
+        (Language::English, TreeppError::Match(_)) => "Match error",
+        (Language::English, TreeppError::Render(_)) => "Render error",
+        (Language::English, TreeppError::Output(_)) => "Output error",
+        (Language::Chinese, TreeppError::Cli(_)) => "参数错误",
+        (Language::Chinese, TreeppError::Config(_)) => "配置错误",
+        (Language::Chinese, TreeppError::Scan(_)) => "扫描错误",
+        (Language::Chinese, TreeppError::Match(_)) => "匹配错误",
+        (Language::Chinese, TreeppError::Render(_)) => "渲染错误",
+        (Language::Chinese, TreeppError::Output(_)) => "输出错误",
+    }
+}
+
+/// Formats an error message in the given language.
+///
+/// # Arguments
+///
+/// * `err` - The error to describe
+/// * `language` - Output language
+///
+/// # Returns
+///
+/// The `Display` text for English, otherwise the translated message.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::error::{CliError, TreeppError};
+/// use treepp::i18n::error_message;
+///
+/// let err: TreeppError = CliError::MissingValue { option: "--level".to_string() }.into();
+/// assert_eq!(error_message(&err, Language::Chinese), "选项 --level 需要一个值。");
+/// ```
+#[must_use]
+pub fn error_message(err: &TreeppError, language: Language) -> String {
+    if language == Language::English {
+        return err.to_string();
+    }
+    match err {
+        TreeppError::Cli(e) => cli_error_zh(e),
+        TreeppError::Config(e) => config_error_zh(e),
+        TreeppError::Scan(e) => scan_error_zh(e),
+        TreeppError::Match(e) => match_error_zh(e),
+        TreeppError::Render(e) => render_error_zh(e),
+        TreeppError::Output(e) => output_error_zh(e),
+    }
+}
+
+/// Formats a configuration warning, including its category.
+///
+/// # Arguments
+///
+/// * `warning` - The warning
+/// * `language` - Language of the message
+///
+/// # Returns
+///
+/// The warning line, e.g. `Warning: Thread count 10000 is too large; using 256 threads`.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::{ConfigWarning, Language};
+/// use treepp::i18n::warning_message;
+///
+/// let warning = ConfigWarning::ThreadCountClamped { requested: 10000, used: 256 };
+/// assert_eq!(
+///     warning_message(&warning, Language::Chinese),
+///     "警告：线程数 10000 过大，将使用 256 个线程"
+/// );
+/// ```
+#[must_use]
+pub fn warning_message(warning: &ConfigWarning, language: Language) -> String {
+    match language {
+        Language::English => format!("Warning: {}", warning),
+        Language::Chinese => match warning {
+            ConfigWarning::ThreadCountClamped { requested, used } => {
+                format!("警告：线程数 {} 过大，将使用 {} 个线程", requested, used)
+            }
+        },
+    }
+}
+
+/// Formats a rendering problem that did not stop the tree as a warning.
+///
+/// # Arguments
+///
+/// * `err` - The problem, e.g. a banner that could not be fetched
+/// * `language` - Language of the message
+///
+/// # Returns
+///
+/// The warning line, including its category.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::error::RenderError;
+/// use treepp::i18n::render_warning;
+///
+/// let err = RenderError::BannerFetchFailed { reason: "timeout".to_string() };
+/// assert_eq!(
+///     render_warning(&err, Language::English),
+///     "Warning: Failed to fetch Windows tree banner: timeout"
+/// );
+/// ```
+#[must_use]
+pub fn render_warning(err: &RenderError, language: Language) -> String {
+    match language {
+        Language::English => format!("Warning: {}", err),
+        Language::Chinese => format!("警告：{}", render_error_zh(err)),
+    }
+}
+
+/// Returns the hint printed after an unknown option error.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::unknown_option_hint;
+///
+/// assert_eq!(
+///     unknown_option_hint(Language::English),
+///     "Hint: run `treepp --help` to list available options"
+/// );
+/// ```
+#[must_use]
+pub fn unknown_option_hint(language: Language) -> &'static str {
+    match language {
+        Language::English => "Hint: run `treepp --help` to list available options",
+        Language::Chinese => "提示：运行 `treepp --help` 查看可用选项",
+    }
+}
+
+/// Returns the hint printed after an invalid value error with a suggestion.
+///
+/// # Arguments
+///
+/// * `language` - Language of the hint
+/// * `suggestion` - The closest accepted value
+///
+/// # Returns
+///
+/// The hint line.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::did_you_mean_hint;
+///
+/// assert_eq!(did_you_mean_hint(Language::English, "size"), "Hint: did you mean 'size'?");
+/// ```
+#[must_use]
+pub fn did_you_mean_hint(language: Language, suggestion: &str) -> String {
+    match language {
+        Language::English => format!("Hint: did you mean '{}'?", suggestion),
+        Language::Chinese => format!("提示：是否要使用 '{}'？", suggestion),
+    }
+}
+
+/// Returns the hint printed when the context menu of all users cannot be changed.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::elevation_hint;
+///
+/// assert!(elevation_hint(Language::English).contains("run as administrator"));
+/// ```
+#[must_use]
+pub fn elevation_hint(language: Language) -> &'static str {
+    match language {
+        Language::English => {
+            "Hint: run as administrator to change the menu for all users, or use the user scope"
+        }
+        Language::Chinese => {
+            "提示：请在管理员权限的命令行中运行以修改所有用户的菜单，或使用 user 范围"
+        }
+    }
+}
+
+/// Returns the hint printed after a multiple paths error.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::i18n::multiple_paths_hint;
+///
+/// assert_eq!(
+///     multiple_paths_hint(Language::English),
+///     "Hint: only one target path can be specified."
+/// );
+/// ```
+#[must_use]
+pub fn multiple_paths_hint(language: Language) -> &'static str {
+    match language {
+        Language::English => "Hint: only one target path can be specified.",
+        Language::Chinese => "提示：只能指定一个目标路径。",
+    }
+}
+
+/// Translates a CLI error.
+fn cli_error_zh(err: &CliError) -> String {
+    match err {
+        CliError::UnknownOption { option } => format!("未知选项：{}", option),
+        CliError::MissingValue { option } => format!("选项 {} 需要一个值。", option),
+        CliError::InvalidValue {
+            option,
+            value,
+            reason,
+            ..
+        } => format!("选项 {} 的值 '{}' 无效：{}", option, value, reason),
+        CliError::DuplicateOption { option } => format!("选项 {} 被指定了多次。", option),
+        CliError::ConflictingOptions { opt_a, opt_b } => {
+            format!("选项冲突：{} 与 {} 不能同时使用。", opt_a, opt_b)
+        }
+        CliError::MultiplePaths { paths } => {
+            format!("只能指定一个路径，但提供了多个：{:?}", paths)
+        }
+        CliError::InvalidPath { arg } => format!("无法解析路径参数：{}", arg),
+        CliError::ValueOptionInCluster { option, cluster } => {
+            format!("选项 {} 需要一个值，必须位于 {} 的末尾", option, cluster)
+        }
+        CliError::ParseError { message } => format!("参数解析失败：{}", message),
+        CliError::RecursivePreset { name } => format!("预设 @{} 引用了自身", name),
+        CliError::InvalidConfig(e) => config_error_zh(e),
+    }
+}
+
+/// Translates a configuration error.
+fn config_error_zh(err: &ConfigError) -> String {
+    match err {
+        ConfigError::ConflictingOptions {
+            opt_a,
+            opt_b,
+            reason,
+        } => format!(
+            "选项冲突：{} 与 {} 不能同时使用（{}）",
+            opt_a, opt_b, reason
+        ),
+        ConfigError::InvalidValue {
+            option,
+            value,
+            reason,
+        } => format!("参数值无效：{} = {}（{}）", option, value, reason),
+        ConfigError::InvalidPath { path, reason } => {
+            format!("路径无效：{}（{}）", path.display(), reason)
+        }
+        ConfigError::UnknownOutputFormat { path } => format!(
+            "无法推断输出格式：{}（支持的扩展名：.txt、.json、.yml、.yaml、.toml）",
+            path.display()
+        ),
+        ConfigError::InvalidSettings { path, reason } => {
+            format!("设置文件无效：{}（{}）", path.display(), reason)
+        }
+    }
+}
+
+/// Translates a scan error.
+fn scan_error_zh(err: &ScanError) -> String {
+    match err {
+        ScanError::PathNotFound { path } => format!("路径不存在：{}", path.display()),
+        ScanError::NotADirectory { path } => format!("路径不是目录：{}", path.display()),
+        ScanError::PermissionDenied { path } => format!("权限不足：{}", path.display()),
+        ScanError::ReadDirFailed { path, .. } => format!("无法读取目录：{}", path.display()),
+        ScanError::MetadataFailed { path, .. } => format!("无法获取元数据：{}", path.display()),
+        ScanError::CanonicalizeFailed { path, .. } => {
+            format!("无法规范化路径：{}", path.display())
+        }
+        ScanError::WalkError { message, .. } => format!("目录遍历错误：{}", message),
+        ScanError::PathListReadFailed { origin, .. } => format!("无法读取路径列表：{}", origin),
+        ScanError::PathOutsideRoot { path, root } => format!(
+            "列出的路径不在根目录内：{}（根目录：{}）",
+            path.display(),
+            root.display()
+        ),
+        ScanError::GitIndexFailed { path, reason } => {
+            format!("无法加载 git 索引：{}（{}）", path.display(), reason)
+        }
+        ScanError::OutputLimitReached { limit } => format!("已达到 {} 字节的输出大小上限", limit),
+        ScanError::OutputClosed => "输出已被读取方关闭".to_string(),
+    }
+}
+
+/// Translates a match error.
+fn match_error_zh(err: &MatchError) -> String {
+    match err {
+        MatchError::InvalidPattern { pattern, reason } => {
+            format!("模式 '{}' 无效：{}", pattern, reason)
+        }
+        MatchError::GitignoreParseError { path, .. } => {
+            format!("无法解析 .gitignore：{}", path.display())
+        }
+        MatchError::GitignoreBuildError { reason } => {
+            format!("无法构建 gitignore 规则：{}", reason)
+        }
+    }
+}
+
+/// Translates a render error.
+fn render_error_zh(err: &RenderError) -> String {
+    match err {
+        RenderError::FormatError { context, detail } => {
+            format!("格式化错误（{}）：{}", context, detail)
+        }
+        RenderError::InvalidUtf8Path { .. } => "编码错误：路径包含无效的 UTF-8 字符".to_string(),
+        RenderError::BannerFetchFailed { reason } => {
+            format!("无法获取 Windows tree 样板信息：{}", reason)
+        }
+        RenderError::InvalidPath { path, reason } => {
+            format!("路径 '{}' 无效：{}", path.display(), reason)
+        }
+    }
+}
+
+/// Translates an output error.
+fn output_error_zh(err: &OutputError) -> String {
+    match err {
+        OutputError::FileCreateFailed { path, .. } => {
+            format!("无法创建输出文件：{}", path.display())
+        }
+        OutputError::WriteFailed { path, .. } => format!("无法写入文件：{}", path.display()),
+        OutputError::SerializationFailed { format, reason } => {
+            format!("{} 序列化失败：{}", format, reason)
+        }
+        OutputError::StdoutFailed { .. } => "无法写入标准输出".to_string(),
+        OutputError::InvalidOutputPath { path, reason } => {
+            format!("输出路径无效：{}（{}）", path.display(), reason)
+        }
+        OutputError::FileExists { path } => format!(
+            "输出文件已存在：{}（请删除它，或改用 --append 代替 --no-clobber）",
+            path.display()
+        ),
+        OutputError::RegistryFailed { key, .. } => format!("无法修改注册表项：{}", key),
+        OutputError::ListenFailed { address, .. } => format!("无法监听 {}", address),
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_format_report_line_in_english() {
+        let duration = Duration::from_millis(100);
+        assert_eq!(
+            report_line(Language::English, 5, Some(20), duration),
+            "5 directory, 20 files in 0.100s"
+        );
+        assert_eq!(
+            report_line(Language::English, 5, None, duration),
+            "5 directory in 0.100s"
+        );
+    }
+
+    #[test]
+    fn should_format_report_line_in_chinese() {
+        let duration = Duration::from_millis(50);
+        assert_eq!(
+            report_line(Language::Chinese, 5, Some(20), duration),
+            "5 个目录，20 个文件，用时 0.050s"
+        );
+        assert_eq!(
+            report_line(Language::Chinese, 3, None, duration),
+            "3 个目录，用时 0.050s"
+        );
+    }
+
+    #[test]
+    fn should_name_resumed_directory() {
+        let dir = PathBuf::from("D:").join("Data");
+        let english = checkpoint_resumed(Language::English, &dir);
+        assert!(english.ends_with(&dir.display().to_string()));
+        let chinese = checkpoint_resumed(Language::Chinese, &dir);
+        assert!(
+            chinese.contains(&dir.display().to_string()),
+            "译文应包含目录: {chinese}"
+        );
+    }
+
+    #[test]
+    fn should_keep_display_text_for_english_errors() {
+        let err: TreeppError = ScanError::PathNotFound {
+            path: PathBuf::from("missing"),
+        }
+        .into();
+        assert_eq!(error_message(&err, Language::English), err.to_string());
+        assert_eq!(error_category(&err, Language::English), "Scan error");
+    }
+
+    #[test]
+    fn should_translate_errors_to_chinese() {
+        let err: TreeppError = ScanError::PathNotFound {
+            path: PathBuf::from("missing"),
+        }
+        .into();
+        assert_eq!(
+            error_message(&err, Language::Chinese),
+            "路径不存在：missing"
+        );
+        assert_eq!(error_category(&err, Language::Chinese), "扫描错误");
+
+        let err: TreeppError = CliError::ConflictingOptions {
+            opt_a: "--thread".to_string(),
+            opt_b: "(no --batch)".to_string(),
+        }
+        .into();
+        assert_eq!(
+            error_message(&err, Language::Chinese),
+            "选项冲突：--thread 与 (no --batch) 不能同时使用。"
+        );
+    }
+
+    #[test]
+    fn should_keep_placeholders_in_chinese_errors() {
+        let err: TreeppError = OutputError::FileExists {
+            path: PathBuf::from("tree.txt"),
+        }
+        .into();
+        let message = error_message(&err, Language::Chinese);
+        assert!(message.contains("tree.txt"), "译文应包含路径: {message}");
+        assert!(
+            message.contains("--no-clobber"),
+            "译文应保留选项名: {message}"
+        );
+    }
+
+    #[test]
+    fn should_pluralize_file_noun_in_english_only() {
+        assert_eq!(file_noun(Language::English, 1), "file");
+        assert_eq!(file_noun(Language::English, 2), "files");
+        assert_eq!(file_noun(Language::Chinese, 1), "个文件");
+    }
+
+    #[test]
+    fn should_localize_did_you_mean_hint() {
+        assert_eq!(
+            did_you_mean_hint(Language::English, "size"),
+            "Hint: did you mean 'size'?"
+        );
+        assert_eq!(
+            did_you_mean_hint(Language::Chinese, "size"),
+            "提示：是否要使用 'size'？"
+        );
+    }
+
+    #[test]
+    fn should_localize_render_warning() {
+        let err = RenderError::BannerFetchFailed {
+            reason: "timeout".to_string(),
+        };
+        assert_eq!(
+            render_warning(&err, Language::Chinese),
+            "警告：无法获取 Windows tree 样板信息：timeout"
+        );
+    }
+
+    #[test]
+    fn should_localize_thread_clamp_warning() {
+        let warning = ConfigWarning::ThreadCountClamped {
+            requested: 10000,
+            used: 256,
+        };
+        assert_eq!(
+            warning_message(&warning, Language::English),
+            "Warning: Thread count 10000 is too large; using 256 threads"
+        );
+        assert_eq!(
+            warning_message(&warning, Language::Chinese),
+            "警告：线程数 10000 过大，将使用 256 个线程"
+        );
+    }
+
+    #[test]
+    fn should_format_unreadable_summary() {
+        assert_eq!(
+            unreadable_summary(Language::English, 2),
+            "2 directories could not be read"
+        );
+        assert_eq!(
+            unreadable_summary(Language::English, 1),
+            "1 directory could not be read"
+        );
+        assert_eq!(unreadable_summary(Language::Chinese, 2), "2 个目录无法读取");
+    }
+
+    #[test]
+    fn should_format_budget_summary() {
+        assert_eq!(
+            budget_summary(Language::English, 4),
+            "scan budget ran out; 4 directories were not read"
+        );
+        assert_eq!(
+            budget_summary(Language::Chinese, 1),
+            "扫描预算已用完；1 个目录未读取"
+        );
+    }
+
+    #[test]
+    fn should_format_vanished_warning() {
+        assert_eq!(
+            vanished_warning(Language::English, 2),
+            "Warning: 2 entries were deleted or renamed during the scan and are not shown"
+        );
+        assert_eq!(
+            vanished_warning(Language::Chinese, 1),
+            "警告：1 个条目在扫描期间被删除或重命名，未显示"
+        );
+    }
+
+    #[test]
+    fn should_describe_context_menu_changes() {
+        assert_eq!(
+            context_menu_installed(Language::English, MenuScope::User),
+            "Added \"Open tree++ here\" to the folder context menu for the current user"
+        );
+        assert_eq!(
+            context_menu_removed(Language::Chinese, MenuScope::Machine, false),
+            "所有用户未安装文件夹右键菜单项"
+        );
+    }
+}
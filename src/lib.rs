@@ -0,0 +1,44 @@
+//! tree++ library: the scanner, filters, renderers and output writers
+//! behind the `treepp` command.
+//!
+//! The command-line tool in `main.rs` and the C interface in `treepp-ffi`
+//! are built on this crate. A scan is configured with a validated
+//! `config::Config`, usually parsed from command-line arguments with
+//! `cli::CliParser`, and run with `scan::scan`, `scan::scan_streaming` or
+//! `scan::Scanner`.
+//!
+//! File: src/lib.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![deny(missing_docs)]
+#![allow(dead_code)]
+
+pub mod arena;
+pub mod cache;
+pub mod checkpoint;
+pub mod cli;
+pub mod config;
+pub mod contextmenu;
+pub mod error;
+pub mod gitindex;
+pub mod i18n;
+pub mod logging;
+pub mod matching;
+pub mod normalize;
+pub mod output;
+pub mod redact;
+pub mod render;
+pub mod report;
+pub mod scan;
+pub mod server;
+pub mod settings;
+pub mod snapshot;
+pub mod stats;
+pub mod transform;
+pub mod vfs;
+pub mod warning;
+pub mod winpath;
+pub mod winvol;
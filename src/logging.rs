@@ -0,0 +1,163 @@
+//! Diagnostic log module: `--verbose` and `--log-file`.
+//!
+//! tree++ reports what it does while scanning through `tracing` events, and
+//! this module installs the subscriber that prints them in a human-readable
+//! form. Nothing is logged unless `--verbose` or `--log-file` is given:
+//!
+//! | Level | Switch          | Reported                                                  |
+//! |-------|-----------------|-----------------------------------------------------------|
+//! | info  | `/VB`           | Scan backend, entries rejected per filter, skipped        |
+//! |       |                 | subtrees, phase timings                                   |
+//! | debug | `/VB /VB`       | Loaded `.gitignore` files, detected projects (`/SM`)      |
+//!
+//! The log goes to stderr, so it never mixes with the tree on stdout, or to
+//! the file given by `--log-file`. `-v` remains the version switch, which is
+//! why verbosity is raised by repeating `--verbose` instead of `-vv`.
+//!
+//! File: src/logging.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::fs::File;
+use std::io;
+use std::sync::Mutex;
+
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::time::Uptime;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+
+use crate::config::OutputOptions;
+use crate::error::OutputError;
+
+// ============================================================================
+// Public Functions
+// ============================================================================
+
+/// Returns the most detailed level logged at a verbosity.
+///
+/// # Arguments
+///
+/// * `verbosity` - Number of `--verbose` switches.
+///
+/// # Returns
+///
+/// `OFF` for 0, `INFO` for 1 and `DEBUG` for 2 or more.
+///
+/// # Examples
+///
+/// ```
+/// use tracing::level_filters::LevelFilter;
+/// use treepp::logging::level_filter;
+///
+/// assert_eq!(level_filter(0), LevelFilter::OFF);
+/// assert_eq!(level_filter(2), LevelFilter::DEBUG);
+/// ```
+#[must_use]
+pub const fn level_filter(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::OFF,
+        1 => LevelFilter::INFO,
+        _ => LevelFilter::DEBUG,
+    }
+}
+
+/// Installs the log subscriber for the configured verbosity.
+///
+/// Does nothing if the verbosity is 0. Each event is written as one line
+/// with the time since startup and its level, e.g.
+/// `0.004211s  INFO backend: streaming`.
+///
+/// # Arguments
+///
+/// * `options` - Output options holding the verbosity and log file.
+///
+/// # Errors
+///
+/// Returns `OutputError::FileCreateFailed` if the log file cannot be created.
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::config::OutputOptions;
+/// use treepp::logging::init;
+///
+/// let options = OutputOptions { verbosity: 1, ..OutputOptions::default() };
+/// init(&options).expect("log setup failed");
+/// tracing::info!("visible on stderr");
+/// ```
+pub fn init(options: &OutputOptions) -> Result<(), OutputError> {
+    if options.verbosity == 0 {
+        return Ok(());
+    }
+
+    let writer = match options.log_file {
+        Some(ref path) => {
+            let file = File::create(path).map_err(|source| OutputError::FileCreateFailed {
+                path: path.clone(),
+                source,
+            })?;
+            BoxMakeWriter::new(Mutex::new(file))
+        }
+        None => BoxMakeWriter::new(io::stderr),
+    };
+
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(level_filter(options.verbosity))
+        .with_target(false)
+        .with_timer(Uptime::default())
+        .with_writer(writer)
+        .finish();
+    // Only fails if a subscriber is already installed, which then keeps logging.
+    let _ = tracing::subscriber::set_global_default(subscriber);
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn should_map_verbosity_to_levels() {
+        assert_eq!(level_filter(0), LevelFilter::OFF);
+        assert_eq!(level_filter(1), LevelFilter::INFO);
+        assert_eq!(level_filter(2), LevelFilter::DEBUG);
+        assert_eq!(
+            level_filter(u8::MAX),
+            LevelFilter::DEBUG,
+            "更高级别应按 debug 处理"
+        );
+    }
+
+    #[test]
+    fn should_not_create_log_file_when_quiet() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("scan.log");
+        let options = OutputOptions {
+            log_file: Some(path.clone()),
+            ..OutputOptions::default()
+        };
+        init(&options).unwrap();
+        assert!(!path.exists(), "级别为 0 时不应创建日志文件");
+    }
+
+    #[test]
+    fn should_fail_for_unwritable_log_file() {
+        let dir = TempDir::new().unwrap();
+        let options = OutputOptions {
+            verbosity: 1,
+            log_file: Some(dir.path().join("missing").join("scan.log")),
+            ..OutputOptions::default()
+        };
+        let err = init(&options).unwrap_err();
+        assert!(matches!(err, OutputError::FileCreateFailed { ref path, .. }
+            if path.ends_with(PathBuf::from("missing").join("scan.log"))));
+    }
+}
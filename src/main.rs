@@ -17,33 +17,45 @@
 //! | `1`  | CLI/argument error |
 //! | `2`  | Scan error |
 //! | `3`  | Output error |
+//! | `4`  | Some directories could not be read |
+//! | `5`  | Scan budget ran out (`--budget-time`, `--budget-entries`) |
+//!
+//! With `--json-errors`, an error is written as a JSON object carrying a
+//! stable code whose first digit is the exit code (see `TreeppError::code`).
 //!
 //! File: src/main.rs
 //! Author: WaterRun
-//! Date: 2026-01-12
+//! Date: 2026-10-16
 
 #![forbid(unsafe_code)]
 #![deny(warnings)]
 #![deny(missing_docs)]
-#![allow(dead_code)]
-
-mod cli;
-mod config;
-mod error;
-mod output;
-mod render;
-mod scan;
-
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::{Component, Path};
+
+use std::env;
+use std::io::{self, IsTerminal, Stdout, Write};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::Instant;
 
-use cli::{CliError, CliParser, ParseResult};
-use config::Config;
-use error::{OutputError, ScanError, TreeppError};
-use render::{StreamRenderConfig, StreamRenderer, TreeChars, WinBanner};
-use scan::{EntryKind, StreamEvent};
+use serde::Serialize;
+use tracing::info;
+use treepp::checkpoint::{Checkpoint, LoadOutcome, Recorder};
+use treepp::cli::{CliParser, ParseResult};
+use treepp::config::{
+    CharsetMode, Config, EscapeMode, Language, LevelGuides, MenuScope, PagerMode,
+};
+use treepp::error::{CliError, OutputError, ScanError, TreeppError};
+use treepp::output::{OutputFile, PagedWriter, StreamWriter, TextEncoder};
+use treepp::render::{StreamRenderConfig, StreamRenderer, WinBanner};
+use treepp::scan::{EntryKind, StreamEvent};
+use treepp::server::{PipeListener, TreeServer};
+use treepp::settings::{Settings, SettingsCommand};
+use treepp::stats::{CountSummary, ScanSummary, StatsCollector};
+use treepp::winvol::Utf8Console;
+use treepp::{
+    checkpoint, cli, contextmenu, i18n, logging, output, render, report, scan, server, settings,
+    stats, warning, winpath, winvol,
+};
 
 /// Exit code indicating successful execution.
 const EXIT_SUCCESS: u8 = 0;
@@ -57,6 +69,29 @@ const EXIT_SCAN_ERROR: u8 = 2;
 /// Exit code indicating an output writing error.
 const EXIT_OUTPUT_ERROR: u8 = 3;
 
+/// Exit code indicating that some directories could not be read.
+const EXIT_PARTIAL_SCAN: u8 = 4;
+
+/// Exit code indicating that the scan stopped at `--budget-time` or `--budget-entries`.
+const EXIT_BUDGET_EXHAUSTED: u8 = 5;
+
+/// An error as written by `--json-errors`.
+#[derive(Serialize)]
+struct ErrorReport {
+    /// Stable error code, e.g. `E101`.
+    code: &'static str,
+    /// Stable error category, e.g. `cli`.
+    category: &'static str,
+    /// Exit code of the process.
+    exit_code: u8,
+    /// File or directory the error concerns.
+    path: Option<String>,
+    /// Error message in the language of `--lang`.
+    message: String,
+    /// Hint for resolving the error.
+    hint: Option<String>,
+}
+
 /// Program main entry point.
 ///
 /// Parses command-line arguments and executes the appropriate action.
@@ -68,12 +103,23 @@ const EXIT_OUTPUT_ERROR: u8 = 3;
 /// - `EXIT_CLI_ERROR` (1) on argument errors
 /// - `EXIT_SCAN_ERROR` (2) on scan errors
 /// - `EXIT_OUTPUT_ERROR` (3) on output errors
+/// - `EXIT_PARTIAL_SCAN` (4) if the tree was produced but some directories
+///   could not be read
+/// - `EXIT_BUDGET_EXHAUSTED` (5) if the scan budget ran out before the tree
+///   was complete
 fn main() -> ExitCode {
-    match run() {
-        Ok(()) => ExitCode::from(EXIT_SUCCESS),
+    let parser = CliParser::from_env();
+    let language = parser.language();
+    let json_errors = parser.json_errors();
+    match run(parser, language) {
+        Ok(code) => ExitCode::from(code),
         Err(e) => {
             let code = error_to_exit_code(&e);
-            print_error(&e);
+            if json_errors {
+                print_json_error(&e, language, code);
+            } else {
+                print_error(&e, language);
+            }
             ExitCode::from(code)
         }
     }
@@ -83,40 +129,148 @@ fn main() -> ExitCode {
 ///
 /// Selects between batch mode and streaming mode based on configuration.
 ///
+/// # Arguments
+///
+/// * `parser` - Parser holding the command-line arguments.
+/// * `language` - Language of the help text.
+///
 /// # Returns
 ///
-/// Returns `Ok(())` on success, or a `TreeppError` on failure.
+/// Returns the exit code on success (`EXIT_SUCCESS`, `EXIT_PARTIAL_SCAN` or
+/// `EXIT_BUDGET_EXHAUSTED`), or a `TreeppError` on failure.
 ///
 /// # Errors
 ///
 /// Returns an error if:
+/// - The settings file is invalid
 /// - CLI parsing fails
 /// - Configuration validation fails
+/// - The log file cannot be created
 /// - Directory scanning fails
 /// - Output writing fails
-fn run() -> Result<(), TreeppError> {
-    let parser = CliParser::from_env();
-    let parse_result = parser.parse()?;
+fn run(parser: CliParser, language: Language) -> Result<u8, TreeppError> {
+    let settings = Settings::load(&settings::settings_file_path())?;
+    let parse_result = parser.with_settings(settings.clone()).parse()?;
 
     match parse_result {
         ParseResult::Help => {
-            cli::print_help();
-            Ok(())
+            cli::print_help(language);
+            Ok(EXIT_SUCCESS)
         }
         ParseResult::Version => {
             cli::print_version();
-            Ok(())
+            Ok(EXIT_SUCCESS)
+        }
+        ParseResult::Settings(command) => {
+            settings_mode(settings, &command, language).map(|()| EXIT_SUCCESS)
         }
-        ParseResult::Config(config) => {
-            if config.batch_mode {
+        ParseResult::Config(mut config) => {
+            warning::set_quiet(config.output.quiet_warnings);
+            for issue in &config.warnings {
+                warning::emit(&i18n::warning_message(issue, language));
+            }
+            logging::init(&config.output)?;
+            if config.render.truncate && config.output.output_path.is_none() {
+                // Only a console has a width; files and redirected output
+                // keep whole lines.
+                config.render.line_width = winvol::console_width();
+            }
+            if config.render.columns && config.output.output_path.is_none() {
+                // Files and redirected output are laid out for a standard
+                // console width.
+                config.render.columns_width = winvol::console_width();
+            }
+            if !config.output.silent {
+                config.output.page_after = match config.output.pager {
+                    PagerMode::Never => None,
+                    // One row stays free for the prompt of the pager.
+                    PagerMode::Auto => winvol::console_height().map(|rows| rows - 1),
+                    PagerMode::Always => Some(0),
+                };
+            }
+            if config.sanitizes_console_names() && io::stdout().is_terminal() {
+                // Names are written as with `/ES question`, so control
+                // characters in them are not interpreted by the console.
+                config.render.escape = EscapeMode::Question;
+            }
+            if config.render.highlight || config.render.level_guides == LevelGuides::Color {
+                // Redirected output keeps the escape sequences; only a
+                // console needs to be told to interpret them.
+                let _ = winvol::enable_virtual_terminal();
+            }
+            let console = if config.output.silent {
+                Utf8Console::NotConsole
+            } else {
+                winvol::enable_utf8_output()
+            };
+            if config.render.charset == CharsetMode::Auto {
+                config.render.charset = auto_charset(&config, console);
+            }
+
+            let result = if let Some(scope) = config.install_menu {
+                install_menu_mode(scope, language).map(|()| EXIT_SUCCESS)
+            } else if let Some(scope) = config.uninstall_menu {
+                uninstall_menu_mode(scope, language).map(|()| EXIT_SUCCESS)
+            } else if config.print_config {
+                print_config(&config).map(|()| EXIT_SUCCESS)
+            } else if config.print_schema {
+                print_schema().map(|()| EXIT_SUCCESS)
+            } else if config.serve {
+                serve_mode(&config).map(|()| EXIT_SUCCESS)
+            } else if let Some(ref target) = config.explain_target {
+                explain_mode(&config, target).map(|()| EXIT_SUCCESS)
+            } else if config.render.count_only {
+                count_mode(&config)
+            } else if config.batch_mode {
                 batch_mode(&config)
             } else {
-                stream_mode(&config)
+                let resumed = load_checkpoint(&config);
+                config.scan.resume_after = resumed.as_ref().map(|c| c.completed.clone());
+                stream_mode(&config, resumed.as_deref())
+            };
+            if result.is_ok()
+                && let (Some(keep), Some(report)) =
+                    (config.output.rotate, &config.output.output_path)
+                && let Err(e) = report::rotate(report, keep)
+            {
+                warning::emit(&i18n::rotation_warning(language, &e.to_string()));
+            }
+            if let Utf8Console::Enabled { previous } = console {
+                // The code page belongs to the console, which outlives tree++.
+                winvol::set_output_code_page(previous);
             }
+            result
         }
     }
 }
 
+/// Resolves `--charset auto` for the current console.
+///
+/// ASCII is used when the tree goes only to a console that cannot show
+/// box-drawing characters: one left on a legacy code page, or one using a
+/// raster font. Output files and redirected output always use Unicode.
+///
+/// # Arguments
+///
+/// * `config` - The validated configuration.
+/// * `console` - Outcome of switching the console to UTF-8.
+///
+/// # Returns
+///
+/// `CharsetMode::Ascii` or `CharsetMode::Unicode`.
+fn auto_charset(config: &Config, console: Utf8Console) -> CharsetMode {
+    let legacy_console = match console {
+        Utf8Console::NotConsole => false,
+        Utf8Console::Unavailable => true,
+        Utf8Console::Enabled { .. } => winvol::console_uses_raster_font() == Some(true),
+    };
+    if legacy_console && config.output.output_path.is_none() {
+        CharsetMode::Ascii
+    } else {
+        CharsetMode::Unicode
+    }
+}
+
 /// Executes the batch processing pipeline.
 ///
 /// Performs a complete scan of the directory tree, then renders and outputs
@@ -129,20 +283,357 @@ fn run() -> Result<(), TreeppError> {
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` on success, or a `TreeppError` on failure.
+/// Returns the exit code on success (see `report_unreadable`), or a
+/// `TreeppError` on failure.
 ///
 /// # Errors
 ///
 /// Returns an error if:
 /// - Directory scanning fails
 /// - Output writing fails
-fn batch_mode(config: &Config) -> Result<(), TreeppError> {
+fn batch_mode(config: &Config) -> Result<u8, TreeppError> {
+    output::check_no_clobber(config)?;
     let stats = scan::scan(config)?;
+    let phase = Instant::now();
     let render_result = render::render(&stats, config);
+    info!("render: {:.2?}", phase.elapsed());
+
+    let phase = Instant::now();
     output::execute_output(&render_result, &stats.tree, config)?;
+    info!("output: {:.2?}", phase.elapsed());
+
+    if let Some(ref target) = config.output.stats_json {
+        let summary = ScanSummary::new(
+            config,
+            StatsCollector::from_tree(&stats.tree),
+            stats.directory_count,
+            stats.file_count,
+            stats.duration,
+        );
+        stats::write_summary(&summary, target)?;
+    }
+    report_vanished(config, stats.vanished_entries);
+    Ok(report_unreadable(
+        config,
+        &stats.unreadable_dirs,
+        stats.tree.count_unread(),
+    ))
+}
+
+/// Reports directories that could not be read and picks the exit code.
+///
+/// Prints a summary line to stderr, followed by the directories themselves
+/// with `--report-errors`, and a line for directories left unread by the
+/// scan budget.
+///
+/// # Arguments
+///
+/// * `config` - The validated configuration.
+/// * `unreadable` - Directories that could not be read.
+/// * `unread_dirs` - Number of directories left unread by `--budget-time`
+///   or `--budget-entries`.
+///
+/// # Returns
+///
+/// `EXIT_BUDGET_EXHAUSTED` if the scan budget ran out, `EXIT_PARTIAL_SCAN`
+/// if any directory could not be read, `EXIT_SUCCESS` otherwise.
+fn report_unreadable(config: &Config, unreadable: &[PathBuf], unread_dirs: usize) -> u8 {
+    let language = config.render.language;
+    if !unreadable.is_empty() {
+        eprintln!(
+            "tree++: {}",
+            i18n::unreadable_summary(language, unreadable.len())
+        );
+        if config.render.report_errors {
+            for dir in unreadable {
+                eprintln!("  {}", dir.display());
+            }
+        }
+    }
+    if unread_dirs > 0 {
+        eprintln!("tree++: {}", i18n::budget_summary(language, unread_dirs));
+        return EXIT_BUDGET_EXHAUSTED;
+    }
+    if unreadable.is_empty() {
+        EXIT_SUCCESS
+    } else {
+        EXIT_PARTIAL_SCAN
+    }
+}
+
+/// Warns about entries deleted or renamed while the scan was listing them.
+///
+/// Such entries are left out of the tree and do not affect the exit code.
+fn report_vanished(config: &Config, count: usize) {
+    if count > 0 {
+        warning::emit(&i18n::vanished_warning(config.render.language, count));
+    }
+}
+
+/// Explains why a single path is shown or hidden (`--explain`).
+///
+/// Runs the filter chain for the path and prints the deciding rule to
+/// stdout instead of rendering a tree.
+///
+/// # Arguments
+///
+/// * `config` - The validated configuration.
+/// * `target` - The path to explain.
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or a `TreeppError` on failure.
+///
+/// # Errors
+///
+/// Returns an error if the path cannot be resolved below the root or does
+/// not exist, or if writing to stdout fails.
+fn explain_mode(config: &Config, target: &Path) -> Result<(), TreeppError> {
+    let explanation = scan::explain_path(config, target)?;
+    let text = render::render_explanation(&explanation, &config.root_path);
+    let stdout = io::stdout();
+    let mut handle = PagedWriter::new(stdout.lock(), None);
+    handle
+        .write_all(text.as_bytes())
+        .and_then(|()| handle.finish())
+        .map_err(OutputError::from)?;
     Ok(())
 }
 
+/// Answers queries about the tree over local HTTP, a named pipe, or both,
+/// until shut down (`--serve`).
+///
+/// # Arguments
+///
+/// * `config` - The validated configuration.
+///
+/// # Returns
+///
+/// Returns `Ok(())` once a `shutdown` request was answered, or a
+/// `TreeppError` on failure.
+///
+/// # Errors
+///
+/// Returns an error if the port or the pipe cannot be listened on.
+fn serve_mode(config: &Config) -> Result<(), TreeppError> {
+    let language = config.render.language;
+    let pipe = config
+        .serve_pipe
+        .as_deref()
+        .map(PipeListener::bind)
+        .transpose()?;
+    let http = if pipe.is_none() || config.serve_port.is_some() {
+        let port = config.serve_port.unwrap_or(server::DEFAULT_PORT);
+        Some(server::bind(port)?)
+    } else {
+        None
+    };
+
+    if let Some(listener) = &http {
+        let url = listener.local_addr().map_or_else(
+            |_| "http://127.0.0.1/".to_string(),
+            |address| format!("http://{address}/"),
+        );
+        println!(
+            "{}",
+            i18n::server_listening(language, &url, &config.root_path)
+        );
+    }
+    if let Some(listener) = &pipe {
+        println!(
+            "{}",
+            i18n::server_listening(language, listener.path(), &config.root_path)
+        );
+    }
+    server::run(TreeServer::new(config.clone()), http, pipe);
+    Ok(())
+}
+
+/// Adds "Open tree++ here" to the folder context menu (`--install-context-menu`).
+///
+/// The entry runs this executable; if its path cannot be determined, the
+/// entry runs `treepp.exe` from `PATH` instead.
+///
+/// # Arguments
+///
+/// * `scope` - Whose menu to change.
+/// * `language` - Language of the entry and of the confirmation.
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or a `TreeppError` on failure.
+///
+/// # Errors
+///
+/// Returns an error if the registry cannot be written.
+fn install_menu_mode(scope: MenuScope, language: Language) -> Result<(), TreeppError> {
+    let exe = env::current_exe().unwrap_or_else(|_| PathBuf::from("treepp.exe"));
+    contextmenu::install(scope, i18n::context_menu_label(language), &exe)?;
+    println!("{}", i18n::context_menu_installed(language, scope));
+    Ok(())
+}
+
+/// Runs a `treepp config` command against the settings file.
+///
+/// # Arguments
+///
+/// * `settings` - The settings loaded at startup.
+/// * `command` - The command to run.
+/// * `language` - Language of the confirmation.
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or a `TreeppError` on failure.
+///
+/// # Errors
+///
+/// Returns an error if a bookmarked path is not a directory, or if the
+/// changed settings cannot be written.
+fn settings_mode(
+    mut settings: Settings,
+    command: &SettingsCommand,
+    language: Language,
+) -> Result<(), TreeppError> {
+    let messages: Vec<String> = match command {
+        SettingsCommand::ListExcludes => {
+            for pattern in &settings.exclude {
+                println!("{}", pattern);
+            }
+            return Ok(());
+        }
+        SettingsCommand::AddExclude(patterns) => patterns
+            .iter()
+            .map(|p| i18n::exclude_added(language, p, settings.add_exclude(p)))
+            .collect(),
+        SettingsCommand::RemoveExclude(patterns) => patterns
+            .iter()
+            .map(|p| i18n::exclude_removed(language, p, settings.remove_exclude(p)))
+            .collect(),
+        SettingsCommand::ListBookmarks => {
+            let width = settings.bookmark.keys().map(String::len).max().unwrap_or(0);
+            for (name, path) in &settings.bookmark {
+                println!("{:<width$}  {}", name, path.display(), width = width);
+            }
+            return Ok(());
+        }
+        SettingsCommand::AddBookmark { name, path } => {
+            let path = settings.add_bookmark(name, path)?;
+            vec![i18n::bookmark_added(language, name, &path)]
+        }
+        SettingsCommand::RemoveBookmark(name) => {
+            vec![i18n::bookmark_removed(
+                language,
+                name,
+                settings.remove_bookmark(name),
+            )]
+        }
+    };
+    settings.save(&settings::settings_file_path())?;
+    for message in messages {
+        println!("{}", message);
+    }
+    Ok(())
+}
+
+/// Removes the folder context-menu entry (`--uninstall-context-menu`).
+///
+/// # Arguments
+///
+/// * `scope` - Whose menu to change.
+/// * `language` - Language of the confirmation.
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, including when no entry was installed, or a
+/// `TreeppError` on failure.
+///
+/// # Errors
+///
+/// Returns an error if an existing registry key cannot be deleted.
+fn uninstall_menu_mode(scope: MenuScope, language: Language) -> Result<(), TreeppError> {
+    let removed = contextmenu::uninstall(scope)?;
+    println!("{}", i18n::context_menu_removed(language, scope, removed));
+    Ok(())
+}
+
+/// Prints the resolved configuration as TOML (`--print-config`).
+///
+/// The configuration is printed after parsing, validation and the settings
+/// derived at startup (console width and pager), so it shows exactly what
+/// a scan would use. Options that are not set are left out.
+///
+/// # Arguments
+///
+/// * `config` - The validated configuration.
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or a `TreeppError` on failure.
+///
+/// # Errors
+///
+/// Returns an error if the configuration cannot be serialized or writing to
+/// stdout fails.
+fn print_config(config: &Config) -> Result<(), TreeppError> {
+    let text =
+        toml::to_string_pretty(config).map_err(|e| OutputError::toml_error(e.to_string()))?;
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+    handle
+        .write_all(text.as_bytes())
+        .map_err(OutputError::from)?;
+    Ok(())
+}
+
+/// Prints the JSON Schema of the JSON and YAML output (`--print-schema`).
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or a `TreeppError` on failure.
+///
+/// # Errors
+///
+/// Returns an error if the schema cannot be serialized or writing to stdout
+/// fails.
+fn print_schema() -> Result<(), TreeppError> {
+    let schema = output::structured_schema()?;
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+    writeln!(handle, "{schema}").map_err(OutputError::from)?;
+    Ok(())
+}
+
+/// Loads the checkpoint of an interrupted `--checkpoint` run.
+///
+/// Prints a notice to stderr when a checkpoint is resumed or ignored.
+///
+/// # Arguments
+///
+/// * `config` - The validated configuration.
+///
+/// # Returns
+///
+/// The checkpoint to continue from, or `None` if the run starts over.
+fn load_checkpoint(config: &Config) -> Option<Box<Checkpoint>> {
+    if !config.output.checkpoint {
+        return None;
+    }
+
+    let language = config.render.language;
+    match checkpoint::load(config) {
+        LoadOutcome::Missing => None,
+        LoadOutcome::Stale => {
+            eprintln!("tree++: {}", i18n::checkpoint_ignored(language));
+            None
+        }
+        LoadOutcome::Resumable(checkpoint) => {
+            let completed = config.root_path.join(&checkpoint.completed);
+            eprintln!("tree++: {}", i18n::checkpoint_resumed(language, &completed));
+            Some(checkpoint)
+        }
+    }
+}
+
 /// Executes the streaming pipeline.
 ///
 /// Scans, renders, and outputs the directory tree simultaneously for
@@ -152,13 +643,18 @@ fn batch_mode(config: &Config) -> Result<(), TreeppError> {
 /// - If an output file is specified, writes to both file and stdout (unless silent)
 /// - `disk_usage` is unavailable (requires batch mode)
 ///
+/// With `--checkpoint`, progress is recorded while scanning, and a run given
+/// a checkpoint continues the output file after its last directory.
+///
 /// # Arguments
 ///
 /// * `config` - The validated configuration specifying scan and render options.
+/// * `resumed` - The checkpoint of an interrupted run to continue, if any.
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` on success, or a `TreeppError` on failure.
+/// Returns the exit code on success (see `report_unreadable`), or a
+/// `TreeppError` on failure.
 ///
 /// # Errors
 ///
@@ -166,30 +662,81 @@ fn batch_mode(config: &Config) -> Result<(), TreeppError> {
 /// - Output file creation fails
 /// - Directory scanning fails
 /// - Writing to file or stdout fails
-fn stream_mode(config: &Config) -> Result<(), TreeppError> {
-    let mut file_writer = create_file_writer_if_needed(config)?;
-    let mut output_context = StreamOutputContext::new(config, &mut file_writer);
+fn stream_mode(config: &Config, resumed: Option<&Checkpoint>) -> Result<u8, TreeppError> {
+    let mut file_writer = match config.output.output_path {
+        Some(ref path) if config.output.checkpoint => {
+            let len = resumed.map_or(0, |checkpoint| checkpoint.output_len);
+            Some(OutputFile::resume(path, config, len)?)
+        }
+        _ => create_file_writer_if_needed(config)?,
+    };
+    let stdout = io::stdout();
+    let mut output_context = StreamOutputContext::new(config, &mut file_writer, &stdout);
 
     let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(config));
-    let chars = TreeChars::from_charset(config.render.charset);
-
-    let header = renderer.render_header(&config.root_path, config.path_explicitly_set);
-    output_context.write(&header)?;
-
     let mut has_subdirs = false;
-    let mut has_files = false;
+    let mut collector = config.needs_collected_stats().then(StatsCollector::default);
+    let mut recorder = config
+        .output
+        .checkpoint
+        .then(|| Recorder::new(config, resumed));
 
-    let stats = scan::scan_streaming(config, |event| {
-        handle_stream_event(
-            event,
-            &mut renderer,
-            &mut output_context,
-            &mut has_subdirs,
-            &mut has_files,
-        )
+    match resumed {
+        // The header was written by the interrupted run.
+        Some(checkpoint) => {
+            renderer.restore(checkpoint.renderer.clone());
+            has_subdirs = checkpoint.has_subdirs;
+            if collector.is_some() {
+                collector = checkpoint.stats.clone().or(collector);
+            }
+        }
+        None => {
+            let header = renderer.render_header(&config.root_path, config.path_explicitly_set);
+            output_context.write(&header)?;
+        }
+    }
+
+    let mut stats = scan::scan_streaming(config, |event| {
+        if let (Some(collector), StreamEvent::Entry(entry)) = (collector.as_mut(), &event) {
+            collector.record_entry(entry);
+        }
+        let due = recorder
+            .as_mut()
+            .is_some_and(|recorder| recorder.record(&event));
+        handle_stream_event(event, &mut renderer, &mut output_context, &mut has_subdirs)?;
+        if due && let Some(recorder) = recorder.as_mut() {
+            output_context
+                .flushed_len()
+                .and_then(|len| {
+                    recorder
+                        .save(len, renderer.state(), has_subdirs, collector.as_ref())
+                        .map_err(OutputError::from)
+                })
+                .map_err(|e| ScanError::WalkError {
+                    message: e.to_string(),
+                    path: None,
+                })?;
+        }
+        Ok(())
     })?;
+    if let Some(ref recorder) = recorder {
+        recorder.complete(&mut stats);
+    }
 
-    render_empty_directory_notice(config, &chars, has_subdirs, has_files, &mut output_context)?;
+    if let Some(trailing) = renderer.finish() {
+        output_context.emit_line(&trailing)?;
+    }
+    render_empty_directory_notice(config, has_subdirs, &mut output_context)?;
+
+    let output_limit = config
+        .output
+        .max_output_size
+        .filter(|_| stats.output_truncated);
+    let notice =
+        render::render_truncation_notice(stats.truncated_dirs, stats.unread_dirs, output_limit);
+    if !notice.is_empty() {
+        output_context.write(&notice)?;
+    }
 
     if config.render.show_report {
         let report =
@@ -199,13 +746,120 @@ fn stream_mode(config: &Config) -> Result<(), TreeppError> {
         }
     }
 
-    output_context.flush()?;
+    if config.render.report_extensions
+        && let Some(ref collector) = collector
+    {
+        let rows = collector.top_extensions(stats::REPORT_EXTENSION_LIMIT);
+        output_context.write(&render::render_extension_report(
+            &rows,
+            config.render.language,
+        ))?;
+    }
+    if config.render.depth_histogram
+        && let Some(ref collector) = collector
+    {
+        let histogram = render::render_depth_histogram(collector.levels(), config.render.language);
+        output_context.write(&histogram)?;
+    }
+
+    output_context.finish()?;
     print_output_path_notice(config);
+    if let Some(recorder) = recorder {
+        recorder.discard().map_err(OutputError::from)?;
+    }
 
-    Ok(())
+    if let (Some(target), Some(collector)) = (&config.output.stats_json, collector) {
+        let summary = ScanSummary::new(
+            config,
+            collector,
+            stats.directory_count,
+            stats.file_count,
+            stats.duration,
+        );
+        stats::write_summary(&summary, target)?;
+    }
+
+    report_vanished(config, stats.vanished_entries);
+    Ok(report_unreadable(
+        config,
+        &stats.unreadable_dirs,
+        stats.unread_dirs,
+    ))
 }
 
-/// Creates a buffered file writer if an output path is configured.
+/// Prints only the totals of a streaming scan (`--count`).
+///
+/// Nothing is rendered while scanning; the entries are only counted, which
+/// makes this the fastest way to size a tree.
+///
+/// # Arguments
+///
+/// * `config` - The validated configuration specifying scan and render options.
+///
+/// # Returns
+///
+/// Returns the exit code on success (see `report_unreadable`), or a
+/// `TreeppError` on failure.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - Output file creation fails
+/// - Directory scanning fails
+/// - Writing to file or stdout fails
+fn count_mode(config: &Config) -> Result<u8, TreeppError> {
+    let mut file_writer = create_file_writer_if_needed(config)?;
+    let stdout = io::stdout();
+    let mut output_context = StreamOutputContext::new(config, &mut file_writer, &stdout);
+
+    let mut collector = StatsCollector::default();
+    let stats = scan::scan_streaming(config, |event| {
+        if let StreamEvent::Entry(ref entry) = event {
+            collector.record_entry(entry);
+        }
+        Ok(())
+    })?;
+
+    let summary = CountSummary::new(&collector, &stats);
+    output_context.write(&render::render_count_summary(
+        &summary,
+        config.render.language,
+    ))?;
+    if config.render.report_extensions {
+        let rows = collector.top_extensions(stats::REPORT_EXTENSION_LIMIT);
+        output_context.write(&render::render_extension_report(
+            &rows,
+            config.render.language,
+        ))?;
+    }
+    if config.render.depth_histogram {
+        let histogram = render::render_depth_histogram(collector.levels(), config.render.language);
+        output_context.write(&histogram)?;
+    }
+
+    output_context.finish()?;
+    print_output_path_notice(config);
+
+    if let Some(ref target) = config.output.stats_json {
+        let summary = ScanSummary::new(
+            config,
+            collector,
+            stats.directory_count,
+            stats.file_count,
+            stats.duration,
+        );
+        stats::write_summary(&summary, target)?;
+    }
+
+    report_vanished(config, stats.vanished_entries);
+    Ok(report_unreadable(
+        config,
+        &stats.unreadable_dirs,
+        stats.unread_dirs,
+    ))
+}
+
+/// Opens the output file if an output path is configured.
 ///
 /// # Arguments
 ///
@@ -213,21 +867,16 @@ fn stream_mode(config: &Config) -> Result<(), TreeppError> {
 ///
 /// # Returns
 ///
-/// Returns `Some(BufWriter<File>)` if an output path is specified,
+/// Returns `Some(OutputFile)` if an output path is specified,
 /// `None` otherwise.
 ///
 /// # Errors
 ///
-/// Returns an error if the file cannot be created.
-fn create_file_writer_if_needed(config: &Config) -> Result<Option<BufWriter<File>>, TreeppError> {
+/// Returns an error if the file cannot be created, or if it exists and
+/// `--no-clobber` is set.
+fn create_file_writer_if_needed(config: &Config) -> Result<Option<OutputFile>, TreeppError> {
     match config.output.output_path {
-        Some(ref path) => {
-            let file = File::create(path).map_err(|e| OutputError::FileCreateFailed {
-                path: path.clone(),
-                source: e,
-            })?;
-            Ok(Some(BufWriter::new(file)))
-        }
+        Some(ref path) => Ok(Some(OutputFile::create(path, config)?)),
         None => Ok(None),
     }
 }
@@ -236,8 +885,12 @@ fn create_file_writer_if_needed(config: &Config) -> Result<Option<BufWriter<File
 struct StreamOutputContext<'a> {
     /// Reference to the configuration.
     config: &'a Config,
+    /// Buffered writer holding the stdout lock, `None` in silent mode.
+    stdout: Option<StreamWriter<'a>>,
     /// Mutable reference to an optional file writer.
-    file_writer: &'a mut Option<BufWriter<File>>,
+    file_writer: &'a mut Option<OutputFile>,
+    /// Number of bytes written so far, checked against `--max-output-size`.
+    written: u64,
 }
 
 impl<'a> StreamOutputContext<'a> {
@@ -247,14 +900,24 @@ impl<'a> StreamOutputContext<'a> {
     ///
     /// * `config` - The configuration controlling output behavior.
     /// * `file_writer` - Mutable reference to an optional file writer.
+    /// * `stdout` - The standard output handle, locked for the whole stream.
     ///
     /// # Returns
     ///
     /// Returns a new `StreamOutputContext` instance.
-    fn new(config: &'a Config, file_writer: &'a mut Option<BufWriter<File>>) -> Self {
+    fn new(
+        config: &'a Config,
+        file_writer: &'a mut Option<OutputFile>,
+        stdout: &'a Stdout,
+    ) -> Self {
         Self {
             config,
+            stdout: (!config.output.silent).then(|| {
+                StreamWriter::with_encoder(stdout, TextEncoder::for_stdout(config))
+                    .paged(config.output.page_after)
+            }),
             file_writer,
+            written: 0,
         }
     }
 
@@ -270,16 +933,14 @@ impl<'a> StreamOutputContext<'a> {
     ///
     /// # Errors
     ///
-    /// Returns an error if writing to the file fails.
+    /// Returns an error if writing to stdout or the file fails.
     fn write(&mut self, content: &str) -> Result<(), TreeppError> {
-        if !self.config.output.silent {
-            print!("{}", content);
+        self.written += content.len() as u64;
+        if let Some(stdout) = self.stdout.as_mut() {
+            stdout.write(content)?;
         }
         if let Some(writer) = self.file_writer.as_mut() {
-            write!(writer, "{}", content).map_err(|e| OutputError::WriteFailed {
-                path: self.config.output.output_path.clone().unwrap(),
-                source: e,
-            })?;
+            writer.write(content)?;
         }
         Ok(())
     }
@@ -296,16 +957,15 @@ impl<'a> StreamOutputContext<'a> {
     ///
     /// # Errors
     ///
-    /// Returns an error if writing to the file fails.
+    /// Returns an error if writing to stdout or the file fails.
     fn writeln(&mut self, content: &str) -> Result<(), TreeppError> {
-        if !self.config.output.silent {
-            println!("{}", content);
+        self.written += content.len() as u64 + 1;
+        if let Some(stdout) = self.stdout.as_mut() {
+            stdout.write_line(content)?;
         }
         if let Some(writer) = self.file_writer.as_mut() {
-            writeln!(writer, "{}", content).map_err(|e| OutputError::WriteFailed {
-                path: self.config.output.output_path.clone().unwrap(),
-                source: e,
-            })?;
+            writer.write(content)?;
+            writer.write("\n")?;
         }
         Ok(())
     }
@@ -318,21 +978,23 @@ impl<'a> StreamOutputContext<'a> {
     ///
     /// # Errors
     ///
-    /// Returns an error if writing to the file fails.
+    /// Returns an error if writing to stdout or the file fails.
     fn writeln_empty(&mut self) -> Result<(), TreeppError> {
-        if !self.config.output.silent {
-            println!();
+        self.written += 1;
+        if let Some(stdout) = self.stdout.as_mut() {
+            stdout.write_line("")?;
         }
         if let Some(writer) = self.file_writer.as_mut() {
-            writeln!(writer).map_err(|e| OutputError::WriteFailed {
-                path: self.config.output.output_path.clone().unwrap(),
-                source: e,
-            })?;
+            writer.write("\n")?;
         }
         Ok(())
     }
 
-    /// Flushes the file writer buffer if present.
+    /// Writes a tree line during the scan, enforcing `--max-output-size`.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line to write, without the trailing newline.
     ///
     /// # Returns
     ///
@@ -340,14 +1002,72 @@ impl<'a> StreamOutputContext<'a> {
     ///
     /// # Errors
     ///
-    /// Returns an error if flushing fails.
-    fn flush(&mut self) -> Result<(), TreeppError> {
-        if let Some(writer) = self.file_writer.as_mut() {
-            writer.flush().map_err(|e| OutputError::WriteFailed {
-                path: self.config.output.output_path.clone().unwrap(),
-                source: e,
+    /// Returns `ScanError::OutputLimitReached` if the line does not fit in
+    /// the limit, or `ScanError::OutputClosed` if the pager was quit and no
+    /// file is being written, both of which stop the scan. Returns an error
+    /// if writing to stdout or the file fails.
+    fn emit_line(&mut self, line: &str) -> Result<(), ScanError> {
+        let size = line.len() as u64 + 1;
+        if let Some(limit) = self.config.output.max_output_size
+            && self.written + size > limit
+        {
+            return Err(ScanError::OutputLimitReached { limit });
+        }
+        self.written += size;
+
+        if let Some(stdout) = self.stdout.as_mut() {
+            stdout.write_line(line).map_err(|e| ScanError::WalkError {
+                message: e.to_string(),
+                path: None,
             })?;
         }
+        if self.stdout.as_ref().is_some_and(StreamWriter::is_closed) && self.file_writer.is_none() {
+            return Err(ScanError::OutputClosed);
+        }
+        if let Some(writer) = self.file_writer.as_mut() {
+            writer
+                .write(line)
+                .and_then(|()| writer.write("\n"))
+                .map_err(|e| ScanError::WalkError {
+                    message: e.to_string(),
+                    path: None,
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the output file and returns its length, for `--checkpoint`.
+    ///
+    /// # Returns
+    ///
+    /// The length of the output file, 0 if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if flushing the file fails.
+    fn flushed_len(&mut self) -> Result<u64, OutputError> {
+        self.file_writer
+            .as_mut()
+            .map_or(Ok(0), OutputFile::flushed_len)
+    }
+
+    /// Flushes stdout, waits for the pager and commits the output file.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if flushing fails or the output file cannot be
+    /// moved into place.
+    fn finish(&mut self) -> Result<(), TreeppError> {
+        if let Some(stdout) = self.stdout.as_mut() {
+            stdout.finish()?;
+        }
+        if let Some(writer) = self.file_writer.take() {
+            writer.commit()?;
+        }
         Ok(())
     }
 }
@@ -363,7 +1083,6 @@ impl<'a> StreamOutputContext<'a> {
 /// * `renderer` - The stream renderer for generating output lines.
 /// * `output_context` - The output context for writing results.
 /// * `has_subdirs` - Mutable flag tracking whether subdirectories were found.
-/// * `has_files` - Mutable flag tracking whether files were found.
 ///
 /// # Returns
 ///
@@ -377,27 +1096,27 @@ fn handle_stream_event(
     renderer: &mut StreamRenderer,
     output_context: &mut StreamOutputContext<'_>,
     has_subdirs: &mut bool,
-    has_files: &mut bool,
 ) -> Result<(), ScanError> {
     match event {
         StreamEvent::Entry(ref entry) => {
             if entry.kind == EntryKind::Directory {
                 *has_subdirs = true;
-            } else {
-                *has_files = true;
             }
 
             let line = renderer.render_entry(&entry.clone());
             for l in line.lines() {
-                if !output_context.config.output.silent {
-                    println!("{}", l);
-                }
-                if let Some(writer) = output_context.file_writer.as_mut() {
-                    writeln!(writer, "{}", l).map_err(|e| ScanError::WalkError {
-                        message: e.to_string(),
-                        path: None,
-                    })?;
-                }
+                output_context.emit_line(l)?;
+            }
+        }
+        StreamEvent::Omitted { depth, count } => {
+            // The root's omitted entries may include subdirectories, so a
+            // truncated root gets no "no subfolders" notice.
+            if depth == 0 {
+                *has_subdirs = true;
+            }
+            let line = renderer.render_omitted(depth, count);
+            for l in line.lines() {
+                output_context.emit_line(l)?;
             }
         }
         StreamEvent::EnterDir { is_last } => {
@@ -405,15 +1124,7 @@ fn handle_stream_event(
         }
         StreamEvent::LeaveDir => {
             if let Some(trailing) = renderer.pop_level() {
-                if !output_context.config.output.silent {
-                    println!("{}", trailing);
-                }
-                if let Some(writer) = output_context.file_writer.as_mut() {
-                    writeln!(writer, "{}", trailing).map_err(|e| ScanError::WalkError {
-                        message: e.to_string(),
-                        path: None,
-                    })?;
-                }
+                output_context.emit_line(&trailing)?;
             }
         }
     }
@@ -428,9 +1139,7 @@ fn handle_stream_event(
 /// # Arguments
 ///
 /// * `config` - The configuration specifying render options.
-/// * `chars` - The tree characters for formatting.
 /// * `has_subdirs` - Whether subdirectories were found.
-/// * `has_files` - Whether files were found.
 /// * `output_context` - The output context for writing results.
 ///
 /// # Returns
@@ -442,25 +1151,18 @@ fn handle_stream_event(
 /// Returns an error if writing output fails.
 fn render_empty_directory_notice(
     config: &Config,
-    chars: &TreeChars,
     has_subdirs: bool,
-    has_files: bool,
     output_context: &mut StreamOutputContext<'_>,
 ) -> Result<(), TreeppError> {
     if has_subdirs || config.render.no_win_banner {
         return Ok(());
     }
 
-    if let Some(drive) = drive_letter_from_path(&config.root_path) {
-        if let Ok(banner) = WinBanner::fetch_for_drive(drive) {
-            if has_files && config.scan.show_files && !config.render.no_indent {
-                output_context.writeln(&chars.space)?;
-            }
-
-            if !banner.no_subfolder.is_empty() {
-                output_context.writeln(&banner.no_subfolder)?;
-            }
-        }
+    if let Some(drive) = winpath::drive_letter(&config.root_path)
+        && let Ok(banner) = WinBanner::fetch_for_drive(drive)
+        && !banner.no_subfolder.is_empty()
+    {
+        output_context.writeln(&banner.no_subfolder)?;
     }
 
     output_context.writeln_empty()?;
@@ -476,54 +1178,16 @@ fn render_empty_directory_notice(
 ///
 /// * `config` - The configuration containing the optional output path.
 fn print_output_path_notice(config: &Config) {
-    if let Some(ref path) = config.output.output_path {
-        if !config.output.silent {
-            println!("\nOutput written to: {}", path.display());
-        }
+    if let Some(ref path) = config.output.output_path
+        && !config.output.silent
+    {
+        let notice = format!("\nOutput written to: {}\n", path.display());
+        let mut encoder =
+            TextEncoder::continuation(TextEncoder::for_stdout(config).encoding(), config);
+        let _ = io::stdout().write_all(&encoder.encode(&notice));
     }
 }
 
-/// Extracts the drive letter (uppercase) from a path.
-///
-/// Handles both standard paths (e.g., `C:\`) and long path format
-/// (e.g., `\\?\C:\`).
-///
-/// # Arguments
-///
-/// * `path` - The path to extract the drive letter from.
-///
-/// # Returns
-///
-/// Returns `Some(char)` containing the uppercase drive letter if found,
-/// `None` otherwise.
-///
-/// # Examples
-///
-/// ```rust,ignore
-/// use std::path::Path;
-///
-/// let path = Path::new("C:\\Users");
-/// assert_eq!(drive_letter_from_path(path), Some('C'));
-/// ```
-fn drive_letter_from_path(path: &Path) -> Option<char> {
-    let first_component = path.components().next()?;
-
-    if let Component::Prefix(prefix) = first_component {
-        let prefix_str = prefix.as_os_str().to_string_lossy();
-        let chars: Vec<char> = prefix_str.chars().collect();
-
-        if chars.len() >= 2 && chars[1] == ':' {
-            return Some(chars[0].to_ascii_uppercase());
-        }
-
-        if prefix_str.starts_with(r"\\?\") && chars.len() >= 6 && chars[5] == ':' {
-            return Some(chars[4].to_ascii_uppercase());
-        }
-    }
-
-    None
-}
-
 /// Maps an error to its corresponding exit code.
 ///
 /// # Arguments
@@ -552,25 +1216,71 @@ fn error_to_exit_code(err: &TreeppError) -> u8 {
 /// # Arguments
 ///
 /// * `err` - The error to print.
-fn print_error(err: &TreeppError) {
-    let prefix = match err {
-        TreeppError::Cli(_) => "CLI error",
-        TreeppError::Config(_) => "Config error",
-        TreeppError::Scan(_) => "Scan error",
-        TreeppError::Match(_) => "Match error",
-        TreeppError::Render(_) => "Render error",
-        TreeppError::Output(_) => "Output error",
-    };
+/// * `language` - Language of the message.
+fn print_error(err: &TreeppError, language: Language) {
+    eprintln!(
+        "tree++: {}: {}",
+        i18n::error_category(err, language),
+        i18n::error_message(err, language)
+    );
+    if let Some(hint) = error_hint(err, language) {
+        eprintln!("{}", hint);
+    }
+}
 
-    eprintln!("tree++: {}: {}", prefix, err);
+/// Prints an error to stderr as a single-line JSON object (`--json-errors`).
+///
+/// The object holds the stable `code` and `category` of the error, the
+/// `exit_code`, the `path` it concerns, the `message` and the `hint`; the
+/// last two follow `--lang`, and `path` and `hint` are `null` when absent.
+///
+/// # Arguments
+///
+/// * `err` - The error to print.
+/// * `language` - Language of the message and hint.
+/// * `exit_code` - The exit code the error leads to.
+fn print_json_error(err: &TreeppError, language: Language, exit_code: u8) {
+    let report = ErrorReport {
+        code: err.code(),
+        category: err.category(),
+        exit_code,
+        path: err.path().map(|path| path.to_string_lossy().into_owned()),
+        message: i18n::error_message(err, language),
+        hint: error_hint(err, language),
+    };
+    match serde_json::to_string(&report) {
+        Ok(json) => eprintln!("{}", json),
+        Err(_) => print_error(err, language),
+    }
+}
 
+/// Returns the hint shown after an error, for common error scenarios.
+///
+/// # Arguments
+///
+/// * `err` - The error.
+/// * `language` - Language of the hint.
+///
+/// # Returns
+///
+/// The hint line, or `None` if there is no hint for the error.
+fn error_hint(err: &TreeppError, language: Language) -> Option<String> {
     match err {
         TreeppError::Cli(CliError::UnknownOption { .. }) => {
-            eprintln!("Hint: run `treepp --help` to list available options");
+            Some(i18n::unknown_option_hint(language).to_string())
         }
         TreeppError::Cli(CliError::MultiplePaths { .. }) => {
-            eprintln!("Hint: only one target path can be specified.");
+            Some(i18n::multiple_paths_hint(language).to_string())
+        }
+        TreeppError::Output(OutputError::RegistryFailed { source, .. })
+            if source.kind() == io::ErrorKind::PermissionDenied =>
+        {
+            Some(i18n::elevation_hint(language).to_string())
         }
-        _ => {}
+        TreeppError::Cli(CliError::InvalidValue {
+            suggestion: Some(suggestion),
+            ..
+        }) => Some(i18n::did_you_mean_hint(language, suggestion)),
+        _ => None,
     }
 }
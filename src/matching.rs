@@ -0,0 +1,2478 @@
+//! Filter engine shared by scanning, `--explain` and `--paths-from`.
+//!
+//! Every entry that tree++ considers is run through one `MatchEngine`, which
+//! applies the filters in a fixed order and reports the first that rejects
+//! the entry as a `FilterReason`:
+//!
+//! 1. `.gitignore` rules (`/G`), from the deepest file upwards
+//! 2. Git tracking (`/GT`)
+//! 3. Project output directories (`/SM`)
+//! 4. The hidden attribute (unless `/AL`), cloud-only placeholders (`/SCO`)
+//!    and empty files (`/PEF`)
+//! 5. Exclude patterns (`/X`), the last matching one deciding
+//! 6. Include patterns (`/M`), for files only
+//! 7. Files when `/F` is off and no option needs them
+//!
+//! An exclude pattern therefore wins over an include pattern, and a
+//! `.gitignore` whitelist rule (`!pattern`) in a deeper file stops the
+//! search before the rules of its parents are consulted. Exclude patterns
+//! layer the same way: an exclude pattern written `!pattern` re-includes
+//! the entries it matches that earlier exclude patterns left out, and is
+//! overridden in turn by later ones. An entry matching an include override
+//! (`/IO`) skips steps 1, 3, 5 and 6 altogether. Patterns are matched
+//! against entry names, or against the path from the root when they contain
+//! a `/` (see `EntryPattern`), case-insensitively on Windows and, unless
+//! `--no-normalize` is set, after composing both to NFC. A trailing `/`
+//! restricts a pattern to directories.
+//!
+//! File: src/matching.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use glob::{MatchOptions, Pattern};
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use regex::{Regex, RegexBuilder};
+use tracing::debug;
+
+use crate::config::Config;
+use crate::error::{MatchError, TreeppResult};
+use crate::gitindex::GitIndex;
+use crate::normalize;
+use crate::winpath;
+
+// ============================================================================
+// Filter Reasons
+// ============================================================================
+
+/// A project type recognized by `--smart`.
+///
+/// A directory is a project root of a type when it holds the type's marker;
+/// the type's output directory inside that root is then left out.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::matching::ProjectKind;
+///
+/// assert_eq!(ProjectKind::Cargo.marker(), "Cargo.toml");
+/// assert_eq!(ProjectKind::Node.excluded_dir(), "node_modules");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectKind {
+    /// A Rust package or workspace (`Cargo.toml`, excludes `target`).
+    Cargo,
+    /// A Node.js package (`package.json`, excludes `node_modules`).
+    Node,
+    /// A CMake project (`CMakeLists.txt`, excludes `build`).
+    CMake,
+    /// A git repository (`.git`, excludes `.git` itself).
+    Git,
+}
+
+impl ProjectKind {
+    /// All project types, in detection order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::ProjectKind;
+    ///
+    /// assert_eq!(ProjectKind::ALL[0], ProjectKind::Cargo);
+    /// assert!(ProjectKind::ALL.contains(&ProjectKind::Git));
+    /// ```
+    pub const ALL: [Self; 4] = [Self::Cargo, Self::Node, Self::CMake, Self::Git];
+
+    /// Returns the name of the file or directory that marks a project root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::ProjectKind;
+    ///
+    /// assert_eq!(ProjectKind::CMake.marker(), "CMakeLists.txt");
+    /// ```
+    #[must_use]
+    pub const fn marker(self) -> &'static str {
+        match self {
+            Self::Cargo => "Cargo.toml",
+            Self::Node => "package.json",
+            Self::CMake => "CMakeLists.txt",
+            Self::Git => ".git",
+        }
+    }
+
+    /// Returns the name of the directory left out in a project root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::ProjectKind;
+    ///
+    /// assert_eq!(ProjectKind::Cargo.excluded_dir(), "target");
+    /// ```
+    #[must_use]
+    pub const fn excluded_dir(self) -> &'static str {
+        match self {
+            Self::Cargo => "target",
+            Self::Node => "node_modules",
+            Self::CMake => "build",
+            Self::Git => ".git",
+        }
+    }
+
+    /// Returns the project whose output directory `dir` is, if any.
+    ///
+    /// The name is compared case-insensitively, and the marker is looked up
+    /// next to `dir`, in its parent directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Path of a directory.
+    ///
+    /// # Returns
+    ///
+    /// The first project type whose excluded directory is named like `dir`
+    /// and whose marker exists in the parent, or `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use treepp::matching::ProjectKind;
+    ///
+    /// // `app/target` belongs to a Cargo project if `app/Cargo.toml` exists.
+    /// if let Some(project) = ProjectKind::owning(Path::new("app/target")) {
+    ///     println!("output of a {} project", project);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn owning(dir: &Path) -> Option<Self> {
+        let name = dir.file_name()?.to_str()?;
+        let parent = dir.parent()?;
+        Self::ALL.into_iter().find(|kind| {
+            name.eq_ignore_ascii_case(kind.excluded_dir())
+                && fs::symlink_metadata(winpath::to_extended(&parent.join(kind.marker()))).is_ok()
+        })
+    }
+}
+
+impl std::fmt::Display for ProjectKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Cargo => "Cargo",
+            Self::Node => "Node.js",
+            Self::CMake => "CMake",
+            Self::Git => "git",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The reason an entry is left out of the tree.
+///
+/// Produced by the scanner's filter chain and reported by `--explain`.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::matching::FilterReason;
+///
+/// let reason = FilterReason::Excluded { pattern: "target".to_string() };
+/// assert!(reason.to_string().contains("target"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterReason {
+    /// The entry has the Windows hidden attribute and `/AL` is not set.
+    Hidden,
+    /// The entry is a cloud-only placeholder and `/SCO` is set.
+    CloudOnly,
+    /// The entry is a zero-byte file and `/PEF` is set.
+    EmptyFile,
+    /// The entry name matches an exclude pattern (`/X`).
+    Excluded {
+        /// The matching pattern.
+        pattern: String,
+    },
+    /// The directory is the output of a detected project (`/SM`).
+    Smart {
+        /// The project type detected in the parent directory.
+        project: ProjectKind,
+    },
+    /// The file name matches none of the include patterns (`/M`).
+    NotIncluded,
+    /// Files are not displayed without `/F`.
+    FilesNotShown,
+    /// A `.gitignore` rule matches the entry (`/G`).
+    Gitignored {
+        /// The `.gitignore` file containing the rule, if known.
+        source: Option<PathBuf>,
+        /// The 1-based line of the rule within `source`, if resolved.
+        line: Option<usize>,
+        /// The rule as written in the file.
+        pattern: String,
+    },
+    /// The entry is not tracked by the git index (`/GT`).
+    Untracked,
+    /// The entry lies beyond the depth limit (`/L`).
+    BeyondDepth {
+        /// The configured depth limit.
+        max_depth: usize,
+    },
+    /// The directory contains no files after filtering (`/P`).
+    Pruned,
+}
+
+impl FilterReason {
+    /// Returns whether the reason comes from a filter rule.
+    ///
+    /// Rule matches (`.gitignore`, git tracking, hidden and cloud-only
+    /// attributes, empty files, include and exclude patterns, `--smart`) are what
+    /// `--show-filtered` annotates; display limits such as `/F`, `/L` and
+    /// `/P` are not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::FilterReason;
+    ///
+    /// assert!(FilterReason::Hidden.is_rule_match());
+    /// assert!(!FilterReason::Pruned.is_rule_match());
+    /// ```
+    #[must_use]
+    pub fn is_rule_match(&self) -> bool {
+        matches!(
+            self,
+            Self::Hidden
+                | Self::CloudOnly
+                | Self::EmptyFile
+                | Self::Excluded { .. }
+                | Self::Smart { .. }
+                | Self::NotIncluded
+                | Self::Gitignored { .. }
+                | Self::Untracked
+        )
+    }
+}
+
+impl std::fmt::Display for FilterReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hidden => write!(f, "has the hidden attribute (use /AL to show)"),
+            Self::CloudOnly => write!(f, "is a cloud-only placeholder (/SCO)"),
+            Self::EmptyFile => write!(f, "is an empty file (/PEF)"),
+            Self::Excluded { pattern } => write!(f, "matches exclude pattern '{}' (/X)", pattern),
+            Self::Smart { project } => write!(
+                f,
+                "is skipped in the {} project marked by {} (/SM)",
+                project,
+                project.marker()
+            ),
+            Self::NotIncluded => write!(f, "matches none of the include patterns (/M)"),
+            Self::FilesNotShown => write!(f, "is a file and files are not shown (use /F)"),
+            Self::Gitignored {
+                source,
+                line,
+                pattern,
+            } => {
+                write!(f, "ignored by .gitignore rule '{}'", pattern)?;
+                match (source, line) {
+                    (Some(source), Some(line)) => write!(f, " ({}:{})", source.display(), line),
+                    (Some(source), None) => write!(f, " ({})", source.display()),
+                    _ => Ok(()),
+                }
+            }
+            Self::Untracked => write!(f, "is not tracked by the git index (/GT)"),
+            Self::BeyondDepth { max_depth } => {
+                write!(f, "is deeper than the depth limit of {} (/L)", max_depth)
+            }
+            Self::Pruned => write!(f, "is a directory without files after filtering (/P)"),
+        }
+    }
+}
+
+/// The filters whose rejections are counted by `--count`.
+///
+/// Display limits that never drop an entry while scanning (`/F` is implied
+/// and `/L` stops before reading) have no kind.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::matching::{FilterKind, FilterReason};
+///
+/// assert_eq!(FilterReason::Hidden.kind(), Some(FilterKind::Hidden));
+/// assert!(FilterKind::Hidden < FilterKind::Pruned);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FilterKind {
+    /// The Windows hidden attribute.
+    Hidden,
+    /// Cloud-only placeholders (`/SCO`).
+    CloudOnly,
+    /// Empty files (`/PEF`).
+    EmptyFile,
+    /// An exclude pattern (`/X`).
+    Excluded,
+    /// Project output directories (`/SM`).
+    Smart,
+    /// The include patterns (`/M`).
+    NotIncluded,
+    /// A `.gitignore` rule (`/G`).
+    Gitignored,
+    /// The git index (`/GT`).
+    Untracked,
+    /// Empty directory pruning (`/P`).
+    Pruned,
+}
+
+impl FilterReason {
+    /// Returns the filter that produced the reason.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::{FilterKind, FilterReason};
+    ///
+    /// assert_eq!(FilterReason::Untracked.kind(), Some(FilterKind::Untracked));
+    /// assert_eq!(FilterReason::FilesNotShown.kind(), None);
+    /// ```
+    #[must_use]
+    pub fn kind(&self) -> Option<FilterKind> {
+        match self {
+            Self::Hidden => Some(FilterKind::Hidden),
+            Self::CloudOnly => Some(FilterKind::CloudOnly),
+            Self::EmptyFile => Some(FilterKind::EmptyFile),
+            Self::Excluded { .. } => Some(FilterKind::Excluded),
+            Self::Smart { .. } => Some(FilterKind::Smart),
+            Self::NotIncluded => Some(FilterKind::NotIncluded),
+            Self::Gitignored { .. } => Some(FilterKind::Gitignored),
+            Self::Untracked => Some(FilterKind::Untracked),
+            Self::Pruned => Some(FilterKind::Pruned),
+            Self::FilesNotShown | Self::BeyondDepth { .. } => None,
+        }
+    }
+}
+
+/// Number of entries left out by each filter (`--count`).
+///
+/// # Examples
+///
+/// ```
+/// use treepp::matching::{FilterKind, FilterReason, RejectionCounts};
+///
+/// let mut rejected = RejectionCounts::default();
+/// rejected.record(&FilterReason::Hidden);
+/// rejected.record(&FilterReason::Hidden);
+/// rejected.record(&FilterReason::Pruned);
+/// assert_eq!(rejected.get(FilterKind::Hidden), 2);
+/// assert_eq!(rejected.total(), 3);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RejectionCounts {
+    counts: BTreeMap<FilterKind, usize>,
+    subtrees: usize,
+}
+
+impl RejectionCounts {
+    /// Counts an entry left out for `reason`; reasons without a kind are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::{FilterReason, RejectionCounts};
+    ///
+    /// let mut rejected = RejectionCounts::default();
+    /// rejected.record(&FilterReason::EmptyFile);
+    /// rejected.record(&FilterReason::FilesNotShown);
+    /// assert_eq!(rejected.total(), 1);
+    /// ```
+    pub fn record(&mut self, reason: &FilterReason) {
+        if let Some(kind) = reason.kind() {
+            *self.counts.entry(kind).or_default() += 1;
+        }
+    }
+
+    /// Counts a filtered directory whose content was never read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::RejectionCounts;
+    ///
+    /// let mut rejected = RejectionCounts::default();
+    /// rejected.record_subtree();
+    /// assert_eq!(rejected.subtrees(), 1);
+    /// assert_eq!(rejected.total(), 0);
+    /// ```
+    pub fn record_subtree(&mut self) {
+        self.subtrees += 1;
+    }
+
+    /// Adds the entries counted by `other`.
+    ///
+    /// Scan threads count the rejections of a directory locally and merge
+    /// them once, instead of locking shared counts for every entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::{FilterKind, FilterReason, RejectionCounts};
+    ///
+    /// let mut total = RejectionCounts::default();
+    /// total.record(&FilterReason::Hidden);
+    /// let mut local = RejectionCounts::default();
+    /// local.record(&FilterReason::Hidden);
+    /// local.record_subtree();
+    /// total.merge(&local);
+    /// assert_eq!(total.get(FilterKind::Hidden), 2);
+    /// assert_eq!(total.subtrees(), 1);
+    /// ```
+    pub fn merge(&mut self, other: &Self) {
+        for (kind, count) in other.iter() {
+            *self.counts.entry(kind).or_default() += count;
+        }
+        self.subtrees += other.subtrees;
+    }
+
+    /// Returns the number of entries left out by `kind`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::{FilterKind, FilterReason, RejectionCounts};
+    ///
+    /// let mut rejected = RejectionCounts::default();
+    /// rejected.record(&FilterReason::NotIncluded);
+    /// assert_eq!(rejected.get(FilterKind::NotIncluded), 1);
+    /// assert_eq!(rejected.get(FilterKind::Hidden), 0);
+    /// ```
+    #[must_use]
+    pub fn get(&self, kind: FilterKind) -> usize {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of filtered directories that were not descended
+    /// into.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::RejectionCounts;
+    ///
+    /// assert_eq!(RejectionCounts::default().subtrees(), 0);
+    /// ```
+    #[must_use]
+    pub const fn subtrees(&self) -> usize {
+        self.subtrees
+    }
+
+    /// Returns the number of entries left out by any filter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::{FilterReason, RejectionCounts};
+    ///
+    /// let mut rejected = RejectionCounts::default();
+    /// rejected.record(&FilterReason::Hidden);
+    /// rejected.record(&FilterReason::Untracked);
+    /// assert_eq!(rejected.total(), 2);
+    /// ```
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Iterates over the filters that left out at least one entry, in
+    /// `FilterKind` order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::matching::{FilterKind, FilterReason, RejectionCounts};
+    ///
+    /// let mut rejected = RejectionCounts::default();
+    /// rejected.record(&FilterReason::Pruned);
+    /// rejected.record(&FilterReason::Hidden);
+    /// let counts: Vec<_> = rejected.iter().collect();
+    /// assert_eq!(counts, [(FilterKind::Hidden, 1), (FilterKind::Pruned, 1)]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (FilterKind, usize)> + '_ {
+        self.counts.iter().map(|(&kind, &count)| (kind, count))
+    }
+}
+
+// ============================================================================
+// Patterns
+// ============================================================================
+
+/// Compiles a glob pattern string into a `Pattern`.
+///
+/// # Arguments
+///
+/// * `pattern` - The glob pattern string to compile.
+///
+/// # Returns
+///
+/// A compiled `Pattern` on success, or a `MatchError` if the pattern is invalid.
+///
+/// # Errors
+///
+/// Returns `MatchError::InvalidPattern` if the pattern syntax is invalid.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::matching::compile_pattern;
+///
+/// let pattern = compile_pattern("*.rs").unwrap();
+/// assert!(pattern.matches("main.rs"));
+/// assert!(!pattern.matches("main.txt"));
+/// ```
+pub fn compile_pattern(pattern: &str) -> Result<Pattern, MatchError> {
+    Pattern::new(pattern).map_err(|e| MatchError::InvalidPattern {
+        pattern: pattern.to_string(),
+        reason: e.msg.to_string(),
+    })
+}
+
+/// A rule read from a pattern file (`--patterns-from`).
+///
+/// # Examples
+///
+/// ```
+/// use treepp::matching::{parse_pattern_file, PatternRule};
+///
+/// let rules = parse_pattern_file("+*.md\n");
+/// assert_eq!(rules, [PatternRule::Include("*.md".to_string())]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternRule {
+    /// An include pattern, as given to `--include`.
+    Include(String),
+    /// An exclude pattern, as given to `--exclude`, negated by a leading `!`.
+    Exclude(String),
+}
+
+/// Parses the contents of a pattern file (`--patterns-from`).
+///
+/// Each line holds one pattern, surrounding whitespace removed. Blank lines
+/// and lines starting with `#` are skipped. A line starting with `+` is an
+/// include pattern; any other line is an exclude pattern, so `!pattern`
+/// re-includes entries as it does with `--exclude`. `\#` and `\+` stand for
+/// a pattern starting with a literal `#` or `+`.
+///
+/// # Arguments
+///
+/// * `text` - The contents of the file.
+///
+/// # Returns
+///
+/// The rules in the order of their lines.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::matching::{parse_pattern_file, PatternRule};
+///
+/// let rules = parse_pattern_file("# build output\ntarget/\n!target/doc/\n+*.rs\n");
+/// assert_eq!(
+///     rules,
+///     [
+///         PatternRule::Exclude("target/".to_string()),
+///         PatternRule::Exclude("!target/doc/".to_string()),
+///         PatternRule::Include("*.rs".to_string()),
+///     ]
+/// );
+/// ```
+#[must_use]
+pub fn parse_pattern_file(text: &str) -> Vec<PatternRule> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match line.strip_prefix('+') {
+            Some(include) => {
+                let include = include.trim_start();
+                (!include.is_empty()).then(|| PatternRule::Include(include.to_string()))
+            }
+            None => {
+                let literal = line
+                    .strip_prefix('\\')
+                    .filter(|rest| rest.starts_with(['#', '+']));
+                Some(PatternRule::Exclude(literal.unwrap_or(line).to_string()))
+            }
+        })
+        .collect()
+}
+
+/// A compiled include, exclude or include override pattern.
+///
+/// A pattern is matched against the entry name, unless it contains a `/`
+/// (or `\`) before its end: then it is matched against the path from the
+/// root, where `*` and `?` stay within one component and a `**` component
+/// spans any number of them, so `/build` only matches `build` in the root.
+/// A trailing `/` makes the pattern match only directories, and a trailing
+/// `/**` matches the directory it starts at as well as everything below it.
+struct EntryPattern {
+    /// The pattern as given, reported by `--explain`.
+    source: String,
+    /// The pattern without its trailing `/`.
+    glob: Pattern,
+    /// The directory a trailing `/**` starts at.
+    subtree_root: Option<Pattern>,
+    /// Whether only directories match (trailing `/`).
+    dir_only: bool,
+    /// Whether the pattern is matched against the path from the root.
+    anchored: bool,
+}
+
+impl EntryPattern {
+    /// Compiles a pattern as given on the command line.
+    fn compile(
+        source: &str,
+        compile: impl Fn(&str) -> Result<Pattern, MatchError>,
+    ) -> Result<Self, MatchError> {
+        let unified = source.replace('\\', "/");
+        let dir_only = unified.len() > 1 && unified.ends_with('/');
+        let trimmed = if dir_only {
+            &unified[..unified.len() - 1]
+        } else {
+            &unified
+        };
+        let glob = trimmed.strip_prefix('/').unwrap_or(trimmed);
+        let anchored = trimmed.contains('/');
+        let subtree_root = match glob.strip_suffix("/**") {
+            Some(root) if !root.is_empty() => Some(compile(root)?),
+            _ => None,
+        };
+        Ok(Self {
+            source: source.to_string(),
+            glob: compile(glob)?,
+            subtree_root,
+            dir_only,
+            anchored,
+        })
+    }
+
+    /// Checks whether the pattern matches an entry.
+    ///
+    /// `path` is the entry's path from the root with `/` separators.
+    fn matches(&self, path: &str, is_dir: bool, options: MatchOptions) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if !self.anchored {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            return self.glob.matches_with(name, options);
+        }
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..options
+        };
+        let starts_subtree = || {
+            is_dir
+                && self
+                    .subtree_root
+                    .as_ref()
+                    .is_some_and(|root| root.matches_with(path, options))
+        };
+        self.glob.matches_with(path, options) || starts_subtree()
+    }
+}
+
+/// An exclude pattern, or with `!` a negation that re-includes its matches.
+struct ExcludeRule {
+    pattern: EntryPattern,
+    negated: bool,
+}
+
+impl ExcludeRule {
+    /// Parses an exclude pattern as given on the command line.
+    ///
+    /// A leading `!` negates the pattern; `\!` stands for a literal `!`.
+    fn parse(
+        pattern: &str,
+        compile: impl Fn(&str) -> Result<Pattern, MatchError>,
+    ) -> Result<Self, MatchError> {
+        let (glob, negated) = match pattern.strip_prefix('!') {
+            Some(rest) => (rest, true),
+            None => {
+                let literal = pattern
+                    .strip_prefix('\\')
+                    .filter(|rest| rest.starts_with('!'));
+                (literal.unwrap_or(pattern), false)
+            }
+        };
+        Ok(Self {
+            pattern: EntryPattern::compile(glob, compile)?,
+            negated,
+        })
+    }
+}
+
+/// Compiled include, exclude and include override pattern sets.
+///
+/// Entries are identified by their path from the root with `/` separators,
+/// whose last component is the entry name. Unless `--no-normalize` is set,
+/// patterns and paths are both composed to NFC, so a decomposed name
+/// matches a pattern typed in the composed form.
+struct CompiledRules {
+    include_patterns: Vec<EntryPattern>,
+    exclude_rules: Vec<ExcludeRule>,
+    override_patterns: Vec<EntryPattern>,
+    match_options: MatchOptions,
+    normalize: bool,
+}
+
+impl CompiledRules {
+    /// Compiles matching rules from configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration containing pattern strings.
+    ///
+    /// # Returns
+    ///
+    /// Compiled rules on success, or a `MatchError` if any pattern is invalid.
+    fn compile(config: &Config) -> Result<Self, MatchError> {
+        let normalize = !config.matching.no_normalize;
+        let compile = |pattern: &str| {
+            if normalize {
+                compile_pattern(&normalize::nfc(pattern))
+            } else {
+                compile_pattern(pattern)
+            }
+        };
+
+        let include_patterns = config
+            .matching
+            .include_patterns
+            .iter()
+            .map(|pattern| EntryPattern::compile(pattern, compile))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let exclude_rules = config
+            .matching
+            .exclude_patterns
+            .iter()
+            .map(|pattern| ExcludeRule::parse(pattern, compile))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let override_patterns = config
+            .matching
+            .include_overrides
+            .iter()
+            .map(|pattern| EntryPattern::compile(pattern, compile))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // On Windows, file matching should be case-insensitive to match
+        // the behavior of the native filesystem and tree command.
+        let match_options = MatchOptions {
+            case_sensitive: !cfg!(windows),
+            require_literal_separator: false,
+            require_literal_leading_dot: false,
+        };
+
+        Ok(Self {
+            include_patterns,
+            exclude_rules,
+            override_patterns,
+            match_options,
+            normalize,
+        })
+    }
+
+    /// Returns the form of a path that patterns are matched against.
+    fn key<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        if self.normalize {
+            normalize::nfc(path)
+        } else {
+            Cow::Borrowed(path)
+        }
+    }
+
+    /// Checks if an entry should be included based on include patterns.
+    ///
+    /// Directories are always included. Files are included if no include
+    /// patterns are specified, or if they match at least one pattern or an
+    /// include override.
+    fn should_include(&self, path: &str, is_dir: bool) -> bool {
+        if is_dir {
+            return true;
+        }
+        if self.include_patterns.is_empty() {
+            return true;
+        }
+        let path = self.key(path);
+        self.include_patterns
+            .iter()
+            .chain(&self.override_patterns)
+            .any(|p| p.matches(&path, is_dir, self.match_options))
+    }
+
+    /// Checks if an entry matches an include override (`--include-override`).
+    fn is_overridden(&self, path: &str, is_dir: bool) -> bool {
+        if self.override_patterns.is_empty() {
+            return false;
+        }
+        let path = self.key(path);
+        self.override_patterns
+            .iter()
+            .any(|p| p.matches(&path, is_dir, self.match_options))
+    }
+
+    /// Checks if an entry should be excluded based on exclude patterns.
+    fn should_exclude(&self, path: &str, is_dir: bool) -> bool {
+        self.matching_exclude(path, is_dir).is_some()
+    }
+
+    /// Returns the exclude pattern that leaves an entry out.
+    ///
+    /// The last exclude pattern matching the entry decides, as in a
+    /// `.gitignore` file: the entry is left out if it is a plain pattern,
+    /// and kept if it is a `!` negation. Include overrides keep the entry
+    /// regardless.
+    fn matching_exclude(&self, path: &str, is_dir: bool) -> Option<&str> {
+        if self.exclude_rules.is_empty() || self.is_overridden(path, is_dir) {
+            return None;
+        }
+        let path = self.key(path);
+        self.exclude_rules
+            .iter()
+            .rev()
+            .find(|rule| rule.pattern.matches(&path, is_dir, self.match_options))
+            .filter(|rule| !rule.negated)
+            .map(|rule| rule.pattern.source.as_str())
+    }
+}
+
+/// Locates the parts of names matched by include patterns (`--highlight`).
+///
+/// Each include pattern is translated into an anchored regular expression
+/// whose literal text and character classes are captured, so a name that
+/// matches the pattern yields the byte ranges that the pattern spelled out.
+/// Wildcards (`*`, `?`) match arbitrary text and are not reported.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Config;
+/// use treepp::matching::MatchHighlighter;
+///
+/// let mut config = Config::default();
+/// config.matching.include_patterns = vec!["*test*.rs".to_string()];
+/// let highlighter = MatchHighlighter::from_config(&config).unwrap();
+/// assert_eq!(highlighter.spans("my_test_case.rs"), vec![3..7, 12..15]);
+/// assert!(highlighter.spans("main.rs").is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct MatchHighlighter {
+    patterns: Vec<Regex>,
+}
+
+impl MatchHighlighter {
+    /// Compiles the include patterns of a configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration containing the include patterns.
+    ///
+    /// # Returns
+    ///
+    /// A highlighter with one expression per include pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MatchError::InvalidPattern` if a pattern is not a valid glob.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::Config;
+    /// use treepp::matching::MatchHighlighter;
+    ///
+    /// let mut config = Config::default();
+    /// config.matching.include_patterns = vec!["[".to_string()];
+    /// assert!(MatchHighlighter::from_config(&config).is_err());
+    /// ```
+    pub fn from_config(config: &Config) -> Result<Self, MatchError> {
+        let patterns = config
+            .matching
+            .include_patterns
+            .iter()
+            .map(|pattern| {
+                compile_pattern(pattern)?;
+                RegexBuilder::new(&glob_to_regex(pattern))
+                    .case_insensitive(cfg!(windows))
+                    .dot_matches_new_line(true)
+                    .build()
+                    .map_err(|e| MatchError::InvalidPattern {
+                        pattern: pattern.clone(),
+                        reason: e.to_string(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Returns the byte ranges of `name` matched by literal pattern text.
+    ///
+    /// Ranges from every matching pattern are returned in pattern order and
+    /// may overlap; a name matched by no pattern yields no ranges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::Config;
+    /// use treepp::matching::MatchHighlighter;
+    ///
+    /// let mut config = Config::default();
+    /// config.matching.include_patterns = vec!["*.rs".to_string()];
+    /// let highlighter = MatchHighlighter::from_config(&config).unwrap();
+    /// assert_eq!(highlighter.spans("lib.rs"), vec![3..6]);
+    /// ```
+    #[must_use]
+    pub fn spans(&self, name: &str) -> Vec<Range<usize>> {
+        self.patterns
+            .iter()
+            .filter_map(|pattern| pattern.captures(name))
+            .flat_map(|captures| {
+                captures
+                    .iter()
+                    .skip(1)
+                    .flatten()
+                    .map(|m| m.range())
+                    .filter(|range| !range.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+/// Translates a glob pattern into an anchored regular expression.
+///
+/// Runs of literal characters and character classes become capture groups;
+/// `*` and `?` become uncaptured wildcards. The pattern is expected to have
+/// been validated by `compile_pattern`.
+fn glob_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    let mut in_group = false;
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if matches!(c, '*' | '?') {
+            if in_group {
+                regex.push(')');
+                in_group = false;
+            }
+            regex.push_str(if c == '*' { ".*" } else { "." });
+            continue;
+        }
+
+        if !in_group {
+            regex.push('(');
+            in_group = true;
+        }
+        if c != '[' {
+            regex.push_str(&regex::escape(&c.to_string()));
+            continue;
+        }
+
+        regex.push('[');
+        if chars.peek() == Some(&'!') {
+            chars.next();
+            regex.push('^');
+        }
+        let mut first = true;
+        while let Some(c) = chars.next() {
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            write_class_char(&mut regex, c);
+            if chars.peek() == Some(&'-') {
+                let mut lookahead = chars.clone();
+                lookahead.next();
+                if let Some(end) = lookahead.next().filter(|&end| end != ']') {
+                    chars.next();
+                    chars.next();
+                    regex.push('-');
+                    write_class_char(&mut regex, end);
+                }
+            }
+        }
+        regex.push(']');
+    }
+
+    if in_group {
+        regex.push(')');
+    }
+    regex.push('$');
+    regex
+}
+
+/// Writes a character class member as a code point escape.
+fn write_class_char(regex: &mut String, c: char) {
+    let _ = write!(regex, "\\x{{{:X}}}", u32::from(c));
+}
+
+// ============================================================================
+// Gitignore Rules
+// ============================================================================
+
+/// A chain of gitignore rules supporting inheritance.
+///
+/// Allows child directories to inherit and extend parent rules.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::sync::Arc;
+/// use ignore::gitignore::GitignoreBuilder;
+/// use treepp::matching::GitignoreChain;
+///
+/// let mut builder = GitignoreBuilder::new("project");
+/// builder.add_line(None, "*.log").unwrap();
+/// let chain = GitignoreChain::new().with_child(Arc::new(builder.build().unwrap()));
+/// assert!(chain.is_ignored(Path::new("project/app.log"), false));
+/// ```
+#[derive(Clone, Default)]
+pub struct GitignoreChain {
+    rules: Vec<Arc<Gitignore>>,
+}
+
+impl GitignoreChain {
+    /// Creates an empty rule chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use treepp::matching::GitignoreChain;
+    ///
+    /// let chain = GitignoreChain::new();
+    /// assert!(!chain.is_ignored(Path::new("anything"), false));
+    /// ```
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Creates a new chain with an additional rule appended.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use std::sync::Arc;
+    /// use ignore::gitignore::GitignoreBuilder;
+    /// use treepp::matching::GitignoreChain;
+    ///
+    /// let mut parent = GitignoreBuilder::new("project");
+    /// parent.add_line(None, "*.log").unwrap();
+    /// let mut child = GitignoreBuilder::new("project/logs");
+    /// child.add_line(None, "!keep.log").unwrap();
+    /// let chain = GitignoreChain::new()
+    ///     .with_child(Arc::new(parent.build().unwrap()))
+    ///     .with_child(Arc::new(child.build().unwrap()));
+    /// assert!(chain.is_ignored(Path::new("project/app.log"), false));
+    /// assert!(!chain.is_ignored(Path::new("project/logs/keep.log"), false));
+    /// ```
+    pub fn with_child(&self, gitignore: Arc<Gitignore>) -> Self {
+        let mut new_chain = self.clone();
+        new_chain.rules.push(gitignore);
+        new_chain
+    }
+
+    /// Checks if a path is ignored by any rule in the chain.
+    ///
+    /// Checks from most specific (deepest) to least specific, respecting
+    /// whitelist rules.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use std::sync::Arc;
+    /// use ignore::gitignore::GitignoreBuilder;
+    /// use treepp::matching::GitignoreChain;
+    ///
+    /// let mut builder = GitignoreBuilder::new("project");
+    /// builder.add_line(None, "build/").unwrap();
+    /// let chain = GitignoreChain::new().with_child(Arc::new(builder.build().unwrap()));
+    /// assert!(chain.is_ignored(Path::new("project/build"), true));
+    /// assert!(!chain.is_ignored(Path::new("project/build"), false));
+    /// ```
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.ignore_reason(path, is_dir).is_some()
+    }
+
+    /// Returns the rule that ignores a path, if any.
+    ///
+    /// The source line is left unresolved; see `locate_gitignore_line`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use std::sync::Arc;
+    /// use ignore::gitignore::GitignoreBuilder;
+    /// use treepp::matching::{FilterReason, GitignoreChain};
+    ///
+    /// let mut builder = GitignoreBuilder::new("project");
+    /// builder.add_line(None, "*.tmp").unwrap();
+    /// let chain = GitignoreChain::new().with_child(Arc::new(builder.build().unwrap()));
+    /// assert_eq!(
+    ///     chain.ignore_reason(Path::new("project/a.tmp"), false),
+    ///     Some(FilterReason::Gitignored {
+    ///         source: None,
+    ///         line: None,
+    ///         pattern: "*.tmp".to_string(),
+    ///     })
+    /// );
+    /// ```
+    pub fn ignore_reason(&self, path: &Path, is_dir: bool) -> Option<FilterReason> {
+        for gi in self.rules.iter().rev() {
+            match gi.matched(path, is_dir) {
+                Match::Ignore(glob) => {
+                    return Some(FilterReason::Gitignored {
+                        source: glob.from().map(|p| winpath::to_display(p).into_owned()),
+                        line: None,
+                        pattern: glob.original().to_string(),
+                    });
+                }
+                Match::Whitelist(_) => return None,
+                Match::None => {}
+            }
+        }
+        None
+    }
+}
+
+/// Maximum number of directories whose `.gitignore` state is cached.
+const GITIGNORE_CACHE_CAPACITY: usize = 4096;
+
+/// The version of a `.gitignore` file: its modification time and size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    size: u64,
+}
+
+impl FileStamp {
+    /// Reads the stamp of a file, or `None` if it does not exist.
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(winpath::to_extended(path)).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            size: metadata.len(),
+        })
+    }
+}
+
+/// A cached lookup of one directory's `.gitignore`.
+struct CachedGitignore {
+    /// Stamp of the file when it was loaded; `None` if there was no file.
+    stamp: Option<FileStamp>,
+    rules: Option<Arc<Gitignore>>,
+    last_used: u64,
+}
+
+/// Entries of a `GitignoreCache` with their recency.
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<PathBuf, CachedGitignore>,
+    clock: u64,
+}
+
+/// Thread-safe cache for loaded gitignore files.
+///
+/// Entries are keyed by directory and remember the modification time and
+/// size of the file they were loaded from, so an edited, created or deleted
+/// `.gitignore` is loaded again on the next lookup. At most `capacity`
+/// directories are kept; the least recently used one is dropped first.
+struct GitignoreCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+impl GitignoreCache {
+    /// Creates a new empty cache.
+    fn new() -> Self {
+        Self::with_capacity(GITIGNORE_CACHE_CAPACITY)
+    }
+
+    /// Creates a new empty cache holding at most `capacity` directories.
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Gets or loads the gitignore rules for a directory.
+    ///
+    /// Returns the cached result if the file is unchanged, otherwise loads
+    /// from disk and caches. The file is read and parsed without holding the
+    /// lock, so scan threads only wait for each other on the map itself.
+    fn get_or_load(&self, dir: &Path) -> Option<Arc<Gitignore>> {
+        let stamp = FileStamp::of(&dir.join(".gitignore"));
+        {
+            let mut state = self.state.lock().unwrap();
+            state.clock += 1;
+            let now = state.clock;
+            if let Some(cached) = state.entries.get_mut(dir)
+                && cached.stamp == stamp
+            {
+                cached.last_used = now;
+                return cached.rules.clone();
+            }
+        }
+
+        let rules = stamp
+            .and_then(|_| load_gitignore_from_path(dir))
+            .map(Arc::new);
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let now = state.clock;
+        if state.entries.len() >= self.capacity && !state.entries.contains_key(dir) {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+        state.entries.insert(
+            dir.to_path_buf(),
+            CachedGitignore {
+                stamp,
+                rules: rules.clone(),
+                last_used: now,
+            },
+        );
+        rules
+    }
+}
+
+/// Loads gitignore rules from a directory's `.gitignore` file.
+///
+/// # Arguments
+///
+/// * `dir` - Directory to load `.gitignore` from.
+///
+/// # Returns
+///
+/// `Some(Gitignore)` if the file exists and parses successfully, `None` otherwise.
+fn load_gitignore_from_path(dir: &Path) -> Option<Gitignore> {
+    let gitignore_path = dir.join(".gitignore");
+    let fs_path = winpath::to_extended(&gitignore_path);
+    if !fs_path.exists() {
+        return None;
+    }
+
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(error) = builder.add(&fs_path) {
+        debug!("skipped {}: {}", gitignore_path.display(), error);
+        return None;
+    }
+
+    let gitignore = builder.build().ok()?;
+    debug!(
+        "loaded {} ({} rule(s))",
+        gitignore_path.display(),
+        gitignore.len()
+    );
+    Some(gitignore)
+}
+
+/// Finds the 1-based line number of a rule within a `.gitignore` file.
+///
+/// # Arguments
+///
+/// * `source` - The `.gitignore` file.
+/// * `pattern` - The rule as written (trailing whitespace removed).
+///
+/// # Returns
+///
+/// The line number of the first matching line, or `None` if the file
+/// cannot be read or no line matches.
+fn locate_gitignore_line(source: &Path, pattern: &str) -> Option<usize> {
+    let content = fs::read_to_string(winpath::to_extended(source)).ok()?;
+    content
+        .lines()
+        .position(|line| line == pattern || line.trim_end() == pattern)
+        .map(|index| index + 1)
+}
+
+// ============================================================================
+// Match Engine
+// ============================================================================
+
+/// The filter chain applied to every entry (see the module documentation
+/// for the order of the checks).
+///
+/// Built once per run from the configuration. `.gitignore` files are loaded
+/// on first use and cached until they change on disk, so one engine can be
+/// shared by all scan threads and kept across scans.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::{Path, PathBuf};
+/// use treepp::config::Config;
+/// use treepp::matching::{FilterReason, GitignoreChain, MatchEngine};
+///
+/// let mut config = Config::with_root(PathBuf::from("."));
+/// config.scan.show_files = true;
+/// config.matching.exclude_patterns = vec!["*.log".to_string()];
+/// let engine = MatchEngine::from_config(&config).unwrap();
+///
+/// let chain = engine.chain_for(Path::new("."), &GitignoreChain::new());
+/// let reason = engine.filter_reason(Path::new("./app.log"), false, false, false, false, &chain);
+/// assert_eq!(reason, Some(FilterReason::Excluded { pattern: "*.log".to_string() }));
+/// ```
+pub struct MatchEngine {
+    rules: CompiledRules,
+    root: PathBuf,
+    respect_gitignore: bool,
+    gitignore_cache: GitignoreCache,
+    show_hidden: bool,
+    skip_cloud_only: bool,
+    prune_empty_files: bool,
+    smart_excludes: bool,
+    git_index: Option<GitIndex>,
+    keep_files: bool,
+}
+
+impl MatchEngine {
+    /// Builds the filter chain from configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration holding the patterns and filter switches.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MatchError::InvalidPattern` if a pattern is not a valid glob.
+    /// Returns `ScanError` if `--git-tracked` is set and the git index cannot
+    /// be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::config::Config;
+    /// use treepp::matching::MatchEngine;
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("project"));
+    /// config.matching.exclude_patterns = vec!["*.log".to_string()];
+    /// let engine = MatchEngine::from_config(&config).unwrap();
+    /// assert!(engine.is_excluded("app.log", false));
+    /// ```
+    pub fn from_config(config: &Config) -> TreeppResult<Self> {
+        let git_index = if config.scan.git_tracked {
+            Some(GitIndex::discover(&config.root_path)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            rules: CompiledRules::compile(config)?,
+            root: config.root_path.clone(),
+            respect_gitignore: config.scan.respect_gitignore,
+            gitignore_cache: GitignoreCache::new(),
+            show_hidden: config.scan.show_hidden,
+            skip_cloud_only: config.scan.skip_cloud_only,
+            prune_empty_files: config.matching.prune_empty_files,
+            smart_excludes: config.matching.smart_excludes,
+            git_index,
+            // Disk usage and `/P` look at files even when they are not shown.
+            keep_files: config.scan.show_files
+                || config.render.show_disk_usage
+                || config.matching.prune_empty,
+        })
+    }
+
+    /// Checks whether an entry is left out by the exclude patterns.
+    ///
+    /// `path` is the entry's path from the root with `/` separators; for an
+    /// entry in the root it is the name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::config::Config;
+    /// use treepp::matching::MatchEngine;
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("project"));
+    /// config.matching.exclude_patterns = vec!["target".to_string()];
+    /// let engine = MatchEngine::from_config(&config).unwrap();
+    /// assert!(engine.is_excluded("target", true));
+    /// assert!(!engine.is_excluded("src", true));
+    /// ```
+    #[must_use]
+    pub fn is_excluded(&self, path: &str, is_dir: bool) -> bool {
+        self.rules.should_exclude(path, is_dir)
+    }
+
+    /// Checks whether an entry passes the include patterns.
+    ///
+    /// Directories always pass; files pass if no include pattern is given
+    /// or one of them matches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::config::Config;
+    /// use treepp::matching::MatchEngine;
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("project"));
+    /// config.matching.include_patterns = vec!["*.rs".to_string()];
+    /// let engine = MatchEngine::from_config(&config).unwrap();
+    /// assert!(engine.is_included("main.rs", false));
+    /// assert!(!engine.is_included("README.md", false));
+    /// assert!(engine.is_included("docs", true));
+    /// ```
+    #[must_use]
+    pub fn is_included(&self, path: &str, is_dir: bool) -> bool {
+        self.rules.should_include(path, is_dir)
+    }
+
+    /// Checks if an entry should be filtered out by its path and attributes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::config::Config;
+    /// use treepp::matching::MatchEngine;
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("project"));
+    /// config.scan.show_files = true;
+    /// let engine = MatchEngine::from_config(&config).unwrap();
+    /// assert!(engine.should_filter(".env", false, true, false, false));
+    /// assert!(!engine.should_filter("main.rs", false, false, false, false));
+    /// ```
+    #[must_use]
+    pub fn should_filter(
+        &self,
+        path: &str,
+        is_dir: bool,
+        hidden: bool,
+        cloud_only: bool,
+        empty: bool,
+    ) -> bool {
+        self.entry_filter_reason(path, is_dir, hidden, cloud_only, empty)
+            .is_some()
+    }
+
+    /// Applies the pattern- and attribute-based filters to an entry.
+    ///
+    /// These are the checks from step 4 on; they need no `.gitignore`
+    /// rules. `path` is the entry's path from the root with `/` separators;
+    /// for an entry in the root it is the name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::config::Config;
+    /// use treepp::matching::{FilterReason, MatchEngine};
+    ///
+    /// let config = Config::with_root(PathBuf::from("project"));
+    /// let engine = MatchEngine::from_config(&config).unwrap();
+    /// assert_eq!(
+    ///     engine.entry_filter_reason("main.rs", false, false, false, false),
+    ///     Some(FilterReason::FilesNotShown)
+    /// );
+    /// assert_eq!(engine.entry_filter_reason("src", true, false, false, false), None);
+    /// ```
+    #[must_use]
+    pub fn entry_filter_reason(
+        &self,
+        path: &str,
+        is_dir: bool,
+        hidden: bool,
+        cloud_only: bool,
+        empty: bool,
+    ) -> Option<FilterReason> {
+        // Check hidden attribute first (unless show_hidden is enabled)
+        if !self.show_hidden && hidden {
+            return Some(FilterReason::Hidden);
+        }
+
+        if self.skip_cloud_only && cloud_only {
+            return Some(FilterReason::CloudOnly);
+        }
+
+        if self.prune_empty_files && empty && !is_dir {
+            return Some(FilterReason::EmptyFile);
+        }
+
+        if let Some(pattern) = self.rules.matching_exclude(path, is_dir) {
+            return Some(FilterReason::Excluded {
+                pattern: pattern.to_string(),
+            });
+        }
+
+        if !is_dir && !self.rules.should_include(path, is_dir) {
+            return Some(FilterReason::NotIncluded);
+        }
+
+        if !is_dir && !self.keep_files {
+            return Some(FilterReason::FilesNotShown);
+        }
+
+        None
+    }
+
+    /// Runs the full filter chain for an entry of a directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the entry.
+    /// * `is_dir` - Whether the entry is a directory.
+    /// * `hidden` - Whether the entry has the hidden attribute.
+    /// * `cloud_only` - Whether the entry is a cloud-only placeholder.
+    /// * `empty` - Whether the entry is a zero-byte file (see `is_empty_file`).
+    /// * `chain` - Gitignore rules in effect for the containing directory.
+    ///
+    /// # Returns
+    ///
+    /// The reason the entry is filtered out, or `None` if it is kept. The
+    /// line of a `.gitignore` rule is left unresolved; see
+    /// `resolve_source_line`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    /// use treepp::config::Config;
+    /// use treepp::matching::{FilterReason, GitignoreChain, MatchEngine};
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("project"));
+    /// config.scan.show_files = true;
+    /// config.matching.include_patterns = vec!["*.rs".to_string()];
+    /// let engine = MatchEngine::from_config(&config).unwrap();
+    /// let chain = GitignoreChain::new();
+    /// let path = Path::new("project/notes.txt");
+    /// let reason = engine.filter_reason(path, false, false, false, false, &chain);
+    /// assert_eq!(reason, Some(FilterReason::NotIncluded));
+    /// ```
+    #[must_use]
+    pub fn filter_reason(
+        &self,
+        path: &Path,
+        is_dir: bool,
+        hidden: bool,
+        cloud_only: bool,
+        empty: bool,
+        chain: &GitignoreChain,
+    ) -> Option<FilterReason> {
+        let relative = self.relative_path(path);
+        let overridden = self.rules.is_overridden(&relative, is_dir);
+
+        if self.respect_gitignore
+            && !overridden
+            && let Some(reason) = chain.ignore_reason(path, is_dir)
+        {
+            return Some(reason);
+        }
+
+        if self.is_untracked(path, is_dir) {
+            return Some(FilterReason::Untracked);
+        }
+
+        if self.smart_excludes
+            && is_dir
+            && !overridden
+            && let Some(project) = ProjectKind::owning(path)
+        {
+            debug!("{}: output of a {} project", path.display(), project);
+            return Some(FilterReason::Smart { project });
+        }
+
+        self.entry_filter_reason(&relative, is_dir, hidden, cloud_only, empty)
+    }
+
+    /// Returns the path patterns are matched against: the path from the
+    /// root with `/` separators, or the name for a path outside the root.
+    fn relative_path(&self, path: &Path) -> String {
+        match path.strip_prefix(&self.root) {
+            Ok(relative) if relative.components().next().is_some() => relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            _ => path
+                .file_name()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns the gitignore chain in effect inside `dir`.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory whose entries are about to be filtered.
+    /// * `parent` - The chain in effect in the parent of `dir`.
+    ///
+    /// # Returns
+    ///
+    /// `parent` extended by the `.gitignore` of `dir`, if it has one and
+    /// `/G` is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    /// use treepp::config::Config;
+    /// use treepp::matching::{GitignoreChain, MatchEngine};
+    ///
+    /// // Without `/G` no `.gitignore` is read and the parent chain is kept.
+    /// let config = Config::with_root(PathBuf::from("project"));
+    /// let engine = MatchEngine::from_config(&config).unwrap();
+    /// let chain = engine.chain_for(Path::new("project/src"), &GitignoreChain::new());
+    /// assert!(!chain.is_ignored(Path::new("project/src/main.rs"), false));
+    /// ```
+    #[must_use]
+    pub fn chain_for(&self, dir: &Path, parent: &GitignoreChain) -> GitignoreChain {
+        match self.gitignore(dir) {
+            Some(gi) => parent.with_child(gi),
+            None => parent.clone(),
+        }
+    }
+
+    /// Checks if an entry is excluded by `--git-tracked`.
+    fn is_untracked(&self, path: &Path, is_dir: bool) -> bool {
+        self.git_index
+            .as_ref()
+            .is_some_and(|index| !index.is_tracked(path, is_dir))
+    }
+
+    /// Gets or loads gitignore rules for a directory.
+    fn gitignore(&self, dir: &Path) -> Option<Arc<Gitignore>> {
+        if !self.respect_gitignore {
+            return None;
+        }
+        self.gitignore_cache.get_or_load(dir)
+    }
+}
+
+/// Fills in the `.gitignore` line number for a gitignore reason.
+///
+/// Finding the line reads the file again, so it is only done where the
+/// line is shown (`--explain` and `--show-filtered`).
+///
+/// # Arguments
+///
+/// * `reason` - A reason returned by `MatchEngine::filter_reason`.
+///
+/// # Returns
+///
+/// The reason with `line` set if the rule was found; any other reason
+/// unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::matching::{resolve_source_line, FilterReason};
+///
+/// assert_eq!(resolve_source_line(FilterReason::Hidden), FilterReason::Hidden);
+/// ```
+#[must_use]
+pub fn resolve_source_line(reason: FilterReason) -> FilterReason {
+    match reason {
+        FilterReason::Gitignored {
+            source: Some(source),
+            line: None,
+            pattern,
+        } => FilterReason::Gitignored {
+            line: locate_gitignore_line(&source, &pattern),
+            source: Some(source),
+            pattern,
+        },
+        other => other,
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn gitignore_chain_new_empty() {
+        let chain = GitignoreChain::new();
+        assert!(!chain.is_ignored(Path::new("test.txt"), false));
+        assert!(!chain.is_ignored(Path::new("anything"), true));
+    }
+
+    #[test]
+    fn gitignore_chain_with_child_creates_independent_chain() {
+        let chain1 = GitignoreChain::new();
+        let chain2 = chain1.clone();
+        assert_eq!(chain1.rules.len(), chain2.rules.len());
+    }
+
+    #[test]
+    fn gitignore_chain_is_ignored_basic() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        File::create(root.join(".gitignore"))
+            .unwrap()
+            .write_all(b"*.log\n")
+            .unwrap();
+
+        let gi = load_gitignore_from_path(root).unwrap();
+        let chain = GitignoreChain::new().with_child(Arc::new(gi));
+
+        assert!(chain.is_ignored(&root.join("test.log"), false));
+        assert!(!chain.is_ignored(&root.join("test.txt"), false));
+    }
+
+    #[test]
+    fn gitignore_cache_returns_none_for_missing() {
+        let dir = TempDir::new().unwrap();
+        let cache = GitignoreCache::new();
+
+        let result = cache.get_or_load(dir.path());
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn gitignore_cache_loads_existing() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        File::create(root.join(".gitignore"))
+            .unwrap()
+            .write_all(b"*.log\n")
+            .unwrap();
+
+        let cache = GitignoreCache::new();
+        let result = cache.get_or_load(root);
+
+        assert!(result.is_some());
+    }
+
+    #[test]
+    fn gitignore_cache_caches_result() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        File::create(root.join(".gitignore"))
+            .unwrap()
+            .write_all(b"*.log\n")
+            .unwrap();
+
+        let cache = GitignoreCache::new();
+
+        let result1 = cache.get_or_load(root);
+        let result2 = cache.get_or_load(root);
+
+        assert!(result1.is_some());
+        assert!(result2.is_some());
+        assert!(Arc::ptr_eq(&result1.unwrap(), &result2.unwrap()));
+    }
+
+    #[test]
+    fn gitignore_cache_caches_none() {
+        let dir = TempDir::new().unwrap();
+        let cache = GitignoreCache::new();
+
+        let _result1 = cache.get_or_load(dir.path());
+        let _result2 = cache.get_or_load(dir.path());
+
+        let state = cache.state.lock().unwrap();
+        assert!(state.entries.contains_key(dir.path()));
+    }
+
+    #[test]
+    fn gitignore_cache_reloads_changed_file() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let cache = GitignoreCache::new();
+        assert!(cache.get_or_load(root).is_none());
+
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        let first = cache.get_or_load(root).expect("新建的 .gitignore 应被加载");
+        assert_eq!(first.num_ignores(), 1);
+
+        fs::write(root.join(".gitignore"), "*.log\n*.tmp\n").unwrap();
+        let second = cache.get_or_load(root).expect("修改后应重新加载");
+        assert_eq!(second.num_ignores(), 2);
+
+        fs::remove_file(root.join(".gitignore")).unwrap();
+        assert!(cache.get_or_load(root).is_none(), "删除后不应返回旧规则");
+    }
+
+    #[test]
+    fn gitignore_cache_evicts_least_recently_used() {
+        let dir = TempDir::new().unwrap();
+        let dirs: Vec<PathBuf> = ["a", "b", "c"].iter().map(|n| dir.path().join(n)).collect();
+        for path in &dirs {
+            fs::create_dir(path).unwrap();
+        }
+        let cache = GitignoreCache::with_capacity(2);
+
+        cache.get_or_load(&dirs[0]);
+        cache.get_or_load(&dirs[1]);
+        cache.get_or_load(&dirs[0]);
+        cache.get_or_load(&dirs[2]);
+
+        let state = cache.state.lock().unwrap();
+        assert_eq!(state.entries.len(), 2);
+        assert!(state.entries.contains_key(&dirs[0]), "最近使用的条目应保留");
+        assert!(
+            !state.entries.contains_key(&dirs[1]),
+            "最久未使用的条目应被移除"
+        );
+    }
+
+    #[test]
+    fn gitignore_cache_loads_concurrently() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        let cache = GitignoreCache::new();
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let rules = cache.get_or_load(dir.path()).expect("应加载 .gitignore");
+                    assert!(rules.matched(dir.path().join("app.log"), false).is_ignore());
+                });
+            }
+        });
+        assert_eq!(cache.state.lock().unwrap().entries.len(), 1);
+    }
+
+    #[test]
+    fn rejection_counts_merge_adds_per_kind() {
+        let mut total = RejectionCounts::default();
+        total.record(&FilterReason::Hidden);
+        let mut local = RejectionCounts::default();
+        local.record(&FilterReason::Hidden);
+        local.record(&FilterReason::NotIncluded);
+        local.record_subtree();
+
+        total.merge(&local);
+
+        assert_eq!(total.get(FilterKind::Hidden), 2);
+        assert_eq!(total.get(FilterKind::NotIncluded), 1);
+        assert_eq!(total.total(), 3);
+        assert_eq!(total.subtrees(), 1);
+    }
+
+    #[test]
+    fn load_gitignore_from_path_returns_none_when_missing() {
+        let dir = TempDir::new().unwrap();
+        assert!(load_gitignore_from_path(dir.path()).is_none());
+    }
+
+    #[test]
+    fn load_gitignore_from_path_loads_valid_file() {
+        let dir = TempDir::new().unwrap();
+        File::create(dir.path().join(".gitignore"))
+            .unwrap()
+            .write_all(b"*.txt\n")
+            .unwrap();
+        assert!(load_gitignore_from_path(dir.path()).is_some());
+    }
+
+    #[test]
+    fn locate_gitignore_line_finds_rule() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".gitignore");
+        fs::write(&path, "# comment\n\ntarget/   \n*.tmp\n").unwrap();
+        assert_eq!(locate_gitignore_line(&path, "target/"), Some(3));
+        assert_eq!(locate_gitignore_line(&path, "*.tmp"), Some(4));
+        assert_eq!(locate_gitignore_line(&path, "missing"), None);
+    }
+
+    #[test]
+    fn compile_pattern_basic() {
+        let pattern = compile_pattern("*.rs").expect("编译失败");
+        assert!(pattern.matches("main.rs"));
+        assert!(pattern.matches("lib.rs"));
+        assert!(!pattern.matches("main.txt"));
+    }
+
+    #[test]
+    fn compile_pattern_invalid() {
+        let result = compile_pattern("[invalid");
+        assert!(result.is_err());
+
+        if let Err(MatchError::InvalidPattern { pattern, .. }) = result {
+            assert_eq!(pattern, "[invalid");
+        } else {
+            panic!("Expected InvalidPattern error");
+        }
+    }
+
+    #[test]
+    fn compile_pattern_complex_glob() {
+        let pattern = compile_pattern("test_*.rs").unwrap();
+        assert!(pattern.matches("test_foo.rs"));
+        assert!(!pattern.matches("foo_test.rs"));
+    }
+
+    #[test]
+    fn glob_to_regex_captures_literal_runs() {
+        assert_eq!(glob_to_regex("*.rs"), "^.*(\\.rs)$");
+        assert_eq!(glob_to_regex("a?b"), "^(a).(b)$");
+        assert_eq!(glob_to_regex("[!a-c]x"), "^([^\\x{61}-\\x{63}]x)$");
+        assert_eq!(glob_to_regex("[]-]"), "^([\\x{5D}\\x{2D}])$");
+    }
+
+    #[test]
+    fn match_highlighter_reports_literal_spans() {
+        let mut config = Config::default();
+        config.matching.include_patterns = vec!["*test*".to_string(), "*.rs".to_string()];
+        let highlighter = MatchHighlighter::from_config(&config).unwrap();
+
+        assert_eq!(highlighter.spans("unit_test.rs"), vec![5..9, 9..12]);
+        assert_eq!(highlighter.spans("test_[1].rs"), vec![0..4, 8..11]);
+        assert!(
+            highlighter.spans("main.txt").is_empty(),
+            "不匹配的名称不应有高亮区间"
+        );
+    }
+
+    #[test]
+    fn match_highlighter_rejects_invalid_pattern() {
+        let mut config = Config::default();
+        config.matching.include_patterns = vec!["[invalid".to_string()];
+        assert!(matches!(
+            MatchHighlighter::from_config(&config),
+            Err(MatchError::InvalidPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn compiled_rules_should_include_with_pattern() {
+        let mut config = Config::default();
+        config.matching.include_patterns = vec!["*.rs".to_string()];
+
+        let rules = CompiledRules::compile(&config).unwrap();
+
+        assert!(rules.should_include("main.rs", false));
+        assert!(!rules.should_include("main.txt", false));
+    }
+
+    #[test]
+    fn compiled_rules_should_include_no_patterns() {
+        let config = Config::default();
+        let rules = CompiledRules::compile(&config).unwrap();
+
+        assert!(rules.should_include("any.rs", false));
+        assert!(rules.should_include("any.txt", false));
+    }
+
+    #[test]
+    fn compiled_rules_should_include_directory_always() {
+        let mut config = Config::default();
+        config.matching.include_patterns = vec!["*.rs".to_string()];
+
+        let rules = CompiledRules::compile(&config).unwrap();
+
+        assert!(rules.should_include("src", true));
+        assert!(rules.should_include("tests", true));
+    }
+
+    #[test]
+    fn compiled_rules_should_exclude_no_patterns() {
+        let config = Config::default();
+        let rules = CompiledRules::compile(&config).unwrap();
+
+        assert!(!rules.should_exclude("any.rs", false));
+        assert!(!rules.should_exclude("any.txt", false));
+    }
+
+    #[test]
+    fn compiled_rules_should_exclude_with_pattern() {
+        let mut config = Config::default();
+        config.matching.exclude_patterns = vec!["*.log".to_string()];
+
+        let rules = CompiledRules::compile(&config).unwrap();
+
+        assert!(rules.should_exclude("app.log", false));
+        assert!(!rules.should_exclude("app.txt", false));
+    }
+
+    #[test]
+    fn compiled_rules_last_matching_exclude_decides() {
+        let mut config = Config::default();
+        config.matching.exclude_patterns = ["*.log", "!keep*.log", "keep-old.log", "\\!bang"]
+            .map(String::from)
+            .to_vec();
+
+        let rules = CompiledRules::compile(&config).unwrap();
+        assert!(rules.should_exclude("app.log", false));
+        assert!(
+            !rules.should_exclude("keep.log", false),
+            "否定模式应重新包含"
+        );
+        assert!(
+            rules.should_exclude("keep-old.log", false),
+            "后面的模式应再次排除"
+        );
+        assert!(rules.should_exclude("!bang", false), "\\! 应匹配字面的 !");
+        assert!(!rules.should_exclude("bang", false));
+        assert_eq!(
+            rules.matching_exclude("keep-old.log", false),
+            Some("keep-old.log")
+        );
+    }
+
+    #[test]
+    fn entry_pattern_classes_ignore_case_like_windows() {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        let matches = |pattern: &str, path: &str| {
+            EntryPattern::compile(pattern, compile_pattern)
+                .unwrap()
+                .matches(path, false, options)
+        };
+        assert!(matches("[A-Z]*.TXT", "readme.txt"), "字符类应不区分大小写");
+        assert!(matches("[a-c]at.rs", "Bat.rs"));
+        assert!(!matches("[!a-z]*", "Readme"), "取反字符类也应不区分大小写");
+        assert!(matches("SRC/[m]ain.RS", "src/Main.rs"));
+    }
+
+    #[test]
+    fn compiled_rules_dir_only_patterns_skip_files() {
+        let mut config = Config::default();
+        config.matching.exclude_patterns = vec!["build/".to_string(), "out\\".to_string()];
+
+        let rules = CompiledRules::compile(&config).unwrap();
+        assert!(rules.should_exclude("build", true));
+        assert!(
+            rules.should_exclude("src/build", true),
+            "无斜杠前缀时应匹配任意层级"
+        );
+        assert!(
+            !rules.should_exclude("build", false),
+            "目录模式不应匹配文件"
+        );
+        assert!(rules.should_exclude("out", true));
+        assert_eq!(
+            rules.matching_exclude("build", true),
+            Some("build/"),
+            "应报告原始模式"
+        );
+    }
+
+    #[test]
+    fn compiled_rules_path_patterns_match_from_root() {
+        let mut config = Config::default();
+        config.matching.exclude_patterns = ["**/docs/**", "src/*.tmp", "/target"]
+            .map(String::from)
+            .to_vec();
+
+        let rules = CompiledRules::compile(&config).unwrap();
+        assert!(rules.should_exclude("docs", true), "子树模式应包含起始目录");
+        assert!(rules.should_exclude("web/docs", true));
+        assert!(rules.should_exclude("web/docs/api/index.md", false));
+        assert!(!rules.should_exclude("docs", false), "起始目录只能是目录");
+        assert!(!rules.should_exclude("web/docs.md", false));
+        assert!(!rules.should_exclude("docsite", true));
+
+        assert!(rules.should_exclude("src/a.tmp", false));
+        assert!(
+            !rules.should_exclude("src/nested/a.tmp", false),
+            "* 不应跨越路径分隔符"
+        );
+        assert!(!rules.should_exclude("a.tmp", false));
+
+        assert!(rules.should_exclude("target", true));
+        assert!(
+            !rules.should_exclude("crates/target", true),
+            "前导 / 应锚定到根目录"
+        );
+    }
+
+    #[test]
+    fn parse_pattern_file_skips_comments_and_unescapes() {
+        let text = "\u{feff}# 注释\r\n\n  *.log  \r\n+ *.rs\n+\n\\#notes\n\\+plus\n!keep.log\n";
+        let rules = parse_pattern_file(text);
+        assert_eq!(
+            rules,
+            [
+                PatternRule::Exclude("*.log".to_string()),
+                PatternRule::Include("*.rs".to_string()),
+                PatternRule::Exclude("#notes".to_string()),
+                PatternRule::Exclude("+plus".to_string()),
+                PatternRule::Exclude("!keep.log".to_string()),
+            ],
+            "应跳过 BOM、注释、空行和空的包含模式"
+        );
+    }
+
+    #[test]
+    fn compiled_rules_include_override_beats_patterns() {
+        let mut config = Config::default();
+        config.matching.include_patterns = vec!["*.rs".to_string()];
+        config.matching.exclude_patterns = vec!["docs".to_string(), "*.md".to_string()];
+        config.matching.include_overrides = vec!["README.md".to_string()];
+
+        let rules = CompiledRules::compile(&config).unwrap();
+        assert!(!rules.should_exclude("README.md", false));
+        assert!(rules.should_include("README.md", false));
+        assert!(rules.should_exclude("CHANGELOG.md", false));
+        assert!(!rules.should_include("CHANGELOG.md", false));
+    }
+
+    #[test]
+    fn compiled_rules_match_names_in_any_normal_form() {
+        let mut config = Config::default();
+        config.matching.include_patterns = vec!["caf\u{e9}*".to_string()];
+        config.matching.exclude_patterns = vec!["*re\u{301}sume\u{301}*".to_string()];
+
+        let rules = CompiledRules::compile(&config).unwrap();
+        assert!(
+            rules.should_include("cafe\u{301}.txt", false),
+            "NFD 名称应匹配 NFC 模式"
+        );
+        assert!(
+            rules.should_exclude("my_r\u{e9}sum\u{e9}.pdf", false),
+            "NFC 名称应匹配 NFD 模式"
+        );
+
+        config.matching.no_normalize = true;
+        let rules = CompiledRules::compile(&config).unwrap();
+        assert!(!rules.should_include("cafe\u{301}.txt", false));
+        assert!(rules.should_include("caf\u{e9}.txt", false));
+    }
+
+    #[test]
+    fn compiled_rules_multiple_patterns() {
+        let mut config = Config::default();
+        config.matching.include_patterns = vec!["*.rs".to_string(), "*.toml".to_string()];
+        config.matching.exclude_patterns = vec!["test_*".to_string()];
+
+        let rules = CompiledRules::compile(&config).unwrap();
+
+        assert!(rules.should_include("main.rs", false));
+        assert!(rules.should_include("Cargo.toml", false));
+        assert!(!rules.should_include("README.md", false));
+        assert!(rules.should_exclude("test_foo.rs", false));
+    }
+
+    #[test]
+    fn should_filter_includes_files_when_collect_for_size() {
+        let mut config = Config {
+            batch_mode: true,
+            ..Default::default()
+        };
+        config.render.show_disk_usage = true;
+        config.scan.show_files = false;
+
+        let engine = MatchEngine::from_config(&config).unwrap();
+
+        assert!(!engine.should_filter("test.txt", false, false, false, false));
+    }
+
+    #[test]
+    fn should_filter_excludes_files_when_no_show_no_collect() {
+        let config = Config::default();
+
+        let engine = MatchEngine::from_config(&config).unwrap();
+
+        assert!(engine.should_filter("test.txt", false, false, false, false));
+    }
+
+    #[test]
+    fn should_filter_cloud_only_entries_with_skip_cloud_only() {
+        let mut config = Config::with_root(PathBuf::from("."));
+        config.scan.show_files = true;
+        let engine = MatchEngine::from_config(&config).unwrap();
+        assert!(!engine.should_filter("report.docx", false, false, true, false));
+
+        config.scan.skip_cloud_only = true;
+        let engine = MatchEngine::from_config(&config).unwrap();
+        assert!(engine.should_filter("report.docx", false, false, true, false));
+        assert!(!engine.should_filter("notes.txt", false, false, false, false));
+        assert_eq!(
+            engine.entry_filter_reason("report.docx", false, false, true, false),
+            Some(FilterReason::CloudOnly)
+        );
+    }
+
+    #[test]
+    fn should_filter_empty_files_with_prune_empty_files() {
+        let mut config = Config::with_root(PathBuf::from("."));
+        config.scan.show_files = true;
+        let engine = MatchEngine::from_config(&config).unwrap();
+        assert!(!engine.should_filter("blank.txt", false, false, false, true));
+
+        config.matching.prune_empty_files = true;
+        let engine = MatchEngine::from_config(&config).unwrap();
+        assert_eq!(
+            engine.entry_filter_reason("blank.txt", false, false, false, true),
+            Some(FilterReason::EmptyFile)
+        );
+        assert!(!engine.should_filter("notes.txt", false, false, false, false));
+        assert!(
+            !engine.should_filter("empty_dir", true, false, false, true),
+            "目录不受影响"
+        );
+        assert!(FilterReason::EmptyFile.is_rule_match());
+        assert_eq!(FilterReason::EmptyFile.kind(), Some(FilterKind::EmptyFile));
+    }
+
+    #[test]
+    fn engine_should_filter_respects_exclude_over_include() {
+        let mut config = Config::default();
+        config.scan.show_files = true;
+        config.matching.include_patterns = vec!["*.rs".to_string()];
+        config.matching.exclude_patterns = vec!["test_*.rs".to_string()];
+
+        let engine = MatchEngine::from_config(&config).unwrap();
+
+        assert!(!engine.should_filter("main.rs", false, false, false, false));
+        assert!(engine.should_filter("test_main.rs", false, false, false, false));
+    }
+
+    #[test]
+    fn engine_show_hidden_default_false() {
+        let config = Config::default();
+        let engine = MatchEngine::from_config(&config).unwrap();
+        assert!(!engine.show_hidden);
+    }
+
+    #[test]
+    fn engine_show_hidden_enabled() {
+        let mut config = Config::default();
+        config.scan.show_hidden = true;
+        let engine = MatchEngine::from_config(&config).unwrap();
+        assert!(engine.show_hidden);
+    }
+
+    #[test]
+    fn engine_from_config_with_all_options() {
+        let mut config = Config::default();
+        config.scan.respect_gitignore = true;
+        config.scan.skip_cloud_only = true;
+        config.matching.smart_excludes = true;
+        config.matching.prune_empty = true;
+
+        let engine = MatchEngine::from_config(&config).unwrap();
+
+        assert!(engine.respect_gitignore);
+        assert!(engine.skip_cloud_only);
+        assert!(engine.smart_excludes);
+        assert!(engine.keep_files, "/P 需要保留文件以判断目录是否为空");
+        assert!(engine.git_index.is_none());
+    }
+
+    #[test]
+    fn engine_chain_for_extends_parent_only_with_gitignore() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("sub")).unwrap();
+        File::create(root.join("sub").join(".gitignore"))
+            .unwrap()
+            .write_all(b"*.log\n!keep.log\n")
+            .unwrap();
+
+        let mut config = Config::with_root(root.to_path_buf());
+        config.scan.show_files = true;
+        config.scan.respect_gitignore = true;
+        let engine = MatchEngine::from_config(&config).unwrap();
+
+        let top = engine.chain_for(root, &GitignoreChain::new());
+        assert!(top.rules.is_empty(), "无 .gitignore 的目录不应扩展规则链");
+        let sub = engine.chain_for(&root.join("sub"), &top);
+        assert_eq!(sub.rules.len(), 1);
+
+        let sub_dir = root.join("sub");
+        let reason =
+            engine.filter_reason(&sub_dir.join("app.log"), false, false, false, false, &sub);
+        assert!(matches!(
+            reason,
+            Some(FilterReason::Gitignored { line: None, .. })
+        ));
+        assert!(matches!(
+            resolve_source_line(reason.unwrap()),
+            FilterReason::Gitignored { line: Some(1), .. }
+        ));
+        let kept =
+            engine.filter_reason(&sub_dir.join("keep.log"), false, false, false, false, &sub);
+        assert_eq!(kept, None, "白名单规则应保留条目");
+    }
+
+    #[test]
+    fn engine_checks_gitignore_before_patterns() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        File::create(root.join(".gitignore"))
+            .unwrap()
+            .write_all(b"build/\n")
+            .unwrap();
+
+        let mut config = Config::with_root(root.to_path_buf());
+        config.scan.respect_gitignore = true;
+        config.matching.exclude_patterns = vec!["bu*".to_string(), "build".to_string()];
+        let engine = MatchEngine::from_config(&config).unwrap();
+        let chain = engine.chain_for(root, &GitignoreChain::new());
+
+        let reason = engine.filter_reason(&root.join("build"), true, true, false, false, &chain);
+        assert_eq!(reason.and_then(|r| r.kind()), Some(FilterKind::Gitignored));
+        let reason = engine.filter_reason(&root.join("bundle"), true, false, false, false, &chain);
+        assert_eq!(
+            reason,
+            Some(FilterReason::Excluded {
+                pattern: "bu*".to_string()
+            }),
+            "应报告第一个匹配的排除模式"
+        );
+    }
+
+    #[test]
+    fn engine_matches_path_patterns_relative_to_root() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let mut config = Config::with_root(root.to_path_buf());
+        config.scan.show_files = true;
+        config.matching.include_patterns = vec!["**/docs/**".to_string()];
+        config.matching.exclude_patterns = vec!["docs/drafts/".to_string()];
+        let engine = MatchEngine::from_config(&config).unwrap();
+        let chain = GitignoreChain::new();
+        let reason = |path: PathBuf, is_dir: bool| {
+            engine.filter_reason(&path, is_dir, false, false, false, &chain)
+        };
+
+        let docs = root.join("docs");
+        assert_eq!(reason(docs.join("guide.md"), false), None);
+        assert_eq!(
+            reason(root.join("README.md"), false),
+            Some(FilterReason::NotIncluded)
+        );
+        assert_eq!(
+            reason(docs.join("drafts"), true),
+            Some(FilterReason::Excluded {
+                pattern: "docs/drafts/".to_string()
+            })
+        );
+        assert_eq!(
+            reason(root.join("web").join("drafts"), true),
+            None,
+            "应从根目录起算"
+        );
+    }
+
+    #[test]
+    fn engine_include_override_skips_gitignore_and_smart() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        File::create(root.join(".gitignore"))
+            .unwrap()
+            .write_all(b"*.env\n")
+            .unwrap();
+        File::create(root.join("package.json")).unwrap();
+
+        let mut config = Config::with_root(root.to_path_buf());
+        config.scan.respect_gitignore = true;
+        config.matching.smart_excludes = true;
+        config.scan.show_files = true;
+        config.matching.include_overrides = vec!["sample.env".into(), "node_modules".into()];
+        let engine = MatchEngine::from_config(&config).unwrap();
+        let chain = engine.chain_for(root, &GitignoreChain::new());
+
+        let reason = |name: &str, is_dir: bool, hidden: bool| {
+            engine.filter_reason(&root.join(name), is_dir, hidden, false, false, &chain)
+        };
+        assert_eq!(
+            reason("sample.env", false, false),
+            None,
+            "覆盖模式应优先于 .gitignore"
+        );
+        assert_eq!(
+            reason("node_modules", true, false),
+            None,
+            "覆盖模式应优先于 /SM"
+        );
+        let ignored = reason("local.env", false, false).and_then(|r| r.kind());
+        assert_eq!(ignored, Some(FilterKind::Gitignored));
+        let hidden = reason("sample.env", false, true);
+        assert_eq!(hidden, Some(FilterReason::Hidden), "覆盖模式不影响隐藏属性");
+    }
+
+    #[test]
+    fn project_kind_owning_requires_marker_in_parent() {
+        let dir = TempDir::new().unwrap();
+        File::create(dir.path().join("Cargo.toml")).unwrap();
+        fs::create_dir(dir.path().join("tests")).unwrap();
+        fs::create_dir(dir.path().join("target")).unwrap();
+        fs::create_dir_all(dir.path().join("src").join("build")).unwrap();
+
+        assert_eq!(
+            ProjectKind::owning(&dir.path().join("target")),
+            Some(ProjectKind::Cargo)
+        );
+        assert_eq!(
+            ProjectKind::owning(&dir.path().join("TARGET")),
+            Some(ProjectKind::Cargo)
+        );
+        let build = dir.path().join("src").join("build");
+        assert_eq!(
+            ProjectKind::owning(&build),
+            None,
+            "无 CMakeLists.txt 时不应识别"
+        );
+        assert_eq!(ProjectKind::owning(&dir.path().join("tests")), None);
+    }
+
+    #[test]
+    fn filter_reason_reports_smart_project() {
+        let reason = FilterReason::Smart {
+            project: ProjectKind::Node,
+        };
+        assert!(reason.is_rule_match());
+        assert_eq!(reason.kind(), Some(FilterKind::Smart));
+        assert!(reason.to_string().contains("package.json"), "{reason}");
+    }
+
+    #[test]
+    fn filter_reason_display_mentions_option() {
+        assert!(FilterReason::Hidden.to_string().contains("/AL"));
+        assert!(FilterReason::CloudOnly.to_string().contains("/SCO"));
+        assert!(FilterReason::Untracked.to_string().contains("/GT"));
+        assert!(FilterReason::Pruned.to_string().contains("/P"));
+        let reason = FilterReason::Gitignored {
+            source: Some(PathBuf::from(".gitignore")),
+            line: Some(7),
+            pattern: "build/".to_string(),
+        };
+        assert!(reason.to_string().contains(".gitignore:7"));
+    }
+}
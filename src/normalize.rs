@@ -0,0 +1,99 @@
+//! Unicode normalization of names for matching and sorting.
+//!
+//! The same name can be stored in two forms: precomposed (NFC, `é` as
+//! U+00E9), as written by Windows, or decomposed (NFD, `e` followed by the
+//! combining acute accent U+0301), as written by macOS and some archivers.
+//! Both look the same but differ in their code points, so a decomposed name
+//! would not match a pattern typed in the composed form and would sort
+//! apart from its composed neighbours.
+//!
+//! `nfc` brings a name to the composed form. Normalized names are only used
+//! for comparisons: the tree always shows the name as stored.
+//!
+//! File: src/normalize.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::borrow::Cow;
+
+use unicode_normalization::{IsNormalized, UnicodeNormalization, is_nfc_quick};
+
+// ============================================================================
+// Public Functions
+// ============================================================================
+
+/// Returns the NFC form of a name.
+///
+/// # Arguments
+///
+/// * `name` - The name as stored.
+///
+/// # Returns
+///
+/// The composed name, borrowed if it is already composed.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::normalize::nfc;
+///
+/// assert_eq!(nfc("cafe\u{301}.txt"), "caf\u{e9}.txt");
+/// assert_eq!(nfc("\u{1112}\u{1161}\u{11AB}"), "\u{D55C}");
+/// assert!(matches!(nfc("plain.txt"), std::borrow::Cow::Borrowed(_)));
+/// ```
+#[must_use]
+pub fn nfc(name: &str) -> Cow<'_, str> {
+    if is_nfc_quick(name.chars()) == IsNormalized::Yes {
+        return Cow::Borrowed(name);
+    }
+    let composed: String = name.nfc().collect();
+    if composed == name {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(composed)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_borrow_names_without_combining_marks() {
+        assert!(matches!(nfc("README.md"), Cow::Borrowed(_)));
+        assert!(matches!(nfc("caf\u{e9}.txt"), Cow::Borrowed(_)));
+        let chinese = nfc("\u{6587}\u{6863}.docx");
+        assert!(matches!(chinese, Cow::Borrowed(_)), "中文名称无需组合");
+    }
+
+    #[test]
+    fn should_compose_decomposed_latin_letters() {
+        assert_eq!(nfc("Cafe\u{301}"), "Caf\u{e9}");
+        assert_eq!(nfc("u\u{308}ber"), "\u{fc}ber");
+        assert_eq!(
+            nfc("a\u{302}\u{323}"),
+            "\u{1EAD}",
+            "组合符应按组合类别重排后组合"
+        );
+        assert_eq!(nfc("x\u{301}"), "x\u{301}", "无组合形式时应保持不变");
+    }
+
+    #[test]
+    fn should_compose_hangul_and_kana() {
+        assert_eq!(nfc("\u{1100}\u{1161}\u{11A8}"), "\u{AC01}");
+        assert_eq!(nfc("\u{1112}\u{1161}"), "\u{D558}");
+        assert_eq!(nfc("\u{304B}\u{3099}"), "\u{304C}");
+    }
+
+    #[test]
+    fn should_replace_singletons() {
+        assert_eq!(nfc("\u{2126}"), "\u{3A9}");
+        assert_eq!(nfc("\u{212B}ngstr\u{f6}m"), "\u{C5}ngstr\u{f6}m");
+    }
+}
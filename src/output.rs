@@ -4,26 +4,44 @@
 //!
 //! - **Output strategies**: stdout, file writing, silent mode (file only)
 //! - **Multiple formats**: txt/json/yml/toml with fixed serialization schema
-//! - **File writing**: overwrite strategy with atomic semantics
-//! - **Streaming output**: `StreamWriter` for immediate flush streaming
+//! - **JSON Schema**: `structured_schema` describes the JSON/YAML documents
+//! - **File writing**: atomic replacement, append, no-clobber, or in place
+//!   for `--checkpoint`
+//! - **Streaming output**: `StreamWriter` for buffered streaming with periodic flush
+//! - **Paging**: `PagedWriter` hands long terminal output to `%PAGER%` or `more`
+//! - **Text encoding**: UTF-8, UTF-16LE or GBK with an optional BOM
 //!
 //! File: src/output.rs
 //! Author: WaterRun
-//! Date: 2026-01-26
+//! Date: 2026-10-16
 
 #![forbid(unsafe_code)]
 
-use std::fs::{self, File};
-use std::io::{self, BufWriter, Stdout, StdoutLock, Write};
-use std::path::Path;
-
+use std::borrow::Cow;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, ErrorKind, IsTerminal, Seek, SeekFrom, Stdout, StdoutLock, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+use encoding_rs::EncoderResult;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::config::{Config, OutputFormat};
+use crate::arena::{NodeRef, TreeArena};
+use crate::config::{Config, LineEnding, OutputEncoding, OutputFormat};
 use crate::error::OutputError;
 use crate::render::RenderResult;
 use crate::scan::{EntryKind, TreeNode};
+use crate::stats::{ExtensionEntry, LevelStats, REPORT_EXTENSION_LIMIT, StatsCollector};
+use crate::winpath;
+use crate::winvol;
 
 // ============================================================================
 // Constants
@@ -32,13 +50,39 @@ use crate::scan::{EntryKind, TreeNode};
 /// Schema version for structured output formats.
 const SCHEMA_VERSION: &str = "treepp.pretty.v1";
 
+/// Version of the JSON/YAML document layout.
+///
+/// The major number changes when a field is removed, renamed or changes its
+/// meaning; the minor number changes when optional fields are added.
+pub const FORMAT_VERSION: &str = "1.0";
+
+/// FNV-1a offset basis of the `--node-ids` hash.
+const NODE_ID_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV-1a prime of the `--node-ids` hash.
+const NODE_ID_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Buffer capacity of the streaming stdout writer, in bytes.
+const STREAM_BUFFER_CAPACITY: usize = 64 * 1024;
+
+/// Maximum time streamed lines may sit in the buffer before being flushed.
+const STREAM_FLUSH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Pager started when `PAGER` is unset; ships with every Windows version.
+const DEFAULT_PAGER: &str = "more";
+
 // ============================================================================
 // Streaming Writer
 // ============================================================================
 
-/// A streaming writer that flushes immediately after each write.
+/// A buffered streaming writer over a single stdout lock.
+///
+/// Holds the stdout lock for its whole lifetime and batches lines in a
+/// buffer, flushing once `STREAM_FLUSH_INTERVAL` has passed since the last
+/// flush. This keeps output scrolling in real time without paying for a
+/// console write per line, which is very slow on Windows consoles.
 ///
-/// Wraps stdout with automatic flushing to provide real-time scrolling output.
+/// Any buffered content is flushed when the writer is dropped.
 ///
 /// # Examples
 ///
@@ -49,9 +93,12 @@ const SCHEMA_VERSION: &str = "treepp.pretty.v1";
 /// let mut writer = StreamWriter::new(&stdout);
 /// writer.write_line("├─src").unwrap();
 /// writer.write("Header content\n").unwrap();
+/// writer.flush().unwrap();
 /// ```
 pub struct StreamWriter<'a> {
-    handle: StdoutLock<'a>,
+    handle: BufWriter<PagedWriter<StdoutLock<'a>>>,
+    encoder: TextEncoder,
+    last_flush: Instant,
 }
 
 impl<'a> StreamWriter<'a> {
@@ -63,7 +110,7 @@ impl<'a> StreamWriter<'a> {
     ///
     /// # Returns
     ///
-    /// A new `StreamWriter` instance with the stdout locked.
+    /// A new `StreamWriter` instance with the stdout locked and buffered.
     ///
     /// # Examples
     ///
@@ -75,12 +122,56 @@ impl<'a> StreamWriter<'a> {
     /// ```
     #[must_use]
     pub fn new(stdout: &'a Stdout) -> Self {
+        Self::with_encoder(stdout, TextEncoder::new(OutputEncoding::Utf8, false))
+    }
+
+    /// Creates a new streaming writer that encodes its output.
+    ///
+    /// # Arguments
+    ///
+    /// * `stdout` - Reference to the standard output handle.
+    /// * `encoder` - Encoder applied to everything written.
+    ///
+    /// # Returns
+    ///
+    /// A new `StreamWriter` instance with the stdout locked and buffered.
+    #[must_use]
+    pub fn with_encoder(stdout: &'a Stdout, encoder: TextEncoder) -> Self {
         Self {
-            handle: stdout.lock(),
+            handle: BufWriter::with_capacity(
+                STREAM_BUFFER_CAPACITY,
+                PagedWriter::new(stdout.lock(), None),
+            ),
+            encoder,
+            last_flush: Instant::now(),
         }
     }
 
-    /// Writes a line and flushes immediately.
+    /// Pipes the output through a pager once it exceeds `page_after` lines.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_after` - Lines shown before the pager starts, or `None` to
+    ///   never start one (see `PagedWriter::new`).
+    ///
+    /// # Returns
+    ///
+    /// The writer, paging its output as configured.
+    #[must_use]
+    pub fn paged(mut self, page_after: Option<usize>) -> Self {
+        self.handle.get_mut().page_after(page_after);
+        self
+    }
+
+    /// Returns whether the reader of the output has gone away.
+    ///
+    /// Once closed, everything written is discarded.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.handle.get_ref().is_closed()
+    }
+
+    /// Writes a line, flushing if the flush interval has elapsed.
     ///
     /// Automatically appends a newline character.
     ///
@@ -107,12 +198,13 @@ impl<'a> StreamWriter<'a> {
     /// writer.write_line("└─tests").unwrap();
     /// ```
     pub fn write_line(&mut self, line: &str) -> Result<(), OutputError> {
-        writeln!(self.handle, "{}", line)?;
-        self.handle.flush()?;
-        Ok(())
+        self.handle.write_all(&self.encoder.encode(line))?;
+        self.handle.write_all(&self.encoder.encode("\n"))?;
+        self.flush_if_due()
     }
 
-    /// Writes a string without appending a newline and flushes.
+    /// Writes a string without appending a newline, flushing if the flush
+    /// interval has elapsed.
     ///
     /// # Arguments
     ///
@@ -137,10 +229,547 @@ impl<'a> StreamWriter<'a> {
     /// writer.write("value\n").unwrap();
     /// ```
     pub fn write(&mut self, content: &str) -> Result<(), OutputError> {
-        write!(self.handle, "{}", content)?;
+        self.handle.write_all(&self.encoder.encode(content))?;
+        self.flush_if_due()
+    }
+
+    /// Flushes all buffered content to stdout.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputError::StdoutFailed` if flushing fails.
+    pub fn flush(&mut self) -> Result<(), OutputError> {
         self.handle.flush()?;
+        self.last_flush = Instant::now();
         Ok(())
     }
+
+    /// Flushes all buffered content and waits for the pager, if any.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputError::StdoutFailed` if flushing fails.
+    pub fn finish(&mut self) -> Result<(), OutputError> {
+        self.flush()?;
+        self.handle.get_mut().finish()?;
+        Ok(())
+    }
+
+    /// Flushes the buffer if `STREAM_FLUSH_INTERVAL` has elapsed since the
+    /// last flush.
+    fn flush_if_due(&mut self) -> Result<(), OutputError> {
+        if self.last_flush.elapsed() >= STREAM_FLUSH_INTERVAL {
+            self.flush()?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Pager
+// ============================================================================
+
+/// Where a `PagedWriter` currently sends its output.
+enum PagerSink {
+    /// Straight to the inner writer.
+    Direct,
+    /// Held back until the output is known to exceed `limit` lines.
+    Holding {
+        held: Vec<u8>,
+        lines: usize,
+        limit: usize,
+    },
+    /// Into the standard input of a running pager.
+    Paging(Child),
+    /// Nowhere; the reader went away before reading everything.
+    Closed,
+}
+
+/// A writer that hands its output to a pager once it gets long.
+///
+/// Output is held back until more than `page_after` lines have been
+/// written; the pager is then started and receives the held lines and
+/// everything after. Output that ends before that goes to the inner writer
+/// unpaged, so short trees never start a pager. With a limit of zero, the
+/// pager starts with the first line.
+///
+/// The pager is `%PAGER%` if set and `more` otherwise, run through
+/// `cmd /C` so that built-in commands and arguments work. If it cannot be
+/// started, the output goes to the inner writer instead.
+///
+/// When the reader goes away before reading everything, because the user
+/// quit the pager or the pipe stdout was redirected into was closed,
+/// further output is discarded and `is_closed` reports it; writes do not
+/// fail.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use treepp::output::PagedWriter;
+///
+/// let mut writer = PagedWriter::new(Vec::new(), Some(24));
+/// writer.write_all(b"C:.\n").unwrap();
+/// writer.finish().unwrap();
+/// assert_eq!(writer.get_ref(), b"C:.\n");
+/// ```
+pub struct PagedWriter<W: Write> {
+    inner: W,
+    sink: PagerSink,
+}
+
+impl<W: Write> PagedWriter<W> {
+    /// Creates a writer that pages after `page_after` lines.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - Writer receiving the output when no pager runs.
+    /// * `page_after` - Lines written before the pager starts, or `None` to
+    ///   never start one.
+    ///
+    /// # Returns
+    ///
+    /// A new `PagedWriter`.
+    #[must_use]
+    pub fn new(inner: W, page_after: Option<usize>) -> Self {
+        let mut writer = Self {
+            inner,
+            sink: PagerSink::Direct,
+        };
+        writer.page_after(page_after);
+        writer
+    }
+
+    /// Changes the line count after which the pager starts.
+    ///
+    /// Only takes effect before anything has been written.
+    fn page_after(&mut self, page_after: Option<usize>) {
+        self.sink = match page_after {
+            Some(limit) => PagerSink::Holding {
+                held: Vec::new(),
+                lines: 0,
+                limit,
+            },
+            None => PagerSink::Direct,
+        };
+    }
+
+    /// Returns the inner writer.
+    #[must_use]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns whether the reader went away before reading everything.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        matches!(self.sink, PagerSink::Closed)
+    }
+
+    /// Writes out any held output and waits for the pager to exit.
+    ///
+    /// Everything written afterwards goes to the inner writer.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing the held output fails or the pager
+    /// cannot be waited for.
+    pub fn finish(&mut self) -> io::Result<()> {
+        match mem::replace(&mut self.sink, PagerSink::Direct) {
+            PagerSink::Direct => {}
+            PagerSink::Holding { held, .. } => self.write_direct(&held)?,
+            PagerSink::Paging(mut pager) => {
+                drop(pager.stdin.take());
+                return pager.wait().map(drop);
+            }
+            PagerSink::Closed => {
+                self.sink = PagerSink::Closed;
+                return Ok(());
+            }
+        }
+        self.flush()
+    }
+
+    /// Starts the pager and hands it the held output.
+    fn start_pager(&mut self) -> io::Result<()> {
+        let PagerSink::Holding { held, .. } = mem::replace(&mut self.sink, PagerSink::Direct)
+        else {
+            return Ok(());
+        };
+        match spawn_pager() {
+            Ok(pager) => {
+                self.sink = PagerSink::Paging(pager);
+                self.write_to_pager(&held)
+            }
+            Err(_) => self.write_direct(&held),
+        }
+    }
+
+    /// Writes to the inner writer, closing the sink if its reader is gone.
+    fn write_direct(&mut self, buf: &[u8]) -> io::Result<()> {
+        let result = self.inner.write_all(buf);
+        self.close_on_broken_pipe(result)
+    }
+
+    /// Writes to the running pager, closing the sink if it has exited.
+    fn write_to_pager(&mut self, buf: &[u8]) -> io::Result<()> {
+        let PagerSink::Paging(ref mut pager) = self.sink else {
+            return Ok(());
+        };
+        let result = pager
+            .stdin
+            .as_mut()
+            .expect("pager stdin is piped")
+            .write_all(buf);
+        self.close_on_broken_pipe(result)
+    }
+
+    /// Turns a broken pipe into a closed sink; other results pass through.
+    fn close_on_broken_pipe(&mut self, result: io::Result<()>) -> io::Result<()> {
+        match result {
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => {
+                if let PagerSink::Paging(mut pager) =
+                    mem::replace(&mut self.sink, PagerSink::Closed)
+                {
+                    drop(pager.stdin.take());
+                    let _ = pager.wait();
+                }
+                Ok(())
+            }
+            result => result,
+        }
+    }
+}
+
+impl<W: Write> Write for PagedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.sink {
+            PagerSink::Direct => self.write_direct(buf)?,
+            PagerSink::Holding {
+                ref mut held,
+                ref mut lines,
+                limit,
+            } => {
+                held.extend_from_slice(buf);
+                *lines += buf.iter().filter(|&&b| b == b'\n').count();
+                if *lines > limit {
+                    self.start_pager()?;
+                }
+            }
+            PagerSink::Paging(_) => self.write_to_pager(buf)?,
+            PagerSink::Closed => {}
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // The pager pipe is unbuffered, and held output waits for `finish`.
+        match self.sink {
+            PagerSink::Direct => {
+                let result = self.inner.flush();
+                self.close_on_broken_pipe(result)
+            }
+            PagerSink::Holding { .. } | PagerSink::Paging(_) | PagerSink::Closed => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Drop for PagedWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Starts the pager with its standard input piped.
+fn spawn_pager() -> io::Result<Child> {
+    let command = env::var("PAGER")
+        .ok()
+        .filter(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    Command::new("cmd")
+        .args(["/C", &command])
+        .stdin(Stdio::piped())
+        .spawn()
+}
+
+// ============================================================================
+// Text Encoding
+// ============================================================================
+
+/// Encodes output text, writing the byte order mark before the first chunk.
+///
+/// Characters that GBK cannot represent are written as `?`, as Windows does
+/// when converting to an ANSI code page. Text is produced with `\n` line
+/// ends, which are written as the configured `LineEnding`. With trailing
+/// whitespace trimming, whitespace at the end of a chunk is held back until
+/// the rest of its line shows whether it is trailing.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::OutputEncoding;
+/// use treepp::output::TextEncoder;
+///
+/// let mut encoder = TextEncoder::new(OutputEncoding::Utf16Le, true);
+/// assert_eq!(encoder.encode("a").as_ref(), &[0xFF, 0xFE, b'a', 0]);
+/// assert_eq!(encoder.encode("b").as_ref(), &[b'b', 0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextEncoder {
+    encoding: OutputEncoding,
+    bom_pending: bool,
+    trim_trailing: bool,
+    pending_whitespace: String,
+    line_ending: LineEnding,
+}
+
+impl TextEncoder {
+    /// Creates an encoder.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - Target encoding.
+    /// * `bom` - Whether to write the byte order mark before the first chunk.
+    ///
+    /// # Returns
+    ///
+    /// A new `TextEncoder`.
+    #[must_use]
+    pub const fn new(encoding: OutputEncoding, bom: bool) -> Self {
+        Self {
+            encoding,
+            bom_pending: bom,
+            trim_trailing: false,
+            pending_whitespace: String::new(),
+            line_ending: LineEnding::Lf,
+        }
+    }
+
+    /// Sets the line ending written for each `\n`.
+    ///
+    /// # Arguments
+    ///
+    /// * `line_ending` - Line ending of the output.
+    ///
+    /// # Returns
+    ///
+    /// The encoder with the setting applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::{LineEnding, OutputEncoding};
+    /// use treepp::output::TextEncoder;
+    ///
+    /// let mut encoder = TextEncoder::new(OutputEncoding::Utf8, false).line_ending(LineEnding::Crlf);
+    /// assert_eq!(encoder.encode("a\nb\n").as_ref(), b"a\r\nb\r\n");
+    /// ```
+    #[must_use]
+    pub const fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Creates an encoder with the line settings of `config` but without
+    /// a byte order mark, for text following output that already had one.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - Target encoding.
+    /// * `config` - Configuration holding the line settings.
+    ///
+    /// # Returns
+    ///
+    /// A new `TextEncoder`.
+    #[must_use]
+    pub const fn continuation(encoding: OutputEncoding, config: &Config) -> Self {
+        Self::new(encoding, false)
+            .trim_trailing(config.output.trim_trailing_whitespace)
+            .line_ending(config.output.line_ending)
+    }
+
+    /// Sets whether whitespace at the end of lines is removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `trim` - Whether to remove trailing whitespace.
+    ///
+    /// # Returns
+    ///
+    /// The encoder with the setting applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::OutputEncoding;
+    /// use treepp::output::TextEncoder;
+    ///
+    /// let mut encoder = TextEncoder::new(OutputEncoding::Utf8, false).trim_trailing(true);
+    /// assert_eq!(encoder.encode("│  \n").as_ref(), "│\n".as_bytes());
+    /// ```
+    #[must_use]
+    pub const fn trim_trailing(mut self, trim: bool) -> Self {
+        self.trim_trailing = trim;
+        self
+    }
+
+    /// Creates the encoder for the output file.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration holding the encoding and BOM choice.
+    ///
+    /// # Returns
+    ///
+    /// An encoder using the configured encoding.
+    #[must_use]
+    pub const fn for_file(config: &Config) -> Self {
+        Self::new(config.output.encoding, config.output.bom)
+            .trim_trailing(config.output.trim_trailing_whitespace)
+            .line_ending(config.output.line_ending)
+    }
+
+    /// Creates the encoder for stdout.
+    ///
+    /// A console is always written as UTF-8, which the standard library
+    /// converts to UTF-16 for the console regardless of its code page.
+    /// Only redirected stdout uses the configured encoding.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration holding the encoding and BOM choice.
+    ///
+    /// # Returns
+    ///
+    /// An encoder suited to the current stdout.
+    #[must_use]
+    pub fn for_stdout(config: &Config) -> Self {
+        if io::stdout().is_terminal() {
+            Self::continuation(OutputEncoding::Utf8, config)
+        } else {
+            Self::for_file(config)
+        }
+    }
+
+    /// Returns the target encoding.
+    #[must_use]
+    pub const fn encoding(&self) -> OutputEncoding {
+        self.encoding
+    }
+
+    /// Encodes a chunk of text.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Text to encode.
+    ///
+    /// # Returns
+    ///
+    /// The encoded bytes, preceded by the BOM on the first call if requested.
+    pub fn encode<'a>(&mut self, content: &'a str) -> Cow<'a, [u8]> {
+        let content = self.trim(content);
+        let content = self.end_lines(content);
+        let bom = if self.bom_pending {
+            self.bom_pending = false;
+            self.encoding.bom()
+        } else {
+            &[]
+        };
+
+        let encoded: Cow<'a, [u8]> = match self.encoding {
+            OutputEncoding::Utf8 => match content {
+                Cow::Borrowed(text) => Cow::Borrowed(text.as_bytes()),
+                Cow::Owned(text) => Cow::Owned(text.into_bytes()),
+            },
+            OutputEncoding::Utf16Le => {
+                Cow::Owned(content.encode_utf16().flat_map(u16::to_le_bytes).collect())
+            }
+            OutputEncoding::Gbk => Cow::Owned(encode_gbk(&content)),
+        };
+
+        if bom.is_empty() {
+            encoded
+        } else {
+            let mut bytes = Vec::with_capacity(bom.len() + encoded.len());
+            bytes.extend_from_slice(bom);
+            bytes.extend_from_slice(&encoded);
+            Cow::Owned(bytes)
+        }
+    }
+
+    /// Converts the `\n` line ends of a chunk to the configured line ending.
+    fn end_lines<'a>(&self, content: Cow<'a, str>) -> Cow<'a, str> {
+        if self.line_ending == LineEnding::Lf || !content.contains('\n') {
+            return content;
+        }
+        Cow::Owned(content.replace('\n', self.line_ending.as_str()))
+    }
+
+    /// Removes trailing whitespace from the lines of a chunk, if enabled.
+    ///
+    /// Whitespace at the end of the chunk is kept back and written before
+    /// the next non-whitespace text on the same line; it is dropped if the
+    /// line ends first.
+    fn trim<'a>(&mut self, content: &'a str) -> Cow<'a, str> {
+        if !self.trim_trailing {
+            return Cow::Borrowed(content);
+        }
+
+        let mut trimmed = String::with_capacity(content.len());
+        for piece in content.split_inclusive('\n') {
+            let (line, newline) = match piece.strip_suffix('\n') {
+                Some(line) => (line, true),
+                None => (piece, false),
+            };
+            let text = line.trim_end();
+            if !text.is_empty() {
+                trimmed.push_str(&self.pending_whitespace);
+                self.pending_whitespace.clear();
+                trimmed.push_str(text);
+            }
+            if newline {
+                self.pending_whitespace.clear();
+                trimmed.push('\n');
+            } else {
+                self.pending_whitespace.push_str(&line[text.len()..]);
+            }
+        }
+        Cow::Owned(trimmed)
+    }
+}
+
+/// Encodes text as GBK, replacing unmappable characters with `?`.
+fn encode_gbk(content: &str) -> Vec<u8> {
+    let mut encoder = encoding_rs::GBK.new_encoder();
+    let mut bytes = Vec::with_capacity(content.len());
+    let mut buffer = [0u8; 4096];
+    let mut rest = content;
+
+    loop {
+        let (result, read, written) =
+            encoder.encode_from_utf8_without_replacement(rest, &mut buffer, true);
+        bytes.extend_from_slice(&buffer[..written]);
+        rest = &rest[read..];
+        match result {
+            EncoderResult::InputEmpty => break,
+            EncoderResult::OutputFull => {}
+            EncoderResult::Unmappable(_) => bytes.push(b'?'),
+        }
+    }
+
+    bytes
 }
 
 // ============================================================================
@@ -150,13 +779,21 @@ impl<'a> StreamWriter<'a> {
 /// Directory node in the structured output format.
 ///
 /// Represents a directory with its files and subdirectories.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct DirNode {
     /// Node type, always "dir" for directories.
     #[serde(rename = "type")]
     pub node_type: String,
-    /// List of file names in this directory.
-    pub files: Vec<String>,
+    /// Stable identifier of the directory (only when node_ids is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Identifier of the containing directory (only when node_ids is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// List of file names in this directory; files with metadata, links
+    /// and files with node ids are objects with the name.
+    #[schemars(with = "Vec<FileValue>")]
+    pub files: Vec<Value>,
     /// Map of subdirectory names to their nodes.
     pub dirs: std::collections::BTreeMap<String, DirNode>,
     /// File size in bytes (only when show_size is enabled).
@@ -165,9 +802,26 @@ pub struct DirNode {
     /// Disk usage for directory (only when show_disk_usage is enabled).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disk_usage: Option<u64>,
+    /// Share of the parent's disk usage in percent, to one decimal place
+    /// (only when show_du_percent is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percent_of_parent: Option<f64>,
+    /// Change in disk usage since the previous `--du-delta` run, in bytes
+    /// (only when show_du_delta is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_usage_delta: Option<i64>,
     /// Last modification date (only when show_date is enabled).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub modified: Option<String>,
+    /// Creation date (only when show_created is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    /// Last access date (only when show_accessed is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accessed: Option<String>,
+    /// Why the directory's content is missing (only for unreadable directories).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 impl DirNode {
@@ -176,40 +830,79 @@ impl DirNode {
     fn new() -> Self {
         Self {
             node_type: "dir".to_string(),
+            id: None,
+            parent_id: None,
             files: Vec::new(),
             dirs: std::collections::BTreeMap::new(),
             size: None,
             disk_usage: None,
+            percent_of_parent: None,
+            disk_usage_delta: None,
             modified: None,
+            created: None,
+            accessed: None,
+            error: None,
         }
     }
 }
 
+/// A file in the structured output format.
+///
+/// Files are written as plain names, or as objects when metadata, a link
+/// target or node ids are shown.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum FileValue {
+    /// File name.
+    Name(String),
+    /// File name with metadata.
+    Entry(FileEntry),
+}
+
 /// File entry with optional metadata.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct FileEntry {
     /// File name.
     pub name: String,
+    /// Stable identifier of the file (only when node_ids is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Identifier of the containing directory (only when node_ids is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
     /// File size in bytes (only when show_size is enabled).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub size: Option<u64>,
     /// Last modification date (only when show_date is enabled).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub modified: Option<String>,
+    /// Creation date (only when show_created is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    /// Last access date (only when show_accessed is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accessed: Option<String>,
+    /// Target of a symbolic link or junction (only for links).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<String>,
 }
 
 /// Root node in the structured output format.
 ///
 /// Contains the root path and directory structure.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct RootNode {
     /// Root path display string.
     pub path: String,
     /// Node type, always "dir".
     #[serde(rename = "type")]
     pub node_type: String,
+    /// Stable identifier of the root (only when node_ids is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     /// List of file names or file entries in root directory.
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(with = "Vec<FileValue>")]
     pub files: Vec<Value>,
     /// Map of subdirectory names to their nodes.
     pub dirs: std::collections::BTreeMap<String, DirNode>,
@@ -221,19 +914,34 @@ pub struct RootNode {
 /// Top-level structure for structured output.
 ///
 /// Contains schema version and root node.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(title = "tree++ structured output")]
 pub struct StructuredOutput {
     /// Schema version identifier.
     pub schema: String,
+    /// Version of the document layout: the major number changes with
+    /// incompatible changes, the minor number when optional fields are added.
+    pub format_version: String,
+    /// Whether directories were left unread because `--budget-time` or
+    /// `--budget-entries` ran out (left out when the tree is complete).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
     /// Root directory node.
     pub root: RootNode,
+    /// Most common extensions (only when report_extensions is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<ExtensionEntry>>,
+    /// Entries and file sizes per level, starting with level 1 (only when
+    /// depth_histogram is enabled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub levels: Option<Vec<LevelStats>>,
 }
 
 // ============================================================================
 // Serialization Functions
 // ============================================================================
 
-/// Converts a `TreeNode` to a `DirNode` for structured output.
+/// Converts an arena node to a `DirNode` for structured output.
 ///
 /// # Arguments
 ///
@@ -243,39 +951,152 @@ pub struct StructuredOutput {
 /// # Returns
 ///
 /// A `DirNode` representing the directory structure.
-fn tree_to_dir_node(node: &TreeNode, config: &Config) -> DirNode {
+fn tree_to_dir_node(node: NodeRef<'_>, config: &Config) -> DirNode {
     let mut dir_node = DirNode::new();
 
     if config.render.show_disk_usage {
-        dir_node.disk_usage = node.disk_usage;
+        dir_node.disk_usage = node.disk_usage();
+    }
+    if config.render.show_du_percent {
+        dir_node.percent_of_parent = node
+            .share_of_parent()
+            .map(|share| (share * 10.0).round() / 10.0);
+    }
+    if config.render.show_du_delta
+        && let (Some(usage), Some(previous)) = (node.disk_usage(), node.previous_usage())
+    {
+        dir_node.disk_usage_delta = Some(usage as i64 - previous as i64);
     }
 
+    let metadata = node.metadata();
     if config.render.show_date {
-        if let Some(ref modified) = node.metadata.modified {
-            dir_node.modified = Some(crate::render::format_datetime(modified));
-        }
+        dir_node.modified = metadata
+            .modified
+            .as_ref()
+            .map(crate::render::format_datetime);
+    }
+    if config.render.show_created {
+        dir_node.created = metadata
+            .created
+            .as_ref()
+            .map(crate::render::format_datetime);
+    }
+    if config.render.show_accessed {
+        dir_node.accessed = metadata
+            .accessed
+            .as_ref()
+            .map(crate::render::format_datetime);
+    }
+    dir_node.error = node.error().map(|error| error.to_string());
+    if config.output.node_ids {
+        dir_node.id = Some(node_id_of(node));
+        dir_node.parent_id = node.parent().map(node_id_of);
     }
 
-    let (files, dirs): (Vec<_>, Vec<_>) = node
-        .children
-        .iter()
-        .partition(|c| c.kind == EntryKind::File);
+    let (files, dirs): (Vec<_>, Vec<_>) =
+        node.children().partition(|c| c.kind() == EntryKind::File);
 
     for file in files {
         if config.scan.show_files {
-            dir_node.files.push(file.name.clone());
+            dir_node.files.push(file_value(file, config));
         }
     }
 
     for subdir in dirs {
         let sub_dir_node = tree_to_dir_node(subdir, config);
-        dir_node.dirs.insert(subdir.name.clone(), sub_dir_node);
+        dir_node
+            .dirs
+            .insert(redacted(subdir.name(), config), sub_dir_node);
     }
 
     dir_node
 }
 
-/// Converts a `TreeNode` to a `DirNode` with detailed file metadata.
+/// Converts a file node without metadata for structured output.
+///
+/// # Returns
+///
+/// The file name, or an object with `name` and `link_target` for links and
+/// with `id` and `parent_id` with `--node-ids`.
+fn file_value(file: NodeRef<'_>, config: &Config) -> Value {
+    let node_ids = config.output.node_ids;
+    let link_target = file.metadata().link_target.as_ref();
+    if link_target.is_none() && !node_ids {
+        return Value::String(redacted(file.name(), config));
+    }
+
+    let mut file_obj = serde_json::Map::new();
+    file_obj.insert(
+        "name".to_string(),
+        Value::String(redacted(file.name(), config)),
+    );
+    if let Some(target) = link_target {
+        file_obj.insert(
+            "link_target".to_string(),
+            Value::String(redacted(&target.to_string_lossy(), config)),
+        );
+    }
+    if node_ids {
+        insert_node_ids(&mut file_obj, file);
+    }
+    Value::Object(file_obj)
+}
+
+/// Adds the `--node-ids` fields of an entry to its object.
+fn insert_node_ids(object: &mut serde_json::Map<String, Value>, node: NodeRef<'_>) {
+    object.insert("id".to_string(), Value::String(node_id_of(node)));
+    if let Some(parent) = node.parent() {
+        object.insert("parent_id".to_string(), Value::String(node_id_of(parent)));
+    }
+}
+
+/// Returns the `--node-ids` identifier of a node (see `node_id`).
+fn node_id_of(node: NodeRef<'_>) -> String {
+    let mut names = Vec::new();
+    let mut current = node;
+    while let Some(parent) = current.parent() {
+        names.push(current.name());
+        current = parent;
+    }
+    names.reverse();
+    node_id(&names.join("/"))
+}
+
+/// Computes the stable identifier of an entry for `--node-ids`.
+///
+/// The identifier is the 64-bit FNV-1a hash of the UTF-8 path relative to
+/// the root, with components joined by `/` and names as stored, written as
+/// 16 lowercase hexadecimal digits. The root has the empty path. The scheme
+/// is part of the output format and stays the same across versions, so
+/// identifiers can be compared between runs.
+///
+/// # Arguments
+///
+/// * `relative_path` - Path relative to the root, e.g. `src/main.rs`.
+///
+/// # Returns
+///
+/// The identifier.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::output::node_id;
+///
+/// assert_eq!(node_id(""), "cbf29ce484222325");
+/// assert_eq!(node_id("src/main.rs"), "df9f8d2d8eb12e9c");
+/// ```
+#[must_use]
+pub fn node_id(relative_path: &str) -> String {
+    let hash = relative_path
+        .bytes()
+        .fold(NODE_ID_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(NODE_ID_PRIME)
+        });
+    format!("{:016x}", hash)
+}
+
+/// Converts an arena node to a `DirNode` with detailed file metadata.
 ///
 /// # Arguments
 ///
@@ -286,52 +1107,70 @@ fn tree_to_dir_node(node: &TreeNode, config: &Config) -> DirNode {
 ///
 /// A tuple of (files as Value array, dirs as BTreeMap).
 fn tree_to_detailed_content(
-    node: &TreeNode,
+    node: NodeRef<'_>,
     config: &Config,
 ) -> (Vec<Value>, std::collections::BTreeMap<String, DirNode>) {
     let mut files = Vec::new();
     let mut dirs = std::collections::BTreeMap::new();
 
-    let (file_nodes, dir_nodes): (Vec<_>, Vec<_>) = node
-        .children
-        .iter()
-        .partition(|c| c.kind == EntryKind::File);
+    let (file_nodes, dir_nodes): (Vec<_>, Vec<_>) =
+        node.children().partition(|c| c.kind() == EntryKind::File);
 
-    let needs_file_metadata =
-        (config.render.show_size || config.render.show_date) && config.scan.show_files;
+    let dates = [
+        ("modified", config.render.show_date),
+        ("created", config.render.show_created),
+        ("accessed", config.render.show_accessed),
+    ];
+    let needs_file_metadata = (config.render.show_size || dates.iter().any(|&(_, shown)| shown))
+        && config.scan.show_files;
 
     for file in file_nodes {
         if config.scan.show_files {
             if needs_file_metadata {
                 let mut file_obj = serde_json::Map::new();
-                file_obj.insert("name".to_string(), Value::String(file.name.clone()));
+                file_obj.insert(
+                    "name".to_string(),
+                    Value::String(redacted(file.name(), config)),
+                );
 
                 if config.render.show_size {
                     file_obj.insert(
                         "size".to_string(),
-                        Value::Number(file.metadata.size.into()),
+                        Value::Number(file.metadata().size.into()),
                     );
                 }
 
-                if config.render.show_date {
-                    if let Some(ref modified) = file.metadata.modified {
+                let metadata = file.metadata();
+                let times = [metadata.modified, metadata.created, metadata.accessed];
+                for ((key, shown), time) in dates.iter().zip(times) {
+                    if *shown && let Some(ref time) = time {
                         file_obj.insert(
-                            "modified".to_string(),
-                            Value::String(crate::render::format_datetime(modified)),
+                            (*key).to_string(),
+                            Value::String(crate::render::format_datetime(time)),
                         );
                     }
                 }
 
+                if let Some(ref target) = metadata.link_target {
+                    file_obj.insert(
+                        "link_target".to_string(),
+                        Value::String(redacted(&target.to_string_lossy(), config)),
+                    );
+                }
+                if config.output.node_ids {
+                    insert_node_ids(&mut file_obj, file);
+                }
+
                 files.push(Value::Object(file_obj));
             } else {
-                files.push(Value::String(file.name.clone()));
+                files.push(file_value(file, config));
             }
         }
     }
 
     for subdir in dir_nodes {
         let sub_dir_node = tree_to_dir_node(subdir, config);
-        dirs.insert(subdir.name.clone(), sub_dir_node);
+        dirs.insert(redacted(subdir.name(), config), sub_dir_node);
     }
 
     (files, dirs)
@@ -339,6 +1178,9 @@ fn tree_to_detailed_content(
 
 /// Creates the structured output from a tree node.
 ///
+/// `node` may also be a directory below the root, as queried from the
+/// server (`--serve`); `root.path` still names the scanned root then.
+///
 /// # Arguments
 ///
 /// * `node` - The root tree node.
@@ -347,25 +1189,46 @@ fn tree_to_detailed_content(
 /// # Returns
 ///
 /// A `StructuredOutput` structure ready for serialization.
-fn create_structured_output(node: &TreeNode, config: &Config) -> StructuredOutput {
-    let root_path = format_root_path(&config.root_path);
+#[must_use]
+pub fn create_structured_output(node: NodeRef<'_>, config: &Config) -> StructuredOutput {
+    let root_path = match config.render.relative_to {
+        Some(ref base) => winpath::relative_to(&config.root_path, base)
+            .to_string_lossy()
+            .into_owned(),
+        None => format_root_path(&config.root_path),
+    };
     let (files, dirs) = tree_to_detailed_content(node, config);
 
     let mut root = RootNode {
-        path: root_path,
+        path: redacted(&root_path, config),
         node_type: "dir".to_string(),
+        id: config.output.node_ids.then(|| node_id("")),
         files,
         dirs,
         disk_usage: None,
     };
 
     if config.render.show_disk_usage {
-        root.disk_usage = node.disk_usage;
+        root.disk_usage = node.disk_usage();
     }
 
+    let collector = (config.render.report_extensions || config.render.depth_histogram)
+        .then(|| StatsCollector::from_node(node));
+    let extensions = collector
+        .as_ref()
+        .filter(|_| config.render.report_extensions)
+        .map(|collector| collector.top_extensions(REPORT_EXTENSION_LIMIT));
+    let levels = collector
+        .filter(|_| config.render.depth_histogram)
+        .map(|collector| collector.levels().to_vec());
+
     StructuredOutput {
         schema: SCHEMA_VERSION.to_string(),
+        format_version: FORMAT_VERSION.to_string(),
+        truncated: node.count_unread() > 0,
         root,
+        extensions,
+        levels,
     }
 }
 
@@ -377,24 +1240,49 @@ fn create_structured_output(node: &TreeNode, config: &Config) -> StructuredOutpu
 ///
 /// # Returns
 ///
-/// A formatted path string.
+/// A formatted path string: `X:.` for drive-rooted paths, otherwise the
+/// display form of the path (UNC roots keep their `\\server\share` form).
 fn format_root_path(path: &Path) -> String {
-    use std::path::Component;
-
-    if let Some(Component::Prefix(prefix)) = path.components().next() {
-        let prefix_str = prefix.as_os_str().to_string_lossy();
-        let chars: Vec<char> = prefix_str.chars().collect();
-
-        if chars.len() >= 2 && chars[1] == ':' {
-            return format!("{}:.", chars[0].to_ascii_uppercase());
-        }
+    match winpath::drive_letter(path) {
+        Some(drive) => format!("{}:.", drive),
+        None => winpath::to_display(path).to_string_lossy().into_owned(),
+    }
+}
 
-        if prefix_str.starts_with(r"\\?\") && chars.len() >= 6 && chars[5] == ':' {
-            return format!("{}:.", chars[4].to_ascii_uppercase());
-        }
+/// Returns a name or path as written to structured output, with `--redact`
+/// applied.
+fn redacted(text: &str, config: &Config) -> String {
+    match &config.render.redaction {
+        Some(redactor) => redactor.apply(text).into_owned(),
+        None => text.to_string(),
     }
+}
 
-    path.to_string_lossy().into_owned()
+/// Generates the JSON Schema of the JSON and YAML output.
+///
+/// The schema is derived from the types the output is serialized from, so
+/// it always matches what this version writes. It is printed by
+/// `--print-schema` and published as `schema/treepp.pretty.v1.schema.json`.
+///
+/// # Returns
+///
+/// The pretty-printed schema (JSON Schema draft 2020-12).
+///
+/// # Errors
+///
+/// Returns `OutputError::SerializationFailed` if JSON serialization fails.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::output::structured_schema;
+///
+/// let schema = structured_schema().unwrap();
+/// assert!(schema.contains("\"format_version\""));
+/// ```
+pub fn structured_schema() -> Result<String, OutputError> {
+    let schema = schemars::schema_for!(StructuredOutput);
+    serde_json::to_string_pretty(&schema).map_err(|e| OutputError::json_error(e.to_string()))
 }
 
 /// Serializes a tree node to JSON format.
@@ -433,7 +1321,12 @@ fn format_root_path(path: &Path) -> String {
 /// assert!(json.contains("treepp.pretty.v1"));
 /// ```
 pub fn serialize_json(node: &TreeNode, config: &Config) -> Result<String, OutputError> {
-    let output = create_structured_output(node, config);
+    tree_to_json(TreeArena::from_tree(node).root(), config)
+}
+
+/// Serializes an arena tree to JSON format.
+fn tree_to_json(tree: NodeRef<'_>, config: &Config) -> Result<String, OutputError> {
+    let output = create_structured_output(tree, config);
     serde_json::to_string_pretty(&output).map_err(|e| OutputError::json_error(e.to_string()))
 }
 
@@ -472,7 +1365,12 @@ pub fn serialize_json(node: &TreeNode, config: &Config) -> Result<String, Output
 /// assert!(yaml.contains("treepp.pretty.v1"));
 /// ```
 pub fn serialize_yaml(node: &TreeNode, config: &Config) -> Result<String, OutputError> {
-    let output = create_structured_output(node, config);
+    tree_to_yaml(TreeArena::from_tree(node).root(), config)
+}
+
+/// Serializes an arena tree to YAML format.
+fn tree_to_yaml(tree: NodeRef<'_>, config: &Config) -> Result<String, OutputError> {
+    let output = create_structured_output(tree, config);
     serde_yaml::to_string(&output).map_err(|e| OutputError::yaml_error(e.to_string()))
 }
 
@@ -511,7 +1409,12 @@ pub fn serialize_yaml(node: &TreeNode, config: &Config) -> Result<String, Output
 /// assert!(toml_str.contains("treepp.pretty.v1"));
 /// ```
 pub fn serialize_toml(node: &TreeNode, config: &Config) -> Result<String, OutputError> {
-    let output = create_structured_output(node, config);
+    tree_to_toml(TreeArena::from_tree(node).root(), config)
+}
+
+/// Serializes an arena tree to TOML format.
+fn tree_to_toml(tree: NodeRef<'_>, config: &Config) -> Result<String, OutputError> {
+    let output = create_structured_output(tree, config);
 
     // Convert to TOML-compatible structure
     let toml_output = TomlOutput::from_structured(&output);
@@ -526,7 +1429,13 @@ pub fn serialize_toml(node: &TreeNode, config: &Config) -> Result<String, Output
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct TomlOutput {
     schema: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    truncated: bool,
     root: TomlRootNode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extensions: Option<Vec<ExtensionEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    levels: Option<Vec<LevelStats>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -551,23 +1460,24 @@ struct TomlDirNode {
     #[serde(skip_serializing_if = "Option::is_none")]
     disk_usage: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    percent_of_parent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disk_usage_delta: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    accessed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
     #[serde(skip_serializing_if = "std::collections::BTreeMap::is_empty")]
     dirs: std::collections::BTreeMap<String, TomlDirNode>,
 }
 
 impl TomlOutput {
     fn from_structured(output: &StructuredOutput) -> Self {
-        let files: Vec<String> = output
-            .root
-            .files
-            .iter()
-            .filter_map(|v| match v {
-                Value::String(s) => Some(s.clone()),
-                Value::Object(obj) => obj.get("name").and_then(|n| n.as_str()).map(String::from),
-                _ => None,
-            })
-            .collect();
+        let files = file_names(&output.root.files);
 
         let dirs = output
             .root
@@ -578,6 +1488,7 @@ impl TomlOutput {
 
         Self {
             schema: output.schema.clone(),
+            truncated: output.truncated,
             root: TomlRootNode {
                 path: output.root.path.clone(),
                 node_type: output.root.node_type.clone(),
@@ -585,17 +1496,36 @@ impl TomlOutput {
                 disk_usage: output.root.disk_usage,
                 dirs,
             },
+            extensions: output.extensions.clone(),
+            levels: output.levels.clone(),
         }
     }
 }
 
+/// Reduces file entries to their names, since TOML output lists names only.
+fn file_names(files: &[Value]) -> Vec<String> {
+    files
+        .iter()
+        .filter_map(|v| match v {
+            Value::String(s) => Some(s.clone()),
+            Value::Object(obj) => obj.get("name").and_then(|n| n.as_str()).map(String::from),
+            _ => None,
+        })
+        .collect()
+}
+
 impl TomlDirNode {
     fn from_dir_node(node: &DirNode) -> Self {
         Self {
             node_type: node.node_type.clone(),
-            files: node.files.clone(),
+            files: file_names(&node.files),
             disk_usage: node.disk_usage,
+            percent_of_parent: node.percent_of_parent,
+            disk_usage_delta: node.disk_usage_delta,
             modified: node.modified.clone(),
+            created: node.created.clone(),
+            accessed: node.accessed.clone(),
+            error: node.error.clone(),
             dirs: node
                 .dirs
                 .iter()
@@ -633,30 +1563,62 @@ impl TomlDirNode {
 /// use treepp::config::Config;
 /// use treepp::output::write_stdout;
 ///
-/// let config = Config::default();
-/// write_stdout("Hello, World!\n", &config).unwrap();
+/// let config = Config::default();
+/// write_stdout("Hello, World!\n", &config).unwrap();
+/// ```
+pub fn write_stdout(content: &str, config: &Config) -> Result<(), OutputError> {
+    if config.output.silent {
+        return Ok(());
+    }
+
+    let bytes = TextEncoder::for_stdout(config).encode(content);
+    let stdout = io::stdout();
+    let mut handle = PagedWriter::new(stdout.lock(), config.output.page_after);
+    handle.write_all(&bytes)?;
+    handle.finish()?;
+    Ok(())
+}
+
+/// Writes content to a file as UTF-8.
+///
+/// Replaces the file atomically and creates parent directories if needed.
+/// The write is buffered for performance.
+///
+/// # Arguments
+///
+/// * `content` - The content to write.
+/// * `path` - The destination file path.
+///
+/// # Returns
+///
+/// `Ok(())` on success.
+///
+/// # Errors
+///
+/// Returns `OutputError::FileCreateFailed` if the file cannot be created,
+/// or `OutputError::WriteFailed` if writing fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use treepp::output::write_file;
+///
+/// write_file("content", Path::new("output.txt")).unwrap();
 /// ```
-pub fn write_stdout(content: &str, config: &Config) -> Result<(), OutputError> {
-    if config.output.silent {
-        return Ok(());
-    }
-
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
-    handle.write_all(content.as_bytes())?;
-    handle.flush()?;
-    Ok(())
+pub fn write_file(content: &str, path: &Path) -> Result<(), OutputError> {
+    write_encoded_file(content, path, TextEncoder::new(OutputEncoding::Utf8, false))
 }
 
-/// Writes content to a file.
+/// Writes content to a file in the encoding of the given encoder.
 ///
-/// Uses overwrite strategy and creates parent directories if needed.
-/// The write is buffered for performance.
+/// Replaces the file atomically and creates parent directories if needed.
 ///
 /// # Arguments
 ///
 /// * `content` - The content to write.
 /// * `path` - The destination file path.
+/// * `encoder` - Encoder producing the file bytes.
 ///
 /// # Returns
 ///
@@ -671,40 +1633,20 @@ pub fn write_stdout(content: &str, config: &Config) -> Result<(), OutputError> {
 ///
 /// ```no_run
 /// use std::path::Path;
-/// use treepp::output::write_file;
+/// use treepp::config::OutputEncoding;
+/// use treepp::output::{write_encoded_file, TextEncoder};
 ///
-/// write_file("content", Path::new("output.txt")).unwrap();
+/// let encoder = TextEncoder::new(OutputEncoding::Utf16Le, true);
+/// write_encoded_file("content", Path::new("output.txt"), encoder).unwrap();
 /// ```
-pub fn write_file(content: &str, path: &Path) -> Result<(), OutputError> {
-    if let Some(parent) = path.parent()
-        && !parent.as_os_str().is_empty()
-        && !parent.exists()
-    {
-        fs::create_dir_all(parent).map_err(|e| OutputError::FileCreateFailed {
-            path: path.to_path_buf(),
-            source: e,
-        })?;
-    }
-
-    let file = File::create(path).map_err(|e| OutputError::FileCreateFailed {
-        path: path.to_path_buf(),
-        source: e,
-    })?;
-
-    let mut writer = BufWriter::new(file);
-    writer
-        .write_all(content.as_bytes())
-        .map_err(|e| OutputError::WriteFailed {
-            path: path.to_path_buf(),
-            source: e,
-        })?;
-
-    writer.flush().map_err(|e| OutputError::WriteFailed {
-        path: path.to_path_buf(),
-        source: e,
-    })?;
-
-    Ok(())
+pub fn write_encoded_file(
+    content: &str,
+    path: &Path,
+    encoder: TextEncoder,
+) -> Result<(), OutputError> {
+    let mut file = OutputFile::open(path, encoder, false, false, false)?;
+    file.write(content)?;
+    file.commit()
 }
 
 /// Prints a file output notice to stdout.
@@ -740,13 +1682,450 @@ pub fn print_file_notice(path: &Path, config: &Config) -> Result<(), OutputError
     }
 
     let notice = format!("\noutput: {}\n", path.display());
+    // The tree before the notice already carried the BOM, if any.
+    let mut encoder = TextEncoder::continuation(TextEncoder::for_stdout(config).encoding(), config);
     let stdout = io::stdout();
-    let mut handle = stdout.lock();
-    handle.write_all(notice.as_bytes())?;
-    handle.flush()?;
+    let mut handle = PagedWriter::new(stdout.lock(), None);
+    handle.write_all(&encoder.encode(&notice))?;
+    handle.finish()?;
     Ok(())
 }
 
+// ============================================================================
+// Output File
+// ============================================================================
+
+/// An output file that is replaced atomically or appended to.
+///
+/// By default content is written to a temporary file next to the target,
+/// which replaces the target on [`OutputFile::commit`]. A failed or
+/// interrupted run therefore leaves the previous file intact, and the
+/// temporary file is removed when an uncommitted `OutputFile` is dropped.
+/// Each run gets a temporary file of its own, so concurrent runs writing the
+/// same target do not interfere; with `--no-clobber` the commit fails
+/// instead of replacing a target that appeared during the run.
+/// In append mode content goes straight to the end of the target, and a
+/// `--checkpoint` run writes the target in place (see [`OutputFile::resume`]).
+///
+/// With `--compress` the encoded text is gzipped. Appending adds another
+/// gzip member, which `gzip -d` and 7-Zip read as one continuous text.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use treepp::config::Config;
+/// use treepp::output::OutputFile;
+///
+/// let config = Config::with_root(PathBuf::from("."));
+/// let mut file = OutputFile::create(&PathBuf::from("tree.txt"), &config).unwrap();
+/// file.write("D:.\n").unwrap();
+/// file.commit().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct OutputFile {
+    path: PathBuf,
+    temp_path: Option<PathBuf>,
+    no_clobber: bool,
+    writer: Option<FileWriter>,
+    encoder: TextEncoder,
+}
+
+/// The byte sink of an `OutputFile`, gzipped or not.
+#[derive(Debug)]
+enum FileWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl FileWriter {
+    /// Wraps an opened file.
+    fn new(file: File, compress: bool) -> Self {
+        let file = BufWriter::new(file);
+        if compress {
+            Self::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            Self::Plain(file)
+        }
+    }
+
+    /// Returns the underlying file.
+    fn file(&self) -> &File {
+        match self {
+            Self::Plain(file) => file.get_ref(),
+            Self::Gzip(encoder) => encoder.get_ref().get_ref(),
+        }
+    }
+
+    /// Writes the gzip trailer, if any, and flushes the file.
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Plain(mut file) => file.flush(),
+            Self::Gzip(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for FileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(file) => file.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(file) => file.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+impl OutputFile {
+    /// Opens the output file according to the configured write mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The destination file path.
+    /// * `config` - Configuration holding the encoding and the
+    ///   `--append`, `--no-clobber` and `--compress` choices.
+    ///
+    /// # Returns
+    ///
+    /// The opened `OutputFile`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputError::FileExists` if `--no-clobber` is set and the
+    /// file exists, or `OutputError::FileCreateFailed` if the file cannot be
+    /// created.
+    pub fn create(path: &Path, config: &Config) -> Result<Self, OutputError> {
+        Self::open(
+            path,
+            TextEncoder::for_file(config),
+            config.output.append,
+            config.output.no_clobber,
+            config.output.compress,
+        )
+    }
+
+    /// Opens the output file of a `--checkpoint` run.
+    ///
+    /// The target is written in place instead of through a temporary file,
+    /// so that an interrupted run leaves what it wrote. The file is cut back
+    /// to `len` bytes, the length recorded by the last checkpoint, and
+    /// continued from there; a `len` of 0 starts it over.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The destination file path.
+    /// * `config` - Configuration holding the encoding and `--no-clobber`.
+    /// * `len` - Length of the content to keep.
+    ///
+    /// # Returns
+    ///
+    /// The opened `OutputFile`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputError::FileExists` if `--no-clobber` is set, the file
+    /// exists and nothing is resumed, or `OutputError::FileCreateFailed` if
+    /// the file cannot be opened.
+    pub fn resume(path: &Path, config: &Config, len: u64) -> Result<Self, OutputError> {
+        if len == 0 && config.output.no_clobber && path.exists() {
+            return Err(OutputError::FileExists {
+                path: path.to_path_buf(),
+            });
+        }
+
+        let create_failed = |e| OutputError::FileCreateFailed {
+            path: path.to_path_buf(),
+            source: e,
+        };
+
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+            && !parent.exists()
+        {
+            fs::create_dir_all(parent).map_err(create_failed)?;
+        }
+
+        // A fresh file is created exclusively, so `--no-clobber` also holds
+        // against a file created since the check above.
+        let mut file = OpenOptions::new()
+            .create(true)
+            .create_new(len == 0 && config.output.no_clobber)
+            .write(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|e| match e.kind() {
+                ErrorKind::AlreadyExists => OutputError::FileExists {
+                    path: path.to_path_buf(),
+                },
+                _ => create_failed(e),
+            })?;
+        file.set_len(len)
+            .and_then(|()| file.seek(SeekFrom::End(0)))
+            .map_err(create_failed)?;
+
+        // The byte order mark was written by the run being resumed.
+        let encoder = TextEncoder::for_file(config);
+        let encoder = if len == 0 {
+            encoder
+        } else {
+            TextEncoder::continuation(encoder.encoding(), config)
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            temp_path: None,
+            no_clobber: false,
+            writer: Some(FileWriter::new(file, false)),
+            encoder,
+        })
+    }
+
+    /// Opens a file for writing.
+    fn open(
+        path: &Path,
+        encoder: TextEncoder,
+        append: bool,
+        no_clobber: bool,
+        compress: bool,
+    ) -> Result<Self, OutputError> {
+        if no_clobber && path.exists() {
+            return Err(OutputError::FileExists {
+                path: path.to_path_buf(),
+            });
+        }
+
+        let create_failed = |e| OutputError::FileCreateFailed {
+            path: path.to_path_buf(),
+            source: e,
+        };
+
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+            && !parent.exists()
+        {
+            fs::create_dir_all(parent).map_err(create_failed)?;
+        }
+
+        if append {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(create_failed)?;
+            // Only a new or empty file starts with a byte order mark.
+            let is_empty = file.metadata().map_or(true, |m| m.len() == 0);
+            let encoder = if is_empty {
+                encoder
+            } else {
+                TextEncoder::new(encoder.encoding(), false)
+                    .trim_trailing(encoder.trim_trailing)
+                    .line_ending(encoder.line_ending)
+            };
+            return Ok(Self {
+                path: path.to_path_buf(),
+                temp_path: None,
+                no_clobber: false,
+                writer: Some(FileWriter::new(file, compress)),
+                encoder,
+            });
+        }
+
+        let (temp_path, file) = create_temp_file(path).map_err(create_failed)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            temp_path: Some(temp_path),
+            no_clobber,
+            writer: Some(FileWriter::new(file, compress)),
+            encoder,
+        })
+    }
+
+    /// Writes text to the file in its encoding.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content to write.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputError::WriteFailed` if writing fails.
+    pub fn write(&mut self, content: &str) -> Result<(), OutputError> {
+        let bytes = self.encoder.encode(content);
+        if let Some(writer) = self.writer.as_mut() {
+            writer
+                .write_all(&bytes)
+                .map_err(|e| OutputError::WriteFailed {
+                    path: self.path.clone(),
+                    source: e,
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Flushes buffered content to the file.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputError::WriteFailed` if flushing fails.
+    pub fn flush(&mut self) -> Result<(), OutputError> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush().map_err(|e| OutputError::WriteFailed {
+                path: self.path.clone(),
+                source: e,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Flushes buffered content and returns the length of the file.
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes written to the file so far.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputError::WriteFailed` if flushing or reading the length
+    /// fails.
+    pub fn flushed_len(&mut self) -> Result<u64, OutputError> {
+        self.flush()?;
+        let len = self
+            .writer
+            .as_ref()
+            .map_or(Ok(0), |writer| writer.file().metadata().map(|m| m.len()));
+        len.map_err(|e| OutputError::WriteFailed {
+            path: self.path.clone(),
+            source: e,
+        })
+    }
+
+    /// Finishes the file, moving the temporary file over the target.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputError::FileExists` if `--no-clobber` is set and the
+    /// target was created during the run, or `OutputError::WriteFailed` if
+    /// flushing or replacing the target fails.
+    pub fn commit(mut self) -> Result<(), OutputError> {
+        // Windows cannot rename a file that is still open.
+        if let Some(writer) = self.writer.take() {
+            writer.finish().map_err(|e| OutputError::WriteFailed {
+                path: self.path.clone(),
+                source: e,
+            })?;
+        }
+
+        if let Some(temp_path) = self.temp_path.take() {
+            let renamed = if self.no_clobber {
+                winvol::rename_no_replace(&temp_path, &self.path)
+            } else {
+                fs::rename(&temp_path, &self.path)
+            };
+            renamed.map_err(|e| {
+                let _ = fs::remove_file(&temp_path);
+                if e.kind() == ErrorKind::AlreadyExists {
+                    OutputError::FileExists {
+                        path: self.path.clone(),
+                    }
+                } else {
+                    OutputError::WriteFailed {
+                        path: self.path.clone(),
+                        source: e,
+                    }
+                }
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates the temporary file an `OutputFile` writes before the commit.
+///
+/// The file is named `<target>.<pid>-<n>.tmp` and created exclusively;
+/// a name left behind by an earlier run is skipped.
+///
+/// # Returns
+///
+/// The path of the temporary file and the file, opened for writing.
+///
+/// # Errors
+///
+/// Returns the I/O error if the file cannot be created.
+fn create_temp_file(path: &Path) -> io::Result<(PathBuf, File)> {
+    static NEXT_TEMP: AtomicU32 = AtomicU32::new(0);
+
+    loop {
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(format!(
+            ".{}-{}.tmp",
+            process::id(),
+            NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = PathBuf::from(temp_path);
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+impl Drop for OutputFile {
+    fn drop(&mut self) {
+        drop(self.writer.take());
+        if let Some(ref temp_path) = self.temp_path {
+            let _ = fs::remove_file(temp_path);
+        }
+    }
+}
+
+/// Checks that `--no-clobber` allows writing the output file.
+///
+/// Lets batch mode fail before scanning instead of after it.
+///
+/// # Arguments
+///
+/// * `config` - Configuration holding the output path and `--no-clobber`.
+///
+/// # Returns
+///
+/// `Ok(())` if the output may be written.
+///
+/// # Errors
+///
+/// Returns `OutputError::FileExists` if the output file exists and
+/// `--no-clobber` is set.
+pub fn check_no_clobber(config: &Config) -> Result<(), OutputError> {
+    match config.output.output_path {
+        Some(ref path) if config.output.no_clobber && path.exists() => {
+            Err(OutputError::FileExists { path: path.clone() })
+        }
+        _ => Ok(()),
+    }
+}
+
 // ============================================================================
 // Unified Output Interface
 // ============================================================================
@@ -762,7 +2141,7 @@ pub fn print_file_notice(path: &Path, config: &Config) -> Result<(), OutputError
 /// # Arguments
 ///
 /// * `render_result` - The render result (used for TXT format).
-/// * `tree` - The tree node (used for structured formats).
+/// * `tree` - The scanned tree (used for structured formats).
 /// * `config` - The complete configuration.
 ///
 /// # Returns
@@ -789,20 +2168,22 @@ pub fn print_file_notice(path: &Path, config: &Config) -> Result<(), OutputError
 /// ```
 pub fn execute_output(
     render_result: &RenderResult,
-    tree: &TreeNode,
+    tree: &TreeArena,
     config: &Config,
 ) -> Result<(), OutputError> {
     let content = match config.output.format {
         OutputFormat::Txt => render_result.content.clone(),
-        OutputFormat::Json => serialize_json(tree, config)?,
-        OutputFormat::Yaml => serialize_yaml(tree, config)?,
-        OutputFormat::Toml => serialize_toml(tree, config)?,
+        OutputFormat::Json => tree_to_json(tree.root(), config)?,
+        OutputFormat::Yaml => tree_to_yaml(tree.root(), config)?,
+        OutputFormat::Toml => tree_to_toml(tree.root(), config)?,
     };
 
     write_stdout(&content, config)?;
 
     if let Some(ref output_path) = config.output.output_path {
-        write_file(&content, output_path)?;
+        let mut file = OutputFile::create(output_path, config)?;
+        file.write(&content)?;
+        file.commit()?;
         print_file_notice(output_path, config)?;
     }
 
@@ -816,7 +2197,7 @@ pub fn execute_output(
 /// # Arguments
 ///
 /// * `render_result` - The render result (used for TXT format).
-/// * `tree` - The tree node (used for structured formats).
+/// * `tree` - The scanned tree (used for structured formats).
 /// * `config` - The complete configuration.
 /// * `path` - The output file path.
 ///
@@ -844,18 +2225,20 @@ pub fn execute_output(
 /// ```
 pub fn write_to_file_only(
     render_result: &RenderResult,
-    tree: &TreeNode,
+    tree: &TreeArena,
     config: &Config,
     path: &Path,
 ) -> Result<(), OutputError> {
     let content = match config.output.format {
         OutputFormat::Txt => render_result.content.clone(),
-        OutputFormat::Json => serialize_json(tree, config)?,
-        OutputFormat::Yaml => serialize_yaml(tree, config)?,
-        OutputFormat::Toml => serialize_toml(tree, config)?,
+        OutputFormat::Json => tree_to_json(tree.root(), config)?,
+        OutputFormat::Yaml => tree_to_yaml(tree.root(), config)?,
+        OutputFormat::Toml => tree_to_toml(tree.root(), config)?,
     };
 
-    write_file(&content, path)
+    let mut file = OutputFile::create(path, config)?;
+    file.write(&content)?;
+    file.commit()
 }
 
 // ============================================================================
@@ -955,7 +2338,9 @@ pub fn validate_output_path(path: &Path) -> Result<(), OutputError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::redact::Redactor;
     use crate::scan::EntryMetadata;
+    use std::io::Read;
     use std::path::PathBuf;
     use std::time::SystemTime;
     use tempfile::tempdir;
@@ -1088,6 +2473,26 @@ mod tests {
         assert!(json.contains("\"subdir\""));
     }
 
+    #[test]
+    fn should_redact_names_in_json() {
+        let tree = create_deep_tree();
+        let mut redactor = Redactor::default();
+        redactor.add_term("level2");
+        redactor.add_term("deep_");
+        let mut config = Config::default();
+        config.scan.show_files = true;
+        config.render.redaction = Some(redactor);
+
+        let json = serialize_json(&tree, &config).expect("JSON序列化应成功");
+
+        assert!(json.contains("level1"));
+        assert!(
+            !json.contains("level2") && !json.contains("deep_file"),
+            "实际：{json}"
+        );
+        assert!(json.contains("file.txt"));
+    }
+
     #[test]
     fn should_serialize_json_for_empty_tree() {
         let tree = create_empty_tree();
@@ -1115,8 +2520,10 @@ mod tests {
     #[test]
     fn should_serialize_json_with_file_size_when_enabled() {
         let tree = create_test_tree();
-        let mut config = Config::default();
-        config.batch_mode = true;
+        let mut config = Config {
+            batch_mode: true,
+            ..Default::default()
+        };
         config.scan.show_files = true;
         config.render.show_size = true;
 
@@ -1131,18 +2538,110 @@ mod tests {
         let mut tree = create_test_tree();
         tree.compute_disk_usage();
 
+        let mut config = Config {
+            batch_mode: true,
+            ..Default::default()
+        };
+        config.scan.show_files = true;
+        config.render.show_disk_usage = true;
+
+        let json = serialize_json(&tree, &config).expect("JSON序列化应成功");
+
+        assert!(json.contains("\"disk_usage\""));
+    }
+
+    #[test]
+    fn should_serialize_percent_of_parent_when_enabled() {
+        let mut tree = create_test_tree();
+        tree.compute_disk_usage();
+
+        let mut config = Config::with_root(PathBuf::from("test_root"));
+        config.batch_mode = true;
+        config.render.show_disk_usage = true;
+
+        let json = serialize_json(&tree, &config).expect("JSON序列化应成功");
+        assert!(!json.contains("percent_of_parent"));
+
+        config.render.show_du_percent = true;
+        let json = serialize_json(&tree, &config).expect("JSON序列化应成功");
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["root"]["dirs"]["subdir"]["percent_of_parent"], 66.7);
+
+        let toml = serialize_toml(&tree, &config).expect("TOML序列化应成功");
+        assert!(toml.contains("percent_of_parent = 66.7"));
+    }
+
+    #[test]
+    fn should_serialize_link_target_for_links() {
+        let link = || {
+            TreeNode::new(
+                PathBuf::from("link"),
+                EntryKind::File,
+                EntryMetadata {
+                    link_target: Some(PathBuf::from(r"C:\target")),
+                    ..Default::default()
+                },
+            )
+        };
+        let mut tree = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let mut subdir = TreeNode::new(
+            PathBuf::from("root/sub"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        subdir.children.push(link());
+        tree.children.push(link());
+        tree.children.push(subdir);
+
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.batch_mode = true;
+        config.scan.show_files = true;
+
+        let json = serialize_json(&tree, &config).expect("JSON序列化应成功");
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["root"]["files"][0]["name"], "link");
+        assert_eq!(value["root"]["files"][0]["link_target"], r"C:\target");
+        assert_eq!(
+            value["root"]["dirs"]["sub"]["files"][0]["link_target"],
+            r"C:\target"
+        );
+
+        let toml = serialize_toml(&tree, &config).expect("TOML序列化应成功");
+        assert!(toml.contains("\"link\""), "TOML 应只保留名称: {toml}");
+    }
+
+    #[test]
+    fn should_serialize_json_with_modified_date_when_enabled() {
+        let mut tree = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        tree.children.push(TreeNode::new(
+            PathBuf::from("root/file.txt"),
+            EntryKind::File,
+            EntryMetadata {
+                size: 100,
+                modified: Some(SystemTime::now()),
+                ..Default::default()
+            },
+        ));
+
         let mut config = Config::default();
-        config.batch_mode = true;
         config.scan.show_files = true;
-        config.render.show_disk_usage = true;
+        config.render.show_date = true;
 
         let json = serialize_json(&tree, &config).expect("JSON序列化应成功");
 
-        assert!(json.contains("\"disk_usage\""));
+        assert!(json.contains("\"modified\""));
     }
 
     #[test]
-    fn should_serialize_json_with_modified_date_when_enabled() {
+    fn should_serialize_json_with_created_and_accessed_dates_when_enabled() {
         let mut tree = TreeNode::new(
             PathBuf::from("root"),
             EntryKind::Directory,
@@ -1152,19 +2651,22 @@ mod tests {
             PathBuf::from("root/file.txt"),
             EntryKind::File,
             EntryMetadata {
-                size: 100,
-                modified: Some(SystemTime::now()),
+                created: Some(SystemTime::now()),
+                accessed: Some(SystemTime::now()),
                 ..Default::default()
             },
         ));
 
         let mut config = Config::default();
         config.scan.show_files = true;
-        config.render.show_date = true;
+        config.render.show_created = true;
+        config.render.show_accessed = true;
 
         let json = serialize_json(&tree, &config).expect("JSON序列化应成功");
 
-        assert!(json.contains("\"modified\""));
+        assert!(json.contains("\"created\""));
+        assert!(json.contains("\"accessed\""));
+        assert!(!json.contains("\"modified\""));
     }
 
     // ========================================================================
@@ -1412,6 +2914,282 @@ mod tests {
         assert_eq!(content, "你好世界 🌍 émoji");
     }
 
+    #[test]
+    fn should_write_utf16le_file_with_bom() {
+        let dir = tempdir().expect("创建临时目录失败");
+        let file_path = dir.path().join("utf16.txt");
+
+        let encoder = TextEncoder::new(OutputEncoding::Utf16Le, true);
+        write_encoded_file("树 a", &file_path, encoder).expect("写入应成功");
+
+        let bytes = fs::read(&file_path).expect("读取文件失败");
+        assert_eq!(bytes, [0xFF, 0xFE, 0x11, 0x68, 0x20, 0x00, b'a', 0x00]);
+    }
+
+    #[test]
+    fn should_trim_trailing_whitespace_across_chunks() {
+        let mut encoder = TextEncoder::new(OutputEncoding::Utf8, false).trim_trailing(true);
+        let mut out = Vec::new();
+        for chunk in ["|   a.txt  \n|   ", "\n+---src", " ", "dir\n", "   "] {
+            out.extend_from_slice(&encoder.encode(chunk));
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "|   a.txt\n|\n+---src dir\n"
+        );
+
+        let mut untouched = TextEncoder::new(OutputEncoding::Utf8, false);
+        assert_eq!(
+            untouched.encode("|   \n").as_ref(),
+            b"|   \n",
+            "默认不应去除行尾空白"
+        );
+    }
+
+    #[test]
+    fn should_write_crlf_line_endings_in_every_encoding() {
+        let mut utf8 = TextEncoder::new(OutputEncoding::Utf8, false).line_ending(LineEnding::Crlf);
+        assert_eq!(utf8.encode("a\nb").as_ref(), b"a\r\nb");
+
+        let mut utf16 =
+            TextEncoder::new(OutputEncoding::Utf16Le, false).line_ending(LineEnding::Crlf);
+        assert_eq!(utf16.encode("\n").as_ref(), &[b'\r', 0, b'\n', 0]);
+
+        let mut trimmed = TextEncoder::new(OutputEncoding::Gbk, false)
+            .trim_trailing(true)
+            .line_ending(LineEnding::Crlf);
+        assert_eq!(
+            trimmed.encode("|   \n").as_ref(),
+            b"|\r\n",
+            "应先去除行尾空白再转换换行"
+        );
+    }
+
+    #[test]
+    fn should_write_crlf_output_file() {
+        let dir = tempdir().expect("创建临时目录失败");
+        let file_path = dir.path().join("tree.txt");
+        let mut config = Config::default();
+        config.output.line_ending = LineEnding::Crlf;
+
+        let mut file = OutputFile::create(&file_path, &config).expect("创建文件失败");
+        file.write("root\n").unwrap();
+        file.write("└─src\n").unwrap();
+        file.commit().unwrap();
+
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "root\r\n└─src\r\n");
+    }
+
+    #[test]
+    fn should_replace_file_without_leaving_temp_file() {
+        let dir = tempdir().expect("创建临时目录失败");
+        let file_path = dir.path().join("tree.txt");
+        fs::write(&file_path, "old").expect("写入文件失败");
+
+        write_file("new", &file_path).expect("覆盖写入应成功");
+
+        assert_eq!(fs::read_to_string(&file_path).expect("读取文件失败"), "new");
+        assert_eq!(fs::read_dir(dir.path()).expect("读取目录失败").count(), 1);
+    }
+
+    #[test]
+    fn should_keep_previous_file_when_not_committed() {
+        let dir = tempdir().expect("创建临时目录失败");
+        let file_path = dir.path().join("tree.txt");
+        fs::write(&file_path, "old").expect("写入文件失败");
+
+        let encoder = TextEncoder::new(OutputEncoding::Utf8, false);
+        let mut file =
+            OutputFile::open(&file_path, encoder, false, false, false).expect("打开应成功");
+        file.write("partial").expect("写入应成功");
+        drop(file);
+
+        assert_eq!(fs::read_to_string(&file_path).expect("读取文件失败"), "old");
+        assert_eq!(fs::read_dir(dir.path()).expect("读取目录失败").count(), 1);
+    }
+
+    #[test]
+    fn should_give_each_open_file_its_own_temp_file() {
+        let dir = tempdir().expect("创建临时目录失败");
+        let file_path = dir.path().join("tree.txt");
+        let open = || {
+            let encoder = TextEncoder::new(OutputEncoding::Utf8, false);
+            OutputFile::open(&file_path, encoder, false, false, false).expect("打开应成功")
+        };
+
+        let (mut first, mut second) = (open(), open());
+        first.write("first").expect("写入应成功");
+        second.write("second").expect("写入应成功");
+        first.commit().expect("提交应成功");
+        second.commit().expect("提交应成功");
+
+        assert_eq!(
+            fs::read_to_string(&file_path).expect("读取文件失败"),
+            "second"
+        );
+        assert_eq!(fs::read_dir(dir.path()).expect("读取目录失败").count(), 1);
+    }
+
+    #[test]
+    fn should_not_replace_file_created_during_no_clobber_run() {
+        let dir = tempdir().expect("创建临时目录失败");
+        let file_path = dir.path().join("tree.txt");
+
+        let encoder = TextEncoder::new(OutputEncoding::Utf8, false);
+        let mut file =
+            OutputFile::open(&file_path, encoder, false, true, false).expect("打开应成功");
+        file.write("new").expect("写入应成功");
+        fs::write(&file_path, "other").expect("写入文件失败");
+
+        let result = file.commit();
+        assert!(matches!(result, Err(OutputError::FileExists { .. })));
+        assert_eq!(
+            fs::read_to_string(&file_path).expect("读取文件失败"),
+            "other"
+        );
+        assert_eq!(fs::read_dir(dir.path()).expect("读取目录失败").count(), 1);
+    }
+
+    #[test]
+    fn should_append_to_existing_file_without_bom() {
+        let dir = tempdir().expect("创建临时目录失败");
+        let file_path = dir.path().join("tree.txt");
+        fs::write(&file_path, "first\n").expect("写入文件失败");
+
+        let encoder = TextEncoder::new(OutputEncoding::Utf8, true);
+        let mut file =
+            OutputFile::open(&file_path, encoder, true, false, false).expect("打开应成功");
+        file.write("second\n").expect("写入应成功");
+        file.commit().expect("提交应成功");
+
+        let content = fs::read_to_string(&file_path).expect("读取文件失败");
+        assert_eq!(content, "first\nsecond\n");
+    }
+
+    #[test]
+    fn should_resume_file_from_checkpoint_length() {
+        let dir = tempdir().expect("创建临时目录失败");
+        let file_path = dir.path().join("tree.txt");
+        let mut config = Config::with_root(PathBuf::from("."));
+        config.output.bom = true;
+
+        let mut file = OutputFile::resume(&file_path, &config, 0).expect("打开应成功");
+        file.write("D:.\n├─a\n").expect("写入应成功");
+        let len = file.flushed_len().expect("获取长度应成功");
+        file.write("│  └─par").expect("写入应成功");
+        drop(file);
+        assert!(file_path.exists(), "未提交时应保留已写入的内容");
+
+        let mut file = OutputFile::resume(&file_path, &config, len).expect("恢复应成功");
+        file.write("└─b\n").expect("写入应成功");
+        file.commit().expect("提交应成功");
+
+        let content = fs::read_to_string(&file_path).expect("读取文件失败");
+        assert_eq!(
+            content, "\u{feff}D:.\n├─a\n└─b\n",
+            "应截断到检查点并且只写一次 BOM"
+        );
+
+        config.output.no_clobber = true;
+        assert!(OutputFile::resume(&file_path, &config, len).is_ok());
+        let result = OutputFile::resume(&file_path, &config, 0);
+        assert!(matches!(result, Err(OutputError::FileExists { .. })));
+    }
+
+    /// Reads all gzip members of a file as UTF-8 text.
+    fn read_gzip(path: &Path) -> String {
+        let mut content = String::new();
+        flate2::read::MultiGzDecoder::new(File::open(path).expect("打开文件失败"))
+            .read_to_string(&mut content)
+            .expect("解压失败");
+        content
+    }
+
+    #[test]
+    fn should_gzip_compressed_file() {
+        let dir = tempdir().expect("创建临时目录失败");
+        let file_path = dir.path().join("tree.txt.gz");
+
+        let encoder = TextEncoder::new(OutputEncoding::Utf8, false);
+        let mut file =
+            OutputFile::open(&file_path, encoder, false, false, true).expect("打开应成功");
+        file.write(&"├─src\n".repeat(1000)).expect("写入应成功");
+        file.commit().expect("提交应成功");
+
+        let bytes = fs::read(&file_path).expect("读取文件失败");
+        assert_eq!(&bytes[..2], [0x1f, 0x8b], "应以 gzip 魔数开头");
+        assert!(bytes.len() < 1000, "重复内容应被压缩");
+        assert_eq!(read_gzip(&file_path), "├─src\n".repeat(1000));
+    }
+
+    #[test]
+    fn should_append_gzip_member() {
+        let dir = tempdir().expect("创建临时目录失败");
+        let file_path = dir.path().join("tree.txt.gz");
+
+        for line in ["first\n", "second\n"] {
+            let encoder = TextEncoder::new(OutputEncoding::Utf8, false);
+            let mut file =
+                OutputFile::open(&file_path, encoder, true, false, true).expect("打开应成功");
+            file.write(line).expect("写入应成功");
+            file.commit().expect("提交应成功");
+        }
+
+        assert_eq!(
+            read_gzip(&file_path),
+            "first\nsecond\n",
+            "追加应读作连续文本"
+        );
+    }
+
+    #[test]
+    fn should_refuse_existing_file_with_no_clobber() {
+        let dir = tempdir().expect("创建临时目录失败");
+        let file_path = dir.path().join("tree.txt");
+        fs::write(&file_path, "old").expect("写入文件失败");
+
+        let encoder = TextEncoder::new(OutputEncoding::Utf8, false);
+        let result = OutputFile::open(&file_path, encoder, false, true, false);
+        assert!(matches!(result, Err(OutputError::FileExists { .. })));
+
+        let mut config = Config::with_root(PathBuf::from("."));
+        config.output.output_path = Some(file_path);
+        config.output.no_clobber = true;
+        assert!(check_no_clobber(&config).is_err());
+    }
+
+    // ========================================================================
+    // Text Encoding Tests
+    // ========================================================================
+
+    #[test]
+    fn should_encode_utf8_without_copy() {
+        let mut encoder = TextEncoder::new(OutputEncoding::Utf8, false);
+        assert!(matches!(encoder.encode("├─src"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn should_write_utf8_bom_only_once() {
+        let mut encoder = TextEncoder::new(OutputEncoding::Utf8, true);
+        assert_eq!(encoder.encode("a").as_ref(), b"\xEF\xBB\xBFa");
+        assert_eq!(encoder.encode("b").as_ref(), b"b");
+    }
+
+    #[test]
+    fn should_encode_gbk_tree_characters() {
+        let mut encoder = TextEncoder::new(OutputEncoding::Gbk, false);
+        let bytes = encoder.encode("└─文件");
+        let (decoded, _, had_errors) = encoding_rs::GBK.decode(&bytes);
+        assert!(!had_errors);
+        assert_eq!(decoded, "└─文件");
+    }
+
+    #[test]
+    fn should_replace_unmappable_gbk_characters() {
+        let mut encoder = TextEncoder::new(OutputEncoding::Gbk, true);
+        assert_eq!(encoder.encode("a🌍b").as_ref(), b"a?b");
+    }
+
     // ========================================================================
     // Path Validation Tests
     // ========================================================================
@@ -1482,6 +3260,20 @@ mod tests {
         assert_eq!(formatted, "relative/path");
     }
 
+    #[test]
+    fn should_format_root_path_with_verbatim_drive() {
+        let path = PathBuf::from(r"\\?\E:\deep\tree");
+        let formatted = format_root_path(&path);
+        assert_eq!(formatted, "E:.");
+    }
+
+    #[test]
+    fn should_format_root_path_with_unc_share() {
+        let path = PathBuf::from(r"\\?\UNC\server\share\dir");
+        let formatted = format_root_path(&path);
+        assert_eq!(formatted, r"\\server\share\dir");
+    }
+
     // ========================================================================
     // DirNode Tests
     // ========================================================================
@@ -1503,10 +3295,14 @@ mod tests {
         let mut config = Config::default();
         config.scan.show_files = true;
 
-        let dir_node = tree_to_dir_node(&tree, &config);
+        let dir_node = tree_to_dir_node(TreeArena::from_tree(&tree).root(), &config);
 
         assert_eq!(dir_node.node_type, "dir");
-        assert!(dir_node.files.contains(&"file1.txt".to_string()));
+        assert!(
+            dir_node
+                .files
+                .contains(&Value::String("file1.txt".to_string()))
+        );
         assert!(dir_node.dirs.contains_key("subdir"));
     }
 
@@ -1515,12 +3311,149 @@ mod tests {
         let tree = create_test_tree();
         let config = Config::default();
 
-        let dir_node = tree_to_dir_node(&tree, &config);
+        let dir_node = tree_to_dir_node(TreeArena::from_tree(&tree).root(), &config);
 
         assert!(dir_node.files.is_empty());
         assert!(dir_node.dirs.contains_key("subdir"));
     }
 
+    #[test]
+    fn should_report_error_of_unreadable_directory() {
+        let mut tree = create_test_tree();
+        tree.children
+            .iter_mut()
+            .find(|c| c.name == "subdir")
+            .unwrap()
+            .error = Some(crate::scan::EntryError::AccessDenied);
+        let config = Config::default();
+
+        let dir_node = tree_to_dir_node(TreeArena::from_tree(&tree).root(), &config);
+        assert_eq!(
+            dir_node.dirs["subdir"].error.as_deref(),
+            Some("access denied")
+        );
+        assert!(dir_node.error.is_none());
+
+        let json = serialize_json(&tree, &config).unwrap();
+        assert!(
+            json.contains("\"error\": \"access denied\""),
+            "JSON 应包含 error 字段"
+        );
+        assert_eq!(
+            json.matches("\"error\"").count(),
+            1,
+            "可读目录不应输出 error 字段"
+        );
+        let toml = serialize_toml(&tree, &config).unwrap();
+        assert!(toml.contains("error = \"access denied\""));
+    }
+
+    #[test]
+    fn should_mark_output_truncated_when_budget_ran_out() {
+        let mut tree = create_test_tree();
+        let config = Config::default();
+        let json = serialize_json(&tree, &config).unwrap();
+        assert!(
+            !json.contains("\"truncated\""),
+            "完整的树不应输出 truncated 字段"
+        );
+
+        tree.children
+            .iter_mut()
+            .find(|c| c.name == "subdir")
+            .unwrap()
+            .error = Some(crate::scan::EntryError::BudgetExhausted);
+        let json = serialize_json(&tree, &config).unwrap();
+        assert!(json.contains("\"truncated\": true"), "{json}");
+        assert!(json.contains("\"error\": \"budget exhausted\""));
+        let toml = serialize_toml(&tree, &config).unwrap();
+        assert!(toml.contains("truncated = true"), "{toml}");
+    }
+
+    #[test]
+    fn should_write_format_version_to_json_and_yaml() {
+        let tree = create_test_tree();
+        let config = Config::default();
+        let json = serialize_json(&tree, &config).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["format_version"], FORMAT_VERSION);
+        let yaml = serialize_yaml(&tree, &config).unwrap();
+        assert!(
+            yaml.contains(&format!("format_version: '{FORMAT_VERSION}'")),
+            "{yaml}"
+        );
+    }
+
+    #[test]
+    fn published_schema_should_match_generated_schema() {
+        let schema = structured_schema().unwrap();
+        let published = include_str!("../schema/treepp.pretty.v1.schema.json");
+        assert_eq!(
+            published.replace("\r\n", "\n").trim_end(),
+            schema,
+            "schema/treepp.pretty.v1.schema.json 需要用 --print-schema 重新生成"
+        );
+    }
+
+    #[test]
+    fn schema_should_describe_output_fields() {
+        let schema: Value = serde_json::from_str(&structured_schema().unwrap()).unwrap();
+        let required = schema["required"].as_array().unwrap();
+        for field in ["schema", "format_version", "root"] {
+            assert!(required.iter().any(|f| f == field), "{field} 应为必需字段");
+        }
+        assert!(
+            !required.iter().any(|f| f == "truncated"),
+            "truncated 为可选字段"
+        );
+        let file_value = &schema["$defs"]["FileValue"]["anyOf"];
+        assert_eq!(file_value[0]["type"], "string", "文件可以是单纯的名称");
+        assert!(schema["$defs"]["FileEntry"]["properties"]["link_target"].is_object());
+    }
+
+    #[test]
+    fn node_id_should_stay_stable() {
+        assert_eq!(node_id(""), "cbf29ce484222325");
+        assert_eq!(node_id("subdir"), "8e201589a06819e4");
+        assert_eq!(node_id("subdir/file2.txt"), "d9a0932613064a99");
+        assert_eq!(
+            node_id("src/main.rs"),
+            "df9f8d2d8eb12e9c",
+            "标识方案不得随版本改变"
+        );
+    }
+
+    #[test]
+    fn should_add_node_ids_when_enabled() {
+        let tree = create_test_tree();
+        let mut config = Config::default();
+        config.scan.show_files = true;
+        let json = serialize_json(&tree, &config).unwrap();
+        assert!(!json.contains("\"id\""), "默认不应输出 id 字段");
+
+        config.output.node_ids = true;
+        let json = serialize_json(&tree, &config).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["root"]["id"], "cbf29ce484222325");
+        assert_eq!(value["root"]["files"][0]["name"], "file1.txt");
+        assert_eq!(value["root"]["files"][0]["id"], "9bb8d62867af9e90");
+        assert_eq!(value["root"]["files"][0]["parent_id"], "cbf29ce484222325");
+        let subdir = &value["root"]["dirs"]["subdir"];
+        assert_eq!(subdir["id"], "8e201589a06819e4");
+        assert_eq!(subdir["parent_id"], "cbf29ce484222325");
+        assert_eq!(subdir["files"][0]["id"], "d9a0932613064a99");
+        assert_eq!(subdir["files"][0]["parent_id"], "8e201589a06819e4");
+
+        config.render.show_size = true;
+        let json = serialize_json(&tree, &config).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["root"]["files"][0]["size"], 1024);
+        assert_eq!(
+            value["root"]["files"][0]["id"], "9bb8d62867af9e90",
+            "详细模式同样输出 id"
+        );
+    }
+
     // ========================================================================
     // StructuredOutput Tests
     // ========================================================================
@@ -1531,7 +3464,7 @@ mod tests {
         let mut config = Config::default();
         config.scan.show_files = true;
 
-        let output = create_structured_output(&tree, &config);
+        let output = create_structured_output(TreeArena::from_tree(&tree).root(), &config);
 
         assert_eq!(output.schema, SCHEMA_VERSION);
         assert_eq!(output.root.node_type, "dir");
@@ -1542,11 +3475,13 @@ mod tests {
         let mut tree = create_test_tree();
         tree.compute_disk_usage();
 
-        let mut config = Config::default();
-        config.batch_mode = true;
+        let mut config = Config {
+            batch_mode: true,
+            ..Default::default()
+        };
         config.render.show_disk_usage = true;
 
-        let output = create_structured_output(&tree, &config);
+        let output = create_structured_output(TreeArena::from_tree(&tree).root(), &config);
 
         assert!(output.root.disk_usage.is_some());
     }
@@ -1748,7 +3683,7 @@ mod tests {
         let mut config = Config::default();
         config.scan.show_files = true;
 
-        let structured = create_structured_output(&tree, &config);
+        let structured = create_structured_output(TreeArena::from_tree(&tree).root(), &config);
         let toml_output = TomlOutput::from_structured(&structured);
 
         assert_eq!(toml_output.schema, SCHEMA_VERSION);
@@ -1776,7 +3711,7 @@ mod tests {
         config.scan.show_files = true;
         config.render.show_size = true;
 
-        let structured = create_structured_output(&root, &config);
+        let structured = create_structured_output(TreeArena::from_tree(&root).root(), &config);
         let toml_output = TomlOutput::from_structured(&structured);
 
         assert!(toml_output.root.files.contains(&"file.txt".to_string()));
@@ -1859,4 +3794,147 @@ mod tests {
         let content = fs::read_to_string(&file_path).expect("读取失败");
         assert!(content.contains("treepp.pretty.v1"));
     }
+
+    #[test]
+    fn should_serialize_levels_when_enabled() {
+        let tree = create_deep_tree();
+        let mut config = Config::default();
+        config.scan.show_files = true;
+
+        let json = serialize_json(&tree, &config).expect("序列化应成功");
+        assert!(!json.contains("levels"));
+
+        config.render.depth_histogram = true;
+        let json = serialize_json(&tree, &config).expect("序列化应成功");
+        let parsed: Value = serde_json::from_str(&json).expect("解析失败");
+        let levels = parsed["levels"].as_array().expect("应包含各层统计");
+        assert_eq!(levels.len(), 3);
+        assert_eq!(levels[0]["directories"], 1);
+        assert_eq!(levels[2]["files"], 1);
+        assert_eq!(levels[2]["size"], 512);
+        assert!(
+            !json.contains("extensions"),
+            "仅启用深度分布时不输出扩展名统计"
+        );
+
+        let toml = serialize_toml(&tree, &config).expect("序列化应成功");
+        assert!(toml.contains("[[levels]]"));
+    }
+
+    #[test]
+    fn should_serialize_extensions_when_enabled() {
+        let tree = create_deep_tree();
+        let mut config = Config::default();
+        config.scan.show_files = true;
+
+        let json = serialize_json(&tree, &config).expect("序列化应成功");
+        assert!(!json.contains("extensions"));
+
+        config.render.report_extensions = true;
+        let json = serialize_json(&tree, &config).expect("序列化应成功");
+        let parsed: Value = serde_json::from_str(&json).expect("解析失败");
+        let extensions = parsed["extensions"].as_array().expect("应包含扩展名统计");
+        assert_eq!(extensions.len(), 1);
+        assert_eq!(extensions[0]["extension"], ".txt");
+        assert_eq!(extensions[0]["size"], 512);
+
+        let toml = serialize_toml(&tree, &config).expect("序列化应成功");
+        assert!(toml.contains("[[extensions]]"));
+    }
+
+    // ========================================================================
+    // Pager Tests
+    // ========================================================================
+
+    #[test]
+    fn should_write_through_when_not_paging() {
+        let mut writer = PagedWriter::new(Vec::new(), None);
+        writer.write_all(b"C:.\n").expect("写入应成功");
+        assert_eq!(writer.get_ref(), b"C:.\n", "未分页时应直接写出");
+    }
+
+    #[test]
+    fn should_hold_short_output_until_finished() {
+        let mut writer = PagedWriter::new(Vec::new(), Some(3));
+        writer.write_all(b"C:.\n").expect("写入应成功");
+        writer
+            .write_all(b"\xE2\x94\x9C\xE2\x94\x80src\n")
+            .expect("写入应成功");
+        writer.flush().expect("刷新应成功");
+        assert!(
+            writer.get_ref().is_empty(),
+            "不足一屏的输出在结束前应被暂存"
+        );
+
+        writer.finish().expect("结束应成功");
+        assert_eq!(writer.get_ref(), "C:.\n├─src\n".as_bytes());
+        assert!(!writer.is_closed());
+    }
+
+    #[test]
+    fn should_write_directly_after_finish() {
+        let mut writer = PagedWriter::new(Vec::new(), Some(10));
+        writer.write_all(b"a\n").expect("写入应成功");
+        writer.finish().expect("结束应成功");
+        writer.write_all(b"b\n").expect("写入应成功");
+        assert_eq!(writer.get_ref(), b"a\nb\n");
+    }
+
+    /// A pipe whose reading end has been closed.
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::from(ErrorKind::BrokenPipe))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::from(ErrorKind::BrokenPipe))
+        }
+    }
+
+    #[test]
+    fn should_discard_output_once_pipe_is_closed() {
+        let mut writer = PagedWriter::new(ClosedPipe, None);
+        writer
+            .write_all(b"C:.\n")
+            .expect("管道关闭不应视为写入错误");
+        writer.flush().expect("管道关闭不应视为刷新错误");
+        assert!(writer.is_closed());
+
+        writer
+            .write_all(b"\xE2\x94\x94\xE2\x94\x80src\n")
+            .expect("关闭后的写入应被丢弃");
+        writer.finish().expect("结束应成功");
+        assert!(writer.is_closed(), "关闭状态在结束后保持");
+    }
+
+    #[test]
+    fn should_discard_held_output_when_pipe_is_closed() {
+        let mut writer = PagedWriter::new(ClosedPipe, Some(24));
+        writer.write_all(b"C:.\n").expect("写入应成功");
+        assert!(!writer.is_closed(), "暂存期间尚未写出");
+
+        writer.finish().expect("管道关闭不应视为错误");
+        assert!(writer.is_closed());
+    }
+
+    #[test]
+    fn should_report_other_write_errors() {
+        struct FullDisk;
+
+        impl Write for FullDisk {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = PagedWriter::new(FullDisk, None);
+        assert!(writer.write_all(b"C:.\n").is_err(), "其他错误应照常返回");
+        assert!(!writer.is_closed());
+    }
 }
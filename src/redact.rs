@@ -0,0 +1,308 @@
+//! Redaction of user names and chosen terms in rendered output (`--redact`).
+//!
+//! Trees are often pasted into issues and chat, where the user profile
+//! directory gives away the account name and directory names may give away
+//! project codenames. With `--redact`, every rendered name, path and root
+//! header is passed through a `Redactor`, which replaces:
+//!
+//! - **The user profile directory** (`%USERPROFILE%`) wherever a path
+//!   starts inside it, with `%USERPROFILE%`
+//! - **The user name** (`%USERNAME%`, and the name of the profile folder)
+//!   where it is a whole path component, with `%USERNAME%`
+//! - **Terms given with `--redact-term`** anywhere in a name or path, with
+//!   `<redacted-xxxxxxxx>`, where `xxxxxxxx` is a hash of the term. The same
+//!   term always gets the same placeholder, so trees shared separately can
+//!   still be compared.
+//!
+//! All matches ignore ASCII case, like Windows paths do.
+//!
+//! File: src/redact.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::borrow::Cow;
+use std::env;
+use std::path::Path;
+
+use serde::Serialize;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Placeholder of the user profile directory.
+const PROFILE_PLACEHOLDER: &str = "%USERPROFILE%";
+
+/// Placeholder of the user name.
+const USER_PLACEHOLDER: &str = "%USERNAME%";
+
+/// FNV-1a offset basis of the term hash.
+const TERM_OFFSET_BASIS: u32 = 0x811c_9dc5;
+
+/// FNV-1a prime of the term hash.
+const TERM_PRIME: u32 = 0x0100_0193;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Replaces the user profile, the user name and chosen terms in text.
+///
+/// Only the terms are part of the configuration shown by `--print-config`;
+/// the profile and user name are read from the environment when the
+/// configuration is validated.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::redact::Redactor;
+///
+/// let mut redactor = Redactor::default().with_profile(Path::new(r"C:\Users\alice"), None);
+/// redactor.add_term("Falcon");
+///
+/// assert_eq!(redactor.apply(r"C:\Users\alice\src"), r"%USERPROFILE%\src");
+/// assert_eq!(redactor.apply(r"D:\Backup\Alice"), r"D:\Backup\%USERNAME%");
+/// assert_eq!(redactor.apply("falcon-api"), "<redacted-c1abeea8>-api");
+/// assert_eq!(redactor.apply("main.rs"), "main.rs");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct Redactor {
+    /// User profile directory, without a trailing separator.
+    #[serde(skip)]
+    profile: Option<String>,
+    /// User names, matched as whole path components.
+    #[serde(skip)]
+    users: Vec<String>,
+    /// Terms given with `--redact-term`, longest first.
+    terms: Vec<String>,
+}
+
+impl Redactor {
+    /// Adds a term to redact.
+    ///
+    /// Empty terms and terms already added (ignoring ASCII case) are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `term` - Text to replace wherever it appears.
+    pub fn add_term(&mut self, term: impl Into<String>) {
+        let term = term.into();
+        if term.is_empty() || self.terms.iter().any(|t| t.eq_ignore_ascii_case(&term)) {
+            return;
+        }
+        let at = self.terms.partition_point(|t| t.len() >= term.len());
+        self.terms.insert(at, term);
+    }
+
+    /// Returns the terms given with `--redact-term`.
+    #[must_use]
+    pub fn terms(&self) -> &[String] {
+        &self.terms
+    }
+
+    /// Sets the user profile directory and user name to redact.
+    ///
+    /// The name of the profile folder is redacted as a user name as well,
+    /// since it usually is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The user profile directory.
+    /// * `user` - The account name, if known.
+    ///
+    /// # Returns
+    ///
+    /// The redactor with the profile applied.
+    #[must_use]
+    pub fn with_profile(mut self, profile: &Path, user: Option<&str>) -> Self {
+        let profile = profile.to_string_lossy();
+        let profile = profile.trim_end_matches(is_separator);
+        if !profile.is_empty() {
+            self.profile = Some(profile.to_string());
+        }
+
+        let folder = profile.rsplit(is_separator).next();
+        for name in [folder, user].into_iter().flatten() {
+            if !name.is_empty() && !self.users.iter().any(|u| u.eq_ignore_ascii_case(name)) {
+                self.users.push(name.to_string());
+            }
+        }
+        self
+    }
+
+    /// Reads the user profile directory and user name from the environment.
+    ///
+    /// Uses `USERPROFILE` and `USERNAME`; a variable that is not set is
+    /// left out.
+    ///
+    /// # Returns
+    ///
+    /// The redactor with the current user's profile applied.
+    #[must_use]
+    pub fn with_current_user(self) -> Self {
+        let user = env::var("USERNAME").ok();
+        match env::var_os("USERPROFILE") {
+            Some(profile) => self.with_profile(Path::new(&profile), user.as_deref()),
+            None if user.is_some() => self.with_profile(Path::new(""), user.as_deref()),
+            None => self,
+        }
+    }
+
+    /// Replaces the profile, user names and terms in a text.
+    ///
+    /// At each position the profile is tried first, then the terms from
+    /// longest to shortest, then the user names, so a user name inside the
+    /// profile path is replaced as part of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - A name, path or header line.
+    ///
+    /// # Returns
+    ///
+    /// The redacted text, borrowed if nothing was replaced.
+    #[must_use]
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut out = String::new();
+        let mut copied = 0;
+        let mut pos = 0;
+        while pos < text.len() {
+            match self.match_at(text, pos) {
+                Some((len, placeholder)) => {
+                    out.push_str(&text[copied..pos]);
+                    out.push_str(&placeholder);
+                    pos += len;
+                    copied = pos;
+                }
+                None => pos += text[pos..].chars().next().map_or(1, char::len_utf8),
+            }
+        }
+
+        if copied == 0 {
+            return Cow::Borrowed(text);
+        }
+        out.push_str(&text[copied..]);
+        Cow::Owned(out)
+    }
+
+    /// Returns the length of the redacted text at `pos` and its placeholder.
+    fn match_at(&self, text: &str, pos: usize) -> Option<(usize, Cow<'static, str>)> {
+        let component_ends = |len: usize| text[pos + len..].starts_with(is_separator);
+        let ends_at = |len: usize| pos + len == text.len() || component_ends(len);
+
+        if let Some(profile) = &self.profile
+            && starts_with_ignore_case(text, pos, profile)
+            && ends_at(profile.len())
+        {
+            return Some((profile.len(), Cow::Borrowed(PROFILE_PLACEHOLDER)));
+        }
+
+        if let Some(term) = self
+            .terms
+            .iter()
+            .find(|t| starts_with_ignore_case(text, pos, t))
+        {
+            return Some((term.len(), Cow::Owned(term_placeholder(term))));
+        }
+
+        let component_starts = pos == 0 || text[..pos].ends_with(is_separator);
+        if component_starts {
+            let user = self
+                .users
+                .iter()
+                .find(|u| starts_with_ignore_case(text, pos, u) && ends_at(u.len()))?;
+            return Some((user.len(), Cow::Borrowed(USER_PLACEHOLDER)));
+        }
+        None
+    }
+}
+
+// ============================================================================
+// Internal Functions
+// ============================================================================
+
+/// Returns whether a character separates path components.
+const fn is_separator(c: char) -> bool {
+    matches!(c, '\\' | '/')
+}
+
+/// Checks whether `text` has `needle` at byte offset `pos`, ignoring ASCII case.
+///
+/// A match always ends on a character boundary, since only ASCII bytes may
+/// differ from `needle`.
+fn starts_with_ignore_case(text: &str, pos: usize, needle: &str) -> bool {
+    text.as_bytes()
+        .get(pos..pos + needle.len())
+        .is_some_and(|bytes| bytes.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Returns the placeholder of a `--redact-term` term.
+///
+/// The placeholder holds the 32-bit FNV-1a hash of the lowercased term, so
+/// it does not depend on the case the term was given or found in.
+fn term_placeholder(term: &str) -> String {
+    let hash = term.bytes().fold(TERM_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u32::from(byte.to_ascii_lowercase())).wrapping_mul(TERM_PRIME)
+    });
+    format!("<redacted-{:08x}>", hash)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alice() -> Redactor {
+        Redactor::default().with_profile(Path::new(r"C:\Users\alice\"), Some("alice.w"))
+    }
+
+    #[test]
+    fn replaces_profile_only_at_component_end() {
+        let redactor = alice();
+        assert_eq!(redactor.apply(r"C:\Users\alice"), PROFILE_PLACEHOLDER);
+        assert_eq!(
+            redactor.apply(r"C:\USERS\ALICE\Desktop"),
+            r"%USERPROFILE%\Desktop"
+        );
+        assert_eq!(
+            redactor.apply(r"C:\Users\alice2"),
+            r"C:\Users\alice2",
+            "不应匹配前缀"
+        );
+    }
+
+    #[test]
+    fn replaces_user_names_as_whole_components() {
+        let redactor = alice();
+        assert_eq!(redactor.apply("alice"), USER_PLACEHOLDER);
+        assert_eq!(redactor.apply("Alice.W/notes"), "%USERNAME%/notes");
+        assert_eq!(redactor.apply("malice.txt"), "malice.txt");
+        assert!(matches!(redactor.apply("src"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn replaces_terms_with_stable_placeholders() {
+        let mut redactor = Redactor::default();
+        redactor.add_term("hawk");
+        redactor.add_term("nighthawk");
+        redactor.add_term("HAWK");
+        redactor.add_term("");
+        assert_eq!(
+            redactor.terms(),
+            ["nighthawk", "hawk"],
+            "应去重并按长度排序"
+        );
+
+        let hawk = term_placeholder("hawk");
+        assert_eq!(term_placeholder("Hawk"), hawk, "占位符应不区分大小写");
+        assert_eq!(redactor.apply("Hawk_hawk"), format!("{hawk}_{hawk}"));
+        assert_eq!(redactor.apply("nighthawk"), term_placeholder("nighthawk"));
+        assert_eq!(redactor.apply("日本hawk語"), format!("日本{hawk}語"));
+    }
+}
@@ -6,27 +6,55 @@
 //! - **Tree styles**: ASCII (`/A`) or Unicode (default)
 //! - **No-indent mode**: whitespace-only indentation (`/NI`)
 //! - **Path display**: relative names (default) or full paths (`/FP`)
+//! - **Console width**: long names shortened in the middle to fit (`--truncate`)
 //! - **Metadata display**: file size (`/S`), human-readable size (`/HR`),
-//!   modification date (`/DT`), directory cumulative size (`/DU`)
-//! - **Statistics report**: end-of-output statistics (`/RP`)
-//! - **Windows banner**: system volume info (default), disabled with `/NB`
+//!   modification date (`/DT`), creation and access dates (`--created`,
+//!   `--accessed`), directory cumulative size (`/DU`), with
+//!   decimal units (`--si`), digit grouping (`--number-format`), relative
+//!   dates (`--relative-dates`) and date patterns (`--date-format`)
+//! - **Statistics report**: end-of-output statistics (`/RP`), or the totals
+//!   alone (`--count`)
+//! - **Windows banner**: system volume info (default), disabled with `/NB`;
+//!   built without spawning `tree` for languages with bundled strings
 //! - **Streaming render**: `StreamRenderer` supports incremental rendering
+//! - **Filter explanations**: `render_explanation` reports `--explain` results,
+//!   and entries kept by `--show-filtered` are annotated with their reason
 //!
 //! File: src/render.rs
 //! Author: WaterRun
-//! Date: 2026-01-13
+//! Date: 2026-10-16
 
 #![forbid(unsafe_code)]
 
+use std::borrow::Cow;
 use std::fmt::Write as FmtWrite;
 use std::fs;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::OnceLock;
 use std::time::{Duration, SystemTime};
 
-use crate::config::{CharsetMode, Config, PathMode};
+use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::arena::{NodeRef, TreeArena};
+use crate::config::{
+    CharsetMode, Config, EscapeMode, GroupBy, Language, LevelGuides, NumberFormat, PathMode,
+};
 use crate::error::RenderError;
-use crate::scan::{EntryKind, EntryMetadata, ScanStats, StreamEntry, TreeNode};
+use crate::i18n;
+use crate::matching::{FilterReason, MatchHighlighter};
+use crate::redact::Redactor;
+use crate::scan::{
+    EntryError, EntryKind, EntryMetadata, FilterExplanation, ScanStats, StreamEntry, TreeNode,
+};
+use crate::stats::{
+    CountSummary, ExtensionEntry, LevelStats, REPORT_EXTENSION_LIMIT, StatsCollector,
+};
+use crate::warning;
+use crate::winpath;
+use crate::winvol;
 
 // ============================================================================
 // Constants
@@ -43,15 +71,84 @@ You may safely delete this directory. If you do not want tree++ to create it, us
 GitHub: https://github.com/Water-Run/treepp
 "#;
 
+/// Separator between a name and its metadata with `--no-align`.
+const LEGACY_META_GAP: &str = "        ";
+
+/// Minimum number of spaces between a name and aligned metadata.
+const META_GAP: usize = 2;
+
+/// Date pattern used when no `--date-format` is given.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Escape sequence that starts a `--highlight` match (underlined yellow).
+const HIGHLIGHT_START: &str = "\x1b[4;33m";
+
+/// Escape sequence that ends a `--highlight` match.
+const HIGHLIGHT_END: &str = "\x1b[0m";
+
+/// Colors of the connectors of successive levels with `--level-guides color`.
+const LEVEL_COLORS: [&str; 6] = [
+    "\x1b[36m", "\x1b[33m", "\x1b[35m", "\x1b[32m", "\x1b[34m", "\x1b[31m",
+];
+
+/// Escape sequence that ends a level color.
+const LEVEL_COLOR_END: &str = "\x1b[0m";
+
+/// Width `--columns` lays files out in when the output is not a console.
+const DEFAULT_COLUMNS_WIDTH: usize = 80;
+
+/// Number of spaces between the columns of `--columns`.
+const COLUMN_GAP: usize = 2;
+
+/// Prefix of entries whose upper levels were collapsed by `--leaf-depth`.
+const ELIDED_PREFIX: &str = ".../";
+
+/// Text replacing the middle of names shortened by `--truncate`.
+const TRUNCATION_MARK: &str = "...";
+
+/// Narrowest a name is shortened to by `--truncate`; tighter lines wrap instead.
+const MIN_TRUNCATED_WIDTH: usize = 8;
+
+/// Column at which the streaming renderer starts aligned metadata.
+const STREAM_META_COLUMN: usize = 40;
+
+/// Step by which the streaming metadata column grows past a long name.
+const STREAM_META_COLUMN_STEP: usize = 8;
+
+/// Width of the longest bar in the `--depth-histogram` table.
+const HISTOGRAM_BAR_WIDTH: usize = 20;
+
+/// Extensions of files marked as executable by `--classify`.
+const EXECUTABLE_EXTENSIONS: &[&str] = &["exe", "bat", "cmd", "ps1"];
+
+/// Extensions of files listed under Code by `--group-by type`.
+const CODE_EXTENSIONS: &[&str] = &[
+    "rs", "c", "h", "cpp", "hpp", "cc", "cs", "java", "kt", "go", "py", "rb", "php", "js", "jsx",
+    "ts", "tsx", "swift", "lua", "sh", "ps1", "bat", "cmd", "html", "css", "sql", "json", "toml",
+    "yaml", "yml", "xml",
+];
+
+/// Extensions of files listed under Images by `--group-by type`.
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "webp", "svg", "ico", "tif", "tiff", "heic", "psd",
+];
+
+/// Extensions of files listed under Archives by `--group-by type`.
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    "zip", "7z", "rar", "tar", "gz", "tgz", "bz2", "xz", "zst", "cab", "iso",
+];
+
 // ============================================================================
 // Windows Banner
 // ============================================================================
 
 /// Windows tree command banner information.
 ///
-/// Contains boilerplate text extracted from the native Windows `tree` command.
-/// Obtained by executing `tree` in a controlled `C:\__tree++__` directory to
-/// capture the system-localized banner text.
+/// Contains the boilerplate text printed by the native Windows `tree` command.
+/// Built from the volume label and serial (`GetVolumeInformationW`) and the
+/// strings of the user's UI language; for languages without bundled strings
+/// it falls back to executing `tree` in a controlled `C:\__tree++__`
+/// directory to capture the system-localized banner text.
 ///
 /// # Output Format
 ///
@@ -69,8 +166,11 @@ GitHub: https://github.com/Water-Run/treepp
 /// ```
 /// use treepp::render::WinBanner;
 ///
-/// let output = "Folder PATH listing\nSerial 1234\nC:.\nNo subfolders";
-/// let banner = WinBanner::parse(output).unwrap();
+/// let banner = WinBanner {
+///     volume_line: "Folder PATH listing".to_string(),
+///     serial_line: "Serial 1234".to_string(),
+///     no_subfolder: "No subfolders".to_string(),
+/// };
 /// assert_eq!(banner.volume_line, "Folder PATH listing");
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -86,8 +186,9 @@ pub struct WinBanner {
 impl WinBanner {
     /// Fetches Windows banner information for the specified drive letter.
     ///
-    /// Creates a marker directory `X:\__tree++__` (where X is the drive letter),
-    /// executes the native `tree` command there, and parses the output.
+    /// Queries the volume directly when the UI language has bundled banner
+    /// strings, without spawning any process. Otherwise falls back to the
+    /// native `tree` command (see `fetch_from_tree`).
     ///
     /// # Arguments
     ///
@@ -95,14 +196,11 @@ impl WinBanner {
     ///
     /// # Returns
     ///
-    /// The parsed `WinBanner` on success.
+    /// The `WinBanner` on success.
     ///
     /// # Errors
     ///
-    /// Returns `RenderError::BannerFetchFailed` if:
-    /// - The banner directory cannot be created
-    /// - The tree command fails to execute
-    /// - The tree output cannot be parsed
+    /// Returns `RenderError::BannerFetchFailed` if the fallback fails.
     ///
     /// # Examples
     ///
@@ -113,6 +211,26 @@ impl WinBanner {
     /// println!("Volume: {}", banner.volume_line);
     /// ```
     pub fn fetch_for_drive(drive: char) -> Result<Self, RenderError> {
+        if let Some(strings) = BannerStrings::for_language(winvol::ui_language())
+            && let Some(volume) = winvol::volume_info(drive)
+        {
+            return Ok(strings.banner(&volume));
+        }
+        Self::fetch_from_tree(drive)
+    }
+
+    /// Fetches banner information by running the native `tree` command.
+    ///
+    /// Creates a marker directory `X:\__tree++__` (where X is the drive letter),
+    /// executes the native `tree` command there, and parses the output.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RenderError::BannerFetchFailed` if:
+    /// - The banner directory cannot be created
+    /// - The tree command fails to execute
+    /// - The tree output cannot be parsed
+    fn fetch_from_tree(drive: char) -> Result<Self, RenderError> {
         let drive = drive.to_ascii_uppercase();
         let banner_dir = format!(r"{}:\__tree++__", drive);
         let dir_path = Path::new(&banner_dir);
@@ -142,7 +260,10 @@ impl WinBanner {
 
         if !output.status.success() {
             return Err(RenderError::BannerFetchFailed {
-                reason: format!("tree command returned error code: {:?}", output.status.code()),
+                reason: format!(
+                    "tree command returned error code: {:?}",
+                    output.status.code()
+                ),
             });
         }
 
@@ -217,6 +338,73 @@ impl WinBanner {
     }
 }
 
+/// Banner text of `tree.com` for one UI language.
+///
+/// The volume line wraps the label in `volume_prefix` and `volume_suffix`;
+/// volumes without a label use `unlabeled` instead.
+#[derive(Debug)]
+struct BannerStrings {
+    /// Text before the volume label.
+    volume_prefix: &'static str,
+    /// Text after the volume label.
+    volume_suffix: &'static str,
+    /// Volume line for a volume without a label.
+    unlabeled: &'static str,
+    /// Text before the serial number.
+    serial_prefix: &'static str,
+    /// No subfolder hint, including its trailing space.
+    no_subfolder: &'static str,
+}
+
+/// English banner strings.
+const BANNER_EN: BannerStrings = BannerStrings {
+    volume_prefix: "Folder PATH listing for volume ",
+    volume_suffix: "",
+    unlabeled: "Folder PATH listing",
+    serial_prefix: "Volume serial number is ",
+    no_subfolder: "No subfolders exist ",
+};
+
+/// Simplified Chinese banner strings.
+const BANNER_ZH_CN: BannerStrings = BannerStrings {
+    volume_prefix: "卷 ",
+    volume_suffix: " 的文件夹 PATH 列表",
+    unlabeled: "文件夹 PATH 列表",
+    serial_prefix: "卷序列号为 ",
+    no_subfolder: "没有子文件夹 ",
+};
+
+impl BannerStrings {
+    /// Looks up the bundled strings for a `LANGID`.
+    ///
+    /// Any English variant maps to the English strings; Simplified Chinese
+    /// covers China and Singapore. Other languages return `None`.
+    fn for_language(langid: u16) -> Option<&'static Self> {
+        match langid {
+            0x0804 | 0x1004 => Some(&BANNER_ZH_CN),
+            id if id & 0x03FF == 0x09 => Some(&BANNER_EN),
+            _ => None,
+        }
+    }
+
+    /// Builds the banner for a volume.
+    fn banner(&self, volume: &winvol::VolumeInfo) -> WinBanner {
+        let volume_line = if volume.label.is_empty() {
+            self.unlabeled.to_string()
+        } else {
+            format!(
+                "{}{}{}",
+                self.volume_prefix, volume.label, self.volume_suffix
+            )
+        };
+        WinBanner {
+            volume_line,
+            serial_line: format!("{}{}", self.serial_prefix, volume.serial_text()),
+            no_subfolder: self.no_subfolder.to_string(),
+        }
+    }
+}
+
 // ============================================================================
 // Tree Characters
 // ============================================================================
@@ -275,7 +463,7 @@ impl TreeChars {
     #[must_use]
     pub fn from_charset(charset: CharsetMode) -> Self {
         match charset {
-            CharsetMode::Unicode => Self {
+            CharsetMode::Unicode | CharsetMode::Auto => Self {
                 branch: "├─",
                 last_branch: "└─",
                 vertical: "│  ",
@@ -346,6 +534,10 @@ pub struct StreamRenderConfig {
     pub charset: CharsetMode,
     /// Whether to disable tree connectors.
     pub no_indent: bool,
+    /// Decoration of the tree connectors by level.
+    pub level_guides: LevelGuides,
+    /// Whether to list full paths one per line instead of a tree.
+    pub flat: bool,
     /// Whether to disable Windows banner.
     pub no_win_banner: bool,
     /// Whether to show statistics report.
@@ -354,12 +546,44 @@ pub struct StreamRenderConfig {
     pub show_files: bool,
     /// Path display mode.
     pub path_mode: PathMode,
+    /// Directory full paths are shown relative to (`--relative-to`).
+    pub relative_to: Option<PathBuf>,
+    /// Redaction of names and paths (`--redact`).
+    pub redaction: Option<Redactor>,
+    /// Escaping policy for entry names.
+    pub escape: EscapeMode,
+    /// Whether to wrap entry names in double quotes.
+    pub quote_names: bool,
+    /// Whether to append a type indicator (`/`, `*`, `@`) to names.
+    pub classify: bool,
+    /// Include pattern matcher used by `--highlight` (`None` when disabled).
+    pub highlighter: Option<MatchHighlighter>,
     /// Whether to show file sizes.
     pub show_size: bool,
     /// Whether to use human-readable size format.
     pub human_readable: bool,
+    /// Digit grouping and units for sizes.
+    pub number_style: NumberStyle,
     /// Whether to show modification dates.
     pub show_date: bool,
+    /// Whether to show creation dates.
+    pub show_created: bool,
+    /// Whether to show last access dates.
+    pub show_accessed: bool,
+    /// Whether to show dates relative to now.
+    pub relative_dates: bool,
+    /// strftime pattern for dates.
+    pub date_format: Option<String>,
+    /// Whether to align metadata in a column.
+    pub align_meta: bool,
+    /// Whether to mark cloud-only placeholders with `[cloud]`.
+    pub mark_cloud: bool,
+    /// Whether to mark zero-byte files with `[empty]`.
+    pub mark_empty_files: bool,
+    /// Console width lines are fitted into by `--truncate` (`None` when disabled).
+    pub line_width: Option<usize>,
+    /// Language of the statistics report.
+    pub language: Language,
 }
 
 impl StreamRenderConfig {
@@ -389,13 +613,31 @@ impl StreamRenderConfig {
         Self {
             charset: config.render.charset,
             no_indent: config.render.no_indent,
+            level_guides: config.render.level_guides,
+            flat: config.render.flat,
             no_win_banner: config.render.no_win_banner,
             show_report: config.render.show_report,
             show_files: config.scan.show_files,
             path_mode: config.render.path_mode,
+            relative_to: config.render.relative_to.clone(),
+            redaction: config.render.redaction.clone(),
+            escape: config.render.escape,
+            quote_names: config.render.quote_names,
+            classify: config.render.classify,
+            highlighter: build_highlighter(config),
             show_size: config.render.show_size,
             human_readable: config.render.human_readable,
+            number_style: NumberStyle::from_config(config),
             show_date: config.render.show_date,
+            show_created: config.render.show_created,
+            show_accessed: config.render.show_accessed,
+            relative_dates: config.render.relative_dates,
+            date_format: config.render.date_format.clone(),
+            align_meta: !config.render.no_align,
+            mark_cloud: config.render.mark_cloud,
+            mark_empty_files: config.render.mark_empty_files,
+            line_width: config.render.line_width,
+            language: config.render.language,
         }
     }
 }
@@ -434,6 +676,26 @@ pub struct StreamRenderer {
     level_state_stack: Vec<(Option<String>, bool)>,
     /// Whether a trailing line was just emitted (prevents duplicates).
     trailing_line_emitted: bool,
+    /// Column at which aligned metadata starts; grows past long names.
+    meta_column: usize,
+}
+
+/// The position of a `StreamRenderer` within the tree.
+///
+/// Saved with a `--checkpoint`, so that a resumed run draws the connectors
+/// of the directories it continues exactly as the interrupted run would have.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamRendererState {
+    /// Whether each open level has more siblings.
+    prefix_stack: Vec<bool>,
+    /// Whether the last rendered entry was a file.
+    last_was_file: bool,
+    /// Per-level state: (file prefix, whether last rendered was file).
+    level_state_stack: Vec<(Option<String>, bool)>,
+    /// Whether a trailing line was just emitted.
+    trailing_line_emitted: bool,
+    /// Column at which aligned metadata starts.
+    meta_column: usize,
 }
 
 impl StreamRenderer {
@@ -466,13 +728,20 @@ impl StreamRenderer {
             chars,
             config,
             last_was_file: false,
-            level_state_stack: Vec::new(),
+            // The root level is tracked like any other, so `finish` can
+            // close it with the same trailing line.
+            level_state_stack: vec![(None, false)],
             trailing_line_emitted: false,
+            meta_column: STREAM_META_COLUMN,
         }
     }
 
     /// Renders the banner and root path header.
     ///
+    /// A flat listing has no header, so the result is empty with `--flat`.
+    /// A banner that cannot be fetched is reported through `warning` and
+    /// left out, so the header only ever holds the banner and root line.
+    ///
     /// # Arguments
     ///
     /// * `root_path` - The root directory path
@@ -497,6 +766,9 @@ impl StreamRenderer {
     #[must_use]
     pub fn render_header(&self, root_path: &Path, path_explicitly_set: bool) -> String {
         let mut output = String::new();
+        if self.config.flat {
+            return output;
+        }
         let drive = extract_drive_letter(root_path).ok();
 
         let banner = if self.config.no_win_banner {
@@ -505,7 +777,7 @@ impl StreamRenderer {
             match WinBanner::fetch_for_drive(d) {
                 Ok(b) => Some(b),
                 Err(e) => {
-                    let _ = writeln!(output, "Warning: {}", e);
+                    warning::emit(&i18n::render_warning(&e, self.config.language));
                     None
                 }
             }
@@ -520,13 +792,13 @@ impl StreamRenderer {
             output.push('\n');
         }
 
-        let root_display = match format_root_path_display(root_path, path_explicitly_set) {
-            Ok(s) => s,
-            Err(e) => {
-                let _ = writeln!(output, "Warning: {}", e);
-                root_path.to_string_lossy().to_uppercase()
-            }
-        };
+        let root_display = header_root_display(
+            root_path,
+            path_explicitly_set,
+            self.config.relative_to.as_deref(),
+            self.config.redaction.as_ref(),
+            self.config.language,
+        );
         output.push_str(&root_display);
         output.push('\n');
 
@@ -564,6 +836,9 @@ impl StreamRenderer {
     ///     is_last: true,
     ///     is_file: false,
     ///     has_more_dirs: false,
+    ///     filtered: None,
+    ///     collapsed: None,
+    ///     error: None,
     /// };
     /// let line = renderer.render_entry(&entry);
     /// assert!(line.contains("test"));
@@ -598,7 +873,7 @@ impl StreamRenderer {
         }
 
         self.last_was_file = entry.is_file;
-        output
+        decorate_level_guides(output, &self.chars, self.config.level_guides)
     }
 
     /// Enters a subdirectory level.
@@ -658,7 +933,11 @@ impl StreamRenderer {
         }
 
         let result = if let Some((file_prefix, last_was_file)) = level_state {
-            if self.config.show_files && file_prefix.is_some() && last_was_file && !self.config.no_indent {
+            if self.config.show_files
+                && file_prefix.is_some()
+                && last_was_file
+                && !self.config.no_indent
+            {
                 self.trailing_line_emitted = true;
                 file_prefix
             } else {
@@ -669,7 +948,129 @@ impl StreamRenderer {
         };
 
         let _ = self.prefix_stack.pop();
-        result
+        result.map(|line| decorate_level_guides(line, &self.chars, self.config.level_guides))
+    }
+
+    /// Closes the root level once every subdirectory has been left.
+    ///
+    /// Root-level files get the same trailing line as files in a
+    /// subdirectory, which only happens when the root has no subdirectories.
+    ///
+    /// # Returns
+    ///
+    /// Optional trailing line if the root's last rendered entry was a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::render::{StreamRenderer, StreamRenderConfig};
+    /// use treepp::config::Config;
+    ///
+    /// let config = Config::default();
+    /// let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+    /// assert!(renderer.finish().is_none());
+    /// ```
+    #[must_use]
+    pub fn finish(&mut self) -> Option<String> {
+        self.pop_level()
+    }
+
+    /// Returns the position of the renderer within the tree.
+    ///
+    /// # Returns
+    ///
+    /// A state that `restore` brings a new renderer back to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::render::{StreamRenderer, StreamRenderConfig};
+    /// use treepp::config::Config;
+    ///
+    /// let config = Config::default();
+    /// let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+    /// renderer.push_level(true);
+    ///
+    /// let mut resumed = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+    /// resumed.restore(renderer.state());
+    /// assert!(!resumed.is_at_root_level());
+    /// ```
+    #[must_use]
+    pub fn state(&self) -> StreamRendererState {
+        StreamRendererState {
+            prefix_stack: self.prefix_stack.clone(),
+            last_was_file: self.last_was_file,
+            level_state_stack: self.level_state_stack.clone(),
+            trailing_line_emitted: self.trailing_line_emitted,
+            meta_column: self.meta_column,
+        }
+    }
+
+    /// Continues from a position saved by `state`.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The saved position.
+    pub fn restore(&mut self, state: StreamRendererState) {
+        self.prefix_stack = state.prefix_stack;
+        self.last_was_file = state.last_was_file;
+        self.level_state_stack = state.level_state_stack;
+        self.trailing_line_emitted = state.trailing_line_emitted;
+        self.meta_column = state.meta_column;
+    }
+
+    /// Renders the `--max-entries` marker that closes a truncated level.
+    ///
+    /// The marker is drawn like a last directory entry so the connectors of
+    /// the entries above it stay continuous.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - Depth of the omitted entries (root children have depth 0)
+    /// * `count` - Number of omitted entries
+    ///
+    /// # Returns
+    ///
+    /// The rendered marker line(s).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::render::{StreamRenderer, StreamRenderConfig};
+    /// use treepp::config::Config;
+    ///
+    /// let config = Config::default();
+    /// let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+    /// let line = renderer.render_omitted(0, 42);
+    /// assert!(line.contains("[+42 more]"));
+    /// ```
+    #[must_use]
+    pub fn render_omitted(&mut self, depth: usize, count: usize) -> String {
+        if let Some(last) = self.level_state_stack.last_mut() {
+            last.1 = false;
+        }
+
+        let marker = format_omitted_marker(count);
+        if self.config.flat {
+            // A path list has no place for the marker; the notice after the
+            // listing still reports the truncated directories.
+            self.last_was_file = false;
+            return String::new();
+        }
+        if self.config.no_indent {
+            self.last_was_file = false;
+            return format!("{}{}", "  ".repeat(depth), marker);
+        }
+
+        let mut output = String::new();
+        let prefix = self.build_prefix();
+        if self.config.show_files && self.last_was_file {
+            let _ = writeln!(output, "{}{}", prefix, self.chars.vertical);
+        }
+        let _ = write!(output, "{}{}{}", prefix, self.chars.last_branch, marker);
+
+        self.last_was_file = false;
+        decorate_level_guides(output, &self.chars, self.config.level_guides)
     }
 
     /// Renders the statistics report.
@@ -710,17 +1111,12 @@ impl StreamRenderer {
         let mut output = String::new();
 
         if self.config.show_report {
-            let time_str = format!(" in {:.3}s", duration.as_secs_f64());
-
-            if self.config.show_files {
-                let _ = writeln!(
-                    output,
-                    "{} directory, {} files{}",
-                    directory_count, file_count, time_str
-                );
-            } else {
-                let _ = writeln!(output, "{} directory{}", directory_count, time_str);
-            }
+            let file_count = self.config.show_files.then_some(file_count);
+            let _ = writeln!(
+                output,
+                "{}",
+                i18n::report_line(self.config.language, directory_count, file_count, duration)
+            );
         }
 
         output
@@ -762,7 +1158,7 @@ impl StreamRenderer {
     }
 
     /// Renders a file entry with indentation (no branch connectors).
-    fn render_file_entry(&self, entry: &StreamEntry) -> String {
+    fn render_file_entry(&mut self, entry: &StreamEntry) -> String {
         let mut line = String::new();
         let prefix = self.build_prefix();
         line.push_str(&prefix);
@@ -773,13 +1169,12 @@ impl StreamRenderer {
             line.push_str(self.chars.space);
         }
 
-        line.push_str(&self.format_name(&entry.name, &entry.path));
-        line.push_str(&self.format_meta(&entry.metadata, entry.kind));
+        self.push_entry(&mut line, entry);
         line
     }
 
     /// Renders a directory entry with branch connectors.
-    fn render_dir_entry(&self, entry: &StreamEntry) -> String {
+    fn render_dir_entry(&mut self, entry: &StreamEntry) -> String {
         let mut line = String::new();
         let prefix = self.build_prefix();
         line.push_str(&prefix);
@@ -791,22 +1186,63 @@ impl StreamRenderer {
         };
         line.push_str(connector);
 
-        line.push_str(&self.format_name(&entry.name, &entry.path));
-        line.push_str(&self.format_meta(&entry.metadata, entry.kind));
+        self.push_entry(&mut line, entry);
         line
     }
 
     /// Renders an entry without tree connectors (indent-only mode).
+    ///
+    /// Flat listings are rendered the same way without the indentation.
     fn render_entry_no_indent(&mut self, entry: &StreamEntry) -> String {
         let mut line = String::new();
-        let indent = "  ".repeat(entry.depth);
-        line.push_str(&indent);
-        line.push_str(&self.format_name(&entry.name, &entry.path));
-        line.push_str(&self.format_meta(&entry.metadata, entry.kind));
+        if !self.config.flat {
+            line.push_str(&"  ".repeat(entry.depth));
+        }
+        self.push_entry(&mut line, entry);
         self.last_was_file = entry.is_file;
         line
     }
 
+    /// Appends the name, metadata and annotations of an entry to `line`,
+    /// which holds the prefix.
+    ///
+    /// Aligned metadata starts at `meta_column`. A name reaching past it
+    /// moves the column right for the rest of the output, since later
+    /// lines cannot be known in advance.
+    fn push_entry(&mut self, line: &mut String, entry: &StreamEntry) {
+        let meta = self.format_meta(&entry.metadata, entry.kind);
+        let annotations = format_cloud_annotation(&entry.metadata, self.config.mark_cloud)
+            + &format_empty_annotation(&entry.metadata, entry.kind, self.config.mark_empty_files)
+            + &format_collapsed_annotation(entry.collapsed)
+            + &format_error_annotation(entry.error)
+            + &format_filter_annotation(entry.filtered.as_ref());
+        let name = fit_name(
+            self.format_name(entry),
+            display_width(line),
+            suffix_width(&meta, &annotations),
+            self.config.line_width,
+        );
+        line.push_str(&name);
+
+        if !meta.is_empty() {
+            let column = self.config.align_meta.then(|| {
+                let needed = display_width(line) + META_GAP;
+                if needed > self.meta_column {
+                    self.meta_column = needed.next_multiple_of(STREAM_META_COLUMN_STEP);
+                }
+                fit_column(
+                    self.meta_column,
+                    &meta,
+                    &annotations,
+                    self.config.line_width,
+                )
+            });
+            line.push_str(&meta_gap(line, column));
+            line.push_str(&meta);
+        }
+        line.push_str(&annotations);
+    }
+
     /// Builds the complete file prefix for trailing line alignment.
     fn build_file_prefix(&self, has_more_dirs: bool) -> String {
         let mut prefix = self.build_prefix();
@@ -832,36 +1268,112 @@ impl StreamRenderer {
     }
 
     /// Formats entry name based on path mode.
-    fn format_name(&self, name: &str, path: &Path) -> String {
-        match self.config.path_mode {
-            PathMode::Full => path.to_string_lossy().into_owned(),
-            PathMode::Relative => name.to_string(),
-        }
+    fn format_name(&self, entry: &StreamEntry) -> String {
+        let display = match self.config.path_mode {
+            PathMode::Full => full_path_display(&entry.path, self.config.relative_to.as_deref()),
+            PathMode::Relative => entry.name.as_str().into(),
+        };
+        let redaction = self.config.redaction.as_ref();
+        let display = redact(display, redaction);
+        let spans = match &self.config.highlighter {
+            Some(highlighter) if entry.kind == EntryKind::File => highlighter.spans(&entry.name),
+            _ => Vec::new(),
+        };
+        let (escape, quote) = (self.config.escape, self.config.quote_names);
+        let indicator = if self.config.classify {
+            classify_indicator(entry.kind, &entry.metadata, &entry.name)
+        } else {
+            ""
+        };
+        highlight_name(&display, &entry.name, spans, escape, quote)
+            + indicator
+            + &format_link_target(&entry.metadata, escape, quote, redaction)
     }
 
-    /// Formats entry metadata (size, date).
+    /// Formats entry metadata (size, date), without the gap before it.
     fn format_meta(&self, metadata: &EntryMetadata, kind: EntryKind) -> String {
         let mut parts = Vec::new();
 
         if self.config.show_size && kind == EntryKind::File {
-            let size_str = if self.config.human_readable {
-                format_size_human(metadata.size)
-            } else {
-                metadata.size.to_string()
-            };
-            parts.push(size_str);
+            parts.push(format_size(
+                metadata.size,
+                self.config.human_readable,
+                self.config.number_style,
+            ));
         }
 
-        if self.config.show_date {
-            if let Some(ref modified) = metadata.modified {
-                parts.push(format_datetime(modified));
+        let dates = [
+            (self.config.show_date, metadata.modified),
+            (self.config.show_created, metadata.created),
+            (self.config.show_accessed, metadata.accessed),
+        ];
+        for (shown, time) in dates {
+            if shown && let Some(ref time) = time {
+                parts.push(format_datetime_with(
+                    time,
+                    self.config.relative_dates,
+                    self.config.date_format.as_deref(),
+                ));
             }
         }
 
-        if parts.is_empty() {
-            String::new()
+        parts.join("  ")
+    }
+}
+
+// ============================================================================
+// Type Sections
+// ============================================================================
+
+/// A headed section of a directory listing with `--group-by type`.
+///
+/// Files are sorted into sections by extension; the sections are listed in
+/// declaration order, with the subdirectories last.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::render::Section;
+///
+/// assert_eq!(Section::of_file("main.RS"), Section::Code);
+/// assert_eq!(Section::of_file("backup.tar.gz"), Section::Archives);
+/// assert_eq!(Section::of_file("README"), Section::Other);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// Source code, scripts and structured text.
+    Code,
+    /// Pictures.
+    Images,
+    /// Compressed archives and disk images.
+    Archives,
+    /// Files with any other extension, or none.
+    Other,
+    /// Subdirectories.
+    Directories,
+}
+
+impl Section {
+    /// File sections in display order.
+    pub const FILE_SECTIONS: [Self; 4] = [Self::Code, Self::Images, Self::Archives, Self::Other];
+
+    /// Returns the section a file belongs to, based on its extension.
+    ///
+    /// The extension is compared case-insensitively.
+    #[must_use]
+    pub fn of_file(name: &str) -> Self {
+        let Some(ext) = Path::new(name).extension().and_then(|ext| ext.to_str()) else {
+            return Self::Other;
+        };
+        let matches = |list: &[&str]| list.iter().any(|e| ext.eq_ignore_ascii_case(e));
+        if matches(CODE_EXTENSIONS) {
+            Self::Code
+        } else if matches(IMAGE_EXTENSIONS) {
+            Self::Images
+        } else if matches(ARCHIVE_EXTENSIONS) {
+            Self::Archives
         } else {
-            format!("        {}", parts.join("  "))
+            Self::Other
         }
     }
 }
@@ -879,6 +1391,10 @@ struct BatchRenderState {
     /// Per-level state: (file prefix, whether last rendered was file).
     level_state_stack: Vec<(Option<String>, bool)>,
     last_rendered_is_file: bool,
+    /// Column at which aligned metadata starts (`None` with `--no-align`).
+    meta_column: Option<usize>,
+    /// Include pattern matcher used by `--highlight` (`None` when disabled).
+    highlighter: Option<MatchHighlighter>,
 }
 
 impl BatchRenderState {
@@ -888,6 +1404,8 @@ impl BatchRenderState {
         Self {
             level_state_stack: Vec::new(),
             last_rendered_is_file: false,
+            meta_column: None,
+            highlighter: None,
         }
     }
 
@@ -931,83 +1449,364 @@ impl BatchRenderState {
 // Public Formatting Functions
 // ============================================================================
 
-/// Formats a file size into human-readable form.
+/// Resolved digit grouping and units for sizes in text output.
 ///
-/// Converts byte sizes to KB, MB, GB, or TB with one decimal place.
+/// # Examples
+///
+/// ```
+/// use treepp::render::{format_size, NumberStyle};
+///
+/// let style = NumberStyle { group: Some(','), ..NumberStyle::PLAIN };
+/// assert_eq!(format_size(1234567, false, style), "1,234,567");
+/// assert_eq!(format_size(1234567, false, NumberStyle::PLAIN), "1234567");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberStyle {
+    /// Thousands separator (`None` prints raw digits).
+    pub group: Option<char>,
+    /// Decimal point of human-readable sizes.
+    pub decimal: char,
+    /// Whether human-readable sizes use decimal units (1 kB = 1000 B).
+    pub si: bool,
+}
+
+impl NumberStyle {
+    /// Raw digits and binary units, as printed without `--si` or `--number-format`.
+    pub const PLAIN: Self = Self {
+        group: None,
+        decimal: '.',
+        si: false,
+    };
+
+    /// Resolves the number style of a configuration.
+    ///
+    /// `NumberFormat::Locale` reads the separators from the user's
+    /// regional settings the first time it is needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The full configuration
+    ///
+    /// # Returns
+    ///
+    /// The style used to format sizes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::config::{Config, NumberFormat};
+    /// use treepp::render::NumberStyle;
+    ///
+    /// let mut config = Config::default();
+    /// config.render.number_format = NumberFormat::Period;
+    /// let style = NumberStyle::from_config(&config);
+    /// assert_eq!(style.group, Some('.'));
+    /// assert_eq!(style.decimal, ',');
+    /// ```
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let (group, decimal) = match config.render.number_format {
+            NumberFormat::Plain => (None, '.'),
+            NumberFormat::Locale => locale_separators(),
+            NumberFormat::Comma => (Some(','), '.'),
+            NumberFormat::Period => (Some('.'), ','),
+            NumberFormat::Space => (Some(' '), '.'),
+            NumberFormat::Apostrophe => (Some('\''), '.'),
+        };
+        Self {
+            group,
+            decimal,
+            si: config.render.si_units,
+        }
+    }
+}
+
+/// Returns the thousands separator and decimal point of the user's
+/// regional settings, falling back to `,` and `.` if they cannot be read.
+fn locale_separators() -> (Option<char>, char) {
+    static SEPARATORS: OnceLock<(Option<char>, char)> = OnceLock::new();
+    *SEPARATORS.get_or_init(|| {
+        Command::new("reg")
+            .args(["query", r"HKCU\Control Panel\International"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| WinBanner::decode_system_output(&output.stdout).ok())
+            .map_or((Some(','), '.'), |text| parse_international(&text))
+    })
+}
+
+/// Parses `sThousand` and `sDecimal` from `reg query` output.
+///
+/// An empty `sThousand` turns grouping off; missing values fall back
+/// to `,` and `.`.
+fn parse_international(output: &str) -> (Option<char>, char) {
+    let value = |name: &str| {
+        output.lines().find_map(|line| {
+            let rest = line.trim_start().strip_prefix(name)?;
+            let rest = rest.trim_start().strip_prefix("REG_SZ")?;
+            // Values are separated from the type by four spaces; a value
+            // may itself be a space.
+            Some(rest.strip_prefix("    ").unwrap_or(rest).to_string())
+        })
+    };
+    let group = value("sThousand").map_or(Some(','), |v| v.chars().next());
+    let decimal = value("sDecimal")
+        .and_then(|v| v.chars().next())
+        .unwrap_or('.');
+    (group, decimal)
+}
+
+/// Formats a size for text output.
 ///
 /// # Arguments
 ///
 /// * `size` - Size in bytes
+/// * `human_readable` - Whether to convert to KB, MB, GB or TB
+/// * `style` - Digit grouping, decimal point and units
 ///
 /// # Returns
 ///
-/// Formatted size string with unit suffix.
+/// The formatted size.
 ///
 /// # Examples
 ///
 /// ```
-/// use treepp::render::format_size_human;
+/// use treepp::render::{format_size, NumberStyle};
 ///
-/// assert_eq!(format_size_human(0), "0 B");
-/// assert_eq!(format_size_human(1024), "1.0 KB");
-/// assert_eq!(format_size_human(1048576), "1.0 MB");
-/// assert_eq!(format_size_human(1073741824), "1.0 GB");
+/// assert_eq!(format_size(1536, false, NumberStyle::PLAIN), "1536");
+/// assert_eq!(format_size(1536, true, NumberStyle::PLAIN), "1.5 KB");
 /// ```
 #[must_use]
-pub fn format_size_human(size: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = 1024 * KB;
-    const GB: u64 = 1024 * MB;
-    const TB: u64 = 1024 * GB;
-
-    if size >= TB {
-        format!("{:.1} TB", size as f64 / TB as f64)
-    } else if size >= GB {
-        format!("{:.1} GB", size as f64 / GB as f64)
-    } else if size >= MB {
-        format!("{:.1} MB", size as f64 / MB as f64)
-    } else if size >= KB {
-        format!("{:.1} KB", size as f64 / KB as f64)
+pub fn format_size(size: u64, human_readable: bool, style: NumberStyle) -> String {
+    if human_readable {
+        format_size_human_with(size, style)
     } else {
-        format!("{size} B")
+        group_digits(&size.to_string(), style.group)
     }
 }
 
-/// Formats a `SystemTime` as a local timezone datetime string.
+/// Formats a file size into human-readable form.
 ///
-/// Converts UTC time to local timezone and formats as "YYYY-MM-DD HH:MM:SS".
+/// Converts byte sizes to KB, MB, GB, or TB with one decimal place.
 ///
 /// # Arguments
 ///
-/// * `time` - The system time to format
+/// * `size` - Size in bytes
 ///
 /// # Returns
 ///
-/// Formatted datetime string in local timezone.
+/// Formatted size string with unit suffix.
 ///
 /// # Examples
 ///
 /// ```
-/// use std::time::SystemTime;
-/// use treepp::render::format_datetime;
+/// use treepp::render::format_size_human;
 ///
-/// let now = SystemTime::now();
-/// let formatted = format_datetime(&now);
+/// assert_eq!(format_size_human(0), "0 B");
+/// assert_eq!(format_size_human(1024), "1.0 KB");
+/// assert_eq!(format_size_human(1048576), "1.0 MB");
+/// assert_eq!(format_size_human(1073741824), "1.0 GB");
+/// ```
+#[must_use]
+pub fn format_size_human(size: u64) -> String {
+    format_size_human_with(size, NumberStyle::PLAIN)
+}
+
+/// Converts byte sizes to human-readable units in the given style.
+///
+/// Binary units step by 1024 and are labelled KB, MB, GB and TB;
+/// with `style.si` they step by 1000 and are labelled kB, MB, GB and TB.
+///
+/// # Arguments
+///
+/// * `size` - Size in bytes
+/// * `style` - Digit grouping, decimal point and units
+///
+/// # Returns
+///
+/// Formatted size string with unit suffix.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::render::{format_size_human_with, NumberStyle};
+///
+/// let si = NumberStyle { si: true, ..NumberStyle::PLAIN };
+/// assert_eq!(format_size_human_with(1500, si), "1.5 kB");
+/// assert_eq!(format_size_human_with(1500, NumberStyle::PLAIN), "1.5 KB");
+/// ```
+#[must_use]
+pub fn format_size_human_with(size: u64, style: NumberStyle) -> String {
+    let (base, units) = if style.si {
+        (1000, ["kB", "MB", "GB", "TB"])
+    } else {
+        (1024, ["KB", "MB", "GB", "TB"])
+    };
+
+    if size < base {
+        return format!("{} B", group_digits(&size.to_string(), style.group));
+    }
+
+    let mut divisor = base;
+    let mut unit = 0;
+    while unit + 1 < units.len() && size / divisor >= base {
+        divisor *= base;
+        unit += 1;
+    }
+
+    let value = format!("{:.1}", size as f64 / divisor as f64);
+    let (integer, fraction) = value.split_once('.').unwrap_or((&value, "0"));
+    format!(
+        "{}{}{} {}",
+        group_digits(integer, style.group),
+        style.decimal,
+        fraction,
+        units[unit]
+    )
+}
+
+/// Inserts `separator` between groups of three digits, if there is one.
+fn group_digits(digits: &str, separator: Option<char>) -> String {
+    let Some(separator) = separator else {
+        return digits.to_string();
+    };
+    let mut output = String::with_capacity(digits.len() + digits.len() / 3 * separator.len_utf8());
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            output.push(separator);
+        }
+        output.push(c);
+    }
+    output
+}
+
+/// Formats a count with `,` thousands separators (e.g. `1,204`).
+fn format_thousands(value: usize) -> String {
+    group_digits(&value.to_string(), Some(','))
+}
+
+/// Formats a `SystemTime` as a local timezone datetime string.
+///
+/// Converts UTC time to local timezone and formats as "YYYY-MM-DD HH:MM:SS".
+///
+/// # Arguments
+///
+/// * `time` - The system time to format
+///
+/// # Returns
+///
+/// Formatted datetime string in local timezone.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::SystemTime;
+/// use treepp::render::format_datetime;
+///
+/// let now = SystemTime::now();
+/// let formatted = format_datetime(&now);
 /// assert_eq!(formatted.len(), 19);
 /// assert!(formatted.contains("-"));
 /// assert!(formatted.contains(":"));
 /// ```
 #[must_use]
 pub fn format_datetime(time: &SystemTime) -> String {
+    format_datetime_with(time, false, None)
+}
+
+/// Formats a `SystemTime` for text output.
+///
+/// Relative dates count whole years (365 days), months (30 days), weeks,
+/// days, hours and minutes, so a file changed 50 hours ago shows as
+/// `2 days ago`. Anything under a minute old is `just now`, and times in
+/// the future read `in 3 days`.
+///
+/// # Arguments
+///
+/// * `time` - The system time to format
+/// * `relative` - Whether to show the time relative to now
+/// * `pattern` - strftime pattern for absolute dates (`None` means
+///   `%Y-%m-%d %H:%M:%S`)
+///
+/// # Returns
+///
+/// Formatted date in local timezone, or the relative description.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+/// use treepp::render::format_datetime_with;
+///
+/// let three_days_ago = SystemTime::now() - Duration::from_secs(3 * 86400 + 60);
+/// assert_eq!(format_datetime_with(&three_days_ago, true, None), "3 days ago");
+///
+/// let formatted = format_datetime_with(&SystemTime::now(), false, Some("%Y"));
+/// assert_eq!(formatted.len(), 4);
+/// ```
+#[must_use]
+pub fn format_datetime_with(time: &SystemTime, relative: bool, pattern: Option<&str>) -> String {
     use chrono::{DateTime, Local};
+
+    if relative {
+        return format_relative(time, SystemTime::now());
+    }
+
     let datetime: DateTime<Local> = (*time).into();
-    datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+    let mut output = String::new();
+    // The CLI rejects invalid patterns; formatting one would otherwise panic.
+    if write!(
+        output,
+        "{}",
+        datetime.format(pattern.unwrap_or(DEFAULT_DATE_FORMAT))
+    )
+    .is_err()
+    {
+        output.clear();
+        let _ = write!(output, "{}", datetime.format(DEFAULT_DATE_FORMAT));
+    }
+    output
+}
+
+/// Describes `time` relative to `now`, e.g. `3 days ago` or `in 2 hours`.
+fn format_relative(time: &SystemTime, now: SystemTime) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const UNITS: [(u64, &str); 6] = [
+        (365 * DAY, "year"),
+        (30 * DAY, "month"),
+        (7 * DAY, "week"),
+        (DAY, "day"),
+        (HOUR, "hour"),
+        (MINUTE, "minute"),
+    ];
+
+    let (seconds, future) = match now.duration_since(*time) {
+        Ok(elapsed) => (elapsed.as_secs(), false),
+        Err(e) => (e.duration().as_secs(), true),
+    };
+    let Some(&(length, unit)) = UNITS.iter().find(|(length, _)| seconds >= *length) else {
+        return "just now".to_string();
+    };
+
+    let count = seconds / length;
+    let plural = if count == 1 { "" } else { "s" };
+    if future {
+        format!("in {count} {unit}{plural}")
+    } else {
+        format!("{count} {unit}{plural} ago")
+    }
 }
 
 /// Formats root path display to match Windows tree command style.
 ///
 /// When path is not explicitly specified, displays as `D:.` format.
 /// When explicitly specified, displays full uppercase path.
+/// UNC roots have no drive letter and always display their full
+/// uppercase `\\SERVER\SHARE\...` path. Verbatim `\\?\` prefixes are
+/// never shown.
 ///
 /// # Arguments
 ///
@@ -1039,14 +1838,69 @@ pub fn format_root_path_display(
     root_path: &Path,
     path_explicitly_set: bool,
 ) -> Result<String, RenderError> {
-    if path_explicitly_set {
-        Ok(root_path.to_string_lossy().to_uppercase())
+    if path_explicitly_set || winpath::is_unc(root_path) {
+        Ok(winpath::to_display(root_path)
+            .to_string_lossy()
+            .to_uppercase())
     } else {
         let drive = extract_drive_letter(root_path)?;
         Ok(format!("{}:.", drive))
     }
 }
 
+/// Formats the root line of the tree header.
+///
+/// With `--relative-to`, the root is shown relative to that base directory,
+/// so that no absolute path is written. Otherwise it is formatted by
+/// `format_root_path_display`, falling back to the uppercase path with a
+/// warning. With `--redact`, the result is redacted like entry names.
+fn header_root_display(
+    root_path: &Path,
+    path_explicitly_set: bool,
+    relative_to: Option<&Path>,
+    redaction: Option<&Redactor>,
+    language: Language,
+) -> String {
+    let display = match relative_to {
+        Some(base) => winpath::relative_to(root_path, base)
+            .to_string_lossy()
+            .into_owned(),
+        None => match format_root_path_display(root_path, path_explicitly_set) {
+            Ok(s) => s,
+            Err(e) => {
+                warning::emit(&i18n::render_warning(&e, language));
+                root_path.to_string_lossy().to_uppercase()
+            }
+        },
+    };
+    redact(display.into(), redaction).into_owned()
+}
+
+/// Returns the path shown for an entry in full-path mode.
+///
+/// With `--relative-to`, the path is given relative to that base directory.
+fn full_path_display<'a>(path: &'a Path, relative_to: Option<&Path>) -> Cow<'a, str> {
+    match relative_to {
+        Some(base) => Cow::Owned(
+            winpath::relative_to(path, base)
+                .to_string_lossy()
+                .into_owned(),
+        ),
+        None => path.to_string_lossy(),
+    }
+}
+
+/// Applies `--redact` to a name or path shown in the tree.
+fn redact<'a>(text: Cow<'a, str>, redaction: Option<&Redactor>) -> Cow<'a, str> {
+    let Some(redactor) = redaction else {
+        return text;
+    };
+    match redactor.apply(&text) {
+        Cow::Borrowed(_) => text,
+        Cow::Owned(redacted) => Cow::Owned(redacted),
+    }
+}
+
 // ============================================================================
 // Main Render Function
 // ============================================================================
@@ -1070,6 +1924,7 @@ pub fn format_root_path_display(
 /// ```
 /// use std::path::PathBuf;
 /// use std::time::Duration;
+/// use treepp::arena::TreeArena;
 /// use treepp::render::{render, RenderResult};
 /// use treepp::scan::{TreeNode, ScanStats, EntryKind, EntryMetadata};
 /// use treepp::config::Config;
@@ -1080,10 +1935,12 @@ pub fn format_root_path_display(
 ///     EntryMetadata::default(),
 /// );
 /// let stats = ScanStats {
-///     tree: root,
+///     tree: TreeArena::from_tree(&root),
 ///     duration: Duration::from_millis(100),
 ///     directory_count: 0,
 ///     file_count: 0,
+///     unreadable_dirs: Vec::new(),
+///     vanished_entries: 0,
 /// };
 /// let mut config = Config::with_root(PathBuf::from("test"));
 /// config.render.no_win_banner = true;
@@ -1103,7 +1960,7 @@ pub fn render(stats: &ScanStats, config: &Config) -> RenderResult {
         match WinBanner::fetch_for_drive(d) {
             Ok(b) => Some(b),
             Err(e) => {
-                let _ = writeln!(output, "Warning: {}", e);
+                warning::emit(&i18n::render_warning(&e, config.render.language));
                 None
             }
         }
@@ -1118,57 +1975,95 @@ pub fn render(stats: &ScanStats, config: &Config) -> RenderResult {
         output.push('\n');
     }
 
-    let root_display = match format_root_path_display(&config.root_path, config.path_explicitly_set)
-    {
-        Ok(s) => s,
-        Err(e) => {
-            let _ = writeln!(output, "Warning: {}", e);
-            config.root_path.to_string_lossy().to_uppercase()
-        }
-    };
-    output.push_str(&root_display);
-    output.push('\n');
+    if !config.render.flat {
+        let root_display = header_root_display(
+            &config.root_path,
+            config.path_explicitly_set,
+            config.render.relative_to.as_deref(),
+            config.render.redaction.as_ref(),
+            config.render.language,
+        );
+        output.push_str(&root_display);
+        output.push('\n');
+    }
 
+    let tree = stats.tree.root();
+    let mut state = BatchRenderState::new();
+    state.meta_column = batch_meta_column(tree, config, &chars, 0);
+    state.highlighter = build_highlighter(config);
     if config.render.no_indent {
-        render_children_no_indent(&mut output, &stats.tree, config, 1);
+        render_children_no_indent(&mut output, tree, config, 1, &state);
     } else {
-        let mut state = BatchRenderState::new();
-        render_children(&mut output, &stats.tree, &chars, config, "", 1, &mut state);
-    }
-
-    if !tree_has_subdirectories(&stats.tree) {
-        // Check if there are files at root level
-        let has_files = stats
-            .tree
-            .children
-            .iter()
-            .any(|c| c.kind == EntryKind::File);
-
-        if has_files && config.scan.show_files && !config.render.no_indent {
-            // Output trailing space line after files
-            let _ = writeln!(output, "{}", chars.space);
+        let mut lines = String::new();
+        state.push_level();
+        render_children(&mut lines, tree, &chars, config, "", 1, &mut state);
+
+        // The root level closes like any other: a trailing line follows
+        // its files, which are last only when there are no subdirectories.
+        if let Some(trailing) = state.pop_level()
+            && config.scan.show_files
+        {
+            let _ = writeln!(lines, "{}", trailing);
         }
+        output.push_str(&decorate_level_guides(
+            lines,
+            &chars,
+            config.render.level_guides,
+        ));
+    }
 
-        if let Some(b) = &banner {
-            if !b.no_subfolder.is_empty() {
-                output.push_str(&b.no_subfolder);
-                output.push('\n');
-            }
+    // A root cut short by `--max-entries` may have more subdirectories than
+    // it shows, so it gets no "no subfolders" notice; see `main`. Neither
+    // does one whose directories `--leaf-depth` collapsed.
+    if !tree_has_subdirectories(tree)
+        && tree.omitted() == 0
+        && config.scan.leaf_depth.is_none()
+        && !config.render.flat
+    {
+        if let Some(b) = &banner
+            && !b.no_subfolder.is_empty()
+        {
+            output.push_str(&b.no_subfolder);
+            output.push('\n');
         }
         output.push('\n');
     }
 
+    let output_limit = config
+        .output
+        .max_output_size
+        .filter(|&limit| truncate_at_line_boundary(&mut output, limit));
+    output.push_str(&render_truncation_notice(
+        stats.tree.count_truncated(),
+        stats.tree.count_unread(),
+        output_limit,
+    ));
+
     if config.render.show_report {
-        let time_str = format!(" in {:.3}s", stats.duration.as_secs_f64());
+        let file_count = config.scan.show_files.then_some(stats.file_count);
+        let _ = writeln!(
+            output,
+            "{}",
+            i18n::report_line(
+                config.render.language,
+                stats.directory_count,
+                file_count,
+                stats.duration
+            )
+        );
+    }
 
-        if config.scan.show_files {
-            let _ = writeln!(
-                output,
-                "{} directory, {} files{}",
-                stats.directory_count, stats.file_count, time_str
-            );
-        } else {
-            let _ = writeln!(output, "{} directory{}", stats.directory_count, time_str);
+    if config.render.report_extensions || config.render.depth_histogram {
+        let collector = StatsCollector::from_tree(&stats.tree);
+        if config.render.report_extensions {
+            let rows = collector.top_extensions(REPORT_EXTENSION_LIMIT);
+            output.push_str(&render_extension_report(&rows, config.render.language));
+        }
+        if config.render.depth_histogram {
+            output.push_str(&render_depth_histogram(
+                collector.levels(),
+                config.render.language,
+            ));
         }
     }
 
@@ -1181,6 +2076,289 @@ pub fn render(stats: &ScanStats, config: &Config) -> RenderResult {
     }
 }
 
+/// Renders the notice printed when the output was truncated.
+///
+/// Shown regardless of `/RP`, since a silently shortened tree is easy to
+/// mistake for a complete one.
+///
+/// # Arguments
+///
+/// * `truncated_dirs` - Number of directories truncated by `--max-entries`
+/// * `unread_dirs` - Number of directories left unread by `--budget-time` or
+///   `--budget-entries`
+/// * `output_limit` - The `--max-output-size` limit, if it was reached
+///
+/// # Returns
+///
+/// The notice lines, or an empty string if nothing was truncated.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::render::render_truncation_notice;
+///
+/// assert!(render_truncation_notice(0, 0, None).is_empty());
+/// assert!(render_truncation_notice(3, 0, None).contains("3 directories"));
+/// assert!(render_truncation_notice(0, 2, None).contains("budget"));
+/// assert!(render_truncation_notice(0, 0, Some(1024)).contains("1.0 KB"));
+/// ```
+#[must_use]
+pub fn render_truncation_notice(
+    truncated_dirs: usize,
+    unread_dirs: usize,
+    output_limit: Option<u64>,
+) -> String {
+    let mut output = String::new();
+    if truncated_dirs > 0 {
+        let noun = if truncated_dirs == 1 {
+            "directory has"
+        } else {
+            "directories have"
+        };
+        let _ = writeln!(
+            output,
+            "Truncated: {} {} more entries than --max-entries",
+            truncated_dirs, noun
+        );
+    }
+    if unread_dirs > 0 {
+        let noun = if unread_dirs == 1 {
+            "directory was"
+        } else {
+            "directories were"
+        };
+        let _ = writeln!(
+            output,
+            "Truncated: {} {} not read after the scan budget ran out",
+            unread_dirs, noun
+        );
+    }
+    if let Some(limit) = output_limit {
+        let _ = writeln!(
+            output,
+            "Truncated: output stopped at the --max-output-size limit of {}",
+            format_size_human(limit)
+        );
+    }
+    output
+}
+
+/// Renders the `--report-extensions` table.
+///
+/// # Arguments
+///
+/// * `rows` - The extensions to list, most common first
+/// * `language` - Language of the heading and nouns
+///
+/// # Returns
+///
+/// The table, one extension per line with its file count and total size,
+/// or a single line noting that no files were listed.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::render::render_extension_report;
+/// use treepp::stats::ExtensionEntry;
+///
+/// let rows = [ExtensionEntry { extension: ".rs".to_string(), count: 1204, size: 24_536_678 }];
+/// let report = render_extension_report(&rows, Language::English);
+/// assert!(report.contains(".rs  1,204 files  23.4 MB"));
+/// ```
+#[must_use]
+pub fn render_extension_report(rows: &[ExtensionEntry], language: Language) -> String {
+    if rows.is_empty() {
+        return format!("{}\n", i18n::no_files_listed(language));
+    }
+
+    let cells: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let noun = i18n::file_noun(language, row.count);
+            (
+                row.extension.as_str(),
+                format_thousands(row.count),
+                noun,
+                format_size_human(row.size),
+            )
+        })
+        .collect();
+    let ext_width = cells.iter().map(|c| display_width(c.0)).max().unwrap_or(0);
+    let count_width = cells.iter().map(|c| c.1.len()).max().unwrap_or(0);
+    let size_width = cells.iter().map(|c| c.3.len()).max().unwrap_or(0);
+
+    let mut output = format!("{}\n", i18n::extensions_heading(language));
+    for (extension, count, noun, size) in cells {
+        let padding = " ".repeat(ext_width - display_width(extension));
+        let _ = writeln!(
+            output,
+            "  {extension}{padding}  {:>count_width$} {:<5}  {:>size_width$}",
+            count, noun, size
+        );
+    }
+    output
+}
+
+/// Renders the `--depth-histogram` table.
+///
+/// # Arguments
+///
+/// * `levels` - Statistics per level, starting with level 1
+/// * `language` - Language of the heading and column headers
+///
+/// # Returns
+///
+/// The table, one level per line with its directory and file counts, the
+/// total size of its files and a bar proportional to its entry count, or a
+/// single line noting that no entries were listed.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::render::render_depth_histogram;
+/// use treepp::stats::LevelStats;
+///
+/// let levels = [
+///     LevelStats { directories: 2, files: 10, size: 4096 },
+///     LevelStats { directories: 0, files: 5, size: 100 },
+/// ];
+/// let table = render_depth_histogram(&levels, Language::English);
+/// assert!(table.contains("      1     2     10  4.0 KB  ####################"));
+/// assert!(table.contains("      2     0      5   100 B  #########"));
+/// ```
+#[must_use]
+pub fn render_depth_histogram(levels: &[LevelStats], language: Language) -> String {
+    if levels.is_empty() {
+        return format!("{}\n", i18n::no_entries_listed(language));
+    }
+
+    let rows: Vec<[String; 4]> = levels
+        .iter()
+        .enumerate()
+        .map(|(index, level)| {
+            [
+                (index + 1).to_string(),
+                format_thousands(level.directories),
+                format_thousands(level.files),
+                format_size_human(level.size),
+            ]
+        })
+        .collect();
+    let headers = i18n::depth_histogram_columns(language);
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].len())
+                .chain(std::iter::once(display_width(headers[column])))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let most_entries = levels
+        .iter()
+        .map(LevelStats::entries)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let header: Vec<String> = headers
+        .iter()
+        .zip(&widths)
+        .map(|(header, width)| format!("{}{}", " ".repeat(width - display_width(header)), header))
+        .collect();
+    let mut output = format!("{}\n", i18n::depth_histogram_heading(language));
+    let _ = writeln!(output, "  {}", header.join("  "));
+    for (row, level) in rows.iter().zip(levels) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:>width$}"))
+            .collect();
+        let bar = "#".repeat((level.entries() * HISTOGRAM_BAR_WIDTH).div_ceil(most_entries));
+        let line = format!("  {}  {}", cells.join("  "), bar);
+        let _ = writeln!(output, "{}", line.trim_end());
+    }
+    output
+}
+
+/// Renders the totals printed by `--count`.
+///
+/// # Arguments
+///
+/// * `summary` - The totals of the scan
+/// * `language` - Language of the labels
+///
+/// # Returns
+///
+/// One line per total with the values in a column, followed by one
+/// indented line per filter that left out at least one entry.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::Language;
+/// use treepp::render::render_count_summary;
+/// use treepp::matching::{FilterReason, RejectionCounts};
+/// use treepp::stats::CountSummary;
+///
+/// let mut rejected = RejectionCounts::default();
+/// rejected.record(&FilterReason::NotIncluded);
+/// let summary = CountSummary {
+///     directories: 12,
+///     files: 1204,
+///     total_size: 24_536_678,
+///     max_depth: 4,
+///     rejected,
+/// };
+/// let output = render_count_summary(&summary, Language::English);
+/// assert!(output.contains("Files:         1,204"));
+/// assert!(output.contains("Total size:    23.4 MB"));
+/// assert!(output.contains("  not included (/M)  1"));
+/// ```
+#[must_use]
+pub fn render_count_summary(summary: &CountSummary, language: Language) -> String {
+    let labels = i18n::count_labels(language);
+    let values = [
+        format_thousands(summary.directories),
+        format_thousands(summary.files),
+        format_size_human(summary.total_size),
+        summary.max_depth.to_string(),
+        format_thousands(summary.rejected.total()),
+    ];
+    let label_width = labels.iter().map(|l| display_width(l)).max().unwrap_or(0) + 2;
+
+    let mut output = String::new();
+    for (label, value) in labels.iter().zip(values) {
+        let padding = label_width - display_width(label);
+        let _ = writeln!(output, "{}{}{}", label, " ".repeat(padding), value);
+    }
+
+    let filters: Vec<_> = summary
+        .rejected
+        .iter()
+        .map(|(kind, count)| (i18n::filter_label(language, kind), count))
+        .collect();
+    let filter_width = filters
+        .iter()
+        .map(|f| display_width(f.0))
+        .max()
+        .unwrap_or(0)
+        + 2;
+    for (label, count) in filters {
+        let padding = filter_width - display_width(label);
+        let _ = writeln!(
+            output,
+            "  {}{}{}",
+            label,
+            " ".repeat(padding),
+            format_thousands(count)
+        );
+    }
+    output
+}
+
 /// Renders only the tree structure without banner or statistics.
 ///
 /// # Arguments
@@ -1211,57 +2389,115 @@ pub fn render(stats: &ScanStats, config: &Config) -> RenderResult {
 /// assert!(output.contains("project"));
 /// ```
 pub fn render_tree_only(node: &TreeNode, config: &Config) -> String {
+    let tree = TreeArena::from_tree(node);
+    let node = tree.root();
     let mut output = String::new();
     let chars = TreeChars::from_charset(config.render.charset);
 
-    let root_name = format_entry_name(node, config);
-    let root_meta = format_entry_meta(node, config);
-    let _ = writeln!(output, "{root_name}{root_meta}");
-
+    let root_name = format_entry_name(node, config, None);
+    let root_width = if format_entry_meta(node, config).is_empty() {
+        0
+    } else {
+        display_width(&root_name)
+    };
+    let meta_column = batch_meta_column(node, config, &chars, root_width);
+    let _ = writeln!(
+        output,
+        "{}",
+        format_entry_line("", node, config, meta_column, None)
+    );
+
+    let mut state = BatchRenderState::new();
+    state.meta_column = meta_column;
     if config.render.no_indent {
-        render_children_no_indent(&mut output, node, config, 1);
+        render_children_no_indent(&mut output, node, config, 1, &state);
     } else {
-        let mut state = BatchRenderState::new();
-        render_children(&mut output, node, &chars, config, "", 1, &mut state);
+        let mut lines = String::new();
+        render_children(&mut lines, node, &chars, config, "", 1, &mut state);
+        output.push_str(&decorate_level_guides(
+            lines,
+            &chars,
+            config.render.level_guides,
+        ));
     }
 
     output
 }
 
-// ============================================================================
-// Internal Helper Functions
-// ============================================================================
-
-/// Checks if depth is within the optional limit.
-#[inline]
-fn depth_within_limit(depth: usize, max_depth: Option<usize>) -> bool {
-    max_depth.map_or(true, |m| depth <= m)
-}
-
-/// Checks if recursion is allowed at the given depth.
-#[inline]
-fn can_recurse(depth: usize, max_depth: Option<usize>) -> bool {
-    max_depth.map_or(true, |m| depth < m)
-}
-
-/// Extracts the drive letter from a canonicalized path.
-fn extract_drive_letter(root_path: &Path) -> Result<char, RenderError> {
-    use std::path::Component;
-
-    if let Some(Component::Prefix(prefix)) = root_path.components().next() {
-        let prefix_str = prefix.as_os_str().to_string_lossy();
-        let chars: Vec<char> = prefix_str.chars().collect();
-
-        if chars.len() >= 2 && chars[1] == ':' {
-            return Ok(chars[0].to_ascii_uppercase());
+/// Renders the result of `--explain` for a single path.
+///
+/// Paths are shown relative to the root directory.
+///
+/// # Arguments
+///
+/// * `explanation` - The filter explanation to render.
+/// * `root` - The root directory of the scan.
+///
+/// # Returns
+///
+/// A multi-line report ending with a newline.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::render::render_explanation;
+/// use treepp::matching::FilterReason;
+/// use treepp::scan::FilterExplanation;
+///
+/// let explanation = FilterExplanation {
+///     path: PathBuf::from(r"C:\project\target\debug"),
+///     decided_at: PathBuf::from(r"C:\project\target"),
+///     reason: Some(FilterReason::Excluded { pattern: "target".to_string() }),
+/// };
+/// let output = render_explanation(&explanation, &PathBuf::from(r"C:\project"));
+/// assert!(output.contains("hidden"));
+/// ```
+pub fn render_explanation(explanation: &FilterExplanation, root: &Path) -> String {
+    let relative = |path: &Path| -> String {
+        match path.strip_prefix(root) {
+            Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+            Ok(rel) => rel.display().to_string(),
+            Err(_) => path.display().to_string(),
         }
+    };
 
-        if prefix_str.starts_with(r"\\?\") && chars.len() >= 6 && chars[5] == ':' {
-            return Ok(chars[4].to_ascii_uppercase());
+    let mut output = String::new();
+    let _ = writeln!(output, "Path:       {}", relative(&explanation.path));
+    match explanation.reason {
+        None => {
+            let _ = writeln!(output, "Result:     shown");
+        }
+        Some(ref reason) => {
+            let _ = writeln!(output, "Result:     hidden");
+            let _ = writeln!(output, "Decided at: {}", relative(&explanation.decided_at));
+            let _ = writeln!(output, "Reason:     {}", reason);
         }
     }
+    output
+}
+
+// ============================================================================
+// Internal Helper Functions
+// ============================================================================
+
+/// Checks if depth is within the optional limit.
+#[inline]
+fn depth_within_limit(depth: usize, max_depth: Option<usize>) -> bool {
+    max_depth.is_none_or(|m| depth <= m)
+}
+
+/// Checks if recursion is allowed at the given depth.
+#[inline]
+fn can_recurse(depth: usize, max_depth: Option<usize>) -> bool {
+    max_depth.is_none_or(|m| depth < m)
+}
 
-    Err(RenderError::InvalidPath {
+/// Extracts the drive letter from a canonicalized path.
+///
+/// UNC roots yield an error, which callers treat as "no banner available".
+fn extract_drive_letter(root_path: &Path) -> Result<char, RenderError> {
+    winpath::drive_letter(root_path).ok_or_else(|| RenderError::InvalidPath {
         path: root_path.to_path_buf(),
         reason: "Unable to extract drive letter".to_string(),
     })
@@ -1269,213 +2505,1130 @@ fn extract_drive_letter(root_path: &Path) -> Result<char, RenderError> {
 
 /// Checks if tree has any subdirectories (not counting root itself).
 #[must_use]
-fn tree_has_subdirectories(node: &TreeNode) -> bool {
-    node.children
-        .iter()
-        .any(|child| child.kind == EntryKind::Directory)
+fn tree_has_subdirectories(node: NodeRef<'_>) -> bool {
+    node.children()
+        .any(|child| child.kind() == EntryKind::Directory)
 }
 
 /// Formats entry name based on path mode.
-fn format_entry_name(node: &TreeNode, config: &Config) -> String {
-    match config.render.path_mode {
-        PathMode::Full => node.path.to_string_lossy().into_owned(),
-        PathMode::Relative => node.name.clone(),
-    }
+///
+/// In relative mode, entries hoisted by `--leaf-depth` are prefixed with
+/// `.../` to show that upper levels were collapsed. With a `highlighter`,
+/// the parts of file names matched by include patterns are highlighted.
+fn format_entry_name(
+    node: NodeRef<'_>,
+    config: &Config,
+    highlighter: Option<&MatchHighlighter>,
+) -> String {
+    let spans = match highlighter {
+        Some(highlighter) if node.kind() == EntryKind::File => highlighter.spans(node.name()),
+        _ => Vec::new(),
+    };
+    let (escape, quote) = (config.render.escape, config.render.quote_names);
+    let redaction = config.render.redaction.as_ref();
+    let name = match config.render.path_mode {
+        PathMode::Full => {
+            let path = node.path();
+            let display = full_path_display(&path, config.render.relative_to.as_deref());
+            highlight_name(
+                &redact(display, redaction),
+                node.name(),
+                spans,
+                escape,
+                quote,
+            )
+        }
+        PathMode::Relative => {
+            let display = redact(node.name().into(), redaction);
+            let name = highlight_name(&display, node.name(), spans, escape, quote);
+            if node.elided() {
+                format!("{}{}", ELIDED_PREFIX, name)
+            } else {
+                name
+            }
+        }
+    };
+    let indicator = if config.render.classify {
+        classify_indicator(node.kind(), node.metadata(), node.name())
+    } else {
+        ""
+    };
+    name + indicator + &format_link_target(node.metadata(), escape, quote, redaction)
 }
 
-/// Formats entry metadata (size, date, disk usage).
-fn format_entry_meta(node: &TreeNode, config: &Config) -> String {
-    let mut parts = Vec::new();
+/// Returns the `--classify` indicator of an entry, like GNU `tree -F`.
+///
+/// Directories get `/`, links `@` and files with an executable extension
+/// (`.exe`, `.bat`, `.cmd`, `.ps1`) `*`; other files get none.
+fn classify_indicator(kind: EntryKind, metadata: &EntryMetadata, name: &str) -> &'static str {
+    if metadata.link_target.is_some() {
+        return "@";
+    }
+    if kind == EntryKind::Directory {
+        return "/";
+    }
+    let executable = Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            EXECUTABLE_EXTENSIONS
+                .iter()
+                .any(|e| ext.eq_ignore_ascii_case(e))
+        });
+    if executable { "*" } else { "" }
+}
 
-    if config.render.show_size && node.kind == EntryKind::File {
-        let size = node.metadata.size;
-        let size_str = if config.render.human_readable {
-            format_size_human(size)
-        } else {
-            size.to_string()
-        };
-        parts.push(size_str);
+/// Formats the ` -> target` suffix of a symbolic link or junction.
+///
+/// The target is redacted, escaped and quoted like the name. Returns an
+/// empty string for entries that are not links.
+fn format_link_target(
+    metadata: &EntryMetadata,
+    escape: EscapeMode,
+    quote: bool,
+    redaction: Option<&Redactor>,
+) -> String {
+    match &metadata.link_target {
+        Some(target) => {
+            let target = redact(target.to_string_lossy(), redaction);
+            format!(" -> {}", escape_name(&target, escape, quote))
+        }
+        None => String::new(),
     }
+}
 
-    if config.render.show_disk_usage
-        && node.kind == EntryKind::Directory
-        && let Some(usage) = node.disk_usage
-    {
-        let usage_str = if config.render.human_readable {
-            format_size_human(usage)
-        } else {
-            usage.to_string()
-        };
-        parts.push(usage_str);
+/// Escapes and optionally quotes an entry name for text output.
+///
+/// `EscapeMode::Question` replaces non-printable characters with `?`.
+/// `EscapeMode::C` writes them as C-style escapes (`\n`, `\t`, `\x1B`,
+/// `\u202E`), escapes backslashes, and writes trailing spaces as `\x20`
+/// so they remain visible. When quoting in C mode, embedded double quotes
+/// are escaped as well.
+///
+/// # Arguments
+///
+/// * `name` - Entry name or path as displayed
+/// * `mode` - Escaping policy
+/// * `quote` - Whether to wrap the result in double quotes
+///
+/// # Returns
+///
+/// The display form of the name.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::EscapeMode;
+/// use treepp::render::escape_name;
+///
+/// assert_eq!(escape_name("a\nb", EscapeMode::Raw, false), "a\nb");
+/// assert_eq!(escape_name("a\nb", EscapeMode::Question, false), "a?b");
+/// assert_eq!(escape_name("a\nb ", EscapeMode::C, false), "a\\nb\\x20");
+/// assert_eq!(escape_name("main.rs", EscapeMode::Raw, true), "\"main.rs\"");
+/// ```
+#[must_use]
+pub fn escape_name(name: &str, mode: EscapeMode, quote: bool) -> String {
+    let mut out = String::with_capacity(name.len() + 2);
+    if quote {
+        out.push('"');
     }
-
-    if config.render.show_date
-        && let Some(ref modified) = node.metadata.modified
-    {
-        parts.push(format_datetime(modified));
+    push_escaped(&mut out, name, mode, quote, true);
+    if quote {
+        out.push('"');
     }
+    out
+}
 
-    if parts.is_empty() {
-        String::new()
-    } else {
-        format!("        {}", parts.join("  "))
+/// Appends the escaped form of part of a name to `out`.
+///
+/// `at_end` marks the part that ends the name, whose trailing spaces are
+/// written as `\x20` in C mode.
+fn push_escaped(out: &mut String, text: &str, mode: EscapeMode, quote: bool, at_end: bool) {
+    match mode {
+        EscapeMode::Raw => out.push_str(text),
+        EscapeMode::Question => {
+            out.extend(text.chars().map(|c| if is_printable(c) { c } else { '?' }));
+        }
+        EscapeMode::C => {
+            let body = if at_end {
+                text.trim_end_matches(' ')
+            } else {
+                text
+            };
+            for c in body.chars() {
+                match c {
+                    '\\' => out.push_str("\\\\"),
+                    '"' if quote => out.push_str("\\\""),
+                    '\x07' => out.push_str("\\a"),
+                    '\x08' => out.push_str("\\b"),
+                    '\t' => out.push_str("\\t"),
+                    '\n' => out.push_str("\\n"),
+                    '\x0B' => out.push_str("\\v"),
+                    '\x0C' => out.push_str("\\f"),
+                    '\r' => out.push_str("\\r"),
+                    c if is_printable(c) => out.push(c),
+                    c if c.is_ascii() => {
+                        let _ = write!(out, "\\x{:02X}", u32::from(c));
+                    }
+                    c => {
+                        let _ = write!(out, "\\u{:04X}", u32::from(c));
+                    }
+                }
+            }
+            for _ in body.len()..text.len() {
+                out.push_str("\\x20");
+            }
+        }
     }
 }
 
-/// Renders children with tree connectors.
-fn render_children(
-    output: &mut String,
-    node: &TreeNode,
-    chars: &TreeChars,
-    config: &Config,
-    prefix: &str,
-    depth: usize,
-    state: &mut BatchRenderState,
-) {
-    if !depth_within_limit(depth, config.scan.max_depth) {
-        return;
+/// Escapes a displayed name and highlights the given spans of it.
+///
+/// `spans` are byte ranges of `name`, the bare entry name, which `display`
+/// ends with; in full path mode they are shifted to the end of the path.
+/// Overlapping spans are merged. Each span is escaped on its own and wrapped
+/// in terminal escape sequences, outside of the quotes added by `quote`.
+///
+/// # Arguments
+///
+/// * `display` - Name or path as displayed
+/// * `name` - Entry name the spans refer to
+/// * `spans` - Byte ranges of `name` to highlight
+/// * `mode` - Escaping policy
+/// * `quote` - Whether to wrap the result in double quotes
+///
+/// # Returns
+///
+/// The display form of the name with highlighted spans.
+fn highlight_name(
+    display: &str,
+    name: &str,
+    spans: Vec<Range<usize>>,
+    mode: EscapeMode,
+    quote: bool,
+) -> String {
+    if spans.is_empty() || !display.ends_with(name) {
+        return escape_name(display, mode, quote);
+    }
+
+    let offset = display.len() - name.len();
+    let mut out = String::with_capacity(display.len() + 16);
+    if quote {
+        out.push('"');
+    }
+    let mut pos = 0;
+    for span in merge_spans(spans) {
+        let (start, end) = (span.start + offset, span.end + offset);
+        push_escaped(&mut out, &display[pos..start], mode, quote, false);
+        out.push_str(HIGHLIGHT_START);
+        push_escaped(
+            &mut out,
+            &display[start..end],
+            mode,
+            quote,
+            end == display.len(),
+        );
+        out.push_str(HIGHLIGHT_END);
+        pos = end;
     }
+    push_escaped(&mut out, &display[pos..], mode, quote, true);
+    if quote {
+        out.push('"');
+    }
+    out
+}
 
-    let (files, dirs): (Vec<_>, Vec<_>) = get_filtered_children(node, config)
-        .into_iter()
-        .partition(|c| c.kind == EntryKind::File);
+/// Sorts byte ranges and merges the ones that overlap or touch.
+fn merge_spans(mut spans: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    spans.sort_by_key(|span| span.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
 
-    let has_dirs = !dirs.is_empty();
+/// Builds the include pattern matcher when `--highlight` is enabled.
+///
+/// Invalid patterns yield no highlighter; the scan reports them.
+fn build_highlighter(config: &Config) -> Option<MatchHighlighter> {
+    if !config.render.highlight {
+        return None;
+    }
+    MatchHighlighter::from_config(config).ok()
+}
 
-    if config.scan.show_files {
-        let file_prefix = if has_dirs {
-            format!("{}{}", prefix, chars.vertical)
-        } else {
-            format!("{}{}", prefix, chars.space)
-        };
+/// Returns whether a character can be printed without disturbing the layout.
+///
+/// Control characters and bidirectional formatting marks are treated as
+/// non-printable, since the latter can visually reorder the rest of the line.
+fn is_printable(c: char) -> bool {
+    !c.is_control()
+        && !matches!(c, '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
 
-        for file in &files {
-            if !depth_within_limit(depth, config.scan.max_depth) {
-                continue;
-            }
+/// Formats entry metadata (size, date, disk usage), or an empty string if none is shown.
+fn format_entry_meta(node: NodeRef<'_>, config: &Config) -> String {
+    let mut parts = Vec::new();
+    let style = NumberStyle::from_config(config);
 
-            let name = format_entry_name(file, config);
-            let meta = format_entry_meta(file, config);
-            let _ = writeln!(output, "{}{}{}", file_prefix, name, meta);
+    if config.render.show_size && node.kind() == EntryKind::File {
+        parts.push(format_size(
+            node.metadata().size,
+            config.render.human_readable,
+            style,
+        ));
+    }
 
-            state.record_file(file_prefix.clone());
+    if config.render.show_disk_usage
+        && node.kind() == EntryKind::Directory
+        && let Some(usage) = node.disk_usage()
+    {
+        let mut size = format_size(usage, config.render.human_readable, style);
+        if config.render.show_du_percent
+            && let Some(share) = node.share_of_parent()
+        {
+            let _ = write!(size, " ({share:.0}%)");
         }
-
-        if !files.is_empty() && has_dirs {
-            let separator = format!("{}{}", prefix, chars.vertical);
-            let _ = writeln!(output, "{}", separator);
+        if config.render.show_du_delta
+            && let Some(previous) = node.previous_usage()
+            && previous != usage
+        {
+            let (sign, change) = if usage > previous {
+                ('+', usage - previous)
+            } else {
+                ('-', previous - usage)
+            };
+            let change = format_size(change, config.render.human_readable, style);
+            let _ = write!(size, " ({sign}{change})");
         }
-    }
-
-    let dir_count = dirs.len();
-    for (i, dir) in dirs.iter().enumerate() {
-        if !depth_within_limit(depth, config.scan.max_depth) {
-            continue;
+        parts.push(size);
+    }
+
+    let metadata = node.metadata();
+    let dates = [
+        (config.render.show_date, metadata.modified),
+        (config.render.show_created, metadata.created),
+        (config.render.show_accessed, metadata.accessed),
+    ];
+    for (shown, time) in dates {
+        if shown && let Some(ref time) = time {
+            parts.push(format_datetime_with(
+                time,
+                config.render.relative_dates,
+                config.render.date_format.as_deref(),
+            ));
         }
+    }
 
-        let is_last = i == dir_count - 1;
-        let connector = if is_last {
-            chars.last_branch
-        } else {
-            chars.branch
-        };
-
-        let name = format_entry_name(dir, config);
-        let meta = format_entry_meta(dir, config);
-        let _ = writeln!(output, "{}{}{}{}", prefix, connector, name, meta);
+    parts.join("  ")
+}
 
-        state.record_directory();
+/// Formats an entry line of batch output: prefix, name, aligned metadata
+/// and annotations.
+///
+/// With `--truncate`, the name is shortened so that the line fits in the
+/// console.
+fn format_entry_line(
+    prefix: &str,
+    node: NodeRef<'_>,
+    config: &Config,
+    meta_column: Option<usize>,
+    highlighter: Option<&MatchHighlighter>,
+) -> String {
+    let meta = format_entry_meta(node, config);
+    let annotations = format_entry_annotations(node, config);
+    let width = config.render.line_width;
+    let name = fit_name(
+        format_entry_name(node, config, highlighter),
+        display_width(prefix),
+        suffix_width(&meta, &annotations),
+        width,
+    );
+
+    let mut line = format!("{}{}", prefix, name);
+    if !meta.is_empty() {
+        let column = meta_column.map(|column| fit_column(column, &meta, &annotations, width));
+        line.push_str(&meta_gap(&line, column));
+        line.push_str(&meta);
+    }
+    line + &annotations
+}
 
-        if !dir.children.is_empty() && can_recurse(depth, config.scan.max_depth) {
-            let new_prefix = if is_last {
-                format!("{}{}", prefix, chars.space)
-            } else {
-                format!("{}{}", prefix, chars.vertical)
-            };
+/// Formats the annotations following an entry name and its metadata, such
+/// as `[empty]` or `[filtered: ...]`.
+fn format_entry_annotations(node: NodeRef<'_>, config: &Config) -> String {
+    format_cloud_annotation(node.metadata(), config.render.mark_cloud)
+        + &format_empty_annotation(node.metadata(), node.kind(), config.render.mark_empty_files)
+        + &format_collapsed_annotation(node.collapsed())
+        + &format_error_annotation(node.error())
+        + &format_notes(node.annotations())
+        + &format_filter_annotation(node.filtered())
+}
 
-            state.push_level();
-            render_children(output, dir, chars, config, &new_prefix, depth + 1, state);
+/// Writes a run of files in columns (`--columns`), like `ls -C`.
+///
+/// Names run down the columns, which are as many as fit in the console
+/// width left after the prefix.
+///
+/// # Arguments
+///
+/// * `output` - The output being rendered
+/// * `prefix` - Connectors or indentation starting every row
+/// * `files` - The files, in display order
+/// * `config` - Render configuration
+/// * `highlighter` - Include pattern matcher used by `--highlight`
+///
+/// # Returns
+///
+/// `false`, with nothing written, if fewer than two columns fit; the files
+/// are then listed one per line as usual.
+fn write_file_columns(
+    output: &mut String,
+    prefix: &str,
+    files: &[NodeRef<'_>],
+    config: &Config,
+    highlighter: Option<&MatchHighlighter>,
+) -> bool {
+    let cells: Vec<String> = files
+        .iter()
+        .map(|&file| {
+            format_entry_name(file, config, highlighter) + &format_entry_annotations(file, config)
+        })
+        .collect();
+    let widths: Vec<usize> = cells.iter().map(|cell| display_width(cell)).collect();
+    // Lines stop one column short of the width, since consoles wrap when
+    // the last column is written.
+    let available = config
+        .render
+        .columns_width
+        .unwrap_or(DEFAULT_COLUMNS_WIDTH)
+        .saturating_sub(display_width(prefix) + 1);
+
+    let layout = (1..cells.len()).find_map(|rows| {
+        let column_widths: Vec<usize> = widths
+            .chunks(rows)
+            .map(|column| column.iter().copied().max().unwrap_or(0))
+            .collect();
+        let total = column_widths.iter().sum::<usize>() + COLUMN_GAP * (column_widths.len() - 1);
+        (column_widths.len() > 1 && total <= available).then_some((rows, column_widths))
+    });
+    let Some((rows, column_widths)) = layout else {
+        return false;
+    };
 
-            if let Some(trailing) = state.pop_level() {
-                if config.scan.show_files {
-                    let _ = writeln!(output, "{}", trailing);
-                }
+    for row in 0..rows {
+        let mut line = prefix.to_string();
+        let row_cells: Vec<_> = cells.iter().enumerate().skip(row).step_by(rows).collect();
+        for (column, (index, cell)) in row_cells.iter().enumerate() {
+            line.push_str(cell);
+            if column + 1 < row_cells.len() {
+                let padding = column_widths[column] - widths[*index] + COLUMN_GAP;
+                line.push_str(&" ".repeat(padding));
             }
         }
+        let _ = writeln!(output, "{}", line);
     }
+    true
 }
 
-/// Renders children without tree connectors (indent-only mode).
-fn render_children_no_indent(output: &mut String, node: &TreeNode, config: &Config, depth: usize) {
-    if !depth_within_limit(depth, config.scan.max_depth) {
-        return;
-    }
+/// Returns the number of columns after an entry name: the metadata with
+/// the gap before it, and the annotations.
+fn suffix_width(meta: &str, annotations: &str) -> usize {
+    let meta_width = if meta.is_empty() {
+        0
+    } else {
+        META_GAP + display_width(meta)
+    };
+    meta_width + display_width(annotations)
+}
 
-    let (files, dirs): (Vec<_>, Vec<_>) = get_filtered_children(node, config)
-        .into_iter()
-        .partition(|c| c.kind == EntryKind::File);
+/// Moves the metadata column left so that the metadata ends inside `width`
+/// (`--truncate`).
+fn fit_column(column: usize, meta: &str, annotations: &str, width: Option<usize>) -> usize {
+    match width {
+        Some(width) => {
+            column.min(width.saturating_sub(display_width(meta) + display_width(annotations) + 1))
+        }
+        None => column,
+    }
+}
 
-    // Stream mode uses depth directly for indent, starting from 0 for root children
-    // So we need to use (depth - 1) to match stream mode behavior
-    let indent = "  ".repeat(depth.saturating_sub(1));
+/// Shortens an entry name so that its line fits in `width` columns (`--truncate`).
+///
+/// The prefix before the name and the metadata and annotations after it are
+/// kept whole, and the middle of the name gives way. Lines stop one column
+/// short of the width, since consoles wrap when the last column is written.
+/// A shortened name loses its `--highlight` marks.
+///
+/// # Arguments
+///
+/// * `name` - The name as displayed
+/// * `prefix_width` - Columns taken before the name
+/// * `suffix_width` - Columns taken after the name (see `suffix_width`)
+/// * `width` - Console width, or `None` if lines are not truncated
+fn fit_name(
+    name: String,
+    prefix_width: usize,
+    suffix_width: usize,
+    width: Option<usize>,
+) -> String {
+    let Some(width) = width else {
+        return name;
+    };
+    let budget = width
+        .saturating_sub(prefix_width + suffix_width + 1)
+        .max(MIN_TRUNCATED_WIDTH);
+    if display_width(&name) <= budget {
+        return name;
+    }
+    let plain = name.replace(HIGHLIGHT_START, "").replace(HIGHLIGHT_END, "");
+    ellipsize_middle(&plain, budget)
+}
 
-    for file in &files {
-        if !depth_within_limit(depth, config.scan.max_depth) {
-            continue;
+/// Replaces the middle of `text` with `TRUNCATION_MARK` so that it takes at
+/// most `budget` columns.
+///
+/// The start of the name keeps the extra column when the remainder is odd.
+fn ellipsize_middle(text: &str, budget: usize) -> String {
+    let keep = budget.saturating_sub(TRUNCATION_MARK.len());
+    let head_budget = keep.div_ceil(2);
+    let tail_budget = keep - head_budget;
+
+    let mut head_end = 0;
+    let mut width = 0;
+    for (i, c) in text.char_indices() {
+        width += char_width(c);
+        if width > head_budget {
+            break;
         }
-        let name = format_entry_name(file, config);
-        let meta = format_entry_meta(file, config);
-        let _ = writeln!(output, "{}{}{}", indent, name, meta);
+        head_end = i + c.len_utf8();
     }
 
-    for dir in &dirs {
-        if !depth_within_limit(depth, config.scan.max_depth) {
-            continue;
+    let mut tail_start = text.len();
+    width = 0;
+    for (i, c) in text.char_indices().rev() {
+        width += char_width(c);
+        if i < head_end || width > tail_budget {
+            break;
         }
-        let name = format_entry_name(dir, config);
-        let meta = format_entry_meta(dir, config);
-        let _ = writeln!(output, "{}{}{}", indent, name, meta);
+        tail_start = i;
+    }
 
-        if !dir.children.is_empty() && can_recurse(depth, config.scan.max_depth) {
-            render_children_no_indent(output, dir, config, depth + 1);
-        }
+    // Marks and joiners are dropped with the character they belong to.
+    let head = text[..head_end].trim_end_matches('\u{200D}');
+    let tail = text[tail_start..].trim_start_matches(|c| char_width(c) == 0);
+    format!("{head}{TRUNCATION_MARK}{tail}")
+}
+
+/// Returns the spacing between a name and its metadata.
+///
+/// Pads up to `column` when aligning, keeping at least `META_GAP` spaces,
+/// or uses the fixed legacy separator when `column` is `None`.
+fn meta_gap(left: &str, column: Option<usize>) -> String {
+    match column {
+        Some(column) => " ".repeat(column.saturating_sub(display_width(left)).max(META_GAP)),
+        None => LEGACY_META_GAP.to_string(),
     }
 }
 
-/// Gets filtered children based on configuration.
-fn get_filtered_children<'a>(node: &'a TreeNode, config: &Config) -> Vec<&'a TreeNode> {
-    node.children
-        .iter()
-        .filter(|c| config.scan.show_files || c.kind == EntryKind::Directory)
-        .collect()
+/// Computes the column at which aligned metadata starts in batch output.
+///
+/// Measures every line that will be rendered with metadata, so the column
+/// is just past the widest of them.
+///
+/// # Returns
+///
+/// `None` with `--no-align` or when no metadata is shown.
+fn batch_meta_column(
+    node: NodeRef<'_>,
+    config: &Config,
+    chars: &TreeChars,
+    root_width: usize,
+) -> Option<usize> {
+    let render = &config.render;
+    let shows_meta = render.show_size
+        || render.show_date
+        || render.show_created
+        || render.show_accessed
+        || render.show_disk_usage;
+    if render.no_align || !shows_meta {
+        return None;
+    }
+
+    let mut widest = root_width;
+    measure_meta_lines(node, config, chars, 1, &mut widest);
+    Some(widest + META_GAP)
 }
 
-/// Removes trailing line containing only pipe characters and whitespace.
-fn remove_trailing_pipe_only_line(mut output: String) -> String {
-    let trimmed = output.trim_end_matches('\n');
-    if let Some(last_newline_pos) = trimmed.rfind('\n') {
-        let last_line = &trimmed[last_newline_pos + 1..];
+/// Records the widest name part of the lines below `node` that carry metadata.
+fn measure_meta_lines(
+    node: NodeRef<'_>,
+    config: &Config,
+    chars: &TreeChars,
+    depth: usize,
+    widest: &mut usize,
+) {
+    if !depth_within_limit(depth, config.scan.max_depth) {
+        return;
+    }
 
-        let has_pipe = last_line.chars().any(|c| c == '|' || c == '│');
-        let only_pipes_and_whitespace = !last_line.is_empty()
-            && last_line
-            .chars()
-            .all(|c| c == '|' || c == '│' || c.is_whitespace());
+    let (indent, file_connector, dir_connector) = if config.render.flat {
+        (0, 0, 0)
+    } else if config.render.no_indent {
+        (2 * (depth - 1), 0, 0)
+    } else {
+        let level = display_width(chars.vertical);
+        (level * (depth - 1), level, display_width(chars.branch))
+    };
 
-        if has_pipe && only_pipes_and_whitespace {
-            output.truncate(last_newline_pos + 1);
+    for child in get_filtered_children(node, config) {
+        let is_dir = child.kind() == EntryKind::Directory;
+        if !format_entry_meta(child, config).is_empty() {
+            let connector = if is_dir {
+                dir_connector
+            } else {
+                file_connector
+            };
+            let width = indent + connector + display_width(&format_entry_name(child, config, None));
+            *widest = (*widest).max(width);
+        }
+        if is_dir && child.has_children() && can_recurse(depth, config.scan.max_depth) {
+            measure_meta_lines(child, config, chars, depth + 1, widest);
         }
     }
-    output
 }
 
-// ============================================================================
-// Unit Tests
-// ============================================================================
+/// Returns the number of terminal columns a string occupies.
+///
+/// Widths follow Unicode's East Asian Width and emoji properties: CJK
+/// characters and emoji take two columns, combining marks, joiners and
+/// directional marks none, and everything else, right-to-left scripts
+/// included, one column. An emoji sequence joined by U+200D counts as the
+/// single glyph it is drawn as. Box-drawing characters count as two
+/// columns, as in the consoles the Unicode tree characters are laid out
+/// for, which keeps `│  ` as wide as the four-space indent.
+fn display_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut rest = s;
+    while let Some(start) = rest.find("\x1b[") {
+        // `--highlight` escape sequences take no columns.
+        width += text_width(&rest[..start]);
+        let sequence = &rest[start + 2..];
+        let end = sequence
+            .find(|c| matches!(c, '\x40'..='\x7E'))
+            .map_or(sequence.len(), |i| i + 1);
+        rest = &sequence[end..];
+    }
+    width + text_width(rest)
+}
+
+/// Removes the `--highlight` and `--level-guides` escape sequences from a string.
+fn strip_escape_sequences(s: &str) -> String {
+    let mut plain = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("\x1b[") {
+        plain.push_str(&rest[..start]);
+        let sequence = &rest[start + 2..];
+        let end = sequence
+            .find(|c| matches!(c, '\x40'..='\x7E'))
+            .map_or(sequence.len(), |i| i + 1);
+        rest = &sequence[end..];
+    }
+    plain + rest
+}
+
+/// Returns the number of terminal columns a string without escape
+/// sequences occupies.
+fn text_width(s: &str) -> usize {
+    s.width() + s.chars().filter(|&c| is_box_drawing(c)).count()
+}
+
+/// Returns the number of terminal columns a character occupies on its own.
+fn char_width(c: char) -> usize {
+    if is_box_drawing(c) {
+        2
+    } else {
+        c.width().unwrap_or(0)
+    }
+}
+
+/// Returns whether a character is from the Box Drawing block.
+const fn is_box_drawing(c: char) -> bool {
+    matches!(c, '\u{2500}'..='\u{257F}')
+}
+
+/// Formats the `--mark-cloud` annotation for a cloud-only placeholder.
+///
+/// Returns an empty string for entries that are stored locally or when
+/// marking is disabled.
+fn format_cloud_annotation(metadata: &EntryMetadata, mark_cloud: bool) -> String {
+    if mark_cloud && metadata.cloud_only {
+        " [cloud]".to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// Formats the `--mark-empty-files` annotation for a zero-byte file.
+///
+/// Returns an empty string for directories, links, files with content or
+/// when marking is disabled.
+fn format_empty_annotation(metadata: &EntryMetadata, kind: EntryKind, mark_empty: bool) -> String {
+    if mark_empty && metadata.is_empty_file(kind) {
+        " [empty]".to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// Formats the `--filelimit` annotation for a directory that was not descended into.
+///
+/// Returns an empty string for directories that are not collapsed.
+fn format_collapsed_annotation(collapsed: Option<usize>) -> String {
+    match collapsed {
+        Some(count) => format!(" [{} entries]", count),
+        None => String::new(),
+    }
+}
+
+/// Formats the annotation for a directory whose content could not be read.
+///
+/// Returns an empty string for readable entries.
+fn format_error_annotation(error: Option<EntryError>) -> String {
+    match error {
+        Some(error) => format!(" [{}]", error),
+        None => String::new(),
+    }
+}
+
+/// Formats the notes added to an entry by a `TreeTransform`.
+///
+/// Each note is shown in its own brackets; returns an empty string for
+/// entries without notes.
+fn format_notes(notes: &[String]) -> String {
+    notes.iter().map(|note| format!(" [{}]", note)).collect()
+}
+
+/// Formats the `--show-filtered` annotation for an entry.
+///
+/// Returns an empty string for entries that are not filtered.
+fn format_filter_annotation(filtered: Option<&FilterReason>) -> String {
+    match filtered {
+        Some(reason) => format!("  [filtered: {}]", reason),
+        None => String::new(),
+    }
+}
+
+/// Formats the marker for entries left out by `--max-entries`.
+fn format_omitted_marker(count: usize) -> String {
+    format!("[+{} more]", count)
+}
+
+/// Formats the marker for directories hidden by `--du-threshold`.
+fn format_small_dirs_marker(count: usize) -> String {
+    let noun = if count == 1 { "dir" } else { "dirs" };
+    format!("[+ {} small {}]", count, noun)
+}
+
+/// Returns the markers that close the listing of `node`, in display order.
+fn closing_markers(node: NodeRef<'_>) -> Vec<String> {
+    let mut markers = Vec::new();
+    if node.small_dirs() > 0 {
+        markers.push(format_small_dirs_marker(node.small_dirs()));
+    }
+    if node.omitted() > 0 {
+        markers.push(format_omitted_marker(node.omitted()));
+    }
+    markers
+}
+
+/// Renders children with tree connectors.
+fn render_children(
+    output: &mut String,
+    node: NodeRef<'_>,
+    chars: &TreeChars,
+    config: &Config,
+    prefix: &str,
+    depth: usize,
+    state: &mut BatchRenderState,
+) {
+    if !depth_within_limit(depth, config.scan.max_depth) {
+        return;
+    }
+
+    let (files, dirs) = partition_children(node, config);
+
+    // The `--du-threshold` and `--max-entries` markers are drawn as the last
+    // branches of the level.
+    let markers = closing_markers(node);
+    let has_dirs = !dirs.is_empty() || !markers.is_empty();
+
+    if config.scan.show_files {
+        let file_prefix = if has_dirs {
+            format!("{}{}", prefix, chars.vertical)
+        } else {
+            format!("{}{}", prefix, chars.space)
+        };
+
+        for (i, (section, members)) in file_sections(&files, config).iter().enumerate() {
+            if let Some(section) = section {
+                if i > 0 {
+                    let _ = writeln!(output, "{}", file_prefix);
+                }
+                let label = i18n::section_label(config.render.language, *section);
+                let _ = writeln!(output, "{}[{}]", file_prefix, label);
+            }
+
+            if config.render.columns
+                && write_file_columns(
+                    output,
+                    &file_prefix,
+                    members,
+                    config,
+                    state.highlighter.as_ref(),
+                )
+            {
+                state.record_file(file_prefix.clone());
+                continue;
+            }
+
+            for file in members {
+                if !depth_within_limit(depth, config.scan.max_depth) {
+                    continue;
+                }
+
+                let line = format_entry_line(
+                    &file_prefix,
+                    *file,
+                    config,
+                    state.meta_column,
+                    state.highlighter.as_ref(),
+                );
+                let _ = writeln!(output, "{}", line);
+
+                state.record_file(file_prefix.clone());
+            }
+        }
+
+        if !files.is_empty() && has_dirs {
+            let separator = format!("{}{}", prefix, chars.vertical);
+            let _ = writeln!(output, "{}", separator);
+            if config.render.group_by != GroupBy::None && !dirs.is_empty() {
+                let label = i18n::section_label(config.render.language, Section::Directories);
+                let _ = writeln!(output, "{}[{}]", separator, label);
+            }
+        }
+    }
+
+    let dir_count = dirs.len();
+    for (i, dir) in dirs.iter().enumerate() {
+        if !depth_within_limit(depth, config.scan.max_depth) {
+            continue;
+        }
+
+        let is_last = i == dir_count - 1 && markers.is_empty();
+        let connector = if is_last {
+            chars.last_branch
+        } else {
+            chars.branch
+        };
+
+        let dir_prefix = format!("{}{}", prefix, connector);
+        let line = format_entry_line(
+            &dir_prefix,
+            *dir,
+            config,
+            state.meta_column,
+            state.highlighter.as_ref(),
+        );
+        let _ = writeln!(output, "{}", line);
+
+        state.record_directory();
+
+        if dir.has_children() && can_recurse(depth, config.scan.max_depth) {
+            let new_prefix = if is_last {
+                format!("{}{}", prefix, chars.space)
+            } else {
+                format!("{}{}", prefix, chars.vertical)
+            };
+
+            state.push_level();
+            render_children(output, *dir, chars, config, &new_prefix, depth + 1, state);
+
+            if let Some(trailing) = state.pop_level()
+                && config.scan.show_files
+            {
+                let _ = writeln!(output, "{}", trailing);
+            }
+        }
+    }
+
+    for (i, marker) in markers.iter().enumerate() {
+        let connector = if i == markers.len() - 1 {
+            chars.last_branch
+        } else {
+            chars.branch
+        };
+        let _ = writeln!(output, "{}{}{}", prefix, connector, marker);
+        state.record_directory();
+    }
+}
+
+/// Renders children without tree connectors (indent-only mode).
+fn render_children_no_indent(
+    output: &mut String,
+    node: NodeRef<'_>,
+    config: &Config,
+    depth: usize,
+    state: &BatchRenderState,
+) {
+    if !depth_within_limit(depth, config.scan.max_depth) {
+        return;
+    }
+
+    let (files, dirs) = partition_children(node, config);
+
+    // Stream mode uses depth directly for indent, starting from 0 for root children
+    // So we need to use (depth - 1) to match stream mode behavior
+    let indent = if config.render.flat {
+        String::new()
+    } else {
+        "  ".repeat(depth.saturating_sub(1))
+    };
+
+    for (section, members) in file_sections(&files, config) {
+        if let Some(section) = section {
+            let label = i18n::section_label(config.render.language, section);
+            let _ = writeln!(output, "{}[{}]", indent, label);
+        }
+        if config.render.columns
+            && write_file_columns(
+                output,
+                &indent,
+                &members,
+                config,
+                state.highlighter.as_ref(),
+            )
+        {
+            continue;
+        }
+        for file in &members {
+            if !depth_within_limit(depth, config.scan.max_depth) {
+                continue;
+            }
+            let line = format_entry_line(
+                &indent,
+                *file,
+                config,
+                state.meta_column,
+                state.highlighter.as_ref(),
+            );
+            let _ = writeln!(output, "{}", line);
+        }
+    }
+
+    if config.render.group_by != GroupBy::None && !files.is_empty() && !dirs.is_empty() {
+        let label = i18n::section_label(config.render.language, Section::Directories);
+        let _ = writeln!(output, "{}[{}]", indent, label);
+    }
+
+    for dir in &dirs {
+        if !depth_within_limit(depth, config.scan.max_depth) {
+            continue;
+        }
+        let highlighter = state.highlighter.as_ref();
+        let line = format_entry_line(&indent, *dir, config, state.meta_column, highlighter);
+        let _ = writeln!(output, "{}", line);
+
+        if dir.has_children() && can_recurse(depth, config.scan.max_depth) {
+            render_children_no_indent(output, *dir, config, depth + 1, state);
+        }
+    }
+
+    if !config.render.flat {
+        for marker in closing_markers(node) {
+            let _ = writeln!(output, "{}{}", indent, marker);
+        }
+    }
+}
+
+/// Splits the files of a directory into the sections of `--group-by`.
+///
+/// Empty sections are dropped. Without grouping, all files form a single
+/// section without a heading.
+fn file_sections<'a>(
+    files: &[NodeRef<'a>],
+    config: &Config,
+) -> Vec<(Option<Section>, Vec<NodeRef<'a>>)> {
+    match config.render.group_by {
+        GroupBy::None => vec![(None, files.to_vec())],
+        GroupBy::Type => Section::FILE_SECTIONS
+            .iter()
+            .map(|&section| {
+                let members: Vec<_> = files
+                    .iter()
+                    .copied()
+                    .filter(|file| Section::of_file(file.name()) == section)
+                    .collect();
+                (Some(section), members)
+            })
+            .filter(|(_, members)| !members.is_empty())
+            .collect(),
+    }
+}
+
+/// Splits the displayed children of a directory into the files listed
+/// before its subdirectories and the entries drawn as branches.
+///
+/// With `--interleave`, files are drawn as branches like directories, as
+/// GNU `tree` does, so every child is in the second list, in sort order.
+fn partition_children<'a>(
+    node: NodeRef<'a>,
+    config: &Config,
+) -> (Vec<NodeRef<'a>>, Vec<NodeRef<'a>>) {
+    let children = get_filtered_children(node, config);
+    if config.render.interleave {
+        return (Vec::new(), children);
+    }
+    children
+        .into_iter()
+        .partition(|c| c.kind() == EntryKind::File)
+}
+
+/// Gets filtered children based on configuration.
+fn get_filtered_children<'a>(node: NodeRef<'a>, config: &Config) -> Vec<NodeRef<'a>> {
+    node.children()
+        .filter(|c| config.scan.show_files || c.kind() == EntryKind::Directory)
+        .collect()
+}
+
+/// Cuts the output after the last complete line that fits in `limit` bytes.
+///
+/// # Returns
+///
+/// `true` if the output was longer than the limit and has been cut.
+fn truncate_at_line_boundary(output: &mut String, limit: u64) -> bool {
+    let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+    if output.len() <= limit {
+        return false;
+    }
+    let cut = output.as_bytes()[..limit]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |pos| pos + 1);
+    output.truncate(cut);
+    true
+}
+
+/// Decorates the tree connectors at the start of each line (`--level-guides`).
+///
+/// A line starts with one connector segment per level: vertical lines and
+/// blanks for the levels above the entry, then its branch. Guides are
+/// applied to these segments only, so the rest of the line, and the width
+/// of every segment, stay as rendered.
+///
+/// # Arguments
+///
+/// * `text` - Rendered lines, each ending with a newline except the last
+/// * `chars` - The characters the connectors were drawn with
+/// * `guides` - The decoration to apply
+///
+/// # Returns
+///
+/// The decorated lines.
+fn decorate_level_guides(text: String, chars: &TreeChars, guides: LevelGuides) -> String {
+    if guides == LevelGuides::None {
+        return text;
+    }
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let mut rest = line;
+        let mut level = 0;
+        loop {
+            level += 1;
+            if let Some(after) = rest.strip_prefix(chars.vertical) {
+                output.push_str(&decorate_segment(chars.vertical, level, guides));
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix(chars.space) {
+                output.push_str(chars.space);
+                rest = after;
+            } else {
+                // The branch ends the connectors; the name follows.
+                for branch in [chars.branch, chars.last_branch] {
+                    if let Some(after) = rest.strip_prefix(branch) {
+                        output.push_str(&decorate_segment(branch, level, guides));
+                        rest = after;
+                        break;
+                    }
+                }
+                break;
+            }
+        }
+        output.push_str(rest);
+    }
+    output
+}
+
+/// Decorates one connector segment of the given level (1 for the children
+/// of the root).
+///
+/// Numbers replace the blanks after a vertical line; a number too long to
+/// fit leaves the line plain. Branches are only colored.
+fn decorate_segment(segment: &str, level: usize, guides: LevelGuides) -> String {
+    match guides {
+        LevelGuides::None => segment.to_string(),
+        LevelGuides::Number => {
+            let mut chars = segment.chars();
+            let Some(line) = chars.next() else {
+                return String::new();
+            };
+            let blanks = chars.as_str();
+            let number = level.to_string();
+            if blanks.is_empty() || !blanks.trim().is_empty() || number.len() > blanks.len() {
+                return segment.to_string();
+            }
+            format!("{line}{number:<width$}", width = blanks.len())
+        }
+        LevelGuides::Color => {
+            let color = LEVEL_COLORS[(level - 1) % LEVEL_COLORS.len()];
+            format!("{color}{segment}{LEVEL_COLOR_END}")
+        }
+    }
+}
+
+/// Removes trailing line containing only pipe characters and whitespace.
+///
+/// Level numbers and colors of `--level-guides` do not count as content.
+fn remove_trailing_pipe_only_line(mut output: String) -> String {
+    let trimmed = output.trim_end_matches('\n');
+    if let Some(last_newline_pos) = trimmed.rfind('\n') {
+        let last_line = strip_escape_sequences(&trimmed[last_newline_pos + 1..]);
+
+        let has_pipe = last_line.chars().any(|c| c == '|' || c == '│');
+        let only_pipes_and_whitespace = !last_line.is_empty()
+            && last_line
+                .chars()
+                .all(|c| c == '|' || c == '│' || c.is_whitespace() || c.is_ascii_digit());
+
+        if has_pipe && only_pipes_and_whitespace {
+            output.truncate(last_newline_pos + 1);
+        }
+    }
+    output
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::matching::RejectionCounts;
     use crate::scan::EntryMetadata;
     use std::path::PathBuf;
 
@@ -1533,10 +3686,12 @@ mod tests {
         let file_count = tree.count_files();
 
         ScanStats {
-            tree,
+            tree: TreeArena::from_tree(&tree),
             duration: Duration::from_millis(100),
             directory_count,
             file_count,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         }
     }
 
@@ -1565,8 +3720,7 @@ mod tests {
 
     #[test]
     fn should_preserve_trailing_whitespace_in_banner() {
-        let output =
-            "卷 系统 的文件夹 PATH 列表  \n  卷序列号为 2810-11C7\nC:.\n没有子文件夹  \n";
+        let output = "卷 系统 的文件夹 PATH 列表  \n  卷序列号为 2810-11C7\nC:.\n没有子文件夹  \n";
         let banner = WinBanner::parse(output).expect("should parse successfully");
 
         assert_eq!(banner.volume_line, "卷 系统 的文件夹 PATH 列表  ");
@@ -1585,27 +3739,81 @@ mod tests {
     }
 
     #[test]
-    fn should_fail_parsing_with_insufficient_lines() {
-        assert!(WinBanner::parse("only one line").is_err());
-        assert!(WinBanner::parse("line1\nline2").is_err());
-        assert!(WinBanner::parse("line1\nline2\nline3").is_err());
+    fn should_build_banner_from_bundled_strings() {
+        let volume = winvol::VolumeInfo {
+            label: "系统".to_string(),
+            serial: 0x2810_11C7,
+        };
+        let expected = WinBanner::parse(
+            "卷 系统 的文件夹 PATH 列表\n卷序列号为 2810-11C7\nC:.\n没有子文件夹 ",
+        )
+        .unwrap();
+        assert_eq!(BANNER_ZH_CN.banner(&volume), expected);
+
+        let volume = winvol::VolumeInfo {
+            label: "OS".to_string(),
+            serial: 0xABCD_1234,
+        };
+        let expected = WinBanner::parse("Folder PATH listing for volume OS\nVolume serial number is ABCD-1234\nC:.\nNo subfolders exist ").unwrap();
+        assert_eq!(BANNER_EN.banner(&volume), expected);
     }
 
     #[test]
-    fn should_parse_exactly_4_lines() {
-        let output = "line1\nline2\nline3\nline4";
-        let banner = WinBanner::parse(output).expect("should parse 4 lines");
-
-        assert_eq!(banner.volume_line, "line1");
-        assert_eq!(banner.serial_line, "line2");
-        assert_eq!(banner.no_subfolder, "line4");
+    fn should_use_unlabeled_line_for_volume_without_label() {
+        let volume = winvol::VolumeInfo {
+            label: String::new(),
+            serial: 1,
+        };
+        assert_eq!(BANNER_EN.banner(&volume).volume_line, "Folder PATH listing");
+        assert_eq!(BANNER_ZH_CN.banner(&volume).volume_line, "文件夹 PATH 列表");
     }
 
     #[test]
-    fn should_parse_more_than_4_lines() {
-        let output = "line1\nline2\nline3\nline4\nline5\nline6";
-        let banner = WinBanner::parse(output).expect("should parse with extra lines");
-
+    fn should_select_banner_strings_by_language() {
+        assert_eq!(
+            BannerStrings::for_language(0x0409).unwrap().unlabeled,
+            BANNER_EN.unlabeled
+        );
+        assert_eq!(
+            BannerStrings::for_language(0x0809).unwrap().unlabeled,
+            BANNER_EN.unlabeled
+        );
+        assert_eq!(
+            BannerStrings::for_language(0x0804).unwrap().unlabeled,
+            BANNER_ZH_CN.unlabeled
+        );
+        assert!(
+            BannerStrings::for_language(0x0407).is_none(),
+            "德语应回退到 tree 命令"
+        );
+        assert!(
+            BannerStrings::for_language(0x0404).is_none(),
+            "繁体中文应回退到 tree 命令"
+        );
+    }
+
+    #[test]
+    fn should_fail_parsing_with_insufficient_lines() {
+        assert!(WinBanner::parse("only one line").is_err());
+        assert!(WinBanner::parse("line1\nline2").is_err());
+        assert!(WinBanner::parse("line1\nline2\nline3").is_err());
+    }
+
+    #[test]
+    fn should_parse_exactly_4_lines() {
+        let output = "line1\nline2\nline3\nline4";
+        let banner = WinBanner::parse(output).expect("should parse 4 lines");
+
+        assert_eq!(banner.volume_line, "line1");
+        assert_eq!(banner.serial_line, "line2");
+        assert_eq!(banner.no_subfolder, "line4");
+    }
+
+    #[test]
+    fn should_parse_more_than_4_lines() {
+        let output = "line1\nline2\nline3\nline4\nline5\nline6";
+        let banner = WinBanner::parse(output).expect("should parse with extra lines");
+
         assert_eq!(banner.volume_line, "line1");
         assert_eq!(banner.serial_line, "line2");
         assert_eq!(banner.no_subfolder, "line4");
@@ -1670,6 +3878,50 @@ mod tests {
         assert_eq!(format_size_human(1024 * 1024), "1.0 MB");
     }
 
+    #[test]
+    fn should_format_si_units() {
+        let si = NumberStyle {
+            si: true,
+            ..NumberStyle::PLAIN
+        };
+        assert_eq!(format_size_human_with(999, si), "999 B");
+        assert_eq!(format_size_human_with(1000, si), "1.0 kB");
+        assert_eq!(format_size_human_with(1_500_000, si), "1.5 MB");
+        assert_eq!(
+            format_size_human_with(2_000_000_000_000_000, si),
+            "2000.0 TB"
+        );
+    }
+
+    #[test]
+    fn should_group_digits_of_sizes() {
+        let comma = NumberStyle {
+            group: Some(','),
+            ..NumberStyle::PLAIN
+        };
+        let period = NumberStyle {
+            group: Some('.'),
+            decimal: ',',
+            si: false,
+        };
+        assert_eq!(format_size(1234567890, false, comma), "1,234,567,890");
+        assert_eq!(format_size(999, false, comma), "999");
+        assert_eq!(format_size(1234567890, false, period), "1.234.567.890");
+        assert_eq!(format_size(1023, true, comma), "1,023 B");
+        assert_eq!(format_size(1536, true, period), "1,5 KB");
+        assert_eq!(format_size(1024 * 1024 - 1, true, comma), "1,024.0 KB");
+    }
+
+    #[test]
+    fn should_parse_regional_separators() {
+        let output = "\r\nHKEY_CURRENT_USER\\Control Panel\\International\r\n    \
+            sDecimal    REG_SZ    ,\r\n    sThousand    REG_SZ     \r\n";
+        assert_eq!(parse_international(output), (Some(' '), ','));
+        let no_grouping = "    sThousand    REG_SZ    \r\n    sDecimal    REG_SZ    .\r\n";
+        assert_eq!(parse_international(no_grouping), (None, '.'));
+        assert_eq!(parse_international(""), (Some(','), '.'));
+    }
+
     // ------------------------------------------------------------------------
     // format_datetime Tests
     // ------------------------------------------------------------------------
@@ -1704,6 +3956,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_format_datetime_with_pattern() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(86400 * 400);
+        assert_eq!(format_datetime_with(&time, false, Some("%Y")), "1971");
+        assert_eq!(
+            format_datetime_with(&time, false, Some("%Q")),
+            format_datetime(&time)
+        );
+    }
+
+    #[test]
+    fn should_format_relative_dates() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let ago = |secs| format_relative(&(now - Duration::from_secs(secs)), now);
+        assert_eq!(ago(0), "just now");
+        assert_eq!(ago(59), "just now");
+        assert_eq!(ago(60), "1 minute ago");
+        assert_eq!(ago(2 * 3600 + 5), "2 hours ago");
+        assert_eq!(ago(50 * 3600), "2 days ago");
+        assert_eq!(ago(14 * 86400), "2 weeks ago");
+        assert_eq!(ago(45 * 86400), "1 month ago");
+        assert_eq!(ago(800 * 86400), "2 years ago");
+        assert_eq!(
+            format_relative(&(now + Duration::from_secs(3 * 86400)), now),
+            "in 3 days"
+        );
+    }
+
     // ------------------------------------------------------------------------
     // TreeChars Tests
     // ------------------------------------------------------------------------
@@ -1755,6 +4035,9 @@ mod tests {
             is_last: true,
             is_file: false,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
 
         let line = renderer.render_entry(&entry);
@@ -1777,6 +4060,9 @@ mod tests {
             is_last: false,
             is_file: false,
             has_more_dirs: true,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
 
         let line = renderer.render_entry(&entry);
@@ -1799,6 +4085,9 @@ mod tests {
             is_last: true,
             is_file: false,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
 
         let line = renderer.render_entry(&entry);
@@ -1821,6 +4110,9 @@ mod tests {
             is_last: true,
             is_file: true,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
 
         let line = renderer.render_entry(&entry);
@@ -1849,6 +4141,9 @@ mod tests {
             is_last: true,
             is_file: true,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
 
         let line = renderer.render_entry(&entry);
@@ -1875,6 +4170,9 @@ mod tests {
             is_last: true,
             is_file: true,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
 
         let line = renderer.render_entry(&entry);
@@ -1898,6 +4196,9 @@ mod tests {
             is_last: true,
             is_file: false,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
 
         let line = renderer.render_entry(&entry);
@@ -1921,6 +4222,9 @@ mod tests {
             is_last: true,
             is_file: false,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
 
         let line = renderer.render_entry(&entry);
@@ -1990,6 +4294,9 @@ mod tests {
             is_last: true,
             is_file: true,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
         let _ = renderer.render_entry(&entry);
 
@@ -2015,6 +4322,9 @@ mod tests {
             is_last: true,
             is_file: false,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
         let _ = renderer.render_entry(&entry);
 
@@ -2232,6 +4542,33 @@ mod tests {
         assert_eq!(drive, 'D');
     }
 
+    #[test]
+    fn should_format_implicit_unc_path_as_full_path() {
+        let path = Path::new(r"\\server\share\Project");
+        let result = format_root_path_display(path, false).unwrap();
+        assert_eq!(result, r"\\SERVER\SHARE\PROJECT");
+    }
+
+    #[test]
+    fn should_strip_verbatim_prefix_from_explicit_path() {
+        let path = Path::new(r"\\?\C:\Deep\Tree");
+        let result = format_root_path_display(path, true).unwrap();
+        assert_eq!(result, r"C:\DEEP\TREE");
+    }
+
+    #[test]
+    fn should_extract_drive_letter_from_verbatim_path() {
+        let path = Path::new(r"\\?\f:\data");
+        let drive = extract_drive_letter(path).unwrap();
+        assert_eq!(drive, 'F');
+    }
+
+    #[test]
+    fn should_fail_extracting_drive_from_unc_path() {
+        let path = Path::new(r"\\server\share");
+        assert!(extract_drive_letter(path).is_err());
+    }
+
     #[test]
     fn should_fail_extracting_drive_from_relative_path() {
         let path = Path::new("relative/path");
@@ -2256,7 +4593,7 @@ mod tests {
             EntryMetadata::default(),
         ));
 
-        assert!(tree_has_subdirectories(&root));
+        assert!(tree_has_subdirectories(TreeArena::from_tree(&root).root()));
     }
 
     #[test]
@@ -2272,7 +4609,7 @@ mod tests {
             EntryMetadata::default(),
         ));
 
-        assert!(!tree_has_subdirectories(&root));
+        assert!(!tree_has_subdirectories(TreeArena::from_tree(&root).root()));
     }
 
     #[test]
@@ -2283,7 +4620,7 @@ mod tests {
             EntryMetadata::default(),
         );
 
-        assert!(!tree_has_subdirectories(&root));
+        assert!(!tree_has_subdirectories(TreeArena::from_tree(&root).root()));
     }
 
     #[test]
@@ -2304,7 +4641,7 @@ mod tests {
             EntryMetadata::default(),
         ));
 
-        assert!(tree_has_subdirectories(&root));
+        assert!(tree_has_subdirectories(TreeArena::from_tree(&root).root()));
     }
 
     // ------------------------------------------------------------------------
@@ -2333,10 +4670,11 @@ mod tests {
         let mut config = Config::default();
         config.scan.show_files = false;
 
-        let filtered = get_filtered_children(&root, &config);
+        let tree = TreeArena::from_tree(&root);
+        let filtered = get_filtered_children(tree.root(), &config);
 
         assert_eq!(filtered.len(), 1);
-        assert_eq!(filtered[0].name, "subdir");
+        assert_eq!(filtered[0].name(), "subdir");
     }
 
     #[test]
@@ -2361,7 +4699,8 @@ mod tests {
         let mut config = Config::default();
         config.scan.show_files = true;
 
-        let filtered = get_filtered_children(&root, &config);
+        let tree = TreeArena::from_tree(&root);
+        let filtered = get_filtered_children(tree.root(), &config);
 
         assert_eq!(filtered.len(), 2);
     }
@@ -2455,10 +4794,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 1,
             file_count: 1,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -2518,10 +4859,12 @@ mod tests {
         config.render.show_size = true;
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 1,
             file_count: 0,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let result = render(&stats, &config);
@@ -2565,10 +4908,12 @@ mod tests {
         let directory_count = root.count_directories();
         let file_count = root.count_files();
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(1),
             directory_count,
             file_count,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -2588,6 +4933,218 @@ mod tests {
         assert!(rendered.contains("5"));
     }
 
+    #[test]
+    fn should_follow_disk_usage_with_share_of_parent() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        for (name, size) in [("big", 30), ("small", 10)] {
+            let mut dir = TreeNode::new(
+                PathBuf::from("root").join(name),
+                EntryKind::Directory,
+                EntryMetadata::default(),
+            );
+            dir.children.push(TreeNode::new(
+                PathBuf::from("root").join(name).join("data.bin"),
+                EntryKind::File,
+                EntryMetadata {
+                    size,
+                    ..Default::default()
+                },
+            ));
+            root.children.push(dir);
+        }
+        root.compute_disk_usage();
+
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.render.show_disk_usage = true;
+        config.render.show_du_percent = true;
+        let output = render_tree_only(&root, &config);
+        let lines: Vec<_> = output.lines().collect();
+        assert!(
+            lines[0].ends_with("40"),
+            "根目录没有上级，不应显示百分比: {output}"
+        );
+        assert!(lines[1].ends_with("30 (75%)"), "{output}");
+        assert!(lines[2].ends_with("10 (25%)"), "{output}");
+    }
+
+    #[test]
+    fn should_render_du_delta_after_disk_usage() {
+        let mut tree = TreeArena::new(PathBuf::from("root"));
+        let root = tree.add(None, "root", EntryKind::Directory, EntryMetadata::default());
+        for (name, size, previous) in [("grown", 30, 10), ("shrunk", 10, 25), ("same", 5, 5)] {
+            let dir = tree.add(
+                Some(root),
+                name,
+                EntryKind::Directory,
+                EntryMetadata::default(),
+            );
+            let file = EntryMetadata {
+                size,
+                ..Default::default()
+            };
+            tree.add(Some(dir), "data.bin", EntryKind::File, file);
+            tree.set_previous_usage(dir, Some(previous));
+        }
+        tree.compute_disk_usage();
+
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.render.show_disk_usage = true;
+        config.render.show_du_delta = true;
+        let metas: Vec<_> = tree
+            .root()
+            .children()
+            .map(|child| format_entry_meta(child, &config))
+            .collect();
+        assert_eq!(
+            metas,
+            ["30 (+20)", "10 (-15)", "5"],
+            "不变的目录不应显示变化"
+        );
+        assert_eq!(
+            format_entry_meta(tree.root(), &config),
+            "45",
+            "没有上次大小时不应显示变化"
+        );
+    }
+
+    // ------------------------------------------------------------------------
+    // File Columns Tests
+    // ------------------------------------------------------------------------
+
+    fn create_wide_dir(names: &[&str]) -> TreeNode {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        for name in names {
+            root.children.push(TreeNode::new(
+                PathBuf::from("root").join(name),
+                EntryKind::File,
+                EntryMetadata::default(),
+            ));
+        }
+        root
+    }
+
+    #[test]
+    fn should_lay_files_out_down_columns() {
+        let root = create_wide_dir(&["a.rs", "b.rs", "c.rs", "d.rs", "e.rs", "long_name.rs"]);
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.columns = true;
+        config.render.columns_width = Some(30);
+
+        let output = render_tree_only(&root, &config);
+        let lines: Vec<_> = output.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            ["    a.rs  c.rs  e.rs", "    b.rs  d.rs  long_name.rs"],
+            "名称应按列自上而下排列"
+        );
+    }
+
+    #[test]
+    fn should_list_files_one_per_line_when_columns_do_not_fit() {
+        let root = create_wide_dir(&["first_long_name.rs", "second_long_name.rs"]);
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.columns = true;
+        config.render.columns_width = Some(30);
+
+        let output = render_tree_only(&root, &config);
+        assert_eq!(output.lines().count(), 3, "{output}");
+    }
+
+    // ------------------------------------------------------------------------
+    // Interleave Tests
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn should_draw_files_as_branches_when_interleaved() {
+        let tree = create_test_tree();
+        let mut config = Config::with_root(PathBuf::from("test_root"));
+        config.scan.show_files = true;
+        config.render.interleave = true;
+
+        let output = render_tree_only(&tree, &config);
+        let lines: Vec<_> = output.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            ["├─src", "│  ├─main.rs", "│  └─lib.rs", "└─Cargo.toml"],
+            "文件应与目录一样以分支绘制"
+        );
+    }
+
+    // ------------------------------------------------------------------------
+    // Level Guides Tests
+    // ------------------------------------------------------------------------
+
+    fn create_nested_dirs() -> TreeNode {
+        let dir = |path: &str| {
+            TreeNode::new(
+                PathBuf::from(path),
+                EntryKind::Directory,
+                EntryMetadata::default(),
+            )
+        };
+        let mut b = dir("root/a/b");
+        b.children.push(dir("root/a/b/c"));
+        let mut a = dir("root/a");
+        a.children.push(b);
+        let mut root = dir("root");
+        root.children.push(a);
+        root.children.push(dir("root/d"));
+        root
+    }
+
+    #[test]
+    fn should_number_level_guides() {
+        let stats = create_test_stats(create_nested_dirs());
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.render.no_win_banner = true;
+        config.render.level_guides = LevelGuides::Number;
+
+        let result = render(&stats, &config);
+        assert!(
+            result.content.contains("├─a\n│1 └─b\n│1     └─c\n└─d"),
+            "{}",
+            result.content
+        );
+    }
+
+    #[test]
+    fn should_number_ascii_level_guides() {
+        let chars = TreeChars::from_charset(CharsetMode::Ascii);
+        let lines = "|   |   +---x\n|       \\---y".to_string();
+        assert_eq!(
+            decorate_level_guides(lines, &chars, LevelGuides::Number),
+            "|1  |2  +---x\n|1      \\---y"
+        );
+    }
+
+    #[test]
+    fn should_color_level_guides_by_level() {
+        let chars = TreeChars::from_charset(CharsetMode::Unicode);
+        let line = decorate_level_guides("│  ├─x".to_string(), &chars, LevelGuides::Color);
+        assert_eq!(line, "\x1b[36m│  \x1b[0m\x1b[33m├─\x1b[0mx");
+        assert_eq!(
+            display_width(&line),
+            display_width("│  ├─x"),
+            "颜色不应改变宽度"
+        );
+    }
+
+    #[test]
+    fn should_remove_numbered_trailing_pipe_line() {
+        let result = remove_trailing_pipe_only_line("├─a\n│1 \n".to_string());
+        assert_eq!(result, "├─a\n");
+    }
+
     // ------------------------------------------------------------------------
     // Files and Directories Separator Tests
     // ------------------------------------------------------------------------
@@ -2634,10 +5191,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 2,
             file_count: 3,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -2692,10 +5251,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 1,
             file_count: 1,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -2747,10 +5308,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 2,
             file_count: 0,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -2764,7 +5327,11 @@ mod tests {
         let dir1_idx = lines.iter().position(|l| l.contains("dir1")).unwrap();
         let dir2_idx = lines.iter().position(|l| l.contains("dir2")).unwrap();
 
-        assert_eq!(dir2_idx - dir1_idx, 1, "empty directories should be adjacent");
+        assert_eq!(
+            dir2_idx - dir1_idx,
+            1,
+            "empty directories should be adjacent"
+        );
     }
 
     // ------------------------------------------------------------------------
@@ -2815,10 +5382,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 3,
             file_count: 2,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -2891,10 +5460,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 3,
             file_count: 2,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -2915,8 +5486,7 @@ mod tests {
                 let trailing_char_count = next_line.chars().count();
 
                 assert_eq!(
-                    trailing_char_count,
-                    v1_prefix_char_count,
+                    trailing_char_count, v1_prefix_char_count,
                     "trailing line chars should match file prefix chars"
                 );
             }
@@ -2974,10 +5544,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 4,
             file_count: 2,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -3073,10 +5645,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 4,
             file_count: 6,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -3090,12 +5664,12 @@ mod tests {
         for i in 0..lines.len().saturating_sub(1) {
             let current_is_trailing = !lines[i].is_empty()
                 && lines[i]
-                .chars()
-                .all(|c| c.is_whitespace() || c == '│' || c == '|');
+                    .chars()
+                    .all(|c| c.is_whitespace() || c == '│' || c == '|');
             let next_is_trailing = !lines[i + 1].is_empty()
                 && lines[i + 1]
-                .chars()
-                .all(|c| c.is_whitespace() || c == '│' || c == '|');
+                    .chars()
+                    .all(|c| c.is_whitespace() || c == '│' || c == '|');
 
             assert!(
                 !(current_is_trailing && next_is_trailing),
@@ -3161,10 +5735,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 3,
             file_count: 3,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -3218,10 +5794,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 2,
             file_count: 0,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -3235,7 +5813,11 @@ mod tests {
         let dir1_idx = lines.iter().position(|l| l.contains("dir1")).unwrap();
         let subdir_idx = lines.iter().position(|l| l.contains("subdir")).unwrap();
 
-        assert_eq!(subdir_idx - dir1_idx, 1, "pure directory structure needs no trailing");
+        assert_eq!(
+            subdir_idx - dir1_idx,
+            1,
+            "pure directory structure needs no trailing"
+        );
     }
 
     // ------------------------------------------------------------------------
@@ -3293,10 +5875,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 4,
             file_count: 2,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -3396,10 +5980,12 @@ mod tests {
         sort_tree(&mut root, false);
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 4,
             file_count: 5,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -3463,10 +6049,12 @@ mod tests {
         ));
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 0,
             file_count: 3,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let mut config = Config::with_root(PathBuf::from("root"));
@@ -3478,7 +6066,10 @@ mod tests {
         let lines: Vec<&str> = result.content.lines().collect();
 
         let c_idx = lines.iter().position(|l| l.contains("c.txt")).unwrap();
-        assert!(c_idx + 1 < lines.len(), "should have trailing line after c.txt");
+        assert!(
+            c_idx + 1 < lines.len(),
+            "should have trailing line after c.txt"
+        );
         assert_eq!(lines[c_idx + 1], "    ", "trailing line should be 4 spaces");
     }
 
@@ -3571,6 +6162,9 @@ mod tests {
             is_last: false,
             is_file: true,
             has_more_dirs: true,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
         let _ = renderer.render_entry(&file1);
 
@@ -3583,6 +6177,9 @@ mod tests {
             is_last: false,
             is_file: true,
             has_more_dirs: true,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
         let _ = renderer.render_entry(&file2);
 
@@ -3595,6 +6192,9 @@ mod tests {
             is_last: false,
             is_file: false,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
         let _ = renderer.render_entry(&child1);
 
@@ -3608,6 +6208,9 @@ mod tests {
             is_last: true,
             is_file: false,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
         let _ = renderer.render_entry(&grandchild);
         let _ = renderer.pop_level();
@@ -3621,12 +6224,18 @@ mod tests {
             is_last: true,
             is_file: false,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
         let _ = renderer.render_entry(&child2);
 
         renderer.push_level(false);
         let child2_trailing = renderer.pop_level();
-        assert!(child2_trailing.is_none(), "empty dir child2 should have no trailing");
+        assert!(
+            child2_trailing.is_none(),
+            "empty dir child2 should have no trailing"
+        );
 
         let parent_trailing = renderer.pop_level();
 
@@ -3655,6 +6264,9 @@ mod tests {
             is_last: false,
             is_file: false,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
         let _ = renderer.render_entry(&subdir);
 
@@ -3667,12 +6279,18 @@ mod tests {
             is_last: true,
             is_file: true,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
         let _ = renderer.render_entry(&file);
 
         let trailing = renderer.pop_level();
 
-        assert!(trailing.is_some(), "last child is file, should have trailing");
+        assert!(
+            trailing.is_some(),
+            "last child is file, should have trailing"
+        );
     }
 
     // ------------------------------------------------------------------------
@@ -3698,14 +6316,120 @@ mod tests {
         config.scan.show_files = true;
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
+            duration: Duration::from_millis(100),
+            directory_count: 0,
+            file_count: 1,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
+        };
+
+        let result = render(&stats, &config);
+        assert!(
+            result.content.contains("root/file.txt") || result.content.contains("root\\file.txt")
+        );
+    }
+
+    #[test]
+    fn should_render_paths_relative_to_base() {
+        let mut root = TreeNode::new(
+            PathBuf::from("/work/repo"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        root.children.push(TreeNode::new(
+            PathBuf::from("/work/repo/Cargo.toml"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        ));
+
+        let mut config = Config::with_root(PathBuf::from("/work/repo"));
+        config.render.no_win_banner = true;
+        config.render.path_mode = PathMode::Full;
+        config.render.relative_to = Some(PathBuf::from("/work"));
+        config.scan.show_files = true;
+
+        let stats = ScanStats {
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 0,
             file_count: 1,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
+        };
+
+        let result = render(&stats, &config);
+        let sep = std::path::MAIN_SEPARATOR;
+        let lines: Vec<&str> = result.content.lines().collect();
+        assert_eq!(lines[0], "repo", "根目录应相对于基准目录显示");
+        assert!(
+            lines[1].ends_with(&format!("repo{sep}Cargo.toml")),
+            "实际：{}",
+            lines[1]
+        );
+        assert!(!result.content.contains("work"), "不应出现绝对路径");
+    }
+
+    #[test]
+    fn should_redact_profile_and_terms() {
+        let mut root = TreeNode::new(
+            PathBuf::from("/home/alice"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let mut project = TreeNode::new(
+            PathBuf::from("/home/alice/falcon-api"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        project.children.push(TreeNode::new(
+            PathBuf::from("/home/alice/falcon-api/main.rs"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        ));
+        root.children.push(project);
+
+        let mut redactor = Redactor::default().with_profile(Path::new("/home/alice"), None);
+        redactor.add_term("falcon");
+        let mut config = Config::with_root(PathBuf::from("/home/alice"));
+        config.render.no_win_banner = true;
+        config.render.redaction = Some(redactor);
+        config.scan.show_files = true;
+
+        let stats = ScanStats {
+            tree: TreeArena::from_tree(&root),
+            duration: Duration::from_millis(100),
+            directory_count: 1,
+            file_count: 1,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let result = render(&stats, &config);
-        assert!(result.content.contains("root/file.txt") || result.content.contains("root\\file.txt"));
+        assert!(
+            result.content.contains("<redacted-"),
+            "实际：{}",
+            result.content
+        );
+        assert!(result.content.contains("main.rs"));
+        assert!(
+            !result.content.contains("falcon"),
+            "实际：{}",
+            result.content
+        );
+
+        config.render.path_mode = PathMode::Full;
+        let result = render(&stats, &config);
+        assert!(
+            result.content.contains("%USERPROFILE%/<redacted-"),
+            "实际：{}",
+            result.content
+        );
+        assert!(
+            !result.content.contains("alice"),
+            "实际：{}",
+            result.content
+        );
     }
 
     #[test]
@@ -3720,14 +6444,16 @@ mod tests {
         config.render.no_win_banner = true;
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(1),
             directory_count: 0,
             file_count: 0,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let result = render(&stats, &config);
-        assert!(result.content.len() > 0);
+        assert!(!result.content.is_empty());
         assert_eq!(result.directory_count, 0);
         assert_eq!(result.file_count, 0);
     }
@@ -3757,10 +6483,12 @@ mod tests {
         config.scan.show_files = true;
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 0,
             file_count: 1,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let result = render(&stats, &config);
@@ -3801,10 +6529,12 @@ mod tests {
         config.scan.show_files = true;
 
         let stats = ScanStats {
-            tree: root,
+            tree: TreeArena::from_tree(&root),
             duration: Duration::from_millis(100),
             directory_count: 2,
             file_count: 1,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
         };
 
         let result = render(&stats, &config);
@@ -3837,6 +6567,9 @@ mod tests {
             is_last: true,
             is_file: true,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
 
         let line = renderer.render_entry(&entry);
@@ -3847,9 +6580,17 @@ mod tests {
     #[test]
     fn should_handle_very_large_file_sizes() {
         let result = format_size_human(u64::MAX);
-        assert!(result.ends_with(" TB"), "very large size should be in TB, got: {}", result);
-        assert!(result.contains("16777216"), "should be approximately 16777216 TB, got: {}", result);
-    }
+        assert!(
+            result.ends_with(" TB"),
+            "very large size should be in TB, got: {}",
+            result
+        );
+        assert!(
+            result.contains("16777216"),
+            "should be approximately 16777216 TB, got: {}",
+            result
+        );
+    }
 
     #[test]
     fn should_handle_stream_renderer_multiple_levels() {
@@ -3870,6 +6611,47 @@ mod tests {
         assert!(renderer.is_at_root_level());
     }
 
+    #[test]
+    fn should_continue_rendering_from_restored_state() {
+        let mut config = Config::default();
+        config.scan.show_files = true;
+        let entry = |name: &str, depth: usize, is_file: bool, is_last: bool| StreamEntry {
+            path: PathBuf::from(name),
+            name: name.to_string(),
+            kind: if is_file {
+                EntryKind::File
+            } else {
+                EntryKind::Directory
+            },
+            metadata: EntryMetadata::default(),
+            depth,
+            is_last,
+            is_file,
+            has_more_dirs: !is_file && !is_last,
+            filtered: None,
+            collapsed: None,
+            error: None,
+        };
+
+        let mut whole = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        let _ = whole.render_entry(&entry("a", 0, false, false));
+        whole.push_level(true);
+        let _ = whole.render_entry(&entry("a.txt", 1, true, true));
+        let state = whole.state();
+
+        let mut resumed = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        resumed.restore(state.clone());
+        assert_eq!(resumed.state(), state);
+
+        for renderer in [&mut whole, &mut resumed] {
+            let _ = renderer.pop_level();
+        }
+        let expected = whole.render_entry(&entry("b", 0, false, true));
+        let line = resumed.render_entry(&entry("b", 0, false, true));
+        assert_eq!(line, expected, "恢复后应继续相同的前缀");
+        assert!(expected.contains('b'));
+    }
+
     #[test]
     fn should_render_report_without_show_report() {
         let config = Config::default();
@@ -3897,6 +6679,9 @@ mod tests {
             is_last: true,
             is_file: true,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
         let _ = renderer.render_entry(&entry);
 
@@ -3944,10 +6729,12 @@ mod tests {
 
         let batch_result = render(
             &ScanStats {
-                tree: root,
+                tree: TreeArena::from_tree(&root),
                 duration: Duration::from_millis(100),
                 directory_count: 2,
                 file_count: 1,
+                unreadable_dirs: Vec::new(),
+                vanished_entries: 0,
             },
             &config,
         );
@@ -3959,8 +6746,8 @@ mod tests {
             let before_child = lines[child_idx - 1];
             let is_trailing = !before_child.is_empty()
                 && before_child
-                .chars()
-                .all(|c| c.is_whitespace() || c == '│' || c == '|');
+                    .chars()
+                    .all(|c| c.is_whitespace() || c == '│' || c == '|');
             assert!(
                 !is_trailing,
                 "should not have trailing line before last dir 'child', got: '{}'",
@@ -3975,7 +6762,7 @@ mod tests {
 
     #[test]
     fn should_produce_consistent_output_between_batch_and_stream_modes() {
-        use crate::scan::{scan, scan_streaming, StreamEvent};
+        use crate::scan::{StreamEvent, scan, scan_streaming};
         use std::fs;
         use tempfile::TempDir;
 
@@ -4040,6 +6827,10 @@ mod tests {
                         stream_output.push('\n');
                     }
                 }
+                StreamEvent::Omitted { depth, count } => {
+                    stream_output.push_str(&renderer.render_omitted(depth, count));
+                    stream_output.push('\n');
+                }
                 StreamEvent::EnterDir { is_last } => {
                     renderer.push_level(!is_last);
                 }
@@ -4077,10 +6868,12 @@ mod tests {
             stream_lines.join("\n")
         );
 
-        for (i, (batch_line, stream_line)) in batch_lines.iter().zip(stream_lines.iter()).enumerate()
+        for (i, (batch_line, stream_line)) in
+            batch_lines.iter().zip(stream_lines.iter()).enumerate()
         {
             assert_eq!(
-                batch_line, stream_line,
+                batch_line,
+                stream_line,
                 "Line {} differs:\nBatch:  '{}'\nStream: '{}'",
                 i + 1,
                 batch_line,
@@ -4091,7 +6884,7 @@ mod tests {
 
     #[test]
     fn should_produce_consistent_output_with_ascii_charset() {
-        use crate::scan::{scan, scan_streaming, StreamEvent};
+        use crate::scan::{StreamEvent, scan, scan_streaming};
         use std::fs;
         use tempfile::TempDir;
 
@@ -4138,6 +6931,10 @@ mod tests {
                         stream_output.push('\n');
                     }
                 }
+                StreamEvent::Omitted { depth, count } => {
+                    stream_output.push_str(&renderer.render_omitted(depth, count));
+                    stream_output.push('\n');
+                }
                 StreamEvent::EnterDir { is_last } => {
                     renderer.push_level(!is_last);
                 }
@@ -4169,7 +6966,7 @@ mod tests {
 
     #[test]
     fn should_produce_consistent_output_directories_only() {
-        use crate::scan::{scan, scan_streaming, StreamEvent};
+        use crate::scan::{StreamEvent, scan, scan_streaming};
         use std::fs;
         use tempfile::TempDir;
 
@@ -4217,6 +7014,10 @@ mod tests {
                         stream_output.push('\n');
                     }
                 }
+                StreamEvent::Omitted { depth, count } => {
+                    stream_output.push_str(&renderer.render_omitted(depth, count));
+                    stream_output.push('\n');
+                }
                 StreamEvent::EnterDir { is_last } => {
                     renderer.push_level(!is_last);
                 }
@@ -4257,7 +7058,7 @@ mod tests {
 
     #[test]
     fn should_produce_consistent_output_with_depth_limit() {
-        use crate::scan::{scan, scan_streaming, StreamEvent};
+        use crate::scan::{StreamEvent, scan, scan_streaming};
         use std::fs;
         use tempfile::TempDir;
 
@@ -4271,10 +7072,13 @@ mod tests {
         fs::write(root.join("level1").join("level2").join("file2.txt"), "2").unwrap();
         fs::create_dir(root.join("level1").join("level2").join("level3")).unwrap();
         fs::write(
-            root.join("level1").join("level2").join("level3").join("file3.txt"),
+            root.join("level1")
+                .join("level2")
+                .join("level3")
+                .join("file3.txt"),
             "3",
         )
-            .unwrap();
+        .unwrap();
 
         // Batch mode with depth limit
         let mut batch_config = Config::with_root(root.to_path_buf());
@@ -4312,6 +7116,10 @@ mod tests {
                         stream_output.push('\n');
                     }
                 }
+                StreamEvent::Omitted { depth, count } => {
+                    stream_output.push_str(&renderer.render_omitted(depth, count));
+                    stream_output.push('\n');
+                }
                 StreamEvent::EnterDir { is_last } => {
                     renderer.push_level(!is_last);
                 }
@@ -4352,7 +7160,7 @@ mod tests {
 
     #[test]
     fn should_produce_consistent_trailing_lines_between_modes() {
-        use crate::scan::{scan, scan_streaming, StreamEvent};
+        use crate::scan::{StreamEvent, scan, scan_streaming};
         use std::fs;
         use tempfile::TempDir;
 
@@ -4401,6 +7209,10 @@ mod tests {
                         stream_output.push('\n');
                     }
                 }
+                StreamEvent::Omitted { depth, count } => {
+                    stream_output.push_str(&renderer.render_omitted(depth, count));
+                    stream_output.push('\n');
+                }
                 StreamEvent::EnterDir { is_last } => {
                     renderer.push_level(!is_last);
                 }
@@ -4443,7 +7255,8 @@ mod tests {
             batch_trailing.iter().zip(stream_trailing.iter())
         {
             assert_eq!(
-                batch_line, stream_line,
+                batch_line,
+                stream_line,
                 "trailing line content should match.\n\
                  Batch line {} (idx {}): '{}'\n\
                  Stream line {} (idx {}): '{}'",
@@ -4459,7 +7272,7 @@ mod tests {
 
     #[test]
     fn should_produce_consistent_output_with_file_sizes() {
-        use crate::scan::{scan, scan_streaming, StreamEvent};
+        use crate::scan::{StreamEvent, scan, scan_streaming};
         use std::fs;
         use tempfile::TempDir;
 
@@ -4479,6 +7292,8 @@ mod tests {
         batch_config.render.show_size = true;
         batch_config.render.human_readable = true;
         batch_config.render.show_report = false;
+        // Aligned columns are measured differently by the two renderers.
+        batch_config.render.no_align = true;
 
         let batch_stats = scan(&batch_config).expect("batch scan should succeed");
         let batch_result = render(&batch_stats, &batch_config);
@@ -4491,6 +7306,7 @@ mod tests {
         stream_config.render.show_size = true;
         stream_config.render.human_readable = true;
         stream_config.render.show_report = false;
+        stream_config.render.no_align = true;
 
         let mut stream_output = String::new();
         let stream_render_config = StreamRenderConfig::from_config(&stream_config);
@@ -4507,6 +7323,10 @@ mod tests {
                         stream_output.push('\n');
                     }
                 }
+                StreamEvent::Omitted { depth, count } => {
+                    stream_output.push_str(&renderer.render_omitted(depth, count));
+                    stream_output.push('\n');
+                }
                 StreamEvent::EnterDir { is_last } => {
                     renderer.push_level(!is_last);
                 }
@@ -4531,7 +7351,9 @@ mod tests {
         let stream_lines = normalize(&stream_output);
 
         // Verify sizes are present
-        let has_size_info = batch_lines.iter().any(|l| l.contains("KB") || l.contains("MB") || l.contains("B"));
+        let has_size_info = batch_lines
+            .iter()
+            .any(|l| l.contains("KB") || l.contains("MB") || l.contains("B"));
         assert!(has_size_info, "output should contain file size information");
 
         assert_eq!(
@@ -4542,7 +7364,7 @@ mod tests {
 
     #[test]
     fn should_produce_consistent_output_with_empty_directories() {
-        use crate::scan::{scan, scan_streaming, StreamEvent};
+        use crate::scan::{StreamEvent, scan, scan_streaming};
         use std::fs;
         use tempfile::TempDir;
 
@@ -4589,6 +7411,10 @@ mod tests {
                         stream_output.push('\n');
                     }
                 }
+                StreamEvent::Omitted { depth, count } => {
+                    stream_output.push_str(&renderer.render_omitted(depth, count));
+                    stream_output.push('\n');
+                }
                 StreamEvent::EnterDir { is_last } => {
                     renderer.push_level(!is_last);
                 }
@@ -4620,7 +7446,7 @@ mod tests {
 
     #[test]
     fn should_produce_consistent_output_with_no_indent_mode() {
-        use crate::scan::{scan, scan_streaming, StreamEvent};
+        use crate::scan::{StreamEvent, scan, scan_streaming};
         use std::fs;
         use tempfile::TempDir;
 
@@ -4667,6 +7493,10 @@ mod tests {
                         stream_output.push('\n');
                     }
                 }
+                StreamEvent::Omitted { depth, count } => {
+                    stream_output.push_str(&renderer.render_omitted(depth, count));
+                    stream_output.push('\n');
+                }
                 StreamEvent::EnterDir { is_last } => {
                     renderer.push_level(!is_last);
                 }
@@ -4704,4 +7534,1758 @@ mod tests {
             "no-indent output should be identical between batch and stream modes"
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn should_render_explanation_for_shown_path() {
+        use crate::scan::FilterExplanation;
+
+        let root = PathBuf::from("project");
+        let explanation = FilterExplanation {
+            path: root.join("src"),
+            decided_at: root.join("src"),
+            reason: None,
+        };
+        let output = render_explanation(&explanation, &root);
+        assert!(output.contains("Path:       src"));
+        assert!(output.contains("Result:     shown"));
+        assert!(!output.contains("Reason"));
+    }
+
+    #[test]
+    fn should_render_explanation_with_deciding_ancestor() {
+        use crate::matching::FilterReason;
+        use crate::scan::FilterExplanation;
+
+        let root = PathBuf::from("project");
+        let explanation = FilterExplanation {
+            path: root.join("target").join("debug"),
+            decided_at: root.join("target"),
+            reason: Some(FilterReason::Gitignored {
+                source: Some(root.join(".gitignore")),
+                line: Some(1),
+                pattern: "target/".to_string(),
+            }),
+        };
+        let output = render_explanation(&explanation, &root);
+        assert!(output.contains("Result:     hidden"));
+        assert!(output.contains("Decided at: target\n"));
+        assert!(output.contains("'target/'"));
+        assert!(output.contains(".gitignore:1"));
+    }
+
+    #[test]
+    fn should_annotate_filtered_entries_in_batch_render() {
+        use crate::matching::FilterReason;
+
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let mut ignored = TreeNode::new(
+            PathBuf::from("root/target"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        ignored.filtered = Some(FilterReason::Excluded {
+            pattern: "target".to_string(),
+        });
+        root.children.push(ignored);
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/src"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        ));
+
+        let config = Config::with_root(PathBuf::from("root"));
+        let output = render_tree_only(&root, &config);
+        assert!(output.contains("target  [filtered: matches exclude pattern 'target' (/X)]"));
+        assert!(output.lines().any(|l| l.ends_with("src")));
+    }
+
+    #[test]
+    fn should_annotate_filtered_entries_in_stream_render() {
+        use crate::matching::FilterReason;
+
+        let config = Config::default();
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        let entry = StreamEntry {
+            path: PathBuf::from("node_modules"),
+            name: "node_modules".to_string(),
+            kind: EntryKind::Directory,
+            metadata: EntryMetadata::default(),
+            depth: 0,
+            is_last: true,
+            is_file: false,
+            has_more_dirs: false,
+            filtered: Some(FilterReason::Untracked),
+            collapsed: None,
+            error: None,
+        };
+        let line = renderer.render_entry(&entry);
+        assert!(line.contains("node_modules  [filtered: is not tracked by the git index (/GT)]"));
+    }
+
+    #[test]
+    fn should_render_omitted_marker_consistently_in_batch_and_stream() {
+        use crate::scan::{StreamEvent, scan, scan_streaming};
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+        fs::create_dir(root.join("d1")).unwrap();
+        for name in ["w.txt", "x.txt", "y.txt", "z.txt"] {
+            fs::write(root.join("d1").join(name), name).unwrap();
+        }
+        fs::create_dir(root.join("d2")).unwrap();
+        fs::create_dir(root.join("d3")).unwrap();
+
+        let mut config = Config::with_root(root.to_path_buf());
+        config.render.no_win_banner = true;
+        config.render.charset = CharsetMode::Unicode;
+        config.scan.show_files = true;
+        config.scan.max_entries = Some(3);
+
+        let batch_stats = scan(&config).expect("batch scan should succeed");
+        let batch_result = render(&batch_stats, &config);
+
+        let mut stream_output = String::new();
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        stream_output.push_str(&renderer.render_header(root, config.path_explicitly_set));
+
+        let stream_stats = scan_streaming(&config, |event| {
+            match event {
+                StreamEvent::Entry(ref entry) => {
+                    let line = renderer.render_entry(&entry.clone());
+                    for l in line.lines() {
+                        stream_output.push_str(l);
+                        stream_output.push('\n');
+                    }
+                }
+                StreamEvent::Omitted { depth, count } => {
+                    stream_output.push_str(&renderer.render_omitted(depth, count));
+                    stream_output.push('\n');
+                }
+                StreamEvent::EnterDir { is_last } => {
+                    renderer.push_level(!is_last);
+                }
+                StreamEvent::LeaveDir => {
+                    if let Some(trailing) = renderer.pop_level() {
+                        stream_output.push_str(&trailing);
+                        stream_output.push('\n');
+                    }
+                }
+            }
+            Ok(())
+        })
+        .expect("stream scan should succeed");
+
+        let normalize = |s: &str| -> Vec<String> {
+            s.lines()
+                .map(|l| l.trim_end().to_string())
+                .filter(|l| !l.is_empty() && !l.starts_with("Truncated:"))
+                .collect()
+        };
+
+        let batch_lines = normalize(&batch_result.content);
+        assert_eq!(batch_lines, normalize(&stream_output));
+        assert_eq!(
+            batch_lines[1..],
+            [
+                "│  a.txt",
+                "│  b.txt",
+                "│",
+                "├─d1",
+                "│  │  w.txt",
+                "│  │  x.txt",
+                "│  │  y.txt",
+                "│  │",
+                "│  └─[+1 more]",
+                "└─[+2 more]",
+            ]
+        );
+        assert!(
+            batch_result
+                .content
+                .contains("Truncated: 2 directories have more entries")
+        );
+        assert_eq!(
+            batch_stats.tree.count_truncated(),
+            stream_stats.truncated_dirs
+        );
+        assert_eq!(batch_stats.file_count, stream_stats.file_count);
+    }
+
+    #[test]
+    fn should_render_truncated_root_identically_in_batch_and_stream() {
+        use crate::scan::{StreamEvent, scan, scan_streaming};
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::create_dir(root.join("src")).unwrap();
+
+        let mut config = Config::with_root(root.to_path_buf());
+        config.render.no_win_banner = true;
+        config.render.charset = CharsetMode::Unicode;
+        config.scan.show_files = true;
+        config.scan.max_entries = Some(1);
+
+        let mut batch_config = config.clone();
+        batch_config.batch_mode = true;
+        let batch = render(&scan(&batch_config).unwrap(), &batch_config).content;
+
+        let mut stream = String::new();
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        stream.push_str(&renderer.render_header(root, config.path_explicitly_set));
+        let stats = scan_streaming(&config, |event| {
+            let text = match event {
+                StreamEvent::Entry(ref entry) => Some(renderer.render_entry(entry)),
+                StreamEvent::Omitted { depth, count } => {
+                    Some(renderer.render_omitted(depth, count))
+                }
+                StreamEvent::EnterDir { is_last } => {
+                    renderer.push_level(!is_last);
+                    None
+                }
+                StreamEvent::LeaveDir => renderer.pop_level(),
+            };
+            if let Some(text) = text {
+                stream.push_str(text.trim_end_matches('\n'));
+                stream.push('\n');
+            }
+            Ok(())
+        })
+        .unwrap();
+        if let Some(trailing) = renderer.finish() {
+            stream.push_str(&trailing);
+            stream.push('\n');
+        }
+        stream.push_str(&render_truncation_notice(
+            stats.truncated_dirs,
+            stats.unread_dirs,
+            None,
+        ));
+
+        assert_eq!(batch, stream, "批处理与流式输出应逐字节相同");
+        let (_, body) = batch.split_once('\n').unwrap();
+        assert_eq!(
+            body,
+            "│  a.txt\n│  \n└─[+3 more]\n\
+             Truncated: 1 directory has more entries than --max-entries\n"
+        );
+    }
+
+    #[test]
+    fn should_render_omitted_marker_without_indent() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/src"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        ));
+        root.omitted = 7;
+
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.render.no_indent = true;
+        let output = render_tree_only(&root, &config);
+        assert!(output.lines().any(|l| l == "[+7 more]"));
+    }
+
+    #[test]
+    fn should_render_small_dirs_marker_before_omitted_marker() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/src"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        ));
+        root.small_dirs = 3;
+        root.omitted = 2;
+
+        let config = Config::with_root(PathBuf::from("root"));
+        let output = render_tree_only(&root, &config);
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(
+            lines[1..],
+            ["├─src", "├─[+ 3 small dirs]", "└─[+2 more]"],
+            "{output}"
+        );
+
+        root.omitted = 0;
+        root.small_dirs = 1;
+        let output = render_tree_only(&root, &config);
+        assert!(output.lines().any(|l| l == "└─[+ 1 small dir]"), "{output}");
+    }
+
+    #[test]
+    fn should_render_link_targets_after_names() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/current"),
+            EntryKind::File,
+            EntryMetadata {
+                link_target: Some(PathBuf::from(r"D:\releases\v2")),
+                ..Default::default()
+            },
+        ));
+
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        let output = render_tree_only(&root, &config);
+        assert!(output.contains(r"current -> D:\releases\v2"), "{output}");
+
+        config.render.quote_names = true;
+        let output = render_tree_only(&root, &config);
+        assert!(
+            output.contains(r#""current" -> "D:\releases\v2""#),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn should_append_classify_indicators() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        for name in ["build.CMD", "notes.txt", "setup.exe"] {
+            root.children.push(TreeNode::new(
+                PathBuf::from("root").join(name),
+                EntryKind::File,
+                EntryMetadata::default(),
+            ));
+        }
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/latest"),
+            EntryKind::File,
+            EntryMetadata {
+                link_target: Some(PathBuf::from("v2")),
+                ..Default::default()
+            },
+        ));
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/src"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        ));
+
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.classify = true;
+        config.render.quote_names = true;
+        let output = render_tree_only(&root, &config);
+        let expected = [
+            r#""build.CMD"*"#,
+            r#""setup.exe"*"#,
+            r#""latest"@ -> "v2""#,
+            r#""src"/"#,
+        ];
+        for expected in expected {
+            assert!(output.contains(expected), "缺少 {expected}: {output}");
+        }
+        assert!(
+            output.contains("\"notes.txt\"\n"),
+            "普通文件不应带标记: {output}"
+        );
+    }
+
+    #[test]
+    fn should_sort_files_into_sections_by_extension() {
+        assert_eq!(Section::of_file("lib.rs"), Section::Code);
+        assert_eq!(Section::of_file("Photo.JPG"), Section::Images);
+        assert_eq!(Section::of_file("backup.tar.gz"), Section::Archives);
+        assert_eq!(Section::of_file("notes.txt"), Section::Other);
+        assert_eq!(Section::of_file("Makefile"), Section::Other);
+    }
+
+    fn create_grouping_tree() -> TreeNode {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        for name in ["a.zip", "b.png", "main.py", "notes.txt"] {
+            root.children.push(TreeNode::new(
+                PathBuf::from("root").join(name),
+                EntryKind::File,
+                EntryMetadata::default(),
+            ));
+        }
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/sub"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        ));
+        root
+    }
+
+    #[test]
+    fn should_render_files_in_type_sections() {
+        let root = create_grouping_tree();
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.charset = CharsetMode::Ascii;
+        config.render.group_by = GroupBy::Type;
+        let output = render_tree_only(&root, &config);
+        let expected = "root\n\
+                        |   [Code]\n\
+                        |   main.py\n\
+                        |   \n\
+                        |   [Images]\n\
+                        |   b.png\n\
+                        |   \n\
+                        |   [Archives]\n\
+                        |   a.zip\n\
+                        |   \n\
+                        |   [Other]\n\
+                        |   notes.txt\n\
+                        |   \n\
+                        |   [Directories]\n\
+                        \\---sub\n";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn should_render_type_sections_without_indent() {
+        let root = create_grouping_tree();
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.no_indent = true;
+        config.render.group_by = GroupBy::Type;
+        config.render.language = Language::Chinese;
+        let output = render_tree_only(&root, &config);
+        let expected = "root\n[代码]\nmain.py\n[图片]\nb.png\n[压缩包]\na.zip\n[其他]\nnotes.txt\n\
+                        [目录]\nsub\n";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn should_show_transform_notes_after_name() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let mut ws = TreeNode::new(
+            PathBuf::from("root/ws"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        ws.annotations = vec![
+            "cargo workspace, 2 members".to_string(),
+            "vendored".to_string(),
+        ];
+        root.children.push(ws);
+
+        let config = Config::with_root(PathBuf::from("root"));
+        let output = render_tree_only(&root, &config);
+        assert!(
+            output.contains("ws [cargo workspace, 2 members] [vendored]\n"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn should_mark_cloud_only_files_when_enabled() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/local.txt"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        ));
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/online.docx"),
+            EntryKind::File,
+            EntryMetadata {
+                cloud_only: true,
+                ..Default::default()
+            },
+        ));
+
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        let output = render_tree_only(&root, &config);
+        assert!(!output.contains("[cloud]"), "未启用时不应标记: {output}");
+
+        config.render.mark_cloud = true;
+        let output = render_tree_only(&root, &config);
+        assert!(output.contains("online.docx [cloud]"), "{output}");
+        assert!(!output.contains("local.txt [cloud]"), "{output}");
+    }
+
+    #[test]
+    fn should_mark_empty_files_when_enabled() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/blank.txt"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        ));
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/data.bin"),
+            EntryKind::File,
+            EntryMetadata {
+                size: 42,
+                ..Default::default()
+            },
+        ));
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/link.txt"),
+            EntryKind::File,
+            EntryMetadata {
+                link_target: Some(PathBuf::from("data.bin")),
+                ..Default::default()
+            },
+        ));
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/sub"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        ));
+
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        let output = render_tree_only(&root, &config);
+        assert!(!output.contains("[empty]"), "未启用时不应标记: {output}");
+
+        config.render.mark_empty_files = true;
+        let output = render_tree_only(&root, &config);
+        assert!(output.contains("blank.txt [empty]"), "{output}");
+        assert_eq!(
+            output.matches("[empty]").count(),
+            1,
+            "仅零字节文件应被标记: {output}"
+        );
+    }
+
+    #[test]
+    fn should_cut_batch_output_at_max_output_size() {
+        let tree = create_test_tree();
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.render.no_win_banner = true;
+        config.scan.show_files = true;
+        let full = render(&create_test_stats(tree.clone()), &config).content;
+
+        config.output.max_output_size = Some(20);
+        let cut = render(&create_test_stats(tree), &config).content;
+        let (tree_part, notice) = cut.split_once("Truncated:").expect("应包含截断提示");
+        assert!(tree_part.len() <= 20);
+        assert!(tree_part.ends_with('\n'));
+        assert!(full.starts_with(tree_part));
+        assert!(notice.contains("--max-output-size"));
+    }
+
+    #[test]
+    fn should_not_cut_output_within_max_output_size() {
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.render.no_win_banner = true;
+        config.output.max_output_size = Some(1024 * 1024);
+        let output = render(&create_test_stats(create_test_tree()), &config).content;
+        assert!(!output.contains("Truncated:"));
+    }
+
+    #[test]
+    fn should_format_truncation_notice() {
+        assert!(render_truncation_notice(0, 0, None).is_empty());
+        assert!(render_truncation_notice(1, 0, None).contains("1 directory has"));
+        let notice = render_truncation_notice(2, 0, Some(10 * 1024 * 1024));
+        assert_eq!(notice.lines().count(), 2);
+        assert!(notice.contains("10.0 MB"));
+        let notice = render_truncation_notice(0, 3, None);
+        assert_eq!(
+            notice,
+            "Truncated: 3 directories were not read after the scan budget ran out\n"
+        );
+    }
+
+    #[test]
+    fn should_annotate_collapsed_directories() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let mut vendor = TreeNode::new(
+            PathBuf::from("root/node_modules"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        vendor.collapsed = Some(18234);
+        root.children.push(vendor);
+
+        let config = Config::with_root(PathBuf::from("root"));
+        let output = render_tree_only(&root, &config);
+        assert!(output.contains("└─node_modules [18234 entries]"));
+
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        let entry = StreamEntry {
+            path: PathBuf::from("root/node_modules"),
+            name: "node_modules".to_string(),
+            kind: EntryKind::Directory,
+            metadata: EntryMetadata::default(),
+            depth: 0,
+            is_last: true,
+            is_file: false,
+            has_more_dirs: false,
+            filtered: None,
+            collapsed: Some(18234),
+            error: None,
+        };
+        assert_eq!(
+            renderer.render_entry(&entry),
+            "└─node_modules [18234 entries]"
+        );
+    }
+
+    #[test]
+    fn should_annotate_unreadable_directories() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let mut locked = TreeNode::new(
+            PathBuf::from("root/locked"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        locked.error = Some(EntryError::AccessDenied);
+        root.children.push(locked);
+
+        let config = Config::with_root(PathBuf::from("root"));
+        let output = render_tree_only(&root, &config);
+        assert!(
+            output.contains("└─locked [access denied]"),
+            "无法读取的目录应带有注记"
+        );
+
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        let entry = StreamEntry {
+            path: PathBuf::from("root/locked"),
+            name: "locked".to_string(),
+            kind: EntryKind::Directory,
+            metadata: EntryMetadata::default(),
+            depth: 0,
+            is_last: true,
+            is_file: false,
+            has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: Some(EntryError::ReadFailed),
+        };
+        assert_eq!(renderer.render_entry(&entry), "└─locked [read failed]");
+    }
+
+    #[test]
+    fn should_prefix_entries_hoisted_by_leaf_depth() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let mut outer = TreeNode::new(
+            PathBuf::from("root/gen"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let mut inner = TreeNode::new(
+            PathBuf::from("root/gen/out"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        inner.children.push(TreeNode::new(
+            PathBuf::from("root/gen/out/lib.rs"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        ));
+        outer.children.push(inner);
+        root.children.push(outer);
+
+        let mut stats = create_test_stats(root);
+        stats.tree.keep_leaf_levels(2);
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.no_win_banner = true;
+        config.render.show_report = false;
+
+        let result = render(&stats, &config);
+        assert!(
+            result.content.contains("└─.../out\n"),
+            "被提升的目录应带有 .../ 前缀"
+        );
+        assert!(
+            result.content.contains("      lib.rs"),
+            "被提升目录的内容应正常显示"
+        );
+        assert!(!result.content.contains("gen"), "被折叠的上层目录不应显示");
+
+        config.render.path_mode = PathMode::Full;
+        let result = render(&stats, &config);
+        assert!(
+            !result.content.contains(".../"),
+            "完整路径模式下不应添加前缀"
+        );
+    }
+
+    #[test]
+    fn should_end_collapsed_leaf_level_without_blank_line() {
+        use crate::scan::scan;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        fs::create_dir(dir.path().join("c")).unwrap();
+        fs::write(dir.path().join("r.txt"), "").unwrap();
+        fs::write(dir.path().join("a/g.txt"), "").unwrap();
+        fs::write(dir.path().join("a/b/f.txt"), "").unwrap();
+        fs::write(dir.path().join("c/h.txt"), "").unwrap();
+
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.batch_mode = true;
+        config.scan.show_files = true;
+        config.scan.leaf_depth = Some(1);
+        config.render.no_win_banner = true;
+        config.render.charset = CharsetMode::Unicode;
+
+        let content = render(&scan(&config).unwrap(), &config).content;
+        let (_, body) = content.split_once('\n').unwrap();
+        assert_eq!(
+            body, "    r.txt\n    .../g.txt\n    .../f.txt\n    .../h.txt\n    \n",
+            "折叠的叶层之后不应有多余的空行"
+        );
+    }
+
+    #[test]
+    fn should_render_flat_listing_in_batch_mode() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let mut src = TreeNode::new(
+            PathBuf::from("root/src"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        src.children.push(TreeNode::new(
+            PathBuf::from("root/src/main.rs"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        ));
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/Cargo.toml"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        ));
+        root.children.push(src);
+
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.flat = true;
+        config.render.path_mode = PathMode::Full;
+        config.render.no_indent = true;
+        config.render.no_win_banner = true;
+
+        let result = render(&create_test_stats(root), &config);
+        let expected = [
+            PathBuf::from("root/Cargo.toml"),
+            PathBuf::from("root/src"),
+            PathBuf::from("root/src/main.rs"),
+        ]
+        .iter()
+        .map(|p| format!("{}\n", p.display()))
+        .collect::<String>();
+        assert_eq!(result.content, expected, "平铺模式应逐行输出完整路径");
+    }
+
+    #[test]
+    fn should_render_flat_entries_in_stream_mode() {
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.render.flat = true;
+        config.render.path_mode = PathMode::Full;
+        config.render.no_indent = true;
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        assert!(
+            renderer.render_header(Path::new("root"), false).is_empty(),
+            "平铺模式不应输出标题"
+        );
+
+        let entry = StreamEntry {
+            path: PathBuf::from("root/src/lib"),
+            name: "lib".to_string(),
+            kind: EntryKind::Directory,
+            metadata: EntryMetadata::default(),
+            depth: 1,
+            is_last: true,
+            is_file: false,
+            has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
+        };
+        assert_eq!(
+            renderer.render_entry(&entry),
+            PathBuf::from("root/src/lib").display().to_string()
+        );
+        assert!(
+            renderer.render_omitted(1, 3).is_empty(),
+            "平铺模式不应输出省略标记"
+        );
+    }
+
+    #[test]
+    fn should_merge_overlapping_spans() {
+        assert_eq!(
+            merge_spans(vec![5..9, 0..2, 8..12, 12..13]),
+            vec![0..2, 5..13]
+        );
+        assert!(merge_spans(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn should_highlight_spans_in_name_and_path() {
+        let name = highlight_name(
+            "unit_test.rs",
+            "unit_test.rs",
+            vec![9..12, 5..9],
+            EscapeMode::Raw,
+            false,
+        );
+        assert_eq!(name, "unit_\x1b[4;33mtest.rs\x1b[0m");
+
+        let path = highlight_name("src/a.rs", "a.rs", vec![1..2, 2..4], EscapeMode::Raw, true);
+        assert_eq!(
+            path, "\"src/a\x1b[4;33m.rs\x1b[0m\"",
+            "完整路径模式下区间应偏移到文件名处"
+        );
+
+        let escaped = highlight_name("a\tb.rs", "a\tb.rs", vec![3..4, 4..6], EscapeMode::C, false);
+        assert_eq!(escaped, "a\\tb\x1b[4;33m.rs\x1b[0m");
+    }
+
+    #[test]
+    fn should_ignore_escape_sequences_in_display_width() {
+        assert_eq!(display_width("unit_\x1b[4;33mtest\x1b[0m"), 9);
+    }
+
+    #[test]
+    fn should_highlight_include_matches_in_both_modes() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/main.rs"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        ));
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.no_win_banner = true;
+        config.render.highlight = true;
+        config.matching.include_patterns = vec!["*.rs".to_string()];
+
+        let result = render(&create_test_stats(root), &config);
+        assert!(
+            result.content.contains("main\x1b[4;33m.rs\x1b[0m"),
+            "批处理模式应高亮匹配部分"
+        );
+
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        let entry = StreamEntry {
+            path: PathBuf::from("root/main.rs"),
+            name: "main.rs".to_string(),
+            kind: EntryKind::File,
+            metadata: EntryMetadata::default(),
+            depth: 0,
+            is_last: true,
+            is_file: true,
+            has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
+        };
+        assert!(
+            renderer
+                .render_entry(&entry)
+                .ends_with("main\x1b[4;33m.rs\x1b[0m"),
+            "流式模式应高亮匹配部分"
+        );
+    }
+
+    #[test]
+    fn should_render_extension_report_aligned() {
+        let rows = [
+            ExtensionEntry {
+                extension: ".rs".to_string(),
+                count: 1204,
+                size: 24_536_678,
+            },
+            ExtensionEntry {
+                extension: "(none)".to_string(),
+                count: 1,
+                size: 0,
+            },
+        ];
+        let report = render_extension_report(&rows, Language::English);
+        let lines: Vec<_> = report.lines().collect();
+        assert_eq!(lines[0], "Extensions:");
+        assert_eq!(lines[1], "  .rs     1,204 files  23.4 MB");
+        assert_eq!(lines[2], "  (none)      1 file       0 B");
+        assert_eq!(
+            render_extension_report(&[], Language::English),
+            "Extensions: no files listed\n"
+        );
+
+        let wide = [
+            ExtensionEntry {
+                extension: ".文档".to_string(),
+                count: 2,
+                size: 0,
+            },
+            ExtensionEntry {
+                extension: ".rs".to_string(),
+                count: 1,
+                size: 0,
+            },
+        ];
+        let report = render_extension_report(&wide, Language::English);
+        let lines: Vec<_> = report.lines().collect();
+        assert_eq!(
+            lines[1], "  .文档  2 files  0 B",
+            "宽字符扩展名应按显示宽度对齐"
+        );
+        assert_eq!(lines[2], "  .rs    1 file   0 B");
+    }
+
+    #[test]
+    fn should_render_depth_histogram_aligned() {
+        let levels = [
+            LevelStats {
+                directories: 3,
+                files: 1,
+                size: 2048,
+            },
+            LevelStats {
+                directories: 12,
+                files: 1530,
+                size: 24_536_678,
+            },
+            LevelStats {
+                directories: 0,
+                files: 2,
+                size: 0,
+            },
+        ];
+        let table = render_depth_histogram(&levels, Language::English);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines[0], "Depth histogram:");
+        assert_eq!(lines[1], "  Level  Dirs  Files     Size");
+        assert_eq!(lines[2], "      1     3      1   2.0 KB  #");
+        assert_eq!(
+            lines[3],
+            "      2    12  1,530  23.4 MB  ####################"
+        );
+        assert_eq!(lines[4], "      3     0      2      0 B  #");
+        assert_eq!(
+            render_depth_histogram(&[], Language::English),
+            "Depth histogram: no entries listed\n"
+        );
+    }
+
+    #[test]
+    fn should_align_depth_histogram_headers_by_display_width() {
+        let levels = [LevelStats {
+            directories: 1,
+            files: 0,
+            size: 0,
+        }];
+        let table = render_depth_histogram(&levels, Language::Chinese);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines[0], "深度分布：");
+        assert_eq!(lines[1], "  层级  目录  文件  大小");
+        assert_eq!(lines[2], "     1     1     0   0 B  ####################");
+    }
+
+    #[test]
+    fn should_render_count_summary_aligned() {
+        let mut rejected = RejectionCounts::default();
+        rejected.record(&FilterReason::Excluded {
+            pattern: "target".to_string(),
+        });
+        rejected.record(&FilterReason::Pruned);
+        let summary = CountSummary {
+            directories: 3,
+            files: 1000,
+            total_size: 0,
+            max_depth: 2,
+            rejected,
+        };
+
+        let lines: Vec<_> = render_count_summary(&summary, Language::English)
+            .lines()
+            .map(String::from)
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "Directories:   3",
+                "Files:         1,000",
+                "Total size:    0 B",
+                "Max depth:     2",
+                "Filtered out:  2",
+                "  excluded (/X)  1",
+                "  pruned (/P)    1",
+            ]
+        );
+
+        let zh = render_count_summary(&summary, Language::Chinese);
+        assert!(
+            zh.starts_with("目录：      3\n"),
+            "中文标签应按显示宽度对齐"
+        );
+        assert!(zh.contains("最大深度：  2\n"));
+    }
+
+    #[test]
+    fn should_omit_filter_lines_when_nothing_is_filtered() {
+        let summary = CountSummary {
+            directories: 0,
+            files: 0,
+            total_size: 0,
+            max_depth: 0,
+            rejected: RejectionCounts::default(),
+        };
+        let output = render_count_summary(&summary, Language::English);
+        assert_eq!(output.lines().count(), 5);
+        assert!(output.ends_with("Filtered out:  0\n"));
+    }
+
+    #[test]
+    fn should_format_thousands() {
+        assert_eq!(format_thousands(0), "0");
+        assert_eq!(format_thousands(999), "999");
+        assert_eq!(format_thousands(1000), "1,000");
+        assert_eq!(format_thousands(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn should_append_extension_report_in_batch_render() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/main.rs"),
+            EntryKind::File,
+            EntryMetadata {
+                size: 2048,
+                ..Default::default()
+            },
+        ));
+        let stats = ScanStats {
+            tree: TreeArena::from_tree(&root),
+            duration: Duration::from_millis(1),
+            directory_count: 0,
+            file_count: 1,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
+        };
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.render.no_win_banner = true;
+        config.scan.show_files = true;
+        config.render.report_extensions = true;
+
+        let result = render(&stats, &config);
+        assert!(
+            result
+                .content
+                .contains("Extensions:\n  .rs  1 file   2.0 KB")
+        );
+    }
+
+    #[test]
+    fn should_escape_names_by_mode() {
+        assert_eq!(
+            escape_name("a\tb\u{1b}", EscapeMode::Question, false),
+            "a?b?"
+        );
+        assert_eq!(
+            escape_name("a\tb\u{1b}", EscapeMode::C, false),
+            "a\\tb\\x1B"
+        );
+        assert_eq!(escape_name("x\u{202E}y", EscapeMode::C, false), "x\\u202Ey");
+        assert_eq!(
+            escape_name("dir\\name", EscapeMode::C, false),
+            "dir\\\\name"
+        );
+        assert_eq!(
+            escape_name("trail  ", EscapeMode::C, false),
+            "trail\\x20\\x20"
+        );
+        assert_eq!(escape_name("日本 語", EscapeMode::C, false), "日本 語");
+        assert_eq!(escape_name("a\"b", EscapeMode::C, true), "\"a\\\"b\"");
+        assert_eq!(escape_name("trail ", EscapeMode::Raw, true), "\"trail \"");
+    }
+
+    #[test]
+    fn should_escape_names_in_batch_and_stream_render() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/bad\nname"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        ));
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.render.escape = EscapeMode::C;
+        config.render.quote_names = true;
+
+        let output = render_tree_only(&root, &config);
+        assert!(output.contains("└─\"bad\\nname\""));
+        assert_eq!(output.lines().count(), 2);
+
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        let entry = StreamEntry {
+            path: PathBuf::from("root/bad\nname"),
+            name: "bad\nname".to_string(),
+            kind: EntryKind::Directory,
+            metadata: EntryMetadata::default(),
+            depth: 0,
+            is_last: true,
+            is_file: false,
+            has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
+        };
+        assert_eq!(renderer.render_entry(&entry), "└─\"bad\\nname\"");
+    }
+
+    #[test]
+    fn should_align_metadata_in_batch_render() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        for (name, size) in [("a.rs", 1), ("much_longer_name.rs", 22)] {
+            root.children.push(TreeNode::new(
+                PathBuf::from("root").join(name),
+                EntryKind::File,
+                EntryMetadata {
+                    size,
+                    ..Default::default()
+                },
+            ));
+        }
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.show_size = true;
+
+        let output = render_tree_only(&root, &config);
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines[1], "    a.rs                 1");
+        assert_eq!(lines[2], "    much_longer_name.rs  22");
+
+        config.render.no_align = true;
+        let output = render_tree_only(&root, &config);
+        assert!(output.contains("    a.rs        1\n"));
+    }
+
+    #[test]
+    fn should_grow_stream_metadata_column_past_long_names() {
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.show_size = true;
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+
+        let file = |name: &str| StreamEntry {
+            path: PathBuf::from("root").join(name),
+            name: name.to_string(),
+            kind: EntryKind::File,
+            metadata: EntryMetadata {
+                size: 7,
+                ..Default::default()
+            },
+            depth: 0,
+            is_last: false,
+            is_file: true,
+            has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
+        };
+
+        let short = renderer.render_entry(&file("a.rs"));
+        assert_eq!(short.find('7'), Some(STREAM_META_COLUMN));
+
+        let long_name = "x".repeat(STREAM_META_COLUMN);
+        let long = renderer.render_entry(&file(&long_name));
+        let column = long.find('7').unwrap();
+        assert!(column.is_multiple_of(STREAM_META_COLUMN_STEP));
+        assert!(column >= STREAM_META_COLUMN + 4 + META_GAP);
+
+        let after = renderer.render_entry(&file("b.rs"));
+        assert_eq!(after.find('7'), Some(column));
+    }
+
+    #[test]
+    fn should_measure_display_width() {
+        assert_eq!(display_width("main.rs"), 7);
+        assert_eq!(display_width("文件.txt"), 8);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("└─"), display_width("    "));
+    }
+
+    #[test]
+    fn should_measure_emoji_and_right_to_left_names() {
+        assert_eq!(display_width("照片📷.jpg"), 10);
+        assert_eq!(
+            display_width("👨\u{200D}👩\u{200D}👧.png"),
+            6,
+            "ZWJ 序列应按单个字形计算"
+        );
+        assert_eq!(display_width("שלום.txt"), 8);
+        assert_eq!(
+            display_width("\u{200F}שלום\u{200E}.txt"),
+            8,
+            "方向标记不应占列"
+        );
+        assert_eq!(display_width("ｆｕｌｌ"), 8, "全角字母应占两列");
+    }
+
+    // ------------------------------------------------------------------------
+    // Native Tree Golden Tests
+    // ------------------------------------------------------------------------
+
+    /// Renders `root` in batch and stream mode, returning the lines below
+    /// the root path with trailing blank lines removed.
+    fn render_body_both_modes(root: &Path) -> (String, String) {
+        use crate::scan::{StreamEvent, scan, scan_streaming};
+
+        let mut config = Config::with_root(root.to_path_buf());
+        config.render.no_win_banner = true;
+        config.render.charset = CharsetMode::Ascii;
+        config.scan.show_files = true;
+
+        let mut batch_config = config.clone();
+        batch_config.batch_mode = true;
+        let batch = render(&scan(&batch_config).unwrap(), &batch_config).content;
+
+        let mut stream = String::new();
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        stream.push_str(&renderer.render_header(root, config.path_explicitly_set));
+        scan_streaming(&config, |event| {
+            let text = match event {
+                StreamEvent::Entry(ref entry) => Some(renderer.render_entry(entry)),
+                StreamEvent::Omitted { depth, count } => {
+                    Some(renderer.render_omitted(depth, count))
+                }
+                StreamEvent::EnterDir { is_last } => {
+                    renderer.push_level(!is_last);
+                    None
+                }
+                StreamEvent::LeaveDir => renderer.pop_level(),
+            };
+            if let Some(text) = text {
+                stream.push_str(text.trim_end_matches('\n'));
+                stream.push('\n');
+            }
+            Ok(())
+        })
+        .unwrap();
+        if let Some(trailing) = renderer.finish() {
+            stream.push_str(&trailing);
+            stream.push('\n');
+        }
+
+        let body = |output: &str| {
+            let (_, body) = output.split_once('\n').unwrap();
+            body.trim_end_matches('\n').to_string()
+        };
+        (body(&batch), body(&stream))
+    }
+
+    /// Captured `tree /F /A` output below the root path, with trailing
+    /// spaces kept.
+    const NATIVE_FILES_ONLY: &str = "    a.txt\n    b.txt\n    ";
+
+    const NATIVE_MIXED: &str = "|   a.txt\n|   \n+---sub\n|       c.txt\n|       \n\
+        +---sub2\n|   |   d.txt\n|   |   \n|   \\---deep\n\\---zz";
+
+    const NATIVE_LAST_DIR_WITH_FILES: &str = "|   a.txt\n|   \n\\---x\n        y.txt\n        ";
+
+    #[test]
+    fn should_match_native_tree_for_root_files_only() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "").unwrap();
+        fs::write(dir.path().join("b.txt"), "").unwrap();
+
+        let (batch, stream) = render_body_both_modes(dir.path());
+        assert_eq!(batch, NATIVE_FILES_ONLY);
+        assert_eq!(stream, NATIVE_FILES_ONLY);
+    }
+
+    #[test]
+    fn should_match_native_tree_for_mixed_levels() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "").unwrap();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub").join("c.txt"), "").unwrap();
+        fs::create_dir_all(root.join("sub2").join("deep")).unwrap();
+        fs::write(root.join("sub2").join("d.txt"), "").unwrap();
+        fs::create_dir_all(root.join("zz")).unwrap();
+
+        let (batch, stream) = render_body_both_modes(root);
+        assert_eq!(batch, NATIVE_MIXED);
+        assert_eq!(stream, NATIVE_MIXED);
+    }
+
+    #[test]
+    fn should_match_native_tree_for_last_directory_with_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "").unwrap();
+        fs::create_dir_all(root.join("x")).unwrap();
+        fs::write(root.join("x").join("y.txt"), "").unwrap();
+
+        let (batch, stream) = render_body_both_modes(root);
+        assert_eq!(batch, NATIVE_LAST_DIR_WITH_FILES);
+        assert_eq!(stream, NATIVE_LAST_DIR_WITH_FILES);
+    }
+
+    // ------------------------------------------------------------------------
+    // Stream Renderer Property Tests
+    // ------------------------------------------------------------------------
+
+    /// Small xorshift generator so the property tests stay reproducible
+    /// without an extra dependency.
+    struct PropRng(u64);
+
+    impl PropRng {
+        fn below(&mut self, bound: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % bound
+        }
+    }
+
+    /// An arbitrary directory: file count, subdirectories and entries
+    /// left out as if by `--max-entries`.
+    struct PropDir {
+        files: usize,
+        dirs: Vec<PropDir>,
+        omitted: usize,
+    }
+
+    fn arbitrary_dir(rng: &mut PropRng, depth: usize) -> PropDir {
+        let files = rng.below(4) as usize;
+        let dir_count = if depth >= 4 { 0 } else { rng.below(4) as usize };
+        let dirs = (0..dir_count)
+            .map(|_| arbitrary_dir(rng, depth + 1))
+            .collect();
+        let omitted = if rng.below(6) == 0 {
+            1 + rng.below(9) as usize
+        } else {
+            0
+        };
+        PropDir {
+            files,
+            dirs,
+            omitted,
+        }
+    }
+
+    /// Counts directories, including `dir`, that contain at least one file.
+    fn dirs_with_files(dir: &PropDir) -> usize {
+        usize::from(dir.files > 0) + dir.dirs.iter().map(dirs_with_files).sum::<usize>()
+    }
+
+    /// Emits the events the streaming scanner would produce for `dir`.
+    fn emit_events(
+        dir: &PropDir,
+        depth: usize,
+        next_id: &mut usize,
+        events: &mut Vec<crate::scan::StreamEvent>,
+    ) {
+        use crate::scan::StreamEvent;
+
+        let has_more_dirs = !dir.dirs.is_empty() || dir.omitted > 0;
+        for i in 0..dir.files {
+            *next_id += 1;
+            events.push(StreamEvent::Entry(Box::new(StreamEntry {
+                path: PathBuf::from(format!("f{}", next_id)),
+                name: format!("f{}", next_id),
+                kind: EntryKind::File,
+                metadata: EntryMetadata::default(),
+                depth,
+                is_last: i == dir.files - 1 && !has_more_dirs,
+                is_file: true,
+                has_more_dirs,
+                filtered: None,
+                collapsed: None,
+                error: None,
+            })));
+        }
+        for (i, sub) in dir.dirs.iter().enumerate() {
+            let is_last = i == dir.dirs.len() - 1 && dir.omitted == 0;
+            *next_id += 1;
+            events.push(StreamEvent::Entry(Box::new(StreamEntry {
+                path: PathBuf::from(format!("d{}", next_id)),
+                name: format!("d{}", next_id),
+                kind: EntryKind::Directory,
+                metadata: EntryMetadata::default(),
+                depth,
+                is_last,
+                is_file: false,
+                has_more_dirs: !is_last,
+                filtered: None,
+                collapsed: None,
+                error: None,
+            })));
+            events.push(StreamEvent::EnterDir { is_last });
+            emit_events(sub, depth + 1, next_id, events);
+            events.push(StreamEvent::LeaveDir);
+        }
+        if dir.omitted > 0 {
+            events.push(StreamEvent::Omitted {
+                depth,
+                count: dir.omitted,
+            });
+        }
+    }
+
+    /// Feeds `events` through a renderer, returning every output line and
+    /// the depth of each entry line keyed by entry name.
+    fn render_events(events: Vec<crate::scan::StreamEvent>) -> (Vec<String>, Vec<(String, usize)>) {
+        use crate::scan::StreamEvent;
+
+        let mut config = Config::default();
+        config.render.charset = CharsetMode::Ascii;
+        config.scan.show_files = true;
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+
+        let mut lines = Vec::new();
+        let mut entries = Vec::new();
+        for event in events {
+            let text = match event {
+                StreamEvent::Entry(entry) => {
+                    entries.push((entry.name.clone(), entry.depth));
+                    Some(renderer.render_entry(&entry))
+                }
+                StreamEvent::Omitted { depth, count } => {
+                    Some(renderer.render_omitted(depth, count))
+                }
+                StreamEvent::EnterDir { is_last } => {
+                    renderer.push_level(!is_last);
+                    None
+                }
+                StreamEvent::LeaveDir => renderer.pop_level(),
+            };
+            if let Some(text) = text {
+                lines.extend(text.lines().map(str::to_string));
+            }
+        }
+        assert!(renderer.is_at_root_level(), "事件结束后应回到根层级");
+        if let Some(trailing) = renderer.finish() {
+            lines.push(trailing);
+        }
+        (lines, entries)
+    }
+
+    /// Runs `check` against the rendering of many arbitrary trees.
+    fn for_arbitrary_trees(check: impl Fn(u64, &PropDir, &[String], &[(String, usize)])) {
+        for seed in 1..=300u64 {
+            let mut rng = PropRng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let root = arbitrary_dir(&mut rng, 0);
+            let mut events = Vec::new();
+            emit_events(&root, 0, &mut 0, &mut events);
+            let (lines, entries) = render_events(events);
+            check(seed, &root, &lines, &entries);
+        }
+    }
+
+    /// Returns `true` for spacer lines, which hold only connectors.
+    fn is_spacer(line: &str) -> bool {
+        line.chars().all(|c| c == '|' || c == ' ')
+    }
+
+    #[test]
+    fn should_match_prefix_depth_to_entry_depth_for_arbitrary_trees() {
+        for_arbitrary_trees(|seed, _, lines, entries| {
+            for (name, depth) in entries {
+                let line = lines
+                    .iter()
+                    .find(|l| l.ends_with(name.as_str()))
+                    .unwrap_or_else(|| panic!("seed {}: 缺少条目 {}", seed, name));
+                assert_eq!(
+                    line.len() - name.len(),
+                    (depth + 1) * 4,
+                    "seed {}: {:?} 的前缀深度错误",
+                    seed,
+                    line
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn should_leave_no_dangling_vertical_bars_for_arbitrary_trees() {
+        for_arbitrary_trees(|seed, _, lines, _| {
+            for (i, line) in lines.iter().enumerate() {
+                for (col, _) in line.char_indices().filter(|(_, c)| *c == '|') {
+                    let below = lines.get(i + 1).and_then(|l| l.as_bytes().get(col));
+                    assert!(
+                        matches!(below, Some(b'|' | b'+' | b'\\')),
+                        "seed {}: 第 {} 行第 {} 列的竖线悬空\n{}",
+                        seed,
+                        i + 1,
+                        col,
+                        lines.join("\n")
+                    );
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn should_emit_one_spacer_per_directory_with_files_for_arbitrary_trees() {
+        for_arbitrary_trees(|seed, root, lines, _| {
+            let spacers = lines.iter().filter(|l| is_spacer(l)).count();
+            assert_eq!(
+                spacers,
+                dirs_with_files(root),
+                "seed {}: 间隔行数量错误\n{}",
+                seed,
+                lines.join("\n")
+            );
+            assert!(
+                !lines
+                    .windows(2)
+                    .any(|w| is_spacer(&w[0]) && is_spacer(&w[1])),
+                "seed {}: 出现连续间隔行\n{}",
+                seed,
+                lines.join("\n")
+            );
+        });
+    }
+
+    #[test]
+    fn should_close_root_level_only_once() {
+        let mut config = Config::default();
+        config.scan.show_files = true;
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+        let mut events = Vec::new();
+        emit_events(
+            &PropDir {
+                files: 2,
+                dirs: Vec::new(),
+                omitted: 0,
+            },
+            0,
+            &mut 0,
+            &mut events,
+        );
+        for event in events {
+            if let crate::scan::StreamEvent::Entry(entry) = event {
+                let _ = renderer.render_entry(&entry);
+            }
+        }
+        assert!(renderer.finish().is_some());
+        assert!(renderer.finish().is_none());
+    }
+    #[test]
+    fn should_ellipsize_middle_of_names() {
+        assert_eq!(ellipsize_middle("abcdefghijklmnop", 9), "abc...nop");
+        assert_eq!(
+            ellipsize_middle("abcdefghijklmnop", 10),
+            "abcd...nop",
+            "奇数余量应留给开头"
+        );
+        assert_eq!(
+            ellipsize_middle("数据文件夹名称.txt", 10),
+            "数据...txt",
+            "宽字符应按显示宽度计算"
+        );
+    }
+
+    #[test]
+    fn should_ellipsize_wide_and_combining_names_within_budget() {
+        let name = "🎉🎉🎉🎉🎉🎉🎉🎉.txt";
+        let fitted = ellipsize_middle(name, 11);
+        assert!(display_width(&fitted) <= 11, "{fitted:?}");
+        assert_eq!(fitted, "🎉🎉....txt", "宽字符不应超出预算");
+
+        let fitted = ellipsize_middle("cafe\u{301}cafe\u{301}cafe\u{301}", 9);
+        assert_eq!(fitted, "caf...afe\u{301}", "组合符应随其基字符保留");
+        let fitted = ellipsize_middle("abcdefe\u{301}fg", 7);
+        assert!(
+            !fitted.contains("...\u{301}"),
+            "结尾部分不应以组合符开头: {fitted:?}"
+        );
+    }
+
+    #[test]
+    fn should_align_metadata_after_wide_names() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        for name in ["数据.txt", "📷📷.jpg", "plain.txt"] {
+            root.children.push(TreeNode::new(
+                PathBuf::from("root").join(name),
+                EntryKind::File,
+                EntryMetadata {
+                    size: 7,
+                    ..Default::default()
+                },
+            ));
+        }
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.show_size = true;
+
+        let output = render_tree_only(&root, &config);
+        let columns: Vec<usize> = output
+            .lines()
+            .filter(|line| line.ends_with('7'))
+            .map(display_width)
+            .collect();
+        assert_eq!(columns.len(), 3, "{output}");
+        assert!(
+            columns.iter().all(|&c| c == columns[0]),
+            "大小列应对齐: {output}"
+        );
+    }
+
+    #[test]
+    fn should_fit_names_only_when_truncating() {
+        let long = "a_rather_long_file_name_that_does_not_fit.txt".to_string();
+        assert_eq!(
+            fit_name(long.clone(), 4, 10, None),
+            long,
+            "未启用 --truncate 时不应缩短"
+        );
+
+        let fitted = fit_name(long.clone(), 4, 10, Some(40));
+        assert_eq!(display_width(&fitted), 25);
+        assert!(fitted.starts_with("a_rather_l") && fitted.ends_with("fit.txt"));
+
+        let highlighted = format!(
+            "{long_name}{HIGHLIGHT_START}.txt{HIGHLIGHT_END}",
+            long_name = &long[..41]
+        );
+        assert!(
+            !fit_name(highlighted, 0, 0, Some(20)).contains('\x1b'),
+            "缩短后的名称不应保留高亮"
+        );
+        assert_eq!(fit_name(long, 30, 30, Some(40)).len(), MIN_TRUNCATED_WIDTH);
+    }
+
+    #[test]
+    fn should_keep_metadata_visible_when_truncating_batch_lines() {
+        let mut root = TreeNode::new(
+            PathBuf::from("root"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/a_rather_long_file_name_that_does_not_fit.txt"),
+            EntryKind::File,
+            EntryMetadata {
+                size: 2048,
+                ..Default::default()
+            },
+        ));
+        root.children.push(TreeNode::new(
+            PathBuf::from("root/short.txt"),
+            EntryKind::File,
+            EntryMetadata {
+                size: 1,
+                ..Default::default()
+            },
+        ));
+        let mut config = Config::with_root(PathBuf::from("root"));
+        config.scan.show_files = true;
+        config.render.show_size = true;
+        config.render.line_width = Some(40);
+
+        let output = render_tree_only(&root, &config);
+        for line in output.lines() {
+            assert!(display_width(line) < 40, "行应适应控制台宽度: {line:?}");
+        }
+        assert!(
+            output.contains("...") && output.contains("2048"),
+            "应缩短名称并保留大小"
+        );
+        assert!(output.contains("short.txt"));
+    }
+
+    #[test]
+    fn should_keep_metadata_visible_when_truncating_stream_lines() {
+        let mut config = Config::default();
+        config.render.show_size = true;
+        config.scan.show_files = true;
+        config.render.line_width = Some(40);
+        let mut renderer = StreamRenderer::new(StreamRenderConfig::from_config(&config));
+
+        let entry = StreamEntry {
+            path: PathBuf::from("a_rather_long_file_name_that_does_not_fit.txt"),
+            name: "a_rather_long_file_name_that_does_not_fit.txt".to_string(),
+            kind: EntryKind::File,
+            metadata: EntryMetadata {
+                size: 1024,
+                ..Default::default()
+            },
+            depth: 0,
+            is_last: true,
+            is_file: true,
+            has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
+        };
+
+        let line = renderer.render_entry(&entry);
+        assert!(display_width(&line) < 40, "行应适应控制台宽度: {line:?}");
+        assert!(line.ends_with("1024"));
+        assert!(line.contains("..."));
+    }
+}
@@ -0,0 +1,229 @@
+//! Report module: one timestamped output file per run (`--report-dir`).
+//!
+//! Scheduled runs, such as a nightly Task Scheduler job tracking disk
+//! growth, should keep a history of trees instead of replacing one file.
+//! With `--report-dir DIR`, each run writes its output to a new file in
+//! `DIR`:
+//!
+//! - **Naming**: `<stem>-<YYYYMMDD>-<HHMMSS><extensions>` in local time,
+//!   from the file name given with `--output`, or `tree.txt`; so
+//!   `/O tree.json.gz` gives `tree-20261016-020000.json.gz`
+//! - **Rotation**: with `--rotate N`, once the report is written, the
+//!   reports of the same name in `DIR` beyond the newest `N` are deleted
+//!
+//! Reports of one name sort by file name in the order they were written.
+//! Other files in the directory, including reports of other names, are
+//! never touched.
+//!
+//! File: src/report.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// File name of the reports without `--output`.
+pub const DEFAULT_REPORT_NAME: &str = "tree.txt";
+
+/// Format of the timestamp in report names.
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Length of a formatted timestamp, such as `20261016-020000`.
+const TIMESTAMP_LEN: usize = 15;
+
+// ============================================================================
+// Public Functions
+// ============================================================================
+
+/// Returns the path of the report of a run.
+///
+/// The timestamp goes before the first `.` of the name, so all extensions,
+/// including `.gz`, are kept.
+///
+/// # Arguments
+///
+/// * `dir` - The report directory.
+/// * `name` - File name of the reports, such as `tree.json`.
+/// * `time` - Local start time of the run.
+///
+/// # Returns
+///
+/// The path of the report in `dir`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use chrono::NaiveDate;
+/// use treepp::report::report_path;
+///
+/// let time = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap().and_hms_opt(2, 0, 0).unwrap();
+/// let path = report_path(Path::new("reports"), "tree.json.gz", time);
+/// assert_eq!(path, Path::new("reports").join("tree-20261016-020000.json.gz"));
+/// ```
+#[must_use]
+pub fn report_path(dir: &Path, name: &str, time: NaiveDateTime) -> PathBuf {
+    let (stem, extensions) = split_name(name);
+    dir.join(format!(
+        "{stem}-{}{extensions}",
+        time.format(TIMESTAMP_FORMAT)
+    ))
+}
+
+/// Deletes the oldest reports of the same name as a new report.
+///
+/// Nothing is deleted unless `report` exists, so a run that did not write
+/// its report keeps the older ones.
+///
+/// # Arguments
+///
+/// * `report` - Path of the report just written, from `report_path`.
+/// * `keep` - Number of reports to keep, including `report`.
+///
+/// # Returns
+///
+/// The paths of the deleted reports, oldest first.
+///
+/// # Errors
+///
+/// Returns the I/O error if the report directory cannot be read, or if a
+/// report cannot be deleted; the error names the report. Reports older
+/// than one that failed are kept.
+pub fn rotate(report: &Path, keep: usize) -> io::Result<Vec<PathBuf>> {
+    let series = report
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(series_of);
+    let Some(series) = series.filter(|_| report.is_file()) else {
+        return Ok(Vec::new());
+    };
+    let dir = match report.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if let Ok(name) = entry.file_name().into_string()
+            && series_of(&name).is_some_and(|other| other.same_as(&series))
+            && entry.file_type()?.is_file()
+        {
+            names.push(name);
+        }
+    }
+    names.sort();
+
+    let excess = names.len().saturating_sub(keep);
+    let mut removed = Vec::with_capacity(excess);
+    for name in names.into_iter().take(excess) {
+        let path = dir.join(name);
+        fs::remove_file(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+// ============================================================================
+// Internal Functions
+// ============================================================================
+
+/// The name of a report without its timestamp.
+#[derive(Debug, PartialEq, Eq)]
+struct Series<'a> {
+    stem: &'a str,
+    extensions: &'a str,
+}
+
+impl Series<'_> {
+    /// Checks whether two reports share a name, ignoring ASCII case like
+    /// Windows file names do.
+    fn same_as(&self, other: &Series<'_>) -> bool {
+        self.stem.eq_ignore_ascii_case(other.stem)
+            && self.extensions.eq_ignore_ascii_case(other.extensions)
+    }
+}
+
+/// Splits a file name before its first `.`.
+fn split_name(name: &str) -> (&str, &str) {
+    name.find('.').map_or((name, ""), |dot| name.split_at(dot))
+}
+
+/// Returns the name a report file belongs to, or `None` if the file name
+/// has no report timestamp.
+fn series_of(file_name: &str) -> Option<Series<'_>> {
+    let (stamped, extensions) = split_name(file_name);
+    let at = stamped.len().checked_sub(TIMESTAMP_LEN + 1)?;
+    let (stem, rest) = (stamped.get(..at)?, stamped.get(at..)?);
+    let timestamp = rest.strip_prefix('-')?;
+    NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+    Some(Series { stem, extensions })
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use tempfile::TempDir;
+
+    fn night(day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(2, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn recognizes_only_timestamped_names() {
+        let series = series_of("tree-20261016-020000.json.gz").unwrap();
+        assert_eq!(series.stem, "tree");
+        assert_eq!(series.extensions, ".json.gz");
+        assert!(series.same_as(&series_of("TREE-20261017-020000.JSON.gz").unwrap()));
+
+        assert_eq!(series_of("tree.json"), None);
+        assert_eq!(series_of("tree-20261399-020000.txt"), None, "日期无效");
+        assert_eq!(series_of("tree_20261016-020000.txt"), None);
+        assert_eq!(series_of("-20261016-020000.txt").unwrap().stem, "");
+    }
+
+    #[test]
+    fn rotate_keeps_newest_reports_of_same_name() {
+        let dir = TempDir::new().unwrap();
+        let mut reports = Vec::new();
+        for day in 10..15 {
+            let path = report_path(dir.path(), DEFAULT_REPORT_NAME, night(day));
+            fs::write(&path, "").unwrap();
+            reports.push(path);
+        }
+        let other = report_path(dir.path(), "tree.json", night(1));
+        fs::write(&other, "").unwrap();
+        fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+        let removed = rotate(&reports[4], 2).unwrap();
+        assert_eq!(removed, reports[..3], "应删除最旧的三份报告");
+        assert!(reports[3].exists() && reports[4].exists());
+        assert!(other.exists(), "其他名称的报告不应删除");
+        assert!(dir.path().join("notes.txt").exists());
+
+        assert!(rotate(&reports[4], 2).unwrap().is_empty());
+        let unwritten = report_path(dir.path(), DEFAULT_REPORT_NAME, night(20));
+        assert!(
+            rotate(&unwritten, 1).unwrap().is_empty(),
+            "未写出报告时不应删除"
+        );
+    }
+}
@@ -4,32 +4,61 @@
 //!
 //! - **Unified IR**: `TreeNode` and `EntryKind` represent directory tree structure
 //! - **Scan statistics**: `ScanStats` records scan results and timing
-//! - **Parallel scanning**: Uses rayon divide-and-conquer strategy with configurable thread count
+//! - **Parallel scanning**: Uses rayon divide-and-conquer strategy with configurable thread count,
+//!   building a compact `TreeArena` rather than a `TreeNode` tree
+//! - **Backends**: `--backend` reads directories one at a time (`walk`), in
+//!   parallel into a shared arena (`parallel`), or in parallel towards a
+//!   builder thread behind a bounded channel (`channel`)
 //! - **Streaming scanning**: `scan_streaming` supports callback-based real-time output
-//! - **Filtering**: Include/exclude glob patterns, depth limits, empty directory pruning
-//! - **Gitignore support**: Layered `.gitignore` rules with inheritance and caching
+//! - **Entry iterator**: `Scanner::iter` yields the streamed entries lazily for embedders
+//! - **Path lists**: `build_tree_from_paths` builds the tree from an explicit list (`--paths-from`)
+//! - **Transforms**: `scan_with` runs `TreeTransform` hooks on the sorted tree
+//! - **Filtering**: Entries are filtered by the shared `matching::MatchEngine`
+//!   (patterns, `.gitignore`, git tracking, `--smart`); depth limits and empty
+//!   directory pruning are applied here
 //! - **Deterministic sorting**: Windows-style sorting with optional reverse order
+//! - **Long paths**: filesystem calls use `\\?\`-prefixed paths past `MAX_PATH`,
+//!   while `TreeNode` paths stay in their user-facing form
+//! - **File system access**: directories are listed through `vfs::FileSystem`,
+//!   which tests replace to inject failures and `scan_on` replaces for
+//!   targets without a disk to scan
 //!
 //! File: src/scan.rs
 //! Author: WaterRun
-//! Date: 2026-02-06
+//! Date: 2026-10-16
 
 #![forbid(unsafe_code)]
 
-use std::collections::HashMap;
-use std::fs::{self, Metadata};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, FileType, Metadata};
+use std::io::{self, Read};
+#[cfg(windows)]
+use std::os::windows::fs::MetadataExt;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
 use std::time::{Duration, Instant, SystemTime};
-use std::os::windows::fs::MetadataExt;
 
-use glob::{MatchOptions, Pattern};
-use ignore::gitignore::{Gitignore, GitignoreBuilder};
-use rayon::prelude::*;
 use rayon::ThreadPoolBuilder;
-
-use crate::config::Config;
-use crate::error::{MatchError, ScanError, TreeppResult};
+use rayon::prelude::*;
+use tracing::info;
+
+use crate::arena::{NodeId, TreeArena};
+use crate::cache::{self, CachedEntry, ScanCache};
+use crate::config::{CacheMode, Config, Language, PathListSource, ScanBackend, SortKey};
+use crate::error::{ScanError, TreeppResult};
+use crate::i18n;
+use crate::matching::{
+    FilterReason, GitignoreChain, MatchEngine, RejectionCounts, resolve_source_line,
+};
+use crate::normalize;
+use crate::snapshot::{self, SizeSnapshot};
+use crate::transform::TreeTransform;
+use crate::vfs::{FileSystem, OsFileSystem, ScannedEntry, VanishedEntry};
+use crate::winpath;
+use crate::winvol;
 
 /// Checks if a file or directory has the Windows hidden attribute.
 ///
@@ -55,8 +84,94 @@ use crate::error::{MatchError, ScanError, TreeppResult};
 /// ```
 #[must_use]
 pub fn is_hidden(metadata: &Metadata) -> bool {
-    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
-    (metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN) != 0
+    #[cfg(windows)]
+    {
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        (metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN) != 0
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// Checks if a file is a cloud placeholder whose content is not on disk.
+///
+/// OneDrive, Dropbox and other cloud sync clients leave such placeholders
+/// for files that are only kept online. They report the full size, but
+/// opening them for reading downloads the content. This checks the
+/// FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS and FILE_ATTRIBUTE_OFFLINE flags,
+/// which are part of the metadata and can be read without a download.
+/// On non-Windows platforms, this always returns false.
+///
+/// # Arguments
+///
+/// * `metadata` - The filesystem metadata to check.
+///
+/// # Returns
+///
+/// `true` if the entry is a cloud-only placeholder, `false` otherwise.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs;
+/// use treepp::scan::is_cloud_placeholder;
+///
+/// let meta = fs::metadata("some_file.txt").unwrap();
+/// let cloud_only = is_cloud_placeholder(&meta);
+/// ```
+#[must_use]
+pub fn is_cloud_placeholder(metadata: &Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+        const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+        let not_on_disk = FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS;
+        (metadata.file_attributes() & not_on_disk) != 0
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// Reads the target of a symbolic link or junction.
+///
+/// `fs::read_link` resolves both kinds of reparse points, symbolic links
+/// and junctions (mount points), so no `DeviceIoControl` call is needed.
+/// Verbatim prefixes of absolute targets are stripped for display.
+///
+/// # Arguments
+///
+/// * `path` - Path of the entry.
+/// * `file_type` - Type of the entry, as listed without following links.
+///
+/// # Returns
+///
+/// The link target, or `None` if the entry is not a link or its target
+/// cannot be read.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs;
+/// use std::path::Path;
+/// use treepp::scan::read_link_target;
+///
+/// let path = Path::new(r"C:\Users\All Users");
+/// let file_type = fs::symlink_metadata(path).unwrap().file_type();
+/// assert!(read_link_target(path, file_type).is_some());
+/// ```
+#[must_use]
+pub fn read_link_target(path: &Path, file_type: FileType) -> Option<PathBuf> {
+    if !file_type.is_symlink() {
+        return None;
+    }
+    let target = fs::read_link(winpath::to_extended(path)).ok()?;
+    Some(winpath::to_display(&target).into_owned())
 }
 
 /// Filesystem entry type distinguishing directories from files.
@@ -108,6 +223,38 @@ impl EntryKind {
             Self::File
         }
     }
+
+    /// Creates an `EntryKind` from the file type of a directory entry.
+    ///
+    /// Unlike `from_metadata`, this needs no metadata call: the type comes
+    /// with the directory listing.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_type` - The file type to examine.
+    ///
+    /// # Returns
+    ///
+    /// `EntryKind::Directory` if the file type is a directory,
+    /// `EntryKind::File` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs;
+    /// use treepp::scan::EntryKind;
+    ///
+    /// let entry = fs::read_dir(".").unwrap().next().unwrap().unwrap();
+    /// let kind = EntryKind::from_file_type(entry.file_type().unwrap());
+    /// ```
+    #[must_use]
+    pub fn from_file_type(file_type: FileType) -> Self {
+        if file_type.is_dir() {
+            Self::Directory
+        } else {
+            Self::File
+        }
+    }
 }
 
 /// Metadata for a filesystem entry.
@@ -132,6 +279,12 @@ pub struct EntryMetadata {
     pub modified: Option<SystemTime>,
     /// Creation time, if available.
     pub created: Option<SystemTime>,
+    /// Last access time, if available.
+    pub accessed: Option<SystemTime>,
+    /// Whether the entry is a cloud-only placeholder (see `is_cloud_placeholder`).
+    pub cloud_only: bool,
+    /// Target of a symbolic link or junction (see `read_link_target`).
+    pub link_target: Option<PathBuf>,
 }
 
 impl EntryMetadata {
@@ -144,7 +297,9 @@ impl EntryMetadata {
     /// # Returns
     ///
     /// A new `EntryMetadata` instance with size (for files only),
-    /// modification time, and creation time populated from the metadata.
+    /// modification, creation and access times and the cloud placeholder
+    /// flag populated from the metadata.
+    /// The link target is left empty, since reading it needs the path.
     ///
     /// # Examples
     ///
@@ -162,8 +317,35 @@ impl EntryMetadata {
             size: if meta.is_file() { meta.len() } else { 0 },
             modified: meta.modified().ok(),
             created: meta.created().ok(),
+            accessed: meta.accessed().ok(),
+            cloud_only: is_cloud_placeholder(meta),
+            link_target: None,
         }
     }
+
+    /// Checks whether the entry is a zero-byte file.
+    ///
+    /// Links are never reported as empty, since their size is not that of
+    /// the target. Sizes are only read when needed (see
+    /// `Config::needs_size_info`); without them every file looks empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The type of the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    ///
+    /// let meta = EntryMetadata::default();
+    /// assert!(meta.is_empty_file(EntryKind::File));
+    /// assert!(!meta.is_empty_file(EntryKind::Directory));
+    /// ```
+    #[must_use]
+    pub fn is_empty_file(&self, kind: EntryKind) -> bool {
+        kind == EntryKind::File && self.size == 0 && self.link_target.is_none()
+    }
 }
 
 /// A node in the directory tree structure.
@@ -200,6 +382,18 @@ pub struct TreeNode {
     pub children: Vec<TreeNode>,
     /// Cumulative size for disk usage display.
     pub disk_usage: Option<u64>,
+    /// Why the entry would be hidden, set only for entries kept by `--show-filtered`.
+    pub filtered: Option<FilterReason>,
+    /// Number of children left out by `--max-entries`.
+    pub omitted: usize,
+    /// Number of subdirectories hidden by `--du-threshold`.
+    pub small_dirs: usize,
+    /// Entry count of a directory not descended into because of `--filelimit`.
+    pub collapsed: Option<usize>,
+    /// Why the content of a directory is missing, set for unreadable directories.
+    pub error: Option<EntryError>,
+    /// Notes shown after the name, added by a `TreeTransform`.
+    pub annotations: Vec<String>,
 }
 
 impl TreeNode {
@@ -244,6 +438,12 @@ impl TreeNode {
             metadata,
             children: Vec::new(),
             disk_usage: None,
+            filtered: None,
+            omitted: 0,
+            small_dirs: 0,
+            collapsed: None,
+            error: None,
+            annotations: Vec::new(),
         }
     }
 
@@ -298,11 +498,19 @@ impl TreeNode {
             metadata,
             children,
             disk_usage: None,
+            filtered: None,
+            omitted: 0,
+            small_dirs: 0,
+            collapsed: None,
+            error: None,
+            annotations: Vec::new(),
         }
     }
 
     /// Recursively counts the number of directories (excluding root).
     ///
+    /// Entries kept by `--show-filtered` are not counted.
+    ///
     /// # Returns
     ///
     /// The total number of directory nodes in the subtree, not counting the
@@ -331,7 +539,7 @@ impl TreeNode {
         self.children
             .iter()
             .map(|c| {
-                if c.kind == EntryKind::Directory {
+                if c.kind == EntryKind::Directory && c.filtered.is_none() {
                     1 + c.count_directories()
                 } else {
                     0
@@ -342,6 +550,8 @@ impl TreeNode {
 
     /// Recursively counts the number of files.
     ///
+    /// Entries kept by `--show-filtered` are not counted.
+    ///
     /// # Returns
     ///
     /// The total number of file nodes in the subtree, including the current
@@ -367,6 +577,9 @@ impl TreeNode {
     /// ```
     #[must_use]
     pub fn count_files(&self) -> usize {
+        if self.filtered.is_some() {
+            return 0;
+        }
         let self_count = if self.kind == EntryKind::File { 1 } else { 0 };
         self_count + self.children.iter().map(Self::count_files).sum::<usize>()
     }
@@ -374,7 +587,8 @@ impl TreeNode {
     /// Recursively computes and stores cumulative directory sizes.
     ///
     /// For files, returns the file size. For directories, computes the sum
-    /// of all descendant file sizes and stores it in `disk_usage`.
+    /// of all descendant file sizes and stores it in `disk_usage`. Entries
+    /// kept by `--show-filtered` contribute nothing.
     ///
     /// # Returns
     ///
@@ -400,6 +614,9 @@ impl TreeNode {
     /// assert_eq!(root.disk_usage, Some(100));
     /// ```
     pub fn compute_disk_usage(&mut self) -> u64 {
+        if self.filtered.is_some() {
+            return 0;
+        }
         if self.kind == EntryKind::File {
             return self.metadata.size;
         }
@@ -413,17 +630,157 @@ impl TreeNode {
         self.disk_usage = Some(total);
         total
     }
+
+    /// Recursively removes directories that contain no files.
+    ///
+    /// Works post-order on the tree structure: a directory is kept only if at
+    /// least one file remains somewhere below it after its own empty
+    /// subdirectories have been removed. The node itself is never removed.
+    /// Entries kept by `--show-filtered` are retained but do not count as content;
+    /// directories collapsed by `--filelimit` always do.
+    ///
+    /// # Returns
+    ///
+    /// `true` if this node is a file or a directory that still contains files.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::scan::{TreeNode, EntryKind, EntryMetadata};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("."),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.children.push(TreeNode::new(
+    ///     PathBuf::from("empty"),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// ));
+    /// assert!(!root.prune_empty_directories());
+    /// assert!(root.children.is_empty());
+    /// ```
+    pub fn prune_empty_directories(&mut self) -> bool {
+        if self.filtered.is_some() {
+            return false;
+        }
+        if self.kind == EntryKind::File || self.collapsed.is_some() || self.error.is_some() {
+            return true;
+        }
+        let mut has_content = false;
+        self.children.retain_mut(|child| {
+            let keep = child.prune_empty_directories();
+            has_content |= keep;
+            keep || child.filtered.is_some()
+        });
+        has_content
+    }
+
+    /// Recursively removes all file nodes, keeping only directories.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::scan::{TreeNode, EntryKind, EntryMetadata};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("."),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// root.children.push(TreeNode::new(
+    ///     PathBuf::from("main.rs"),
+    ///     EntryKind::File,
+    ///     EntryMetadata::default(),
+    /// ));
+    /// root.remove_files();
+    /// assert_eq!(root.count_files(), 0);
+    /// ```
+    pub fn remove_files(&mut self) {
+        self.children.retain(|c| c.kind == EntryKind::Directory);
+        for child in &mut self.children {
+            child.remove_files();
+        }
+    }
+
+    /// Recursively counts the directories truncated by `--max-entries`.
+    ///
+    /// # Returns
+    ///
+    /// The number of nodes in the subtree, including the current node, that
+    /// have omitted children.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::scan::{TreeNode, EntryKind, EntryMetadata};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("."),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// assert_eq!(root.count_truncated(), 0);
+    /// root.omitted = 12;
+    /// assert_eq!(root.count_truncated(), 1);
+    /// ```
+    #[must_use]
+    pub fn count_truncated(&self) -> usize {
+        let self_count = usize::from(self.omitted > 0);
+        self_count
+            + self
+                .children
+                .iter()
+                .map(Self::count_truncated)
+                .sum::<usize>()
+    }
+
+    /// Recursively counts the directories left unread by `--budget-time` or
+    /// `--budget-entries`.
+    ///
+    /// # Returns
+    ///
+    /// The number of nodes in the subtree, including the current node, that
+    /// were not read because the scan budget ran out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::scan::{TreeNode, EntryError, EntryKind, EntryMetadata};
+    ///
+    /// let mut root = TreeNode::new(
+    ///     PathBuf::from("."),
+    ///     EntryKind::Directory,
+    ///     EntryMetadata::default(),
+    /// );
+    /// assert_eq!(root.count_unread(), 0);
+    /// root.error = Some(EntryError::BudgetExhausted);
+    /// assert_eq!(root.count_unread(), 1);
+    /// ```
+    #[must_use]
+    pub fn count_unread(&self) -> usize {
+        let self_count = usize::from(self.error == Some(EntryError::BudgetExhausted));
+        self_count + self.children.iter().map(Self::count_unread).sum::<usize>()
+    }
 }
 
 /// Statistics from a completed scan operation.
 ///
 /// Contains the resulting directory tree, timing information, and entry counts.
+/// The tree is kept in a `TreeArena`; use `TreeArena::to_tree` to obtain a
+/// `TreeNode`.
 ///
 /// # Examples
 ///
 /// ```
 /// use std::path::PathBuf;
 /// use std::time::Duration;
+/// use treepp::arena::TreeArena;
 /// use treepp::scan::{ScanStats, TreeNode, EntryKind, EntryMetadata};
 ///
 /// let tree = TreeNode::new(
@@ -432,24 +789,30 @@ impl TreeNode {
 ///     EntryMetadata::default(),
 /// );
 /// let stats = ScanStats {
-///     tree,
+///     tree: TreeArena::from_tree(&tree),
 ///     duration: Duration::from_millis(100),
 ///     directory_count: 5,
 ///     file_count: 20,
+///     unreadable_dirs: Vec::new(),
+///     vanished_entries: 0,
 /// };
 /// assert_eq!(stats.directory_count, 5);
 /// assert_eq!(stats.file_count, 20);
 /// ```
 #[derive(Debug)]
 pub struct ScanStats {
-    /// Root node of the scanned tree.
-    pub tree: TreeNode,
+    /// The scanned tree.
+    pub tree: TreeArena,
     /// Total scan duration.
     pub duration: Duration,
     /// Number of directories (excluding root).
     pub directory_count: usize,
     /// Number of files.
     pub file_count: usize,
+    /// Directories that could not be read, sorted; their content is missing.
+    pub unreadable_dirs: Vec<PathBuf>,
+    /// Number of entries deleted or renamed while the scan listed them; they are left out.
+    pub vanished_entries: usize,
 }
 
 /// An entry discovered during streaming scan.
@@ -471,6 +834,9 @@ pub struct ScanStats {
 ///     is_last: true,
 ///     is_file: true,
 ///     has_more_dirs: false,
+///     filtered: None,
+///     collapsed: None,
+///     error: None,
 /// };
 /// assert_eq!(entry.name, "main.rs");
 /// assert!(entry.is_last);
@@ -493,6 +859,12 @@ pub struct StreamEntry {
     pub is_file: bool,
     /// Whether more directories follow at this level.
     pub has_more_dirs: bool,
+    /// Why the entry would be hidden, set only for entries kept by `--show-filtered`.
+    pub filtered: Option<FilterReason>,
+    /// Entry count of a directory not descended into because of `--filelimit`.
+    pub collapsed: Option<usize>,
+    /// Why the content of a directory is missing, set for unreadable directories.
+    pub error: Option<EntryError>,
 }
 
 /// Simplified statistics for streaming scan (without tree structure).
@@ -501,12 +873,19 @@ pub struct StreamEntry {
 ///
 /// ```
 /// use std::time::Duration;
+/// use treepp::matching::RejectionCounts;
 /// use treepp::scan::StreamStats;
 ///
 /// let stats = StreamStats {
 ///     duration: Duration::from_millis(50),
 ///     directory_count: 3,
 ///     file_count: 10,
+///     truncated_dirs: 0,
+///     unread_dirs: 0,
+///     output_truncated: false,
+///     unreadable_dirs: Vec::new(),
+///     vanished_entries: 0,
+///     rejected: RejectionCounts::default(),
 /// };
 /// assert_eq!(stats.directory_count, 3);
 /// assert_eq!(stats.file_count, 10);
@@ -519,6 +898,18 @@ pub struct StreamStats {
     pub directory_count: usize,
     /// Number of files.
     pub file_count: usize,
+    /// Number of directories truncated by `--max-entries`.
+    pub truncated_dirs: usize,
+    /// Number of directories left unread after `--budget-time` or `--budget-entries` ran out.
+    pub unread_dirs: usize,
+    /// Whether the scan was stopped by `--max-output-size`.
+    pub output_truncated: bool,
+    /// Directories that could not be read, sorted; their content is missing.
+    pub unreadable_dirs: Vec<PathBuf>,
+    /// Number of entries deleted or renamed while the scan listed them; they are left out.
+    pub vanished_entries: usize,
+    /// Entries left out by each filter; only counted for `--count` and `--verbose`.
+    pub rejected: RejectionCounts,
 }
 
 /// Events emitted during streaming scan.
@@ -540,8 +931,11 @@ pub struct StreamStats {
 ///     is_last: true,
 ///     is_file: true,
 ///     has_more_dirs: false,
+///     filtered: None,
+///     collapsed: None,
+///     error: None,
 /// };
-/// let event = StreamEvent::Entry(entry);
+/// let event = StreamEvent::Entry(Box::new(entry));
 /// ```
 #[derive(Debug, Clone)]
 pub enum StreamEvent {
@@ -553,202 +947,82 @@ pub enum StreamEvent {
     /// Leaving a directory (after processing all children).
     LeaveDir,
     /// A discovered entry.
-    Entry(StreamEntry),
+    Entry(Box<StreamEntry>),
+    /// Entries left out by `--max-entries`; always the last item at its level.
+    Omitted {
+        /// Depth of the omitted entries (root children have depth 0).
+        depth: usize,
+        /// Number of omitted entries.
+        count: usize,
+    },
 }
 
-/// Compiles a glob pattern string into a `Pattern`.
+/// Why the content of a directory is missing from the tree.
 ///
-/// # Arguments
-///
-/// * `pattern` - The glob pattern string to compile.
-///
-/// # Returns
-///
-/// A compiled `Pattern` on success, or a `MatchError` if the pattern is invalid.
-///
-/// # Errors
-///
-/// Returns `MatchError::InvalidPattern` if the pattern syntax is invalid.
+/// Set on directory entries whose listing could not be read, so that they are
+/// shown with an annotation instead of as empty directories.
 ///
 /// # Examples
 ///
 /// ```
-/// use treepp::scan::compile_pattern;
+/// use std::io;
+/// use treepp::scan::EntryError;
 ///
-/// let pattern = compile_pattern("*.rs").unwrap();
-/// assert!(pattern.matches("main.rs"));
-/// assert!(!pattern.matches("main.txt"));
+/// let error = EntryError::from_io(&io::Error::from(io::ErrorKind::PermissionDenied));
+/// assert_eq!(error, EntryError::AccessDenied);
+/// assert_eq!(error.to_string(), "access denied");
 /// ```
-pub fn compile_pattern(pattern: &str) -> Result<Pattern, MatchError> {
-    Pattern::new(pattern).map_err(|e| MatchError::InvalidPattern {
-        pattern: pattern.to_string(),
-        reason: e.msg.to_string(),
-    })
-}
-
-/// Compiled include and exclude pattern sets.
-struct CompiledRules {
-    include_patterns: Vec<Pattern>,
-    exclude_patterns: Vec<Pattern>,
-    match_options: MatchOptions,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryError {
+    /// Reading the directory was refused.
+    AccessDenied,
+    /// Reading the directory failed for another reason.
+    ReadFailed,
+    /// Reading the directory did not finish within `--read-timeout` or `--timeout`.
+    TimedOut,
+    /// The directory is on another volume than the root and `--one-file-system` is set.
+    OtherVolume,
+    /// The directory was not read because `--budget-time` or `--budget-entries` ran out.
+    BudgetExhausted,
+    /// The directory was deleted or renamed after its parent was listed.
+    ///
+    /// Never set on an entry: such directories are left out of the tree and
+    /// counted in `vanished_entries` instead.
+    Vanished,
 }
 
-impl CompiledRules {
-    /// Compiles matching rules from configuration.
+impl EntryError {
+    /// Classifies the error returned when a directory could not be read.
     ///
     /// # Arguments
     ///
-    /// * `config` - Configuration containing pattern strings.
+    /// * `error` - The I/O error from opening the directory.
     ///
     /// # Returns
     ///
-    /// Compiled rules on success, or a `MatchError` if any pattern is invalid.
-    fn compile(config: &Config) -> Result<Self, MatchError> {
-        let include_patterns = config
-            .matching
-            .include_patterns
-            .iter()
-            .map(|p| compile_pattern(p))
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let exclude_patterns = config
-            .matching
-            .exclude_patterns
-            .iter()
-            .map(|p| compile_pattern(p))
-            .collect::<Result<Vec<_>, _>>()?;
-
-        // On Windows, file matching should be case-insensitive to match
-        // the behavior of the native filesystem and tree command.
-        let match_options = MatchOptions {
-            case_sensitive: !cfg!(windows),
-            require_literal_separator: false,
-            require_literal_leading_dot: false,
-        };
-
-        Ok(Self {
-            include_patterns,
-            exclude_patterns,
-            match_options,
-        })
-    }
-
-    /// Checks if a name should be included based on include patterns.
-    ///
-    /// Directories are always included. Files are included if no include
-    /// patterns are specified, or if they match at least one pattern.
-    fn should_include(&self, name: &str, is_dir: bool) -> bool {
-        if is_dir {
-            return true;
-        }
-        if self.include_patterns.is_empty() {
-            return true;
-        }
-        self.include_patterns
-            .iter()
-            .any(|p| p.matches_with(name, self.match_options))
-    }
-
-    /// Checks if a name should be excluded based on exclude patterns.
-    fn should_exclude(&self, name: &str) -> bool {
-        if self.exclude_patterns.is_empty() {
-            return false;
-        }
-        self.exclude_patterns
-            .iter()
-            .any(|p| p.matches_with(name, self.match_options))
-    }
-}
-
-/// A chain of gitignore rules supporting inheritance.
-///
-/// Allows child directories to inherit and extend parent rules.
-#[derive(Clone, Default)]
-struct GitignoreChain {
-    rules: Vec<Arc<Gitignore>>,
-}
-
-impl GitignoreChain {
-    /// Creates an empty rule chain.
-    fn new() -> Self {
-        Self { rules: Vec::new() }
-    }
-
-    /// Creates a new chain with an additional rule appended.
-    fn with_child(&self, gitignore: Arc<Gitignore>) -> Self {
-        let mut new_chain = self.clone();
-        new_chain.rules.push(gitignore);
-        new_chain
-    }
-
-    /// Checks if a path is ignored by any rule in the chain.
-    ///
-    /// Checks from most specific (deepest) to least specific, respecting
-    /// whitelist rules.
-    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
-        for gi in self.rules.iter().rev() {
-            let matched = gi.matched(path, is_dir);
-            if matched.is_ignore() {
-                return true;
-            }
-            if matched.is_whitelist() {
-                return false;
-            }
+    /// `AccessDenied` for permission errors, `Vanished` if the directory no
+    /// longer exists, `ReadFailed` otherwise.
+    #[must_use]
+    pub fn from_io(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => Self::AccessDenied,
+            io::ErrorKind::NotFound => Self::Vanished,
+            _ => Self::ReadFailed,
         }
-        false
     }
 }
 
-/// Thread-safe cache for loaded gitignore files.
-struct GitignoreCache {
-    cache: Mutex<HashMap<PathBuf, Option<Arc<Gitignore>>>>,
-}
-
-impl GitignoreCache {
-    /// Creates a new empty cache.
-    fn new() -> Self {
-        Self {
-            cache: Mutex::new(HashMap::new()),
-        }
-    }
-
-    /// Gets or loads the gitignore rules for a directory.
-    ///
-    /// Returns cached result if available, otherwise loads from disk and caches.
-    fn get_or_load(&self, dir: &Path) -> Option<Arc<Gitignore>> {
-        let mut cache = self.cache.lock().unwrap();
-
-        if let Some(cached) = cache.get(dir) {
-            return cached.clone();
+impl std::fmt::Display for EntryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AccessDenied => write!(f, "access denied"),
+            Self::ReadFailed => write!(f, "read failed"),
+            Self::TimedOut => write!(f, "timed out"),
+            Self::OtherVolume => write!(f, "other volume"),
+            Self::BudgetExhausted => write!(f, "budget exhausted"),
+            Self::Vanished => write!(f, "vanished"),
         }
-
-        let gitignore = load_gitignore_from_path(dir).map(Arc::new);
-        cache.insert(dir.to_path_buf(), gitignore.clone());
-        gitignore
-    }
-}
-
-/// Loads gitignore rules from a directory's `.gitignore` file.
-///
-/// # Arguments
-///
-/// * `dir` - Directory to load `.gitignore` from.
-///
-/// # Returns
-///
-/// `Some(Gitignore)` if the file exists and parses successfully, `None` otherwise.
-fn load_gitignore_from_path(dir: &Path) -> Option<Gitignore> {
-    let gitignore_path = dir.join(".gitignore");
-    if !gitignore_path.exists() {
-        return None;
-    }
-
-    let mut builder = GitignoreBuilder::new(dir);
-    if builder.add(&gitignore_path).is_some() {
-        return None;
     }
-
-    builder.build().ok()
 }
 
 /// Returns the Windows-style sort priority for a character.
@@ -843,64 +1117,418 @@ fn windows_compare_names(a: &str, b: &str) -> std::cmp::Ordering {
 /// assert_eq!(root.children[1].name, "zebra.txt");
 /// ```
 pub fn sort_tree(node: &mut TreeNode, reverse: bool) {
-    node.children.sort_by(|a, b| {
-        let kind_order = match (a.kind, b.kind) {
-            (EntryKind::Directory, EntryKind::File) => std::cmp::Ordering::Greater,
-            (EntryKind::File, EntryKind::Directory) => std::cmp::Ordering::Less,
-            _ => std::cmp::Ordering::Equal,
-        };
-
-        if kind_order != std::cmp::Ordering::Equal {
-            return kind_order;
-        }
-
-        let cmp = windows_compare_names(&a.name, &b.name);
+    sort_tree_by(node, SortOrder::by_name(reverse));
+}
 
-        if reverse {
-            cmp.reverse()
-        } else {
-            cmp
-        }
+/// Sorts tree nodes recursively in the given order.
+///
+/// # Arguments
+///
+/// * `node` - Root node to sort (modified in place).
+/// * `order` - Sort key and direction.
+pub fn sort_tree_by(node: &mut TreeNode, order: SortOrder) {
+    node.children.sort_by(|a, b| {
+        compare_entries(
+            a.kind,
+            &a.name,
+            &a.metadata,
+            b.kind,
+            &b.name,
+            &b.metadata,
+            order,
+        )
     });
 
     for child in &mut node.children {
-        sort_tree(child, reverse);
+        sort_tree_by(child, order);
     }
 }
 
-/// Sorts a list of path-metadata pairs using Windows-style ordering.
-fn sort_entries(entries: &mut [(PathBuf, Metadata)], reverse: bool) {
-    entries.sort_by(|(path_a, meta_a), (path_b, meta_b)| {
-        let is_dir_a = meta_a.is_dir();
-        let is_dir_b = meta_b.is_dir();
+/// Sort key and direction for entries of the same kind.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::config::{Config, SortKey};
+/// use treepp::scan::SortOrder;
+///
+/// let mut config = Config::default();
+/// config.render.sort_key = SortKey::Size;
+/// config.render.reverse_sort = true;
+/// let order = SortOrder::from_config(&config);
+/// assert_eq!(order.key, SortKey::Size);
+/// assert!(order.reverse);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SortOrder {
+    /// Key compared before the name.
+    pub key: SortKey,
+    /// Whether to reverse the order.
+    pub reverse: bool,
+    /// Whether to compare names as stored instead of composed to NFC.
+    pub no_normalize: bool,
+    /// Whether files and directories are ordered together instead of files
+    /// first.
+    pub interleave: bool,
+}
 
-        let kind_order = match (is_dir_a, is_dir_b) {
-            (true, false) => std::cmp::Ordering::Greater,
-            (false, true) => std::cmp::Ordering::Less,
-            _ => std::cmp::Ordering::Equal,
-        };
+impl SortOrder {
+    /// Creates a name order.
+    ///
+    /// # Arguments
+    ///
+    /// * `reverse` - Whether to reverse the order.
+    ///
+    /// # Returns
+    ///
+    /// A `SortOrder` by `SortKey::Name`.
+    #[must_use]
+    pub const fn by_name(reverse: bool) -> Self {
+        Self {
+            key: SortKey::Name,
+            reverse,
+            no_normalize: false,
+            interleave: false,
+        }
+    }
+
+    /// Reads the sort order from configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The full configuration
+    ///
+    /// # Returns
+    ///
+    /// The configured `SortOrder`.
+    #[must_use]
+    pub const fn from_config(config: &Config) -> Self {
+        Self {
+            key: config.render.sort_key,
+            reverse: config.render.reverse_sort,
+            no_normalize: config.matching.no_normalize,
+            interleave: config.render.interleave,
+        }
+    }
+}
+
+/// Compares two entries in display order.
+///
+/// Files come before directories unless `order.interleave` is set; entries
+/// of the same kind are ordered by the sort key, then by name using
+/// Windows-style ordering, reversed if `order.reverse` is set. Directories
+/// have no size of their own, so sorting by size orders them by name, and
+/// interleaved with files as if they were empty. Entries without the
+/// compared timestamp come first.
+///
+/// Names are composed to NFC before they are compared unless
+/// `order.no_normalize` is set, so a decomposed name sorts next to its
+/// composed form. This is a total order that does not depend on the system
+/// locale: names that Windows-style ordering treats as equal, e.g. that
+/// differ only in letter case, are ordered by their stored code points.
+/// Every backend therefore lists entries in the same order, whatever order
+/// the file system returns them in.
+///
+/// # Arguments
+///
+/// * `a_kind`, `a_name`, `a_meta` - Type, name and metadata of the first entry.
+/// * `b_kind`, `b_name`, `b_meta` - Type, name and metadata of the second entry.
+/// * `order` - Sort key and direction.
+///
+/// # Returns
+///
+/// The ordering of the first entry relative to the second.
+pub fn compare_entries(
+    a_kind: EntryKind,
+    a_name: &str,
+    a_meta: &EntryMetadata,
+    b_kind: EntryKind,
+    b_name: &str,
+    b_meta: &EntryMetadata,
+    order: SortOrder,
+) -> std::cmp::Ordering {
+    let kind_order = match (a_kind, b_kind) {
+        _ if order.interleave => std::cmp::Ordering::Equal,
+        (EntryKind::Directory, EntryKind::File) => std::cmp::Ordering::Greater,
+        (EntryKind::File, EntryKind::Directory) => std::cmp::Ordering::Less,
+        _ => std::cmp::Ordering::Equal,
+    };
 
-        if kind_order != std::cmp::Ordering::Equal {
-            return kind_order;
+    if kind_order != std::cmp::Ordering::Equal {
+        return kind_order;
+    }
+
+    let cmp = match order.key {
+        SortKey::Name => std::cmp::Ordering::Equal,
+        // Directories are ordered by cumulative size in `TreeArena::sort`.
+        SortKey::Size | SortKey::Share => a_meta.size.cmp(&b_meta.size),
+        SortKey::Modified => a_meta.modified.cmp(&b_meta.modified),
+        SortKey::Created => a_meta.created.cmp(&b_meta.created),
+        SortKey::Accessed => a_meta.accessed.cmp(&b_meta.accessed),
+    }
+    .then_with(|| {
+        if order.no_normalize {
+            windows_compare_names(a_name, b_name)
+        } else {
+            windows_compare_names(&normalize::nfc(a_name), &normalize::nfc(b_name))
         }
+    })
+    .then_with(|| a_name.cmp(b_name));
+
+    if order.reverse { cmp.reverse() } else { cmp }
+}
 
-        let name_a = path_a
+/// Sorts a list of scanned entries in display order.
+fn sort_entries(entries: &mut [ScannedEntry], order: SortOrder) {
+    entries.sort_by(|a, b| {
+        let name_a = a
+            .path
             .file_name()
             .map(|s| s.to_string_lossy().into_owned())
             .unwrap_or_default();
-        let name_b = path_b
+        let name_b = b
+            .path
             .file_name()
             .map(|s| s.to_string_lossy().into_owned())
             .unwrap_or_default();
 
-        let cmp = windows_compare_names(&name_a, &name_b);
+        compare_entries(
+            a.kind,
+            &name_a,
+            &a.metadata,
+            b.kind,
+            &name_b,
+            &b.metadata,
+            order,
+        )
+    });
+}
+
+/// A directory listing with the entries that could not be inspected.
+type Listing = Vec<Result<ScannedEntry, VanishedEntry>>;
 
-        if reverse {
-            cmp.reverse()
-        } else {
-            cmp
+/// Reads and inspects the entries of a directory on the current thread.
+fn read_entries(
+    fs: &dyn FileSystem,
+    path: &Path,
+    needs_metadata: bool,
+) -> Result<Listing, EntryError> {
+    fs.read_dir(path, needs_metadata)
+        .map_err(|e| EntryError::from_io(&e))
+}
+
+/// Reads the entries of a directory within the scan's time limits.
+///
+/// Without `--read-timeout` or `--timeout` the directory is read directly.
+/// Otherwise it is read on a worker thread; if the read does not finish in
+/// time, the worker is left behind (a hung network share may never return)
+/// and the scan moves on.
+///
+/// # Errors
+///
+/// Returns `EntryError::TimedOut` if the time limit ran out, or the error
+/// of the read itself.
+fn read_entries_timed(path: &Path, ctx: &ScanContext) -> Result<Listing, EntryError> {
+    let needs_metadata = ctx.needs_metadata;
+    let Some(limit) = ctx.read_limit() else {
+        return read_entries(ctx.fs.as_ref(), path, needs_metadata);
+    };
+    if limit.is_zero() {
+        return Err(EntryError::TimedOut);
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let owned = path.to_path_buf();
+    let fs = Arc::clone(&ctx.fs);
+    let spawned = thread::Builder::new()
+        .name("treepp-read".to_string())
+        .spawn(move || {
+            let _ = sender.send(read_entries(fs.as_ref(), &owned, needs_metadata));
+        });
+    if spawned.is_err() {
+        return read_entries(ctx.fs.as_ref(), path, needs_metadata);
+    }
+    match receiver.recv_timeout(limit) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(EntryError::TimedOut),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(EntryError::ReadFailed),
+    }
+}
+
+/// Lists the entries of a directory, unsorted and unfiltered.
+///
+/// With `--cache`, a directory the change journal reports as unchanged, or
+/// whose modification time matches its cached listing, is not read again;
+/// other directories are read and recorded.
+///
+/// # Errors
+///
+/// Returns the `EntryError` if the directory cannot be read; the directory
+/// is then recorded as unreadable, or as vanished if it no longer exists.
+/// A directory on another volume than the root is not read with
+/// `--one-file-system`, and not recorded either.
+fn list_dir(path: &Path, ctx: &ScanContext) -> Result<Vec<ScannedEntry>, EntryError> {
+    if ctx.budget.is_exhausted() {
+        return Err(EntryError::BudgetExhausted);
+    }
+    if ctx.is_other_volume(path) {
+        return Err(EntryError::OtherVolume);
+    }
+
+    let entries = read_listing(path, ctx)?;
+    ctx.budget.charge(entries.len());
+    Ok(entries)
+}
+
+/// Reads the entries of a directory, from the scan cache if it is current.
+///
+/// Entries deleted or renamed between the listing and their inspection are
+/// left out and recorded as vanished, as is the directory itself if it no
+/// longer exists.
+///
+/// # Errors
+///
+/// Returns the `EntryError` if the directory cannot be read.
+fn read_listing(path: &Path, ctx: &ScanContext) -> Result<Vec<ScannedEntry>, EntryError> {
+    let read = || -> Result<Vec<ScannedEntry>, EntryError> {
+        match read_entries_timed(path, ctx) {
+            Ok(listing) => Ok(listing
+                .into_iter()
+                .filter_map(|entry| entry.inspect_err(|e| ctx.record_vanished(&e.path)).ok())
+                .collect()),
+            Err(EntryError::Vanished) => {
+                ctx.record_vanished(path);
+                Err(EntryError::Vanished)
+            }
+            Err(error) => {
+                ctx.record_unreadable(path);
+                Err(error)
+            }
         }
-    });
+    };
+
+    let Some(cache) = &ctx.cache else {
+        return read();
+    };
+    if let Some(cached) = cache.unchanged(path) {
+        return Ok(cached_entries(path, cached));
+    }
+    let Some(modified) = ctx
+        .fs
+        .inspect(path)
+        .ok()
+        .and_then(|dir| dir.metadata.modified)
+    else {
+        return read();
+    };
+
+    if let Some(cached) = cache.lookup(path, modified) {
+        return Ok(cached_entries(path, cached));
+    }
+
+    let entries = read()?;
+    let listing = entries
+        .iter()
+        .map(|entry| CachedEntry {
+            name: entry
+                .path
+                .file_name()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            is_dir: entry.kind == EntryKind::Directory,
+            hidden: entry.hidden,
+            cloud_only: entry.metadata.cloud_only,
+            link_target: entry.metadata.link_target.clone(),
+        })
+        .collect();
+    cache.record(path, modified, listing);
+    Ok(entries)
+}
+
+/// Turns the cached listing of a directory into scanned entries.
+fn cached_entries(dir: &Path, cached: &[CachedEntry]) -> Vec<ScannedEntry> {
+    cached
+        .iter()
+        .map(|entry| ScannedEntry {
+            path: dir.join(&entry.name),
+            kind: if entry.is_dir {
+                EntryKind::Directory
+            } else {
+                EntryKind::File
+            },
+            hidden: entry.hidden,
+            metadata: EntryMetadata {
+                cloud_only: entry.cloud_only,
+                link_target: entry.link_target.clone(),
+                ..Default::default()
+            },
+        })
+        .collect()
+}
+
+/// Writes back the listings read during a scan with `--cache`.
+///
+/// Failing to write the cache is not an error: it only costs the next run
+/// its speed-up.
+fn save_cache(ctx: &ScanContext) {
+    if let Some(cache) = &ctx.cache {
+        cache.save().ok();
+    }
+}
+
+/// Opens the scan cache according to `--cache` / `--no-cache`.
+///
+/// `--no-cache` deletes the cache of the scan root. Failing to do so is not
+/// an error: the scan itself does not depend on it.
+fn open_cache(config: &Config) -> Option<ScanCache> {
+    match config.scan.cache {
+        CacheMode::Enabled => Some(ScanCache::open(&config.root_path)),
+        CacheMode::Discard => {
+            cache::remove(&config.root_path).ok();
+            None
+        }
+        CacheMode::Disabled => None,
+    }
+}
+
+/// The `--budget-time` and `--budget-entries` limits, shared by all scanning threads.
+///
+/// Once either runs out, no further directories are read; those already read
+/// are still listed. The root is always read, so that the tree is never empty.
+#[derive(Debug, Default)]
+struct ScanBudget {
+    deadline: Option<Instant>,
+    entries: Option<usize>,
+    listed: AtomicUsize,
+}
+
+impl ScanBudget {
+    /// Creates the budget of a scan starting now.
+    fn from_config(config: &Config) -> Self {
+        Self {
+            deadline: config
+                .scan
+                .budget_time
+                .and_then(|budget| Instant::now().checked_add(budget)),
+            entries: config.scan.budget_entries,
+            listed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Checks whether the next directory must be left unread.
+    fn is_exhausted(&self) -> bool {
+        let listed = self.listed.load(Ordering::Relaxed);
+        listed > 0
+            && (self.entries.is_some_and(|limit| listed >= limit)
+                || self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline))
+    }
+
+    /// Counts the entries of a directory that was read.
+    fn charge(&self, entries: usize) {
+        self.listed.fetch_add(entries, Ordering::Relaxed);
+    }
 }
 
 /// Internal scan context holding all scan configuration.
@@ -908,219 +1536,811 @@ struct ScanContext {
     show_files: bool,
     collect_files_for_size: bool,
     max_depth: Option<usize>,
-    respect_gitignore: bool,
-    rules: CompiledRules,
+    engine: MatchEngine,
     reverse: bool,
+    sort_key: SortKey,
+    no_normalize: bool,
+    interleave: bool,
     needs_size: bool,
-    gitignore_cache: Arc<GitignoreCache>,
-    show_hidden: bool,
+    needs_metadata: bool,
+    prune_empty: bool,
+    show_filtered: bool,
+    max_entries: Option<usize>,
+    file_limit: Option<usize>,
+    cache: Option<ScanCache>,
+    unreadable: Mutex<BTreeSet<PathBuf>>,
+    vanished: Mutex<BTreeSet<PathBuf>>,
+    rejected: Option<Mutex<RejectionCounts>>,
+    read_timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    budget: ScanBudget,
+    root_volume: Option<u32>,
+    backend: ScanBackend,
+    fs: Arc<dyn FileSystem>,
 }
 
 impl ScanContext {
     /// Creates a scan context from configuration.
-    fn from_config(config: &Config) -> Result<Self, MatchError> {
+    fn from_config(config: &Config) -> TreeppResult<Self> {
         Ok(Self {
             show_files: config.scan.show_files,
             collect_files_for_size: config.render.show_disk_usage,
             max_depth: config.scan.max_depth,
-            respect_gitignore: config.scan.respect_gitignore,
-            rules: CompiledRules::compile(config)?,
+            engine: MatchEngine::from_config(config)?,
             reverse: config.render.reverse_sort,
+            sort_key: config.render.sort_key,
+            no_normalize: config.matching.no_normalize,
+            interleave: config.render.interleave,
             needs_size: config.needs_size_info(),
-            gitignore_cache: Arc::new(GitignoreCache::new()),
-            show_hidden: config.scan.show_hidden,
+            // Cached listings record the hidden and cloud-only attributes, which
+            // need the metadata.
+            needs_metadata: config.needs_entry_metadata()
+                || config.scan.cache == CacheMode::Enabled,
+            prune_empty: config.matching.prune_empty,
+            show_filtered: config.scan.show_filtered,
+            max_entries: config.scan.max_entries,
+            file_limit: config.scan.file_limit,
+            cache: None,
+            unreadable: Mutex::new(BTreeSet::new()),
+            vanished: Mutex::new(BTreeSet::new()),
+            rejected: (config.render.count_only || config.output.verbosity > 0)
+                .then(Mutex::default),
+            read_timeout: config.scan.read_timeout,
+            deadline: config
+                .scan
+                .timeout
+                .and_then(|timeout| Instant::now().checked_add(timeout)),
+            budget: ScanBudget::from_config(config),
+            root_volume: config
+                .scan
+                .one_file_system
+                .then(|| winvol::volume_serial(&winpath::to_extended(&config.root_path)))
+                .flatten(),
+            backend: config.scan.backend.resolve(config.scan.thread_count),
+            fs: Arc::new(OsFileSystem),
         })
     }
 
-    /// Checks if an entry should be filtered out.
-    fn should_filter(&self, name: &str, is_dir: bool, metadata: Option<&Metadata>) -> bool {
-        // Check hidden attribute first (unless show_hidden is enabled)
-        if !self.show_hidden {
-            if let Some(meta) = metadata {
-                if is_hidden(meta) {
-                    return true;
-                }
-            }
+    /// Returns how long the next directory read may take.
+    ///
+    /// # Returns
+    ///
+    /// The shorter of `--read-timeout` and the time left before the
+    /// `--timeout` deadline, or `None` if neither is set.
+    fn read_limit(&self) -> Option<Duration> {
+        let remaining = self
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        match (self.read_timeout, remaining) {
+            (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+            (timeout, remaining) => timeout.or(remaining),
         }
+    }
 
-        if self.rules.should_exclude(name) {
-            return true;
-        }
+    /// Checks whether a directory is on another volume than the root
+    /// (`--one-file-system`).
+    ///
+    /// Directories whose volume cannot be determined are treated as being on
+    /// the root's volume.
+    fn is_other_volume(&self, path: &Path) -> bool {
+        self.root_volume.is_some_and(|root| {
+            winvol::volume_serial(&winpath::to_extended(path)).is_some_and(|serial| serial != root)
+        })
+    }
 
-        if !is_dir && !self.rules.should_include(name, is_dir) {
-            return true;
-        }
+    /// Records a directory whose entries could not be read.
+    ///
+    /// A directory may be read more than once (e.g. for `--filelimit`), so
+    /// paths are kept in a set.
+    fn record_unreadable(&self, path: &Path) {
+        self.unreadable.lock().unwrap().insert(path.to_path_buf());
+    }
 
-        if !is_dir && !self.show_files && !self.collect_files_for_size {
-            return true;
-        }
+    /// Returns the directories recorded as unreadable, sorted.
+    fn take_unreadable(&self) -> Vec<PathBuf> {
+        std::mem::take(&mut *self.unreadable.lock().unwrap())
+            .into_iter()
+            .collect()
+    }
 
-        false
+    /// Records an entry that was deleted or renamed after it was listed.
+    ///
+    /// Kept in a set like the unreadable directories, so that an entry seen
+    /// by more than one read is counted once.
+    fn record_vanished(&self, path: &Path) {
+        self.vanished.lock().unwrap().insert(path.to_path_buf());
     }
 
-    /// Gets or loads gitignore rules for a directory.
-    fn get_gitignore(&self, dir: &Path) -> Option<Arc<Gitignore>> {
-        if !self.respect_gitignore {
-            return None;
+    /// Returns the number of entries recorded as vanished.
+    fn vanished_count(&self) -> usize {
+        self.vanished.lock().unwrap().len()
+    }
+
+    /// Counts an entry left out by a filter, for `--count` and `--verbose`.
+    fn record_rejection(&self, reason: &FilterReason) {
+        if let Some(ref rejected) = self.rejected {
+            rejected.lock().unwrap().record(reason);
         }
-        self.gitignore_cache.get_or_load(dir)
     }
-}
 
-/// Recursively scans a directory and builds a tree node.
-fn scan_dir(
-    path: &Path,
-    depth: usize,
-    ctx: &ScanContext,
-    parent_chain: GitignoreChain,
-) -> Option<TreeNode> {
-    let meta = fs::metadata(path).ok()?;
-    let kind = EntryKind::from_metadata(&meta);
-    let metadata = EntryMetadata::from_fs_metadata(&meta);
+    /// Adds entries counted locally by a scan thread.
+    fn record_rejections(&self, counts: &RejectionCounts) {
+        if let Some(ref rejected) = self.rejected
+            && counts.total() > 0
+        {
+            rejected.lock().unwrap().merge(counts);
+        }
+    }
 
-    if kind != EntryKind::Directory {
-        return Some(TreeNode::new(path.to_path_buf(), kind, metadata));
+    /// Returns the entries counted by `record_rejection`.
+    fn take_rejected(&self) -> RejectionCounts {
+        self.rejected
+            .as_ref()
+            .map(|rejected| std::mem::take(&mut *rejected.lock().unwrap()))
+            .unwrap_or_default()
     }
 
-    if let Some(max) = ctx.max_depth {
-        if depth >= max && !ctx.collect_files_for_size {
-            return Some(TreeNode::new(path.to_path_buf(), kind, metadata));
+    /// Returns the configured sort order.
+    const fn sort_order(&self) -> SortOrder {
+        SortOrder {
+            key: self.sort_key,
+            reverse: self.reverse,
+            no_normalize: self.no_normalize,
+            interleave: self.interleave,
         }
     }
 
-    let current_chain = if ctx.respect_gitignore {
-        if let Some(gi) = ctx.get_gitignore(path) {
-            parent_chain.with_child(gi)
-        } else {
-            parent_chain
+    /// Decides how an entry with the given filter result is listed.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the entry is dropped, `Some(None)` if it is kept, and
+    /// `Some(Some(reason))` if it is filtered but kept by `--show-filtered`,
+    /// with the `.gitignore` line of the reason resolved.
+    fn annotation(
+        &self,
+        reason: Option<FilterReason>,
+        is_dir: bool,
+    ) -> Option<Option<FilterReason>> {
+        match reason {
+            None => Some(None),
+            Some(reason)
+                if self.show_filtered && reason.is_rule_match() && (is_dir || self.show_files) =>
+            {
+                Some(Some(resolve_source_line(reason)))
+            }
+            Some(_) => None,
         }
-    } else {
-        parent_chain
-    };
-
-    let entries: Vec<_> = fs::read_dir(path).ok()?.flatten().collect();
-
-    let mut subdirs = Vec::new();
-    let mut files = Vec::new();
+    }
 
-    for entry in entries {
-        let entry_path = entry.path();
-        let entry_name = entry_path
-            .file_name()
-            .map(|s| s.to_string_lossy().into_owned())
-            .unwrap_or_default();
+    /// Checks whether a directory at `depth` has its content listed.
+    fn descends_into(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max| depth < max)
+    }
 
-        let entry_meta = match entry.metadata() {
-            Ok(m) => m,
-            Err(_) => continue,
-        };
+    /// Applies `--filelimit` to a directory with `listed` displayable entries.
+    ///
+    /// # Returns
+    ///
+    /// The entry count if the directory is to be collapsed, `None` otherwise.
+    fn collapsed_count(&self, listed: usize) -> Option<usize> {
+        self.file_limit
+            .filter(|&limit| listed > limit)
+            .map(|_| listed)
+    }
+}
 
-        let is_dir = entry_meta.is_dir();
+/// An entry read by `scan_dir`, before it is added to the tree.
+#[derive(Debug)]
+struct ListedEntry {
+    name: String,
+    kind: EntryKind,
+    metadata: EntryMetadata,
+    collapsed: Option<usize>,
+    error: Option<EntryError>,
+    filtered: Option<FilterReason>,
+}
 
-        if ctx.respect_gitignore && current_chain.is_ignored(&entry_path, is_dir) {
-            continue;
+impl ListedEntry {
+    /// Creates an entry that is neither collapsed, failed nor filtered.
+    fn new(name: String, kind: EntryKind, metadata: EntryMetadata) -> Self {
+        Self {
+            name,
+            kind,
+            metadata,
+            collapsed: None,
+            error: None,
+            filtered: None,
         }
+    }
+}
 
-        if ctx.should_filter(&entry_name, is_dir, Some(&entry_meta)) {
-            continue;
-        }
+/// Destination of the entries read by `scan_dir`.
+///
+/// The `walk` and `parallel` backends add entries to a shared arena; the
+/// `channel` backend hands them to a single thread that builds the arena.
+trait EntrySink: Sync {
+    /// Handle of an added directory, passed back as the parent of its entries.
+    type Key: Copy + Send + Sync;
+
+    /// Adds an entry, and the files listed in it if it is a directory.
+    fn add(
+        &self,
+        parent: Option<Self::Key>,
+        entry: ListedEntry,
+        files: Vec<ListedEntry>,
+    ) -> Self::Key;
+}
 
-        if is_dir {
-            subdirs.push(entry_path);
-        } else {
-            let file_metadata = EntryMetadata::from_fs_metadata(&entry_meta);
-            files.push(TreeNode::new(entry_path, EntryKind::File, file_metadata));
+impl EntrySink for Mutex<TreeArena> {
+    type Key = NodeId;
+
+    /// Adds a directory and its files under a single lock.
+    fn add(&self, parent: Option<NodeId>, entry: ListedEntry, files: Vec<ListedEntry>) -> NodeId {
+        let mut arena = self.lock().unwrap();
+        let id = add_listed(&mut arena, parent, entry);
+        for file in files {
+            add_listed(&mut arena, Some(id), file);
         }
+        id
     }
+}
 
-    let subdir_trees: Vec<TreeNode> = subdirs
-        .into_par_iter()
-        .filter_map(|subdir| scan_dir(&subdir, depth + 1, ctx, current_chain.clone()))
-        .collect();
-
-    let mut children = subdir_trees;
-    children.extend(files);
+/// Adds a listed entry to an arena.
+fn add_listed(arena: &mut TreeArena, parent: Option<NodeId>, entry: ListedEntry) -> NodeId {
+    let id = arena.add(parent, &entry.name, entry.kind, entry.metadata);
+    arena.set_collapsed(id, entry.collapsed);
+    arena.set_error(id, entry.error);
+    arena.set_filtered(id, entry.filtered);
+    id
+}
 
-    Some(TreeNode::with_children(
-        path.to_path_buf(),
-        EntryKind::Directory,
-        metadata,
-        children,
-    ))
+/// An entry sent to the tree builder of the `channel` backend.
+#[derive(Debug)]
+struct FlatEntry {
+    /// Key of this entry, unique within the scan.
+    key: usize,
+    /// Key of the directory the entry was listed in; `None` for the root.
+    parent: Option<usize>,
+    entry: ListedEntry,
 }
 
-/// Scans a directory tree and returns the result with statistics.
-///
-/// Uses rayon for parallel scanning with configurable thread count.
+/// Number of entries the workers of the `channel` backend may read ahead
+/// of the tree builder.
 ///
-/// # Arguments
-///
-/// * `config` - Scan configuration specifying root path, filters, and options.
+/// Workers wait once the channel is full, so a builder that falls behind
+/// slows the scan down instead of letting entries pile up in memory.
+const CHANNEL_CAPACITY: usize = 4096;
+
+/// Sink of the `channel` backend: numbers entries and sends them on.
 ///
-/// # Returns
+/// A directory is sent before its subdirectories are read, so the builder
+/// always receives a parent before the entries listed in it.
+struct ChannelSink {
+    entries: mpsc::SyncSender<FlatEntry>,
+    next_key: AtomicUsize,
+}
+
+impl ChannelSink {
+    /// Numbers an entry and sends it to the builder, waiting while the
+    /// channel is full.
+    fn send(&self, parent: Option<usize>, entry: ListedEntry) -> usize {
+        let key = self.next_key.fetch_add(1, Ordering::Relaxed);
+        // The builder only stops receiving if it panicked, which
+        // `scan_channel` reports once the scan has ended.
+        let _ = self.entries.send(FlatEntry { key, parent, entry });
+        key
+    }
+}
+
+impl EntrySink for ChannelSink {
+    type Key = usize;
+
+    fn add(&self, parent: Option<usize>, entry: ListedEntry, files: Vec<ListedEntry>) -> usize {
+        let key = self.send(parent, entry);
+        for file in files {
+            self.send(Some(key), file);
+        }
+        key
+    }
+}
+
+/// Scans the root directory with the `channel` backend.
 ///
-/// `ScanStats` containing the tree, timing, and counts on success.
+/// Workers of the thread pool read directories and send their entries
+/// through a channel of `capacity` entries to a builder thread, which adds
+/// them to the arena as they arrive. Only the builder touches the arena, so
+/// workers never wait for each other, and memory beyond the arena itself
+/// stays bounded by the channel.
 ///
 /// # Errors
 ///
-/// Returns `ScanError::PathNotFound` if the root path doesn't exist.
-/// Returns `ScanError::NotADirectory` if the root path is not a directory.
-/// Returns `MatchError` if pattern compilation fails.
-///
-/// # Examples
-///
-/// ```no_run
-/// use std::path::PathBuf;
-/// use treepp::config::Config;
-/// use treepp::scan::scan;
-///
-/// let config = Config::with_root(PathBuf::from(".")).validate().unwrap();
-/// let stats = scan(&config).expect("scan failed");
-/// println!("{} directories, {} files", stats.directory_count, stats.file_count);
-/// ```
-pub fn scan(config: &Config) -> TreeppResult<ScanStats> {
-    let start = Instant::now();
+/// Returns `ScanError::ReadDirFailed` if the root cannot be read, or
+/// `ScanError::WalkError` if the thread pool cannot be created or the
+/// builder thread panics.
+fn scan_channel(config: &Config, ctx: &ScanContext, capacity: usize) -> TreeppResult<TreeArena> {
+    let pool = build_thread_pool(config)?;
+    let (entries, received) = mpsc::sync_channel(capacity);
+    let sink = ChannelSink {
+        entries,
+        next_key: AtomicUsize::new(0),
+    };
+    let (scanned, built) = thread::scope(|scope| {
+        let builder = scope.spawn(|| build_from_channel(config.root_path.clone(), received));
+        let scanned = pool.install(|| scan_root(config, ctx, &sink));
+        // Closing the channel lets the builder finish.
+        drop(sink);
+        (scanned, builder.join())
+    });
+    let tree = built.map_err(|_| ScanError::WalkError {
+        message: "tree builder thread panicked".to_string(),
+        path: Some(config.root_path.clone()),
+    })?;
+    scanned?;
+    Ok(tree)
+}
 
-    if !config.root_path.exists() {
-        return Err(ScanError::PathNotFound {
-            path: config.root_path.clone(),
+/// Builds an arena from the entries sent by a `ChannelSink`, in the order
+/// they were sent, until every sender is gone.
+fn build_from_channel(root: PathBuf, entries: mpsc::Receiver<FlatEntry>) -> TreeArena {
+    let mut arena = TreeArena::new(root);
+    // Only directories can be parents, so only their ids are kept.
+    let mut ids = HashMap::new();
+    for flat in entries {
+        let parent = flat.parent.map(|key| ids[&key]);
+        let is_dir = flat.entry.kind == EntryKind::Directory;
+        let id = add_listed(&mut arena, parent, flat.entry);
+        if is_dir {
+            ids.insert(flat.key, id);
         }
-            .into());
     }
+    arena
+}
 
-    if !config.root_path.is_dir() {
-        return Err(ScanError::NotADirectory {
-            path: config.root_path.clone(),
-        }
-            .into());
-    }
+/// Recursively scans a directory into a sink.
+///
+/// The entry for `path` is added below `parent` once its entries have been
+/// read; a directory that cannot be read is added without content and
+/// carries its `EntryError`. Subdirectories are scanned in parallel, except
+/// by the `walk` backend, which reads them one after the other.
+///
+/// # Returns
+///
+/// The key of the added entry, or `None` if `path` itself cannot be
+/// inspected or, for the root, read.
+fn scan_dir<S: EntrySink>(
+    path: &Path,
+    parent: Option<S::Key>,
+    depth: usize,
+    ctx: &ScanContext,
+    parent_chain: GitignoreChain,
+    sink: &S,
+) -> Option<S::Key> {
+    let ScannedEntry { kind, metadata, .. } = match ctx.fs.inspect(path) {
+        Ok(entry) => entry,
+        Err(_) => {
+            // The root was checked before the scan; anything else was listed
+            // by its parent and has since disappeared.
+            if parent.is_some() {
+                ctx.record_vanished(path);
+            }
+            return None;
+        }
+    };
+    let name = path
+        .file_name()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_else(|| path.to_string_lossy())
+        .into_owned();
+
+    let add_leaf = |collapsed: Option<usize>, error: Option<EntryError>| {
+        let entry = ListedEntry {
+            collapsed,
+            error,
+            ..ListedEntry::new(name.clone(), kind, metadata.clone())
+        };
+        sink.add(parent, entry, Vec::new())
+    };
 
-    let ctx = ScanContext::from_config(config)?;
+    if kind != EntryKind::Directory {
+        return Some(add_leaf(None, None));
+    }
 
-    let thread_count = config.scan.thread_count.get();
-    let pool = ThreadPoolBuilder::new()
-        .num_threads(thread_count)
-        .build()
-        .map_err(|e| ScanError::WalkError {
-            message: format!("thread pool creation failed: {}", e),
-            path: Some(config.root_path.clone()),
-        })?;
+    if let Some(max) = ctx.max_depth
+        && depth >= max
+        && !ctx.collect_files_for_size
+        && !ctx.prune_empty
+    {
+        return Some(add_leaf(None, None));
+    }
 
-    let initial_chain = GitignoreChain::new();
+    let current_chain = ctx.engine.chain_for(path, &parent_chain);
 
-    let root_path = config.root_path.clone();
-    let mut tree = pool
-        .install(|| scan_dir(&root_path, 0, &ctx, initial_chain))
-        .ok_or_else(|| ScanError::ReadDirFailed {
+    let entries = match list_dir(path, ctx) {
+        Ok(entries) => entries,
+        Err(_) if parent.is_none() => return None,
+        Err(EntryError::Vanished) => return None,
+        Err(error) => return Some(add_leaf(None, Some(error))),
+    };
+
+    let mut subdirs = Vec::new();
+    let mut files = Vec::new();
+    let mut rejected = RejectionCounts::default();
+
+    // Filtering runs on the worker thread that read the directory, with the
+    // gitignore chain of this branch; only the counts are shared.
+    for entry in entries {
+        let is_dir = entry.kind == EntryKind::Directory;
+
+        let reason = ctx.engine.filter_reason(
+            &entry.path,
+            is_dir,
+            entry.hidden,
+            entry.metadata.cloud_only,
+            entry.metadata.is_empty_file(entry.kind),
+            &current_chain,
+        );
+        if let Some(ref reason) = reason {
+            rejected.record(reason);
+            if is_dir {
+                rejected.record_subtree();
+            }
+        }
+        let Some(filtered) = ctx.annotation(reason, is_dir) else {
+            continue;
+        };
+
+        if filtered.is_some() || !is_dir {
+            let file_name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+            files.push(ListedEntry {
+                filtered,
+                ..ListedEntry::new(file_name.into_owned(), entry.kind, entry.metadata)
+            });
+        } else {
+            subdirs.push(entry.path);
+        }
+    }
+    ctx.record_rejections(&rejected);
+
+    let listed_files = if ctx.show_files {
+        files
+            .iter()
+            .filter(|file| file.kind == EntryKind::File && file.filtered.is_none())
+            .count()
+    } else {
+        0
+    };
+    let collapsed = if depth > 0 && ctx.descends_into(depth) {
+        ctx.collapsed_count(subdirs.len() + listed_files)
+    } else {
+        None
+    };
+    if collapsed.is_some() && !ctx.collect_files_for_size {
+        return Some(add_leaf(collapsed, None));
+    }
+
+    // Collapsed directories are scanned for disk usage only; see `TreeArena::clear_collapsed`.
+    let entry = ListedEntry {
+        collapsed,
+        ..ListedEntry::new(name, EntryKind::Directory, metadata)
+    };
+    let id = sink.add(parent, entry, files);
+
+    let scan_subdir = |subdir: PathBuf| {
+        scan_dir(
+            &subdir,
+            Some(id),
+            depth + 1,
+            ctx,
+            current_chain.clone(),
+            sink,
+        );
+    };
+    if ctx.backend == ScanBackend::Walk {
+        subdirs.into_iter().for_each(scan_subdir);
+    } else {
+        subdirs.into_par_iter().for_each(scan_subdir);
+    }
+
+    Some(id)
+}
+
+/// Removes empty directories when `/P` is set.
+///
+/// Files are retained during scanning so that directories can be judged by
+/// their content; once pruning is done they are dropped again unless they
+/// are displayed (`/F`) or needed for disk usage.
+fn apply_prune(tree: &mut TreeArena, config: &Config) {
+    if !config.matching.prune_empty {
+        return;
+    }
+    tree.prune_empty_directories();
+    if !config.scan.show_files && !config.render.show_disk_usage {
+        tree.remove_files();
+    }
+}
+
+/// Compares directory sizes with the previous run when `--du-delta` is set.
+///
+/// Must run after disk usage has been computed and before any directory is
+/// hidden, so that the snapshot written for the next run is complete. Failing
+/// to write it is not an error: only the next run's changes depend on it.
+fn apply_size_snapshot(tree: &mut TreeArena, config: &Config) {
+    if !config.render.show_du_delta {
+        return;
+    }
+    let file = snapshot::snapshot_file_path(&config.root_path);
+    if let Some(previous) = SizeSnapshot::load(&file, &config.root_path) {
+        previous.apply(tree);
+    }
+    SizeSnapshot::capture(tree)
+        .save(&file, &config.root_path)
+        .ok();
+}
+
+/// Hides directories below the size threshold when `--du-threshold` is set.
+///
+/// Must run after disk usage has been computed. Hidden directories are
+/// counted on their parent so the listing can show how many are missing.
+fn apply_du_threshold(tree: &mut TreeArena, config: &Config) {
+    if let Some(threshold) = config.scan.du_threshold {
+        tree.hide_small_directories(threshold);
+    }
+}
+
+/// Collapses the upper levels of deep branches when `--leaf-depth` is set.
+///
+/// Must run after sorting so that hoisted entries keep their display order,
+/// and before `--max-entries` truncation so the limit applies to what is shown.
+fn apply_leaf_depth(tree: &mut TreeArena, config: &Config) {
+    if let Some(levels) = config.scan.leaf_depth {
+        tree.keep_leaf_levels(levels);
+    }
+}
+
+/// Runs the `TreeTransform` hooks passed to `scan_with`.
+///
+/// Must run after sorting, so transforms see entries in display order, and
+/// before `--leaf-depth` and `--max-entries`, so these apply to the
+/// transformed tree. The arena is converted to a `TreeNode` tree and back,
+/// which is skipped when there is no transform.
+fn apply_transforms(tree: &mut TreeArena, transforms: &[&dyn TreeTransform]) {
+    if transforms.is_empty() {
+        return;
+    }
+    let mut root = tree.to_tree();
+    for transform in transforms {
+        transform.apply(&mut root);
+    }
+    *tree = TreeArena::from_tree(&root);
+}
+
+/// Truncates directory listings when `--max-entries` is set.
+///
+/// Must run after sorting so that the first entries in display order are
+/// kept. Only entries that are displayed count towards the limit.
+fn apply_entry_limit(tree: &mut TreeArena, config: &Config) {
+    if let Some(limit) = config.scan.max_entries {
+        tree.truncate(limit, config.scan.show_files, config.scan.max_depth);
+    }
+}
+
+/// Scans a directory tree and returns the result with statistics.
+///
+/// Uses rayon for parallel scanning with configurable thread count.
+/// When `config.scan.paths_from` is set, the tree is built from that path
+/// list instead (see `build_tree_from_paths`).
+///
+/// # Arguments
+///
+/// * `config` - Scan configuration specifying root path, filters, and options.
+///
+/// # Returns
+///
+/// `ScanStats` containing the tree, timing, and counts on success.
+///
+/// # Errors
+///
+/// Returns `ScanError::PathNotFound` if the root path doesn't exist.
+/// Returns `ScanError::NotADirectory` if the root path is not a directory.
+/// Returns `MatchError` if pattern compilation fails.
+/// Returns `ScanError::PathListReadFailed` or `ScanError::PathOutsideRoot`
+/// if the configured path list cannot be read or resolved.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use treepp::config::Config;
+/// use treepp::scan::scan;
+///
+/// let config = Config::with_root(PathBuf::from(".")).validate().unwrap();
+/// let stats = scan(&config).expect("scan failed");
+/// println!("{} directories, {} files", stats.directory_count, stats.file_count);
+/// ```
+pub fn scan(config: &Config) -> TreeppResult<ScanStats> {
+    scan_with(config, &[])
+}
+
+/// Scans a directory tree like `scan` and runs transforms on the result.
+///
+/// Each transform receives the complete tree after filtering, disk usage
+/// calculation and sorting, in the order given, and may annotate or remove
+/// entries. `--leaf-depth` and `--max-entries` apply to the transformed
+/// tree, and the directory and file counts are taken from it. Disk usage is
+/// not recomputed, and children added by a transform are not sorted.
+///
+/// # Arguments
+///
+/// * `config` - Scan configuration specifying root path, filters, and options.
+/// * `transforms` - Hooks to run on the tree, in order.
+///
+/// # Returns
+///
+/// `ScanStats` containing the transformed tree, timing, and counts on success.
+///
+/// # Errors
+///
+/// Returns the same errors as `scan`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use treepp::config::Config;
+/// use treepp::scan::scan_with;
+/// use treepp::transform::CargoWorkspaces;
+///
+/// let config = Config::with_root(PathBuf::from(".")).validate().unwrap();
+/// let stats = scan_with(&config, &[&CargoWorkspaces]).expect("scan failed");
+/// println!("{} directories", stats.directory_count);
+/// ```
+pub fn scan_with(config: &Config, transforms: &[&dyn TreeTransform]) -> TreeppResult<ScanStats> {
+    let start = Instant::now();
+
+    if !config.root_path.exists() {
+        return Err(ScanError::PathNotFound {
+            path: config.root_path.clone(),
+        }
+        .into());
+    }
+
+    if !config.root_path.is_dir() {
+        return Err(ScanError::NotADirectory {
+            path: config.root_path.clone(),
+        }
+        .into());
+    }
+
+    if let Some(ref source) = config.scan.paths_from {
+        return scan_path_list(config, source, transforms);
+    }
+
+    let mut ctx = ScanContext::from_config(config)?;
+    ctx.cache = open_cache(config);
+    scan_tree(config, &ctx, transforms, start)
+}
+
+/// Scans a directory tree of another file system like `scan_with`.
+///
+/// Directories are listed through `fs` instead of the disk, for the
+/// `wasm32-wasip1` playground and fuzzing harnesses. The scan cache and
+/// `--paths-from` are not used; `.gitignore` files and git state are still
+/// read from the disk if their filters are enabled. On targets without
+/// threads, set `config.scan.backend` to `ScanBackend::Walk`.
+///
+/// # Arguments
+///
+/// * `config` - Scan configuration; its root path is looked up in `fs`.
+/// * `fs` - The file system to scan.
+/// * `transforms` - Hooks to run on the tree, in order.
+///
+/// # Returns
+///
+/// `ScanStats` containing the transformed tree, timing, and counts on success.
+///
+/// # Errors
+///
+/// Returns `ScanError::PathNotFound` if the root path is not in `fs`, and
+/// otherwise the same errors as `scan`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use std::sync::Arc;
+/// use treepp::config::{Config, ScanBackend};
+/// use treepp::scan::scan_on;
+/// use treepp::vfs::MemoryFileSystem;
+///
+/// let fs = MemoryFileSystem::default().with_file("/demo/src/main.rs", 120);
+/// let mut config = Config::with_root(PathBuf::from("/demo"));
+/// config.scan.show_files = true;
+/// config.scan.backend = ScanBackend::Walk;
+/// let stats = scan_on(&config, Arc::new(fs), &[]).expect("scan failed");
+/// assert_eq!((stats.directory_count, stats.file_count), (1, 1));
+/// ```
+pub fn scan_on(
+    config: &Config,
+    fs: Arc<dyn FileSystem>,
+    transforms: &[&dyn TreeTransform],
+) -> TreeppResult<ScanStats> {
+    let start = Instant::now();
+
+    let root = fs
+        .inspect(&config.root_path)
+        .map_err(|_| ScanError::PathNotFound {
             path: config.root_path.clone(),
-            source: std::io::Error::other("cannot read root directory"),
         })?;
+    if root.kind != EntryKind::Directory {
+        return Err(ScanError::NotADirectory {
+            path: config.root_path.clone(),
+        }
+        .into());
+    }
+
+    let mut ctx = ScanContext::from_config(config)?;
+    ctx.fs = fs;
+    scan_tree(config, &ctx, transforms, start)
+}
+
+/// Runs a batch scan of the root directory with a prepared context.
+///
+/// # Errors
+///
+/// Returns `ScanError::ReadDirFailed` if the root cannot be read, or
+/// `ScanError::WalkError` if the scan threads cannot be started.
+fn scan_tree(
+    config: &Config,
+    ctx: &ScanContext,
+    transforms: &[&dyn TreeTransform],
+    start: Instant,
+) -> TreeppResult<ScanStats> {
+    let mut tree = match ctx.backend {
+        ScanBackend::Walk => {
+            info!("backend: batch (walk), 1 thread");
+            let arena = Mutex::new(TreeArena::new(config.root_path.clone()));
+            scan_root(config, ctx, &arena)?;
+            arena.into_inner().unwrap()
+        }
+        ScanBackend::Channel => {
+            info!(
+                "backend: batch (channel), {} threads",
+                config.scan.thread_count
+            );
+            scan_channel(config, ctx, CHANNEL_CAPACITY)?
+        }
+        // `auto` is resolved by `ScanContext::from_config`.
+        ScanBackend::Parallel | ScanBackend::Auto => {
+            let pool = build_thread_pool(config)?;
+            info!(
+                "backend: batch (parallel), {} threads",
+                config.scan.thread_count
+            );
+            let arena = Mutex::new(TreeArena::new(config.root_path.clone()));
+            pool.install(|| scan_root(config, ctx, &arena))?;
+            arena.into_inner().unwrap()
+        }
+    };
+    save_cache(ctx);
+    info!("walk: {:.2?}", start.elapsed());
+    log_rejections(&ctx.take_rejected());
+
+    let phase = Instant::now();
+    apply_prune(&mut tree, config);
 
     if ctx.needs_size {
         tree.compute_disk_usage();
     }
+    apply_size_snapshot(&mut tree, config);
+    apply_du_threshold(&mut tree, config);
+    tree.clear_collapsed();
 
-    sort_tree(&mut tree, ctx.reverse);
+    tree.sort(ctx.sort_order());
+    apply_transforms(&mut tree, transforms);
+    apply_leaf_depth(&mut tree, config);
+    apply_entry_limit(&mut tree, config);
+    info!("post-processing: {:.2?}", phase.elapsed());
 
     let duration = start.elapsed();
     let directory_count = tree.count_directories();
@@ -1131,9 +2351,60 @@ pub fn scan(config: &Config) -> TreeppResult<ScanStats> {
         duration,
         directory_count,
         file_count,
+        unreadable_dirs: ctx.take_unreadable(),
+        vanished_entries: ctx.vanished_count(),
     })
 }
 
+/// Creates the thread pool of a batch scan.
+///
+/// # Errors
+///
+/// Returns `ScanError::WalkError` if the pool cannot be created.
+fn build_thread_pool(config: &Config) -> Result<rayon::ThreadPool, ScanError> {
+    ThreadPoolBuilder::new()
+        .num_threads(config.scan.thread_count.get())
+        .build()
+        .map_err(|e| ScanError::WalkError {
+            message: format!("thread pool creation failed: {}", e),
+            path: Some(config.root_path.clone()),
+        })
+}
+
+/// Scans the root directory of a batch scan into a sink.
+///
+/// # Errors
+///
+/// Returns `ScanError::ReadDirFailed` if the root cannot be read.
+fn scan_root<S: EntrySink>(config: &Config, ctx: &ScanContext, sink: &S) -> Result<(), ScanError> {
+    scan_dir(&config.root_path, None, 0, ctx, GitignoreChain::new(), sink)
+        .map(|_| ())
+        .ok_or_else(|| ScanError::ReadDirFailed {
+            path: config.root_path.clone(),
+            source: std::io::Error::other("cannot read root directory"),
+        })
+}
+
+/// Logs the number of entries left out by each filter (`--verbose`).
+fn log_rejections(rejected: &RejectionCounts) {
+    if rejected.total() == 0 {
+        info!("no entries rejected by filters");
+    }
+    for (kind, count) in rejected.iter() {
+        info!(
+            "rejected by {}: {}",
+            i18n::filter_label(Language::English, kind),
+            count
+        );
+    }
+    if rejected.subtrees() > 0 {
+        info!(
+            "skipped {} filtered subtree(s) without reading them",
+            rejected.subtrees()
+        );
+    }
+}
+
 /// Performs streaming scan with callback-based output.
 ///
 /// Traverses depth-first, calling the callback for each discovered entry.
@@ -1152,7 +2423,10 @@ pub fn scan(config: &Config) -> TreeppResult<ScanStats> {
 ///
 /// Returns `ScanError::PathNotFound` if the root path doesn't exist.
 /// Returns `ScanError::NotADirectory` if the root path is not a directory.
-/// Propagates any error returned by the callback.
+/// Returns `ScanError::ReadDirFailed` if the root directory cannot be read.
+/// Propagates any error returned by the callback, except
+/// `ScanError::OutputLimitReached` and `ScanError::OutputClosed`, which end
+/// the scan early with the counts of the entries emitted so far.
 ///
 /// # Examples
 ///
@@ -1170,7 +2444,7 @@ pub fn scan(config: &Config) -> TreeppResult<ScanStats> {
 /// }).expect("scan failed");
 /// println!("{} directories, {} files", stats.directory_count, stats.file_count);
 /// ```
-pub fn scan_streaming<F>(config: &Config, mut callback: F) -> TreeppResult<StreamStats>
+pub fn scan_streaming<F>(config: &Config, callback: F) -> TreeppResult<StreamStats>
 where
     F: FnMut(StreamEvent) -> Result<(), ScanError>,
 {
@@ -1180,116 +2454,595 @@ where
         return Err(ScanError::PathNotFound {
             path: config.root_path.clone(),
         }
-            .into());
+        .into());
     }
 
     if !config.root_path.is_dir() {
         return Err(ScanError::NotADirectory {
             path: config.root_path.clone(),
         }
-            .into());
+        .into());
     }
 
-    let ctx = ScanContext::from_config(config)?;
-    let initial_chain = GitignoreChain::new();
-
-    let (dir_count, file_count) =
-        streaming_scan_dir(&config.root_path, 0, &ctx, &initial_chain, &mut callback)?;
-
-    let duration = start.elapsed();
-
-    Ok(StreamStats {
-        duration,
-        directory_count: dir_count,
-        file_count,
-    })
+    let mut ctx = ScanContext::from_config(config)?;
+    ctx.cache = open_cache(config);
+    stream_tree(config, &ctx, callback, start)
 }
 
-/// Recursively performs streaming scan of a directory.
-fn streaming_scan_dir<F>(
-    path: &Path,
-    depth: usize,
+/// Runs a streaming scan of the root directory with a prepared context.
+///
+/// # Errors
+///
+/// Returns the same errors as `scan_streaming`, apart from the checks of
+/// the root path.
+fn stream_tree<F>(
+    config: &Config,
     ctx: &ScanContext,
-    parent_chain: &GitignoreChain,
-    callback: &mut F,
-) -> Result<(usize, usize), ScanError>
+    mut callback: F,
+    start: Instant,
+) -> TreeppResult<StreamStats>
 where
     F: FnMut(StreamEvent) -> Result<(), ScanError>,
 {
-    if let Some(max) = ctx.max_depth {
-        if depth >= max {
-            return Ok((0, 0));
-        }
-    }
+    let initial_chain = GitignoreChain::new();
+    info!("backend: streaming");
+
+    // Counts what has been passed on so far, for when the callback stops the scan.
+    let mut emitted = StreamCounts::default();
+    let mut counting = |event: StreamEvent| -> Result<(), ScanError> {
+        let tally = match event {
+            StreamEvent::Entry(ref entry) if entry.filtered.is_none() => StreamCounts {
+                directories: usize::from(!entry.is_file),
+                files: usize::from(entry.is_file),
+                unread_dirs: usize::from(entry.error == Some(EntryError::BudgetExhausted)),
+                ..StreamCounts::default()
+            },
+            StreamEvent::Omitted { .. } => StreamCounts {
+                truncated_dirs: 1,
+                ..StreamCounts::default()
+            },
+            _ => StreamCounts::default(),
+        };
+        callback(event)?;
+        emitted.directories += tally.directories;
+        emitted.files += tally.files;
+        emitted.truncated_dirs += tally.truncated_dirs;
+        emitted.unread_dirs += tally.unread_dirs;
+        Ok(())
+    };
 
-    let current_chain = if ctx.respect_gitignore {
-        if let Some(gi) = ctx.get_gitignore(path) {
-            parent_chain.with_child(gi)
-        } else {
-            parent_chain.clone()
+    let resume: Vec<OsString> = config
+        .scan
+        .resume_after
+        .iter()
+        .flat_map(|path| path.iter().map(OsStr::to_os_string))
+        .collect();
+    let result = streaming_scan_dir(
+        &config.root_path,
+        0,
+        ctx,
+        &initial_chain,
+        &resume,
+        &mut counting,
+    );
+    save_cache(ctx);
+    let (counts, output_truncated) = match result {
+        // The batch scan fails the same way.
+        Ok(counts) if counts.error.is_some() => {
+            return Err(ScanError::ReadDirFailed {
+                path: config.root_path.clone(),
+                source: std::io::Error::other("cannot read root directory"),
+            }
+            .into());
         }
-    } else {
-        parent_chain.clone()
+        Ok(counts) => (counts, false),
+        Err(ScanError::OutputLimitReached { .. }) => (emitted, true),
+        Err(ScanError::OutputClosed) => (emitted, false),
+        Err(e) => return Err(e.into()),
     };
 
-    let raw_entries: Vec<_> = match fs::read_dir(path) {
-        Ok(entries) => entries.flatten().collect(),
-        Err(_) => return Ok((0, 0)),
-    };
+    let duration = start.elapsed();
+    info!("walk: {:.2?}", duration);
+    let rejected = ctx.take_rejected();
+    log_rejections(&rejected);
 
-    let entries_with_meta: Vec<(PathBuf, Metadata)> = raw_entries
-        .into_iter()
-        .filter_map(|entry| {
-            let entry_path = entry.path();
-            let meta = entry.metadata().ok()?;
-            Some((entry_path, meta))
-        })
-        .collect();
+    Ok(StreamStats {
+        duration,
+        directory_count: counts.directories,
+        file_count: counts.files,
+        truncated_dirs: counts.truncated_dirs,
+        unread_dirs: counts.unread_dirs,
+        output_truncated,
+        unreadable_dirs: ctx.take_unreadable(),
+        vanished_entries: ctx.vanished_count(),
+        rejected,
+    })
+}
 
-    let mut filtered: Vec<(PathBuf, Metadata)> = entries_with_meta
-        .into_iter()
-        .filter(|(entry_path, meta)| {
-            let entry_name = entry_path
-                .file_name()
-                .map(|s| s.to_string_lossy().into_owned())
-                .unwrap_or_default();
+/// Number of entries a `ScanIter` reads ahead of its consumer.
+const SCAN_ITER_CAPACITY: usize = 1024;
+
+/// Scanner that hands out its entries through an iterator.
+///
+/// For embedders that want to consume entries lazily instead of passing a
+/// callback to `scan_streaming`. Each call to `iter` starts a new scan.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use treepp::config::Config;
+/// use treepp::scan::Scanner;
+///
+/// let mut config = Config::with_root(PathBuf::from("."));
+/// config.scan.show_files = true;
+/// let scanner = Scanner::new(config.validate().unwrap());
+/// for entry in scanner.iter().filter(|e| e.is_file).take(10) {
+///     println!("{}", entry.path.display());
+/// }
+/// let tree = scanner.iter().into_tree().expect("scan failed");
+/// println!("{} files", tree.count_files());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    /// Validated configuration of the scans.
+    config: Config,
+}
+
+impl Scanner {
+    /// Creates a scanner for a validated configuration.
+    #[must_use]
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    /// Starts a scan and returns an iterator over its entries.
+    ///
+    /// The scan runs on a worker thread and stays at most
+    /// `SCAN_ITER_CAPACITY` entries ahead of the consumer. Entries come in
+    /// the order of `scan_streaming`, with the same filters applied; events
+    /// other than entries are not passed on. Dropping the iterator stops
+    /// the scan.
+    #[must_use]
+    pub fn iter(&self) -> ScanIter {
+        let (sender, entries) = mpsc::sync_channel(SCAN_ITER_CAPACITY);
+        let config = self.config.clone();
+        let worker = thread::Builder::new()
+            .name("treepp-iter".to_string())
+            .spawn(move || {
+                scan_streaming(&config, |event| match event {
+                    StreamEvent::Entry(entry) => {
+                        sender.send(*entry).map_err(|_| ScanError::OutputClosed)
+                    }
+                    _ => Ok(()),
+                })
+            })
+            .map_err(|e| ScanError::WalkError {
+                message: format!("cannot start scan thread: {e}"),
+                path: Some(self.config.root_path.clone()),
+            });
+        ScanIter {
+            root: self.config.root_path.clone(),
+            entries,
+            worker,
+        }
+    }
+}
+
+/// Iterator over the entries of a scan started by `Scanner::iter`.
+///
+/// Ends when the scan is complete or has failed; `finish` tells which.
+#[derive(Debug)]
+pub struct ScanIter {
+    /// Root directory of the scan.
+    root: PathBuf,
+    /// Entries sent by the worker thread.
+    entries: mpsc::Receiver<StreamEntry>,
+    /// The worker thread, or why it could not be started.
+    worker: Result<thread::JoinHandle<TreeppResult<StreamStats>>, ScanError>,
+}
+
+impl Iterator for ScanIter {
+    type Item = StreamEntry;
+
+    fn next(&mut self) -> Option<StreamEntry> {
+        self.entries.recv().ok()
+    }
+}
+
+impl ScanIter {
+    /// Stops the scan if it is still running and returns its statistics.
+    ///
+    /// # Returns
+    ///
+    /// The `StreamStats` of the entries scanned; if the iterator was not
+    /// consumed to the end, the counts cover what was scanned until then.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error that ended the scan, such as
+    /// `ScanError::PathNotFound` for a missing root.
+    pub fn finish(self) -> TreeppResult<StreamStats> {
+        drop(self.entries);
+        let worker = self.worker?;
+        worker.join().unwrap_or_else(|_| {
+            Err(ScanError::WalkError {
+                message: "scan thread panicked".to_string(),
+                path: Some(self.root),
+            }
+            .into())
+        })
+    }
+
+    /// Collects the remaining entries into a tree.
+    ///
+    /// The root node carries no metadata. Entries left out by
+    /// `--max-entries` are not part of the stream, so `omitted` stays 0.
+    ///
+    /// # Returns
+    ///
+    /// The root `TreeNode` with the collected entries as descendants.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error that ended the scan (see `finish`).
+    pub fn into_tree(mut self) -> TreeppResult<TreeNode> {
+        let root = TreeNode::new(
+            self.root.clone(),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        // Open directories from the root down; entries at depth `d` belong
+        // to `open[d]`.
+        let mut open = vec![root];
+        for entry in self.by_ref() {
+            close_levels(&mut open, entry.depth + 1);
+            let mut node = TreeNode::new(entry.path, entry.kind, entry.metadata);
+            node.filtered = entry.filtered;
+            node.collapsed = entry.collapsed;
+            node.error = entry.error;
+            if entry.is_file {
+                open.last_mut()
+                    .expect("root stays open")
+                    .children
+                    .push(node);
+            } else {
+                open.push(node);
+            }
+        }
+        self.finish()?;
+        close_levels(&mut open, 1);
+        Ok(open.pop().expect("root stays open"))
+    }
+}
+
+/// Closes open directories of `ScanIter::into_tree` until `depth` remain,
+/// adding each to its parent.
+fn close_levels(open: &mut Vec<TreeNode>, depth: usize) {
+    while open.len() > depth {
+        let node = open.pop().expect("checked length");
+        open.last_mut()
+            .expect("root stays open")
+            .children
+            .push(node);
+    }
+}
+
+/// Entry counts accumulated by a streaming scan.
+#[derive(Debug, Clone, Copy, Default)]
+struct StreamCounts {
+    /// Number of emitted directories.
+    directories: usize,
+    /// Number of emitted files.
+    files: usize,
+    /// Number of files kept by the filters, including ones hidden without `/F`.
+    retained_files: usize,
+    /// Number of directories truncated by `--max-entries`.
+    truncated_dirs: usize,
+    /// Number of directories left unread because the scan budget ran out.
+    unread_dirs: usize,
+    /// Why the directory itself could not be read.
+    error: Option<EntryError>,
+}
+
+/// Events and counts of a subdirectory scanned ahead of emission (`/P`).
+type BufferedDir = (Vec<StreamEvent>, StreamCounts);
+
+/// A subdirectory waiting to be emitted by the streaming scanner.
+struct PendingDir {
+    /// Path of the directory.
+    path: PathBuf,
+    /// Directory size and dates (empty if metadata was not read).
+    metadata: EntryMetadata,
+    /// `--show-filtered` annotation; annotated directories are not descended into.
+    filtered: Option<FilterReason>,
+    /// `--filelimit` entry count; collapsed directories are not descended into.
+    collapsed: Option<usize>,
+    /// Events collected ahead of emission; `None` means scan on emission.
+    buffered: Option<BufferedDir>,
+}
+
+/// Filesystem entries paired with their metadata and `--show-filtered` annotation.
+type EntryList = Vec<(PathBuf, EntryMetadata, Option<FilterReason>)>;
 
-            let is_dir = meta.is_dir();
+/// Reads a directory and applies all entry filters.
+///
+/// Entries left out by a filter are counted for `--count` when
+/// `count_rejections` is set; reads that only look ahead (`/FL`, `/P`)
+/// leave it unset, so that no entry is counted twice.
+///
+/// # Returns
+///
+/// The listed `(files, directories)`, each sorted. Entries kept only by
+/// `--show-filtered` carry their `FilterReason`.
+///
+/// # Errors
+///
+/// Returns the `EntryError` if the directory cannot be read.
+fn read_filtered_entries(
+    path: &Path,
+    ctx: &ScanContext,
+    chain: &GitignoreChain,
+    count_rejections: bool,
+) -> Result<(EntryList, EntryList), EntryError> {
+    let mut entries = list_dir(path, ctx)?;
+
+    sort_entries(&mut entries, ctx.sort_order());
 
-            if ctx.respect_gitignore && current_chain.is_ignored(entry_path, is_dir) {
-                return false;
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    let mut rejected = RejectionCounts::default();
+    for entry in entries {
+        let is_dir = entry.kind == EntryKind::Directory;
+        let reason = ctx.engine.filter_reason(
+            &entry.path,
+            is_dir,
+            entry.hidden,
+            entry.metadata.cloud_only,
+            entry.metadata.is_empty_file(entry.kind),
+            chain,
+        );
+        if let Some(ref reason) = reason {
+            rejected.record(reason);
+            if is_dir {
+                rejected.record_subtree();
             }
+        }
+        let Some(filtered) = ctx.annotation(reason, is_dir) else {
+            continue;
+        };
+        if is_dir {
+            dirs.push((entry.path, entry.metadata, filtered));
+        } else {
+            files.push((entry.path, entry.metadata, filtered));
+        }
+    }
+    if count_rejections {
+        ctx.record_rejections(&rejected);
+    }
+    Ok((files, dirs))
+}
+
+/// Counts the entries of `dir` that would be displayed, for `--filelimit`.
+fn listed_entry_count(dir: &Path, ctx: &ScanContext, parent_chain: &GitignoreChain) -> usize {
+    let chain = ctx.engine.chain_for(dir, parent_chain);
+    let (files, dirs) = read_filtered_entries(dir, ctx, &chain, false).unwrap_or_default();
+    let listed_files = if ctx.show_files {
+        files.iter().filter(|(_, _, f)| f.is_none()).count()
+    } else {
+        0
+    };
+    listed_files + dirs.iter().filter(|(_, _, f)| f.is_none()).count()
+}
 
-            !ctx.should_filter(&entry_name, is_dir, Some(meta))
+/// Checks whether any file below `path` survives the filters.
+///
+/// Used by `/P` for directories at the depth limit, whose content is not
+/// emitted but still decides whether they are shown.
+fn has_retained_file(path: &Path, ctx: &ScanContext, parent_chain: &GitignoreChain) -> bool {
+    let chain = ctx.engine.chain_for(path, parent_chain);
+    let (files, dirs) = read_filtered_entries(path, ctx, &chain, false).unwrap_or_default();
+    files.iter().any(|(_, _, filtered)| filtered.is_none())
+        || dirs.iter().any(|(dir_path, _, filtered)| {
+            filtered.is_none() && has_retained_file(dir_path, ctx, &chain)
         })
-        .collect();
+}
+
+/// Recursively performs streaming scan of a directory.
+///
+/// An unreadable directory emits nothing; its `EntryError` is returned in
+/// the counts instead. A non-empty `resume` continues a `--checkpoint` run:
+/// see `stream_listing`.
+fn streaming_scan_dir<F>(
+    path: &Path,
+    depth: usize,
+    ctx: &ScanContext,
+    parent_chain: &GitignoreChain,
+    resume: &[OsString],
+    callback: &mut F,
+) -> Result<StreamCounts, ScanError>
+where
+    F: FnMut(StreamEvent) -> Result<(), ScanError>,
+{
+    if let Some(max) = ctx.max_depth
+        && depth >= max
+    {
+        let retained_files =
+            usize::from(ctx.prune_empty && has_retained_file(path, ctx, parent_chain));
+        return Ok(StreamCounts {
+            retained_files,
+            ..StreamCounts::default()
+        });
+    }
+
+    let current_chain = ctx.engine.chain_for(path, parent_chain);
+    let listing = read_filtered_entries(path, ctx, &current_chain, true);
+    stream_listing(listing, depth, ctx, &current_chain, resume, callback)
+}
 
-    sort_entries(&mut filtered, ctx.reverse);
+/// Emits the events for a directory listing read by `read_filtered_entries`.
+///
+/// With `/P`, each subdirectory's events are buffered until its content is
+/// known, so empty directories are never emitted and the `is_last` flags of
+/// the remaining siblings stay correct. Other subdirectories are read just
+/// before their entry is emitted, so that a read failure can be annotated.
+///
+/// `resume` names the last directory written by an interrupted run, one
+/// component per level. Everything up to it was written already: the files
+/// of the level, the directories sorted before it and, if it is the last
+/// component, the directory itself. A directory named by an earlier
+/// component is continued without emitting its entry again.
+fn stream_listing<F>(
+    listing: Result<(EntryList, EntryList), EntryError>,
+    depth: usize,
+    ctx: &ScanContext,
+    current_chain: &GitignoreChain,
+    resume: &[OsString],
+    callback: &mut F,
+) -> Result<StreamCounts, ScanError>
+where
+    F: FnMut(StreamEvent) -> Result<(), ScanError>,
+{
+    let (files, dirs) = match listing {
+        Ok(listing) => listing,
+        Err(error) => {
+            return Ok(StreamCounts {
+                error: Some(error),
+                ..StreamCounts::default()
+            });
+        }
+    };
 
-    let mut files: Vec<(PathBuf, Metadata)> = Vec::new();
-    let mut dirs: Vec<(PathBuf, Metadata)> = Vec::new();
+    let mut counts = StreamCounts {
+        retained_files: files.iter().filter(|(_, _, f)| f.is_none()).count(),
+        ..StreamCounts::default()
+    };
 
-    for (entry_path, meta) in filtered {
-        if meta.is_dir() {
-            dirs.push((entry_path, meta));
+    let mut pending: Vec<PendingDir> = Vec::with_capacity(dirs.len());
+    for (entry_path, metadata, filtered) in dirs {
+        let collapsed =
+            if ctx.file_limit.is_some() && filtered.is_none() && ctx.descends_into(depth + 1) {
+                ctx.collapsed_count(listed_entry_count(&entry_path, ctx, current_chain))
+            } else {
+                None
+            };
+
+        if filtered.is_some() || collapsed.is_some() {
+            // Annotated and collapsed directories are listed but never descended into.
+            // A collapsed directory counts as content for `/P`.
+            counts.retained_files += usize::from(collapsed.is_some());
+            pending.push(PendingDir {
+                path: entry_path,
+                metadata,
+                filtered,
+                collapsed,
+                buffered: Some((Vec::new(), StreamCounts::default())),
+            });
+        } else if ctx.prune_empty {
+            let mut buffer = Vec::new();
+            let sub = {
+                let mut record = |event: StreamEvent| {
+                    buffer.push(event);
+                    Ok(())
+                };
+                let mut sink: &mut dyn FnMut(StreamEvent) -> Result<(), ScanError> = &mut record;
+                streaming_scan_dir(&entry_path, depth + 1, ctx, current_chain, &[], &mut sink)?
+            };
+            // An unreadable directory counts as content for `/P`; one deleted
+            // since the listing is left out.
+            if sub.error == Some(EntryError::Vanished) {
+                continue;
+            }
+            if sub.retained_files > 0 || sub.error.is_some() {
+                counts.retained_files += sub.retained_files + usize::from(sub.error.is_some());
+                pending.push(PendingDir {
+                    path: entry_path,
+                    metadata,
+                    filtered: None,
+                    collapsed: None,
+                    buffered: Some((buffer, sub)),
+                });
+            } else {
+                ctx.record_rejection(&FilterReason::Pruned);
+            }
         } else {
-            files.push((entry_path, meta));
+            pending.push(PendingDir {
+                path: entry_path,
+                metadata,
+                filtered: None,
+                collapsed: None,
+                buffered: None,
+            });
         }
     }
 
-    let mut dir_count = 0;
-    let mut file_count = 0;
+    // With `--max-entries`, files are listed first and the remainder of the
+    // level is summarised by a single `Omitted` event.
+    let listed_files = if ctx.show_files { files.len() } else { 0 };
+    let (shown_files, shown_dirs) = match ctx.max_entries {
+        Some(limit) => {
+            let shown_files = listed_files.min(limit);
+            (shown_files, pending.len().min(limit - shown_files))
+        }
+        None => (listed_files, pending.len()),
+    };
+    let omitted = listed_files + pending.len() - shown_files - shown_dirs;
+
+    // A directory missing since the interrupted run resumes with its
+    // following siblings, all of which are emitted again.
+    let (first_file, first_dir, mut resume_rest) = match resume.split_first() {
+        Some((name, rest)) => {
+            let index = pending
+                .iter()
+                .take(shown_dirs)
+                .position(|dir| dir.path.file_name() == Some(name.as_os_str()));
+            match index {
+                Some(index) if rest.is_empty() => (shown_files, index + 1, None),
+                Some(index) => (shown_files, index, Some(rest)),
+                None => (shown_files, 0, None),
+            }
+        }
+        None => (0, 0, None),
+    };
+
+    // Each directory is read just before it is emitted. The next one is read
+    // ahead, so that one deleted since the listing is left out without the
+    // entry before it being drawn as a middle sibling.
+    let mut dirs = pending
+        .into_iter()
+        .take(shown_dirs)
+        .enumerate()
+        .skip(first_dir)
+        .filter_map(|(i, dir)| {
+            let listing = (dir.buffered.is_none() && ctx.descends_into(depth + 1)).then(|| {
+                let chain = ctx.engine.chain_for(&dir.path, current_chain);
+                let listing = read_filtered_entries(&dir.path, ctx, &chain, true);
+                (listing, chain)
+            });
+            match listing {
+                Some((Err(EntryError::Vanished), _)) => None,
+                listing => Some((i, dir, listing)),
+            }
+        })
+        .peekable();
+    let more_dirs = dirs.peek().is_some();
 
-    let file_total = files.len();
-    for (i, (entry_path, meta)) in files.into_iter().enumerate() {
-        let is_last_file = i == file_total - 1;
-        let is_last_overall = is_last_file && dirs.is_empty();
-        let entry_meta = EntryMetadata::from_fs_metadata(&meta);
+    for (i, (entry_path, entry_meta, filtered)) in files
+        .into_iter()
+        .take(shown_files)
+        .enumerate()
+        .skip(first_file)
+    {
+        let is_last_file = i == shown_files - 1;
+        let is_last_overall = is_last_file && !more_dirs && omitted == 0;
         let name = entry_path
             .file_name()
             .map(|s| s.to_string_lossy().into_owned())
             .unwrap_or_default();
 
+        if filtered.is_none() {
+            counts.files += 1;
+        }
         let entry = StreamEntry {
             path: entry_path,
             name,
@@ -1298,122 +3051,611 @@ where
             depth,
             is_last: is_last_overall,
             is_file: true,
-            has_more_dirs: !dirs.is_empty(),
+            has_more_dirs: more_dirs || omitted > 0,
+            filtered,
+            collapsed: None,
+            error: None,
         };
-        callback(StreamEvent::Entry(entry))?;
-        file_count += 1;
+        callback(StreamEvent::Entry(Box::new(entry)))?;
     }
 
-    let dir_total = dirs.len();
-    for (i, (entry_path, meta)) in dirs.into_iter().enumerate() {
-        let is_last = i == dir_total - 1;
-        let entry_meta = EntryMetadata::from_fs_metadata(&meta);
-        let name = entry_path
-            .file_name()
-            .map(|s| s.to_string_lossy().into_owned())
-            .unwrap_or_default();
-
-        let entry = StreamEntry {
-            path: entry_path.clone(),
-            name,
-            kind: EntryKind::Directory,
-            metadata: entry_meta,
-            depth,
-            is_last,
-            is_file: false,
-            has_more_dirs: !is_last,
+    while let Some((i, dir, listing)) = dirs.next() {
+        let is_last = dirs.peek().is_none() && omitted == 0;
+        // Only the first directory can be the one being continued.
+        let continued = if i == first_dir {
+            resume_rest.take()
+        } else {
+            None
         };
-        callback(StreamEvent::Entry(entry))?;
-        dir_count += 1;
-
-        callback(StreamEvent::EnterDir { is_last })?;
+        let error = match (&dir.buffered, &listing) {
+            (Some((_, sub)), _) => sub.error,
+            (None, Some((Err(error), _))) => Some(*error),
+            _ => None,
+        };
+        if continued.is_none() {
+            if dir.filtered.is_none() {
+                counts.directories += 1;
+                counts.unread_dirs += usize::from(error == Some(EntryError::BudgetExhausted));
+            }
+            let name = dir
+                .path
+                .file_name()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let entry = StreamEntry {
+                path: dir.path.clone(),
+                name,
+                kind: EntryKind::Directory,
+                metadata: dir.metadata,
+                depth,
+                is_last,
+                is_file: false,
+                has_more_dirs: !is_last,
+                filtered: dir.filtered,
+                collapsed: dir.collapsed,
+                error,
+            };
+            callback(StreamEvent::Entry(Box::new(entry)))?;
+
+            callback(StreamEvent::EnterDir { is_last })?;
+        }
 
-        let (sub_dirs, sub_files) =
-            streaming_scan_dir(&entry_path, depth + 1, ctx, &current_chain, callback)?;
-        dir_count += sub_dirs;
-        file_count += sub_files;
+        let sub = match dir.buffered {
+            Some((events, sub)) => {
+                for event in events {
+                    callback(event)?;
+                }
+                sub
+            }
+            None => {
+                let sub = match listing {
+                    Some((listing, chain)) => stream_listing(
+                        listing,
+                        depth + 1,
+                        ctx,
+                        &chain,
+                        continued.unwrap_or_default(),
+                        callback,
+                    )?,
+                    None => StreamCounts::default(),
+                };
+                counts.retained_files += sub.retained_files;
+                sub
+            }
+        };
+        counts.directories += sub.directories;
+        counts.files += sub.files;
+        counts.truncated_dirs += sub.truncated_dirs;
+        counts.unread_dirs += sub.unread_dirs;
 
         callback(StreamEvent::LeaveDir)?;
     }
 
-    Ok((dir_count, file_count))
+    if omitted > 0 {
+        counts.truncated_dirs += 1;
+        callback(StreamEvent::Omitted {
+            depth,
+            count: omitted,
+        })?;
+    }
+
+    Ok(counts)
 }
 
 // ============================================================================
-// Unit Tests
+// Path List Scanning
 // ============================================================================
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::{self, File};
-    use std::io::Write;
-    use tempfile::TempDir;
+/// Intermediate node used while assembling a tree from a path list.
+#[derive(Default)]
+struct PathListNode {
+    /// Whether the list marked this entry as a directory (trailing separator).
+    marked_dir: bool,
+    /// Child entries keyed by name.
+    children: BTreeMap<String, PathListNode>,
+}
 
-    fn setup_test_dir() -> TempDir {
-        let dir = TempDir::new().expect("创建临时目录失败");
-        let root = dir.path();
+impl PathListNode {
+    /// Inserts a relative component sequence below this node.
+    fn insert(&mut self, components: &[String], marked_dir: bool) {
+        let Some((first, rest)) = components.split_first() else {
+            return;
+        };
+        let child = self.children.entry(first.clone()).or_default();
+        if rest.is_empty() {
+            child.marked_dir |= marked_dir;
+        } else {
+            child.insert(rest, marked_dir);
+        }
+    }
 
-        fs::create_dir(root.join("src")).unwrap();
-        fs::create_dir(root.join("tests")).unwrap();
-        fs::create_dir(root.join("empty")).unwrap();
+    /// Converts this node into a `TreeNode` rooted at `path`.
+    ///
+    /// Entries with children or a trailing separator are directories; leaf
+    /// entries consult the filesystem and fall back to files. Listed paths
+    /// that no longer exist keep default metadata.
+    fn into_tree_node(self, path: PathBuf) -> TreeNode {
+        let fs_meta = fs::metadata(winpath::to_extended(&path)).ok();
+        let is_dir = self.marked_dir
+            || !self.children.is_empty()
+            || fs_meta.as_ref().is_some_and(Metadata::is_dir);
+        let kind = if is_dir {
+            EntryKind::Directory
+        } else {
+            EntryKind::File
+        };
+        let metadata = fs_meta
+            .as_ref()
+            .map(EntryMetadata::from_fs_metadata)
+            .unwrap_or_default();
 
-        File::create(root.join("Cargo.toml"))
-            .unwrap()
-            .write_all(b"[package]")
-            .unwrap();
-        File::create(root.join("README.md"))
-            .unwrap()
-            .write_all(b"# Test")
-            .unwrap();
-        File::create(root.join("src/main.rs"))
-            .unwrap()
-            .write_all(b"fn main() {}")
-            .unwrap();
-        File::create(root.join("src/lib.rs"))
-            .unwrap()
-            .write_all(b"pub fn lib() {}")
-            .unwrap();
-        File::create(root.join("tests/test.rs"))
-            .unwrap()
-            .write_all(b"#[test]")
-            .unwrap();
+        let children = self
+            .children
+            .into_iter()
+            .map(|(name, child)| {
+                let child_path = path.join(&name);
+                child.into_tree_node(child_path)
+            })
+            .collect();
 
-        dir
+        TreeNode::with_children(path, kind, metadata, children)
     }
+}
 
-    fn setup_gitignore_dir() -> TempDir {
-        let dir = setup_test_dir();
-        let root = dir.path();
-
-        File::create(root.join(".gitignore"))
-            .unwrap()
-            .write_all(b"target/\n*.log\n")
-            .unwrap();
-
-        fs::create_dir(root.join("target")).unwrap();
-        File::create(root.join("target/debug"))
-            .unwrap()
-            .write_all(b"")
-            .unwrap();
-        File::create(root.join("app.log"))
-            .unwrap()
-            .write_all(b"log")
-            .unwrap();
+/// Reads a newline-separated path list from a file or standard input.
+///
+/// The input may be UTF-8 (with or without BOM) or UTF-16 with BOM, so that
+/// lists produced by `git ls-files` or redirected from PowerShell are both
+/// accepted. Empty lines are skipped and trailing `\r` is removed.
+///
+/// # Arguments
+///
+/// * `source` - Where to read the list from.
+///
+/// # Returns
+///
+/// The listed path strings, in input order.
+///
+/// # Errors
+///
+/// Returns `ScanError::PathListReadFailed` if the source cannot be read.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use treepp::config::PathListSource;
+/// use treepp::scan::read_path_list;
+///
+/// let paths = read_path_list(&PathListSource::File(PathBuf::from("files.txt"))).unwrap();
+/// println!("{} paths", paths.len());
+/// ```
+pub fn read_path_list(source: &PathListSource) -> Result<Vec<String>, ScanError> {
+    let mut bytes = Vec::new();
+    let (origin, result) = match source {
+        PathListSource::Stdin => (
+            "stdin".to_string(),
+            io::stdin().lock().read_to_end(&mut bytes),
+        ),
+        PathListSource::File(path) => (
+            path.to_string_lossy().into_owned(),
+            fs::File::open(path).and_then(|mut file| file.read_to_end(&mut bytes)),
+        ),
+    };
+    result.map_err(|source| ScanError::PathListReadFailed { origin, source })?;
 
-        dir
-    }
+    Ok(parse_path_list(&bytes))
+}
 
-    fn setup_nested_gitignore_dir() -> TempDir {
-        let dir = TempDir::new().expect("创建临时目录失败");
-        let root = dir.path();
+/// Decodes raw path list bytes and splits them into non-empty lines.
+fn parse_path_list(bytes: &[u8]) -> Vec<String> {
+    let text = match encoding_rs::Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) => encoding.decode_without_bom_handling(&bytes[bom_len..]).0,
+        None => String::from_utf8_lossy(bytes),
+    };
 
-        fs::create_dir(root.join("level1")).unwrap();
-        fs::create_dir(root.join("level1/level2")).unwrap();
-        fs::create_dir(root.join("level1/level2/level3")).unwrap();
+    text.lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect()
+}
 
-        File::create(root.join(".gitignore"))
-            .unwrap()
+/// Splits a listed path into components relative to the root.
+///
+/// Accepts both `/` and `\` separators, resolves `.` and `..` lexically, and
+/// strips the root prefix (compared case-insensitively) from absolute paths.
+///
+/// # Returns
+///
+/// The relative components and whether the entry was marked as a directory
+/// by a trailing separator.
+///
+/// # Errors
+///
+/// Returns `ScanError::PathOutsideRoot` if the path does not lie below `root`.
+fn relative_components(line: &str, root: &Path) -> Result<(Vec<String>, bool), ScanError> {
+    let display = winpath::to_display(Path::new(line));
+    let text = display.to_string_lossy();
+    let marked_dir = text.ends_with(['/', '\\']);
+    let outside_root = || ScanError::PathOutsideRoot {
+        path: PathBuf::from(line),
+        root: root.to_path_buf(),
+    };
+
+    let split = |s: &str| -> Vec<String> {
+        s.split(['/', '\\'])
+            .filter(|part| !part.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    let mut parts = split(&text);
+    let is_absolute = text.starts_with(['/', '\\']) || winpath::path_root(&display).is_some();
+    if is_absolute {
+        let root_parts = split(&winpath::to_display(root).to_string_lossy());
+        let has_root_prefix = parts.len() >= root_parts.len()
+            && parts
+                .iter()
+                .zip(&root_parts)
+                .all(|(a, b)| a.to_lowercase() == b.to_lowercase());
+        if !has_root_prefix {
+            return Err(outside_root());
+        }
+        parts.drain(..root_parts.len());
+    }
+
+    let mut components: Vec<String> = Vec::with_capacity(parts.len());
+    for part in parts {
+        match part.as_str() {
+            "." => {}
+            ".." => {
+                components.pop().ok_or_else(outside_root)?;
+            }
+            _ => components.push(part),
+        }
+    }
+
+    Ok((components, marked_dir))
+}
+
+/// Builds a tree from an explicit list of paths instead of traversing the filesystem.
+///
+/// Relative paths are resolved against `config.root_path`; absolute paths
+/// must lie below it. Intermediate directories are created implicitly.
+/// Include/exclude patterns, depth limits and `/F` are honored, while
+/// `.gitignore` and hidden-attribute filtering are not applied since the
+/// list is assumed to be pre-filtered.
+///
+/// # Arguments
+///
+/// * `paths` - Listed path strings.
+/// * `config` - Configuration providing the root and filter options.
+///
+/// # Returns
+///
+/// The unsorted root `TreeNode`.
+///
+/// # Errors
+///
+/// Returns `ScanError::PathOutsideRoot` for paths outside the root.
+/// Returns `MatchError` if pattern compilation fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use treepp::config::Config;
+/// use treepp::scan::build_tree_from_paths;
+///
+/// let mut config = Config::with_root(PathBuf::from("."));
+/// config.scan.show_files = true;
+/// let paths = vec!["src/main.rs".to_string(), "Cargo.toml".to_string()];
+/// let tree = build_tree_from_paths(&paths, &config).unwrap();
+/// assert_eq!(tree.count_files(), 2);
+/// ```
+pub fn build_tree_from_paths(paths: &[String], config: &Config) -> TreeppResult<TreeNode> {
+    let engine = MatchEngine::from_config(config)?;
+    let depth_limit = if config.render.show_disk_usage || config.matching.prune_empty {
+        None
+    } else {
+        config.scan.max_depth
+    };
+
+    let mut root = PathListNode {
+        marked_dir: true,
+        ..PathListNode::default()
+    };
+
+    for line in paths {
+        let (components, marked_dir) = relative_components(line, &config.root_path)?;
+        if components.is_empty() {
+            continue;
+        }
+        let relative = components.join("/");
+        let leaf_is_file = !marked_dir
+            && !fs::metadata(winpath::to_extended(&config.root_path.join(&relative)))
+                .is_ok_and(|m| m.is_dir());
+        let excluded = (1..=components.len()).any(|end| {
+            let is_dir = end < components.len() || !leaf_is_file;
+            engine.is_excluded(&components[..end].join("/"), is_dir)
+        });
+        if excluded {
+            continue;
+        }
+
+        let mut visible = components.as_slice();
+        if leaf_is_file {
+            // Attributes are not read for listed paths.
+            if engine
+                .entry_filter_reason(&relative, false, false, false, false)
+                .is_some()
+            {
+                visible = &components[..components.len() - 1];
+            }
+        }
+        if let Some(limit) = depth_limit {
+            visible = &visible[..visible.len().min(limit)];
+        }
+
+        let visible_marked_dir = marked_dir || visible.len() < components.len();
+        root.insert(visible, visible_marked_dir);
+    }
+
+    Ok(root.into_tree_node(config.root_path.clone()))
+}
+
+/// Scans a configured path list and returns the result with statistics.
+fn scan_path_list(
+    config: &Config,
+    source: &PathListSource,
+    transforms: &[&dyn TreeTransform],
+) -> TreeppResult<ScanStats> {
+    let start = Instant::now();
+    info!("backend: path list, no directories read");
+
+    let paths = read_path_list(source)?;
+    let mut tree = TreeArena::from_tree(&build_tree_from_paths(&paths, config)?);
+    info!("read {} paths: {:.2?}", paths.len(), start.elapsed());
+
+    let phase = Instant::now();
+    apply_prune(&mut tree, config);
+
+    if config.needs_size_info() {
+        tree.compute_disk_usage();
+    }
+
+    tree.sort(SortOrder::from_config(config));
+    apply_transforms(&mut tree, transforms);
+    apply_leaf_depth(&mut tree, config);
+    apply_entry_limit(&mut tree, config);
+    info!("post-processing: {:.2?}", phase.elapsed());
+
+    let duration = start.elapsed();
+    let directory_count = tree.count_directories();
+    let file_count = tree.count_files();
+
+    Ok(ScanStats {
+        tree,
+        duration,
+        directory_count,
+        file_count,
+        unreadable_dirs: Vec::new(),
+        vanished_entries: 0,
+    })
+}
+
+// ============================================================================
+// Filter Explanation
+// ============================================================================
+
+/// The outcome of running the filter chain for a single path (`--explain`).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::{Path, PathBuf};
+/// use treepp::config::Config;
+/// use treepp::scan::explain_path;
+///
+/// let config = Config::with_root(PathBuf::from(".")).validate().unwrap();
+/// let explanation = explain_path(&config, Path::new("target")).unwrap();
+/// println!("{:?}", explanation.reason);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterExplanation {
+    /// The explained path.
+    pub path: PathBuf,
+    /// The entry whose filtering decided the outcome: the path itself or one
+    /// of its ancestors.
+    pub decided_at: PathBuf,
+    /// Why the path is absent from the tree, or `None` if it is shown.
+    pub reason: Option<FilterReason>,
+}
+
+impl FilterExplanation {
+    /// Returns whether the path appears in the tree.
+    #[must_use]
+    pub fn is_shown(&self) -> bool {
+        self.reason.is_none()
+    }
+}
+
+/// Runs the full filter chain for a single path.
+///
+/// Every ancestor between the root and the path is checked in order, since
+/// a filtered directory hides everything below it. The checks match the
+/// scanner: `.gitignore` (with the rule's source file and line), git
+/// tracking, project output directories (`--smart`), hidden attribute,
+/// exclude and include patterns, `/F`, the depth limit and finally `/P`.
+///
+/// # Arguments
+///
+/// * `config` - The validated configuration.
+/// * `target` - Path to explain; relative paths are resolved against the root.
+///
+/// # Returns
+///
+/// A `FilterExplanation` describing the outcome.
+///
+/// # Errors
+///
+/// Returns `ScanError::PathOutsideRoot` if the path does not lie below the root.
+/// Returns `ScanError::PathNotFound` if the path or one of its ancestors does not exist.
+/// Returns `MatchError` if pattern compilation fails.
+pub fn explain_path(config: &Config, target: &Path) -> TreeppResult<FilterExplanation> {
+    let ctx = ScanContext::from_config(config)?;
+    let (components, _) = relative_components(&target.to_string_lossy(), &config.root_path)?;
+    let full_path = components
+        .iter()
+        .fold(config.root_path.clone(), |path, component| {
+            path.join(component)
+        });
+
+    let mut current = config.root_path.clone();
+    let mut parent_chain = GitignoreChain::new();
+    let mut chain = ctx.engine.chain_for(&current, &parent_chain);
+    let mut is_dir = true;
+
+    for (depth, component) in components.iter().enumerate() {
+        let entry_path = current.join(component);
+        let meta = fs::metadata(winpath::to_extended(&entry_path)).map_err(|_| {
+            ScanError::PathNotFound {
+                path: entry_path.clone(),
+            }
+        })?;
+        is_dir = meta.is_dir();
+
+        let reason = ctx
+            .engine
+            .filter_reason(
+                &entry_path,
+                is_dir,
+                is_hidden(&meta),
+                is_cloud_placeholder(&meta),
+                meta.is_file() && meta.len() == 0,
+                &chain,
+            )
+            .or_else(|| {
+                config
+                    .scan
+                    .max_depth
+                    .filter(|&max| depth >= max)
+                    .map(|max_depth| FilterReason::BeyondDepth { max_depth })
+            });
+        if let Some(reason) = reason {
+            return Ok(FilterExplanation {
+                path: full_path,
+                decided_at: entry_path,
+                reason: Some(resolve_source_line(reason)),
+            });
+        }
+
+        if is_dir {
+            parent_chain = chain;
+            chain = ctx.engine.chain_for(&entry_path, &parent_chain);
+        }
+        current = entry_path;
+    }
+
+    let reason = if !is_dir && !config.scan.show_files {
+        Some(FilterReason::FilesNotShown)
+    } else if is_dir
+        && ctx.prune_empty
+        && current != config.root_path
+        && !has_retained_file(&current, &ctx, &parent_chain)
+    {
+        Some(FilterReason::Pruned)
+    } else {
+        None
+    };
+
+    Ok(FilterExplanation {
+        path: full_path,
+        decided_at: current,
+        reason,
+    })
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::TreeppError;
+    use crate::matching::FilterKind;
+    use crate::vfs::MemoryFileSystem;
+    use crate::vfs::faults::{Fault, FaultyFileSystem};
+    use std::fs::{self, File};
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn setup_test_dir() -> TempDir {
+        let dir = TempDir::new().expect("创建临时目录失败");
+        let root = dir.path();
+
+        fs::create_dir(root.join("src")).unwrap();
+        fs::create_dir(root.join("tests")).unwrap();
+        fs::create_dir(root.join("empty")).unwrap();
+
+        File::create(root.join("Cargo.toml"))
+            .unwrap()
+            .write_all(b"[package]")
+            .unwrap();
+        File::create(root.join("README.md"))
+            .unwrap()
+            .write_all(b"# Test")
+            .unwrap();
+        File::create(root.join("src/main.rs"))
+            .unwrap()
+            .write_all(b"fn main() {}")
+            .unwrap();
+        File::create(root.join("src/lib.rs"))
+            .unwrap()
+            .write_all(b"pub fn lib() {}")
+            .unwrap();
+        File::create(root.join("tests/test.rs"))
+            .unwrap()
+            .write_all(b"#[test]")
+            .unwrap();
+
+        dir
+    }
+
+    fn setup_gitignore_dir() -> TempDir {
+        let dir = setup_test_dir();
+        let root = dir.path();
+
+        File::create(root.join(".gitignore"))
+            .unwrap()
+            .write_all(b"target/\n*.log\n")
+            .unwrap();
+
+        fs::create_dir(root.join("target")).unwrap();
+        File::create(root.join("target/debug"))
+            .unwrap()
+            .write_all(b"")
+            .unwrap();
+        File::create(root.join("app.log"))
+            .unwrap()
+            .write_all(b"log")
+            .unwrap();
+
+        dir
+    }
+
+    fn setup_nested_gitignore_dir() -> TempDir {
+        let dir = TempDir::new().expect("创建临时目录失败");
+        let root = dir.path();
+
+        fs::create_dir(root.join("level1")).unwrap();
+        fs::create_dir(root.join("level1/level2")).unwrap();
+        fs::create_dir(root.join("level1/level2/level3")).unwrap();
+
+        File::create(root.join(".gitignore"))
+            .unwrap()
             .write_all(b"*.tmp\n")
             .unwrap();
 
@@ -1504,12 +3746,7 @@ mod tests {
 
     fn count_files_in_tree(node: &TreeNode) -> usize {
         let self_count = if node.kind == EntryKind::File { 1 } else { 0 };
-        self_count
-            + node
-            .children
-            .iter()
-            .map(count_files_in_tree)
-            .sum::<usize>()
+        self_count + node.children.iter().map(count_files_in_tree).sum::<usize>()
     }
 
     #[test]
@@ -1586,6 +3823,9 @@ mod tests {
             size: 42,
             modified: Some(SystemTime::UNIX_EPOCH),
             created: None,
+            accessed: None,
+            cloud_only: false,
+            link_target: None,
         };
         let cloned = meta.clone();
         assert_eq!(cloned.size, 42);
@@ -1836,6 +4076,9 @@ mod tests {
             is_last: true,
             is_file: true,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
 
         assert_eq!(entry.name, "main.rs");
@@ -1858,6 +4101,9 @@ mod tests {
             is_last: true,
             is_file: true,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
         let cloned = entry.clone();
         assert_eq!(cloned.name, "test.txt");
@@ -1869,6 +4115,12 @@ mod tests {
             duration: Duration::from_millis(100),
             directory_count: 5,
             file_count: 20,
+            truncated_dirs: 0,
+            unread_dirs: 0,
+            output_truncated: false,
+            unreadable_dirs: Vec::new(),
+            vanished_entries: 0,
+            rejected: RejectionCounts::default(),
         };
 
         assert_eq!(stats.directory_count, 5);
@@ -1887,8 +4139,11 @@ mod tests {
             is_last: true,
             is_file: true,
             has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
         };
-        let event = StreamEvent::Entry(entry);
+        let event = StreamEvent::Entry(Box::new(entry));
         if let StreamEvent::Entry(e) = event {
             assert_eq!(e.name, "test.txt");
         } else {
@@ -1913,247 +4168,51 @@ mod tests {
     }
 
     #[test]
-    fn gitignore_chain_new_empty() {
-        let chain = GitignoreChain::new();
-        assert!(!chain.is_ignored(Path::new("test.txt"), false));
-        assert!(!chain.is_ignored(Path::new("anything"), true));
+    fn windows_char_priority_ordering() {
+        let (pri_dot, _) = windows_char_priority('.');
+        let (pri_dash, _) = windows_char_priority('-');
+        let (pri_digit, _) = windows_char_priority('5');
+        let (pri_letter, _) = windows_char_priority('a');
+        let (pri_underscore, _) = windows_char_priority('_');
+
+        assert!(pri_dot < pri_digit, "dot should come before digit");
+        assert!(pri_dash < pri_digit, "dash should come before digit");
+        assert!(pri_digit < pri_letter, "digit should come before letter");
+        assert!(
+            pri_letter < pri_underscore,
+            "letter should come before underscore"
+        );
     }
 
     #[test]
-    fn gitignore_chain_with_child_creates_independent_chain() {
-        let chain1 = GitignoreChain::new();
-        let chain2 = chain1.clone();
-        assert_eq!(chain1.rules.len(), chain2.rules.len());
+    fn windows_char_priority_case_insensitive() {
+        let (pri_upper, char_upper) = windows_char_priority('A');
+        let (pri_lower, char_lower) = windows_char_priority('a');
+        assert_eq!(pri_upper, pri_lower);
+        assert_eq!(char_upper, char_lower);
     }
 
     #[test]
-    fn gitignore_chain_is_ignored_basic() {
-        let dir = TempDir::new().unwrap();
-        let root = dir.path();
-
-        File::create(root.join(".gitignore"))
-            .unwrap()
-            .write_all(b"*.log\n")
-            .unwrap();
-
-        let gi = load_gitignore_from_path(root).unwrap();
-        let chain = GitignoreChain::new().with_child(Arc::new(gi));
-
-        assert!(chain.is_ignored(&root.join("test.log"), false));
-        assert!(!chain.is_ignored(&root.join("test.txt"), false));
+    fn windows_compare_names_underscore_after_letter() {
+        assert_eq!(
+            windows_compare_names("_test", "apple"),
+            std::cmp::Ordering::Greater,
+            "_test should come after apple"
+        );
+        assert_eq!(
+            windows_compare_names("_zebra", "apple"),
+            std::cmp::Ordering::Greater,
+            "_zebra should come after apple"
+        );
     }
 
     #[test]
-    fn gitignore_cache_returns_none_for_missing() {
-        let dir = TempDir::new().unwrap();
-        let cache = GitignoreCache::new();
-
-        let result = cache.get_or_load(dir.path());
-        assert!(result.is_none());
-    }
-
-    #[test]
-    fn gitignore_cache_loads_existing() {
-        let dir = TempDir::new().unwrap();
-        let root = dir.path();
-
-        File::create(root.join(".gitignore"))
-            .unwrap()
-            .write_all(b"*.log\n")
-            .unwrap();
-
-        let cache = GitignoreCache::new();
-        let result = cache.get_or_load(root);
-
-        assert!(result.is_some());
-    }
-
-    #[test]
-    fn gitignore_cache_caches_result() {
-        let dir = TempDir::new().unwrap();
-        let root = dir.path();
-
-        File::create(root.join(".gitignore"))
-            .unwrap()
-            .write_all(b"*.log\n")
-            .unwrap();
-
-        let cache = GitignoreCache::new();
-
-        let result1 = cache.get_or_load(root);
-        let result2 = cache.get_or_load(root);
-
-        assert!(result1.is_some());
-        assert!(result2.is_some());
-        assert!(Arc::ptr_eq(&result1.unwrap(), &result2.unwrap()));
-    }
-
-    #[test]
-    fn gitignore_cache_caches_none() {
-        let dir = TempDir::new().unwrap();
-        let cache = GitignoreCache::new();
-
-        let _result1 = cache.get_or_load(dir.path());
-        let _result2 = cache.get_or_load(dir.path());
-
-        let inner = cache.cache.lock().unwrap();
-        assert!(inner.contains_key(dir.path()));
-    }
-
-    #[test]
-    fn load_gitignore_from_path_returns_none_when_missing() {
-        let dir = TempDir::new().unwrap();
-        assert!(load_gitignore_from_path(dir.path()).is_none());
-    }
-
-    #[test]
-    fn load_gitignore_from_path_loads_valid_file() {
-        let dir = TempDir::new().unwrap();
-        File::create(dir.path().join(".gitignore"))
-            .unwrap()
-            .write_all(b"*.txt\n")
-            .unwrap();
-        assert!(load_gitignore_from_path(dir.path()).is_some());
-    }
-
-    #[test]
-    fn compile_pattern_basic() {
-        let pattern = compile_pattern("*.rs").expect("编译失败");
-        assert!(pattern.matches("main.rs"));
-        assert!(pattern.matches("lib.rs"));
-        assert!(!pattern.matches("main.txt"));
-    }
-
-    #[test]
-    fn compile_pattern_invalid() {
-        let result = compile_pattern("[invalid");
-        assert!(result.is_err());
-
-        if let Err(MatchError::InvalidPattern { pattern, .. }) = result {
-            assert_eq!(pattern, "[invalid");
-        } else {
-            panic!("Expected InvalidPattern error");
-        }
-    }
-
-    #[test]
-    fn compile_pattern_complex_glob() {
-        let pattern = compile_pattern("test_*.rs").unwrap();
-        assert!(pattern.matches("test_foo.rs"));
-        assert!(!pattern.matches("foo_test.rs"));
-    }
-
-    #[test]
-    fn compiled_rules_should_include_with_pattern() {
-        let mut config = Config::default();
-        config.matching.include_patterns = vec!["*.rs".to_string()];
-
-        let rules = CompiledRules::compile(&config).unwrap();
-
-        assert!(rules.should_include("main.rs", false));
-        assert!(!rules.should_include("main.txt", false));
-    }
-
-    #[test]
-    fn compiled_rules_should_include_no_patterns() {
-        let config = Config::default();
-        let rules = CompiledRules::compile(&config).unwrap();
-
-        assert!(rules.should_include("any.rs", false));
-        assert!(rules.should_include("any.txt", false));
-    }
-
-    #[test]
-    fn compiled_rules_should_include_directory_always() {
-        let mut config = Config::default();
-        config.matching.include_patterns = vec!["*.rs".to_string()];
-
-        let rules = CompiledRules::compile(&config).unwrap();
-
-        assert!(rules.should_include("src", true));
-        assert!(rules.should_include("tests", true));
-    }
-
-    #[test]
-    fn compiled_rules_should_exclude_no_patterns() {
-        let config = Config::default();
-        let rules = CompiledRules::compile(&config).unwrap();
-
-        assert!(!rules.should_exclude("any.rs"));
-        assert!(!rules.should_exclude("any.txt"));
-    }
-
-    #[test]
-    fn compiled_rules_should_exclude_with_pattern() {
-        let mut config = Config::default();
-        config.matching.exclude_patterns = vec!["*.log".to_string()];
-
-        let rules = CompiledRules::compile(&config).unwrap();
-
-        assert!(rules.should_exclude("app.log"));
-        assert!(!rules.should_exclude("app.txt"));
-    }
-
-    #[test]
-    fn compiled_rules_multiple_patterns() {
-        let mut config = Config::default();
-        config.matching.include_patterns = vec!["*.rs".to_string(), "*.toml".to_string()];
-        config.matching.exclude_patterns = vec!["test_*".to_string()];
-
-        let rules = CompiledRules::compile(&config).unwrap();
-
-        assert!(rules.should_include("main.rs", false));
-        assert!(rules.should_include("Cargo.toml", false));
-        assert!(!rules.should_include("README.md", false));
-        assert!(rules.should_exclude("test_foo.rs"));
-    }
-
-    #[test]
-    fn windows_char_priority_ordering() {
-        let (pri_dot, _) = windows_char_priority('.');
-        let (pri_dash, _) = windows_char_priority('-');
-        let (pri_digit, _) = windows_char_priority('5');
-        let (pri_letter, _) = windows_char_priority('a');
-        let (pri_underscore, _) = windows_char_priority('_');
-
-        assert!(pri_dot < pri_digit, "dot should come before digit");
-        assert!(pri_dash < pri_digit, "dash should come before digit");
-        assert!(pri_digit < pri_letter, "digit should come before letter");
-        assert!(
-            pri_letter < pri_underscore,
-            "letter should come before underscore"
-        );
-    }
-
-    #[test]
-    fn windows_char_priority_case_insensitive() {
-        let (pri_upper, char_upper) = windows_char_priority('A');
-        let (pri_lower, char_lower) = windows_char_priority('a');
-        assert_eq!(pri_upper, pri_lower);
-        assert_eq!(char_upper, char_lower);
-    }
-
-    #[test]
-    fn windows_compare_names_underscore_after_letter() {
-        assert_eq!(
-            windows_compare_names("_test", "apple"),
-            std::cmp::Ordering::Greater,
-            "_test should come after apple"
-        );
-        assert_eq!(
-            windows_compare_names("_zebra", "apple"),
-            std::cmp::Ordering::Greater,
-            "_zebra should come after apple"
-        );
-    }
-
-    #[test]
-    fn windows_compare_names_utf8_case() {
-        assert_eq!(
-            windows_compare_names("utf8parse", "utf8_iter"),
-            std::cmp::Ordering::Less,
-            "utf8parse should come before utf8_iter"
-        );
+    fn windows_compare_names_utf8_case() {
+        assert_eq!(
+            windows_compare_names("utf8parse", "utf8_iter"),
+            std::cmp::Ordering::Less,
+            "utf8parse should come before utf8_iter"
+        );
     }
 
     #[test]
@@ -2199,11 +4258,147 @@ mod tests {
     }
 
     #[test]
-    fn windows_compare_names_length_matters() {
+    fn compare_entries_breaks_case_ties_by_code_point() {
+        let meta = EntryMetadata::default();
+        let compare = |a: &str, b: &str, reverse: bool| {
+            let order = SortOrder::by_name(reverse);
+            compare_entries(EntryKind::File, a, &meta, EntryKind::File, b, &meta, order)
+        };
         assert_eq!(
-            windows_compare_names("ab", "abc"),
+            compare("README.md", "readme.md", false),
             std::cmp::Ordering::Less
         );
+        assert_eq!(
+            compare("readme.md", "README.md", false),
+            std::cmp::Ordering::Greater
+        );
+        assert_eq!(
+            compare("README.md", "readme.md", true),
+            std::cmp::Ordering::Greater
+        );
+        assert_eq!(
+            compare("README.md", "README.md", false),
+            std::cmp::Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn compare_entries_sorts_decomposed_names_with_composed_ones() {
+        let meta = EntryMetadata::default();
+        let compare = |a: &str, b: &str, no_normalize: bool| {
+            let order = SortOrder {
+                no_normalize,
+                ..SortOrder::by_name(false)
+            };
+            compare_entries(EntryKind::File, a, &meta, EntryKind::File, b, &meta, order)
+        };
+        // Composed, the name is a non-ASCII letter, which sorts after "z";
+        // as stored, it starts with "e".
+        assert_eq!(compare("e\u{301}", "z", false), std::cmp::Ordering::Greater);
+        assert_eq!(compare("e\u{301}", "z", true), std::cmp::Ordering::Less);
+        let tie = compare("e\u{301}", "\u{e9}", false);
+        assert_eq!(tie, std::cmp::Ordering::Less, "规范化后相同时按码位区分");
+    }
+
+    #[test]
+    fn sort_tree_is_independent_of_listing_order() {
+        let names = [
+            "readme.md",
+            "b.txt",
+            "README.md",
+            "Readme.md",
+            "_a.txt",
+            "A.txt",
+        ];
+        let sorted_names = |order: &[&str]| {
+            let mut root = TreeNode::new(
+                PathBuf::from("."),
+                EntryKind::Directory,
+                EntryMetadata::default(),
+            );
+            for name in order {
+                root.children.push(TreeNode::new(
+                    PathBuf::from(name),
+                    EntryKind::File,
+                    EntryMetadata::default(),
+                ));
+            }
+            sort_tree(&mut root, false);
+            root.children
+                .into_iter()
+                .map(|c| c.name)
+                .collect::<Vec<_>>()
+        };
+
+        let forward = sorted_names(&names);
+        let mut reversed = names;
+        reversed.reverse();
+        assert_eq!(forward, sorted_names(&reversed), "排序结果不应依赖读取顺序");
+        assert_eq!(
+            forward,
+            vec![
+                "A.txt",
+                "b.txt",
+                "README.md",
+                "Readme.md",
+                "readme.md",
+                "_a.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn batch_and_streaming_list_entries_in_same_order() {
+        fn preorder(node: &TreeNode, names: &mut Vec<String>) {
+            for child in &node.children {
+                names.push(child.name.clone());
+                preorder(child, names);
+            }
+        }
+
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        for name in [
+            "_x.txt",
+            "10.txt",
+            "2.txt",
+            "B.txt",
+            "a.txt",
+            "\u{e9}.txt",
+            "-y.txt",
+        ] {
+            File::create(root.join(name)).unwrap();
+        }
+        fs::create_dir_all(root.join("Zeta").join("inner")).unwrap();
+        fs::create_dir(root.join("alpha")).unwrap();
+        File::create(root.join("Zeta").join("c.txt")).unwrap();
+
+        for reverse in [false, true] {
+            let mut config = Config::with_root(root.to_path_buf());
+            config.scan.show_files = true;
+            config.render.reverse_sort = reverse;
+
+            let mut streamed = Vec::new();
+            scan_streaming(&config, |event| {
+                if let StreamEvent::Entry(entry) = event {
+                    streamed.push(entry.name);
+                }
+                Ok(())
+            })
+            .expect("流式扫描失败");
+
+            let mut batch = Vec::new();
+            preorder(&scan(&config).expect("扫描失败").tree.to_tree(), &mut batch);
+            assert_eq!(
+                streamed, batch,
+                "两种后端的顺序应一致 (reverse = {reverse})"
+            );
+        }
+    }
+
+    #[test]
+    fn windows_compare_names_length_matters() {
+        assert_eq!(windows_compare_names("ab", "abc"), std::cmp::Ordering::Less);
         assert_eq!(
             windows_compare_names("abc", "ab"),
             std::cmp::Ordering::Greater
@@ -2288,998 +4483,2799 @@ mod tests {
             EntryMetadata::default(),
         ));
 
-        sort_tree(&mut root, false);
+        sort_tree(&mut root, false);
+
+        assert_eq!(root.children[0].kind, EntryKind::File);
+        assert_eq!(root.children[1].kind, EntryKind::Directory);
+    }
+
+    #[test]
+    fn sort_tree_interleaved() {
+        let mut root = TreeNode::new(
+            PathBuf::from("."),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        for (name, kind) in [
+            ("b_dir", EntryKind::Directory),
+            ("c.txt", EntryKind::File),
+            ("a.txt", EntryKind::File),
+        ] {
+            root.children.push(TreeNode::new(
+                PathBuf::from(name),
+                kind,
+                EntryMetadata::default(),
+            ));
+        }
+
+        sort_tree_by(
+            &mut root,
+            SortOrder {
+                interleave: true,
+                ..SortOrder::default()
+            },
+        );
+
+        let names: Vec<_> = root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["a.txt", "b_dir", "c.txt"],
+            "文件与目录应按名称混合排序"
+        );
+    }
+
+    #[test]
+    fn sort_tree_by_size_and_created() {
+        let mut root = TreeNode::new(
+            PathBuf::from("."),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        for (name, size, age) in [("a.txt", 300, 1), ("b.txt", 100, 3), ("c.txt", 200, 2)] {
+            root.children.push(TreeNode::new(
+                PathBuf::from(name),
+                EntryKind::File,
+                EntryMetadata {
+                    size,
+                    created: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1000 - age)),
+                    ..Default::default()
+                },
+            ));
+        }
+        let names = |root: &TreeNode| {
+            root.children
+                .iter()
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        sort_tree_by(
+            &mut root,
+            SortOrder {
+                key: SortKey::Size,
+                ..SortOrder::default()
+            },
+        );
+        assert_eq!(names(&root), ["b.txt", "c.txt", "a.txt"]);
+
+        sort_tree_by(
+            &mut root,
+            SortOrder {
+                key: SortKey::Created,
+                ..SortOrder::by_name(true)
+            },
+        );
+        assert_eq!(names(&root), ["a.txt", "c.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn sort_tree_recursive() {
+        let mut root = TreeNode::new(
+            PathBuf::from("."),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+
+        let mut subdir = TreeNode::new(
+            PathBuf::from("sub"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        subdir.children.push(TreeNode::new(
+            PathBuf::from("z.txt"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        ));
+        subdir.children.push(TreeNode::new(
+            PathBuf::from("a.txt"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        ));
+
+        root.children.push(subdir);
+
+        sort_tree(&mut root, false);
+
+        assert_eq!(root.children[0].children[0].name, "a.txt");
+        assert_eq!(root.children[0].children[1].name, "z.txt");
+    }
+
+    #[test]
+    fn sort_tree_windows_style() {
+        let mut root = TreeNode::new(
+            PathBuf::from("."),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+
+        let names = vec!["_underscore", "apple", ".dotfile", "123", "Banana"];
+        for name in names {
+            root.children.push(TreeNode::new(
+                PathBuf::from(name),
+                EntryKind::File,
+                EntryMetadata::default(),
+            ));
+        }
+
+        sort_tree(&mut root, false);
+
+        let sorted_names: Vec<_> = root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            sorted_names,
+            vec![".dotfile", "123", "apple", "Banana", "_underscore"],
+            "sort should follow Windows tree style"
+        );
+    }
+
+    #[test]
+    fn sort_entries_by_name() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        File::create(root.join("zebra.txt")).unwrap();
+        File::create(root.join("alpha.txt")).unwrap();
+        File::create(root.join("beta.txt")).unwrap();
+
+        let ctx = ScanContext::from_config(&Config::with_root(root.to_path_buf())).unwrap();
+        let mut entries = list_dir(root, &ctx).unwrap();
+
+        sort_entries(&mut entries, SortOrder::by_name(false));
+
+        let names: Vec<_> = entries
+            .iter()
+            .map(|e| e.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+
+        assert_eq!(names, vec!["alpha.txt", "beta.txt", "zebra.txt"]);
+    }
+
+    #[test]
+    fn sort_entries_reverse() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        File::create(root.join("a.txt")).unwrap();
+        File::create(root.join("b.txt")).unwrap();
+
+        let ctx = ScanContext::from_config(&Config::with_root(root.to_path_buf())).unwrap();
+        let mut entries = list_dir(root, &ctx).unwrap();
+
+        sort_entries(&mut entries, SortOrder::by_name(true));
+
+        let names: Vec<_> = entries
+            .iter()
+            .map(|e| e.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+
+        assert_eq!(names, vec!["b.txt", "a.txt"]);
+    }
+
+    #[test]
+    fn scan_basic() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.directory_count, 3);
+        assert_eq!(stats.file_count, 5);
+    }
+
+    #[test]
+    fn scan_dirs_only() {
+        let dir = setup_test_dir();
+        let config = Config::with_root(dir.path().to_path_buf());
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.directory_count, 3);
+        assert_eq!(stats.file_count, 0);
+    }
+
+    #[test]
+    fn scan_max_depth_zero() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.max_depth = Some(0);
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.directory_count, 0);
+        assert_eq!(stats.file_count, 0);
+    }
+
+    #[test]
+    fn scan_max_depth_one() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.max_depth = Some(1);
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.directory_count, 3);
+        assert_eq!(stats.file_count, 2);
+    }
+
+    #[test]
+    fn scan_with_include() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.matching.include_patterns = vec!["*.rs".to_string()];
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.file_count, 3);
+    }
+
+    #[test]
+    fn scan_with_exclude() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.matching.exclude_patterns = vec!["*.md".to_string()];
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.file_count, 4);
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "README.md"));
+    }
+
+    #[test]
+    fn scan_nonexistent_path() {
+        let config = Config::with_root(PathBuf::from("/nonexistent/path/12345"));
+        let result = scan(&config);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn scan_file_as_root() {
+        let dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("file.txt");
+        File::create(&file_path).unwrap();
+
+        let config = Config::with_root(file_path);
+        let result = scan(&config);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn scan_with_gitignore() {
+        let dir = setup_gitignore_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.respect_gitignore = true;
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "target"));
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "app.log"));
+        assert!(has_node_with_name(&stats.tree.to_tree(), "Cargo.toml"));
+    }
+
+    #[test]
+    fn scan_without_gitignore() {
+        let dir = setup_gitignore_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.respect_gitignore = false;
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert!(has_node_with_name(&stats.tree.to_tree(), "target"));
+        assert!(has_node_with_name(&stats.tree.to_tree(), "app.log"));
+    }
+
+    #[test]
+    fn scan_nested_gitignore_inheritance() {
+        let dir = setup_nested_gitignore_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.respect_gitignore = true;
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "root.tmp"));
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "l1.tmp"));
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "l2.tmp"));
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "l3.tmp"));
+
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "l1.bak"));
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "l2.bak"));
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "l3.bak"));
+
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "l2.cache"));
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "l3.cache"));
+
+        assert!(has_node_with_name(&stats.tree.to_tree(), "root.txt"));
+        assert!(has_node_with_name(&stats.tree.to_tree(), "l1.txt"));
+        assert!(has_node_with_name(&stats.tree.to_tree(), "l2.txt"));
+        assert!(has_node_with_name(&stats.tree.to_tree(), "l3.txt"));
+    }
+
+    #[test]
+    fn scan_single_thread() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.thread_count = std::num::NonZeroUsize::new(1).unwrap();
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.directory_count, 3);
+        assert_eq!(stats.file_count, 5);
+    }
+
+    #[test]
+    fn scan_multi_thread() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.thread_count = std::num::NonZeroUsize::new(4).unwrap();
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.directory_count, 3);
+        assert_eq!(stats.file_count, 5);
+    }
+
+    #[test]
+    fn scan_thread_count_consistency() {
+        let dir = setup_test_dir();
+
+        let mut config1 = Config::with_root(dir.path().to_path_buf());
+        config1.scan.show_files = true;
+        config1.scan.thread_count = std::num::NonZeroUsize::new(1).unwrap();
+
+        let mut config4 = Config::with_root(dir.path().to_path_buf());
+        config4.scan.show_files = true;
+        config4.scan.thread_count = std::num::NonZeroUsize::new(4).unwrap();
+
+        let mut config8 = Config::with_root(dir.path().to_path_buf());
+        config8.scan.show_files = true;
+        config8.scan.thread_count = std::num::NonZeroUsize::new(8).unwrap();
+
+        let stats1 = scan(&config1).expect("单线程扫描失败");
+        let stats4 = scan(&config4).expect("4线程扫描失败");
+        let stats8 = scan(&config8).expect("8线程扫描失败");
+
+        assert_eq!(stats1.file_count, stats4.file_count);
+        assert_eq!(stats4.file_count, stats8.file_count);
+        assert_eq!(stats1.directory_count, stats4.directory_count);
+        assert_eq!(stats4.directory_count, stats8.directory_count);
+
+        let names1 = collect_names(&stats1.tree.to_tree());
+        let names4 = collect_names(&stats4.tree.to_tree());
+        let names8 = collect_names(&stats8.tree.to_tree());
+        assert_eq!(names1, names4);
+        assert_eq!(names4, names8);
+    }
+
+    #[test]
+    fn scan_backends_yield_same_tree() {
+        let dir = setup_test_dir();
+        let trees: Vec<_> = [
+            ScanBackend::Walk,
+            ScanBackend::Parallel,
+            ScanBackend::Channel,
+        ]
+        .into_iter()
+        .map(|backend| {
+            let mut config = Config::with_root(dir.path().to_path_buf());
+            config.scan.show_files = true;
+            config.scan.backend = backend;
+            let stats = scan(&config).expect("扫描失败");
+            (
+                stats.directory_count,
+                stats.file_count,
+                collect_names(&stats.tree.to_tree()),
+            )
+        })
+        .collect();
+
+        assert_eq!(trees[0], trees[1], "walk 与 parallel 结果应一致");
+        assert_eq!(trees[1], trees[2], "parallel 与 channel 结果应一致");
+    }
+
+    #[test]
+    fn scan_channel_waits_for_slow_builder() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        let ctx = ScanContext::from_config(&config).unwrap();
+
+        // A single slot makes every worker wait for the builder.
+        let tree = scan_channel(&config, &ctx, 1).expect("扫描失败");
+        let expected = scan(&config).expect("扫描失败").tree;
+        assert_eq!(tree.count_files(), expected.count_files());
+        assert_eq!(
+            collect_names(&tree.to_tree()),
+            collect_names(&expected.to_tree()),
+            "通道容量不应影响扫描结果"
+        );
+    }
+
+    /// Every entry below the root with its error, the directories reported
+    /// unreadable and the number of vanished entries.
+    type ScanOutcome = (Vec<(PathBuf, Option<EntryError>)>, Vec<PathBuf>, usize);
+
+    fn collect_entries(node: &TreeNode, entries: &mut Vec<(PathBuf, Option<EntryError>)>) {
+        for child in &node.children {
+            entries.push((child.path.clone(), child.error));
+            collect_entries(child, entries);
+        }
+    }
+
+    /// Scans with each batch backend and the streaming scan, each through a
+    /// new file system from `faults`.
+    fn scan_with_faults(
+        config: &Config,
+        faults: impl Fn() -> FaultyFileSystem,
+    ) -> Vec<TreeppResult<ScanOutcome>> {
+        let mut outcomes: Vec<_> = [
+            ScanBackend::Walk,
+            ScanBackend::Parallel,
+            ScanBackend::Channel,
+        ]
+        .into_iter()
+        .map(|backend| {
+            let mut config = config.clone();
+            config.scan.backend = backend;
+            let mut ctx = ScanContext::from_config(&config).unwrap();
+            ctx.fs = Arc::new(faults());
+            let stats = scan_tree(&config, &ctx, &[], Instant::now())?;
+            let mut entries = Vec::new();
+            collect_entries(&stats.tree.to_tree(), &mut entries);
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Ok((entries, stats.unreadable_dirs, stats.vanished_entries))
+        })
+        .collect();
+
+        let mut ctx = ScanContext::from_config(config).unwrap();
+        ctx.fs = Arc::new(faults());
+        let mut entries = Vec::new();
+        let streamed = stream_tree(
+            config,
+            &ctx,
+            |event| {
+                if let StreamEvent::Entry(entry) = event {
+                    entries.push((entry.path, entry.error));
+                }
+                Ok(())
+            },
+            Instant::now(),
+        );
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        outcomes
+            .push(streamed.map(|stats| (entries, stats.unreadable_dirs, stats.vanished_entries)));
+        outcomes
+    }
+
+    /// Asserts that all scans succeeded with the same result and returns it.
+    fn consistent_outcome(outcomes: Vec<TreeppResult<ScanOutcome>>) -> ScanOutcome {
+        let outcomes: Vec<_> = outcomes.into_iter().map(|o| o.expect("扫描失败")).collect();
+        for (i, outcome) in outcomes.iter().enumerate().skip(1) {
+            assert_eq!(outcome, &outcomes[0], "第 {} 种扫描方式的结果不一致", i + 1);
+        }
+        outcomes.into_iter().next().unwrap()
+    }
+
+    fn faults_config(dir: &TempDir) -> Config {
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.thread_count = std::num::NonZeroUsize::new(4).unwrap();
+        config
+    }
+
+    #[test]
+    fn scan_on_walks_memory_file_system_on_one_thread() {
+        // wasm32-wasip1 cannot start threads; this is the scan it runs.
+        let fs: Arc<dyn FileSystem> = Arc::new(
+            MemoryFileSystem::default()
+                .with_file("/demo/src/main.rs", 120)
+                .with_file("/demo/README.md", 10)
+                .with_dir("/demo/docs"),
+        );
+        let mut config = Config::with_root(PathBuf::from("/demo"));
+        config.scan.show_files = true;
+        config.scan.backend = ScanBackend::Walk;
+
+        let stats = scan_on(&config, fs, &[]).expect("扫描失败");
+        assert_eq!(stats.directory_count, 2, "目录数应为 2");
+        assert_eq!(stats.file_count, 2, "文件数应为 2");
+        let tree = stats.tree.to_tree();
+        let names: Vec<_> = tree.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["README.md", "docs", "src"]);
+        assert_eq!(tree.children[2].children[0].name, "main.rs");
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "wasi", ignore = "wasm32-wasip1 cannot start threads")]
+    fn scan_on_lists_memory_file_system_with_every_backend() {
+        let fs: Arc<dyn FileSystem> = Arc::new(
+            MemoryFileSystem::default()
+                .with_file("/demo/src/main.rs", 120)
+                .with_file("/demo/src/lib.rs", 80)
+                .with_file("/demo/README.md", 10)
+                .with_dir("/demo/docs"),
+        );
+        for backend in [
+            ScanBackend::Walk,
+            ScanBackend::Parallel,
+            ScanBackend::Channel,
+        ] {
+            let mut config = Config::with_root(PathBuf::from("/demo"));
+            config.scan.show_files = true;
+            config.scan.backend = backend;
+            config.render.show_disk_usage = true;
+
+            let stats = scan_on(&config, Arc::clone(&fs), &[]).expect("扫描失败");
+            assert_eq!(stats.directory_count, 2, "{backend:?}");
+            assert_eq!(stats.file_count, 3, "{backend:?}");
+            let tree = stats.tree.to_tree();
+            let names: Vec<_> = tree.children.iter().map(|c| c.name.as_str()).collect();
+            assert_eq!(names, ["README.md", "docs", "src"], "{backend:?}");
+            assert_eq!(tree.children[2].disk_usage, Some(200), "{backend:?}");
+        }
+    }
+
+    #[test]
+    fn scan_on_rejects_root_missing_from_file_system() {
+        let fs: Arc<dyn FileSystem> = Arc::new(MemoryFileSystem::default().with_file("/a.txt", 1));
+
+        let missing = scan_on(
+            &Config::with_root(PathBuf::from("/demo")),
+            Arc::clone(&fs),
+            &[],
+        );
+        assert!(matches!(
+            missing,
+            Err(TreeppError::Scan(ScanError::PathNotFound { .. }))
+        ));
+        let file = scan_on(&Config::with_root(PathBuf::from("/a.txt")), fs, &[]);
+        assert!(matches!(
+            file,
+            Err(TreeppError::Scan(ScanError::NotADirectory { .. }))
+        ));
+    }
+
+    #[test]
+    fn scan_backends_agree_on_denied_directory() {
+        let dir = setup_test_dir();
+        let src = dir.path().join("src");
+        let (entries, unreadable, vanished) =
+            consistent_outcome(scan_with_faults(&faults_config(&dir), || {
+                FaultyFileSystem::default().with(&src, Fault::Denied)
+            }));
+
+        assert!(entries.contains(&(src.clone(), Some(EntryError::AccessDenied))));
+        assert!(
+            !entries
+                .iter()
+                .any(|(path, _)| path.starts_with(&src) && path != &src)
+        );
+        assert_eq!(unreadable, vec![src]);
+        assert_eq!(vanished, 0);
+    }
+
+    #[test]
+    fn scan_backends_agree_on_transient_failure() {
+        let dir = setup_test_dir();
+        let tests = dir.path().join("tests");
+        let (entries, unreadable, _) =
+            consistent_outcome(scan_with_faults(&faults_config(&dir), || {
+                FaultyFileSystem::default().with(&tests, Fault::Transient(1))
+            }));
+
+        // Each directory is read once, so the failure is reported, not retried.
+        assert!(entries.contains(&(tests.clone(), Some(EntryError::ReadFailed))));
+        assert_eq!(unreadable, vec![tests]);
+    }
+
+    #[test]
+    fn scan_backends_agree_on_vanished_entries() {
+        let dir = setup_test_dir();
+        let main_rs = dir.path().join("src/main.rs");
+        let empty = dir.path().join("empty");
+        let (entries, unreadable, vanished) =
+            consistent_outcome(scan_with_faults(&faults_config(&dir), || {
+                FaultyFileSystem::default()
+                    .with(&main_rs, Fault::Vanished)
+                    .with(&empty, Fault::Vanished)
+            }));
+
+        assert!(
+            !entries
+                .iter()
+                .any(|(path, _)| path == &main_rs || path == &empty)
+        );
+        assert!(entries.contains(&(dir.path().join("src/lib.rs"), None)));
+        assert!(unreadable.is_empty());
+        assert_eq!(vanished, 2, "消失的条目应被计数");
+    }
+
+    #[test]
+    fn scan_backends_skip_directory_removed_before_read() {
+        let dir = setup_test_dir();
+        let tests = dir.path().join("tests");
+        for prune_empty in [false, true] {
+            let mut config = faults_config(&dir);
+            config.matching.prune_empty = prune_empty;
+            let (entries, unreadable, vanished) =
+                consistent_outcome(scan_with_faults(&config, || {
+                    FaultyFileSystem::default().with(&tests, Fault::Removed)
+                }));
+
+            assert!(
+                !entries.iter().any(|(path, _)| path.starts_with(&tests)),
+                "不应显示已删除的目录"
+            );
+            assert!(unreadable.is_empty(), "已删除的目录不应算作无法读取");
+            assert_eq!(vanished, 1);
+        }
+    }
+
+    #[test]
+    fn streaming_closes_level_before_removed_last_directory() {
+        let dir = setup_test_dir();
+        let config = faults_config(&dir);
+        let mut ctx = ScanContext::from_config(&config).unwrap();
+        let removed = dir.path().join("tests");
+        ctx.fs = Arc::new(FaultyFileSystem::default().with(removed, Fault::Removed));
+
+        let mut top_level = Vec::new();
+        stream_tree(
+            &config,
+            &ctx,
+            |event| {
+                if let StreamEvent::Entry(entry) = event
+                    && entry.depth == 0
+                {
+                    top_level.push((entry.name, entry.is_last));
+                }
+                Ok(())
+            },
+            Instant::now(),
+        )
+        .expect("流式扫描失败");
+
+        let last = top_level.last().expect("应有条目");
+        assert_eq!(
+            last,
+            &("src".to_string(), true),
+            "删除的最后一个目录之前的目录应为最后一项"
+        );
+        assert_eq!(top_level.iter().filter(|(_, is_last)| *is_last).count(), 1);
+    }
+
+    #[test]
+    fn scan_backends_fail_on_unreadable_root() {
+        let dir = setup_test_dir();
+        let root = dir.path().to_path_buf();
+        let outcomes = scan_with_faults(&faults_config(&dir), || {
+            FaultyFileSystem::default().with(&root, Fault::Denied)
+        });
+
+        for outcome in outcomes {
+            assert!(outcome.is_err(), "根目录无法读取时扫描应失败");
+        }
+    }
+
+    #[test]
+    fn scan_with_gitignore_thread_consistency() {
+        let dir = setup_nested_gitignore_dir();
+
+        let mut config1 = Config::with_root(dir.path().to_path_buf());
+        config1.scan.show_files = true;
+        config1.scan.respect_gitignore = true;
+        config1.scan.thread_count = std::num::NonZeroUsize::new(1).unwrap();
+
+        let mut config8 = Config::with_root(dir.path().to_path_buf());
+        config8.scan.show_files = true;
+        config8.scan.respect_gitignore = true;
+        config8.scan.thread_count = std::num::NonZeroUsize::new(8).unwrap();
+
+        let stats1 = scan(&config1).expect("单线程扫描失败");
+        let stats8 = scan(&config8).expect("8线程扫描失败");
+
+        assert_eq!(stats1.file_count, stats8.file_count);
+        assert_eq!(stats1.directory_count, stats8.directory_count);
+
+        let names1 = collect_names(&stats1.tree.to_tree());
+        let names8 = collect_names(&stats8.tree.to_tree());
+        assert_eq!(names1, names8);
+    }
+
+    #[test]
+    fn scan_streaming_basic() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+
+        let mut entries = Vec::new();
+        let mut enter_count = 0;
+        let mut leave_count = 0;
+
+        let stats = scan_streaming(&config, |event| {
+            match event {
+                StreamEvent::Entry(entry) => {
+                    entries.push(entry);
+                }
+                StreamEvent::EnterDir { .. } => {
+                    enter_count += 1;
+                }
+                StreamEvent::LeaveDir => {
+                    leave_count += 1;
+                }
+                StreamEvent::Omitted { .. } => {}
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+
+        assert_eq!(stats.directory_count, 3);
+        assert_eq!(stats.file_count, 5);
+        assert_eq!(entries.len(), 8);
+        assert_eq!(enter_count, 3);
+        assert_eq!(leave_count, 3);
+    }
+
+    #[test]
+    fn scan_streaming_dirs_only() {
+        let dir = setup_test_dir();
+        let config = Config::with_root(dir.path().to_path_buf());
+
+        let mut entries = Vec::new();
+
+        let stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event {
+                entries.push(entry);
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+
+        assert_eq!(stats.directory_count, 3);
+        assert_eq!(stats.file_count, 0);
+        assert_eq!(entries.len(), 3);
+
+        for entry in &entries {
+            assert_eq!(entry.kind, EntryKind::Directory);
+        }
+    }
+
+    #[test]
+    fn scan_streaming_with_gitignore() {
+        let dir = setup_gitignore_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.respect_gitignore = true;
+
+        let mut names = Vec::new();
+
+        let _stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event {
+                names.push(entry.name.clone());
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+
+        assert!(!names.contains(&"target".to_string()));
+        assert!(!names.contains(&"app.log".to_string()));
+    }
+
+    #[test]
+    fn scan_streaming_depth_info() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+
+        let mut entries = Vec::new();
+
+        let _stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event {
+                entries.push((entry.name.clone(), entry.depth));
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+
+        let root_entries: Vec<_> = entries.iter().filter(|(_, d)| *d == 0).collect();
+        assert!(!root_entries.is_empty());
+
+        let nested_entries: Vec<_> = entries.iter().filter(|(_, d)| *d == 1).collect();
+        assert!(!nested_entries.is_empty());
+    }
+
+    #[test]
+    fn scan_streaming_is_last_flag() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        File::create(root.join("a.txt")).unwrap();
+        File::create(root.join("b.txt")).unwrap();
+        File::create(root.join("c.txt")).unwrap();
+
+        let mut config = Config::with_root(root.to_path_buf());
+        config.scan.show_files = true;
+
+        let mut entries = Vec::new();
+
+        let _stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event {
+                entries.push((entry.name.clone(), entry.is_last));
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+
+        assert_eq!(entries.len(), 3);
+
+        let last_count = entries.iter().filter(|(_, is_last)| *is_last).count();
+        assert_eq!(last_count, 1);
+    }
+
+    #[test]
+    fn scan_streaming_nonexistent_path() {
+        let config = Config::with_root(PathBuf::from("/nonexistent/path/12345"));
+
+        let result = scan_streaming(&config, |_| Ok(()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn scan_streaming_max_depth() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.max_depth = Some(1);
+
+        let mut entries = Vec::new();
+
+        let stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event {
+                entries.push(entry);
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+
+        assert_eq!(stats.directory_count, 3);
+        assert_eq!(stats.file_count, 2);
+    }
+
+    #[test]
+    fn scan_streaming_callback_error_propagation() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+
+        let mut count = 0;
+        let result = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(_) = event {
+                count += 1;
+                if count >= 2 {
+                    return Err(ScanError::WalkError {
+                        message: "test error".to_string(),
+                        path: None,
+                    });
+                }
+            }
+            Ok(())
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn scanner_iter_yields_streamed_entries() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        let scanner = Scanner::new(config);
+
+        let names: Vec<String> = scanner.iter().map(|entry| entry.name).collect();
+        assert_eq!(names.len(), 8);
+        assert_eq!(names[0], "Cargo.toml", "顺序应与流式扫描一致");
+
+        let mut iter = scanner.iter();
+        assert!(iter.next().is_some());
+        assert!(iter.finish().is_ok(), "提前结束不应报错");
+    }
+
+    #[test]
+    fn scanner_iter_collects_into_tree() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+
+        let tree = Scanner::new(config).iter().into_tree().expect("扫描失败");
+        assert_eq!(tree.count_directories(), 3);
+        assert_eq!(tree.count_files(), 5);
+        let src = tree.children.iter().find(|c| c.name == "src").unwrap();
+        let names: Vec<&str> = src.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["lib.rs", "main.rs"]);
+    }
+
+    #[test]
+    fn scanner_iter_reports_scan_errors() {
+        let config = Config::with_root(PathBuf::from("/nonexistent/path/12345"));
+        let mut iter = Scanner::new(config).iter();
+        assert!(iter.next().is_none());
+        assert!(iter.finish().is_err());
+    }
+
+    #[test]
+    fn streaming_vs_batch_entry_names() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+
+        let batch_stats = scan(&config).expect("批处理扫描失败");
+        let batch_names = collect_names(&batch_stats.tree.to_tree());
+
+        let mut stream_names = Vec::new();
+        let _stream_stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event {
+                stream_names.push(entry.name.clone());
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+        stream_names.sort();
+
+        let batch_without_root: Vec<_> = batch_names
+            .into_iter()
+            .filter(|n| n != &batch_stats.tree.to_tree().name)
+            .collect();
+
+        assert_eq!(stream_names, batch_without_root);
+    }
+
+    #[test]
+    fn streaming_vs_batch_counts() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+
+        let batch_stats = scan(&config).expect("批处理扫描失败");
+        let stream_stats = scan_streaming(&config, |_| Ok(())).expect("流式扫描失败");
+
+        assert_eq!(batch_stats.directory_count, stream_stats.directory_count);
+        assert_eq!(batch_stats.file_count, stream_stats.file_count);
+    }
+
+    #[test]
+    fn scan_empty_directory() {
+        let dir = TempDir::new().unwrap();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.directory_count, 0);
+        assert_eq!(stats.file_count, 0);
+    }
+
+    #[test]
+    fn scan_single_file_directory() {
+        let dir = TempDir::new().unwrap();
+        File::create(dir.path().join("only.txt")).unwrap();
+
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.file_count, 1);
+    }
+
+    #[test]
+    fn scan_deeply_nested() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        let mut current = root.to_path_buf();
+        for i in 0..5 {
+            current = current.join(format!("level{}", i));
+            fs::create_dir(&current).unwrap();
+        }
+        File::create(current.join("deep.txt")).unwrap();
+
+        let mut config = Config::with_root(root.to_path_buf());
+        config.scan.show_files = true;
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.directory_count, 5);
+        assert_eq!(stats.file_count, 1);
+        assert!(has_node_with_name(&stats.tree.to_tree(), "deep.txt"));
+    }
+
+    #[test]
+    fn scan_path_beyond_max_path_keeps_display_paths() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        let mut current = root.to_path_buf();
+        let mut levels = 0;
+        while current.to_string_lossy().len() < 300 {
+            current = current.join(format!("node_modules_package_level_{}", levels));
+            levels += 1;
+        }
+        fs::create_dir_all(&current).unwrap();
+        File::create(current.join("deep.js")).unwrap();
+
+        let mut config = Config::with_root(root.to_path_buf());
+        config.scan.show_files = true;
+
+        let stats = scan(&config).expect("扫描失败");
+        assert_eq!(stats.directory_count, levels);
+        assert!(has_node_with_name(&stats.tree.to_tree(), "deep.js"));
+
+        fn assert_no_verbatim(node: &TreeNode) {
+            assert!(
+                !node.path.to_string_lossy().starts_with(r"\\?\"),
+                "路径不应包含 \\\\?\\ 前缀: {}",
+                node.path.display()
+            );
+            node.children.iter().for_each(assert_no_verbatim);
+        }
+        assert_no_verbatim(&stats.tree.to_tree());
+
+        let mut streamed = Vec::new();
+        scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event {
+                streamed.push(entry.name);
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+        assert!(streamed.contains(&"deep.js".to_string()));
+    }
+
+    #[test]
+    fn scan_stats_duration_is_measured() {
+        let dir = setup_test_dir();
+        let config = Config::with_root(dir.path().to_path_buf());
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert!(stats.duration.as_nanos() > 0);
+    }
+
+    #[test]
+    fn scan_stats_counts_match_tree() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.directory_count, stats.tree.count_directories());
+        assert_eq!(stats.file_count, stats.tree.count_files());
+    }
+
+    #[test]
+    fn stream_stats_duration_is_measured() {
+        let dir = setup_test_dir();
+        let config = Config::with_root(dir.path().to_path_buf());
+
+        let stats = scan_streaming(&config, |_| Ok(())).expect("扫描失败");
+
+        assert!(stats.duration.as_nanos() > 0);
+    }
+
+    #[test]
+    fn stream_entry_new_fields() {
+        let entry = StreamEntry {
+            path: PathBuf::from("test.txt"),
+            name: "test.txt".to_string(),
+            kind: EntryKind::File,
+            metadata: EntryMetadata::default(),
+            depth: 0,
+            is_last: true,
+            is_file: true,
+            has_more_dirs: false,
+            filtered: None,
+            collapsed: None,
+            error: None,
+        };
+
+        assert!(entry.is_file);
+        assert!(!entry.has_more_dirs);
+    }
+
+    #[test]
+    fn scan_collects_files_for_disk_usage_even_without_show_files() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.batch_mode = true;
+        config.scan.show_files = false;
+        config.render.show_disk_usage = true;
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert!(stats.tree.to_tree().disk_usage.is_some());
+        assert!(stats.tree.to_tree().disk_usage.unwrap() > 0);
+    }
+
+    #[test]
+    fn scan_disk_usage_correct_without_show_files() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        fs::create_dir(root.join("subdir")).unwrap();
+        File::create(root.join("file1.txt"))
+            .unwrap()
+            .write_all(b"12345")
+            .unwrap();
+        File::create(root.join("subdir/file2.txt"))
+            .unwrap()
+            .write_all(b"1234567890")
+            .unwrap();
+
+        let mut config = Config::with_root(root.to_path_buf());
+        config.batch_mode = true;
+        config.scan.show_files = false;
+        config.render.show_disk_usage = true;
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.tree.to_tree().disk_usage, Some(15));
+    }
+
+    #[test]
+    fn scan_no_files_collected_when_neither_show_files_nor_disk_usage() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = false;
+        config.render.show_disk_usage = false;
+
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.file_count, 0);
+        assert_eq!(count_files_in_tree(&stats.tree.to_tree()), 0);
+    }
+
+    #[test]
+    fn scan_context_collect_files_for_size_enabled_when_disk_usage() {
+        let mut config = Config {
+            batch_mode: true,
+            ..Default::default()
+        };
+        config.render.show_disk_usage = true;
+        config.scan.show_files = false;
+
+        let ctx = ScanContext::from_config(&config).unwrap();
+
+        assert!(!ctx.show_files);
+        assert!(ctx.collect_files_for_size);
+    }
+
+    #[test]
+    fn scan_context_collect_files_for_size_disabled_by_default() {
+        let config = Config::default();
+
+        let ctx = ScanContext::from_config(&config).unwrap();
+
+        assert!(!ctx.show_files);
+        assert!(!ctx.collect_files_for_size);
+    }
+
+    #[test]
+    fn scan_disk_usage_with_max_depth_collects_full_size() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        File::create(root.join("a/b/deep.txt"))
+            .unwrap()
+            .write_all(b"1234567")
+            .unwrap();
+
+        let mut config = Config::with_root(root.to_path_buf());
+        config.batch_mode = true;
+        config.render.show_disk_usage = true;
+        config.scan.show_files = false;
+        config.scan.max_depth = Some(1);
+
+        let stats = scan(&config).expect("扫描失败");
+
+        let tree = stats.tree.to_tree();
+        assert_eq!(tree.disk_usage, Some(7));
+        let dir_a = tree.children.iter().find(|c| c.name == "a").unwrap();
+        assert_eq!(dir_a.disk_usage, Some(7));
+    }
+
+    #[test]
+    fn scan_context_from_config_with_all_options() {
+        let mut config = Config::default();
+        config.scan.show_files = true;
+        config.scan.max_depth = Some(5);
+        config.scan.respect_gitignore = true;
+        config.render.reverse_sort = true;
+        config.matching.include_patterns = vec!["*.rs".to_string()];
+        config.matching.exclude_patterns = vec!["test_*".to_string()];
+
+        let ctx = ScanContext::from_config(&config).unwrap();
+
+        assert!(ctx.show_files);
+        assert_eq!(ctx.max_depth, Some(5));
+        assert!(ctx.reverse);
+    }
+
+    #[test]
+    fn scan_with_multiple_exclude_patterns() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.matching.exclude_patterns = vec!["*.md".to_string(), "*.toml".to_string()];
 
-        assert_eq!(root.children[0].kind, EntryKind::File);
-        assert_eq!(root.children[1].kind, EntryKind::Directory);
+        let stats = scan(&config).expect("扫描失败");
+
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "README.md"));
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "Cargo.toml"));
+        assert!(has_node_with_name(&stats.tree.to_tree(), "main.rs"));
     }
 
     #[test]
-    fn sort_tree_recursive() {
-        let mut root = TreeNode::new(
-            PathBuf::from("."),
-            EntryKind::Directory,
-            EntryMetadata::default(),
-        );
+    fn scan_streaming_with_exclude() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.matching.exclude_patterns = vec!["*.md".to_string()];
 
-        let mut subdir = TreeNode::new(
-            PathBuf::from("sub"),
+        let mut names = Vec::new();
+        let _stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event {
+                names.push(entry.name.clone());
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+
+        assert!(!names.contains(&"README.md".to_string()));
+    }
+
+    #[test]
+    fn tree_node_clone() {
+        let mut original = TreeNode::new(
+            PathBuf::from("test"),
             EntryKind::Directory,
-            EntryMetadata::default(),
+            EntryMetadata {
+                size: 0,
+                ..Default::default()
+            },
         );
-        subdir.children.push(TreeNode::new(
-            PathBuf::from("z.txt"),
-            EntryKind::File,
-            EntryMetadata::default(),
-        ));
-        subdir.children.push(TreeNode::new(
-            PathBuf::from("a.txt"),
+        original.children.push(TreeNode::new(
+            PathBuf::from("child.txt"),
             EntryKind::File,
             EntryMetadata::default(),
         ));
+        original.disk_usage = Some(100);
 
-        root.children.push(subdir);
-
-        sort_tree(&mut root, false);
+        let cloned = original.clone();
 
-        assert_eq!(root.children[0].children[0].name, "a.txt");
-        assert_eq!(root.children[0].children[1].name, "z.txt");
+        assert_eq!(cloned.name, "test");
+        assert_eq!(cloned.children.len(), 1);
+        assert_eq!(cloned.disk_usage, Some(100));
     }
 
     #[test]
-    fn sort_tree_windows_style() {
-        let mut root = TreeNode::new(
-            PathBuf::from("."),
-            EntryKind::Directory,
-            EntryMetadata::default(),
-        );
+    fn scan_handles_special_characters_in_filenames() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
 
-        let names = vec!["_underscore", "apple", ".dotfile", "123", "Banana"];
-        for name in names {
-            root.children.push(TreeNode::new(
-                PathBuf::from(name),
-                EntryKind::File,
-                EntryMetadata::default(),
-            ));
-        }
+        File::create(root.join("file with spaces.txt")).unwrap();
+        File::create(root.join("file-with-dashes.txt")).unwrap();
+        File::create(root.join("file_with_underscores.txt")).unwrap();
 
-        sort_tree(&mut root, false);
+        let mut config = Config::with_root(root.to_path_buf());
+        config.scan.show_files = true;
 
-        let sorted_names: Vec<_> = root.children.iter().map(|c| c.name.as_str()).collect();
-        assert_eq!(
-            sorted_names,
-            vec![".dotfile", "123", "apple", "Banana", "_underscore"],
-            "sort should follow Windows tree style"
-        );
+        let stats = scan(&config).expect("扫描失败");
+
+        assert_eq!(stats.file_count, 3);
+        assert!(has_node_with_name(
+            &stats.tree.to_tree(),
+            "file with spaces.txt"
+        ));
+        assert!(has_node_with_name(
+            &stats.tree.to_tree(),
+            "file-with-dashes.txt"
+        ));
+        assert!(has_node_with_name(
+            &stats.tree.to_tree(),
+            "file_with_underscores.txt"
+        ));
     }
 
     #[test]
-    fn sort_entries_by_name() {
+    fn scan_streaming_resumes_after_completed_directory() {
         let dir = TempDir::new().unwrap();
         let root = dir.path();
 
-        File::create(root.join("zebra.txt")).unwrap();
-        File::create(root.join("alpha.txt")).unwrap();
-        File::create(root.join("beta.txt")).unwrap();
-
-        let mut entries: Vec<(PathBuf, Metadata)> = fs::read_dir(root)
-            .unwrap()
-            .flatten()
-            .filter_map(|e| {
-                let path = e.path();
-                let meta = e.metadata().ok()?;
-                Some((path, meta))
+        File::create(root.join("r.txt")).unwrap();
+        fs::create_dir_all(root.join("a").join("x")).unwrap();
+        fs::create_dir(root.join("a").join("y")).unwrap();
+        fs::create_dir(root.join("b")).unwrap();
+        File::create(root.join("a").join("a.txt")).unwrap();
+        File::create(root.join("a").join("x").join("x.txt")).unwrap();
+        File::create(root.join("b").join("b.txt")).unwrap();
+
+        let events_after = |resume: &str| {
+            let mut config = Config::with_root(root.to_path_buf());
+            config.scan.show_files = true;
+            config.scan.resume_after = Some(PathBuf::from(resume));
+            let mut events = Vec::new();
+            let stats = scan_streaming(&config, |event| {
+                events.push(match event {
+                    StreamEvent::Entry(entry) => entry.name,
+                    StreamEvent::EnterDir { .. } => ">".to_string(),
+                    StreamEvent::LeaveDir => "<".to_string(),
+                    StreamEvent::Omitted { .. } => "...".to_string(),
+                });
+                Ok(())
             })
-            .collect();
-
-        sort_entries(&mut entries, false);
-
-        let names: Vec<_> = entries
-            .iter()
-            .map(|(p, _)| p.file_name().unwrap().to_string_lossy().to_string())
-            .collect();
+            .expect("流式扫描失败");
+            (events.join(" "), stats.directory_count, stats.file_count)
+        };
 
-        assert_eq!(names, vec!["alpha.txt", "beta.txt", "zebra.txt"]);
+        assert_eq!(
+            events_after("a/x"),
+            ("y > < < b > b.txt <".to_string(), 2, 1),
+            "应跳过已写出的条目并关闭未完成的目录"
+        );
+        assert_eq!(events_after("a"), ("b > b.txt <".to_string(), 1, 1));
+        assert_eq!(events_after("b"), (String::new(), 0, 0));
+        assert_eq!(
+            events_after("missing"),
+            (
+                "a > a.txt x > x.txt < y > < < b > b.txt <".to_string(),
+                4,
+                3
+            ),
+            "找不到的目录应从本层的首个目录继续"
+        );
     }
 
     #[test]
-    fn sort_entries_reverse() {
+    fn scan_streaming_has_more_dirs_flag() {
         let dir = TempDir::new().unwrap();
         let root = dir.path();
 
-        File::create(root.join("a.txt")).unwrap();
-        File::create(root.join("b.txt")).unwrap();
-
-        let mut entries: Vec<(PathBuf, Metadata)> = fs::read_dir(root)
-            .unwrap()
-            .flatten()
-            .filter_map(|e| {
-                let path = e.path();
-                let meta = e.metadata().ok()?;
-                Some((path, meta))
-            })
-            .collect();
+        File::create(root.join("file.txt")).unwrap();
+        fs::create_dir(root.join("dir1")).unwrap();
+        fs::create_dir(root.join("dir2")).unwrap();
 
-        sort_entries(&mut entries, true);
+        let mut config = Config::with_root(root.to_path_buf());
+        config.scan.show_files = true;
 
-        let names: Vec<_> = entries
-            .iter()
-            .map(|(p, _)| p.file_name().unwrap().to_string_lossy().to_string())
-            .collect();
+        let mut file_entries = Vec::new();
+        let _stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event
+                && entry.is_file
+            {
+                file_entries.push(entry);
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
 
-        assert_eq!(names, vec!["b.txt", "a.txt"]);
+        assert!(!file_entries.is_empty());
+        assert!(file_entries[0].has_more_dirs);
     }
 
     #[test]
-    fn scan_basic() {
+    fn streaming_vs_batch_order_consistency() {
         let dir = setup_test_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
 
-        let stats = scan(&config).expect("扫描失败");
+        let batch_stats = scan(&config).expect("批处理扫描失败");
 
-        assert_eq!(stats.directory_count, 3);
-        assert_eq!(stats.file_count, 5);
+        let mut batch_order = Vec::new();
+        fn collect_order(node: &TreeNode, order: &mut Vec<String>) {
+            for child in &node.children {
+                order.push(child.name.clone());
+                if child.kind == EntryKind::Directory {
+                    collect_order(child, order);
+                }
+            }
+        }
+        collect_order(&batch_stats.tree.to_tree(), &mut batch_order);
+
+        let mut stream_order = Vec::new();
+        let _stream_stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event {
+                stream_order.push(entry.name.clone());
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+
+        assert_eq!(batch_order, stream_order);
     }
 
     #[test]
-    fn scan_dirs_only() {
-        let dir = setup_test_dir();
-        let config = Config::with_root(dir.path().to_path_buf());
+    fn parse_path_list_skips_empty_lines_and_crlf() {
+        let list = parse_path_list(b"src/main.rs\r\n\r\n  \nCargo.toml\n");
+        assert_eq!(
+            list,
+            vec!["src/main.rs".to_string(), "Cargo.toml".to_string()]
+        );
+    }
 
-        let stats = scan(&config).expect("扫描失败");
+    #[test]
+    fn parse_path_list_strips_utf8_bom() {
+        let list = parse_path_list(b"\xEF\xBB\xBFa.txt\nb.txt");
+        assert_eq!(list, vec!["a.txt".to_string(), "b.txt".to_string()]);
+    }
 
-        assert_eq!(stats.directory_count, 3);
-        assert_eq!(stats.file_count, 0);
+    #[test]
+    fn parse_path_list_decodes_utf16_with_bom() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "文件.txt\r\nb.txt".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        let list = parse_path_list(&bytes);
+        assert_eq!(list, vec!["文件.txt".to_string(), "b.txt".to_string()]);
     }
 
     #[test]
-    fn scan_max_depth_zero() {
-        let dir = setup_test_dir();
-        let mut config = Config::with_root(dir.path().to_path_buf());
-        config.scan.show_files = true;
-        config.scan.max_depth = Some(0);
+    fn relative_components_accepts_both_separators() {
+        let (parts, marked_dir) =
+            relative_components(r"src\nested/file.rs", Path::new("root")).unwrap();
+        assert_eq!(parts, vec!["src", "nested", "file.rs"]);
+        assert!(!marked_dir);
+    }
 
-        let stats = scan(&config).expect("扫描失败");
+    #[test]
+    fn relative_components_detects_trailing_separator() {
+        let (parts, marked_dir) = relative_components("docs/", Path::new("root")).unwrap();
+        assert_eq!(parts, vec!["docs"]);
+        assert!(marked_dir);
+    }
 
-        assert_eq!(stats.directory_count, 0);
-        assert_eq!(stats.file_count, 0);
+    #[test]
+    fn relative_components_resolves_dot_segments() {
+        let (parts, _) = relative_components("./a/b/../c.txt", Path::new("root")).unwrap();
+        assert_eq!(parts, vec!["a", "c.txt"]);
     }
 
     #[test]
-    fn scan_max_depth_one() {
-        let dir = setup_test_dir();
-        let mut config = Config::with_root(dir.path().to_path_buf());
-        config.scan.show_files = true;
-        config.scan.max_depth = Some(1);
+    fn relative_components_rejects_escaping_parent() {
+        let result = relative_components("../outside.txt", Path::new("root"));
+        assert!(matches!(result, Err(ScanError::PathOutsideRoot { .. })));
+    }
 
-        let stats = scan(&config).expect("扫描失败");
+    #[test]
+    fn relative_components_strips_absolute_root_case_insensitively() {
+        let (parts, _) =
+            relative_components(r"c:\PROJECT\src\lib.rs", Path::new(r"C:\Project")).unwrap();
+        assert_eq!(parts, vec!["src", "lib.rs"]);
+    }
 
-        assert_eq!(stats.directory_count, 3);
-        assert_eq!(stats.file_count, 2);
+    #[test]
+    fn relative_components_rejects_absolute_path_outside_root() {
+        let result = relative_components(r"D:\other\lib.rs", Path::new(r"C:\Project"));
+        assert!(matches!(result, Err(ScanError::PathOutsideRoot { .. })));
     }
 
     #[test]
-    fn scan_with_include() {
+    fn build_tree_from_paths_creates_intermediate_directories() {
         let dir = setup_test_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
-        config.matching.include_patterns = vec!["*.rs".to_string()];
 
-        let stats = scan(&config).expect("扫描失败");
+        let paths = vec!["src/main.rs".to_string(), "README.md".to_string()];
+        let tree = build_tree_from_paths(&paths, &config).expect("构建失败");
 
-        assert_eq!(stats.file_count, 3);
+        assert_eq!(tree.count_directories(), 1);
+        assert_eq!(tree.count_files(), 2);
+        assert!(has_node_with_name(&tree, "main.rs"));
+        assert!(!has_node_with_name(&tree, "lib.rs"), "未列出的文件不应出现");
     }
 
     #[test]
-    fn scan_with_exclude() {
+    fn build_tree_from_paths_reads_metadata_for_existing_files() {
         let dir = setup_test_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
-        config.matching.exclude_patterns = vec!["*.md".to_string()];
 
-        let stats = scan(&config).expect("扫描失败");
-
-        assert_eq!(stats.file_count, 4);
-        assert!(!has_node_with_name(&stats.tree, "README.md"));
+        let tree = build_tree_from_paths(&["README.md".to_string()], &config).expect("构建失败");
+        assert_eq!(tree.children[0].metadata.size, 6);
     }
 
     #[test]
-    fn scan_nonexistent_path() {
-        let config = Config::with_root(PathBuf::from("/nonexistent/path/12345"));
-        let result = scan(&config);
-        assert!(result.is_err());
+    fn build_tree_from_paths_keeps_missing_files() {
+        let dir = TempDir::new().unwrap();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+
+        let paths = vec!["deleted/gone.txt".to_string()];
+        let tree = build_tree_from_paths(&paths, &config).expect("构建失败");
+        assert!(has_node_with_name(&tree, "gone.txt"));
+        assert_eq!(tree.count_directories(), 1);
     }
 
     #[test]
-    fn scan_file_as_root() {
-        let dir = TempDir::new().unwrap();
-        let file_path = dir.path().join("file.txt");
-        File::create(&file_path).unwrap();
+    fn build_tree_from_paths_hides_files_without_show_files() {
+        let dir = setup_test_dir();
+        let config = Config::with_root(dir.path().to_path_buf());
 
-        let config = Config::with_root(file_path);
-        let result = scan(&config);
-        assert!(result.is_err());
+        let paths = vec!["src/main.rs".to_string(), "README.md".to_string()];
+        let tree = build_tree_from_paths(&paths, &config).expect("构建失败");
+        assert_eq!(tree.count_files(), 0);
+        assert!(has_node_with_name(&tree, "src"));
     }
 
     #[test]
-    fn scan_with_gitignore() {
-        let dir = setup_gitignore_dir();
+    fn build_tree_from_paths_applies_depth_limit() {
+        let dir = TempDir::new().unwrap();
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
-        config.scan.respect_gitignore = true;
-
-        let stats = scan(&config).expect("扫描失败");
+        config.scan.max_depth = Some(1);
 
-        assert!(!has_node_with_name(&stats.tree, "target"));
-        assert!(!has_node_with_name(&stats.tree, "app.log"));
-        assert!(has_node_with_name(&stats.tree, "Cargo.toml"));
+        let paths = vec!["a/b/c.txt".to_string(), "top.txt".to_string()];
+        let tree = build_tree_from_paths(&paths, &config).expect("构建失败");
+        assert!(has_node_with_name(&tree, "a"));
+        assert!(has_node_with_name(&tree, "top.txt"));
+        assert!(!has_node_with_name(&tree, "b"));
+        assert!(!has_node_with_name(&tree, "c.txt"));
     }
 
     #[test]
-    fn scan_without_gitignore() {
-        let dir = setup_gitignore_dir();
+    fn build_tree_from_paths_applies_include_and_exclude() {
+        let dir = TempDir::new().unwrap();
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
-        config.scan.respect_gitignore = false;
-
-        let stats = scan(&config).expect("扫描失败");
+        config.matching.include_patterns = vec!["*.rs".to_string()];
+        config.matching.exclude_patterns = vec!["target".to_string()];
 
-        assert!(has_node_with_name(&stats.tree, "target"));
-        assert!(has_node_with_name(&stats.tree, "app.log"));
+        let paths = vec![
+            "src/main.rs".to_string(),
+            "src/notes.md".to_string(),
+            "target/debug/build.rs".to_string(),
+        ];
+        let tree = build_tree_from_paths(&paths, &config).expect("构建失败");
+        assert!(has_node_with_name(&tree, "main.rs"));
+        assert!(!has_node_with_name(&tree, "notes.md"));
+        assert!(!has_node_with_name(&tree, "target"));
     }
 
     #[test]
-    fn scan_nested_gitignore_inheritance() {
-        let dir = setup_nested_gitignore_dir();
+    fn build_tree_from_paths_deduplicates_entries() {
+        let dir = TempDir::new().unwrap();
         let mut config = Config::with_root(dir.path().to_path_buf());
-        config.scan.show_files = true;
-        config.scan.respect_gitignore = true;
-
-        let stats = scan(&config).expect("扫描失败");
-
-        assert!(!has_node_with_name(&stats.tree, "root.tmp"));
-        assert!(!has_node_with_name(&stats.tree, "l1.tmp"));
-        assert!(!has_node_with_name(&stats.tree, "l2.tmp"));
-        assert!(!has_node_with_name(&stats.tree, "l3.tmp"));
-
-        assert!(!has_node_with_name(&stats.tree, "l1.bak"));
-        assert!(!has_node_with_name(&stats.tree, "l2.bak"));
-        assert!(!has_node_with_name(&stats.tree, "l3.bak"));
-
-        assert!(!has_node_with_name(&stats.tree, "l2.cache"));
-        assert!(!has_node_with_name(&stats.tree, "l3.cache"));
+        config.scan.show_files = true;
 
-        assert!(has_node_with_name(&stats.tree, "root.txt"));
-        assert!(has_node_with_name(&stats.tree, "l1.txt"));
-        assert!(has_node_with_name(&stats.tree, "l2.txt"));
-        assert!(has_node_with_name(&stats.tree, "l3.txt"));
+        let paths = vec![
+            "a/x.txt".to_string(),
+            "a/x.txt".to_string(),
+            "a/".to_string(),
+        ];
+        let tree = build_tree_from_paths(&paths, &config).expect("构建失败");
+        assert_eq!(tree.count_directories(), 1);
+        assert_eq!(tree.count_files(), 1);
     }
 
     #[test]
-    fn scan_single_thread() {
+    fn scan_uses_path_list_when_configured() {
         let dir = setup_test_dir();
+        let list_dir = TempDir::new().unwrap();
+        let list_path = list_dir.path().join("list.txt");
+        fs::write(&list_path, "tests/test.rs\nsrc/lib.rs\n").unwrap();
+
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
-        config.scan.thread_count = std::num::NonZeroUsize::new(1).unwrap();
+        config.scan.paths_from = Some(PathListSource::File(list_path));
 
         let stats = scan(&config).expect("扫描失败");
-
-        assert_eq!(stats.directory_count, 3);
-        assert_eq!(stats.file_count, 5);
+        assert_eq!(stats.directory_count, 2);
+        assert_eq!(stats.file_count, 2);
+        assert_eq!(stats.tree.to_tree().children[0].name, "src");
+        assert!(!has_node_with_name(&stats.tree.to_tree(), "empty"));
     }
 
     #[test]
-    fn scan_multi_thread() {
+    fn scan_with_smart_excludes_detected_output_only() {
         let dir = setup_test_dir();
-        let mut config = Config::with_root(dir.path().to_path_buf());
-        config.scan.show_files = true;
-        config.scan.thread_count = std::num::NonZeroUsize::new(4).unwrap();
+        fs::create_dir(dir.path().join("target")).unwrap();
+        fs::create_dir_all(dir.path().join("web").join("node_modules").join("lib")).unwrap();
+        fs::create_dir_all(dir.path().join("docs").join("node_modules")).unwrap();
+        File::create(dir.path().join("web").join("package.json")).unwrap();
 
-        let stats = scan(&config).expect("扫描失败");
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.matching.smart_excludes = true;
+        let stats = scan(&config).unwrap();
+        let tree = stats.tree.to_tree();
 
-        assert_eq!(stats.directory_count, 3);
-        assert_eq!(stats.file_count, 5);
+        assert!(
+            !tree.children.iter().any(|c| c.name == "target"),
+            "应排除 Cargo 的 target"
+        );
+        let web = tree.children.iter().find(|c| c.name == "web").unwrap();
+        assert!(web.children.is_empty(), "应排除 Node.js 的 node_modules");
+        let docs = tree.children.iter().find(|c| c.name == "docs").unwrap();
+        assert_eq!(
+            docs.children.len(),
+            1,
+            "无 package.json 时应保留 node_modules"
+        );
     }
 
     #[test]
-    fn scan_thread_count_consistency() {
-        let dir = setup_test_dir();
-
-        let mut config1 = Config::with_root(dir.path().to_path_buf());
-        config1.scan.show_files = true;
-        config1.scan.thread_count = std::num::NonZeroUsize::new(1).unwrap();
+    fn scan_with_applies_transforms_before_counting() {
+        struct DropSrc;
 
-        let mut config4 = Config::with_root(dir.path().to_path_buf());
-        config4.scan.show_files = true;
-        config4.scan.thread_count = std::num::NonZeroUsize::new(4).unwrap();
+        impl TreeTransform for DropSrc {
+            fn apply(&self, tree: &mut TreeNode) {
+                tree.children.retain(|child| child.name != "src");
+                tree.annotations.push("checked".to_string());
+            }
+        }
 
-        let mut config8 = Config::with_root(dir.path().to_path_buf());
-        config8.scan.show_files = true;
-        config8.scan.thread_count = std::num::NonZeroUsize::new(8).unwrap();
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        let plain = scan(&config).unwrap();
+        let stats = scan_with(&config, &[&DropSrc]).unwrap();
 
-        let stats1 = scan(&config1).expect("单线程扫描失败");
-        let stats4 = scan(&config4).expect("4线程扫描失败");
-        let stats8 = scan(&config8).expect("8线程扫描失败");
+        assert_eq!(
+            stats.directory_count,
+            plain.directory_count - 1,
+            "应移除 src 目录"
+        );
+        assert_eq!(
+            stats.file_count,
+            plain.file_count - 2,
+            "应移除 src 下的文件"
+        );
+        assert_eq!(stats.tree.root().annotations(), ["checked".to_string()]);
+        assert!(
+            stats
+                .tree
+                .root()
+                .children()
+                .all(|child| child.name() != "src")
+        );
+    }
 
-        assert_eq!(stats1.file_count, stats4.file_count);
-        assert_eq!(stats4.file_count, stats8.file_count);
-        assert_eq!(stats1.directory_count, stats4.directory_count);
-        assert_eq!(stats4.directory_count, stats8.directory_count);
+    #[test]
+    fn scan_path_list_reports_missing_list_file() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.paths_from = Some(PathListSource::File(dir.path().join("missing.lst")));
 
-        let names1 = collect_names(&stats1.tree);
-        let names4 = collect_names(&stats4.tree);
-        let names8 = collect_names(&stats8.tree);
-        assert_eq!(names1, names4);
-        assert_eq!(names4, names8);
+        let result = scan(&config);
+        assert!(matches!(
+            result,
+            Err(TreeppError::Scan(ScanError::PathListReadFailed { .. }))
+        ));
     }
 
     #[test]
-    fn scan_with_gitignore_thread_consistency() {
-        let dir = setup_nested_gitignore_dir();
+    fn is_hidden_returns_false_for_normal_file() {
+        let dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("normal.txt");
+        File::create(&file_path).unwrap();
 
-        let mut config1 = Config::with_root(dir.path().to_path_buf());
-        config1.scan.show_files = true;
-        config1.scan.respect_gitignore = true;
-        config1.scan.thread_count = std::num::NonZeroUsize::new(1).unwrap();
+        let meta = fs::metadata(&file_path).unwrap();
+        assert!(!is_hidden(&meta));
+    }
 
-        let mut config8 = Config::with_root(dir.path().to_path_buf());
-        config8.scan.show_files = true;
-        config8.scan.respect_gitignore = true;
-        config8.scan.thread_count = std::num::NonZeroUsize::new(8).unwrap();
+    #[test]
+    fn is_hidden_detects_hidden_attribute() {
+        use std::process::Command;
 
-        let stats1 = scan(&config1).expect("单线程扫描失败");
-        let stats8 = scan(&config8).expect("8线程扫描失败");
+        let dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("hidden.txt");
+        File::create(&file_path).unwrap();
 
-        assert_eq!(stats1.file_count, stats8.file_count);
-        assert_eq!(stats1.directory_count, stats8.directory_count);
+        // Set hidden attribute using attrib command
+        let _ = Command::new("attrib")
+            .args(["+H", file_path.to_str().unwrap()])
+            .output();
 
-        let names1 = collect_names(&stats1.tree);
-        let names8 = collect_names(&stats8.tree);
-        assert_eq!(names1, names8);
+        let meta = fs::metadata(&file_path).unwrap();
+        assert!(is_hidden(&meta));
+    }
+
+    /// Writes a version 2 git index listing `paths` into `root/.git/index`.
+    fn write_git_index(root: &Path, paths: &[&str]) {
+        let mut bytes = b"DIRC".to_vec();
+        bytes.extend_from_slice(&2u32.to_be_bytes());
+        bytes.extend_from_slice(&(paths.len() as u32).to_be_bytes());
+        for path in paths {
+            let mut entry = vec![0u8; 60];
+            entry.extend_from_slice(&(path.len() as u16).to_be_bytes());
+            entry.extend_from_slice(path.as_bytes());
+            let padded = (entry.len() + 8) / 8 * 8;
+            entry.resize(padded, 0);
+            bytes.extend_from_slice(&entry);
+        }
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".git").join("index"), bytes).unwrap();
     }
 
     #[test]
-    fn scan_streaming_basic() {
+    fn scan_git_tracked_keeps_only_indexed_entries() {
         let dir = setup_test_dir();
+        write_git_index(dir.path(), &["README.md", "src/main.rs"]);
+
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
+        config.scan.git_tracked = true;
 
-        let mut entries = Vec::new();
-        let mut enter_count = 0;
-        let mut leave_count = 0;
-
-        let stats = scan_streaming(&config, |event| {
-            match event {
-                StreamEvent::Entry(entry) => {
-                    entries.push(entry);
-                }
-                StreamEvent::EnterDir { .. } => {
-                    enter_count += 1;
-                }
-                StreamEvent::LeaveDir => {
-                    leave_count += 1;
-                }
-            }
-            Ok(())
-        })
-            .expect("流式扫描失败");
-
-        assert_eq!(stats.directory_count, 3);
-        assert_eq!(stats.file_count, 5);
-        assert_eq!(entries.len(), 8);
-        assert_eq!(enter_count, 3);
-        assert_eq!(leave_count, 3);
+        let stats = scan(&config).expect("扫描失败");
+        let names = collect_names(&stats.tree.to_tree());
+        assert!(names.contains(&"README.md".to_string()));
+        assert!(names.contains(&"main.rs".to_string()));
+        assert!(names.contains(&"src".to_string()));
+        assert!(!names.contains(&"Cargo.toml".to_string()));
+        assert!(!names.contains(&".git".to_string()));
+        assert_eq!(stats.file_count, 2);
     }
 
     #[test]
-    fn scan_streaming_dirs_only() {
+    fn scan_streaming_git_tracked_keeps_only_indexed_entries() {
         let dir = setup_test_dir();
-        let config = Config::with_root(dir.path().to_path_buf());
+        write_git_index(dir.path(), &["src/main.rs"]);
 
-        let mut entries = Vec::new();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.git_tracked = true;
 
-        let stats = scan_streaming(&config, |event| {
+        let mut names = Vec::new();
+        scan_streaming(&config, |event| {
             if let StreamEvent::Entry(entry) = event {
-                entries.push(entry);
+                names.push(entry.name);
             }
             Ok(())
         })
-            .expect("流式扫描失败");
-
-        assert_eq!(stats.directory_count, 3);
-        assert_eq!(stats.file_count, 0);
-        assert_eq!(entries.len(), 3);
-
-        for entry in &entries {
-            assert_eq!(entry.kind, EntryKind::Directory);
-        }
+        .expect("扫描失败");
+        assert_eq!(names, vec!["src".to_string(), "main.rs".to_string()]);
     }
 
     #[test]
-    fn scan_streaming_with_gitignore() {
-        let dir = setup_gitignore_dir();
+    fn scan_git_tracked_outside_repository_fails() {
+        let dir = TempDir::new().unwrap();
         let mut config = Config::with_root(dir.path().to_path_buf());
-        config.scan.show_files = true;
-        config.scan.respect_gitignore = true;
+        config.scan.git_tracked = true;
 
-        let mut names = Vec::new();
+        let result = scan(&config);
+        assert!(matches!(
+            result,
+            Err(TreeppError::Scan(ScanError::GitIndexFailed { .. }))
+        ));
+    }
 
-        let _stats = scan_streaming(&config, |event| {
+    /// Creates a tree with sibling directories sharing a name prefix.
+    fn setup_prune_dir() -> TempDir {
+        let dir = TempDir::new().expect("创建临时目录失败");
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("src2/nested/deeper")).unwrap();
+        fs::create_dir_all(root.join("docs/only_dirs/inner")).unwrap();
+        File::create(root.join("src/main.rs")).unwrap();
+        File::create(root.join("src2/nested/deeper/notes.txt")).unwrap();
+        File::create(root.join("docs/guide.md")).unwrap();
+        dir
+    }
+
+    fn collect_stream_names(config: &Config) -> Vec<String> {
+        let mut names = Vec::new();
+        scan_streaming(config, |event| {
             if let StreamEvent::Entry(entry) = event {
-                names.push(entry.name.clone());
+                names.push(entry.name);
             }
             Ok(())
         })
-            .expect("流式扫描失败");
+        .expect("扫描失败");
+        names
+    }
 
-        assert!(!names.contains(&"target".to_string()));
-        assert!(!names.contains(&"app.log".to_string()));
+    #[test]
+    fn tree_node_prune_removes_nested_only_directories() {
+        let leaf = TreeNode::new(
+            PathBuf::from("a/b/c"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let mid = TreeNode::with_children(
+            PathBuf::from("a/b"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+            vec![leaf],
+        );
+        let file = TreeNode::new(
+            PathBuf::from("x.rs"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        );
+        let mut root = TreeNode::with_children(
+            PathBuf::from("."),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+            vec![mid, file],
+        );
+
+        assert!(root.prune_empty_directories());
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(root.children[0].name, "x.rs");
     }
 
     #[test]
-    fn scan_streaming_depth_info() {
-        let dir = setup_test_dir();
+    fn tree_node_remove_files_keeps_directories() {
+        let file = TreeNode::new(
+            PathBuf::from("s/a.rs"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        );
+        let sub = TreeNode::with_children(
+            PathBuf::from("s"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+            vec![file],
+        );
+        let mut root = TreeNode::with_children(
+            PathBuf::from("."),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+            vec![sub],
+        );
+        root.remove_files();
+        assert_eq!(root.count_files(), 0);
+        assert_eq!(root.count_directories(), 1);
+    }
+
+    #[test]
+    fn scan_prune_keeps_prefix_sibling_directories_by_content() {
+        let dir = setup_prune_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
+        config.matching.include_patterns.push("*.txt".to_string());
+        config.matching.prune_empty = true;
 
-        let mut entries = Vec::new();
+        let stats = scan(&config).expect("扫描失败");
+        let names = collect_names(&stats.tree.to_tree());
+        assert!(names.contains(&"src2".to_string()));
+        assert!(names.contains(&"deeper".to_string()));
+        assert!(
+            !names.contains(&"src".to_string()),
+            "src 仅含被过滤文件，应被修剪"
+        );
+        assert!(!names.contains(&"docs".to_string()));
+        assert_eq!(stats.directory_count, 3);
+        assert_eq!(stats.file_count, 1);
+    }
 
-        let _stats = scan_streaming(&config, |event| {
-            if let StreamEvent::Entry(entry) = event {
-                entries.push((entry.name.clone(), entry.depth));
-            }
-            Ok(())
-        })
-            .expect("流式扫描失败");
+    #[test]
+    fn scan_prune_without_files_keeps_dirs_with_hidden_files() {
+        let dir = setup_prune_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.matching.prune_empty = true;
 
-        let root_entries: Vec<_> = entries.iter().filter(|(_, d)| *d == 0).collect();
-        assert!(!root_entries.is_empty());
+        let stats = scan(&config).expect("扫描失败");
+        let names = collect_names(&stats.tree.to_tree());
+        assert!(names.contains(&"src".to_string()));
+        assert!(names.contains(&"docs".to_string()));
+        assert!(!names.contains(&"only_dirs".to_string()));
+        assert!(
+            !names.contains(&"main.rs".to_string()),
+            "未指定 /F 时不应保留文件"
+        );
+        assert_eq!(stats.file_count, 0);
+    }
 
-        let nested_entries: Vec<_> = entries.iter().filter(|(_, d)| *d == 1).collect();
-        assert!(!nested_entries.is_empty());
+    #[test]
+    fn scan_prune_with_depth_limit_judges_by_full_content() {
+        let dir = setup_prune_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.max_depth = Some(1);
+        config.matching.prune_empty = true;
+
+        let stats = scan(&config).expect("扫描失败");
+        let top: Vec<_> = stats
+            .tree
+            .to_tree()
+            .children
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+        assert!(top.contains(&"src2".to_string()), "深层含文件的目录应保留");
     }
 
     #[test]
-    fn scan_streaming_is_last_flag() {
-        let dir = TempDir::new().unwrap();
+    fn scan_prune_empty_files_then_empty_directories() {
+        let dir = TempDir::new().expect("创建临时目录失败");
         let root = dir.path();
-
-        File::create(root.join("a.txt")).unwrap();
-        File::create(root.join("b.txt")).unwrap();
-        File::create(root.join("c.txt")).unwrap();
+        fs::create_dir(root.join("logs")).unwrap();
+        File::create(root.join("blank.txt")).unwrap();
+        File::create(root.join("logs/empty.log")).unwrap();
+        fs::write(root.join("data.txt"), b"data").unwrap();
 
         let mut config = Config::with_root(root.to_path_buf());
         config.scan.show_files = true;
+        config.matching.prune_empty_files = true;
+        let stats = scan(&config).expect("扫描失败");
+        let names = collect_names(&stats.tree.to_tree());
+        assert!(names.contains(&"data.txt".to_string()));
+        assert!(
+            !names.contains(&"blank.txt".to_string()),
+            "零字节文件应被省略"
+        );
+        assert!(
+            names.contains(&"logs".to_string()),
+            "未指定 /P 时应保留目录"
+        );
+        assert_eq!(stats.file_count, 1);
 
-        let mut entries = Vec::new();
-
-        let _stats = scan_streaming(&config, |event| {
-            if let StreamEvent::Entry(entry) = event {
-                entries.push((entry.name.clone(), entry.is_last));
-            }
-            Ok(())
-        })
-            .expect("流式扫描失败");
-
-        assert_eq!(entries.len(), 3);
-
-        let last_count = entries.iter().filter(|(_, is_last)| *is_last).count();
-        assert_eq!(last_count, 1);
+        config.matching.prune_empty = true;
+        let stats = scan(&config).expect("扫描失败");
+        let names = collect_names(&stats.tree.to_tree());
+        assert!(
+            !names.contains(&"logs".to_string()),
+            "仅含空文件的目录应被修剪"
+        );
+        let streamed = scan_streaming(&config, |_| Ok(())).expect("流式扫描失败");
+        assert_eq!(streamed.directory_count, stats.directory_count);
+        assert_eq!(streamed.file_count, 1);
     }
 
     #[test]
-    fn scan_streaming_nonexistent_path() {
-        let config = Config::with_root(PathBuf::from("/nonexistent/path/12345"));
+    fn streaming_prune_matches_batch() {
+        let dir = setup_prune_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.matching.include_patterns.push("*.txt".to_string());
+        config.matching.prune_empty = true;
 
-        let result = scan_streaming(&config, |_| Ok(()));
-        assert!(result.is_err());
+        let names = collect_stream_names(&config);
+        assert_eq!(names, vec!["src2", "nested", "deeper", "notes.txt"]);
     }
 
     #[test]
-    fn scan_streaming_max_depth() {
-        let dir = setup_test_dir();
+    fn streaming_prune_sets_is_last_on_last_surviving_directory() {
+        let dir = setup_prune_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
-        config.scan.show_files = true;
-        config.scan.max_depth = Some(1);
-
-        let mut entries = Vec::new();
+        config.matching.prune_empty = true;
 
+        let mut top_level = Vec::new();
         let stats = scan_streaming(&config, |event| {
-            if let StreamEvent::Entry(entry) = event {
-                entries.push(entry);
+            if let StreamEvent::Entry(entry) = event
+                && entry.depth == 0
+            {
+                top_level.push((entry.name, entry.is_last));
             }
             Ok(())
         })
-            .expect("流式扫描失败");
+        .expect("扫描失败");
 
-        assert_eq!(stats.directory_count, 3);
-        assert_eq!(stats.file_count, 2);
+        assert_eq!(
+            top_level,
+            vec![
+                ("docs".to_string(), false),
+                ("src".to_string(), false),
+                ("src2".to_string(), true),
+            ]
+        );
+        assert_eq!(stats.file_count, 0);
+        assert_eq!(stats.directory_count, 5);
     }
 
     #[test]
-    fn scan_streaming_callback_error_propagation() {
-        let dir = setup_test_dir();
+    fn streaming_prune_with_depth_limit_checks_hidden_content() {
+        let dir = setup_prune_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.max_depth = Some(1);
+        config.matching.include_patterns.push("*.txt".to_string());
         config.scan.show_files = true;
+        config.matching.prune_empty = true;
 
-        let mut count = 0;
-        let result = scan_streaming(&config, |event| {
-            if let StreamEvent::Entry(_) = event {
-                count += 1;
-                if count >= 2 {
-                    return Err(ScanError::WalkError {
-                        message: "test error".to_string(),
-                        path: None,
-                    });
-                }
-            }
-            Ok(())
-        });
-
-        assert!(result.is_err());
+        let names = collect_stream_names(&config);
+        assert_eq!(names, vec!["src2"]);
     }
 
     #[test]
-    fn streaming_vs_batch_entry_names() {
-        let dir = setup_test_dir();
+    fn explain_reports_gitignore_source_and_line() {
+        let dir = setup_gitignore_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
+        config.scan.respect_gitignore = true;
 
-        let batch_stats = scan(&config).expect("批处理扫描失败");
-        let batch_names = collect_names(&batch_stats.tree);
-
-        let mut stream_names = Vec::new();
-        let _stream_stats = scan_streaming(&config, |event| {
-            if let StreamEvent::Entry(entry) = event {
-                stream_names.push(entry.name.clone());
+        let explanation = explain_path(&config, Path::new("app.log")).expect("解释失败");
+        match explanation.reason {
+            Some(FilterReason::Gitignored {
+                source: Some(source),
+                line: Some(line),
+                pattern,
+            }) => {
+                assert!(source.ends_with(".gitignore"));
+                assert_eq!(pattern, "*.log");
+                assert_eq!(line, 2);
             }
-            Ok(())
-        })
-            .expect("流式扫描失败");
-        stream_names.sort();
+            other => panic!("意外的原因: {:?}", other),
+        }
+    }
 
-        let batch_without_root: Vec<_> = batch_names
-            .into_iter()
-            .filter(|n| n != &batch_stats.tree.name)
-            .collect();
+    #[test]
+    fn explain_reports_excluded_ancestor() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.matching.exclude_patterns.push("src".to_string());
 
-        assert_eq!(stream_names, batch_without_root);
+        let explanation = explain_path(&config, Path::new("src/main.rs")).expect("解释失败");
+        assert_eq!(
+            explanation.reason,
+            Some(FilterReason::Excluded {
+                pattern: "src".to_string()
+            })
+        );
+        assert_eq!(explanation.decided_at, dir.path().join("src"));
+        assert_eq!(explanation.path, dir.path().join("src").join("main.rs"));
     }
 
     #[test]
-    fn streaming_vs_batch_counts() {
+    fn explain_reports_include_files_depth_and_prune() {
         let dir = setup_test_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
+
+        let reason =
+            |config: &Config, path: &str| explain_path(config, Path::new(path)).unwrap().reason;
+        assert_eq!(
+            reason(&config, "README.md"),
+            Some(FilterReason::FilesNotShown)
+        );
+        assert_eq!(reason(&config, "src"), None);
+
         config.scan.show_files = true;
+        config.matching.include_patterns.push("*.rs".to_string());
+        assert_eq!(
+            reason(&config, "README.md"),
+            Some(FilterReason::NotIncluded)
+        );
+        assert_eq!(reason(&config, "src/lib.rs"), None);
 
-        let batch_stats = scan(&config).expect("批处理扫描失败");
-        let stream_stats = scan_streaming(&config, |_| Ok(())).expect("流式扫描失败");
+        config.scan.max_depth = Some(1);
+        assert_eq!(
+            reason(&config, "src/lib.rs"),
+            Some(FilterReason::BeyondDepth { max_depth: 1 })
+        );
 
-        assert_eq!(batch_stats.directory_count, stream_stats.directory_count);
-        assert_eq!(batch_stats.file_count, stream_stats.file_count);
+        config.scan.max_depth = None;
+        config.matching.prune_empty = true;
+        assert_eq!(reason(&config, "empty"), Some(FilterReason::Pruned));
+        assert_eq!(reason(&config, "src"), None);
     }
 
     #[test]
-    fn scan_empty_directory() {
-        let dir = TempDir::new().unwrap();
+    fn explain_rejects_missing_and_outside_paths() {
+        let dir = setup_test_dir();
+        let config = Config::with_root(dir.path().to_path_buf());
+
+        assert!(matches!(
+            explain_path(&config, Path::new("nope.txt")),
+            Err(TreeppError::Scan(ScanError::PathNotFound { .. }))
+        ));
+        assert!(matches!(
+            explain_path(&config, Path::new("../outside")),
+            Err(TreeppError::Scan(ScanError::PathOutsideRoot { .. }))
+        ));
+    }
+
+    #[test]
+    fn scan_show_filtered_keeps_annotated_entries() {
+        let dir = setup_gitignore_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
+        config.scan.respect_gitignore = true;
+        config.scan.show_filtered = true;
+        config.matching.exclude_patterns.push("tests".to_string());
 
         let stats = scan(&config).expect("扫描失败");
+        let find = |name: &str| {
+            stats
+                .tree
+                .to_tree()
+                .children
+                .iter()
+                .find(|c| c.name == name)
+                .cloned()
+        };
 
-        assert_eq!(stats.directory_count, 0);
-        assert_eq!(stats.file_count, 0);
+        let target = find("target").expect("target 应被保留并标注");
+        assert!(matches!(
+            target.filtered,
+            Some(FilterReason::Gitignored { line: Some(1), .. })
+        ));
+        assert!(target.children.is_empty(), "被过滤的目录不应展开");
+        let log = find("app.log").expect("app.log 应被保留并标注");
+        assert!(log.filtered.is_some());
+        let tests = find("tests").expect("tests 应被保留并标注");
+        assert_eq!(
+            tests.filtered,
+            Some(FilterReason::Excluded {
+                pattern: "tests".to_string()
+            })
+        );
+        assert!(find("src").unwrap().filtered.is_none());
+
+        // Annotated entries are not counted
+        assert!(!collect_names(&stats.tree.to_tree()).contains(&"debug".to_string()));
+        assert_eq!(stats.directory_count, 2);
     }
 
     #[test]
-    fn scan_single_file_directory() {
-        let dir = TempDir::new().unwrap();
-        File::create(dir.path().join("only.txt")).unwrap();
+    fn scan_show_filtered_skips_files_without_files_flag() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_filtered = true;
+        config.matching.include_patterns.push("*.md".to_string());
+
+        let stats = scan(&config).expect("扫描失败");
+        assert_eq!(count_files_in_tree(&stats.tree.to_tree()), 0);
+    }
 
+    #[test]
+    fn streaming_show_filtered_matches_batch() {
+        let dir = setup_gitignore_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
+        config.scan.respect_gitignore = true;
+        config.scan.show_filtered = true;
 
-        let stats = scan(&config).expect("扫描失败");
+        let mut annotated = Vec::new();
+        let stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event
+                && entry.filtered.is_some()
+            {
+                annotated.push(entry.name);
+            }
+            Ok(())
+        })
+        .expect("扫描失败");
 
-        assert_eq!(stats.file_count, 1);
+        annotated.sort();
+        assert_eq!(annotated, vec!["app.log".to_string(), "target".to_string()]);
+        let batch = scan(&config).expect("扫描失败");
+        assert_eq!(stats.file_count, batch.file_count);
+        assert_eq!(stats.directory_count, batch.directory_count);
     }
 
     #[test]
-    fn scan_deeply_nested() {
-        let dir = TempDir::new().unwrap();
-        let root = dir.path();
+    fn prune_keeps_annotated_entries_in_surviving_directories() {
+        let filtered_file = {
+            let mut node = TreeNode::new(
+                PathBuf::from("d/x.log"),
+                EntryKind::File,
+                EntryMetadata::default(),
+            );
+            node.filtered = Some(FilterReason::NotIncluded);
+            node
+        };
+        let kept = TreeNode::new(
+            PathBuf::from("d/a.rs"),
+            EntryKind::File,
+            EntryMetadata::default(),
+        );
+        let only_filtered = TreeNode::with_children(
+            PathBuf::from("e"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+            vec![filtered_file.clone()],
+        );
+        let mixed = TreeNode::with_children(
+            PathBuf::from("d"),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+            vec![filtered_file, kept],
+        );
+        let mut root = TreeNode::with_children(
+            PathBuf::from("."),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+            vec![mixed, only_filtered],
+        );
 
-        let mut current = root.to_path_buf();
-        for i in 0..5 {
-            current = current.join(format!("level{}", i));
-            fs::create_dir(&current).unwrap();
-        }
-        File::create(current.join("deep.txt")).unwrap();
+        root.prune_empty_directories();
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(root.children[0].children.len(), 2);
+        assert_eq!(root.count_files(), 1);
+    }
 
-        let mut config = Config::with_root(root.to_path_buf());
+    #[test]
+    fn scan_max_entries_truncates_each_directory() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
+        config.scan.max_entries = Some(1);
 
         let stats = scan(&config).expect("扫描失败");
-
-        assert_eq!(stats.directory_count, 5);
+        let names: Vec<_> = stats
+            .tree
+            .to_tree()
+            .children
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+        assert_eq!(names, vec!["Cargo.toml".to_string()]);
+        assert_eq!(stats.tree.to_tree().omitted, 4);
+        assert_eq!(stats.tree.count_truncated(), 1);
         assert_eq!(stats.file_count, 1);
-        assert!(has_node_with_name(&stats.tree, "deep.txt"));
+        assert_eq!(stats.directory_count, 0);
     }
 
     #[test]
-    fn scan_stats_duration_is_measured() {
+    fn scan_max_entries_ignores_hidden_files_without_files_flag() {
         let dir = setup_test_dir();
-        let config = Config::with_root(dir.path().to_path_buf());
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.max_entries = Some(2);
 
         let stats = scan(&config).expect("扫描失败");
+        let names: Vec<_> = stats
+            .tree
+            .to_tree()
+            .children
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+        assert_eq!(names, vec!["empty".to_string(), "src".to_string()]);
+        assert_eq!(stats.tree.to_tree().omitted, 1);
+        assert_eq!(stats.directory_count, 2);
+    }
 
-        assert!(stats.duration.as_nanos() > 0);
+    #[test]
+    fn streaming_max_entries_emits_omitted_event() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.max_entries = Some(2);
+
+        let mut names = Vec::new();
+        let mut omitted = Vec::new();
+        let stats = scan_streaming(&config, |event| {
+            match event {
+                StreamEvent::Entry(entry) => names.push((entry.name, entry.is_last)),
+                StreamEvent::Omitted { depth, count } => omitted.push((depth, count)),
+                _ => {}
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+
+        assert_eq!(
+            names,
+            vec![("empty".to_string(), false), ("src".to_string(), false)]
+        );
+        assert_eq!(omitted, vec![(0, 1)]);
+        assert_eq!(stats.truncated_dirs, 1);
+        assert_eq!(stats.directory_count, 2);
+        assert!(!stats.output_truncated);
     }
 
     #[test]
-    fn scan_stats_counts_match_tree() {
+    fn streaming_stops_when_output_limit_reached() {
         let dir = setup_test_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
 
-        let stats = scan(&config).expect("扫描失败");
+        let mut accepted = 0;
+        let stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(_) = event {
+                if accepted == 3 {
+                    return Err(ScanError::OutputLimitReached { limit: 64 });
+                }
+                accepted += 1;
+            }
+            Ok(())
+        })
+        .expect("输出上限不应视为错误");
 
-        assert_eq!(stats.directory_count, stats.tree.count_directories());
-        assert_eq!(stats.file_count, stats.tree.count_files());
+        assert!(stats.output_truncated);
+        assert_eq!(stats.file_count + stats.directory_count, 3);
     }
 
     #[test]
-    fn stream_stats_duration_is_measured() {
+    fn streaming_stops_quietly_when_output_closed() {
         let dir = setup_test_dir();
-        let config = Config::with_root(dir.path().to_path_buf());
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
 
-        let stats = scan_streaming(&config, |_| Ok(())).expect("扫描失败");
+        let mut accepted = 0;
+        let stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(_) = event {
+                if accepted == 2 {
+                    return Err(ScanError::OutputClosed);
+                }
+                accepted += 1;
+            }
+            Ok(())
+        })
+        .expect("输出被关闭不应视为错误");
 
-        assert!(stats.duration.as_nanos() > 0);
+        assert!(!stats.output_truncated, "读取方关闭输出不是截断");
+        assert_eq!(stats.file_count + stats.directory_count, 2);
     }
 
     #[test]
-    fn stream_entry_new_fields() {
-        let entry = StreamEntry {
-            path: PathBuf::from("test.txt"),
-            name: "test.txt".to_string(),
-            kind: EntryKind::File,
-            metadata: EntryMetadata::default(),
-            depth: 0,
-            is_last: true,
-            is_file: true,
-            has_more_dirs: false,
-        };
+    fn scan_filelimit_collapses_large_directories() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.file_limit = Some(1);
 
-        assert!(entry.is_file);
-        assert!(!entry.has_more_dirs);
+        let stats = scan(&config).expect("扫描失败");
+        let tree = stats.tree.to_tree();
+        let find = |name: &str| tree.children.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(find("src").collapsed, Some(2));
+        assert!(find("src").children.is_empty());
+        assert_eq!(find("tests").collapsed, None);
+        assert_eq!(tree.collapsed, None, "根目录不应被折叠");
+        assert_eq!(stats.directory_count, 3);
+        assert_eq!(stats.file_count, 3);
     }
 
     #[test]
-    fn scan_collects_files_for_disk_usage_even_without_show_files() {
+    fn scan_filelimit_keeps_disk_usage_of_collapsed_directories() {
         let dir = setup_test_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
-        config.batch_mode = true;
-        config.scan.show_files = false;
+        config.scan.show_files = true;
+        config.scan.file_limit = Some(1);
         config.render.show_disk_usage = true;
 
         let stats = scan(&config).expect("扫描失败");
-
-        assert!(stats.tree.disk_usage.is_some());
-        assert!(stats.tree.disk_usage.unwrap() > 0);
+        let tree = stats.tree.to_tree();
+        let src = tree.children.iter().find(|c| c.name == "src").unwrap();
+        assert!(src.children.is_empty());
+        assert_eq!(src.disk_usage, Some(27));
+        assert_eq!(stats.file_count, 3);
     }
 
     #[test]
-    fn scan_disk_usage_correct_without_show_files() {
-        let dir = TempDir::new().unwrap();
-        let root = dir.path();
+    fn streaming_filelimit_matches_batch() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.file_limit = Some(1);
 
-        fs::create_dir(root.join("subdir")).unwrap();
-        File::create(root.join("file1.txt"))
-            .unwrap()
-            .write_all(b"12345")
-            .unwrap();
-        File::create(root.join("subdir/file2.txt"))
-            .unwrap()
-            .write_all(b"1234567890")
-            .unwrap();
+        let mut collapsed = Vec::new();
+        let mut names = Vec::new();
+        let stats = scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event {
+                if let Some(count) = entry.collapsed {
+                    collapsed.push((entry.name.clone(), count));
+                }
+                names.push(entry.name);
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
 
-        let mut config = Config::with_root(root.to_path_buf());
-        config.batch_mode = true;
-        config.scan.show_files = false;
-        config.render.show_disk_usage = true;
+        assert_eq!(collapsed, vec![("src".to_string(), 2)]);
+        assert!(!names.contains(&"main.rs".to_string()));
+        let batch = scan(&config).expect("扫描失败");
+        assert_eq!(stats.directory_count, batch.directory_count);
+        assert_eq!(stats.file_count, batch.file_count);
+    }
+
+    #[test]
+    fn prune_keeps_collapsed_directories() {
+        let dir = setup_test_dir();
+        fs::create_dir(dir.path().join("empty/a")).unwrap();
+        fs::create_dir(dir.path().join("empty/b")).unwrap();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.matching.prune_empty = true;
+        config.scan.file_limit = Some(1);
 
         let stats = scan(&config).expect("扫描失败");
+        let tree = stats.tree.to_tree();
+        let empty = tree.children.iter().find(|c| c.name == "empty");
+        assert_eq!(empty.and_then(|e| e.collapsed), Some(2));
 
-        assert_eq!(stats.tree.disk_usage, Some(15));
+        let mut streamed = Vec::new();
+        scan_streaming(&config, |event| {
+            if let StreamEvent::Entry(entry) = event {
+                streamed.push(entry.name);
+            }
+            Ok(())
+        })
+        .expect("流式扫描失败");
+        assert!(streamed.contains(&"empty".to_string()));
     }
 
     #[test]
-    fn scan_no_files_collected_when_neither_show_files_nor_disk_usage() {
+    fn scan_without_entry_metadata_lists_same_entries() {
         let dir = setup_test_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
-        config.scan.show_files = false;
-        config.render.show_disk_usage = false;
+        config.scan.show_files = true;
+        config.scan.show_hidden = true;
+        assert!(!config.needs_entry_metadata());
 
-        let stats = scan(&config).expect("扫描失败");
+        let lean = scan(&config).expect("扫描失败");
+        config.render.show_size = true;
+        let full = scan(&config).expect("扫描失败");
 
-        assert_eq!(stats.file_count, 0);
-        assert_eq!(count_files_in_tree(&stats.tree), 0);
+        assert_eq!(
+            collect_names(&lean.tree.to_tree()),
+            collect_names(&full.tree.to_tree())
+        );
+        assert_eq!(lean.file_count, full.file_count);
+        let tree = lean.tree.to_tree();
+        let readme = tree
+            .children
+            .iter()
+            .find(|c| c.name == "README.md")
+            .unwrap();
+        assert_eq!(readme.metadata.size, 0, "未请求大小时不应读取元数据");
     }
 
     #[test]
-    fn scan_context_collect_files_for_size_enabled_when_disk_usage() {
-        let mut config = Config::default();
-        config.batch_mode = true;
-        config.render.show_disk_usage = true;
-        config.scan.show_files = false;
+    fn streaming_without_entry_metadata_lists_same_entries() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.show_hidden = true;
 
-        let ctx = ScanContext::from_config(&config).unwrap();
+        let collect = |config: &Config| {
+            let mut entries = Vec::new();
+            scan_streaming(config, |event| {
+                if let StreamEvent::Entry(entry) = event {
+                    entries.push((entry.name, entry.kind));
+                }
+                Ok(())
+            })
+            .expect("流式扫描失败");
+            entries
+        };
 
-        assert!(!ctx.show_files);
-        assert!(ctx.collect_files_for_size);
+        let lean = collect(&config);
+        config.render.show_date = true;
+        assert_eq!(lean, collect(&config));
+        assert!(lean.contains(&("src".to_string(), EntryKind::Directory)));
     }
 
     #[test]
-    fn scan_context_collect_files_for_size_disabled_by_default() {
-        let config = Config::default();
+    fn cached_scan_lists_same_entries_and_sees_changes() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.cache = CacheMode::Enabled;
 
-        let ctx = ScanContext::from_config(&config).unwrap();
+        let cold = scan(&config).expect("扫描失败");
+        let warm = scan(&config).expect("扫描失败");
+        assert_eq!(
+            collect_names(&cold.tree.to_tree()),
+            collect_names(&warm.tree.to_tree())
+        );
+        assert_eq!(cold.file_count, warm.file_count);
 
-        assert!(!ctx.show_files);
-        assert!(!ctx.collect_files_for_size);
+        File::create(dir.path().join("src").join("added.rs")).unwrap();
+        let changed = scan(&config).expect("扫描失败");
+        assert!(
+            has_node_with_name(&changed.tree.to_tree(), "added.rs"),
+            "目录修改后应重新读取"
+        );
+        assert_eq!(changed.file_count, cold.file_count + 1);
+
+        cache::remove(&config.root_path).unwrap();
     }
 
     #[test]
-    fn should_filter_includes_files_when_collect_for_size() {
-        let mut config = Config::default();
-        config.batch_mode = true;
-        config.render.show_disk_usage = true;
-        config.scan.show_files = false;
+    fn cached_streaming_lists_same_entries() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.scan.cache = CacheMode::Enabled;
 
-        let ctx = ScanContext::from_config(&config).unwrap();
+        let collect = |config: &Config| {
+            let mut entries = Vec::new();
+            scan_streaming(config, |event| {
+                if let StreamEvent::Entry(entry) = event {
+                    entries.push((entry.name, entry.kind));
+                }
+                Ok(())
+            })
+            .expect("流式扫描失败");
+            entries
+        };
+
+        let cold = collect(&config);
+        assert!(
+            cache::cache_file_path(&config.root_path).exists(),
+            "应写入缓存文件"
+        );
+        assert_eq!(cold, collect(&config));
 
-        assert!(!ctx.should_filter("test.txt", false, None));
+        config.scan.cache = CacheMode::Discard;
+        assert_eq!(cold, collect(&config));
+        assert!(
+            !cache::cache_file_path(&config.root_path).exists(),
+            "--no-cache 应删除缓存"
+        );
     }
 
     #[test]
-    fn should_filter_excludes_files_when_no_show_no_collect() {
-        let config = Config::default();
+    fn list_dir_records_unreadable_directory() {
+        let dir = TempDir::new().unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
 
-        let ctx = ScanContext::from_config(&config).unwrap();
+        let mut ctx =
+            ScanContext::from_config(&Config::with_root(dir.path().to_path_buf())).unwrap();
+        ctx.fs = Arc::new(FaultyFileSystem::default().with(&locked, Fault::Denied));
+        assert_eq!(
+            list_dir(&locked, &ctx).err(),
+            Some(EntryError::AccessDenied)
+        );
+        assert!(list_dir(&locked, &ctx).is_err());
 
-        assert!(ctx.should_filter("test.txt", false, None));
+        assert_eq!(ctx.take_unreadable(), vec![locked], "重复读取应只记录一次");
+        assert!(ctx.take_unreadable().is_empty());
     }
 
     #[test]
-    fn scan_disk_usage_with_max_depth_collects_full_size() {
+    fn list_dir_records_vanished_directory() {
         let dir = TempDir::new().unwrap();
-        let root = dir.path();
-        fs::create_dir_all(root.join("a/b")).unwrap();
-        File::create(root.join("a/b/deep.txt"))
-            .unwrap()
-            .write_all(b"1234567")
-            .unwrap();
-
-        let mut config = Config::with_root(root.to_path_buf());
-        config.batch_mode = true;
-        config.render.show_disk_usage = true;
-        config.scan.show_files = false;
-        config.scan.max_depth = Some(1);
+        let missing = dir.path().join("missing");
 
-        let stats = scan(&config).expect("扫描失败");
+        let ctx = ScanContext::from_config(&Config::with_root(dir.path().to_path_buf())).unwrap();
+        assert_eq!(list_dir(&missing, &ctx).err(), Some(EntryError::Vanished));
+        assert!(list_dir(&missing, &ctx).is_err());
 
-        assert_eq!(stats.tree.disk_usage, Some(7));
-        let dir_a = stats.tree.children.iter().find(|c| c.name == "a").unwrap();
-        assert_eq!(dir_a.disk_usage, Some(7));
+        assert_eq!(ctx.vanished_count(), 1, "重复读取应只计数一次");
+        assert!(
+            ctx.take_unreadable().is_empty(),
+            "已删除的目录不应算作无法读取"
+        );
     }
 
     #[test]
-    fn scan_context_from_config_with_all_options() {
-        let mut config = Config::default();
+    fn scan_of_readable_tree_reports_no_unreadable_dirs() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
-        config.scan.max_depth = Some(5);
-        config.scan.respect_gitignore = true;
-        config.render.reverse_sort = true;
-        config.matching.include_patterns = vec!["*.rs".to_string()];
-        config.matching.exclude_patterns = vec!["test_*".to_string()];
 
-        let ctx = ScanContext::from_config(&config).unwrap();
+        let stats = scan(&config).expect("扫描失败");
+        assert!(stats.unreadable_dirs.is_empty());
 
-        assert!(ctx.show_files);
-        assert_eq!(ctx.max_depth, Some(5));
-        assert!(ctx.respect_gitignore);
-        assert!(ctx.reverse);
+        let stats = scan_streaming(&config, |_| Ok(())).expect("流式扫描失败");
+        assert!(stats.unreadable_dirs.is_empty());
     }
 
     #[test]
-    fn scan_context_should_filter_respects_exclude_over_include() {
-        let mut config = Config::default();
-        config.scan.show_files = true;
-        config.matching.include_patterns = vec!["*.rs".to_string()];
-        config.matching.exclude_patterns = vec!["test_*.rs".to_string()];
+    fn list_dir_reads_on_worker_thread_within_read_timeout() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        let direct = ScanContext::from_config(&config).unwrap();
+        config.scan.read_timeout = Some(Duration::from_secs(30));
+        let timed = ScanContext::from_config(&config).unwrap();
+
+        let names = |ctx: &ScanContext| {
+            let mut entries = list_dir(dir.path(), ctx).unwrap();
+            sort_entries(&mut entries, ctx.sort_order());
+            entries.into_iter().map(|e| e.path).collect::<Vec<_>>()
+        };
+        assert_eq!(names(&direct), names(&timed));
+    }
 
+    #[test]
+    fn list_dir_times_out_after_scan_deadline() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.timeout = Some(Duration::from_millis(1));
         let ctx = ScanContext::from_config(&config).unwrap();
+        thread::sleep(Duration::from_millis(5));
 
-        assert!(!ctx.should_filter("main.rs", false, None));
-        assert!(ctx.should_filter("test_main.rs", false, None));
+        assert_eq!(ctx.read_limit(), Some(Duration::ZERO));
+        assert_eq!(list_dir(dir.path(), &ctx).err(), Some(EntryError::TimedOut));
+        assert_eq!(
+            ctx.take_unreadable(),
+            vec![dir.path().to_path_buf()],
+            "超时的目录应记为无法读取"
+        );
     }
 
     #[test]
-    fn scan_with_multiple_exclude_patterns() {
+    fn list_dir_stops_once_entry_budget_is_spent() {
         let dir = setup_test_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
-        config.scan.show_files = true;
-        config.matching.exclude_patterns = vec!["*.md".to_string(), "*.toml".to_string()];
-
-        let stats = scan(&config).expect("扫描失败");
+        config.scan.budget_entries = Some(1);
+        let ctx = ScanContext::from_config(&config).unwrap();
 
-        assert!(!has_node_with_name(&stats.tree, "README.md"));
-        assert!(!has_node_with_name(&stats.tree, "Cargo.toml"));
-        assert!(has_node_with_name(&stats.tree, "main.rs"));
+        assert_eq!(
+            list_dir(dir.path(), &ctx).unwrap().len(),
+            5,
+            "根目录应始终被读取"
+        );
+        let src = dir.path().join("src");
+        assert_eq!(
+            list_dir(&src, &ctx).err(),
+            Some(EntryError::BudgetExhausted)
+        );
+        assert!(
+            ctx.take_unreadable().is_empty(),
+            "预算用完的目录不应记为无法读取"
+        );
     }
 
     #[test]
-    fn scan_streaming_with_exclude() {
+    fn scan_marks_directories_left_unread_by_budget() {
         let dir = setup_test_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
-        config.matching.exclude_patterns = vec!["*.md".to_string()];
+        config.scan.budget_entries = Some(1);
 
-        let mut names = Vec::new();
-        let _stats = scan_streaming(&config, |event| {
-            if let StreamEvent::Entry(entry) = event {
-                names.push(entry.name.clone());
-            }
-            Ok(())
-        })
-            .expect("流式扫描失败");
+        let stats = scan(&config).expect("扫描失败");
+        assert_eq!(stats.tree.count_unread(), 3);
+        assert_eq!(stats.directory_count, 3, "未读取的目录仍应列出");
+        assert_eq!(stats.file_count, 2);
+        assert!(stats.unreadable_dirs.is_empty());
 
-        assert!(!names.contains(&"README.md".to_string()));
+        let streamed = scan_streaming(&config, |_| Ok(())).expect("流式扫描失败");
+        assert_eq!(streamed.unread_dirs, 3);
+        assert_eq!(streamed.file_count, 2);
+
+        config.scan.budget_entries = Some(1_000);
+        assert_eq!(scan(&config).expect("扫描失败").tree.count_unread(), 0);
     }
 
     #[test]
-    fn tree_node_clone() {
-        let mut original = TreeNode::new(
-            PathBuf::from("test"),
-            EntryKind::Directory,
-            EntryMetadata {
-                size: 0,
-                ..Default::default()
-            },
-        );
-        original.children.push(TreeNode::new(
-            PathBuf::from("child.txt"),
-            EntryKind::File,
-            EntryMetadata::default(),
-        ));
-        original.disk_usage = Some(100);
-
-        let cloned = original.clone();
+    fn list_dir_refuses_directories_on_other_volume() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.one_file_system = true;
+        let mut ctx = ScanContext::from_config(&config).unwrap();
+        let root = ctx.root_volume.expect("根目录所在的卷应可查询");
+        assert!(list_dir(dir.path(), &ctx).is_ok());
 
-        assert_eq!(cloned.name, "test");
-        assert_eq!(cloned.children.len(), 1);
-        assert_eq!(cloned.disk_usage, Some(100));
+        ctx.root_volume = Some(root.wrapping_add(1));
+        assert_eq!(
+            list_dir(dir.path(), &ctx).err(),
+            Some(EntryError::OtherVolume)
+        );
+        assert!(
+            ctx.take_unreadable().is_empty(),
+            "其他卷上的目录不应记为无法读取"
+        );
     }
 
     #[test]
-    fn scan_handles_special_characters_in_filenames() {
-        let dir = TempDir::new().unwrap();
-        let root = dir.path();
-
-        File::create(root.join("file with spaces.txt")).unwrap();
-        File::create(root.join("file-with-dashes.txt")).unwrap();
-        File::create(root.join("file_with_underscores.txt")).unwrap();
-
-        let mut config = Config::with_root(root.to_path_buf());
+    fn one_file_system_keeps_directories_on_same_volume() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
         config.scan.show_files = true;
+        let expected = scan(&config).expect("扫描失败");
 
+        config.scan.one_file_system = true;
         let stats = scan(&config).expect("扫描失败");
+        assert_eq!(stats.directory_count, expected.directory_count);
+        assert_eq!(stats.file_count, expected.file_count);
 
-        assert_eq!(stats.file_count, 3);
-        assert!(has_node_with_name(&stats.tree, "file with spaces.txt"));
-        assert!(has_node_with_name(&stats.tree, "file-with-dashes.txt"));
-        assert!(has_node_with_name(&stats.tree, "file_with_underscores.txt"));
+        let streamed = scan_streaming(&config, |_| Ok(())).expect("流式扫描失败");
+        assert_eq!(streamed.directory_count, expected.directory_count);
     }
 
     #[test]
-    fn scan_streaming_has_more_dirs_flag() {
-        let dir = TempDir::new().unwrap();
-        let root = dir.path();
-
-        File::create(root.join("file.txt")).unwrap();
-        fs::create_dir(root.join("dir1")).unwrap();
-        fs::create_dir(root.join("dir2")).unwrap();
-
-        let mut config = Config::with_root(root.to_path_buf());
-        config.scan.show_files = true;
+    fn read_limit_uses_shorter_of_both_timeouts() {
+        let mut config = Config::with_root(PathBuf::from("."));
+        assert_eq!(
+            ScanContext::from_config(&config).unwrap().read_limit(),
+            None
+        );
 
-        let mut file_entries = Vec::new();
-        let _stats = scan_streaming(&config, |event| {
-            if let StreamEvent::Entry(entry) = event {
-                if entry.is_file {
-                    file_entries.push(entry);
-                }
-            }
-            Ok(())
-        })
-            .expect("流式扫描失败");
+        config.scan.read_timeout = Some(Duration::from_secs(2));
+        assert_eq!(
+            ScanContext::from_config(&config).unwrap().read_limit(),
+            Some(Duration::from_secs(2))
+        );
 
-        assert!(!file_entries.is_empty());
-        assert!(file_entries[0].has_more_dirs);
+        config.scan.timeout = Some(Duration::from_secs(3600));
+        let limit = ScanContext::from_config(&config).unwrap().read_limit();
+        assert_eq!(limit, Some(Duration::from_secs(2)), "应取较短的时限");
     }
 
     #[test]
-    fn streaming_vs_batch_order_consistency() {
+    fn streaming_count_records_rejections_per_filter() {
         let dir = setup_test_dir();
         let mut config = Config::with_root(dir.path().to_path_buf());
+        config.render.count_only = true;
         config.scan.show_files = true;
+        config.scan.file_limit = Some(5);
+        config.matching.exclude_patterns.push("tests".to_string());
+        config.matching.include_patterns.push("*.rs".to_string());
 
-        let batch_stats = scan(&config).expect("批处理扫描失败");
-
-        let mut batch_order = Vec::new();
-        fn collect_order(node: &TreeNode, order: &mut Vec<String>) {
-            for child in &node.children {
-                order.push(child.name.clone());
-                if child.kind == EntryKind::Directory {
-                    collect_order(child, order);
-                }
-            }
-        }
-        collect_order(&batch_stats.tree, &mut batch_order);
-
-        let mut stream_order = Vec::new();
-        let _stream_stats = scan_streaming(&config, |event| {
-            if let StreamEvent::Entry(entry) = event {
-                stream_order.push(entry.name.clone());
-            }
-            Ok(())
-        })
-            .expect("流式扫描失败");
-
-        assert_eq!(batch_order, stream_order);
+        let stats = scan_streaming(&config, |_| Ok(())).expect("流式扫描失败");
+        assert_eq!(stats.rejected.get(FilterKind::Excluded), 1);
+        assert_eq!(
+            stats.rejected.get(FilterKind::NotIncluded),
+            2,
+            "预读的目录不应重复计数"
+        );
+        assert_eq!(stats.rejected.total(), 3);
+        assert_eq!(stats.rejected.subtrees(), 1, "被排除的目录应计为跳过的子树");
     }
 
     #[test]
-    fn scan_context_show_hidden_default_false() {
-        let config = Config::default();
-        let ctx = ScanContext::from_config(&config).unwrap();
-        assert!(!ctx.show_hidden);
-    }
+    fn batch_scan_skips_filtered_subtrees() {
+        let dir = setup_test_dir();
+        File::create(dir.path().join("tests").join("more.rs")).unwrap();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.render.count_only = true;
+        config.scan.show_files = true;
+        config.matching.exclude_patterns.push("tests".to_string());
+        config
+            .matching
+            .exclude_patterns
+            .push("README.md".to_string());
 
-    #[test]
-    fn scan_context_show_hidden_enabled() {
-        let mut config = Config::default();
-        config.scan.show_hidden = true;
         let ctx = ScanContext::from_config(&config).unwrap();
-        assert!(ctx.show_hidden);
+        let arena = Mutex::new(TreeArena::new(config.root_path.clone()));
+        scan_dir(
+            &config.root_path,
+            None,
+            0,
+            &ctx,
+            GitignoreChain::new(),
+            &arena,
+        )
+        .unwrap();
+
+        let rejected = ctx.take_rejected();
+        assert_eq!(
+            rejected.get(FilterKind::Excluded),
+            2,
+            "tests 内的文件不应被读取和计数"
+        );
+        assert_eq!(rejected.subtrees(), 1);
     }
 
     #[test]
-    fn is_hidden_returns_false_for_normal_file() {
-        let dir = TempDir::new().unwrap();
-        let file_path = dir.path().join("normal.txt");
-        File::create(&file_path).unwrap();
+    fn streaming_count_records_pruned_directories() {
+        let dir = setup_prune_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.render.count_only = true;
+        config.scan.show_files = true;
+        config.matching.include_patterns.push("*.txt".to_string());
+        config.matching.prune_empty = true;
 
-        let meta = fs::metadata(&file_path).unwrap();
-        assert!(!is_hidden(&meta));
+        let stats = scan_streaming(&config, |_| Ok(())).expect("流式扫描失败");
+        assert_eq!(stats.rejected.get(FilterKind::Pruned), 4);
+        assert_eq!(stats.rejected.get(FilterKind::NotIncluded), 2);
     }
 
     #[test]
-    fn is_hidden_detects_hidden_attribute() {
-        use std::process::Command;
-
-        let dir = TempDir::new().unwrap();
-        let file_path = dir.path().join("hidden.txt");
-        File::create(&file_path).unwrap();
-
-        // Set hidden attribute using attrib command
-        let _ = Command::new("attrib")
-            .args(["+H", file_path.to_str().unwrap()])
-            .output();
+    fn streaming_skips_rejection_counts_without_count() {
+        let dir = setup_test_dir();
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.matching.exclude_patterns.push("tests".to_string());
 
-        let meta = fs::metadata(&file_path).unwrap();
-        assert!(is_hidden(&meta));
+        let stats = scan_streaming(&config, |_| Ok(())).expect("流式扫描失败");
+        assert_eq!(stats.rejected, RejectionCounts::default());
     }
 }
@@ -0,0 +1,1048 @@
+//! Server module: a local JSON-RPC API over the scanned tree (`--serve`).
+//!
+//! Editors and dashboards that show a directory structure ask for it again
+//! and again. With `--serve`, tree++ keeps the tree of the root in memory
+//! and answers JSON-RPC 2.0 requests about it:
+//!
+//! - **`scan`**: scans the root again and keeps the new tree; returns the
+//!   counts of the scan
+//! - **`tree`**: the structured output of the tree, as written by
+//!   `/O tree.json`, or of the directory at `params.path`
+//! - **`stats`**: the `--stats-json` summary of the tree, or of the
+//!   directory at `params.path`
+//! - **`diff`**: scans the root again and returns the paths added and
+//!   removed since the previous scan, directories with a trailing `/`
+//! - **`shutdown`**: stops the server after answering
+//!
+//! The root is scanned on the first request that needs the tree. tree++
+//! does not watch the file system, so the tree only changes on `scan` and
+//! `diff`; with `--cache`, such a rescan only reads the directories that
+//! changed.
+//!
+//! Requests arrive over one or both transports:
+//!
+//! - **HTTP**: posted to `http://127.0.0.1:<port>/`, on the loopback
+//!   interface only; one request per connection, one connection at a time.
+//!   Requests must name `127.0.0.1:<port>` or `localhost:<port>` as `Host`
+//!   and carry no `Origin`, so web pages open in a browser (including
+//!   through DNS rebinding) cannot query the tree
+//! - **Named pipe** (`--pipe`): `\\.\pipe\<name>`, for editor extensions
+//!   and other local tools that should not open a network port. Each message
+//!   in either direction is a 4-byte little-endian length followed by that
+//!   many bytes of JSON; a client may send any number of requests before it
+//!   closes the pipe, and each client is served on a thread of its own.
+//!
+//! All clients share the same tree, and their requests are answered one
+//! after another.
+//!
+//! File: src/server.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::collections::BTreeSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::mem;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{Value, json};
+use tracing::debug;
+
+use crate::arena::NodeRef;
+use crate::config::Config;
+use crate::error::{OutputError, TreeppError};
+use crate::output;
+use crate::scan::{self, EntryKind, ScanStats};
+use crate::stats::{ScanSummary, StatsCollector};
+use crate::winvol;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Port the server listens on without `--port`.
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Prefix of the full path of a named pipe.
+const PIPE_PREFIX: &str = r"\\.\pipe\";
+
+/// Largest request body or pipe message accepted, in bytes.
+const MAX_BODY_SIZE: usize = 1 << 20;
+
+/// Longest HTTP request line or header line accepted, in bytes.
+const MAX_HEADER_LINE: usize = 8 << 10;
+
+/// Largest number of HTTP header lines accepted.
+const MAX_HEADER_COUNT: usize = 100;
+
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// JSON-RPC error code of a request that is not valid JSON.
+const PARSE_ERROR: i64 = -32700;
+
+/// JSON-RPC error code of a request that is not a JSON-RPC 2.0 request.
+const INVALID_REQUEST: i64 = -32600;
+
+/// JSON-RPC error code of an unknown method.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC error code of invalid method parameters.
+const INVALID_PARAMS: i64 = -32602;
+
+/// Error code of a failed scan, from the range JSON-RPC leaves to servers.
+const SCAN_FAILED: i64 = -32000;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// An error answered to a JSON-RPC request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<TreeppError> for RpcError {
+    fn from(err: TreeppError) -> Self {
+        Self::new(SCAN_FAILED, err.to_string())
+    }
+}
+
+/// Answers JSON-RPC requests about the tree of one root.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use treepp::config::Config;
+/// use treepp::server::TreeServer;
+///
+/// let mut config = Config::with_root(PathBuf::from("."));
+/// config.serve = true;
+/// let mut server = TreeServer::new(config.validate().unwrap());
+///
+/// let reply = server.handle(r#"{"jsonrpc": "2.0", "id": 1, "method": "stats"}"#);
+/// assert!(reply.unwrap().contains("\"directories\""));
+/// ```
+#[derive(Debug)]
+pub struct TreeServer {
+    config: Config,
+    /// The tree of the last scan, if the root was scanned yet.
+    scanned: Option<ScanStats>,
+    /// Whether a `shutdown` request was answered.
+    stopped: bool,
+}
+
+impl TreeServer {
+    /// Creates a server for a validated configuration.
+    ///
+    /// The root is not scanned until a request needs the tree.
+    #[must_use]
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            scanned: None,
+            stopped: false,
+        }
+    }
+
+    /// Returns whether a `shutdown` request was answered.
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Answers one JSON-RPC request.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request as JSON text.
+    ///
+    /// # Returns
+    ///
+    /// The response as JSON text, or `None` for a notification (a valid
+    /// request without an `id`), which is carried out but not answered.
+    /// Text that is not valid JSON or not a valid request is answered with
+    /// an error whose `id` is `null`.
+    pub fn handle(&mut self, request: &str) -> Option<String> {
+        let (id, result) = match serde_json::from_str::<Value>(request) {
+            Ok(request) => match check_request(&request) {
+                Ok((id, method)) => {
+                    let params = request.get("params").unwrap_or(&Value::Null);
+                    let result = self.dispatch(method, params);
+                    (id?, result)
+                }
+                Err(error) => (Value::Null, Err(error)),
+            },
+            Err(e) => (Value::Null, Err(RpcError::new(PARSE_ERROR, e.to_string()))),
+        };
+
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": error.code, "message": error.message },
+            }),
+        };
+        Some(response.to_string())
+    }
+
+    /// Runs the method of a valid request.
+    fn dispatch(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "scan" => Ok(scan_result(self.scanned.insert(scan::scan(&self.config)?))),
+            "tree" => self.tree(params),
+            "stats" => self.stats(params),
+            "diff" => self.diff(),
+            "shutdown" => {
+                self.stopped = true;
+                Ok(Value::Null)
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method: {method}"),
+            )),
+        }
+    }
+
+    /// Returns the structured output of the tree or of a directory in it.
+    fn tree(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let path = path_param(params)?;
+        let stats = scanned(&mut self.scanned, &self.config)?;
+        let node = find_directory(stats.tree.root(), &path)?;
+
+        let mut structured = output::create_structured_output(node, &self.config);
+        if node.id() != stats.tree.root().id() {
+            structured.root.path = path;
+        }
+        serde_json::to_value(&structured).map_err(|e| RpcError::new(SCAN_FAILED, e.to_string()))
+    }
+
+    /// Returns the `--stats-json` summary of the tree or of a directory in it.
+    fn stats(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let path = path_param(params)?;
+        let stats = scanned(&mut self.scanned, &self.config)?;
+        let node = find_directory(stats.tree.root(), &path)?;
+
+        let collector = StatsCollector::from_node(node);
+        let (directories, files) = collector.levels().iter().fold((0, 0), |(d, f), level| {
+            (d + level.directories, f + level.files)
+        });
+        let mut summary =
+            ScanSummary::new(&self.config, collector, directories, files, stats.duration);
+        if node.id() != stats.tree.root().id() {
+            summary.root = node.path().to_string_lossy().into_owned();
+        }
+        serde_json::to_value(&summary).map_err(|e| RpcError::new(SCAN_FAILED, e.to_string()))
+    }
+
+    /// Scans the root again and returns the paths added and removed.
+    fn diff(&mut self) -> Result<Value, RpcError> {
+        let before = entry_paths(scanned(&mut self.scanned, &self.config)?.tree.root());
+        let rescanned = scan::scan(&self.config)?;
+        let after = entry_paths(rescanned.tree.root());
+        self.scanned = Some(rescanned);
+
+        Ok(json!({
+            "added": after.difference(&before).collect::<Vec<_>>(),
+            "removed": before.difference(&after).collect::<Vec<_>>(),
+        }))
+    }
+}
+
+/// Accepts clients on a named pipe (`--pipe`).
+///
+/// One instance of the pipe is always waiting for the next client, so a
+/// client connecting while another is served does not find the pipe busy.
+#[derive(Debug)]
+pub struct PipeListener {
+    /// Full path of the pipe.
+    path: String,
+    /// The instance the next client connects to.
+    next: File,
+}
+
+impl PipeListener {
+    /// Creates the pipe `\\.\pipe\<name>`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the pipe, with or without the `\\.\pipe\` prefix.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputError::ListenFailed` if another process owns the
+    /// pipe, or if named pipes are not supported on this platform.
+    pub fn bind(name: &str) -> Result<Self, OutputError> {
+        let path = pipe_path(name);
+        match winvol::create_pipe(&path, true) {
+            Ok(next) => Ok(Self { path, next }),
+            Err(source) => Err(OutputError::ListenFailed {
+                address: path,
+                source,
+            }),
+        }
+    }
+
+    /// Returns the full path of the pipe.
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Waits for the next client.
+    ///
+    /// # Returns
+    ///
+    /// The connected pipe, or `None` if the client went away before it was
+    /// accepted.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if no new instance of the pipe can be created.
+    fn accept(&mut self) -> io::Result<Option<File>> {
+        let connected = winvol::connect_pipe(&self.next);
+        let client = mem::replace(&mut self.next, winvol::create_pipe(&self.path, false)?);
+        match connected {
+            Ok(()) => Ok(Some(client)),
+            Err(e) => {
+                debug!("serve: pipe client went away: {}", e);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Unblocks the listeners of the other transports once the server stops.
+///
+/// Each listener waits for its next client, so a `shutdown` request on one
+/// transport connects once to every transport to let their loops notice.
+#[derive(Debug, Clone)]
+struct Waker {
+    http: Option<SocketAddr>,
+    pipe: Option<String>,
+}
+
+impl Waker {
+    fn wake(&self) {
+        if let Some(address) = self.http {
+            let _ = TcpStream::connect(address);
+        }
+        if let Some(path) = &self.pipe {
+            let _ = OpenOptions::new().read(true).write(true).open(path);
+        }
+    }
+}
+
+// ============================================================================
+// Public Functions
+// ============================================================================
+
+/// Listens for connections on the loopback interface.
+///
+/// # Arguments
+///
+/// * `port` - The port to listen on; `0` lets the system pick one.
+///
+/// # Returns
+///
+/// The listener to pass to `run`.
+///
+/// # Errors
+///
+/// Returns `OutputError::ListenFailed` if the port is in use or cannot be
+/// bound.
+pub fn bind(port: u16) -> Result<TcpListener, OutputError> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    TcpListener::bind(address).map_err(|source| OutputError::ListenFailed {
+        address: address.to_string(),
+        source,
+    })
+}
+
+/// Answers requests on the given transports until a `shutdown` request.
+///
+/// HTTP connections are answered on the calling thread and pipe clients
+/// each on a thread of their own; all of them share the server, so requests
+/// are answered one at a time. A connection that fails, for instance
+/// because the client went away, is dropped without stopping the server.
+///
+/// # Arguments
+///
+/// * `server` - The server answering the requests.
+/// * `http` - The listener from `bind`, if HTTP is served.
+/// * `pipe` - The listener from `PipeListener::bind`, if a pipe is served.
+pub fn run(server: TreeServer, http: Option<TcpListener>, pipe: Option<PipeListener>) {
+    let server = Arc::new(Mutex::new(server));
+    let waker = Waker {
+        http: http
+            .as_ref()
+            .and_then(|listener| listener.local_addr().ok()),
+        pipe: pipe.as_ref().map(|listener| listener.path.clone()),
+    };
+
+    thread::scope(|scope| {
+        if let Some(listener) = pipe {
+            let (server, waker) = (&server, &waker);
+            scope.spawn(move || serve_pipe(server, listener, waker));
+        }
+        if let Some(listener) = &http {
+            serve_http(&server, listener, &waker);
+        }
+    });
+}
+
+// ============================================================================
+// Internal Functions
+// ============================================================================
+
+/// Locks the shared server, recovering it if a client thread panicked.
+fn lock(server: &Mutex<TreeServer>) -> MutexGuard<'_, TreeServer> {
+    server.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Answers HTTP connections until the server stops.
+fn serve_http(server: &Mutex<TreeServer>, listener: &TcpListener, waker: &Waker) {
+    for stream in listener.incoming() {
+        if lock(server).is_stopped() {
+            break;
+        }
+        if let Err(e) = stream.and_then(|stream| answer_http(server, &stream)) {
+            debug!("serve: connection failed: {}", e);
+        }
+        if lock(server).is_stopped() {
+            waker.wake();
+            break;
+        }
+    }
+}
+
+/// Reads an HTTP request from a connection and writes the answer.
+fn answer_http(server: &Mutex<TreeServer>, stream: &TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let port = stream.local_addr()?.port();
+    let (status, body) = match read_request(&mut BufReader::new(stream), port)? {
+        Ok(request) => match lock(server).handle(&request) {
+            Some(response) => ("200 OK", response),
+            None => ("204 No Content", String::new()),
+        },
+        Err(status) => (status, String::new()),
+    };
+    write_response(&mut &*stream, status, &body)
+}
+
+/// Accepts pipe clients until the server stops, answering each on its own
+/// thread.
+fn serve_pipe(server: &Arc<Mutex<TreeServer>>, mut listener: PipeListener, waker: &Waker) {
+    while !lock(server).is_stopped() {
+        let client = match listener.accept() {
+            Ok(Some(client)) => client,
+            Ok(None) => continue,
+            Err(e) => {
+                debug!("serve: cannot create pipe instance: {}", e);
+                break;
+            }
+        };
+        if lock(server).is_stopped() {
+            break;
+        }
+        let (server, waker) = (Arc::clone(server), waker.clone());
+        thread::spawn(move || {
+            if let Err(e) = answer_frames(&server, client, &waker) {
+                debug!("serve: pipe client failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Answers length-prefixed requests from a pipe client until it closes the
+/// pipe or the server stops.
+fn answer_frames(
+    server: &Mutex<TreeServer>,
+    mut client: impl Read + Write,
+    waker: &Waker,
+) -> io::Result<()> {
+    while let Some(request) = read_frame(&mut client)? {
+        let (response, stopped) = {
+            let mut server = lock(server);
+            (server.handle(&request), server.is_stopped())
+        };
+        if let Some(response) = response {
+            write_frame(&mut client, &response)?;
+        }
+        if stopped {
+            waker.wake();
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the full path of a named pipe, adding the `\\.\pipe\` prefix
+/// unless it is already there.
+fn pipe_path(name: &str) -> String {
+    let has_prefix = name
+        .get(..PIPE_PREFIX.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(PIPE_PREFIX));
+    if has_prefix {
+        name.to_string()
+    } else {
+        format!("{PIPE_PREFIX}{name}")
+    }
+}
+
+/// Reads one length-prefixed message: a 4-byte little-endian length, then
+/// that many bytes of UTF-8 text.
+///
+/// # Returns
+///
+/// The message, or `None` if the client closed the pipe between messages.
+///
+/// # Errors
+///
+/// Returns `InvalidData` if the message is larger than `MAX_BODY_SIZE` or
+/// not UTF-8, or the I/O error if the pipe fails.
+fn read_frame(reader: &mut impl Read) -> io::Result<Option<String>> {
+    let mut prefix = [0; 4];
+    match reader.read_exact(&mut prefix) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let length = u32::from_le_bytes(prefix) as usize;
+    if length > MAX_BODY_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {length} bytes is too large"),
+        ));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Writes one length-prefixed message.
+fn write_frame(writer: &mut impl Write, message: &str) -> io::Result<()> {
+    let length =
+        u32::try_from(message.len()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    writer.write_all(&length.to_le_bytes())?;
+    writer.write_all(message.as_bytes())?;
+    writer.flush()
+}
+
+/// Returns the tree of the last scan, scanning the root first if needed.
+fn scanned<'a>(
+    scanned: &'a mut Option<ScanStats>,
+    config: &Config,
+) -> Result<&'a ScanStats, RpcError> {
+    let stats = match scanned.take() {
+        Some(stats) => stats,
+        None => scan::scan(config)?,
+    };
+    Ok(scanned.insert(stats))
+}
+
+/// Returns the result of a `scan` request.
+fn scan_result(stats: &ScanStats) -> Value {
+    json!({
+        "directories": stats.directory_count,
+        "files": stats.file_count,
+        "duration_ms": stats.duration.as_secs_f64() * 1000.0,
+        "unreadable": stats
+            .unreadable_dirs
+            .iter()
+            .map(|dir| dir.to_string_lossy())
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Checks that a parsed request is a single JSON-RPC 2.0 request.
+///
+/// # Returns
+///
+/// The request's `id` (`None` for a notification) and its method.
+///
+/// # Errors
+///
+/// Returns an `INVALID_REQUEST` error for anything but an object with
+/// `"jsonrpc": "2.0"`, a string `method` and, if present, a string, number
+/// or `null` `id`. Batches are not supported.
+fn check_request(request: &Value) -> Result<(Option<Value>, &str), RpcError> {
+    if request.is_array() {
+        return Err(RpcError::new(
+            INVALID_REQUEST,
+            "batch requests are not supported",
+        ));
+    }
+    let id = request.get("id");
+    request
+        .get("method")
+        .and_then(Value::as_str)
+        .filter(|_| request.get("jsonrpc").and_then(Value::as_str) == Some("2.0"))
+        .filter(|_| id.is_none_or(|id| id.is_string() || id.is_number() || id.is_null()))
+        .map(|method| (id.cloned(), method))
+        .ok_or_else(|| RpcError::new(INVALID_REQUEST, "expected a JSON-RPC 2.0 request"))
+}
+
+/// Reads the optional `path` parameter; a missing path means the root.
+fn path_param(params: &Value) -> Result<String, RpcError> {
+    match params.get("path") {
+        None | Some(Value::Null) => Ok(String::new()),
+        Some(Value::String(path)) => Ok(path.clone()),
+        Some(_) => Err(RpcError::new(
+            INVALID_PARAMS,
+            "params.path must be a string",
+        )),
+    }
+}
+
+/// Finds the directory at a path relative to the root.
+///
+/// Components may be separated by `/` or `\`; on Windows, names are
+/// compared ignoring ASCII case.
+fn find_directory<'a>(root: NodeRef<'a>, path: &str) -> Result<NodeRef<'a>, RpcError> {
+    let same_name = |a: &str, b: &str| {
+        if cfg!(windows) {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    };
+    path.split(['/', '\\'])
+        .filter(|name| !name.is_empty() && *name != ".")
+        .try_fold(root, |node, name| {
+            node.children().find(|child| {
+                child.kind() == EntryKind::Directory
+                    && child.filtered().is_none()
+                    && same_name(child.name(), name)
+            })
+        })
+        .ok_or_else(|| {
+            RpcError::new(
+                INVALID_PARAMS,
+                format!("no directory in the tree at {path}"),
+            )
+        })
+}
+
+/// Collects the paths of the entries in a tree, relative to its root.
+///
+/// Paths use `/` separators, and directories end with `/`. Entries listed
+/// only by `--show-filtered` are left out.
+fn entry_paths(root: NodeRef<'_>) -> BTreeSet<String> {
+    fn collect(node: NodeRef<'_>, prefix: &str, paths: &mut BTreeSet<String>) {
+        for child in node.children().filter(|child| child.filtered().is_none()) {
+            let path = format!("{prefix}{}", child.name());
+            match child.kind() {
+                EntryKind::Directory => {
+                    let dir = format!("{path}/");
+                    collect(child, &dir, paths);
+                    paths.insert(dir);
+                }
+                EntryKind::File => {
+                    paths.insert(path);
+                }
+            }
+        }
+    }
+
+    let mut paths = BTreeSet::new();
+    collect(root, "", &mut paths);
+    paths
+}
+
+/// Reads an HTTP request and returns its body.
+///
+/// # Arguments
+///
+/// * `reader` - The connection.
+/// * `port` - The port the server listens on, which `Host` must name.
+///
+/// # Returns
+///
+/// The body of a `POST /` request from a local client, or the status line
+/// to reject any other request with.
+///
+/// # Errors
+///
+/// Returns the I/O error if the connection fails or times out.
+fn read_request(reader: &mut impl BufRead, port: u16) -> io::Result<Result<String, &'static str>> {
+    let mut request_line = String::new();
+    if !read_header_line(reader, &mut request_line)? {
+        return Ok(Err("431 Request Header Fields Too Large"));
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let mut length = 0;
+    let mut local_host = false;
+    let mut has_origin = false;
+    let mut header = String::new();
+    for count in 0.. {
+        header.clear();
+        if count == MAX_HEADER_COUNT || !read_header_line(reader, &mut header)? {
+            return Ok(Err("431 Request Header Fields Too Large"));
+        }
+        if header.trim_end().is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let (name, value) = (name.trim(), value.trim());
+        if name.eq_ignore_ascii_case("content-length") {
+            match value.parse() {
+                Ok(value) => length = value,
+                Err(_) => return Ok(Err("400 Bad Request")),
+            }
+        } else if name.eq_ignore_ascii_case("host") {
+            local_host = is_local_host(value, port);
+        } else if name.eq_ignore_ascii_case("origin") {
+            has_origin = true;
+        }
+    }
+
+    if !local_host || has_origin {
+        return Ok(Err("403 Forbidden"));
+    }
+    if method != "POST" {
+        return Ok(Err("405 Method Not Allowed"));
+    }
+    if target != "/" {
+        return Ok(Err("404 Not Found"));
+    }
+    if length > MAX_BODY_SIZE {
+        return Ok(Err("413 Content Too Large"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(String::from_utf8(body).map_err(|_| "400 Bad Request"))
+}
+
+/// Reads one line of an HTTP request head, up to `MAX_HEADER_LINE` bytes.
+///
+/// # Returns
+///
+/// `false` if the line is longer than `MAX_HEADER_LINE`; an empty `line` if
+/// the client closed the connection.
+///
+/// # Errors
+///
+/// Returns the I/O error if the connection fails or times out, or
+/// `InvalidData` if the line is not UTF-8.
+fn read_header_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<bool> {
+    let read = Read::take(&mut *reader, MAX_HEADER_LINE as u64).read_line(line)?;
+    Ok(read < MAX_HEADER_LINE || line.ends_with('\n'))
+}
+
+/// Checks whether a `Host` header names the server: `127.0.0.1` or
+/// `localhost` with the port it listens on.
+fn is_local_host(host: &str, port: u16) -> bool {
+    host.rsplit_once(':').is_some_and(|(name, host_port)| {
+        (name == "127.0.0.1" || name.eq_ignore_ascii_case("localhost"))
+            && host_port.parse() == Ok(port)
+    })
+}
+
+/// Writes an HTTP response with a JSON body and closes the exchange.
+fn write_response(writer: &mut impl Write, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    writer.flush()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    fn serve_dir() -> (TempDir, TreeServer) {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src/bin")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.path().join("README.md"), "# demo").unwrap();
+
+        let mut config = Config::with_root(dir.path().to_path_buf());
+        config.scan.show_files = true;
+        config.serve = true;
+        let server = TreeServer::new(config.validate().unwrap());
+        (dir, server)
+    }
+
+    fn call(server: &mut TreeServer, method: &str, params: Value) -> Value {
+        let request = json!({ "jsonrpc": "2.0", "id": 7, "method": method, "params": params });
+        let response = server.handle(&request.to_string()).expect("请求应有响应");
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn answers_tree_and_stats_of_subtree() {
+        let (_dir, mut server) = serve_dir();
+
+        let tree = call(&mut server, "tree", json!({ "path": "src" }));
+        assert_eq!(tree["id"], 7);
+        assert_eq!(tree["result"]["root"]["path"], "src");
+        assert_eq!(tree["result"]["root"]["files"], json!(["main.rs"]));
+        assert!(tree["result"]["root"]["dirs"]["bin"].is_object());
+
+        let stats = call(&mut server, "stats", Value::Null);
+        assert_eq!(stats["result"]["directories"], 2);
+        assert_eq!(stats["result"]["files"], 2);
+
+        let missing = call(&mut server, "tree", json!({ "path": "src/main.rs" }));
+        assert_eq!(missing["error"]["code"], INVALID_PARAMS, "文件不是目录");
+    }
+
+    #[test]
+    fn diff_reports_changes_since_last_scan() {
+        let (dir, mut server) = serve_dir();
+        call(&mut server, "scan", Value::Null);
+
+        fs::write(dir.path().join("src/bin/tool.rs"), "").unwrap();
+        fs::remove_file(dir.path().join("README.md")).unwrap();
+        let diff = call(&mut server, "diff", Value::Null);
+        assert_eq!(diff["result"]["added"], json!(["src/bin/tool.rs"]));
+        assert_eq!(diff["result"]["removed"], json!(["README.md"]));
+
+        let diff = call(&mut server, "diff", Value::Null);
+        assert_eq!(diff["result"]["added"], json!([]), "差异应相对于上一次扫描");
+    }
+
+    #[test]
+    fn rejects_invalid_requests() {
+        let (_dir, mut server) = serve_dir();
+
+        let response: Value = serde_json::from_str(&server.handle("{").unwrap()).unwrap();
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+        assert_eq!(response["id"], Value::Null);
+
+        let unknown = call(&mut server, "rename", Value::Null);
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+
+        let request = r#"{"jsonrpc": "2.0", "method": "shutdown"}"#;
+        assert_eq!(server.handle(request), None, "通知不应有响应");
+        assert!(server.is_stopped());
+    }
+
+    #[test]
+    fn answers_invalid_requests_without_id() {
+        let (_dir, mut server) = serve_dir();
+
+        for request in [
+            "{}",
+            "[]",
+            r#"[{"jsonrpc": "2.0", "id": 1, "method": "stats"}]"#,
+            "42",
+        ] {
+            let response = server.handle(request).expect("无效请求应有错误响应");
+            let response: Value = serde_json::from_str(&response).unwrap();
+            assert_eq!(response["error"]["code"], INVALID_REQUEST, "{request}");
+            assert_eq!(response["id"], Value::Null, "{request}");
+        }
+
+        let request = r#"{"jsonrpc": "2.0", "id": {"n": 1}, "method": "stats"}"#;
+        let response: Value = serde_json::from_str(&server.handle(request).unwrap()).unwrap();
+        assert_eq!(
+            response["error"]["code"], INVALID_REQUEST,
+            "id 只能是字符串或数字"
+        );
+        assert_eq!(response["id"], Value::Null);
+        assert!(!server.is_stopped());
+    }
+
+    #[test]
+    fn reads_only_post_requests_to_root() {
+        let read = |text: &str| read_request(&mut Cursor::new(text.as_bytes()), 7878).unwrap();
+        let body = r#"{"jsonrpc":"2.0"}"#;
+        let post = format!(
+            "POST / HTTP/1.1\r\nHost: 127.0.0.1:7878\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        assert_eq!(read(&post), Ok(body.to_string()));
+        assert_eq!(
+            read("GET / HTTP/1.1\r\nHost: localhost:7878\r\n\r\n"),
+            Err("405 Method Not Allowed")
+        );
+        assert_eq!(
+            read("POST /rpc HTTP/1.1\r\nHost: 127.0.0.1:7878\r\n\r\n"),
+            Err("404 Not Found")
+        );
+        assert_eq!(
+            read("POST / HTTP/1.1\r\nHost: 127.0.0.1:7878\r\ncontent-length: 99999999\r\n\r\n"),
+            Err("413 Content Too Large")
+        );
+    }
+
+    #[test]
+    fn rejects_requests_from_web_pages() {
+        let read = |text: &str| read_request(&mut Cursor::new(text.as_bytes()), 7878).unwrap();
+        assert_eq!(read("POST / HTTP/1.1\r\n\r\n"), Err("403 Forbidden"));
+        assert_eq!(
+            read("POST / HTTP/1.1\r\nHost: attacker.example:7878\r\n\r\n"),
+            Err("403 Forbidden")
+        );
+        assert_eq!(
+            read("POST / HTTP/1.1\r\nHost: 127.0.0.1:8080\r\n\r\n"),
+            Err("403 Forbidden")
+        );
+        assert_eq!(
+            read("POST / HTTP/1.1\r\nHost: localhost:7878\r\nOrigin: null\r\n\r\n"),
+            Err("403 Forbidden")
+        );
+    }
+
+    #[test]
+    fn rejects_oversized_request_heads() {
+        let read = |text: &str| read_request(&mut Cursor::new(text.as_bytes()), 7878).unwrap();
+        let long = format!(
+            "POST / HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+            "a".repeat(MAX_HEADER_LINE)
+        );
+        assert_eq!(read(&long), Err("431 Request Header Fields Too Large"));
+        let many = format!(
+            "POST / HTTP/1.1\r\n{}\r\n",
+            "X-Padding: a\r\n".repeat(MAX_HEADER_COUNT)
+        );
+        assert_eq!(read(&many), Err("431 Request Header Fields Too Large"));
+    }
+
+    #[test]
+    fn serves_requests_over_http_until_shutdown() {
+        let (_dir, server) = serve_dir();
+        let listener = bind(0).unwrap();
+        let address = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || run(server, Some(listener), None));
+
+        let post = |body: &str| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            write!(
+                stream,
+                "POST / HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nContent-Length: {}\r\n\r\n{body}",
+                address.port(),
+                body.len()
+            )
+            .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let response = post(r#"{"jsonrpc": "2.0", "id": 1, "method": "scan"}"#);
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.contains(r#""files":2"#), "{response}");
+
+        post(r#"{"jsonrpc": "2.0", "id": 2, "method": "shutdown"}"#);
+        handle.join().expect("服务器应在 shutdown 后退出");
+    }
+
+    /// A pipe client: requests to read, and the responses written.
+    struct FakePipe {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for FakePipe {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for FakePipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn frames_round_trip_and_reject_oversized_messages() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, "{}").unwrap();
+        write_frame(&mut buffer, "日本").unwrap();
+        assert_eq!(&buffer[..6], &[2, 0, 0, 0, b'{', b'}']);
+
+        let mut reader = Cursor::new(buffer);
+        assert_eq!(read_frame(&mut reader).unwrap().as_deref(), Some("{}"));
+        assert_eq!(read_frame(&mut reader).unwrap().as_deref(), Some("日本"));
+        assert_eq!(read_frame(&mut reader).unwrap(), None, "管道关闭应结束读取");
+
+        let oversized = u32::try_from(MAX_BODY_SIZE + 1).unwrap().to_le_bytes();
+        let err = read_frame(&mut Cursor::new(oversized)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn answers_pipe_frames_until_shutdown() {
+        let (_dir, server) = serve_dir();
+        let server = Mutex::new(server);
+        let mut input = Vec::new();
+        for request in [
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "stats"}"#,
+            r#"{"jsonrpc": "2.0", "method": "scan"}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "shutdown"}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "stats"}"#,
+        ] {
+            write_frame(&mut input, request).unwrap();
+        }
+        let mut pipe = FakePipe {
+            input: Cursor::new(input),
+            output: Vec::new(),
+        };
+        let waker = Waker {
+            http: None,
+            pipe: None,
+        };
+        answer_frames(&server, &mut pipe, &waker).unwrap();
+
+        let mut output = Cursor::new(pipe.output);
+        let stats: Value =
+            serde_json::from_str(&read_frame(&mut output).unwrap().unwrap()).unwrap();
+        assert_eq!(stats["result"]["files"], 2);
+        let shutdown = read_frame(&mut output).unwrap().unwrap();
+        assert!(shutdown.contains(r#""id":2"#), "通知不应有响应: {shutdown}");
+        assert_eq!(
+            read_frame(&mut output).unwrap(),
+            None,
+            "shutdown 后不应再回答"
+        );
+        assert!(lock(&server).is_stopped());
+    }
+
+    #[test]
+    fn adds_pipe_prefix_once() {
+        assert_eq!(pipe_path("treepp"), r"\\.\pipe\treepp");
+        assert_eq!(pipe_path(r"\\.\PIPE\treepp"), r"\\.\PIPE\treepp");
+    }
+}
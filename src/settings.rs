@@ -0,0 +1,467 @@
+//! Settings module: the per-user settings file.
+//!
+//! Settings apply to every run of tree++ for the current user, unlike
+//! options, which apply to a single run:
+//!
+//! - **Location**: `%APPDATA%\treepp\config.toml` (or the temporary
+//!   directory if `APPDATA` is not set)
+//! - **Default excludes**: patterns added to `--exclude` on every scan
+//! - **Presets**: named argument lists, expanded where `@<NAME>` is given
+//! - **Bookmarks**: named directories, used where `@<NAME>` is given
+//!
+//! The file is TOML and may be edited by hand, but `treepp config` keeps it
+//! valid:
+//!
+//! ```text
+//! treepp config add-exclude node_modules
+//! treepp config list
+//! treepp config remove node_modules
+//! treepp bookmark add work D:\repos\big-project
+//! treepp @work
+//! ```
+//!
+//! Presets are written by hand:
+//!
+//! ```toml
+//! [preset]
+//! docs = "/F /L 3 --smart"
+//! ```
+//!
+//! Saving rewrites the file from the settings it holds, so comments in a
+//! hand-edited file are not kept.
+//!
+//! File: src/settings.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigError;
+use crate::error::OutputError;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Application directory below `%APPDATA%`.
+const SETTINGS_APP_DIR: &str = "treepp";
+
+/// Name of the settings file.
+const SETTINGS_FILE: &str = "config.toml";
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Contents of the settings file.
+///
+/// Keys missing from the file take their default values, so an empty or
+/// missing file means no settings.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::settings::Settings;
+///
+/// let mut settings = Settings::default();
+/// assert!(settings.add_exclude("node_modules"));
+/// assert!(!settings.add_exclude("node_modules"));
+/// assert_eq!(settings.exclude, ["node_modules"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Patterns excluded from every scan, as with `--exclude`.
+    pub exclude: Vec<String>,
+    /// Argument lists by name, expanded by the parser where `@<NAME>` is given.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub preset: BTreeMap<String, String>,
+    /// Directories by name, used by the parser where `@<NAME>` is given.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub bookmark: BTreeMap<String, PathBuf>,
+}
+
+/// A `treepp config` or `treepp bookmark` command, which changes the
+/// settings file instead of scanning.
+///
+/// # Variants
+///
+/// * `AddExclude` - `treepp config add-exclude <PATTERN>...`
+/// * `RemoveExclude` - `treepp config remove <PATTERN>...`
+/// * `ListExcludes` - `treepp config list`
+/// * `AddBookmark` - `treepp bookmark add <NAME> <PATH>`
+/// * `RemoveBookmark` - `treepp bookmark remove <NAME>`
+/// * `ListBookmarks` - `treepp bookmark list`
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::settings::SettingsCommand;
+///
+/// let command = SettingsCommand::AddBookmark {
+///     name: "work".to_string(),
+///     path: PathBuf::from("D:\\repos\\big-project"),
+/// };
+/// assert_ne!(command, SettingsCommand::ListBookmarks);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingsCommand {
+    /// Adds patterns to the default excludes.
+    AddExclude(Vec<String>),
+    /// Removes patterns from the default excludes.
+    RemoveExclude(Vec<String>),
+    /// Prints the default excludes.
+    ListExcludes,
+    /// Adds a bookmark, or moves an existing one to another directory.
+    AddBookmark {
+        /// Name of the bookmark, without `@`.
+        name: String,
+        /// The directory, as given on the command line.
+        path: PathBuf,
+    },
+    /// Removes a bookmark.
+    RemoveBookmark(String),
+    /// Prints the bookmarks.
+    ListBookmarks,
+}
+
+impl SettingsCommand {
+    /// Actions accepted after `treepp config`, used in error suggestions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::settings::SettingsCommand;
+    ///
+    /// assert!(SettingsCommand::CONFIG_ACTIONS.contains(&"add-exclude"));
+    /// ```
+    pub const CONFIG_ACTIONS: &'static [&'static str] = &["add-exclude", "list", "remove"];
+
+    /// Actions accepted after `treepp bookmark`, used in error suggestions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::settings::SettingsCommand;
+    ///
+    /// assert!(SettingsCommand::BOOKMARK_ACTIONS.contains(&"remove"));
+    /// ```
+    pub const BOOKMARK_ACTIONS: &'static [&'static str] = &["add", "list", "remove"];
+}
+
+impl Settings {
+    /// Reads the settings file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The settings file, usually `settings_file_path()`.
+    ///
+    /// # Returns
+    ///
+    /// The settings, or the defaults if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::InvalidSettings` if the file cannot be read or
+    /// is not valid settings TOML.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::settings::Settings;
+    ///
+    /// let missing = std::env::temp_dir().join("treepp-missing-settings.toml");
+    /// assert_eq!(Settings::load(&missing).unwrap(), Settings::default());
+    /// ```
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let invalid = |reason: String| ConfigError::InvalidSettings {
+            path: path.to_path_buf(),
+            reason,
+        };
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(invalid(e.to_string())),
+        };
+        toml::from_str(&text).map_err(|e| invalid(e.message().to_string()))
+    }
+
+    /// Writes the settings file, creating its directory if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The settings file, usually `settings_file_path()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputError::FileCreateFailed` if the directory or file
+    /// cannot be written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use treepp::settings::{settings_file_path, Settings};
+    ///
+    /// let path = settings_file_path();
+    /// let mut settings = Settings::load(&path).unwrap();
+    /// settings.add_exclude("node_modules");
+    /// settings.save(&path).unwrap();
+    /// ```
+    pub fn save(&self, path: &Path) -> Result<(), OutputError> {
+        let text =
+            toml::to_string_pretty(self).map_err(|e| OutputError::toml_error(e.to_string()))?;
+        let create_failed = |source| OutputError::FileCreateFailed {
+            path: path.to_path_buf(),
+            source,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(create_failed)?;
+        }
+        fs::write(path, text).map_err(create_failed)
+    }
+
+    /// Adds a pattern to the default excludes.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - The pattern, as given to `--exclude`.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the pattern was already excluded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::settings::Settings;
+    ///
+    /// let mut settings = Settings::default();
+    /// assert!(settings.add_exclude("*.log"));
+    /// assert!(!settings.add_exclude("*.log"));
+    /// ```
+    pub fn add_exclude(&mut self, pattern: &str) -> bool {
+        if self.exclude.iter().any(|p| p == pattern) {
+            return false;
+        }
+        self.exclude.push(pattern.to_string());
+        true
+    }
+
+    /// Removes a pattern from the default excludes.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - The pattern, exactly as it was added.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the pattern was not excluded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::settings::Settings;
+    ///
+    /// let mut settings = Settings::default();
+    /// settings.add_exclude("dist");
+    /// assert!(settings.remove_exclude("dist"));
+    /// assert!(!settings.remove_exclude("dist"));
+    /// ```
+    pub fn remove_exclude(&mut self, pattern: &str) -> bool {
+        let before = self.exclude.len();
+        self.exclude.retain(|p| p != pattern);
+        self.exclude.len() != before
+    }
+
+    /// Bookmarks a directory, replacing any bookmark of the same name.
+    ///
+    /// A relative path is stored as an absolute one, so the bookmark can be
+    /// used from any directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the bookmark, without `@`.
+    /// * `path` - The directory.
+    ///
+    /// # Returns
+    ///
+    /// The absolute path that was stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::InvalidPath` if the path is not an existing
+    /// directory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::settings::Settings;
+    ///
+    /// let mut settings = Settings::default();
+    /// let stored = settings.add_bookmark("tmp", &std::env::temp_dir()).unwrap();
+    /// assert!(stored.is_absolute());
+    /// assert_eq!(settings.bookmark["tmp"], stored);
+    /// ```
+    pub fn add_bookmark(&mut self, name: &str, path: &Path) -> Result<PathBuf, ConfigError> {
+        let invalid = |reason: String| ConfigError::InvalidPath {
+            path: path.to_path_buf(),
+            reason,
+        };
+        if !path.exists() {
+            return Err(invalid("Path does not exist".to_string()));
+        }
+        if !path.is_dir() {
+            return Err(invalid("Path is not a directory".to_string()));
+        }
+        let absolute = std::path::absolute(path).map_err(|e| invalid(e.to_string()))?;
+        self.bookmark.insert(name.to_string(), absolute.clone());
+        Ok(absolute)
+    }
+
+    /// Removes a bookmark.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the bookmark, without `@`.
+    ///
+    /// # Returns
+    ///
+    /// `false` if there was no such bookmark.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::settings::Settings;
+    ///
+    /// let mut settings = Settings::default();
+    /// settings.add_bookmark("tmp", &std::env::temp_dir()).unwrap();
+    /// assert!(settings.remove_bookmark("tmp"));
+    /// assert!(!settings.remove_bookmark("tmp"));
+    /// ```
+    pub fn remove_bookmark(&mut self, name: &str) -> bool {
+        self.bookmark.remove(name).is_some()
+    }
+}
+
+// ============================================================================
+// Public Functions
+// ============================================================================
+
+/// Returns the path of the settings file.
+///
+/// # Returns
+///
+/// `%APPDATA%\treepp\config.toml`, or the same below the temporary
+/// directory if `APPDATA` is not set.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::settings::settings_file_path;
+///
+/// let path = settings_file_path();
+/// assert!(path.ends_with("treepp/config.toml"));
+/// ```
+#[must_use]
+pub fn settings_file_path() -> PathBuf {
+    env::var_os("APPDATA")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join(SETTINGS_APP_DIR)
+        .join(SETTINGS_FILE)
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn missing_file_yields_defaults() {
+        let dir = TempDir::new().unwrap();
+        let settings = Settings::load(&dir.path().join("config.toml")).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn saved_settings_are_loaded_again() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("treepp").join("config.toml");
+        let mut settings = Settings::default();
+        settings.add_exclude("node_modules");
+        settings.add_exclude("*.log");
+        settings
+            .preset
+            .insert("docs".to_string(), "/F /L 3".to_string());
+        settings
+            .bookmark
+            .insert("work".to_string(), PathBuf::from(r"D:\repos\big-project"));
+        settings.save(&file).expect("写入设置失败");
+
+        assert_eq!(Settings::load(&file).unwrap(), settings);
+    }
+
+    #[test]
+    fn remove_exclude_reports_unknown_pattern() {
+        let mut settings = Settings::default();
+        settings.add_exclude("target");
+        assert!(!settings.remove_exclude("node_modules"));
+        assert!(settings.remove_exclude("target"));
+        assert!(settings.exclude.is_empty());
+    }
+
+    #[test]
+    fn presets_are_read_from_dotted_keys() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("config.toml");
+        fs::write(&file, "preset.docs = \"-f -L 3 --smart\"\n").unwrap();
+
+        let settings = Settings::load(&file).unwrap();
+        assert_eq!(settings.preset["docs"], "-f -L 3 --smart");
+        assert!(settings.exclude.is_empty());
+    }
+
+    #[test]
+    fn bookmark_is_stored_as_absolute_directory() {
+        let dir = TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        let stored = settings.add_bookmark("tmp", dir.path()).unwrap();
+        assert!(stored.is_absolute());
+        assert_eq!(settings.bookmark["tmp"], stored);
+
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "").unwrap();
+        assert!(matches!(
+            settings.add_bookmark("file", &file),
+            Err(ConfigError::InvalidPath { .. })
+        ));
+        assert!(settings.remove_bookmark("tmp"));
+        assert!(!settings.remove_bookmark("tmp"));
+    }
+
+    #[test]
+    fn invalid_file_is_rejected() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("config.toml");
+        fs::write(&file, "exclude = \"node_modules\"").unwrap();
+
+        let err = Settings::load(&file).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::InvalidSettings { ref path, .. } if *path == file),
+            "应报告设置文件无效：{err:?}"
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! Size snapshot module: directory sizes of the previous `--du-delta` run.
+//!
+//! A `--du-delta` run compares the cumulative size of each directory with
+//! the snapshot left by the previous `--du-delta` run of the same root, and
+//! then replaces the snapshot with its own sizes:
+//!
+//! - **Key**: the directory path relative to the root, `/`-separated
+//! - **Location**: `%LOCALAPPDATA%\treepp\sizes`, one JSON file per root
+//!
+//! Directories missing from the snapshot count as having been empty. The
+//! sizes depend on the filters of the run, so runs are only comparable when
+//! they use the same options.
+//!
+//! File: src/snapshot.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::arena::{NodeId, NodeRef, TreeArena};
+use crate::scan::EntryKind;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Snapshot file format version; files written by other versions are discarded.
+const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Application directory below `%LOCALAPPDATA%`.
+const SNAPSHOT_APP_DIR: &str = "treepp";
+
+/// Subdirectory holding the snapshot files.
+const SNAPSHOT_SUBDIR: &str = "sizes";
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// On-disk form of a snapshot.
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotFile {
+    version: u32,
+    root: PathBuf,
+    sizes: HashMap<String, u64>,
+}
+
+/// Cumulative directory sizes of a scanned tree.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::arena::TreeArena;
+/// use treepp::scan::{EntryKind, EntryMetadata};
+/// use treepp::snapshot::SizeSnapshot;
+///
+/// let mut tree = TreeArena::new(PathBuf::from("project"));
+/// let root = tree.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+/// let file = EntryMetadata { size: 42, ..EntryMetadata::default() };
+/// tree.add(Some(root), "main.rs", EntryKind::File, file);
+/// tree.compute_disk_usage();
+///
+/// assert_eq!(SizeSnapshot::capture(&tree).size(""), Some(42));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SizeSnapshot {
+    sizes: HashMap<String, u64>,
+}
+
+impl SizeSnapshot {
+    /// Records the cumulative size of every directory of a tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - A tree whose disk usage has been computed.
+    ///
+    /// # Returns
+    ///
+    /// The snapshot; directories without a computed size are left out.
+    #[must_use]
+    pub fn capture(tree: &TreeArena) -> Self {
+        let mut sizes = HashMap::new();
+        for (node, key) in directories(tree) {
+            if let Some(usage) = tree.get(node).disk_usage() {
+                sizes.insert(key, usage);
+            }
+        }
+        Self { sizes }
+    }
+
+    /// Returns the recorded size of a directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The directory path relative to the root, `/`-separated;
+    ///   the root itself is the empty string.
+    ///
+    /// # Returns
+    ///
+    /// The size, or `None` if the directory was not recorded.
+    #[must_use]
+    pub fn size(&self, key: &str) -> Option<u64> {
+        self.sizes.get(key).copied()
+    }
+
+    /// Attaches the recorded sizes to the directories of a tree, as their
+    /// previous sizes.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The tree of the current run.
+    pub fn apply(&self, tree: &mut TreeArena) {
+        for (node, key) in directories(tree) {
+            tree.set_previous_usage(node, Some(self.size(&key).unwrap_or(0)));
+        }
+    }
+
+    /// Reads the snapshot of a scan root.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The snapshot file, usually `snapshot_file_path(root)`.
+    /// * `root` - The scan root.
+    ///
+    /// # Returns
+    ///
+    /// The snapshot, or `None` if the file is missing, unreadable, written
+    /// by another version, or belongs to another root.
+    #[must_use]
+    pub fn load(file: &Path, root: &Path) -> Option<Self> {
+        let bytes = fs::read(file).ok()?;
+        let stored: SnapshotFile = serde_json::from_slice(&bytes).ok()?;
+        if stored.version != SNAPSHOT_FORMAT_VERSION || stored.root != root {
+            return None;
+        }
+        Some(Self {
+            sizes: stored.sizes,
+        })
+    }
+
+    /// Writes the snapshot of a scan root.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The snapshot file, usually `snapshot_file_path(root)`.
+    /// * `root` - The scan root.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error if the file cannot be written.
+    pub fn save(&self, file: &Path, root: &Path) -> io::Result<()> {
+        let stored = SnapshotFile {
+            version: SNAPSHOT_FORMAT_VERSION,
+            root: root.to_path_buf(),
+            sizes: self.sizes.clone(),
+        };
+        let bytes = serde_json::to_vec(&stored).map_err(io::Error::other)?;
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write to a sibling file first so that an interrupted run never
+        // leaves a truncated snapshot behind.
+        let partial = file.with_extension("tmp");
+        fs::write(&partial, bytes)?;
+        fs::rename(&partial, file)
+    }
+}
+
+// ============================================================================
+// Public Functions
+// ============================================================================
+
+/// Returns the snapshot file of a scan root.
+///
+/// Files live in `%LOCALAPPDATA%\treepp\sizes` (or the temporary directory
+/// if `LOCALAPPDATA` is not set), named after a hash of the root path.
+///
+/// # Arguments
+///
+/// * `root` - The scan root.
+///
+/// # Returns
+///
+/// The path of the root's snapshot file.
+#[must_use]
+pub fn snapshot_file_path(root: &Path) -> PathBuf {
+    let base = env::var_os("LOCALAPPDATA")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir);
+    let mut hasher = DefaultHasher::new();
+    root.hash(&mut hasher);
+    base.join(SNAPSHOT_APP_DIR)
+        .join(SNAPSHOT_SUBDIR)
+        .join(format!("{:016x}.json", hasher.finish()))
+}
+
+// ============================================================================
+// Internal Functions
+// ============================================================================
+
+/// Lists the directories of a tree with their snapshot keys.
+fn directories(tree: &TreeArena) -> Vec<(NodeId, String)> {
+    let mut found = Vec::new();
+    collect_directories(tree.root(), String::new(), &mut found);
+    found
+}
+
+/// Adds a directory and the directories below it to `found`.
+fn collect_directories(node: NodeRef<'_>, key: String, found: &mut Vec<(NodeId, String)>) {
+    for child in node.children() {
+        if child.kind() == EntryKind::Directory {
+            let child_key = if key.is_empty() {
+                child.name().to_string()
+            } else {
+                format!("{}/{}", key, child.name())
+            };
+            collect_directories(child, child_key, found);
+        }
+    }
+    found.push((node.id(), key));
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::EntryMetadata;
+    use tempfile::TempDir;
+
+    fn tree_with_file(size: u64) -> TreeArena {
+        let mut tree = TreeArena::new(PathBuf::from("project"));
+        let root = tree.add(
+            None,
+            "project",
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let src = tree.add(
+            Some(root),
+            "src",
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        );
+        let metadata = EntryMetadata {
+            size,
+            ..EntryMetadata::default()
+        };
+        tree.add(Some(src), "main.rs", EntryKind::File, metadata);
+        tree.compute_disk_usage();
+        tree
+    }
+
+    #[test]
+    fn capture_keys_directories_by_relative_path() {
+        let snapshot = SizeSnapshot::capture(&tree_with_file(10));
+        assert_eq!(snapshot.size(""), Some(10));
+        assert_eq!(snapshot.size("src"), Some(10));
+        assert_eq!(snapshot.size("src/main.rs"), None);
+    }
+
+    #[test]
+    fn apply_sets_previous_sizes() {
+        let previous = SizeSnapshot::capture(&tree_with_file(10));
+        let mut tree = tree_with_file(25);
+        previous.apply(&mut tree);
+
+        let src = tree.root().children().next().unwrap();
+        assert_eq!(src.previous_usage(), Some(10));
+        assert_eq!(src.disk_usage(), Some(25));
+    }
+
+    #[test]
+    fn saved_snapshot_is_loaded_for_same_root_only() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("sizes.json");
+        let snapshot = SizeSnapshot::capture(&tree_with_file(10));
+        snapshot
+            .save(&file, Path::new("project"))
+            .expect("写入快照失败");
+
+        assert_eq!(
+            SizeSnapshot::load(&file, Path::new("project")),
+            Some(snapshot)
+        );
+        assert_eq!(SizeSnapshot::load(&file, Path::new("other")), None);
+    }
+}
@@ -0,0 +1,937 @@
+//! Statistics module: per-extension breakdown and scan summary.
+//!
+//! This module backs the `--report-extensions` option, which appends the
+//! most common extensions to the output, the `--depth-histogram` option,
+//! which appends the entries per level, the `--stats-json` option,
+//! which writes a JSON summary of the scanned tree for CI jobs that trend
+//! repository growth, and the `--count` option, which prints the totals
+//! instead of the tree:
+//!
+//! - **Counts**: directories and files, as in the `/RP` report
+//! - **Extensions**: file count and total size per extension
+//! - **Shape**: deepest level below the root, and entries and file sizes per level
+//! - **Run**: scan duration, pipeline (batch or streaming), batch scanner and thread count
+//! - **Filters**: entries left out by each filter (`--count` only)
+//!
+//! Statistics describe the tree as listed: files are only counted when they
+//! are part of the tree (`/F`, or `/DU` in batch mode), and entries listed
+//! only by `--show-filtered` are not counted.
+//!
+//! File: src/stats.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::arena::{NodeRef, TreeArena};
+use crate::config::{Config, ScanBackend, StatsTarget};
+use crate::error::OutputError;
+use crate::matching::RejectionCounts;
+use crate::output;
+use crate::scan::{EntryKind, StreamEntry, StreamStats};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Schema version of the statistics document.
+const STATS_SCHEMA_VERSION: &str = "treepp.stats.v1";
+
+/// Extension key of files without an extension.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::stats::{extension_key, NO_EXTENSION};
+///
+/// assert_eq!(extension_key(".gitignore"), NO_EXTENSION);
+/// ```
+pub const NO_EXTENSION: &str = "(none)";
+
+/// Number of extensions listed by `--report-extensions`.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::stats::{StatsCollector, REPORT_EXTENSION_LIMIT};
+///
+/// let mut stats = StatsCollector::default();
+/// for i in 0..20 {
+///     stats.record_file(&format!("file.ext{}", i), 1, 1);
+/// }
+/// assert_eq!(stats.top_extensions(REPORT_EXTENSION_LIMIT).len(), 10);
+/// ```
+pub const REPORT_EXTENSION_LIMIT: usize = 10;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// File count and total size of one extension.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::stats::StatsCollector;
+///
+/// let mut stats = StatsCollector::default();
+/// stats.record_file("a.txt", 10, 1);
+/// stats.record_file("b.txt", 5, 1);
+/// let txt = stats.extensions()[".txt"];
+/// assert_eq!((txt.count, txt.size), (2, 15));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionStats {
+    /// Number of files.
+    pub count: usize,
+    /// Total size of the files in bytes.
+    pub size: u64,
+}
+
+/// Entry counts and file sizes of one level of the tree.
+///
+/// A row of the `--depth-histogram` table.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::stats::StatsCollector;
+///
+/// let mut stats = StatsCollector::default();
+/// stats.record_directory(1);
+/// stats.record_file("notes.md", 30, 1);
+/// let level = stats.levels()[0];
+/// assert_eq!((level.directories, level.files, level.size), (1, 1, 30));
+/// ```
+// The schema description is given here so that the example above stays out
+// of the published schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(
+    description = "Entry counts and file sizes of one level of the tree.\n\n\
+    A row of the `--depth-histogram` table."
+)]
+pub struct LevelStats {
+    /// Number of directories on the level.
+    pub directories: usize,
+    /// Number of files on the level.
+    pub files: usize,
+    /// Total size of the files on the level in bytes.
+    pub size: u64,
+}
+
+impl LevelStats {
+    /// Returns the number of entries on the level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::stats::LevelStats;
+    ///
+    /// let level = LevelStats { directories: 2, files: 5, size: 0 };
+    /// assert_eq!(level.entries(), 7);
+    /// ```
+    #[must_use]
+    pub const fn entries(&self) -> usize {
+        self.directories + self.files
+    }
+}
+
+/// A row of the `--report-extensions` table.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::stats::{ExtensionEntry, StatsCollector};
+///
+/// let mut stats = StatsCollector::default();
+/// stats.record_file("build.sh", 12, 1);
+/// assert_eq!(
+///     stats.top_extensions(1),
+///     [ExtensionEntry { extension: ".sh".to_string(), count: 1, size: 12 }]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(description = "A row of the `--report-extensions` table.")]
+pub struct ExtensionEntry {
+    /// Lowercase extension with a leading dot, or `NO_EXTENSION`.
+    pub extension: String,
+    /// Number of files.
+    pub count: usize,
+    /// Total size of the files in bytes.
+    pub size: u64,
+}
+
+/// Collects per-extension and per-level statistics.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::stats::StatsCollector;
+///
+/// let mut stats = StatsCollector::default();
+/// stats.record_file("main.rs", 120, 1);
+/// stats.record_file("lib.RS", 80, 2);
+/// assert_eq!(stats.extensions()[".rs"].count, 2);
+/// assert_eq!(stats.max_depth(), 2);
+/// assert_eq!(stats.levels()[1].size, 80);
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StatsCollector {
+    /// Statistics keyed by lowercase extension (e.g. `.rs`).
+    extensions: BTreeMap<String, ExtensionStats>,
+    /// Statistics per level; index 0 holds level 1, the entries directly
+    /// below the root.
+    levels: Vec<LevelStats>,
+}
+
+impl StatsCollector {
+    /// Collects statistics from a scanned tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The tree produced by a batch scan.
+    ///
+    /// # Returns
+    ///
+    /// The collected statistics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    /// use treepp::stats::StatsCollector;
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let src = arena.add(Some(root), "src", EntryKind::Directory, EntryMetadata::default());
+    /// let size = EntryMetadata { size: 64, ..Default::default() };
+    /// arena.add(Some(src), "main.rs", EntryKind::File, size);
+    ///
+    /// let stats = StatsCollector::from_tree(&arena);
+    /// assert_eq!(stats.max_depth(), 2);
+    /// assert_eq!(stats.total_size(), 64);
+    /// ```
+    #[must_use]
+    pub fn from_tree(tree: &TreeArena) -> Self {
+        Self::from_node(tree.root())
+    }
+
+    /// Collects statistics from the subtree below `root`.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The node whose descendants are counted; its children are at level 1.
+    ///
+    /// # Returns
+    ///
+    /// The collected statistics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::arena::TreeArena;
+    /// use treepp::scan::{EntryKind, EntryMetadata};
+    /// use treepp::stats::StatsCollector;
+    ///
+    /// let mut arena = TreeArena::new(PathBuf::from("project"));
+    /// let root = arena.add(None, "project", EntryKind::Directory, EntryMetadata::default());
+    /// let src = arena.add(Some(root), "src", EntryKind::Directory, EntryMetadata::default());
+    /// let size = EntryMetadata { size: 64, ..Default::default() };
+    /// arena.add(Some(src), "main.rs", EntryKind::File, size);
+    ///
+    /// let stats = StatsCollector::from_node(arena.get(src));
+    /// assert_eq!(stats.max_depth(), 1);
+    /// assert_eq!(stats.levels()[0].files, 1);
+    /// ```
+    #[must_use]
+    pub fn from_node(root: NodeRef<'_>) -> Self {
+        let mut stats = Self::default();
+        stats.collect(root, 0);
+        stats
+    }
+
+    /// Records the children of `node`, which is at level `depth`.
+    fn collect(&mut self, node: NodeRef<'_>, depth: usize) {
+        for child in node.children().filter(|c| c.filtered().is_none()) {
+            match child.kind() {
+                EntryKind::Directory => {
+                    self.record_directory(depth + 1);
+                    self.collect(child, depth + 1);
+                }
+                EntryKind::File => {
+                    self.record_file(child.name(), child.metadata().size, depth + 1);
+                }
+            }
+        }
+    }
+
+    /// Records an entry emitted by a streaming scan.
+    ///
+    /// Entries listed only by `--show-filtered` are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The emitted entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use treepp::scan::{EntryKind, EntryMetadata, StreamEntry};
+    /// use treepp::stats::StatsCollector;
+    ///
+    /// let entry = StreamEntry {
+    ///     path: PathBuf::from("src/main.rs"),
+    ///     name: "main.rs".to_string(),
+    ///     kind: EntryKind::File,
+    ///     metadata: EntryMetadata { size: 100, ..Default::default() },
+    ///     depth: 1,
+    ///     is_last: true,
+    ///     is_file: true,
+    ///     has_more_dirs: false,
+    ///     filtered: None,
+    ///     collapsed: None,
+    ///     error: None,
+    /// };
+    /// let mut stats = StatsCollector::default();
+    /// stats.record_entry(&entry);
+    /// assert_eq!(stats.max_depth(), 2);
+    /// assert_eq!(stats.extensions()[".rs"].size, 100);
+    /// ```
+    pub fn record_entry(&mut self, entry: &StreamEntry) {
+        if entry.filtered.is_some() {
+            return;
+        }
+        match entry.kind {
+            EntryKind::Directory => self.record_directory(entry.depth + 1),
+            EntryKind::File => self.record_file(&entry.name, entry.metadata.size, entry.depth + 1),
+        }
+    }
+
+    /// Records a directory at level `depth`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::stats::StatsCollector;
+    ///
+    /// let mut stats = StatsCollector::default();
+    /// stats.record_directory(3);
+    /// assert_eq!(stats.max_depth(), 3);
+    /// assert_eq!(stats.levels()[2].directories, 1);
+    /// ```
+    pub fn record_directory(&mut self, depth: usize) {
+        self.level_mut(depth).directories += 1;
+    }
+
+    /// Records a file at level `depth`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - File name, used for the extension.
+    /// * `size` - File size in bytes.
+    /// * `depth` - Level of the file; entries directly below the root are at level 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::stats::StatsCollector;
+    ///
+    /// let mut stats = StatsCollector::default();
+    /// stats.record_file("README", 2048, 1);
+    /// assert_eq!(stats.extensions()["(none)"].size, 2048);
+    /// ```
+    pub fn record_file(&mut self, name: &str, size: u64, depth: usize) {
+        let level = self.level_mut(depth);
+        level.files += 1;
+        level.size += size;
+        let stats = self.extensions.entry(extension_key(name)).or_default();
+        stats.count += 1;
+        stats.size += size;
+    }
+
+    /// Returns the statistics keyed by extension.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::stats::StatsCollector;
+    ///
+    /// let mut stats = StatsCollector::default();
+    /// stats.record_file("a.toml", 1, 1);
+    /// stats.record_file("b.rs", 1, 1);
+    /// let keys: Vec<_> = stats.extensions().keys().collect();
+    /// assert_eq!(keys, [".rs", ".toml"]);
+    /// ```
+    #[must_use]
+    pub fn extensions(&self) -> &BTreeMap<String, ExtensionStats> {
+        &self.extensions
+    }
+
+    /// Returns the deepest level seen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::stats::StatsCollector;
+    ///
+    /// let mut stats = StatsCollector::default();
+    /// assert_eq!(stats.max_depth(), 0);
+    /// stats.record_file("deep.txt", 1, 4);
+    /// assert_eq!(stats.max_depth(), 4);
+    /// ```
+    #[must_use]
+    pub fn max_depth(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Returns the statistics per level, starting with level 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::stats::StatsCollector;
+    ///
+    /// let mut stats = StatsCollector::default();
+    /// stats.record_file("a.txt", 1, 2);
+    /// assert_eq!(stats.levels().len(), 2);
+    /// assert_eq!(stats.levels()[0].entries(), 0);
+    /// ```
+    #[must_use]
+    pub fn levels(&self) -> &[LevelStats] {
+        &self.levels
+    }
+
+    /// Returns the statistics of level `depth`, adding the levels up to it.
+    fn level_mut(&mut self, depth: usize) -> &mut LevelStats {
+        if self.levels.len() < depth {
+            self.levels.resize(depth, LevelStats::default());
+        }
+        &mut self.levels[depth - 1]
+    }
+
+    /// Returns the total size of all recorded files in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::stats::StatsCollector;
+    ///
+    /// let mut stats = StatsCollector::default();
+    /// stats.record_file("a.bin", 1000, 1);
+    /// stats.record_file("b.txt", 24, 2);
+    /// assert_eq!(stats.total_size(), 1024);
+    /// ```
+    #[must_use]
+    pub fn total_size(&self) -> u64 {
+        self.extensions.values().map(|stats| stats.size).sum()
+    }
+
+    /// Returns the most common extensions.
+    ///
+    /// Extensions are ordered by file count, then by total size (both
+    /// descending), then by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of extensions returned.
+    ///
+    /// # Returns
+    ///
+    /// Up to `limit` rows, most common first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::stats::StatsCollector;
+    ///
+    /// let mut stats = StatsCollector::default();
+    /// stats.record_file("a.rs", 1, 1);
+    /// stats.record_file("b.rs", 1, 1);
+    /// stats.record_file("c.md", 1, 1);
+    /// let top = stats.top_extensions(1);
+    /// assert_eq!(top.len(), 1);
+    /// assert_eq!(top[0].extension, ".rs");
+    /// ```
+    #[must_use]
+    pub fn top_extensions(&self, limit: usize) -> Vec<ExtensionEntry> {
+        let mut rows: Vec<_> = self
+            .extensions
+            .iter()
+            .map(|(extension, stats)| ExtensionEntry {
+                extension: extension.clone(),
+                count: stats.count,
+                size: stats.size,
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(b.size.cmp(&a.size))
+                .then_with(|| a.extension.cmp(&b.extension))
+        });
+        rows.truncate(limit);
+        rows
+    }
+}
+
+/// Totals printed by `--count`.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::matching::RejectionCounts;
+/// use treepp::stats::CountSummary;
+///
+/// let summary = CountSummary {
+///     directories: 3,
+///     files: 12,
+///     total_size: 4096,
+///     max_depth: 2,
+///     rejected: RejectionCounts::default(),
+/// };
+/// assert_eq!(summary.rejected.total(), 0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountSummary {
+    /// Number of directories.
+    pub directories: usize,
+    /// Number of files.
+    pub files: usize,
+    /// Total size of the files in bytes.
+    pub total_size: u64,
+    /// Deepest level below the root.
+    pub max_depth: usize,
+    /// Entries left out by each filter.
+    pub rejected: RejectionCounts,
+}
+
+impl CountSummary {
+    /// Builds the totals of a finished streaming scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `collector` - Statistics collected from the emitted entries.
+    /// * `stats` - Statistics reported by the scan.
+    ///
+    /// # Returns
+    ///
+    /// The totals.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use treepp::matching::RejectionCounts;
+    /// use treepp::scan::StreamStats;
+    /// use treepp::stats::{CountSummary, StatsCollector};
+    ///
+    /// let mut collector = StatsCollector::default();
+    /// collector.record_file("main.rs", 512, 1);
+    /// let stats = StreamStats {
+    ///     duration: Duration::from_millis(5),
+    ///     directory_count: 0,
+    ///     file_count: 1,
+    ///     truncated_dirs: 0,
+    ///     unread_dirs: 0,
+    ///     output_truncated: false,
+    ///     unreadable_dirs: Vec::new(),
+    ///     vanished_entries: 0,
+    ///     rejected: RejectionCounts::default(),
+    /// };
+    /// let summary = CountSummary::new(&collector, &stats);
+    /// assert_eq!((summary.files, summary.total_size, summary.max_depth), (1, 512, 1));
+    /// ```
+    #[must_use]
+    pub fn new(collector: &StatsCollector, stats: &StreamStats) -> Self {
+        Self {
+            directories: stats.directory_count,
+            files: stats.file_count,
+            total_size: collector.total_size(),
+            max_depth: collector.max_depth(),
+            rejected: stats.rejected.clone(),
+        }
+    }
+}
+
+/// Machine-readable summary of a scan (`--stats-json`).
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use std::time::Duration;
+/// use treepp::config::Config;
+/// use treepp::stats::{ScanSummary, StatsCollector};
+///
+/// let config = Config::with_root(PathBuf::from("project"));
+/// let summary = ScanSummary::new(&config, StatsCollector::default(), 4, 9, Duration::ZERO);
+/// assert_eq!(summary.backend, "streaming");
+/// assert_eq!(summary.scanner, None);
+/// assert_eq!(summary.files, 9);
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanSummary {
+    /// Schema version identifier.
+    pub schema: &'static str,
+    /// Scanned root path.
+    pub root: String,
+    /// Pipeline used for the scan (`batch` or `streaming`).
+    pub backend: &'static str,
+    /// Directory traversal of a batch scan (`walk`, `parallel` or
+    /// `channel`); not set for streaming scans.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scanner: Option<&'static str>,
+    /// Number of scanning threads.
+    pub threads: usize,
+    /// Number of directories.
+    pub directories: usize,
+    /// Number of files.
+    pub files: usize,
+    /// Deepest level below the root.
+    pub max_depth: usize,
+    /// Scan duration in milliseconds.
+    pub duration_ms: f64,
+    /// File count and total size per extension.
+    pub extensions: BTreeMap<String, ExtensionStats>,
+}
+
+impl ScanSummary {
+    /// Builds the summary of a finished scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration the scan ran with.
+    /// * `collector` - Statistics collected from the tree.
+    /// * `directories` - Number of directories, as reported by the scan.
+    /// * `files` - Number of files, as reported by the scan.
+    /// * `duration` - Scan duration.
+    ///
+    /// # Returns
+    ///
+    /// The summary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use std::time::Duration;
+    /// use treepp::config::Config;
+    /// use treepp::stats::{ScanSummary, StatsCollector};
+    ///
+    /// let mut config = Config::with_root(PathBuf::from("project"));
+    /// config.batch_mode = true;
+    /// let duration = Duration::from_millis(3);
+    /// let summary = ScanSummary::new(&config, StatsCollector::default(), 2, 5, duration);
+    /// assert_eq!(summary.backend, "batch");
+    /// assert_eq!(summary.duration_ms, 3.0);
+    /// ```
+    #[must_use]
+    pub fn new(
+        config: &Config,
+        collector: StatsCollector,
+        directories: usize,
+        files: usize,
+        duration: Duration,
+    ) -> Self {
+        let (backend, scanner, threads) = if config.batch_mode {
+            let scanner = config.scan.backend.resolve(config.scan.thread_count);
+            let threads = match scanner {
+                ScanBackend::Walk => 1,
+                _ => config.scan.thread_count.get(),
+            };
+            ("batch", Some(scanner), threads)
+        } else {
+            ("streaming", None, 1)
+        };
+        Self {
+            schema: STATS_SCHEMA_VERSION,
+            root: config.root_path.to_string_lossy().into_owned(),
+            backend,
+            scanner: scanner.map(|scanner| scanner.as_str()),
+            threads,
+            directories,
+            files,
+            max_depth: collector.max_depth(),
+            duration_ms: duration.as_secs_f64() * 1000.0,
+            extensions: collector.extensions,
+        }
+    }
+
+    /// Serializes the summary to pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputError::SerializationFailed` if serialization fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use std::time::Duration;
+    /// use treepp::config::Config;
+    /// use treepp::stats::{ScanSummary, StatsCollector};
+    ///
+    /// let config = Config::with_root(PathBuf::from("project"));
+    /// let summary = ScanSummary::new(&config, StatsCollector::default(), 0, 0, Duration::ZERO);
+    /// let json = summary.to_json().unwrap();
+    /// assert!(json.contains("\"schema\": \"treepp.stats.v1\""));
+    /// assert!(!json.contains("scanner"));
+    /// ```
+    pub fn to_json(&self) -> Result<String, OutputError> {
+        serde_json::to_string_pretty(self).map_err(|e| OutputError::json_error(e.to_string()))
+    }
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+/// Returns the statistics key of a file name.
+///
+/// # Arguments
+///
+/// * `name` - The file name.
+///
+/// # Returns
+///
+/// The lowercase extension with a leading dot (e.g. `.rs`), or
+/// `NO_EXTENSION` for names without one (including dotfiles such as
+/// `.gitignore`).
+///
+/// # Examples
+///
+/// ```
+/// use treepp::stats::{extension_key, NO_EXTENSION};
+///
+/// assert_eq!(extension_key("Main.RS"), ".rs");
+/// assert_eq!(extension_key("Makefile"), NO_EXTENSION);
+/// ```
+#[must_use]
+pub fn extension_key(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy().to_lowercase()))
+        .unwrap_or_else(|| NO_EXTENSION.to_string())
+}
+
+/// Writes a scan summary to its `--stats-json` target.
+///
+/// # Arguments
+///
+/// * `summary` - The summary to write.
+/// * `target` - Standard error or a file.
+///
+/// # Errors
+///
+/// Returns `OutputError` if serialization or writing fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use std::time::Duration;
+/// use treepp::config::{Config, StatsTarget};
+/// use treepp::stats::{write_summary, ScanSummary, StatsCollector};
+///
+/// let config = Config::with_root(PathBuf::from("project"));
+/// let summary = ScanSummary::new(&config, StatsCollector::default(), 0, 0, Duration::ZERO);
+/// write_summary(&summary, &StatsTarget::File(PathBuf::from("stats.json"))).unwrap();
+/// ```
+pub fn write_summary(summary: &ScanSummary, target: &StatsTarget) -> Result<(), OutputError> {
+    let json = summary.to_json()?;
+    match target {
+        StatsTarget::Stderr => {
+            eprintln!("{}", json);
+            Ok(())
+        }
+        StatsTarget::File(path) => output::write_file(&format!("{}\n", json), path),
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::FilterReason;
+    use crate::scan::{EntryMetadata, TreeNode};
+    use std::path::PathBuf;
+
+    fn file(path: &str, size: u64) -> TreeNode {
+        TreeNode::new(
+            PathBuf::from(path),
+            EntryKind::File,
+            EntryMetadata {
+                size,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn dir(path: &str, children: Vec<TreeNode>) -> TreeNode {
+        TreeNode::with_children(
+            PathBuf::from(path),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+            children,
+        )
+    }
+
+    #[test]
+    fn extension_key_lowercases_and_handles_missing_extension() {
+        assert_eq!(extension_key("a.TXT"), ".txt");
+        assert_eq!(extension_key("archive.tar.gz"), ".gz");
+        assert_eq!(extension_key("README"), NO_EXTENSION);
+        assert_eq!(extension_key(".gitignore"), NO_EXTENSION);
+    }
+
+    #[test]
+    fn collector_from_tree_counts_extensions_and_depth() {
+        let root = dir(
+            "root",
+            vec![
+                dir(
+                    "root/src",
+                    vec![file("root/src/main.rs", 20), file("root/src/lib.rs", 10)],
+                ),
+                file("root/README.md", 5),
+            ],
+        );
+        let stats = StatsCollector::from_tree(&TreeArena::from_tree(&root));
+
+        assert_eq!(
+            stats.extensions()[".rs"],
+            ExtensionStats { count: 2, size: 30 }
+        );
+        assert_eq!(
+            stats.extensions()[".md"],
+            ExtensionStats { count: 1, size: 5 }
+        );
+        assert_eq!(stats.max_depth(), 2);
+    }
+
+    #[test]
+    fn collector_skips_filtered_entries() {
+        let mut hidden = file("root/secret.key", 100);
+        hidden.filtered = Some(FilterReason::Hidden);
+        let root = dir("root", vec![hidden, file("root/a.txt", 1)]);
+        let stats = StatsCollector::from_tree(&TreeArena::from_tree(&root));
+
+        assert!(!stats.extensions().contains_key(".key"));
+        assert_eq!(stats.extensions()[".txt"].count, 1);
+    }
+
+    #[test]
+    fn top_extensions_orders_by_count_then_size() {
+        let mut stats = StatsCollector::default();
+        stats.record_file("a.md", 500, 1);
+        stats.record_file("a.rs", 10, 1);
+        stats.record_file("b.rs", 10, 1);
+        stats.record_file("a.txt", 900, 1);
+
+        let top = stats.top_extensions(REPORT_EXTENSION_LIMIT);
+        let order: Vec<_> = top.iter().map(|e| e.extension.as_str()).collect();
+        assert_eq!(order, [".rs", ".txt", ".md"]);
+        assert_eq!(top[0].count, 2);
+        assert_eq!(top[0].size, 20);
+        assert_eq!(stats.top_extensions(2).len(), 2);
+    }
+
+    #[test]
+    fn summary_serializes_backend_and_extensions() {
+        let mut collector = StatsCollector::default();
+        collector.record_file("main.rs", 42, 1);
+        let config = Config::with_root(PathBuf::from("."));
+        let summary = ScanSummary::new(&config, collector, 0, 1, Duration::from_millis(3));
+        let json = summary.to_json().unwrap();
+
+        assert!(json.contains(STATS_SCHEMA_VERSION));
+        assert!(json.contains("\"backend\": \"streaming\""));
+        assert!(json.contains("\".rs\""));
+        assert!(json.contains("\"size\": 42"));
+    }
+
+    #[test]
+    fn summary_reports_batch_scanner() {
+        let mut config = Config::with_root(PathBuf::from("."));
+        config.batch_mode = true;
+        config.scan.backend = ScanBackend::Walk;
+        let summary = ScanSummary::new(&config, StatsCollector::default(), 0, 0, Duration::ZERO);
+        assert_eq!(summary.scanner, Some("walk"));
+        assert_eq!(summary.threads, 1, "walk 只使用一个线程");
+
+        config.batch_mode = false;
+        let summary = ScanSummary::new(&config, StatsCollector::default(), 0, 0, Duration::ZERO);
+        assert!(
+            !summary.to_json().unwrap().contains("scanner"),
+            "流式扫描不报告扫描器"
+        );
+    }
+
+    #[test]
+    fn collector_sums_file_sizes() {
+        let mut stats = StatsCollector::default();
+        stats.record_file("a.rs", 10, 1);
+        stats.record_file("b.md", 32, 2);
+        stats.record_directory(3);
+
+        assert_eq!(stats.total_size(), 42);
+        assert_eq!(stats.max_depth(), 3);
+    }
+
+    #[test]
+    fn collector_counts_entries_per_level() {
+        let root = dir(
+            "root",
+            vec![
+                dir(
+                    "root/src",
+                    vec![file("root/src/main.rs", 20), dir("root/src/bin", vec![])],
+                ),
+                file("root/README.md", 7),
+                file("root/LICENSE", 3),
+            ],
+        );
+        let stats = StatsCollector::from_tree(&TreeArena::from_tree(&root));
+
+        assert_eq!(
+            stats.levels(),
+            [
+                LevelStats {
+                    directories: 1,
+                    files: 2,
+                    size: 10
+                },
+                LevelStats {
+                    directories: 1,
+                    files: 1,
+                    size: 20
+                },
+            ]
+        );
+        assert_eq!(stats.levels()[0].entries(), 3);
+    }
+
+    #[test]
+    fn collector_fills_levels_skipped_by_streaming() {
+        let mut stats = StatsCollector::default();
+        stats.record_file("deep.rs", 5, 3);
+
+        assert_eq!(stats.levels().len(), 3);
+        assert_eq!(stats.levels()[0], LevelStats::default());
+        assert_eq!(stats.levels()[2].files, 1);
+    }
+}
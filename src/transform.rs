@@ -0,0 +1,233 @@
+//! Transform module: post-processing hooks for scanned trees.
+//!
+//! Code that embeds tree++ can adjust a scanned tree before it is rendered
+//! without changing the scanner:
+//!
+//! - **Hook**: `TreeTransform` receives the complete `TreeNode` tree and may
+//!   add notes to entries (`TreeNode::annotations`) or remove entries
+//! - **Entry point**: `scan::scan_with` runs the transforms after sorting
+//! - **Example**: `CargoWorkspaces` marks the roots of Cargo workspaces
+//!
+//! Notes are shown in brackets after the name in text output.
+//!
+//! File: src/transform.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::fs;
+use std::path::Path;
+
+use crate::scan::{EntryKind, TreeNode};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// File name of a Cargo manifest.
+const CARGO_MANIFEST: &str = "Cargo.toml";
+
+// ============================================================================
+// Hook
+// ============================================================================
+
+/// A post-processing step applied to a scanned tree before rendering.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use treepp::scan::{EntryKind, EntryMetadata, TreeNode};
+/// use treepp::transform::TreeTransform;
+///
+/// /// Removes log files from the listing.
+/// struct DropLogs;
+///
+/// impl TreeTransform for DropLogs {
+///     fn apply(&self, tree: &mut TreeNode) {
+///         tree.children.retain(|child| !child.name.ends_with(".log"));
+///         for child in &mut tree.children {
+///             self.apply(child);
+///         }
+///     }
+/// }
+///
+/// let mut root = TreeNode::new(
+///     PathBuf::from("app"),
+///     EntryKind::Directory,
+///     EntryMetadata::default(),
+/// );
+/// root.children.push(TreeNode::new(
+///     PathBuf::from("app/debug.log"),
+///     EntryKind::File,
+///     EntryMetadata::default(),
+/// ));
+/// DropLogs.apply(&mut root);
+/// assert!(root.children.is_empty());
+/// ```
+pub trait TreeTransform {
+    /// Rewrites the tree in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The root node of the scanned tree.
+    fn apply(&self, tree: &mut TreeNode);
+}
+
+// ============================================================================
+// Cargo Workspaces
+// ============================================================================
+
+/// Marks directories whose `Cargo.toml` declares a workspace.
+///
+/// The note gives the number of member packages, found by expanding the
+/// `workspace.members` patterns and keeping the directories that hold a
+/// manifest. Manifests are read from disk, so workspace roots are found
+/// whether or not files are listed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use treepp::scan::{EntryKind, EntryMetadata, TreeNode};
+/// use treepp::transform::{CargoWorkspaces, TreeTransform};
+///
+/// let mut root = TreeNode::new(
+///     PathBuf::from("."),
+///     EntryKind::Directory,
+///     EntryMetadata::default(),
+/// );
+/// CargoWorkspaces.apply(&mut root);
+/// println!("{:?}", root.annotations);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CargoWorkspaces;
+
+impl TreeTransform for CargoWorkspaces {
+    fn apply(&self, tree: &mut TreeNode) {
+        if tree.kind != EntryKind::Directory {
+            return;
+        }
+        if let Some(members) = workspace_members(&tree.path) {
+            let noun = if members == 1 { "member" } else { "members" };
+            tree.annotations
+                .push(format!("cargo workspace, {} {}", members, noun));
+        }
+        for child in &mut tree.children {
+            self.apply(child);
+        }
+    }
+}
+
+/// Counts the member packages of the workspace declared in `dir`.
+///
+/// # Returns
+///
+/// The number of member directories holding a `Cargo.toml`, or `None` if
+/// `dir` has no manifest, the manifest cannot be parsed, or it has no
+/// `[workspace]` table.
+fn workspace_members(dir: &Path) -> Option<usize> {
+    let text = fs::read_to_string(dir.join(CARGO_MANIFEST)).ok()?;
+    let manifest: toml::Table = text.parse().ok()?;
+    let workspace = manifest.get("workspace")?.as_table()?;
+    let patterns = workspace
+        .get("members")
+        .and_then(|members| members.as_array())
+        .map(|members| {
+            members
+                .iter()
+                .filter_map(|member| member.as_str())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let count = patterns
+        .iter()
+        .filter_map(|pattern| glob::glob(&dir.join(pattern).to_string_lossy()).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|member| member.join(CARGO_MANIFEST).is_file())
+        .count();
+    Some(count)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::EntryMetadata;
+    use tempfile::TempDir;
+
+    fn write_manifest(dir: &Path, content: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join(CARGO_MANIFEST), content).unwrap();
+    }
+
+    fn directory(path: &Path) -> TreeNode {
+        TreeNode::new(
+            path.to_path_buf(),
+            EntryKind::Directory,
+            EntryMetadata::default(),
+        )
+    }
+
+    #[test]
+    fn should_count_workspace_members_from_patterns() {
+        let dir = TempDir::new().unwrap();
+        write_manifest(
+            dir.path(),
+            "[workspace]\nmembers = [\"crates/*\", \"tool\"]\n",
+        );
+        write_manifest(
+            &dir.path().join("crates").join("core"),
+            "[package]\nname = \"core\"\n",
+        );
+        write_manifest(
+            &dir.path().join("crates").join("cli"),
+            "[package]\nname = \"cli\"\n",
+        );
+        write_manifest(&dir.path().join("tool"), "[package]\nname = \"tool\"\n");
+        fs::create_dir_all(dir.path().join("crates").join("docs")).unwrap();
+
+        assert_eq!(
+            workspace_members(dir.path()),
+            Some(3),
+            "应只统计含清单的成员"
+        );
+    }
+
+    #[test]
+    fn should_ignore_packages_and_invalid_manifests() {
+        let dir = TempDir::new().unwrap();
+        write_manifest(&dir.path().join("pkg"), "[package]\nname = \"pkg\"\n");
+        write_manifest(&dir.path().join("broken"), "[workspace\n");
+
+        assert_eq!(workspace_members(&dir.path().join("pkg")), None);
+        assert_eq!(workspace_members(&dir.path().join("broken")), None);
+        assert_eq!(workspace_members(&dir.path().join("missing")), None);
+    }
+
+    #[test]
+    fn should_annotate_nested_workspace_roots() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("vendor").join("ws");
+        write_manifest(&nested, "[workspace]\nmembers = [\"a\"]\n");
+        write_manifest(&nested.join("a"), "[package]\nname = \"a\"\n");
+
+        let mut vendor = directory(&dir.path().join("vendor"));
+        vendor.children.push(directory(&nested));
+        let mut root = directory(dir.path());
+        root.children.push(vendor);
+
+        CargoWorkspaces.apply(&mut root);
+        assert!(root.annotations.is_empty(), "非工作区目录不应有注释");
+        assert_eq!(
+            root.children[0].children[0].annotations,
+            vec!["cargo workspace, 1 member".to_string()]
+        );
+    }
+}
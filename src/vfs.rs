@@ -0,0 +1,526 @@
+//! File system module: the scanner's access to the disk.
+//!
+//! The directory scan reads directories and inspects entries only through
+//! the `FileSystem` trait, so its handling of failures can be tested with a
+//! file system that fails on purpose:
+//!
+//! - **Listing**: the entries of a directory, each with its type and, when
+//!   asked for, its metadata; an entry that cannot be inspected, usually
+//!   because it was deleted or renamed after it was listed, is reported on
+//!   its own as a `VanishedEntry` instead of failing the whole listing
+//! - **Inspection**: the type and metadata of a single path
+//! - **`OsFileSystem`**: the real file system, via `std::fs` and
+//!   `\\?\`-prefixed paths past `MAX_PATH`
+//! - **`MemoryFileSystem`**: a tree of directories and files held in memory,
+//!   scanned with `scan::scan_on` where there is no disk to scan, such as
+//!   the `wasm32-wasip1` playground and fuzzing harnesses
+//!
+//! Path lists (`--paths-from`) and `--explain` look up single paths and keep
+//! using `std::fs` directly.
+//!
+//! File: src/vfs.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs::{self, DirEntry};
+use std::io;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+
+use crate::scan::{EntryKind, EntryMetadata, is_hidden, read_link_target};
+use crate::winpath;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A directory entry as listed by a `FileSystem`, or read from the scan cache.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::scan::EntryKind;
+/// use treepp::vfs::{FileSystem, MemoryFileSystem};
+///
+/// let fs = MemoryFileSystem::default().with_file("/demo/notes.txt", 42);
+/// let entry = fs.inspect(Path::new("/demo/notes.txt")).unwrap();
+/// assert_eq!(entry.kind, EntryKind::File);
+/// assert_eq!(entry.metadata.size, 42);
+/// assert!(!entry.hidden);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ScannedEntry {
+    /// Path of the entry.
+    pub path: PathBuf,
+    /// Type of the entry.
+    pub kind: EntryKind,
+    /// Whether the entry has the hidden attribute (`false` if metadata was not read).
+    pub hidden: bool,
+    /// Size and dates (empty if metadata was not read).
+    pub metadata: EntryMetadata,
+}
+
+/// An entry that was listed but could not be inspected.
+///
+/// Usually the entry was deleted or renamed between the listing and the
+/// inspection; the scanner leaves such entries out and counts them.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::path::PathBuf;
+/// use treepp::vfs::VanishedEntry;
+///
+/// let vanished = VanishedEntry {
+///     path: PathBuf::from("build/tmp.o"),
+///     error: io::ErrorKind::NotFound.into(),
+/// };
+/// assert_eq!(vanished.error.kind(), io::ErrorKind::NotFound);
+/// ```
+#[derive(Debug)]
+pub struct VanishedEntry {
+    /// Path of the entry as listed.
+    pub path: PathBuf,
+    /// The error from inspecting it.
+    pub error: io::Error,
+}
+
+/// Source of directory listings for the scanner.
+///
+/// Paths are given in their user-facing form; implementations add any
+/// prefix they need.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::path::Path;
+/// use treepp::vfs::{FileSystem, MemoryFileSystem};
+///
+/// let fs: &dyn FileSystem = &MemoryFileSystem::default().with_dir("/demo/src");
+/// assert!(fs.inspect(Path::new("/demo")).is_ok());
+/// let err = fs.read_dir(Path::new("/missing"), false).unwrap_err();
+/// assert_eq!(err.kind(), io::ErrorKind::NotFound);
+/// ```
+pub trait FileSystem: Debug + Send + Sync {
+    /// Lists the entries of a directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The directory.
+    /// * `needs_metadata` - Whether sizes, dates and attributes are needed;
+    ///   without them only the type of each entry is read.
+    ///
+    /// # Returns
+    ///
+    /// Each entry in listing order, or a `VanishedEntry` for an entry that
+    /// could not be inspected.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the directory cannot be read, or if the
+    /// listing fails before it is complete.
+    fn read_dir(
+        &self,
+        path: &Path,
+        needs_metadata: bool,
+    ) -> io::Result<Vec<Result<ScannedEntry, VanishedEntry>>>;
+
+    /// Reads the type and metadata of a path, following links.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the path cannot be inspected.
+    fn inspect(&self, path: &Path) -> io::Result<ScannedEntry>;
+}
+
+/// The real file system.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use treepp::vfs::{FileSystem, OsFileSystem};
+///
+/// let entries = OsFileSystem.read_dir(Path::new("."), false).unwrap();
+/// for entry in entries.into_iter().flatten() {
+///     println!("{}", entry.path.display());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsFileSystem;
+
+impl FileSystem for OsFileSystem {
+    fn read_dir(
+        &self,
+        path: &Path,
+        needs_metadata: bool,
+    ) -> io::Result<Vec<Result<ScannedEntry, VanishedEntry>>> {
+        fs::read_dir(winpath::to_extended(path))?
+            .map(|entry| {
+                let entry = entry?;
+                Ok(
+                    inspect_entry(&entry, path, needs_metadata).map_err(|error| VanishedEntry {
+                        path: path.join(entry.file_name()),
+                        error,
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    fn inspect(&self, path: &Path) -> io::Result<ScannedEntry> {
+        let meta = fs::metadata(winpath::to_extended(path))?;
+        Ok(ScannedEntry {
+            path: path.to_path_buf(),
+            kind: EntryKind::from_metadata(&meta),
+            hidden: is_hidden(&meta),
+            metadata: EntryMetadata::from_fs_metadata(&meta),
+        })
+    }
+}
+
+/// A file system held in memory.
+///
+/// Adding an entry also adds its missing parent directories. Entries are
+/// listed in name order and carry only their type and size: no dates,
+/// attributes or links.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::vfs::{FileSystem, MemoryFileSystem};
+///
+/// let fs = MemoryFileSystem::default()
+///     .with_file("/demo/src/main.rs", 120)
+///     .with_dir("/demo/docs");
+/// let entries = fs.read_dir(Path::new("/demo"), false).unwrap();
+/// assert_eq!(entries.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFileSystem {
+    /// Type and size of each entry, keyed by path.
+    entries: BTreeMap<PathBuf, (EntryKind, u64)>,
+}
+
+impl MemoryFileSystem {
+    /// Adds a directory and its missing parents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use treepp::scan::EntryKind;
+    /// use treepp::vfs::{FileSystem, MemoryFileSystem};
+    ///
+    /// let fs = MemoryFileSystem::default().with_dir("/demo/a/b");
+    /// assert_eq!(fs.inspect(Path::new("/demo/a")).unwrap().kind, EntryKind::Directory);
+    /// assert_eq!(fs.read_dir(Path::new("/demo/a/b"), false).unwrap().len(), 0);
+    /// ```
+    #[must_use]
+    pub fn with_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.insert(path.into(), EntryKind::Directory, 0);
+        self
+    }
+
+    /// Adds a file of `size` bytes and its missing parent directories.
+    ///
+    /// A directory already at `path` is replaced by the file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use treepp::scan::EntryKind;
+    /// use treepp::vfs::{FileSystem, MemoryFileSystem};
+    ///
+    /// let fs = MemoryFileSystem::default()
+    ///     .with_dir("/demo/out")
+    ///     .with_file("/demo/out", 7);
+    /// let entry = fs.inspect(Path::new("/demo/out")).unwrap();
+    /// assert_eq!((entry.kind, entry.metadata.size), (EntryKind::File, 7));
+    /// ```
+    #[must_use]
+    pub fn with_file(mut self, path: impl Into<PathBuf>, size: u64) -> Self {
+        self.insert(path.into(), EntryKind::File, size);
+        self
+    }
+
+    /// Inserts an entry, adding its missing parents as directories.
+    fn insert(&mut self, path: PathBuf, kind: EntryKind, size: u64) {
+        for parent in path.ancestors().skip(1) {
+            if !parent.as_os_str().is_empty() {
+                self.entries
+                    .entry(parent.to_path_buf())
+                    .or_insert((EntryKind::Directory, 0));
+            }
+        }
+        self.entries.insert(path, (kind, size));
+    }
+
+    /// Builds the listing of an entry.
+    fn scanned(path: &Path, kind: EntryKind, size: u64, needs_metadata: bool) -> ScannedEntry {
+        ScannedEntry {
+            path: path.to_path_buf(),
+            kind,
+            hidden: false,
+            metadata: EntryMetadata {
+                size: if needs_metadata { size } else { 0 },
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read_dir(
+        &self,
+        path: &Path,
+        needs_metadata: bool,
+    ) -> io::Result<Vec<Result<ScannedEntry, VanishedEntry>>> {
+        match self.entries.get(path) {
+            Some((EntryKind::Directory, _)) => {}
+            Some((EntryKind::File, _)) => return Err(io::ErrorKind::NotADirectory.into()),
+            None => return Err(io::ErrorKind::NotFound.into()),
+        }
+        // Paths order by component, so the entries below `path` follow it
+        // directly.
+        Ok(self
+            .entries
+            .range::<Path, _>((Bound::Excluded(path), Bound::Unbounded))
+            .take_while(|(entry, _)| entry.starts_with(path))
+            .filter(|(entry, _)| entry.parent() == Some(path))
+            .map(|(entry, &(kind, size))| Ok(Self::scanned(entry, kind, size, needs_metadata)))
+            .collect())
+    }
+
+    fn inspect(&self, path: &Path) -> io::Result<ScannedEntry> {
+        let &(kind, size) = self.entries.get(path).ok_or(io::ErrorKind::NotFound)?;
+        Ok(Self::scanned(path, kind, size, true))
+    }
+}
+
+// ============================================================================
+// Internal Functions
+// ============================================================================
+
+/// Reads the type of a directory entry, and its metadata only when needed.
+///
+/// The type comes with the directory listing; `DirEntry::metadata` is only
+/// called when sizes, dates or attributes are needed (see
+/// `Config::needs_entry_metadata`). Link targets are read in either case,
+/// which only costs a call for the links themselves.
+fn inspect_entry(entry: &DirEntry, dir: &Path, needs_metadata: bool) -> io::Result<ScannedEntry> {
+    let path = dir.join(entry.file_name());
+    if needs_metadata {
+        let meta = entry.metadata()?;
+        let link_target = read_link_target(&path, meta.file_type());
+        Ok(ScannedEntry {
+            kind: EntryKind::from_metadata(&meta),
+            hidden: is_hidden(&meta),
+            metadata: EntryMetadata {
+                link_target,
+                ..EntryMetadata::from_fs_metadata(&meta)
+            },
+            path,
+        })
+    } else {
+        let file_type = entry.file_type()?;
+        let link_target = read_link_target(&path, file_type);
+        Ok(ScannedEntry {
+            path,
+            kind: EntryKind::from_file_type(file_type),
+            hidden: false,
+            metadata: EntryMetadata {
+                link_target,
+                ..Default::default()
+            },
+        })
+    }
+}
+
+// ============================================================================
+// Test File System
+// ============================================================================
+
+/// A file system that injects failures into the real one, for tests.
+#[cfg(test)]
+pub(crate) mod faults {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// A failure injected at a path.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) enum Fault {
+        /// The directory cannot be read: access is denied.
+        Denied,
+        /// The first `n` reads of the directory fail; later ones succeed.
+        Transient(usize),
+        /// The entry is still listed by its parent but was deleted before
+        /// it could be inspected or read.
+        Vanished,
+        /// The directory is listed and inspected, but deleted before it is
+        /// read.
+        Removed,
+    }
+
+    /// The real file system with failures at chosen paths.
+    #[derive(Debug, Default)]
+    pub(crate) struct FaultyFileSystem {
+        faults: HashMap<PathBuf, Fault>,
+        /// Failed reads so far, per path with a `Transient` fault.
+        failed: Mutex<HashMap<PathBuf, usize>>,
+    }
+
+    impl FaultyFileSystem {
+        /// Adds a failure at a path.
+        pub(crate) fn with(mut self, path: impl Into<PathBuf>, fault: Fault) -> Self {
+            self.faults.insert(path.into(), fault);
+            self
+        }
+
+        /// Returns the error of the next read of `path`, if it fails.
+        fn read_error(&self, path: &Path) -> Option<io::Error> {
+            match self.faults.get(path)? {
+                Fault::Denied => Some(io::ErrorKind::PermissionDenied.into()),
+                Fault::Vanished | Fault::Removed => Some(io::ErrorKind::NotFound.into()),
+                Fault::Transient(times) => {
+                    let mut failed = self.failed.lock().unwrap();
+                    let count = failed.entry(path.to_path_buf()).or_default();
+                    (*count < *times).then(|| {
+                        *count += 1;
+                        io::Error::other("transient failure")
+                    })
+                }
+            }
+        }
+    }
+
+    impl FileSystem for FaultyFileSystem {
+        fn read_dir(
+            &self,
+            path: &Path,
+            needs_metadata: bool,
+        ) -> io::Result<Vec<Result<ScannedEntry, VanishedEntry>>> {
+            if let Some(error) = self.read_error(path) {
+                return Err(error);
+            }
+            let entries = OsFileSystem.read_dir(path, needs_metadata)?;
+            Ok(entries
+                .into_iter()
+                .map(|entry| match entry {
+                    Ok(entry) if self.faults.get(&entry.path) == Some(&Fault::Vanished) => {
+                        Err(VanishedEntry {
+                            path: entry.path,
+                            error: io::ErrorKind::NotFound.into(),
+                        })
+                    }
+                    other => other,
+                })
+                .collect())
+        }
+
+        fn inspect(&self, path: &Path) -> io::Result<ScannedEntry> {
+            if self.faults.get(path) == Some(&Fault::Vanished) {
+                return Err(io::ErrorKind::NotFound.into());
+            }
+            OsFileSystem.inspect(path)
+        }
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::faults::{Fault, FaultyFileSystem};
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn read_dir_reports_vanished_entry_alone() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("kept.txt"), "").unwrap();
+        fs::write(dir.path().join("gone.txt"), "").unwrap();
+        let fs = FaultyFileSystem::default().with(dir.path().join("gone.txt"), Fault::Vanished);
+
+        let entries = fs.read_dir(dir.path(), true).unwrap();
+        assert_eq!(entries.len(), 2);
+        let vanished: Vec<_> = entries.iter().filter_map(|e| e.as_ref().err()).collect();
+        assert_eq!(entries.len() - vanished.len(), 1, "只有消失的条目应失败");
+        assert_eq!(vanished[0].path, dir.path().join("gone.txt"));
+        assert!(fs.inspect(&dir.path().join("gone.txt")).is_err());
+    }
+
+    #[test]
+    fn memory_file_system_lists_children_in_name_order() {
+        let fs = MemoryFileSystem::default()
+            .with_file("demo/src/main.rs", 120)
+            .with_file("demo/Cargo.toml", 40)
+            .with_dir("demo/src/bin")
+            .with_file("demo/src0.txt", 1);
+
+        let names: Vec<_> = fs
+            .read_dir(Path::new("demo"), true)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.unwrap().path)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                PathBuf::from("demo/Cargo.toml"),
+                PathBuf::from("demo/src"),
+                PathBuf::from("demo/src0.txt"),
+            ],
+            "只应列出直接子项"
+        );
+
+        let src = fs.read_dir(Path::new("demo/src"), true).unwrap();
+        let main = src
+            .iter()
+            .flatten()
+            .find(|e| e.kind == EntryKind::File)
+            .unwrap();
+        assert_eq!(main.metadata.size, 120);
+        assert_eq!(
+            fs.inspect(Path::new("demo/src/bin")).unwrap().kind,
+            EntryKind::Directory
+        );
+    }
+
+    #[test]
+    fn memory_file_system_rejects_missing_paths_and_files() {
+        let fs = MemoryFileSystem::default().with_file("demo/a.txt", 1);
+        let missing = fs.read_dir(Path::new("demo/b"), false).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        let file = fs.read_dir(Path::new("demo/a.txt"), false).unwrap_err();
+        assert_eq!(
+            file.kind(),
+            io::ErrorKind::NotADirectory,
+            "文件不能作为目录读取"
+        );
+        assert!(fs.inspect(Path::new("demo/b")).is_err());
+    }
+
+    #[test]
+    fn transient_fault_clears_after_failed_reads() {
+        let dir = TempDir::new().unwrap();
+        let fs = FaultyFileSystem::default().with(dir.path(), Fault::Transient(2));
+
+        assert!(fs.read_dir(dir.path(), false).is_err());
+        assert!(fs.read_dir(dir.path(), false).is_err());
+        assert!(fs.read_dir(dir.path(), false).is_ok(), "第三次读取应成功");
+    }
+}
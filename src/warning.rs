@@ -0,0 +1,98 @@
+//! Warning module: the channel for non-fatal problems.
+//!
+//! Some problems do not stop tree++ but should still be reported, such as a
+//! banner that cannot be fetched or a thread count that was clamped. They
+//! are written to stderr through this module, never into the rendered
+//! tree, so that output files and structured formats only ever contain the
+//! tree itself:
+//!
+//! - **Format**: one line per warning, prefixed with `tree++: `
+//! - **Suppression**: `--quiet-warnings` silences the channel for the run
+//!
+//! Messages are localized by the caller, usually through `i18n`.
+//!
+//! File: src/warning.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// ============================================================================
+// State
+// ============================================================================
+
+/// Whether warnings are suppressed (`--quiet-warnings`).
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// Public Functions
+// ============================================================================
+
+/// Suppresses or restores warnings for the rest of the process.
+///
+/// # Arguments
+///
+/// * `quiet` - `true` to drop warnings, `false` to write them to stderr.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Returns whether warnings are currently suppressed.
+///
+/// # Returns
+///
+/// `true` after `set_quiet(true)`.
+#[must_use]
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Reports a warning on stderr, unless warnings are suppressed.
+///
+/// # Arguments
+///
+/// * `message` - The localized warning, including its category, e.g.
+///   `Warning: Failed to fetch Windows tree banner: ...`.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::warning;
+///
+/// warning::emit("Warning: something looks off");
+/// ```
+pub fn emit(message: &str) {
+    if is_quiet() {
+        return;
+    }
+    // A closed stderr leaves nowhere to report the failure.
+    let _ = write_warning(&mut io::stderr().lock(), message);
+}
+
+// ============================================================================
+// Internal Functions
+// ============================================================================
+
+/// Writes a warning line to a writer.
+fn write_warning(out: &mut impl Write, message: &str) -> io::Result<()> {
+    writeln!(out, "tree++: {}", message)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_prefix_warning_with_program_name() {
+        let mut out = Vec::new();
+        write_warning(&mut out, "Warning: test").unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "tree++: Warning: test\n");
+    }
+}
@@ -0,0 +1,683 @@
+//! Path normalization module: long-path and UNC handling for Windows paths.
+//!
+//! This module provides a small, string-based normalization layer used by the
+//! scanner, renderer and output stages:
+//!
+//! - **Root classification**: drive roots (`C:\`, `\\?\C:\`) vs. UNC share
+//!   roots (`\\server\share`, `\\?\UNC\server\share`)
+//! - **Extended-length paths**: transparently applies the `\\?\` prefix to
+//!   absolute paths that approach `MAX_PATH`, so deep trees such as
+//!   `node_modules` can be traversed
+//! - **Display paths**: strips verbatim prefixes so users never see `\\?\`
+//! - **Root arguments**: cleans up the path given on the command line before
+//!   it is canonicalized (stray quotes, forward slashes, trailing separators)
+//! - **Relative paths**: expresses a path relative to a base directory
+//!   (`--relative-to`), comparing components case-insensitively
+//!
+//! Parsing is performed on the textual form of the path rather than on
+//! `std::path::Component`, which keeps the behavior identical regardless of
+//! the platform the logic is exercised on.
+//!
+//! File: src/winpath.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![forbid(unsafe_code)]
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Verbatim (extended-length) path prefix.
+const VERBATIM_PREFIX: &str = r"\\?\";
+
+/// Verbatim UNC path prefix.
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+
+/// Length (in UTF-16 units) from which paths receive the `\\?\` prefix.
+///
+/// `MAX_PATH` is 260, but directory creation and enumeration APIs reserve
+/// 12 characters for an 8.3 file name, so 248 is the effective limit.
+const LONG_PATH_THRESHOLD: usize = 248;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Root of an absolute Windows path.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::winpath::{path_root, PathRoot};
+///
+/// assert_eq!(path_root(Path::new(r"c:\Users")), Some(PathRoot::Drive('C')));
+/// assert_eq!(
+///     path_root(Path::new(r"\\nas\share\docs")),
+///     Some(PathRoot::Unc { server: "nas".to_string(), share: "share".to_string() })
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathRoot {
+    /// Drive letter root (always uppercase), e.g. `C:\`.
+    Drive(char),
+    /// UNC share root, e.g. `\\server\share`.
+    Unc {
+        /// Server (host) name.
+        server: String,
+        /// Share name.
+        share: String,
+    },
+}
+
+// ============================================================================
+// Public Functions
+// ============================================================================
+
+/// Classifies the root of a path.
+///
+/// Recognizes plain and verbatim drive paths as well as plain and verbatim
+/// UNC paths. Both `\` and `/` are accepted as separators for non-verbatim
+/// forms.
+///
+/// # Arguments
+///
+/// * `path` - The path to classify.
+///
+/// # Returns
+///
+/// The detected `PathRoot`, or `None` for relative or unrecognized paths.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::winpath::{path_root, PathRoot};
+///
+/// assert_eq!(path_root(Path::new(r"\\?\D:\data")), Some(PathRoot::Drive('D')));
+/// assert_eq!(path_root(Path::new("relative")), None);
+/// ```
+#[must_use]
+pub fn path_root(path: &Path) -> Option<PathRoot> {
+    let text = path.to_string_lossy();
+
+    if let Some(rest) = strip_prefix_ignore_case(&text, VERBATIM_UNC_PREFIX) {
+        return parse_unc_tail(rest);
+    }
+
+    if let Some(rest) = text.strip_prefix(VERBATIM_PREFIX) {
+        return parse_drive(rest);
+    }
+
+    if let Some(rest) = text.strip_prefix(r"\\").or_else(|| text.strip_prefix("//")) {
+        return parse_unc_tail(rest);
+    }
+
+    parse_drive(&text)
+}
+
+/// Extracts the uppercase drive letter of a path.
+///
+/// # Arguments
+///
+/// * `path` - The path to inspect.
+///
+/// # Returns
+///
+/// `Some(letter)` for drive-rooted paths, `None` for UNC or relative paths.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::winpath::drive_letter;
+///
+/// assert_eq!(drive_letter(Path::new(r"d:\data")), Some('D'));
+/// assert_eq!(drive_letter(Path::new(r"\\server\share")), None);
+/// ```
+#[must_use]
+pub fn drive_letter(path: &Path) -> Option<char> {
+    match path_root(path)? {
+        PathRoot::Drive(letter) => Some(letter),
+        PathRoot::Unc { .. } => None,
+    }
+}
+
+/// Checks whether a path is rooted on a UNC share.
+///
+/// # Arguments
+///
+/// * `path` - The path to inspect.
+///
+/// # Returns
+///
+/// `true` for `\\server\share` and `\\?\UNC\server\share` paths.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::winpath::is_unc;
+///
+/// assert!(is_unc(Path::new(r"\\server\share\dir")));
+/// assert!(!is_unc(Path::new(r"C:\dir")));
+/// ```
+#[must_use]
+pub fn is_unc(path: &Path) -> bool {
+    matches!(path_root(path), Some(PathRoot::Unc { .. }))
+}
+
+/// Converts a path to its extended-length form when required.
+///
+/// Absolute paths whose length reaches `LONG_PATH_THRESHOLD` are rewritten
+/// to `\\?\C:\...` or `\\?\UNC\server\share\...`. Because verbatim paths
+/// bypass Win32 normalization, forward slashes are converted to backslashes
+/// during the rewrite. Short, relative, or already-verbatim paths are
+/// returned unchanged.
+///
+/// # Arguments
+///
+/// * `path` - The path to convert.
+///
+/// # Returns
+///
+/// The path to hand to filesystem APIs.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::winpath::to_extended;
+///
+/// let short = Path::new(r"C:\short");
+/// assert_eq!(to_extended(short), short);
+///
+/// let long = format!(r"C:\{}", "a".repeat(300));
+/// assert!(to_extended(Path::new(&long)).to_string_lossy().starts_with(r"\\?\C:\"));
+/// ```
+#[must_use]
+pub fn to_extended(path: &Path) -> Cow<'_, Path> {
+    let text = path.to_string_lossy();
+
+    if text.starts_with(VERBATIM_PREFIX) || utf16_len(&text) < LONG_PATH_THRESHOLD {
+        return Cow::Borrowed(path);
+    }
+
+    match path_root(path) {
+        Some(PathRoot::Drive(_)) if is_drive_absolute(&text) => Cow::Owned(PathBuf::from(format!(
+            "{}{}",
+            VERBATIM_PREFIX,
+            text.replace('/', "\\")
+        ))),
+        Some(PathRoot::Unc { .. }) => {
+            let tail = text[2..].replace('/', "\\");
+            Cow::Owned(PathBuf::from(format!("{}{}", VERBATIM_UNC_PREFIX, tail)))
+        }
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// Converts a path to its user-facing form.
+///
+/// Strips `\\?\` from drive paths and rewrites `\\?\UNC\server\share` back
+/// to `\\server\share`. Other paths are returned unchanged.
+///
+/// # Arguments
+///
+/// * `path` - The path to convert.
+///
+/// # Returns
+///
+/// The path suitable for display.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::winpath::to_display;
+///
+/// assert_eq!(to_display(Path::new(r"\\?\C:\data")), Path::new(r"C:\data"));
+/// assert_eq!(
+///     to_display(Path::new(r"\\?\UNC\nas\share")),
+///     Path::new(r"\\nas\share")
+/// );
+/// ```
+#[must_use]
+pub fn to_display(path: &Path) -> Cow<'_, Path> {
+    let text = path.to_string_lossy();
+
+    if let Some(rest) = strip_prefix_ignore_case(&text, VERBATIM_UNC_PREFIX) {
+        return Cow::Owned(PathBuf::from(format!(r"\\{}", rest)));
+    }
+
+    if let Some(rest) = text.strip_prefix(VERBATIM_PREFIX)
+        && parse_drive(rest).is_some()
+    {
+        return Cow::Owned(PathBuf::from(rest));
+    }
+
+    Cow::Borrowed(path)
+}
+
+/// Cleans up a root path as typed on the command line.
+///
+/// - Removes surrounding and stray `"`; `treepp "D:\folder\"` arrives as
+///   `D:\folder"` because the shell reads `\"` as an escaped quote
+/// - Converts `/` to `\` in drive and UNC paths
+/// - Strips trailing separators, except the one of a root such as `C:\`
+/// - Turns a bare drive designator `D:` into `D:.`, the current directory of
+///   that drive; other drive-relative paths (`D:folder`) are kept for
+///   canonicalization to resolve
+///
+/// Verbatim paths and paths that are not valid Unicode are only unquoted,
+/// or returned unchanged.
+///
+/// # Arguments
+///
+/// * `path` - The path argument.
+///
+/// # Returns
+///
+/// The path to canonicalize.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::winpath::normalize_root;
+///
+/// assert_eq!(normalize_root(Path::new(r#"D:\folder""#)), Path::new(r"D:\folder"));
+/// assert_eq!(normalize_root(Path::new("d:/data/logs/")), Path::new(r"d:\data\logs"));
+/// assert_eq!(normalize_root(Path::new(r"C:\")), Path::new(r"C:\"));
+/// assert_eq!(normalize_root(Path::new("E:")), Path::new("E:."));
+/// ```
+#[must_use]
+pub fn normalize_root(path: &Path) -> PathBuf {
+    let Some(text) = path.to_str() else {
+        return path.to_path_buf();
+    };
+    let text = text.trim_matches('"');
+    if text.starts_with(VERBATIM_PREFIX) {
+        return PathBuf::from(text);
+    }
+
+    let mut text = if path_root(Path::new(text)).is_some() {
+        text.replace('/', "\\")
+    } else {
+        text.to_string()
+    };
+    if text.len() == 2 && parse_drive(&text).is_some() {
+        text.push('.');
+    }
+    let root_len = if is_drive_absolute(&text) {
+        3
+    } else {
+        usize::from(text.starts_with(['\\', '/']))
+    };
+    while text.len() > root_len && text.ends_with(['\\', '/']) {
+        text.pop();
+    }
+    PathBuf::from(text)
+}
+
+/// Expresses a path relative to a base directory.
+///
+/// Components are compared case-insensitively, as Windows does, and the
+/// path climbs out of the base with `..` where needed. Both paths are
+/// expected in the same (canonical, display) form.
+///
+/// # Arguments
+///
+/// * `path` - The path to express.
+/// * `base` - The directory it is relative to.
+///
+/// # Returns
+///
+/// The relative path, `.` for the base itself, or `path` unchanged if the
+/// two do not share a root (e.g. different drives).
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use treepp::winpath::relative_to;
+///
+/// let base = Path::new(r"C:\Users\dev\repo");
+/// assert_eq!(relative_to(Path::new(r"C:\Users\dev\repo\src"), base), Path::new("src"));
+/// assert_eq!(relative_to(Path::new(r"c:\users\dev\repo"), base), Path::new("."));
+/// assert_eq!(relative_to(Path::new(r"D:\data"), base), Path::new(r"D:\data"));
+/// ```
+#[must_use]
+pub fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let (path_text, base_text) = (path.to_string_lossy(), base.to_string_lossy());
+    let same_root = path_root(path) == path_root(base)
+        && path_text.starts_with(['\\', '/']) == base_text.starts_with(['\\', '/']);
+    if !same_root {
+        return path.to_path_buf();
+    }
+
+    let split = |text: &str| -> Vec<String> {
+        text.split(['\\', '/'])
+            .filter(|part| !part.is_empty() && *part != ".")
+            .map(str::to_string)
+            .collect()
+    };
+    let (parts, base_parts) = (split(&path_text), split(&base_text));
+    let common = parts
+        .iter()
+        .zip(&base_parts)
+        .take_while(|(a, b)| a.to_lowercase() == b.to_lowercase())
+        .count();
+
+    let relative: Vec<&str> = std::iter::repeat_n("..", base_parts.len() - common)
+        .chain(parts[common..].iter().map(String::as_str))
+        .collect();
+    if relative.is_empty() {
+        PathBuf::from(".")
+    } else {
+        PathBuf::from(relative.join(std::path::MAIN_SEPARATOR_STR))
+    }
+}
+
+// ============================================================================
+// Internal Helper Functions
+// ============================================================================
+
+/// Parses a leading `X:` drive designator.
+fn parse_drive(text: &str) -> Option<PathRoot> {
+    let mut chars = text.chars();
+    let letter = chars.next()?;
+    if letter.is_ascii_alphabetic() && chars.next() == Some(':') {
+        Some(PathRoot::Drive(letter.to_ascii_uppercase()))
+    } else {
+        None
+    }
+}
+
+/// Parses `server\share[\...]` following a UNC prefix.
+fn parse_unc_tail(text: &str) -> Option<PathRoot> {
+    let mut parts = text.split(['\\', '/']);
+    let server = parts
+        .next()
+        .filter(|s| !s.is_empty() && *s != "?" && *s != ".")?;
+    let share = parts.next().filter(|s| !s.is_empty())?;
+    Some(PathRoot::Unc {
+        server: server.to_string(),
+        share: share.to_string(),
+    })
+}
+
+/// Checks for a fully qualified drive path (`X:\` rather than drive-relative `X:`).
+fn is_drive_absolute(text: &str) -> bool {
+    matches!(text.as_bytes().get(2), Some(b'\\' | b'/'))
+}
+
+/// Strips an ASCII prefix, ignoring case.
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = text.get(..prefix.len())?;
+    if head.eq_ignore_ascii_case(prefix) {
+        text.get(prefix.len()..)
+    } else {
+        None
+    }
+}
+
+/// Returns the length of a string in UTF-16 code units, as seen by Win32 APIs.
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds an absolute path of roughly `min_len` characters by nesting
+    /// `node_modules\pkg` segments below `base`.
+    fn deep_path(base: &str, min_len: usize) -> String {
+        let mut path = base.to_string();
+        let mut level = 0;
+        while path.len() < min_len {
+            path.push_str(&format!(r"\node_modules\pkg{}", level));
+            level += 1;
+        }
+        path
+    }
+
+    #[test]
+    fn should_classify_plain_drive_root() {
+        assert_eq!(
+            path_root(Path::new(r"C:\Windows")),
+            Some(PathRoot::Drive('C'))
+        );
+    }
+
+    #[test]
+    fn should_uppercase_drive_letter() {
+        assert_eq!(drive_letter(Path::new(r"e:\data")), Some('E'));
+    }
+
+    #[test]
+    fn should_classify_verbatim_drive_root() {
+        assert_eq!(
+            path_root(Path::new(r"\\?\d:\data")),
+            Some(PathRoot::Drive('D'))
+        );
+    }
+
+    #[test]
+    fn should_classify_unc_root() {
+        assert_eq!(
+            path_root(Path::new(r"\\server\share\dir")),
+            Some(PathRoot::Unc {
+                server: "server".to_string(),
+                share: "share".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn should_classify_unc_root_with_forward_slashes() {
+        assert!(is_unc(Path::new("//server/share/dir")));
+    }
+
+    #[test]
+    fn should_classify_verbatim_unc_root() {
+        assert_eq!(
+            path_root(Path::new(r"\\?\UNC\nas\media\movies")),
+            Some(PathRoot::Unc {
+                server: "nas".to_string(),
+                share: "media".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn should_classify_verbatim_unc_root_case_insensitively() {
+        assert!(is_unc(Path::new(r"\\?\unc\nas\media")));
+    }
+
+    #[test]
+    fn should_reject_unc_without_share() {
+        assert_eq!(path_root(Path::new(r"\\server")), None);
+        assert_eq!(path_root(Path::new(r"\\server\")), None);
+    }
+
+    #[test]
+    fn should_return_none_for_relative_path() {
+        assert_eq!(path_root(Path::new(r"relative\path")), None);
+        assert_eq!(drive_letter(Path::new("relative/path")), None);
+    }
+
+    #[test]
+    fn should_return_no_drive_letter_for_unc() {
+        assert_eq!(drive_letter(Path::new(r"\\server\share")), None);
+    }
+
+    #[test]
+    fn should_keep_short_path_unchanged() {
+        let path = Path::new(r"C:\Users\test");
+        assert!(matches!(to_extended(path), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn should_prefix_long_drive_path() {
+        let long = deep_path(r"C:\repo", 400);
+        let extended = to_extended(Path::new(&long));
+        assert_eq!(extended.to_string_lossy(), format!(r"\\?\{}", long));
+    }
+
+    #[test]
+    fn should_prefix_path_at_threshold() {
+        let long = format!(r"C:\{}", "x".repeat(LONG_PATH_THRESHOLD - 3));
+        assert_eq!(long.len(), LONG_PATH_THRESHOLD);
+        assert!(
+            to_extended(Path::new(&long))
+                .to_string_lossy()
+                .starts_with(VERBATIM_PREFIX)
+        );
+    }
+
+    #[test]
+    fn should_not_prefix_path_below_threshold() {
+        let short = format!(r"C:\{}", "x".repeat(LONG_PATH_THRESHOLD - 4));
+        assert!(matches!(to_extended(Path::new(&short)), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn should_normalize_separators_when_prefixing() {
+        let long = deep_path("C:/repo", 300).replace('\\', "/");
+        let extended = to_extended(Path::new(&long)).to_string_lossy().into_owned();
+        assert!(extended.starts_with(r"\\?\C:\repo\node_modules"));
+        assert!(!extended.contains('/'));
+    }
+
+    #[test]
+    fn should_prefix_long_unc_path() {
+        let long = deep_path(r"\\server\share", 300);
+        let extended = to_extended(Path::new(&long)).to_string_lossy().into_owned();
+        assert!(extended.starts_with(r"\\?\UNC\server\share\node_modules"));
+    }
+
+    #[test]
+    fn should_not_prefix_already_verbatim_path() {
+        let long = format!(r"\\?\{}", deep_path(r"C:\repo", 300));
+        assert!(matches!(to_extended(Path::new(&long)), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn should_not_prefix_relative_long_path() {
+        let long = deep_path("repo", 300);
+        assert!(matches!(to_extended(Path::new(&long)), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn should_not_prefix_drive_relative_long_path() {
+        let long = format!("C:{}", "x".repeat(300));
+        assert!(matches!(to_extended(Path::new(&long)), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn should_count_threshold_in_utf16_units() {
+        let long = format!(r"C:\{}", "文".repeat(LONG_PATH_THRESHOLD));
+        assert!(
+            to_extended(Path::new(&long))
+                .to_string_lossy()
+                .starts_with(VERBATIM_PREFIX)
+        );
+    }
+
+    #[test]
+    fn should_strip_verbatim_drive_prefix_for_display() {
+        assert_eq!(to_display(Path::new(r"\\?\C:\data")), Path::new(r"C:\data"));
+    }
+
+    #[test]
+    fn should_restore_unc_form_for_display() {
+        assert_eq!(
+            to_display(Path::new(r"\\?\UNC\server\share\dir")),
+            Path::new(r"\\server\share\dir")
+        );
+    }
+
+    #[test]
+    fn should_keep_non_drive_verbatim_path_for_display() {
+        let volume = Path::new(r"\\?\Volume{1234}\dir");
+        assert!(matches!(to_display(volume), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn should_normalize_root_arguments() {
+        let cases = [
+            (r#""D:\folder\""#, r"D:\folder"),
+            (r#"D:\folder""#, r"D:\folder"),
+            ("D:/folder//", r"D:\folder"),
+            (r"C:\\", r"C:\"),
+            ("c:/", r"c:\"),
+            (r"\\nas\share\", r"\\nas\share"),
+            ("//nas/share/docs/", r"\\nas\share\docs"),
+            ("D:", "D:."),
+            (r"D:sub\", "D:sub"),
+            (r"\\?\C:\data\", r"\\?\C:\data\"),
+            ("relative/dir/", "relative/dir"),
+            ("/", "/"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                normalize_root(Path::new(input)),
+                Path::new(expected),
+                "输入：{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn should_express_path_relative_to_base() {
+        let base = Path::new(r"C:\work\repo");
+        let sep = std::path::MAIN_SEPARATOR;
+        assert_eq!(
+            relative_to(Path::new(r"C:\work\repo\src\main.rs"), base),
+            PathBuf::from(format!("src{sep}main.rs"))
+        );
+        assert_eq!(
+            relative_to(Path::new(r"C:\work\other"), base),
+            PathBuf::from(format!("..{sep}other"))
+        );
+        assert_eq!(
+            relative_to(Path::new(r"C:\WORK\REPO"), base),
+            Path::new(".")
+        );
+        assert_eq!(
+            relative_to(Path::new(r"\\nas\share\x"), base),
+            Path::new(r"\\nas\share\x")
+        );
+        assert_eq!(
+            relative_to(Path::new("/srv/data/logs"), Path::new("/srv/data")),
+            Path::new("logs")
+        );
+    }
+
+    #[test]
+    fn should_round_trip_deep_drive_path() {
+        let long = deep_path(r"C:\projects\web", 1024);
+        let extended = to_extended(Path::new(&long)).into_owned();
+        assert_eq!(to_display(&extended), Path::new(&long));
+        assert_eq!(drive_letter(&extended), Some('C'));
+    }
+
+    #[test]
+    fn should_round_trip_deep_unc_path() {
+        let long = deep_path(r"\\build01\ci", 1024);
+        let extended = to_extended(Path::new(&long)).into_owned();
+        assert_eq!(to_display(&extended), Path::new(&long));
+        assert!(is_unc(&extended));
+    }
+}
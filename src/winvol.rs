@@ -0,0 +1,1308 @@
+//! Win32 system calls: volume information, UI language, console mode and registry.
+//!
+//! This module is the only place in tree++ that calls into the Windows API
+//! directly. It backs the native banner, which previously required spawning
+//! `cmd /C tree` in a marker directory, and the console features:
+//!
+//! - **Volume information**: label and serial number of a drive, via
+//!   `GetVolumeInformationW`, and the volume and file ID of a directory,
+//!   via `GetFileInformationByHandle`, for `--one-file-system` and `--cache`
+//! - **Change journal**: the NTFS change journal of a volume, via
+//!   `DeviceIoControl`, telling `--cache` which directories changed since
+//!   the last run
+//! - **UI language**: the user's display language, via
+//!   `GetUserDefaultUILanguage`, used to pick the banner strings
+//! - **Console mode**: escape sequence processing for `--highlight`, via
+//!   `GetConsoleMode` and `SetConsoleMode`
+//! - **Console code page**: UTF-8 output for the console and the pager
+//!   sharing it, via `GetConsoleOutputCP` and `SetConsoleOutputCP`
+//! - **Console font**: whether the console uses a raster font, which lacks
+//!   box-drawing and CJK glyphs, via `GetCurrentConsoleFontEx`
+//! - **Console size**: the visible width of the console for `--truncate`
+//!   and its height for `--pager auto`, via `GetConsoleScreenBufferInfo`
+//! - **Registry**: string values and key removal for the Explorer context
+//!   menu, via `RegSetKeyValueW` and `RegDeleteTreeW`
+//! - **Named pipes**: the pipe transport of `--serve`, via
+//!   `CreateNamedPipeW` and `ConnectNamedPipe`
+//! - **Renaming**: moving a finished output file into place without
+//!   replacing an existing one, for `--no-clobber`, via `MoveFileExW`
+//!
+//! Every `unsafe` block is confined to this file and wraps a single call with
+//! buffers owned by the calling function.
+//!
+//! The Win32 calls are only compiled on Windows. On other targets, such as
+//! `wasm32-wasip1` for the playground and fuzzing builds, each function has
+//! a fallback that reports nothing: no volume, no change journal, no
+//! console, English as the UI language, and `Unsupported` for registry
+//! writes and named pipes. The scanner, filters and renderer then run
+//! unchanged. Renaming without replacing falls
+//! back to a hard link, which fails just the same if the target exists.
+//!
+//! File: src/winvol.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![allow(unsafe_code)]
+
+use std::collections::HashSet;
+use std::fs::File;
+#[cfg(windows)]
+use std::fs::OpenOptions;
+use std::io;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
+#[cfg(windows)]
+use std::os::windows::fs::OpenOptionsExt;
+#[cfg(windows)]
+use std::os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use windows_sys::Win32::Foundation::{ERROR_PIPE_CONNECTED, ERROR_SUCCESS, INVALID_HANDLE_VALUE};
+#[cfg(windows)]
+use windows_sys::Win32::Globalization::{CP_UTF8, GetUserDefaultUILanguage};
+#[cfg(windows)]
+use windows_sys::Win32::Storage::FileSystem::{
+    BY_HANDLE_FILE_INFORMATION, FILE_ATTRIBUTE_DIRECTORY, FILE_FLAG_BACKUP_SEMANTICS,
+    FILE_FLAG_FIRST_PIPE_INSTANCE, FILE_READ_ATTRIBUTES, GetFileInformationByHandle,
+    GetVolumeInformationW, MOVEFILE_WRITE_THROUGH, MoveFileExW, PIPE_ACCESS_DUPLEX,
+};
+#[cfg(windows)]
+use windows_sys::Win32::System::Console::{
+    CONSOLE_FONT_INFOEX, CONSOLE_SCREEN_BUFFER_INFO, ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+    GetConsoleMode, GetConsoleOutputCP, GetConsoleScreenBufferInfo, GetCurrentConsoleFontEx,
+    GetStdHandle, SMALL_RECT, STD_OUTPUT_HANDLE, SetConsoleMode, SetConsoleOutputCP,
+};
+#[cfg(windows)]
+use windows_sys::Win32::System::IO::DeviceIoControl;
+#[cfg(windows)]
+use windows_sys::Win32::System::Ioctl::{
+    FSCTL_QUERY_USN_JOURNAL, FSCTL_READ_UNPRIVILEGED_USN_JOURNAL, READ_USN_JOURNAL_DATA_V0,
+    USN_JOURNAL_DATA_V0, USN_REASON_BASIC_INFO_CHANGE, USN_REASON_FILE_CREATE,
+    USN_REASON_FILE_DELETE, USN_REASON_HARD_LINK_CHANGE, USN_REASON_RENAME_NEW_NAME,
+    USN_REASON_RENAME_OLD_NAME, USN_REASON_REPARSE_POINT_CHANGE,
+};
+#[cfg(windows)]
+use windows_sys::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+    PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+};
+#[cfg(windows)]
+use windows_sys::Win32::System::Registry::{
+    HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, REG_SZ, RegDeleteTreeW, RegSetKeyValueW,
+};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Capacity of the volume label buffer (`MAX_PATH + 1` UTF-16 units).
+#[cfg(windows)]
+const VOLUME_LABEL_CAPACITY: usize = 261;
+
+/// `TMPF_TRUETYPE` bit of `CONSOLE_FONT_INFOEX::FontFamily`; clear for
+/// raster fonts.
+#[cfg(windows)]
+const TMPF_TRUETYPE: u32 = 0x04;
+
+/// Size of the input and output buffers of a named pipe, in bytes.
+#[cfg(windows)]
+const PIPE_BUFFER_SIZE: u32 = 64 * 1024;
+
+/// Size of the buffer change-journal records are read into, in bytes.
+#[cfg(windows)]
+const USN_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Change-journal reasons that alter the listing of the parent directory:
+/// entries created, deleted or renamed, and changes to the attributes or
+/// link target of an entry.
+#[cfg(windows)]
+const USN_LISTING_REASONS: u32 = USN_REASON_FILE_CREATE
+    | USN_REASON_FILE_DELETE
+    | USN_REASON_RENAME_OLD_NAME
+    | USN_REASON_RENAME_NEW_NAME
+    | USN_REASON_BASIC_INFO_CHANGE
+    | USN_REASON_REPARSE_POINT_CHANGE
+    | USN_REASON_HARD_LINK_CHANGE;
+
+/// Size of a `USN_RECORD_V2` up to its file name.
+#[cfg(windows)]
+const USN_RECORD_V2_HEADER_LEN: usize = 60;
+
+/// Most changed directories collected from the journal; beyond this, a
+/// scan is faster than sorting out what changed.
+#[cfg(windows)]
+const MAX_USN_DIRECTORIES: usize = 100_000;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Label and serial number of a volume.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::winvol::VolumeInfo;
+///
+/// let info = VolumeInfo { label: "OS".to_string(), serial: 0x2810_11C7 };
+/// assert_eq!(info.serial_text(), "2810-11C7");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeInfo {
+    /// Volume label; empty if the volume has none.
+    pub label: String,
+    /// Volume serial number.
+    pub serial: u32,
+}
+
+/// Identity of a file or directory: its volume and its index there, which
+/// NTFS calls the file reference number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileId {
+    /// Serial number of the volume.
+    pub volume: u32,
+    /// Index of the file on the volume.
+    pub index: u64,
+}
+
+/// A position in the NTFS change journal of a volume.
+///
+/// # Examples
+///
+/// ```
+/// use treepp::winvol::UsnPosition;
+///
+/// let position = UsnPosition { volume: 0x2810_11C7, journal_id: 1, next_usn: 4096 };
+/// assert_eq!(position, position.clone());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsnPosition {
+    /// Serial number of the volume.
+    pub volume: u32,
+    /// Identifier of the journal; a journal that is deleted and created
+    /// again gets a new one.
+    pub journal_id: u64,
+    /// Sequence number of the next record to be written.
+    pub next_usn: i64,
+}
+
+/// Directories changed since a position in the change journal, by the
+/// index of their `FileId`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UsnChanges {
+    /// Directories whose listing changed: an entry was created, deleted or
+    /// renamed in them, or changed its attributes or link target.
+    pub listings: HashSet<u64>,
+    /// Directories that were themselves created, deleted, renamed or
+    /// changed, so that paths below them may now lead to other directories.
+    pub moved: HashSet<u64>,
+}
+
+/// Outcome of switching the console attached to stdout to UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utf8Console {
+    /// Stdout is not a console; nothing was changed.
+    NotConsole,
+    /// The console outputs UTF-8; `previous` is the code page it had
+    /// before, to be restored with `set_output_code_page` on exit.
+    Enabled {
+        /// Output code page of the console before the switch.
+        previous: u32,
+    },
+    /// The console keeps a legacy code page that cannot show box-drawing
+    /// characters and CJK names reliably.
+    Unavailable,
+}
+
+/// A predefined registry key that tree++ writes below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryHive {
+    /// `HKEY_CURRENT_USER`, writable without elevation.
+    CurrentUser,
+    /// `HKEY_LOCAL_MACHINE`, writable only from an elevated process.
+    LocalMachine,
+}
+
+#[cfg(windows)]
+impl RegistryHive {
+    /// Returns the predefined handle of the hive.
+    const fn handle(self) -> HKEY {
+        match self {
+            Self::CurrentUser => HKEY_CURRENT_USER,
+            Self::LocalMachine => HKEY_LOCAL_MACHINE,
+        }
+    }
+}
+
+impl VolumeInfo {
+    /// Formats the serial number the way `vol` and `tree` print it.
+    ///
+    /// # Returns
+    ///
+    /// The serial as two groups of four uppercase hex digits, e.g. `2810-11C7`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use treepp::winvol::VolumeInfo;
+    ///
+    /// let info = VolumeInfo { label: String::new(), serial: 0x00AB_0001 };
+    /// assert_eq!(info.serial_text(), "00AB-0001");
+    /// ```
+    #[must_use]
+    pub fn serial_text(&self) -> String {
+        format!("{:04X}-{:04X}", self.serial >> 16, self.serial & 0xFFFF)
+    }
+}
+
+// ============================================================================
+// Public Functions
+// ============================================================================
+
+/// Queries the label and serial number of a drive.
+///
+/// # Arguments
+///
+/// * `drive` - Drive letter (e.g., 'C', 'D')
+///
+/// # Returns
+///
+/// The volume information, or `None` if the drive is not ready or the
+/// call fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::winvol::volume_info;
+///
+/// if let Some(info) = volume_info('C') {
+///     println!("{} {}", info.label, info.serial_text());
+/// }
+/// ```
+#[cfg(windows)]
+#[must_use]
+pub fn volume_info(drive: char) -> Option<VolumeInfo> {
+    let root: Vec<u16> = format!(r"{}:\", drive.to_ascii_uppercase())
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut label = [0u16; VOLUME_LABEL_CAPACITY];
+    let mut serial = 0u32;
+
+    // SAFETY: `root` is NUL-terminated, `label` is writable for the length
+    // passed, and the optional out-parameters we do not need are null.
+    let ok = unsafe {
+        GetVolumeInformationW(
+            root.as_ptr(),
+            label.as_mut_ptr(),
+            VOLUME_LABEL_CAPACITY as u32,
+            &mut serial,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ok == 0 {
+        return None;
+    }
+
+    let len = label.iter().position(|&c| c == 0).unwrap_or(label.len());
+    Some(VolumeInfo {
+        label: String::from_utf16_lossy(&label[..len]),
+        serial,
+    })
+}
+
+/// Queries the serial number of the volume holding a file or directory.
+///
+/// Junctions and mount points are followed, so a junction to another drive
+/// reports the serial of that drive.
+///
+/// # Arguments
+///
+/// * `path` - Path of the file or directory
+///
+/// # Returns
+///
+/// The volume serial number, or `None` if the entry cannot be opened.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use treepp::winvol::volume_serial;
+///
+/// let same = volume_serial(Path::new(r"C:\Users")) == volume_serial(Path::new(r"C:\"));
+/// assert!(same);
+/// ```
+#[cfg(windows)]
+#[must_use]
+pub fn volume_serial(path: &Path) -> Option<u32> {
+    file_information(path).map(|info| info.dwVolumeSerialNumber)
+}
+
+/// Queries the volume and index of a file or directory.
+///
+/// Like `volume_serial`, junctions and mount points are followed.
+///
+/// # Arguments
+///
+/// * `path` - Path of the file or directory
+///
+/// # Returns
+///
+/// The `FileId`, or `None` if the entry cannot be opened.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use treepp::winvol::file_id;
+///
+/// let id = file_id(Path::new(r"C:\Users")).unwrap();
+/// println!("{:08X}:{:016X}", id.volume, id.index);
+/// ```
+#[cfg(windows)]
+#[must_use]
+pub fn file_id(path: &Path) -> Option<FileId> {
+    file_information(path).map(|info| FileId {
+        volume: info.dwVolumeSerialNumber,
+        index: (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow),
+    })
+}
+
+/// Returns the current position in the change journal of the volume
+/// holding a directory.
+///
+/// # Arguments
+///
+/// * `dir` - A directory on the volume
+///
+/// # Returns
+///
+/// The position, or `None` if the volume keeps no change journal (it is
+/// not NTFS, or the journal is turned off) or it cannot be queried.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use treepp::winvol::usn_position;
+///
+/// if let Some(position) = usn_position(Path::new(r"C:\Users")) {
+///     println!("next USN: {}", position.next_usn);
+/// }
+/// ```
+#[cfg(windows)]
+#[must_use]
+pub fn usn_position(dir: &Path) -> Option<UsnPosition> {
+    let volume = open_directory(dir)?;
+    let journal = query_usn_journal(&volume)?;
+    Some(UsnPosition {
+        volume: file_id(dir)?.volume,
+        journal_id: journal.UsnJournalID,
+        next_usn: journal.NextUsn,
+    })
+}
+
+/// Reads the directories changed since a position in the change journal
+/// of the volume holding a directory.
+///
+/// The journal is read with `FSCTL_READ_UNPRIVILEGED_USN_JOURNAL`, which
+/// needs no elevation (Windows 10 1709 or later) and leaves out the names
+/// of the entries; only their directories are needed.
+///
+/// # Arguments
+///
+/// * `dir` - A directory on the volume
+/// * `since` - A position from `usn_position`
+///
+/// # Returns
+///
+/// The changed directories, or `None` if the journal cannot tell: it was
+/// created again or has discarded records written after `since`, it cannot
+/// be read, or too many directories changed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use treepp::winvol::{usn_changes, usn_position};
+///
+/// let dir = Path::new(r"C:\Users");
+/// let since = usn_position(dir).unwrap();
+/// let changes = usn_changes(dir, since).unwrap();
+/// println!("{} directories changed", changes.listings.len());
+/// ```
+#[cfg(windows)]
+#[must_use]
+pub fn usn_changes(dir: &Path, since: UsnPosition) -> Option<UsnChanges> {
+    let volume = open_directory(dir)?;
+    let journal = query_usn_journal(&volume)?;
+    if journal.UsnJournalID != since.journal_id || since.next_usn < journal.FirstUsn {
+        return None;
+    }
+
+    let mut changes = UsnChanges::default();
+    let mut buffer = vec![0u64; USN_BUFFER_SIZE / 8];
+    let mut start = since.next_usn;
+    while start < journal.NextUsn {
+        let request = READ_USN_JOURNAL_DATA_V0 {
+            StartUsn: start,
+            ReasonMask: USN_LISTING_REASONS,
+            UsnJournalID: since.journal_id,
+            ..Default::default()
+        };
+        let mut returned = 0u32;
+
+        // SAFETY: `request` and `buffer` are locals of the sizes passed, and
+        // the handle was opened without `FILE_FLAG_OVERLAPPED`.
+        let ok = unsafe {
+            DeviceIoControl(
+                volume.as_raw_handle(),
+                FSCTL_READ_UNPRIVILEGED_USN_JOURNAL,
+                (&raw const request).cast(),
+                size_of::<READ_USN_JOURNAL_DATA_V0>() as u32,
+                buffer.as_mut_ptr().cast(),
+                USN_BUFFER_SIZE as u32,
+                &mut returned,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return None;
+        }
+
+        let bytes: Vec<u8> = buffer
+            .iter()
+            .flat_map(|word| word.to_ne_bytes())
+            .take(returned as usize)
+            .collect();
+        let next = read_usn_records(&bytes, &mut changes)?;
+        if changes.listings.len() + changes.moved.len() > MAX_USN_DIRECTORIES {
+            return None;
+        }
+        if next <= start {
+            break;
+        }
+        start = next;
+    }
+    Some(changes)
+}
+
+/// Returns the user's UI language identifier (`LANGID`).
+///
+/// # Returns
+///
+/// The language identifier, e.g. `0x0409` for English (United States) or
+/// `0x0804` for Chinese (Simplified).
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::winvol::ui_language;
+///
+/// println!("{:04X}", ui_language());
+/// ```
+#[cfg(windows)]
+#[must_use]
+pub fn ui_language() -> u16 {
+    // SAFETY: takes no arguments and only reads per-user settings.
+    unsafe { GetUserDefaultUILanguage() }
+}
+
+/// Enables escape sequence processing on the console attached to stdout.
+///
+/// Consoles older than Windows Terminal print escape sequences literally
+/// unless this mode is turned on.
+///
+/// # Returns
+///
+/// `true` if stdout is a console that now processes escape sequences,
+/// `false` if it is redirected or the mode cannot be set.
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::winvol::enable_virtual_terminal;
+///
+/// if enable_virtual_terminal() {
+///     println!("\x1b[4mhighlighted\x1b[0m");
+/// }
+/// ```
+#[cfg(windows)]
+#[must_use]
+pub fn enable_virtual_terminal() -> bool {
+    // SAFETY: takes a constant and returns a handle owned by the process.
+    let handle = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+    let mut mode = 0u32;
+
+    // SAFETY: `handle` may be null or invalid, which both calls report as a
+    // failure; `mode` is a writable local.
+    unsafe {
+        if GetConsoleMode(handle, &mut mode) == 0 {
+            return false;
+        }
+        mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+            || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+    }
+}
+
+/// Switches the console attached to stdout to the UTF-8 code page.
+///
+/// tree++ itself writes the console as UTF-16, but a pager sharing the
+/// console, such as `more`, decodes the UTF-8 it is piped with the output
+/// code page of the console. Legacy code pages such as 437 or 936 garble
+/// box-drawing characters and names outside their character set.
+///
+/// # Returns
+///
+/// `Enabled` with the previous code page if the console now outputs UTF-8,
+/// `Unavailable` if it cannot be switched, `NotConsole` if stdout is
+/// redirected.
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::winvol::{enable_utf8_output, set_output_code_page, Utf8Console};
+///
+/// if let Utf8Console::Enabled { previous } = enable_utf8_output() {
+///     println!("├─ UTF-8");
+///     set_output_code_page(previous);
+/// }
+/// ```
+#[cfg(windows)]
+#[must_use]
+pub fn enable_utf8_output() -> Utf8Console {
+    // SAFETY: takes a constant and returns a handle owned by the process.
+    let handle = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+    let mut mode = 0u32;
+
+    // SAFETY: `handle` may be null or invalid, which the call reports as a
+    // failure; `mode` is a writable local.
+    if unsafe { GetConsoleMode(handle, &mut mode) } == 0 {
+        return Utf8Console::NotConsole;
+    }
+
+    // SAFETY: both calls take plain values and only change the console of
+    // this process.
+    let previous = unsafe { GetConsoleOutputCP() };
+    if previous == CP_UTF8 || unsafe { SetConsoleOutputCP(CP_UTF8) } != 0 {
+        Utf8Console::Enabled { previous }
+    } else {
+        Utf8Console::Unavailable
+    }
+}
+
+/// Checks whether the console attached to stdout uses a raster font.
+///
+/// The raster font of the legacy console host ("Terminal") only has the
+/// glyphs of the OEM code page, so box-drawing characters and CJK names
+/// show as boxes or question marks however they are encoded.
+///
+/// # Returns
+///
+/// `Some(true)` for a raster font, `Some(false)` for a TrueType font, or
+/// `None` if stdout is not a console.
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::winvol::console_uses_raster_font;
+///
+/// let ascii = console_uses_raster_font() == Some(true);
+/// println!("{}", if ascii { "+---" } else { "├─" });
+/// ```
+#[cfg(windows)]
+#[must_use]
+pub fn console_uses_raster_font() -> Option<bool> {
+    // SAFETY: takes a constant and returns a handle owned by the process.
+    let handle = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+
+    // SAFETY: the structure is plain data, for which all zeroes is valid.
+    let mut info: CONSOLE_FONT_INFOEX = unsafe { std::mem::zeroed() };
+    info.cbSize = size_of::<CONSOLE_FONT_INFOEX>() as u32;
+
+    // SAFETY: `handle` may be null or invalid, which the call reports as a
+    // failure; `info` is a writable local with its size set.
+    if unsafe { GetCurrentConsoleFontEx(handle, 0, &mut info) } == 0 {
+        return None;
+    }
+    Some(info.FontFamily & TMPF_TRUETYPE == 0)
+}
+
+/// Sets the output code page of the console, e.g. to restore it on exit.
+///
+/// # Arguments
+///
+/// * `code_page` - Code page identifier, e.g. `437` or `65001`
+///
+/// # Returns
+///
+/// `true` if the code page was set.
+#[cfg(windows)]
+pub fn set_output_code_page(code_page: u32) -> bool {
+    // SAFETY: takes a plain value and only changes the console of this
+    // process; an unknown code page is reported as a failure.
+    unsafe { SetConsoleOutputCP(code_page) != 0 }
+}
+
+/// Returns the visible width of the console attached to stdout.
+///
+/// The width is that of the window, not of the screen buffer, which is
+/// often much wider than what is shown.
+///
+/// # Returns
+///
+/// The width in columns, or `None` if stdout is redirected or the console
+/// cannot be queried.
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::winvol::console_width;
+///
+/// if let Some(width) = console_width() {
+///     println!("{}", "-".repeat(width - 1));
+/// }
+/// ```
+#[cfg(windows)]
+#[must_use]
+pub fn console_width() -> Option<usize> {
+    let window = console_window()?;
+    window_extent(window.Left, window.Right)
+}
+
+/// Returns the visible height of the console attached to stdout.
+///
+/// Like the width, this is the height of the window rather than of the
+/// scrollback buffer.
+///
+/// # Returns
+///
+/// The height in rows, or `None` if stdout is redirected or the console
+/// cannot be queried.
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::winvol::console_height;
+///
+/// if let Some(rows) = console_height() {
+///     println!("{rows} rows fit on one screen");
+/// }
+/// ```
+#[cfg(windows)]
+#[must_use]
+pub fn console_height() -> Option<usize> {
+    let window = console_window()?;
+    window_extent(window.Top, window.Bottom)
+}
+
+/// Writes a string value, creating the key and its parents if needed.
+///
+/// # Arguments
+///
+/// * `hive` - Predefined key the path is relative to
+/// * `key` - Path of the key below the hive, separated by backslashes
+/// * `name` - Name of the value, or `None` for the default value of the key
+/// * `value` - Data of the value, stored as `REG_SZ`
+///
+/// # Errors
+///
+/// Returns the system error, e.g. `PermissionDenied` for
+/// `HKEY_LOCAL_MACHINE` without elevation.
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::winvol::{set_registry_string, RegistryHive};
+///
+/// set_registry_string(RegistryHive::CurrentUser, r"Software\tree++", None, "demo").unwrap();
+/// ```
+#[cfg(windows)]
+pub fn set_registry_string(
+    hive: RegistryHive,
+    key: &str,
+    name: Option<&str>,
+    value: &str,
+) -> io::Result<()> {
+    let key = to_wide(key);
+    let name = name.map(to_wide);
+    let data = to_wide(value);
+    let name_ptr = name.as_ref().map_or(std::ptr::null(), |name| name.as_ptr());
+
+    // SAFETY: all strings are NUL-terminated locals, and the data length in
+    // bytes covers `data` including its terminator.
+    let status = unsafe {
+        RegSetKeyValueW(
+            hive.handle(),
+            key.as_ptr(),
+            name_ptr,
+            REG_SZ,
+            data.as_ptr().cast(),
+            (data.len() * size_of::<u16>()) as u32,
+        )
+    };
+    status_to_result(status)
+}
+
+/// Deletes a key with all of its values and subkeys.
+///
+/// # Arguments
+///
+/// * `hive` - Predefined key the path is relative to
+/// * `key` - Path of the key below the hive, separated by backslashes
+///
+/// # Errors
+///
+/// Returns the system error; a missing key is reported as `NotFound`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use treepp::winvol::{delete_registry_tree, RegistryHive};
+///
+/// delete_registry_tree(RegistryHive::CurrentUser, r"Software\tree++").ok();
+/// ```
+#[cfg(windows)]
+pub fn delete_registry_tree(hive: RegistryHive, key: &str) -> io::Result<()> {
+    let key = to_wide(key);
+
+    // SAFETY: `key` is a NUL-terminated local and the hive is predefined.
+    let status = unsafe { RegDeleteTreeW(hive.handle(), key.as_ptr()) };
+    status_to_result(status)
+}
+
+/// Creates an instance of a named pipe for `--serve --pipe`.
+///
+/// The pipe is duplex, byte-oriented, blocking, and rejects clients on
+/// other machines. The returned file reads and writes the pipe once a
+/// client is connected with `connect_pipe`.
+///
+/// # Arguments
+///
+/// * `path` - Full path of the pipe, such as `\\.\pipe\treepp`
+/// * `first` - Whether this is the first instance; creating it fails if
+///   another process already owns a pipe at `path`
+///
+/// # Errors
+///
+/// Returns the system error, e.g. `PermissionDenied` if the name is taken.
+#[cfg(windows)]
+pub fn create_pipe(path: &str, first: bool) -> io::Result<File> {
+    let path = to_wide(path);
+    let mut open_mode = PIPE_ACCESS_DUPLEX;
+    if first {
+        open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+    }
+
+    // SAFETY: `path` is a NUL-terminated local, and a null pointer selects
+    // the default security descriptor.
+    let handle = unsafe {
+        CreateNamedPipeW(
+            path.as_ptr(),
+            open_mode,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            PIPE_BUFFER_SIZE,
+            PIPE_BUFFER_SIZE,
+            0,
+            std::ptr::null(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the handle was just created and is owned by nothing else.
+    Ok(File::from(unsafe { OwnedHandle::from_raw_handle(handle) }))
+}
+
+/// Waits until a client connects to a pipe instance from `create_pipe`.
+///
+/// A client that connected before the call counts as connected.
+///
+/// # Errors
+///
+/// Returns the system error, e.g. if the client closed the pipe before it
+/// was accepted.
+#[cfg(windows)]
+pub fn connect_pipe(pipe: &File) -> io::Result<()> {
+    // SAFETY: the handle is a pipe instance owned by `pipe`, opened without
+    // `FILE_FLAG_OVERLAPPED`, so no `OVERLAPPED` structure is needed.
+    if unsafe { ConnectNamedPipe(pipe.as_raw_handle(), std::ptr::null_mut()) } != 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(ERROR_PIPE_CONNECTED as i32) {
+        return Ok(());
+    }
+    Err(err)
+}
+
+/// Renames a file unless the target already exists.
+///
+/// Unlike `std::fs::rename`, an existing target is never replaced, and
+/// the check and the rename are one step, so a file created at `to` in
+/// the meantime is not lost.
+///
+/// # Arguments
+///
+/// * `from` - The file to move.
+/// * `to` - The new path, on the same volume.
+///
+/// # Errors
+///
+/// Returns `AlreadyExists` if `to` exists, or the system error otherwise.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use treepp::winvol::rename_no_replace;
+///
+/// rename_no_replace(Path::new("tree.txt.tmp"), Path::new("tree.txt")).unwrap();
+/// ```
+#[cfg(windows)]
+pub fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    let wide = |path: &Path| -> Vec<u16> {
+        path.as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    };
+    let (from, to) = (wide(from), wide(to));
+
+    // SAFETY: both paths are NUL-terminated locals; without
+    // `MOVEFILE_REPLACE_EXISTING` the call fails if `to` exists.
+    if unsafe { MoveFileExW(from.as_ptr(), to.as_ptr(), MOVEFILE_WRITE_THROUGH) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Fallbacks for Other Platforms
+// ============================================================================
+
+/// Queries the label and serial number of a drive; there are no drives
+/// outside Windows, so this returns `None`.
+#[cfg(not(windows))]
+#[must_use]
+pub fn volume_info(drive: char) -> Option<VolumeInfo> {
+    let _ = drive;
+    None
+}
+
+/// Queries the serial number of the volume holding a file or directory;
+/// outside Windows this returns `None`, so all entries count as one volume.
+#[cfg(not(windows))]
+#[must_use]
+pub fn volume_serial(path: &Path) -> Option<u32> {
+    let _ = path;
+    None
+}
+
+/// Returns the user's UI language identifier; outside Windows this is
+/// always `0x0409`, English (United States).
+#[cfg(not(windows))]
+#[must_use]
+pub const fn ui_language() -> u16 {
+    0x0409
+}
+
+/// Reports whether stdout processes escape sequences; terminals outside
+/// Windows always do, so this only checks that stdout is one.
+#[cfg(not(windows))]
+#[must_use]
+pub fn enable_virtual_terminal() -> bool {
+    use std::io::IsTerminal;
+    io::stdout().is_terminal()
+}
+
+/// Switches the console to UTF-8; outside Windows terminals have no code
+/// page to set, so this returns `NotConsole`.
+#[cfg(not(windows))]
+#[must_use]
+pub const fn enable_utf8_output() -> Utf8Console {
+    Utf8Console::NotConsole
+}
+
+/// Checks the console font; outside Windows there is no console font to
+/// query, so this returns `None`.
+#[cfg(not(windows))]
+#[must_use]
+pub const fn console_uses_raster_font() -> Option<bool> {
+    None
+}
+
+/// Sets the output code page of the console; outside Windows there is
+/// none, so this returns `false`.
+#[cfg(not(windows))]
+pub const fn set_output_code_page(code_page: u32) -> bool {
+    let _ = code_page;
+    false
+}
+
+/// Returns the visible width of the console; outside Windows this is
+/// `None`.
+#[cfg(not(windows))]
+#[must_use]
+pub const fn console_width() -> Option<usize> {
+    None
+}
+
+/// Returns the visible height of the console; outside Windows this is
+/// `None`.
+#[cfg(not(windows))]
+#[must_use]
+pub const fn console_height() -> Option<usize> {
+    None
+}
+
+/// Writes a registry string value; there is no registry outside Windows.
+///
+/// # Errors
+///
+/// Always returns `Unsupported`.
+#[cfg(not(windows))]
+pub fn set_registry_string(
+    hive: RegistryHive,
+    key: &str,
+    name: Option<&str>,
+    value: &str,
+) -> io::Result<()> {
+    let _ = (hive, key, name, value);
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Deletes a registry key; there is no registry outside Windows.
+///
+/// # Errors
+///
+/// Always returns `Unsupported`.
+#[cfg(not(windows))]
+pub fn delete_registry_tree(hive: RegistryHive, key: &str) -> io::Result<()> {
+    let _ = (hive, key);
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Creates a named pipe; named pipes of this kind only exist on Windows.
+///
+/// # Errors
+///
+/// Always returns `Unsupported`.
+#[cfg(not(windows))]
+pub fn create_pipe(path: &str, first: bool) -> io::Result<File> {
+    let _ = (path, first);
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Queries the volume and index of a file; outside Windows this returns
+/// `None`, like `volume_serial`.
+#[cfg(not(windows))]
+#[must_use]
+pub fn file_id(path: &Path) -> Option<FileId> {
+    let _ = path;
+    None
+}
+
+/// Returns the position in the change journal of a volume; there is no
+/// NTFS change journal outside Windows, so this returns `None`.
+#[cfg(not(windows))]
+#[must_use]
+pub fn usn_position(dir: &Path) -> Option<UsnPosition> {
+    let _ = dir;
+    None
+}
+
+/// Reads the directories changed since a position in the change journal;
+/// there is no NTFS change journal outside Windows, so this returns `None`.
+#[cfg(not(windows))]
+#[must_use]
+pub fn usn_changes(dir: &Path, since: UsnPosition) -> Option<UsnChanges> {
+    let _ = (dir, since);
+    None
+}
+
+/// Waits for a pipe client; named pipes of this kind only exist on Windows.
+///
+/// # Errors
+///
+/// Always returns `Unsupported`.
+#[cfg(not(windows))]
+pub fn connect_pipe(pipe: &File) -> io::Result<()> {
+    let _ = pipe;
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Renames a file unless the target already exists; outside Windows the
+/// file is hard-linked to the new path, which fails if it exists, and the
+/// old path is removed.
+///
+/// # Errors
+///
+/// Returns `AlreadyExists` if `to` exists, or the I/O error otherwise.
+#[cfg(not(windows))]
+pub fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    std::fs::hard_link(from, to)?;
+    std::fs::remove_file(from)
+}
+
+// ============================================================================
+// Internal Functions
+// ============================================================================
+
+/// Queries the information of a file or directory by handle.
+#[cfg(windows)]
+fn file_information(path: &Path) -> Option<BY_HANDLE_FILE_INFORMATION> {
+    // Directories can only be opened with backup semantics.
+    let file = OpenOptions::new()
+        .access_mode(FILE_READ_ATTRIBUTES)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+        .ok()?;
+    let mut info = BY_HANDLE_FILE_INFORMATION::default();
+
+    // SAFETY: the handle stays open while `file` is alive, and `info` is a
+    // writable local.
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle(), &mut info) } == 0 {
+        return None;
+    }
+    Some(info)
+}
+
+/// Opens a directory for reading, as the handle the change journal of its
+/// volume is queried through.
+#[cfg(windows)]
+fn open_directory(dir: &Path) -> Option<File> {
+    OpenOptions::new()
+        .read(true)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(dir)
+        .ok()
+}
+
+/// Queries the change journal of the volume a handle belongs to.
+#[cfg(windows)]
+fn query_usn_journal(volume: &File) -> Option<USN_JOURNAL_DATA_V0> {
+    let mut journal = USN_JOURNAL_DATA_V0::default();
+    let mut returned = 0u32;
+
+    // SAFETY: `journal` is a writable local of the size passed, no input
+    // buffer is needed, and the handle was opened without
+    // `FILE_FLAG_OVERLAPPED`.
+    let ok = unsafe {
+        DeviceIoControl(
+            volume.as_raw_handle(),
+            FSCTL_QUERY_USN_JOURNAL,
+            std::ptr::null(),
+            0,
+            (&raw mut journal).cast(),
+            size_of::<USN_JOURNAL_DATA_V0>() as u32,
+            &mut returned,
+            std::ptr::null_mut(),
+        )
+    };
+    (ok != 0).then_some(journal)
+}
+
+/// Adds the directories touched by a block of change-journal records to
+/// `changes`.
+///
+/// `bytes` is the output of `FSCTL_READ_UNPRIVILEGED_USN_JOURNAL`: the USN
+/// to continue from, followed by `USN_RECORD_V2` records.
+///
+/// # Returns
+///
+/// The USN to continue from, or `None` if a record is malformed or of
+/// another version.
+#[cfg(windows)]
+fn read_usn_records(bytes: &[u8], changes: &mut UsnChanges) -> Option<i64> {
+    let next = i64::from_le_bytes(bytes.get(..8)?.try_into().ok()?);
+    let mut records = &bytes[8..];
+    while !records.is_empty() {
+        let header = records.get(..USN_RECORD_V2_HEADER_LEN)?;
+        let read_u32 =
+            |at: usize| u32::from_le_bytes(header[at..at + 4].try_into().unwrap_or_default());
+        let read_u64 =
+            |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().unwrap_or_default());
+        let length = read_u32(0) as usize;
+        let major_version = u16::from_le_bytes([header[4], header[5]]);
+        if major_version != 2 || length < USN_RECORD_V2_HEADER_LEN || length > records.len() {
+            return None;
+        }
+
+        changes.listings.insert(read_u64(16));
+        if read_u32(52) & FILE_ATTRIBUTE_DIRECTORY != 0 {
+            changes.moved.insert(read_u64(8));
+        }
+        records = &records[length..];
+    }
+    Some(next)
+}
+
+/// Encodes a string as NUL-terminated UTF-16.
+#[cfg(windows)]
+fn to_wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// Converts a registry status code into a result.
+#[cfg(windows)]
+fn status_to_result(status: u32) -> io::Result<()> {
+    if status == ERROR_SUCCESS {
+        Ok(())
+    } else {
+        Err(io::Error::from_raw_os_error(status as i32))
+    }
+}
+
+/// Queries the visible window of the console attached to stdout.
+#[cfg(windows)]
+fn console_window() -> Option<SMALL_RECT> {
+    // SAFETY: takes a constant and returns a handle owned by the process.
+    let handle = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+
+    // SAFETY: the structure is plain data, for which all zeroes is valid.
+    let mut info: CONSOLE_SCREEN_BUFFER_INFO = unsafe { std::mem::zeroed() };
+
+    // SAFETY: `handle` may be null or invalid, which the call reports as a
+    // failure; `info` is a writable local.
+    if unsafe { GetConsoleScreenBufferInfo(handle, &mut info) } == 0 {
+        return None;
+    }
+    Some(info.srWindow)
+}
+
+/// Returns the number of cells between two inclusive window coordinates.
+#[cfg(windows)]
+fn window_extent(first: i16, last: i16) -> Option<usize> {
+    let extent = i32::from(last) - i32::from(first) + 1;
+    usize::try_from(extent).ok().filter(|&extent| extent > 0)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn should_format_serial_as_two_hex_groups() {
+        let info = VolumeInfo {
+            label: "OS".to_string(),
+            serial: 0x2810_11C7,
+        };
+        assert_eq!(info.serial_text(), "2810-11C7");
+    }
+
+    #[test]
+    fn should_pad_serial_groups_with_zeros() {
+        let info = VolumeInfo {
+            label: String::new(),
+            serial: 0x0000_00ff,
+        };
+        assert_eq!(info.serial_text(), "0000-00FF");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn should_count_window_cells_inclusively() {
+        assert_eq!(window_extent(0, 119), Some(120));
+        assert_eq!(window_extent(300, 329), Some(30));
+        assert_eq!(window_extent(5, 4), None);
+    }
+
+    /// Encodes a `USN_RECORD_V2` without a file name.
+    #[cfg(windows)]
+    fn usn_record(file: u64, parent: u64, reason: u32, attributes: u32) -> Vec<u8> {
+        let mut record = vec![0u8; USN_RECORD_V2_HEADER_LEN + 4];
+        let length = record.len() as u32;
+        record[..4].copy_from_slice(&length.to_le_bytes());
+        record[4..6].copy_from_slice(&2u16.to_le_bytes());
+        record[8..16].copy_from_slice(&file.to_le_bytes());
+        record[16..24].copy_from_slice(&parent.to_le_bytes());
+        record[40..44].copy_from_slice(&reason.to_le_bytes());
+        record[52..56].copy_from_slice(&attributes.to_le_bytes());
+        record
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn should_collect_changed_directories_from_usn_records() {
+        let mut bytes = 4096i64.to_le_bytes().to_vec();
+        bytes.extend(usn_record(10, 1, USN_REASON_FILE_CREATE, 0));
+        bytes.extend(usn_record(
+            20,
+            2,
+            USN_REASON_RENAME_NEW_NAME,
+            FILE_ATTRIBUTE_DIRECTORY,
+        ));
+
+        let mut changes = UsnChanges::default();
+        assert_eq!(read_usn_records(&bytes, &mut changes), Some(4096));
+        assert_eq!(changes.listings, HashSet::from([1, 2]));
+        assert_eq!(
+            changes.moved,
+            HashSet::from([20]),
+            "只有目录自身的变更才算移动"
+        );
+
+        bytes.truncate(bytes.len() - 8);
+        assert_eq!(read_usn_records(&bytes, &mut changes), None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn should_encode_wide_strings_with_terminator() {
+        assert_eq!(to_wide("ab"), vec![u16::from(b'a'), u16::from(b'b'), 0]);
+        assert_eq!(to_wide(""), vec![0]);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn should_report_registry_status_as_io_error() {
+        assert!(status_to_result(ERROR_SUCCESS).is_ok());
+        let err = status_to_result(2).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            io::ErrorKind::NotFound,
+            "ERROR_FILE_NOT_FOUND 应映射为 NotFound"
+        );
+    }
+
+    #[test]
+    fn should_report_same_volume_for_nested_directories() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        assert_eq!(volume_serial(dir.path()), volume_serial(&nested));
+        assert_eq!(volume_serial(&dir.path().join("missing")), None);
+        assert_eq!(file_id(&dir.path().join("missing")), None);
+        assert_eq!(file_id(dir.path()).is_some(), cfg!(windows));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn should_reject_registry_writes_elsewhere() {
+        let err = set_registry_string(RegistryHive::CurrentUser, r"Software\tree++", None, "x")
+            .unwrap_err();
+        assert_eq!(
+            err.kind(),
+            io::ErrorKind::Unsupported,
+            "非 Windows 平台应报告不支持"
+        );
+        assert_eq!(volume_info('C'), None);
+        assert_eq!(enable_utf8_output(), Utf8Console::NotConsole);
+        let err = create_pipe(r"\\.\pipe\treepp", true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert_eq!(usn_position(Path::new(".")), None);
+    }
+}
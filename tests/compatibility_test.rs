@@ -285,8 +285,14 @@ fn compact_diff(native: &CommandOutput, treepp: &CommandOutput, context: &str) {
     let max_lines = native_lines.len().max(treepp_lines.len());
 
     for i in 0..max_lines {
-        let n = native_lines.get(i).map(|s| s.as_str()).unwrap_or("<missing>");
-        let t = treepp_lines.get(i).map(|s| s.as_str()).unwrap_or("<missing>");
+        let n = native_lines
+            .get(i)
+            .map(|s| s.as_str())
+            .unwrap_or("<missing>");
+        let t = treepp_lines
+            .get(i)
+            .map(|s| s.as_str())
+            .unwrap_or("<missing>");
 
         if n != t {
             diff_count += 1;
@@ -1709,4 +1715,4 @@ mod tests {
         assert_exit_codes(&native, &treepp, "正斜杠路径/F");
         compact_diff(&native, &treepp, "正斜杠路径-/F");
     }
-}
\ No newline at end of file
+}
@@ -19,7 +19,7 @@
 //! - Path edge cases
 //!
 //! Author: WaterRun
-//! Date: 2026-02-06
+//! Date: 2026-10-16
 
 use std::fs::{self, File};
 use std::io::Write;
@@ -50,9 +50,14 @@ fn get_treepp_path() -> PathBuf {
     panic!("treepp not built, please run `cargo build` first");
 }
 
+/// Language pinned for every run, so report and error text do not depend
+/// on the display language of the machine running the tests.
+const TEST_LANG: [&str; 2] = ["--lang", "en"];
+
 /// Executes treepp with the given arguments.
 fn run_treepp(args: &[&str]) -> Output {
     Command::new(get_treepp_path())
+        .args(TEST_LANG)
         .args(args)
         .output()
         .expect("Failed to execute treepp")
@@ -62,6 +67,7 @@ fn run_treepp(args: &[&str]) -> Output {
 fn run_treepp_in_dir(dir: &Path, args: &[&str]) -> Output {
     Command::new(get_treepp_path())
         .current_dir(dir)
+        .args(TEST_LANG)
         .args(args)
         .output()
         .expect("Failed to execute treepp")
@@ -433,6 +439,16 @@ fn create_gitignore_edge_cases_dir() -> TempDir {
     dir
 }
 
+/// Creates a directory with 2000 empty files, producing more output than a
+/// pipe buffer holds.
+fn create_wide_test_dir() -> TempDir {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    for i in 0..2000 {
+        File::create(dir.path().join(format!("file_{i:04}.txt"))).unwrap();
+    }
+    dir
+}
+
 /// Creates a directory with symbolic links (if supported).
 ///
 /// Returns None if symlink creation fails (e.g., no admin rights on Windows).
@@ -469,15 +485,14 @@ fn create_symlink_test_dir() -> Option<TempDir> {
 fn should_show_help_with_all_flag_variants() {
     for flag in &["--help", "-h", "/?"] {
         let output = run_treepp(&[flag]);
-        assert!(
-            output.status.success(),
-            "Help flag {} should succeed",
-            flag
-        );
+        assert!(output.status.success(), "Help flag {} should succeed", flag);
         let stdout = stdout_str(&output);
         assert!(stdout.contains("tree++"), "Should contain program name");
         assert!(stdout.contains("Usage:"), "Should contain usage section");
-        assert!(stdout.contains("Options:"), "Should contain options section");
+        assert!(
+            stdout.contains("Options:"),
+            "Should contain options section"
+        );
     }
 }
 
@@ -523,6 +538,19 @@ fn should_scan_specified_directory() {
     assert!(stdout_str(&output).contains("src"));
 }
 
+#[test]
+fn should_scan_directory_given_with_trailing_separator_and_quote() {
+    let dir = create_basic_test_dir();
+    let path = dir.path().to_string_lossy().to_string();
+    // `treepp "C:\dir\"` reaches the program as `C:\dir\"`.
+    let typed = format!("{}{}\"", path, std::path::MAIN_SEPARATOR);
+
+    let plain = run_treepp(&[&path, "/nb"]);
+    let output = run_treepp(&[&typed, "/nb"]);
+    assert!(output.status.success());
+    assert_eq!(stdout_str(&output), stdout_str(&plain));
+}
+
 #[test]
 fn should_fail_for_nonexistent_path() {
     let output = run_treepp(&["/nonexistent/path/12345"]);
@@ -590,6 +618,25 @@ fn should_use_ascii_characters_with_a_flag() {
     assert!(!stdout.contains("└─"));
 }
 
+#[test]
+fn should_select_charset_with_cs() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/cs", "ascii", "/nb"]);
+    assert!(output.status.success());
+    assert!(stdout_str(&output).contains("\\---"));
+
+    // Redirected output is drawn with Unicode under the default `auto`.
+    for args in [
+        &["/nb"][..],
+        &["/cs", "auto", "/nb"],
+        &["/a", "/cs", "unicode", "/nb"],
+    ] {
+        let output = run_treepp_in_dir(dir.path(), args);
+        assert!(stdout_str(&output).contains("└─"), "测试 {args:?} 失败");
+    }
+}
+
 #[test]
 fn should_use_unicode_by_default() {
     let dir = create_basic_test_dir();
@@ -615,6 +662,21 @@ fn should_show_full_paths_with_fp_flag() {
     assert!(stdout.contains("main.rs"));
 }
 
+#[test]
+fn should_show_paths_relative_to_base_with_rel_flag() {
+    let dir = create_basic_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["src", "/f", "/rel", "..", "/nb"]);
+    assert!(output.status.success());
+    let stdout = stdout_str(&output);
+
+    let root = dir.path().to_string_lossy();
+    assert!(
+        !stdout.contains(root.as_ref()),
+        "should not show absolute paths"
+    );
+    assert!(stdout.contains("src\\main.rs") || stdout.contains("src/main.rs"));
+}
+
 // ============================================================================
 // Size Display Tests (/S, /HR)
 // ============================================================================
@@ -706,6 +768,124 @@ fn should_combine_no_indent_with_ascii() {
     assert!(!stdout.contains("\\"));
 }
 
+// ============================================================================
+// File Columns Tests (/COL)
+// ============================================================================
+
+#[test]
+fn should_lay_files_out_in_columns() {
+    let dir = TempDir::new().unwrap();
+    for i in 0..30 {
+        File::create(dir.path().join(format!("file_{i:02}.txt"))).unwrap();
+    }
+    fs::create_dir(dir.path().join("sub")).unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/col"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+
+    let file_lines: Vec<_> = stdout.lines().filter(|l| l.contains("file_")).collect();
+    assert!(file_lines.len() < 30, "files should share lines: {stdout}");
+    assert!(
+        file_lines.iter().all(|l| l.chars().count() < 80),
+        "{stdout}"
+    );
+    assert!(
+        stdout.lines().any(|l| l.ends_with("sub")),
+        "directories keep their lines"
+    );
+}
+
+// ============================================================================
+// Interleave Tests (/IL)
+// ============================================================================
+
+#[test]
+fn should_sort_files_and_directories_together() {
+    let dir = TempDir::new().unwrap();
+    File::create(dir.path().join("a.txt")).unwrap();
+    fs::create_dir(dir.path().join("b_dir")).unwrap();
+    File::create(dir.path().join("c.txt")).unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/il"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let lines: Vec<_> = stdout_str(&output)
+        .lines()
+        .skip(1)
+        .map(str::to_string)
+        .collect();
+    assert_eq!(lines, ["├─a.txt", "├─b_dir", "└─c.txt"]);
+
+    let output_file = dir.path().join("tree.json");
+    let output = run_treepp_in_dir(dir.path(), &["/il", "/o", output_file.to_str().unwrap()]);
+    assert!(
+        !output.status.success(),
+        "structured output keeps files apart"
+    );
+}
+
+// ============================================================================
+// Level Guide Tests (/LGD)
+// ============================================================================
+
+#[test]
+fn should_number_level_guides_in_stream_and_batch_mode() {
+    let dir = create_basic_test_dir();
+
+    let stream = run_treepp_in_dir(dir.path(), &["/f", "/lgd", "number", "/nb"]);
+    let batch = run_treepp_in_dir(dir.path(), &["/f", "/lgd", "number", "/nb", "/b"]);
+    assert!(stream.status.success(), "stderr: {}", stderr_str(&stream));
+    assert_eq!(stdout_str(&stream), stdout_str(&batch));
+    assert!(
+        stdout_str(&stream).contains("│1 "),
+        "{}",
+        stdout_str(&stream)
+    );
+}
+
+#[test]
+fn should_reject_level_guides_without_connectors() {
+    let dir = create_basic_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/lgd", "number", "/ni"]);
+    assert!(!output.status.success());
+}
+
+// ============================================================================
+// Flat Listing Tests (/FLAT)
+// ============================================================================
+
+#[test]
+fn should_list_full_paths_with_flat() {
+    let dir = create_basic_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/flat"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines.iter().any(|l| l.ends_with("src\\main.rs")));
+    for line in &lines {
+        assert!(
+            Path::new(line).exists(),
+            "every line is an existing path: {line}"
+        );
+    }
+    assert!(!stdout.contains("Volume"), "no banner in a flat listing");
+}
+
+#[test]
+fn should_list_same_paths_with_flat_in_batch_mode() {
+    let dir = create_basic_test_dir();
+
+    let stream = run_treepp_in_dir(dir.path(), &["/f", "/flat", "/l", "1"]);
+    let batch = run_treepp_in_dir(dir.path(), &["/f", "/flat", "/l", "1", "/b"]);
+    assert!(batch.status.success(), "stderr: {}", stderr_str(&batch));
+    assert_eq!(stdout_str(&stream), stdout_str(&batch));
+    assert!(
+        !stdout_str(&batch).contains("main.rs"),
+        "depth limits are honored"
+    );
+}
+
 // ============================================================================
 // Reverse Sort Tests (/R)
 // ============================================================================
@@ -839,6 +1019,30 @@ fn should_always_show_directories_with_include() {
     assert!(stdout.contains("tests"));
 }
 
+#[test]
+fn should_match_decomposed_names_unless_nn() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("cafe\u{301}-2023.txt"), "").unwrap();
+    fs::write(dir.path().join("caf\u{e9}-2024.txt"), "").unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/m", "caf\u{e9}*", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(
+        stdout.contains("cafe\u{301}-2023.txt"),
+        "分解形式的名称应被匹配: {stdout}"
+    );
+    assert!(stdout.contains("caf\u{e9}-2024.txt"));
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/m", "caf\u{e9}*", "/nb", "/nn"]);
+    let stdout = stdout_str(&output);
+    assert!(
+        !stdout.contains("cafe\u{301}-2023.txt"),
+        "/NN 应按存储形式匹配: {stdout}"
+    );
+    assert!(stdout.contains("caf\u{e9}-2024.txt"));
+}
+
 // ============================================================================
 // Exclude Pattern Tests (/X)
 // ============================================================================
@@ -879,6 +1083,38 @@ fn should_exclude_directories() {
     assert!(stdout.contains("src"));
 }
 
+#[test]
+fn should_read_patterns_from_file_in_declaration_order() {
+    let dir = create_basic_test_dir();
+    fs::write(
+        dir.path().join("ci.patterns"),
+        "# CI filters\r\n*.patterns\r\n*.rs\r\n!lib.rs\r\n+*.rs\r\n+*.md\r\n",
+    )
+    .unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/xf", "ci.patterns", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("lib.rs"), "!lib.rs 应重新包含: {stdout}");
+    assert!(stdout.contains("file2.md"));
+    assert!(!stdout.contains("main.rs"));
+    assert!(!stdout.contains("file1.txt"));
+    assert!(!stdout.contains("ci.patterns"));
+
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["/f", "/xf", "ci.patterns", "/x", "lib*", "/nb"],
+    );
+    let stdout = stdout_str(&output);
+    assert!(
+        !stdout.contains("lib.rs"),
+        "之后的 /X 应覆盖文件中的模式: {stdout}"
+    );
+
+    let output = run_treepp_in_dir(dir.path(), &["/xf", "missing.patterns"]);
+    assert!(!output.status.success());
+}
+
 #[test]
 fn should_combine_include_and_exclude() {
     let dir = create_basic_test_dir();
@@ -895,6 +1131,30 @@ fn should_combine_include_and_exclude() {
     assert!(!stdout.contains("file2.md"));
 }
 
+#[test]
+fn should_exclude_project_output_with_smart() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+    fs::create_dir_all(dir.path().join("target").join("debug")).unwrap();
+    fs::create_dir_all(dir.path().join("docs").join("build")).unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/sm", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(!stdout.contains("target"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("build"),
+        "build without CMakeLists.txt must stay: {stdout}"
+    );
+
+    let explain = run_treepp_in_dir(dir.path(), &["/sm", "/ex", "target\\debug"]);
+    assert!(
+        stdout_str(&explain).contains("Cargo.toml"),
+        "stdout: {}",
+        stdout_str(&explain)
+    );
+}
+
 // ============================================================================
 // Report Tests (/RP)
 // ============================================================================
@@ -1047,6 +1307,46 @@ fn should_handle_empty_gitignore_file() {
     assert!(stdout_str(&output).contains("file.txt"));
 }
 
+// ============================================================================
+// Verbose Log Tests (/VB, /LOG)
+// ============================================================================
+
+#[test]
+fn should_log_scan_details_to_stderr_with_vb() {
+    let dir = create_gitignore_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/vb", "/vb", "/g", "/f", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stderr = stderr_str(&output);
+    assert!(stderr.contains("backend: streaming"), "stderr: {stderr}");
+    assert!(
+        stderr.contains(".gitignore ("),
+        "loaded .gitignore must be logged: {stderr}"
+    );
+    assert!(
+        stderr.contains("rejected by .gitignore (/G)"),
+        "stderr: {stderr}"
+    );
+    assert!(
+        !stdout_str(&output).contains("backend"),
+        "the log must stay off stdout"
+    );
+}
+
+#[test]
+fn should_write_log_to_file_with_log() {
+    let dir = create_basic_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/nb", "/log", "scan.log"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(
+        !stderr_str(&output).contains("backend"),
+        "stderr: {}",
+        stderr_str(&output)
+    );
+    let log = fs::read_to_string(dir.path().join("scan.log")).unwrap();
+    assert!(log.contains("backend: batch"), "log: {log}");
+    assert!(log.contains("render: "), "log: {log}");
+}
+
 // ============================================================================
 // Hidden Files Tests (/AL)
 // ============================================================================
@@ -1210,6 +1510,51 @@ fn should_produce_consistent_results_with_different_thread_counts() {
     assert_eq!(stdout_str(&output4), stdout_str(&output16));
 }
 
+// ============================================================================
+// Scan Backend Tests (/BK)
+// ============================================================================
+
+#[test]
+fn should_produce_same_tree_with_every_backend() {
+    let dir = create_basic_test_dir();
+    let expected = stdout_str(&run_treepp_in_dir(dir.path(), &["/b", "/f", "/nb"]));
+
+    for backend in ["walk", "parallel", "channel"] {
+        let output = run_treepp_in_dir(dir.path(), &["/b", "/f", "/nb", "/bk", backend]);
+        assert!(
+            output.status.success(),
+            "{backend}: {}",
+            stderr_str(&output)
+        );
+        assert_eq!(stdout_str(&output), expected, "backend {backend}");
+    }
+}
+
+#[test]
+fn should_report_backend_in_stats() {
+    let dir = create_basic_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/bk", "channel", "/sj", "-", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stderr_str(&output).contains("\"scanner\": \"channel\""));
+}
+
+#[test]
+fn should_reject_unavailable_or_streaming_backend() {
+    let dir = create_basic_test_dir();
+    assert_eq!(
+        run_treepp_in_dir(dir.path(), &["/b", "/bk", "mft"])
+            .status
+            .code(),
+        Some(1)
+    );
+    assert_eq!(
+        run_treepp_in_dir(dir.path(), &["/bk", "walk"])
+            .status
+            .code(),
+        Some(1)
+    );
+}
+
 // ============================================================================
 // Output File Tests (/O)
 // ============================================================================
@@ -1242,8 +1587,7 @@ fn should_output_to_json_file_with_batch() {
     assert!(output_file.exists());
 
     let content = fs::read_to_string(&output_file).unwrap();
-    let json: serde_json::Value =
-        serde_json::from_str(&content).expect("Should be valid JSON");
+    let json: serde_json::Value = serde_json::from_str(&content).expect("Should be valid JSON");
 
     assert_eq!(
         json.get("schema").and_then(|v| v.as_str()),
@@ -1261,6 +1605,42 @@ fn should_fail_json_output_without_batch() {
     assert_eq!(output.status.code(), Some(1));
 }
 
+#[test]
+fn should_add_node_ids_to_json_output() {
+    let dir = create_basic_test_dir();
+    let output_file = dir.path().join("tree.json");
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &[
+            "/b",
+            "/f",
+            "/id",
+            "/si",
+            "/o",
+            output_file.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_file).unwrap()).unwrap();
+    assert_eq!(json["root"]["id"], "cbf29ce484222325");
+    let file = json["root"]["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["name"] == "file1.txt")
+        .expect("file1.txt should be listed with its id");
+    assert_eq!(file["id"], "9bb8d62867af9e90");
+    assert_eq!(file["parent_id"], "cbf29ce484222325");
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/id"]);
+    assert!(
+        !output.status.success(),
+        "--node-ids requires JSON or YAML output"
+    );
+}
+
 #[test]
 fn should_output_to_yaml_file_with_batch() {
     let dir = create_basic_test_dir();
@@ -1375,11 +1755,29 @@ fn should_fail_with_unknown_option() {
 #[test]
 fn should_fail_with_duplicate_option() {
     let dir = create_basic_test_dir();
-    let output = run_treepp_in_dir(dir.path(), &["/f", "/f"]);
+    let output = run_treepp_in_dir(dir.path(), &["/l", "1", "/l", "2"]);
     assert!(!output.status.success());
     assert_eq!(output.status.code(), Some(1));
 }
 
+#[test]
+fn should_let_last_boolean_option_win() {
+    let dir = create_basic_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/f", "--no-files", "/nb"]);
+    assert!(output.status.success());
+    assert!(
+        !stdout_str(&output).contains("file1.txt"),
+        "--no-files 应关闭文件显示"
+    );
+
+    let output = run_treepp_in_dir(dir.path(), &["--files=false", "/f", "/nb"]);
+    assert!(output.status.success());
+    assert!(
+        stdout_str(&output).contains("file1.txt"),
+        "最后出现的 /f 应生效"
+    );
+}
+
 #[test]
 fn should_fail_with_multiple_paths() {
     let dir = create_basic_test_dir();
@@ -1397,6 +1795,18 @@ fn should_show_hint_for_unknown_option() {
     assert!(stderr.contains("--help") || stderr.contains("Hint"));
 }
 
+#[test]
+fn should_suggest_closest_value_for_invalid_value() {
+    let output = run_treepp(&["--sort", "sise"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = stderr_str(&output);
+    assert!(
+        stderr.contains("name, size, mtime, ctime, atime or share"),
+        "应列出可接受的值"
+    );
+    assert!(stderr.contains("did you mean 'size'?"), "应提示最接近的值");
+}
+
 // ============================================================================
 // Parameter Style and Mixing Tests
 // ============================================================================
@@ -1480,7 +1890,10 @@ fn should_sort_dotfiles_first() {
     let dotfile_pos = stdout.find(".dotfile").expect(".dotfile not found");
     let apple_pos = stdout.find("Apple.txt").expect("Apple.txt not found");
 
-    assert!(dotfile_pos < apple_pos, ".dotfile should come before Apple.txt");
+    assert!(
+        dotfile_pos < apple_pos,
+        ".dotfile should come before Apple.txt"
+    );
 }
 
 #[test]
@@ -1632,36 +2045,103 @@ fn should_handle_deeply_nested_structure() {
 }
 
 // ============================================================================
-// Large Directory Tests
+// Long Path Tests (> MAX_PATH)
 // ============================================================================
 
-#[test]
-fn should_handle_directory_with_many_files() {
-    let dir = TempDir::new().unwrap();
+/// Creates a `node_modules`-style tree whose deepest file path exceeds 260 characters.
+///
+/// Returns the temp dir and the name of the deepest file.
+fn create_long_path_test_dir() -> (TempDir, String) {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let mut current = dir.path().to_path_buf();
+    let mut level = 0;
 
-    for i in 0..500 {
-        File::create(dir.path().join(format!("file_{:04}.txt", i))).unwrap();
+    while current.to_string_lossy().len() < 320 {
+        current = current.join(format!("node_modules\\package-with-a-long-name-{}", level));
+        level += 1;
     }
+    fs::create_dir_all(&current).unwrap();
 
-    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb"]);
-    assert!(output.status.success());
-    let stdout = stdout_str(&output);
+    let file_name = "deepest-file-beyond-max-path.js".to_string();
+    File::create(current.join(&file_name))
+        .unwrap()
+        .write_all(b"module.exports = {};")
+        .unwrap();
 
-    assert!(stdout.contains("file_0000.txt"));
-    assert!(stdout.contains("file_0250.txt"));
-    assert!(stdout.contains("file_0499.txt"));
+    (dir, file_name)
 }
 
-// ============================================================================
-// Symbolic Link Tests
-// ============================================================================
+#[test]
+fn should_stream_tree_beyond_max_path() {
+    let (dir, file_name) = create_long_path_test_dir();
+    let output = run_treepp(&[dir.path().to_str().unwrap(), "/f", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains(&file_name));
+}
 
 #[test]
-fn should_handle_symbolic_links() {
-    if let Some(dir) = create_symlink_test_dir() {
-        let output = run_treepp_in_dir(dir.path(), &["/f", "/nb"]);
-        assert!(output.status.success());
-        let stdout = stdout_str(&output);
+fn should_batch_scan_tree_beyond_max_path() {
+    let (dir, file_name) = create_long_path_test_dir();
+    let output = run_treepp(&[dir.path().to_str().unwrap(), "/f", "/nb", "/b"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains(&file_name));
+}
+
+#[test]
+fn should_not_show_verbatim_prefix_for_long_full_paths() {
+    let (dir, file_name) = create_long_path_test_dir();
+    let output = run_treepp(&[dir.path().to_str().unwrap(), "/f", "/nb", "/fp"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains(&file_name));
+    assert!(!stdout.contains(r"\\?\"));
+}
+
+#[test]
+fn should_accept_verbatim_root_path() {
+    let dir = create_basic_test_dir();
+    let canonical = fs::canonicalize(dir.path()).unwrap();
+    let verbatim = canonical.to_string_lossy().into_owned();
+    assert!(verbatim.starts_with(r"\\?\"));
+
+    let output = run_treepp(&[&verbatim, "/f", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("main.rs"));
+    assert!(!stdout.contains(r"\\?\"));
+}
+
+// ============================================================================
+// Large Directory Tests
+// ============================================================================
+
+#[test]
+fn should_handle_directory_with_many_files() {
+    let dir = TempDir::new().unwrap();
+
+    for i in 0..500 {
+        File::create(dir.path().join(format!("file_{:04}.txt", i))).unwrap();
+    }
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb"]);
+    assert!(output.status.success());
+    let stdout = stdout_str(&output);
+
+    assert!(stdout.contains("file_0000.txt"));
+    assert!(stdout.contains("file_0250.txt"));
+    assert!(stdout.contains("file_0499.txt"));
+}
+
+// ============================================================================
+// Symbolic Link Tests
+// ============================================================================
+
+#[test]
+fn should_handle_symbolic_links() {
+    if let Some(dir) = create_symlink_test_dir() {
+        let output = run_treepp_in_dir(dir.path(), &["/f", "/nb"]);
+        assert!(output.status.success());
+        let stdout = stdout_str(&output);
 
         assert!(stdout.contains("real_file.txt"));
         assert!(stdout.contains("real_dir"));
@@ -1857,7 +2337,10 @@ fn should_fail_when_output_path_is_directory() {
     let output_dir = dir.path().join("output_dir");
     fs::create_dir(&output_dir).unwrap();
 
-    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/o", output_dir.to_str().unwrap()]);
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["/f", "/nb", "/o", output_dir.to_str().unwrap()],
+    );
     assert!(!output.status.success());
 }
 
@@ -2026,18 +2509,39 @@ fn should_handle_character_class_patterns_in_gitignore() {
     let stdout = stdout_str(&output);
 
     // [abc].txt should match a.txt, b.txt but not d.txt
-    assert!(!stdout.contains("a.txt"), "a.txt should be ignored by [abc].txt");
-    assert!(!stdout.contains("b.txt"), "b.txt should be ignored by [abc].txt");
+    assert!(
+        !stdout.contains("a.txt"),
+        "a.txt should be ignored by [abc].txt"
+    );
+    assert!(
+        !stdout.contains("b.txt"),
+        "b.txt should be ignored by [abc].txt"
+    );
     assert!(stdout.contains("d.txt"), "d.txt should NOT be ignored");
 
     // [!xyz].log should match anything except x.log, y.log, z.log
-    assert!(stdout.contains("x.log"), "x.log should NOT be ignored by [!xyz].log");
-    assert!(stdout.contains("y.log"), "y.log should NOT be ignored by [!xyz].log");
-    assert!(!stdout.contains("a.log"), "a.log should be ignored by [!xyz].log");
+    assert!(
+        stdout.contains("x.log"),
+        "x.log should NOT be ignored by [!xyz].log"
+    );
+    assert!(
+        stdout.contains("y.log"),
+        "y.log should NOT be ignored by [!xyz].log"
+    );
+    assert!(
+        !stdout.contains("a.log"),
+        "a.log should be ignored by [!xyz].log"
+    );
 
     // [0-9].dat should match single digit files
-    assert!(!stdout.contains("0.dat"), "0.dat should be ignored by [0-9].dat");
-    assert!(!stdout.contains("5.dat"), "5.dat should be ignored by [0-9].dat");
+    assert!(
+        !stdout.contains("0.dat"),
+        "0.dat should be ignored by [0-9].dat"
+    );
+    assert!(
+        !stdout.contains("5.dat"),
+        "5.dat should be ignored by [0-9].dat"
+    );
     assert!(stdout.contains("a.dat"), "a.dat should NOT be ignored");
 }
 
@@ -2089,7 +2593,10 @@ fn should_handle_escaped_special_characters_in_gitignore() {
     );
 
     // Regular files should be present
-    assert!(stdout.contains("regular.txt"), "regular.txt should be present");
+    assert!(
+        stdout.contains("regular.txt"),
+        "regular.txt should be present"
+    );
 }
 
 /// Creates a directory with root-relative patterns in .gitignore.
@@ -2207,7 +2714,10 @@ fn should_handle_utf8_bom_in_gitignore() {
         !stdout.contains("ignored.txt"),
         "ignored.txt should be ignored even with UTF-8 BOM in .gitignore"
     );
-    assert!(stdout.contains("visible.txt"), "visible.txt should be present");
+    assert!(
+        stdout.contains("visible.txt"),
+        "visible.txt should be present"
+    );
 }
 
 // ============================================================================
@@ -2255,7 +2765,10 @@ fn should_handle_multiple_dots_in_wildcard_pattern() {
     assert!(output.status.success());
     let stdout = stdout_str(&output);
 
-    assert!(stdout.contains("file.tar.gz"), "file.tar.gz should match *.tar.*");
+    assert!(
+        stdout.contains("file.tar.gz"),
+        "file.tar.gz should match *.tar.*"
+    );
     assert!(
         stdout.contains("archive.tar.bz2"),
         "archive.tar.bz2 should match *.tar.*"
@@ -2273,7 +2786,10 @@ fn should_handle_middle_asterisk_pattern() {
     assert!(output.status.success());
     let stdout = stdout_str(&output);
 
-    assert!(stdout.contains("test.txt"), "test.txt should match test*.txt");
+    assert!(
+        stdout.contains("test.txt"),
+        "test.txt should match test*.txt"
+    );
     assert!(
         stdout.contains("test123.txt"),
         "test123.txt should match test*.txt"
@@ -2315,7 +2831,10 @@ fn should_handle_question_mark_single_char_wildcard() {
     assert!(stdout.contains("a.txt"), "a.txt should match ?.txt");
     assert!(stdout.contains("1.txt"), "1.txt should match ?.txt");
     assert!(!stdout.contains("ab.txt"), "ab.txt should not match ?.txt");
-    assert!(!stdout.contains("abc.txt"), "abc.txt should not match ?.txt");
+    assert!(
+        !stdout.contains("abc.txt"),
+        "abc.txt should not match ?.txt"
+    );
 }
 
 #[test]
@@ -2509,8 +3028,7 @@ fn should_produce_identical_results_across_thread_counts() {
     let outputs: Vec<String> = [1, 2, 4, 8, 16]
         .iter()
         .map(|t| {
-            let output =
-                run_treepp_in_dir(dir.path(), &["/b", "/f", "/nb", "/t", &t.to_string()]);
+            let output = run_treepp_in_dir(dir.path(), &["/b", "/f", "/nb", "/t", &t.to_string()]);
             assert!(output.status.success());
             stdout_str(&output)
         })
@@ -2519,7 +3037,8 @@ fn should_produce_identical_results_across_thread_counts() {
     // All outputs should be identical
     for (i, output) in outputs.iter().enumerate().skip(1) {
         assert_eq!(
-            &outputs[0], output,
+            &outputs[0],
+            output,
             "Output with {} threads differs from 1 thread",
             [1, 2, 4, 8, 16][i]
         );
@@ -2550,7 +3069,10 @@ fn should_handle_empty_directory_in_json() {
         .and_then(|r| r.get("dirs"))
         .and_then(|d| d.get("empty"));
 
-    assert!(empty_dir.is_some(), "Empty directory should be in JSON output");
+    assert!(
+        empty_dir.is_some(),
+        "Empty directory should be in JSON output"
+    );
 }
 
 #[test]
@@ -2594,14 +3116,7 @@ fn should_handle_large_file_sizes_in_json() {
     let output_file = dir.path().join("tree.json");
     let output = run_treepp_in_dir(
         dir.path(),
-        &[
-            "/b",
-            "/f",
-            "/s",
-            "/o",
-            output_file.to_str().unwrap(),
-            "/nb",
-        ],
+        &["/b", "/f", "/s", "/o", output_file.to_str().unwrap(), "/nb"],
     );
     assert!(output.status.success());
 
@@ -2671,6 +3186,116 @@ fn should_combine_disk_usage_with_human_readable_correctly() {
     );
 }
 
+#[test]
+fn should_hide_directories_below_du_threshold() {
+    let dir = TempDir::new().unwrap();
+    for (name, size) in [("big", 4096), ("small1", 10), ("small2", 0)] {
+        fs::create_dir(dir.path().join(name)).unwrap();
+        fs::write(dir.path().join(name).join("data.bin"), vec![b'x'; size]).unwrap();
+    }
+
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/du", "/dut", "2K", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+
+    assert!(stdout.contains("big"));
+    assert!(!stdout.contains("small1"));
+    assert!(stdout.contains("[+ 2 small dirs]"), "{stdout}");
+}
+
+/// Creates two directories holding 3000 and 1000 bytes.
+fn create_share_test_dir() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for (name, size) in [("alpha", 1000), ("beta", 3000)] {
+        fs::create_dir(dir.path().join(name)).unwrap();
+        fs::write(dir.path().join(name).join("data.bin"), vec![b'x'; size]).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn should_show_share_of_parent_with_du_percent() {
+    let dir = create_share_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/du", "/dup", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+
+    assert!(stdout.contains("1000 (25%)"), "{stdout}");
+    assert!(stdout.contains("3000 (75%)"), "{stdout}");
+}
+
+#[test]
+fn should_sort_directories_by_share() {
+    let dir = create_share_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/du", "/so", "share", "/r", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+
+    let beta = stdout.find("beta").expect("beta missing");
+    let alpha = stdout.find("alpha").expect("alpha missing");
+    assert!(beta < alpha, "larger directory should come first: {stdout}");
+}
+
+#[test]
+fn should_include_percent_of_parent_in_json() {
+    let dir = create_share_test_dir();
+    let json_path = dir.path().join("tree.json");
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &[
+            "/b",
+            "/du",
+            "/dup",
+            "/si",
+            "/o",
+            json_path.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+    assert_eq!(json["root"]["dirs"]["beta"]["percent_of_parent"], 75.0);
+}
+
+#[test]
+fn should_show_growth_since_previous_du_delta_run() {
+    let dir = create_share_test_dir();
+    let local = TempDir::new().unwrap();
+    let run = || {
+        Command::new(get_treepp_path())
+            .current_dir(dir.path())
+            .env("LOCALAPPDATA", local.path())
+            .args(["/b", "/du", "/dud", "/nb"])
+            .output()
+            .expect("Failed to execute treepp")
+    };
+
+    let first = run();
+    assert!(first.status.success(), "stderr: {}", stderr_str(&first));
+    assert!(
+        !stdout_str(&first).contains("(+"),
+        "first run has nothing to compare"
+    );
+
+    fs::write(dir.path().join("beta").join("more.bin"), vec![b'x'; 500]).unwrap();
+    let second = run();
+    assert!(second.status.success(), "stderr: {}", stderr_str(&second));
+    let stdout = stdout_str(&second);
+    assert!(stdout.contains("3500 (+500)"), "{stdout}");
+    assert!(
+        stdout.contains("1000\n") || stdout.contains("1000\r\n"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn should_fail_du_threshold_without_disk_usage() {
+    let dir = create_basic_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/dut", "1M"]);
+    assert!(!output.status.success());
+}
+
 // ============================================================================
 // Date and Time Handling
 // ============================================================================
@@ -2967,8 +3592,14 @@ fn should_apply_gitignore_before_exclude_pattern() {
     assert!(output.status.success());
     let stdout = stdout_str(&output);
 
-    assert!(!stdout.contains("target"), "target should be ignored by .gitignore");
-    assert!(!stdout.contains("app.log"), "*.log should be ignored by .gitignore");
+    assert!(
+        !stdout.contains("target"),
+        "target should be ignored by .gitignore"
+    );
+    assert!(
+        !stdout.contains("app.log"),
+        "*.log should be ignored by .gitignore"
+    );
     assert!(
         !stdout.contains("file.txt"),
         "*.txt should be excluded by /X"
@@ -3052,5 +3683,1782 @@ fn should_create_output_file_even_when_tree_is_empty() {
         &["/f", "/o", output_file.to_str().unwrap(), "/nb"],
     );
     assert!(output.status.success());
-    assert!(output_file.exists(), "Output file should be created even for empty tree");
-}
\ No newline at end of file
+    assert!(
+        output_file.exists(),
+        "Output file should be created even for empty tree"
+    );
+}
+// ============================================================================
+// Path List Input Tests (/PF)
+// ============================================================================
+
+#[test]
+fn should_build_tree_from_path_list_file() {
+    let dir = create_basic_test_dir();
+    let list_dir = TempDir::new().unwrap();
+    let list_path = list_dir.path().join("files.txt");
+    fs::write(&list_path, "src/main.rs\nfile1.txt\n").unwrap();
+
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["/f", "/nb", "--paths-from", list_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("main.rs"));
+    assert!(stdout.contains("file1.txt"));
+    assert!(!stdout.contains("lib.rs"));
+    assert!(!stdout.contains("tests"));
+}
+
+#[test]
+fn should_build_tree_from_path_list_on_stdin() {
+    use std::process::Stdio;
+
+    let dir = create_basic_test_dir();
+    let mut child = Command::new(get_treepp_path())
+        .current_dir(dir.path())
+        .args(["/f", "/nb", "/pf", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute treepp");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"tests/test.rs\r\nsrc/lib.rs\r\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("test.rs"));
+    assert!(stdout.contains("lib.rs"));
+    assert!(!stdout.contains("main.rs"));
+}
+
+#[test]
+fn should_write_json_from_path_list_without_batch_flag() {
+    let dir = create_basic_test_dir();
+    let list_path = dir.path().join("list.txt");
+    fs::write(&list_path, "src/main.rs\n").unwrap();
+    let json_path = dir.path().join("tree.json");
+
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &[
+            "/f",
+            "/pf",
+            list_path.to_str().unwrap(),
+            "/o",
+            json_path.to_str().unwrap(),
+            "/si",
+        ],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let content = fs::read_to_string(&json_path).unwrap();
+    assert!(content.contains("main.rs"));
+}
+
+#[test]
+fn should_fail_when_path_list_entry_is_outside_root() {
+    let dir = create_basic_test_dir();
+    let list_path = dir.path().join("list.txt");
+    fs::write(&list_path, "..\\escape.txt\n").unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/pf", list_path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr_str(&output).contains("outside the root"));
+}
+
+// ============================================================================
+// Git Tracked Tests (/GT)
+// ============================================================================
+
+/// Writes a minimal version 2 git index listing `paths` into `root/.git/index`.
+fn write_git_index(root: &Path, paths: &[&str]) {
+    let mut bytes = b"DIRC".to_vec();
+    bytes.extend_from_slice(&2u32.to_be_bytes());
+    bytes.extend_from_slice(&(paths.len() as u32).to_be_bytes());
+    for path in paths {
+        let mut entry = vec![0u8; 60];
+        entry.extend_from_slice(&(path.len() as u16).to_be_bytes());
+        entry.extend_from_slice(path.as_bytes());
+        let padded = (entry.len() + 8) / 8 * 8;
+        entry.resize(padded, 0);
+        bytes.extend_from_slice(&entry);
+    }
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::write(root.join(".git").join("index"), bytes).unwrap();
+}
+
+#[test]
+fn should_show_only_git_tracked_files() {
+    let dir = create_basic_test_dir();
+    write_git_index(dir.path(), &["file1.txt", "src/main.rs"]);
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/gt"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("file1.txt"));
+    assert!(stdout.contains("main.rs"));
+    assert!(!stdout.contains("file2.md"));
+    assert!(!stdout.contains("lib.rs"));
+    assert!(!stdout.contains("tests"));
+    assert!(!stdout.contains("empty"));
+}
+
+#[test]
+fn should_fail_git_tracked_outside_repository() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/gt"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr_str(&output).contains("git index"));
+}
+
+// ============================================================================
+// Prune Tests (/P)
+// ============================================================================
+
+#[test]
+fn should_prune_directories_without_matching_files() {
+    let dir = create_basic_test_dir();
+    fs::create_dir(dir.path().join("src2")).unwrap();
+    File::create(dir.path().join("src2").join("notes.txt")).unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/m", "*.txt", "/p"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("src2"));
+    assert!(stdout.contains("notes.txt"));
+    assert!(!stdout.contains("src\r\n") && !stdout.contains("src\n"));
+    assert!(!stdout.contains("tests"));
+    assert!(!stdout.contains("empty"));
+}
+
+#[test]
+fn should_prune_identically_in_stream_and_batch_mode() {
+    let dir = create_basic_test_dir();
+
+    let stream = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/m", "*.rs", "/p"]);
+    let batch = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/m", "*.rs", "/p", "/b"]);
+    assert!(stream.status.success(), "stderr: {}", stderr_str(&stream));
+    assert!(batch.status.success(), "stderr: {}", stderr_str(&batch));
+    assert_eq!(stdout_str(&stream), stdout_str(&batch));
+    assert!(!stdout_str(&stream).contains("empty"));
+}
+
+// ============================================================================
+// Explain Tests (/EX)
+// ============================================================================
+
+#[test]
+fn should_explain_gitignored_path_with_source_line() {
+    let dir = create_basic_test_dir();
+    fs::write(dir.path().join(".gitignore"), "# build output\ntests/\n").unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/g", "/ex", "tests\\test.rs"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("Result:     hidden"));
+    assert!(stdout.contains("Decided at: tests"));
+    assert!(stdout.contains("'tests/'"));
+    assert!(stdout.contains(".gitignore:2"));
+    assert!(
+        !stdout.contains("main.rs"),
+        "explain should not render a tree"
+    );
+}
+
+#[test]
+fn should_explain_shown_path() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "--explain", "src\\main.rs"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("Result:     shown"));
+}
+
+#[test]
+fn should_explain_missing_files_flag() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/ex", "file1.txt"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("/F"));
+}
+
+#[test]
+fn should_fail_explain_for_missing_path() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/ex", "missing.txt"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+// ============================================================================
+// Print Config Tests (/PC)
+// ============================================================================
+
+#[test]
+fn should_print_resolved_config_without_scanning() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/pc", "/f", "/gb", "type", "/o", "tree.txt"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("[render]"), "stdout: {stdout}");
+    assert!(stdout.contains("group_by = \"type\""), "stdout: {stdout}");
+    assert!(
+        stdout.contains("batch_mode = true"),
+        "/GB implies /B: {stdout}"
+    );
+    assert!(
+        !dir.path().join("tree.txt").exists(),
+        "nothing may be written"
+    );
+}
+
+// ============================================================================
+// Print Schema Tests (/PS)
+// ============================================================================
+
+#[test]
+fn should_print_schema_matching_json_output() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/ps"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let schema: serde_json::Value = serde_json::from_str(&stdout_str(&output)).unwrap();
+    assert_eq!(schema["title"], "tree++ structured output");
+
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/f", "/si", "/o", "tree.json"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("tree.json")).unwrap()).unwrap();
+    for field in schema["required"].as_array().unwrap() {
+        assert!(
+            json.get(field.as_str().unwrap()).is_some(),
+            "missing {field}"
+        );
+    }
+}
+
+// ============================================================================
+// Context Menu Tests (/ICM, /UCM)
+// ============================================================================
+
+#[test]
+fn should_reject_unknown_context_menu_scope() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/icm", "everyone"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stderr_str(&output).contains("user or machine"),
+        "stderr: {}",
+        stderr_str(&output)
+    );
+}
+
+#[test]
+fn should_reject_install_with_uninstall_context_menu() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/icm", "user", "/ucm", "user"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr_str(&output).contains("--uninstall-context-menu"));
+}
+
+// ============================================================================
+// Show Filtered Tests (/SF)
+// ============================================================================
+
+#[test]
+fn should_annotate_excluded_directory() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/x", "tests", "/sf"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("tests  [filtered: matches exclude pattern 'tests' (/X)]"));
+    assert!(
+        !stdout.contains("test.rs"),
+        "filtered directory should not be expanded"
+    );
+    assert!(stdout.contains("main.rs"));
+}
+
+#[test]
+fn should_annotate_gitignored_entries_in_batch_mode() {
+    let dir = create_basic_test_dir();
+    fs::write(dir.path().join(".gitignore"), "*.md\n").unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/g", "/sf", "/b"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("file2.md  [filtered: ignored by .gitignore rule '*.md'"));
+    assert!(stdout.contains(".gitignore:1)"));
+}
+
+#[test]
+fn should_reject_show_filtered_with_json_output() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/sf", "/o", "tree.json"]);
+    assert!(!output.status.success());
+    assert!(!dir.path().join("tree.json").exists());
+}
+
+// ============================================================================
+// Pager Tests (/PG)
+// ============================================================================
+
+/// Executes treepp in `dir` with `PAGER` set to `pager`.
+fn run_treepp_with_pager(dir: &Path, pager: &str, args: &[&str]) -> Output {
+    Command::new(get_treepp_path())
+        .current_dir(dir)
+        .env("PAGER", pager)
+        .args(TEST_LANG)
+        .args(args)
+        .output()
+        .expect("Failed to execute treepp")
+}
+
+#[test]
+fn should_hand_output_to_pager_with_pager_always() {
+    let dir = create_basic_test_dir();
+
+    for mode in [&["/f", "/pg", "always"][..], &["/f", "/pg", "always", "/b"]] {
+        let output = run_treepp_with_pager(dir.path(), "echo paged", mode);
+        assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+        let stdout = stdout_str(&output);
+        assert_eq!(
+            stdout.trim(),
+            "paged",
+            "the pager replaces the output: {stdout}"
+        );
+    }
+}
+
+#[test]
+fn should_not_page_redirected_output_with_pager_auto() {
+    let dir = create_basic_test_dir();
+
+    for when in ["auto", "never"] {
+        let output = run_treepp_with_pager(dir.path(), "echo paged", &["/f", "/pg", when]);
+        assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+        let stdout = stdout_str(&output);
+        assert!(stdout.contains("file1.txt"), "{stdout}");
+        assert!(!stdout.contains("paged"), "{stdout}");
+    }
+}
+
+#[test]
+fn should_stop_quietly_when_pager_exits_early() {
+    let dir = create_wide_test_dir();
+
+    let output = run_treepp_with_pager(dir.path(), "exit", &["/f", "/pg", "always"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stderr_str(&output).is_empty(), "{}", stderr_str(&output));
+}
+
+#[test]
+fn should_reject_unknown_pager_mode() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/pg", "sometimes"]);
+    assert!(!output.status.success());
+    assert!(stderr_str(&output).contains("pager"));
+}
+
+// ============================================================================
+// Broken Pipe Tests
+// ============================================================================
+
+/// Executes treepp in `dir` and closes its stdout without reading it, as
+/// `treepp /F | Select-Object -First 20` does once it has enough lines.
+fn run_treepp_with_closed_stdout(dir: &Path, args: &[&str]) -> Output {
+    use std::process::Stdio;
+
+    let mut child = Command::new(get_treepp_path())
+        .current_dir(dir)
+        .args(TEST_LANG)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute treepp");
+    drop(child.stdout.take());
+    child.wait_with_output().expect("Failed to wait for treepp")
+}
+
+#[test]
+fn should_exit_quietly_when_stdout_is_closed() {
+    let dir = create_wide_test_dir();
+
+    for args in [&["/f"][..], &["/f", "/b"], &["/f", "/cnt"], &["/?"]] {
+        let output = run_treepp_with_closed_stdout(dir.path(), args);
+        assert!(output.status.success(), "{args:?}: {}", stderr_str(&output));
+        assert!(
+            stderr_str(&output).is_empty(),
+            "{args:?}: {}",
+            stderr_str(&output)
+        );
+    }
+}
+
+#[test]
+fn should_finish_output_file_when_stdout_is_closed() {
+    let dir = create_wide_test_dir();
+    let out_path = dir.path().join("tree.txt");
+
+    let output = run_treepp_with_closed_stdout(dir.path(), &["/f", "/nb", "/o", "tree.txt"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let content = fs::read_to_string(&out_path).unwrap();
+    assert!(content.contains("file_0000.txt"));
+    assert!(
+        content.contains("file_1999.txt"),
+        "the file keeps every entry"
+    );
+}
+
+// ============================================================================
+// Truncate Tests (/TR)
+// ============================================================================
+
+#[test]
+fn should_keep_whole_lines_when_output_is_redirected() {
+    let dir = create_basic_test_dir();
+    fs::write(dir.path().join(format!("{}.txt", "x".repeat(200))), "").unwrap();
+
+    let plain = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/s"]);
+    let truncated = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/s", "/tr"]);
+    assert!(
+        truncated.status.success(),
+        "stderr: {}",
+        stderr_str(&truncated)
+    );
+    assert_eq!(
+        stdout_str(&plain),
+        stdout_str(&truncated),
+        "redirected output has no console width"
+    );
+}
+
+// ============================================================================
+// Highlight Tests (/HL)
+// ============================================================================
+
+#[test]
+fn should_highlight_include_matches() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/m", "*.rs", "/hl"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("main\x1b[4;33m.rs\x1b[0m"));
+    assert!(
+        !stdout.contains("src\x1b"),
+        "directories are not highlighted"
+    );
+
+    let batch = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/m", "*.rs", "/hl", "/b"]);
+    assert_eq!(stdout_str(&output), stdout_str(&batch));
+}
+
+#[test]
+fn should_reject_highlight_without_include() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/hl"]);
+    assert!(!output.status.success());
+}
+
+// ============================================================================
+// Count Tests (/CNT)
+// ============================================================================
+
+#[test]
+fn should_print_only_totals_with_count() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/cnt", "/x", "empty"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("Directories:   2"), "stdout: {}", stdout);
+    assert!(
+        stdout.contains("Files:         5"),
+        "files are counted without /F"
+    );
+    assert!(stdout.contains("Max depth:     2"));
+    assert!(stdout.contains("  excluded (/X)  1"));
+    assert!(!stdout.contains("main.rs"), "the tree is not printed");
+}
+
+#[test]
+fn should_reject_count_with_batch() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/cnt", "/b"]);
+    assert!(!output.status.success());
+}
+
+// ============================================================================
+// Output Guard Tests (/ME, /MS)
+// ============================================================================
+
+#[test]
+fn should_truncate_directories_with_max_entries() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/me", "2"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("file1.txt"));
+    assert!(stdout.contains("file2.md"));
+    assert!(stdout.contains("[+2 more]"));
+    assert!(!stdout.contains("main.rs"));
+    assert!(stdout.contains("Truncated: 1 directory has more entries than --max-entries"));
+}
+
+#[test]
+fn should_truncate_with_max_entries_in_batch_mode() {
+    let dir = create_basic_test_dir();
+
+    let stream = run_treepp_in_dir(dir.path(), &["/f", "/me", "3", "/nb"]);
+    let batch = run_treepp_in_dir(dir.path(), &["/f", "/me", "3", "/nb", "/b"]);
+    assert!(batch.status.success(), "stderr: {}", stderr_str(&batch));
+    assert_eq!(stdout_str(&stream), stdout_str(&batch));
+}
+
+#[test]
+fn should_stop_output_at_max_output_size() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/ms", "40"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    let (tree, _) = stdout
+        .split_once("Truncated:")
+        .expect("should report truncation");
+    assert!(tree.len() <= 40);
+    assert!(stdout.contains("--max-output-size"));
+}
+
+#[test]
+fn should_reject_invalid_max_output_size() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/ms", "lots"]);
+    assert!(!output.status.success());
+}
+
+// ============================================================================
+// File Limit Tests (/FL)
+// ============================================================================
+
+#[test]
+fn should_collapse_directories_over_filelimit() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/fl", "1"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("src [2 entries]"));
+    assert!(!stdout.contains("main.rs"));
+    assert!(
+        stdout.contains("test.rs"),
+        "directories within the limit are expanded"
+    );
+}
+
+#[test]
+fn should_collapse_directories_over_filelimit_in_batch_mode() {
+    let dir = create_basic_test_dir();
+
+    let stream = run_treepp_in_dir(dir.path(), &["/f", "/fl", "1", "/nb"]);
+    let batch = run_treepp_in_dir(dir.path(), &["/f", "/fl", "1", "/nb", "/b"]);
+    assert!(batch.status.success(), "stderr: {}", stderr_str(&batch));
+    assert_eq!(stdout_str(&stream), stdout_str(&batch));
+}
+
+// ============================================================================
+// Leaf Depth Tests (/LD)
+// ============================================================================
+
+#[test]
+fn should_show_only_last_levels_with_leaf_depth() {
+    let dir = create_basic_test_dir();
+    let deep = dir.path().join("target").join("debug").join("build");
+    fs::create_dir_all(&deep).unwrap();
+    fs::write(deep.join("output"), "").unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/ld", "2"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains(".../build"), "upper levels are collapsed");
+    assert!(stdout.contains("output"));
+    assert!(!stdout.contains("debug"));
+    assert!(stdout.contains("main.rs"), "shallow branches are unchanged");
+}
+
+#[test]
+fn should_reject_leaf_depth_with_structured_output() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/ld", "2", "/o", "tree.json"]);
+    assert!(!output.status.success());
+}
+
+// ============================================================================
+// Scan Cache Tests (/CA, /NC)
+// ============================================================================
+
+#[test]
+fn should_list_same_tree_with_cache() {
+    let dir = create_basic_test_dir();
+
+    let plain = run_treepp_in_dir(dir.path(), &["/f", "/nb"]);
+    let cold = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/ca"]);
+    let warm = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/ca"]);
+    assert!(warm.status.success(), "stderr: {}", stderr_str(&warm));
+    assert_eq!(stdout_str(&plain), stdout_str(&cold));
+    assert_eq!(stdout_str(&plain), stdout_str(&warm));
+
+    let output = run_treepp_in_dir(dir.path(), &["/nc"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+}
+
+#[test]
+fn should_pick_up_new_files_with_cache() {
+    let dir = create_basic_test_dir();
+
+    run_treepp_in_dir(dir.path(), &["/f", "/nb", "/ca"]);
+    fs::write(dir.path().join("src").join("added.rs"), "").unwrap();
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/ca"]);
+    assert!(stdout_str(&output).contains("added.rs"));
+
+    run_treepp_in_dir(dir.path(), &["/nc"]);
+}
+
+#[test]
+fn should_reject_cache_with_size() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/ca", "/s"]);
+    assert!(!output.status.success());
+}
+
+// ============================================================================
+// Stats JSON Tests (/SJ)
+// ============================================================================
+
+#[test]
+fn should_write_stats_json_to_stderr() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/sj", "-"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stderr = stderr_str(&output);
+    assert!(stderr.contains("\"schema\": \"treepp.stats.v1\""));
+    assert!(stderr.contains("\"backend\": \"streaming\""));
+    assert!(stderr.contains("\".rs\""));
+}
+
+#[test]
+fn should_write_stats_json_to_file_in_batch_mode() {
+    let dir = create_basic_test_dir();
+    let stats_path = dir.path().join("stats.json");
+
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["/f", "/b", "/sj", stats_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stats = fs::read_to_string(&stats_path).expect("stats file should exist");
+    assert!(stats.contains("\"backend\": \"batch\""));
+    assert!(stats.contains("\"max_depth\""));
+    assert!(!stdout_str(&output).contains("treepp.stats.v1"));
+}
+
+// ============================================================================
+// Extension Report Tests (/RE)
+// ============================================================================
+
+#[test]
+fn should_append_extension_report() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/re"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("Extensions:"));
+    assert!(stdout.contains(".rs"));
+}
+
+#[test]
+fn should_report_same_extensions_in_batch_mode() {
+    let dir = create_basic_test_dir();
+
+    let stream = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/re"]);
+    let batch = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/re", "/b"]);
+    assert!(batch.status.success(), "stderr: {}", stderr_str(&batch));
+    assert_eq!(stdout_str(&stream), stdout_str(&batch));
+}
+
+#[test]
+fn should_include_extensions_in_json_output() {
+    let dir = create_basic_test_dir();
+    let json_path = dir.path().join("tree.json");
+
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["/f", "/b", "/re", "/si", "/o", json_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let json = fs::read_to_string(&json_path).expect("json file should exist");
+    assert!(json.contains("\"extensions\""));
+}
+
+// ============================================================================
+// One File System Tests (/OFS)
+// ============================================================================
+
+#[test]
+fn should_descend_into_directories_on_same_volume() {
+    let dir = create_deep_test_dir();
+
+    let plain = run_treepp_in_dir(dir.path(), &["/f", "/nb"]);
+    for flag in ["--one-file-system", "-x", "/OFS"] {
+        let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", flag]);
+        assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+        assert_eq!(stdout_str(&output), stdout_str(&plain), "flag {flag}");
+    }
+}
+
+// ============================================================================
+// Cloud Placeholder Tests (/MC, /SCO)
+// ============================================================================
+
+#[test]
+fn should_keep_local_files_with_cloud_options() {
+    let dir = create_deep_test_dir();
+
+    let plain = run_treepp_in_dir(dir.path(), &["/f", "/nb"]);
+    for flags in [
+        &["/MC"][..],
+        &["/SCO"],
+        &["--mark-cloud", "--skip-cloud-only"],
+    ] {
+        let mut args = vec!["/f", "/nb"];
+        args.extend_from_slice(flags);
+        let output = run_treepp_in_dir(dir.path(), &args);
+        assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+        assert_eq!(stdout_str(&output), stdout_str(&plain), "flags {flags:?}");
+    }
+}
+
+#[test]
+fn should_reject_mark_cloud_with_json_output() {
+    let dir = create_deep_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/mc", "/o", "tree.json"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr_str(&output).contains("--mark-cloud"),
+        "stderr: {}",
+        stderr_str(&output)
+    );
+}
+
+// ============================================================================
+// Link Target Tests
+// ============================================================================
+
+/// Creates a directory holding `target\file.txt` and a junction `link` to `target`.
+fn create_junction_test_dir() -> TempDir {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let target = dir.path().join("target");
+    fs::create_dir(&target).unwrap();
+    File::create(target.join("file.txt")).unwrap();
+
+    let status = Command::new("cmd")
+        .args(["/C", "mklink", "/J"])
+        .arg(dir.path().join("link"))
+        .arg(&target)
+        .output()
+        .expect("Failed to run mklink");
+    assert!(status.status.success(), "mklink /J failed");
+    dir
+}
+
+#[test]
+fn should_show_junction_target() {
+    let dir = create_junction_test_dir();
+    let target = dir.path().join("target");
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let expected = format!("link -> {}", target.display());
+    assert!(
+        stdout_str(&output).contains(&expected),
+        "stdout: {}",
+        stdout_str(&output)
+    );
+}
+
+#[test]
+fn should_include_link_target_in_json_output() {
+    let dir = create_junction_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/b", "/o", "tree.json"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let json = fs::read_to_string(dir.path().join("tree.json")).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let link = value["root"]["files"]
+        .as_array()
+        .and_then(|files| files.iter().find(|f| f["name"] == "link"))
+        .expect("link entry missing");
+    let target = dir.path().join("target");
+    assert_eq!(link["link_target"], target.to_string_lossy().as_ref());
+}
+
+// ============================================================================
+// Depth Histogram Tests (/DH)
+// ============================================================================
+
+#[test]
+fn should_append_depth_histogram() {
+    let dir = create_deep_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/dh"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    let histogram = &stdout[stdout.find("Depth histogram:").expect("histogram missing")..];
+    let rows: Vec<_> = histogram.lines().skip(2).collect();
+    assert_eq!(rows.len(), 5, "one row per level: {histogram}");
+    assert_eq!(rows[4], "      5     0      1  12 B  ####################");
+}
+
+#[test]
+fn should_report_same_depth_histogram_in_batch_mode() {
+    let dir = create_deep_test_dir();
+
+    let stream = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/dh"]);
+    let batch = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/dh", "/b"]);
+    assert!(batch.status.success(), "stderr: {}", stderr_str(&batch));
+    assert_eq!(stdout_str(&stream), stdout_str(&batch));
+}
+
+#[test]
+fn should_include_levels_in_json_output() {
+    let dir = create_deep_test_dir();
+    let json_path = dir.path().join("tree.json");
+
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["/f", "/b", "/dh", "/si", "/o", json_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let json = fs::read_to_string(&json_path).expect("json file should exist");
+    assert!(json.contains("\"levels\""));
+}
+
+// ============================================================================
+// Name Escaping Tests (/Q, /ES)
+// ============================================================================
+
+#[test]
+fn should_quote_names() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/q"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("\"file1.txt\""));
+}
+
+#[test]
+fn should_escape_trailing_spaces_in_c_mode() {
+    let dir = TempDir::new().unwrap();
+    let canonical = fs::canonicalize(dir.path()).unwrap();
+    // The verbatim prefix keeps Windows from stripping the trailing space.
+    fs::write(canonical.join("trail "), "x").unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/es", "c"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains(r"trail\x20"));
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/es", "hex"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn should_keep_control_characters_when_redirected() {
+    let dir = TempDir::new().unwrap();
+    // Windows rejects C0 controls in names, but C1 controls such as CSI are allowed.
+    fs::write(dir.path().join("a\u{9b}31mb.txt"), "x").unwrap();
+
+    for args in [&["/f", "/nb"][..], &["/f", "/nb", "/rn"][..]] {
+        let output = run_treepp_in_dir(dir.path(), args);
+        assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+        assert!(
+            stdout_str(&output).contains("a\u{9b}31mb.txt"),
+            "piped output keeps names"
+        );
+    }
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/es", "question"]);
+    assert!(stdout_str(&output).contains("a?31mb.txt"));
+}
+
+#[test]
+fn should_classify_entries() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("bin")).unwrap();
+    fs::write(dir.path().join("bin").join("setup.exe"), "").unwrap();
+    fs::write(dir.path().join("deploy.ps1"), "").unwrap();
+    fs::write(dir.path().join("readme.md"), "").unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/cl"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    for expected in ["└─bin/", "setup.exe*", "deploy.ps1*"] {
+        assert!(stdout.contains(expected), "missing {expected}: {stdout}");
+    }
+    assert!(
+        stdout.lines().any(|line| line.ends_with("readme.md")),
+        "stdout: {stdout}"
+    );
+
+    let batch = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/cl", "/b"]);
+    assert_eq!(stdout_str(&batch), stdout);
+}
+
+#[test]
+fn should_group_files_by_type() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("projects")).unwrap();
+    fs::write(dir.path().join("photo.jpg"), "").unwrap();
+    fs::write(dir.path().join("script.py"), "").unwrap();
+    fs::write(dir.path().join("setup.zip"), "").unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/gb", "type"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    let order = [
+        "[Code]",
+        "script.py",
+        "[Images]",
+        "photo.jpg",
+        "[Archives]",
+        "[Directories]",
+    ];
+    let positions: Vec<_> = order
+        .iter()
+        .map(|text| {
+            stdout
+                .find(text)
+                .unwrap_or_else(|| panic!("missing {text}: {stdout}"))
+        })
+        .collect();
+    assert!(
+        positions.windows(2).all(|w| w[0] < w[1]),
+        "wrong order: {stdout}"
+    );
+    assert!(
+        !stdout.contains("[Other]"),
+        "empty sections must be omitted: {stdout}"
+    );
+
+    let json = run_treepp_in_dir(dir.path(), &["/gb", "type", "/o", "tree.json"]);
+    assert!(!json.status.success(), "sections are text-only");
+}
+
+// ============================================================================
+// Output Encoding Tests (/OE, /BOM)
+// ============================================================================
+
+#[test]
+fn should_write_utf16le_output_file_with_bom() {
+    let dir = create_basic_test_dir();
+    let out_path = dir.path().join("tree.txt");
+
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &[
+            "/f",
+            "/nb",
+            "/oe",
+            "utf16le",
+            "/bom",
+            "/si",
+            "/o",
+            out_path.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let bytes = fs::read(&out_path).expect("output file should exist");
+    assert_eq!(&bytes[..2], &[0xFF, 0xFE]);
+    let units: Vec<u16> = bytes[2..]
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    assert!(String::from_utf16(&units).unwrap().contains("file1.txt"));
+}
+
+#[test]
+fn should_encode_redirected_stdout_as_gbk() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/oe", "gbk"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(std::str::from_utf8(&output.stdout).is_err());
+    let (decoded, _, had_errors) = encoding_rs::GBK.decode(&output.stdout);
+    assert!(!had_errors);
+    assert!(decoded.contains("└─"));
+}
+
+#[test]
+fn should_reject_bom_with_gbk() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/oe", "gbk", "/bom"]);
+    assert!(!output.status.success());
+}
+
+// ============================================================================
+// Append and No-Clobber Tests (/AP, /NCL)
+// ============================================================================
+
+#[test]
+fn should_append_to_output_file() {
+    let dir = create_basic_test_dir();
+    let out_path = dir.path().join("tree.txt");
+    let out = out_path.to_str().unwrap();
+
+    let first = run_treepp_in_dir(dir.path(), &["/nb", "/si", "/o", out]);
+    assert!(first.status.success(), "stderr: {}", stderr_str(&first));
+    let single = fs::read_to_string(&out_path).unwrap();
+
+    let second = run_treepp_in_dir(dir.path(), &["/nb", "/si", "/ap", "/o", out]);
+    assert!(second.status.success(), "stderr: {}", stderr_str(&second));
+    let appended = fs::read_to_string(&out_path).unwrap();
+    assert_eq!(appended, format!("{single}{single}"));
+}
+
+#[test]
+fn should_refuse_existing_output_with_no_clobber() {
+    let dir = create_basic_test_dir();
+    let out_path = dir.path().join("tree.txt");
+    fs::write(&out_path, "keep me").unwrap();
+
+    for extra in [&[][..], &["/b"][..]] {
+        let mut args = vec!["/ncl", "/o", out_path.to_str().unwrap()];
+        args.extend_from_slice(extra);
+        let output = run_treepp_in_dir(dir.path(), &args);
+        assert!(!output.status.success());
+        assert!(stderr_str(&output).contains("already exists"));
+        assert_eq!(fs::read_to_string(&out_path).unwrap(), "keep me");
+    }
+}
+
+#[test]
+fn should_not_leave_temp_file_next_to_output() {
+    let dir = create_basic_test_dir();
+    let out_path = dir.path().join("out").join("tree.txt");
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/si", "/o", out_path.to_str().unwrap()]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(out_path.exists());
+    assert_eq!(fs::read_dir(out_path.parent().unwrap()).unwrap().count(), 1);
+}
+
+#[test]
+fn should_write_timestamped_reports_and_rotate() {
+    let dir = create_basic_test_dir();
+    let reports = dir.path().join("reports");
+    fs::create_dir(&reports).unwrap();
+    for old in [
+        "tree-20200101-020000.txt",
+        "tree-20200102-020000.txt",
+        "notes.txt",
+    ] {
+        fs::write(reports.join(old), "").unwrap();
+    }
+
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["src", "/f", "/si", "/rpd", "reports", "/rot", "2"],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+
+    let mut names: Vec<String> = fs::read_dir(&reports)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names.len(), 3, "应保留两份报告与无关文件: {names:?}");
+    assert_eq!(names[0], "notes.txt");
+    assert_eq!(names[1], "tree-20200102-020000.txt");
+    assert!(names[2].starts_with("tree-") && names[2].ends_with(".txt"));
+    assert!(
+        fs::read_to_string(reports.join(&names[2]))
+            .unwrap()
+            .contains("main.rs")
+    );
+}
+
+// ============================================================================
+// Compressed Output Tests (/GZ)
+// ============================================================================
+
+/// Reads all gzip members of a file as UTF-8 text.
+fn read_gzip(path: &Path) -> String {
+    use std::io::Read;
+
+    let mut content = String::new();
+    flate2::read::MultiGzDecoder::new(File::open(path).unwrap())
+        .read_to_string(&mut content)
+        .unwrap();
+    content
+}
+
+#[test]
+fn should_gzip_output_file_with_gz_extension() {
+    let dir = create_basic_test_dir();
+    let out_path = dir.path().join("tree.txt.gz");
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/o", out_path.to_str().unwrap()]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(
+        stdout_str(&output).contains("file1.txt"),
+        "console output stays text"
+    );
+    assert_eq!(&fs::read(&out_path).unwrap()[..2], &[0x1f, 0x8b]);
+    assert!(read_gzip(&out_path).contains("file1.txt"));
+}
+
+#[test]
+fn should_gzip_structured_output_with_compress() {
+    let dir = create_basic_test_dir();
+    let out_path = dir.path().join("tree.json");
+
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["/f", "/b", "/gz", "/si", "/o", out_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let json: serde_json::Value = serde_json::from_str(&read_gzip(&out_path)).unwrap();
+    assert!(json.is_object());
+
+    let output = run_treepp_in_dir(dir.path(), &["/gz"]);
+    assert!(
+        !output.status.success(),
+        "--compress requires an output file"
+    );
+}
+
+// ============================================================================
+// Checkpoint Tests (/CK)
+// ============================================================================
+
+#[test]
+fn should_remove_checkpoint_after_complete_run() {
+    let dir = create_basic_test_dir();
+    let out_dir = TempDir::new().unwrap();
+    let plain_path = out_dir.path().join("plain.txt");
+    let out_path = out_dir.path().join("tree.txt");
+    let checkpoint_path = out_dir.path().join("tree.txt.checkpoint");
+
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["/f", "/nb", "/o", plain_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["/f", "/nb", "/ck", "/o", out_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(!checkpoint_path.exists(), "完成的运行应删除检查点");
+    let expected = fs::read_to_string(&plain_path).unwrap();
+    assert_eq!(fs::read_to_string(&out_path).unwrap(), expected);
+
+    // A checkpoint that cannot be read is ignored and the run starts over.
+    fs::write(&checkpoint_path, "{}").unwrap();
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["/f", "/nb", "/ck", "/o", out_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(
+        !stderr_str(&output).is_empty(),
+        "ignored checkpoint should be reported"
+    );
+    assert!(!checkpoint_path.exists());
+    assert_eq!(fs::read_to_string(&out_path).unwrap(), expected);
+
+    let output = run_treepp_in_dir(dir.path(), &["/ck"]);
+    assert!(
+        !output.status.success(),
+        "--checkpoint requires an output file"
+    );
+}
+
+// ============================================================================
+// Scan Budget Tests (/BT, /BE)
+// ============================================================================
+
+#[test]
+fn should_exit_with_budget_code_when_entry_budget_runs_out() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/be", "1"]);
+    assert_eq!(
+        output.status.code(),
+        Some(5),
+        "stderr: {}",
+        stderr_str(&output)
+    );
+    let stdout = stdout_str(&output);
+    assert!(
+        stdout.contains("src [budget exhausted]"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        !stdout.contains("main.rs"),
+        "unread directories should have no content"
+    );
+    assert!(
+        stdout.contains("Truncated: 3 directories were not read"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stderr_str(&output).contains("scan budget ran out"));
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/bt", "1m"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(!stdout_str(&output).contains("Truncated:"));
+}
+
+// ============================================================================
+// Metadata Alignment Tests (/NA)
+// ============================================================================
+
+#[test]
+fn should_align_sizes_in_a_column() {
+    let dir = create_basic_test_dir();
+
+    for extra in [&[][..], &["/b"][..]] {
+        let mut args = vec!["/f", "/s", "/nb"];
+        args.extend_from_slice(extra);
+        let output = run_treepp_in_dir(dir.path(), &args);
+        assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+        let stdout = stdout_str(&output);
+        let columns: Vec<usize> = stdout
+            .lines()
+            .filter(|line| line.ends_with(|c: char| c.is_ascii_digit()))
+            .map(|line| {
+                let meta = line.rsplit(' ').next().unwrap();
+                // Box-drawing characters are laid out as two columns.
+                line[..line.len() - meta.len()]
+                    .chars()
+                    .map(|c| {
+                        if ('\u{2500}'..='\u{257F}').contains(&c) {
+                            2
+                        } else {
+                            1
+                        }
+                    })
+                    .sum::<usize>()
+            })
+            .collect();
+        assert!(columns.len() > 1);
+        assert!(columns.windows(2).all(|w| w[0] == w[1]), "{stdout}");
+    }
+}
+
+#[test]
+fn should_keep_legacy_gap_with_no_align() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/s", "/nb", "/na"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.lines().any(|line| line.contains(".txt        ")));
+}
+
+// ============================================================================
+// Number Format Tests (/SU, /NF)
+// ============================================================================
+
+#[test]
+fn should_group_size_digits_with_number_format() {
+    let dir = create_basic_test_dir();
+    fs::write(dir.path().join("big.bin"), vec![0u8; 12345]).unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/s", "/nb", "/nf", "comma"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("12,345"));
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/s", "/nb"]);
+    assert!(stdout_str(&output).contains("12345"));
+}
+
+#[test]
+fn should_use_decimal_units_with_si() {
+    let dir = create_basic_test_dir();
+    fs::write(dir.path().join("big.bin"), vec![0u8; 12345]).unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/su", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("12.3 kB"));
+}
+
+// ============================================================================
+// Date Format Tests (/RD, /DF)
+// ============================================================================
+
+#[test]
+fn should_show_relative_dates() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/rd", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("just now"));
+}
+
+#[test]
+fn should_format_dates_with_pattern() {
+    let dir = create_basic_test_dir();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/df", "year %Y", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains(&format!("year {year}")));
+}
+
+#[test]
+fn should_reject_invalid_date_format() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/df", "%Q"]);
+    assert!(!output.status.success());
+}
+
+// ============================================================================
+// Timestamp and Sort Tests (/CT, /AT, /SO)
+// ============================================================================
+
+#[test]
+fn should_sort_files_by_size() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("a.txt"), "333").unwrap();
+    fs::write(dir.path().join("b.txt"), "1").unwrap();
+    fs::write(dir.path().join("c.txt"), "22").unwrap();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/so", "size"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    let b = stdout.find("b.txt").unwrap();
+    let c = stdout.find("c.txt").unwrap();
+    let a = stdout.find("a.txt").unwrap();
+    assert!(b < c && c < a, "{stdout}");
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/nb", "/so", "size", "/r"]);
+    let stdout = stdout_str(&output);
+    assert!(stdout.find("a.txt").unwrap() < stdout.find("b.txt").unwrap());
+}
+
+#[test]
+fn should_show_creation_and_access_dates() {
+    let dir = create_basic_test_dir();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    let output = run_treepp_in_dir(dir.path(), &["/f", "/ct", "/at", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    let line = stdout.lines().find(|l| l.contains("file1.txt")).unwrap();
+    assert_eq!(line.matches(&format!("{year}-")).count(), 2, "{line}");
+}
+
+// ============================================================================
+// Language Tests (/LG)
+// ============================================================================
+
+/// Executes treepp in `dir` with an explicit language instead of `TEST_LANG`.
+fn run_treepp_with_lang(dir: &Path, lang: &str, args: &[&str]) -> Output {
+    Command::new(get_treepp_path())
+        .current_dir(dir)
+        .args(["/lg", lang])
+        .args(args)
+        .output()
+        .expect("Failed to execute treepp")
+}
+
+#[test]
+fn should_localize_report_with_lang() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_with_lang(dir.path(), "zh", &["/f", "/rp", "/nb"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("个目录，"), "{stdout}");
+    assert!(stdout.contains("个文件，用时"), "{stdout}");
+
+    let output = run_treepp_with_lang(dir.path(), "en", &["/f", "/rp", "/nb"]);
+    assert!(stdout_str(&output).contains(" directory, "));
+}
+
+#[test]
+fn should_localize_errors_and_help_with_lang() {
+    let dir = TempDir::new().unwrap();
+
+    let output = run_treepp_with_lang(dir.path(), "zh", &["/zz"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = stderr_str(&output);
+    assert!(stderr.contains("参数错误: 未知选项：/zz"), "{stderr}");
+    assert!(stderr.contains("提示"), "{stderr}");
+
+    let output = run_treepp_with_lang(dir.path(), "zh", &["/?"]);
+    assert!(stdout_str(&output).contains("用法："));
+}
+
+#[test]
+fn should_reject_unknown_lang() {
+    let dir = TempDir::new().unwrap();
+    let output = run_treepp_with_lang(dir.path(), "fr", &[]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+// ============================================================================
+// JSON Error Tests (/JE)
+// ============================================================================
+
+#[test]
+fn should_report_errors_as_json() {
+    let dir = TempDir::new().unwrap();
+
+    let output = run_treepp_with_lang(dir.path(), "en", &["/je", "missing"]);
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value =
+        serde_json::from_str(stderr_str(&output).trim()).expect("stderr should be one JSON object");
+    assert_eq!(report["code"], "E153");
+    assert_eq!(report["category"], "config");
+    assert_eq!(report["exit_code"], 1);
+    assert_eq!(report["path"], "missing");
+    assert!(report["message"].as_str().unwrap().contains("missing"));
+
+    let output = run_treepp_with_lang(dir.path(), "zh", &["/zz", "--json-errors"]);
+    let report: serde_json::Value = serde_json::from_str(stderr_str(&output).trim()).unwrap();
+    assert_eq!(report["code"], "E101", "parse errors should be JSON too");
+    assert_eq!(report["path"], serde_json::Value::Null);
+    assert!(report["hint"].as_str().unwrap().contains("提示"));
+}
+
+// ============================================================================
+// Short Option Cluster Tests
+// ============================================================================
+
+#[test]
+fn should_treat_short_cluster_like_separate_flags() {
+    let dir = create_basic_test_dir();
+
+    let clustered = run_treepp_in_dir(dir.path(), &["-fa", "/nb"]);
+    let separate = run_treepp_in_dir(dir.path(), &["-f", "-a", "/nb"]);
+    assert!(
+        clustered.status.success(),
+        "stderr: {}",
+        stderr_str(&clustered)
+    );
+    assert_eq!(stdout_str(&clustered), stdout_str(&separate));
+}
+
+#[test]
+fn should_reject_value_option_inside_short_cluster() {
+    let dir = create_basic_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["-Lf", "1"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr_str(&output).contains("-Lf"));
+}
+
+#[test]
+fn should_warn_when_thread_count_is_clamped() {
+    let dir = create_basic_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/t", "10000", "/nb"]);
+    assert!(output.status.success());
+    assert!(
+        stderr_str(&output).contains("Thread count 10000 is too large; using 256 threads"),
+        "应提示线程数已被限制"
+    );
+}
+
+#[test]
+fn should_keep_warnings_out_of_output_and_silence_them() {
+    let dir = create_basic_test_dir();
+
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/t", "10000", "/o", "tree.json", "/si"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stderr_str(&output).starts_with("tree++: Warning:"));
+    let content = fs::read_to_string(dir.path().join("tree.json")).unwrap();
+    assert!(!content.contains("Warning"), "警告不应写入输出文件");
+    serde_json::from_str::<serde_json::Value>(&content).expect("输出应为有效的 JSON");
+
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/t", "10000", "/qw", "/nb"]);
+    assert!(output.status.success());
+    assert!(stderr_str(&output).is_empty(), "/QW 应不输出警告");
+}
+
+#[test]
+fn should_accept_automatic_thread_count() {
+    let dir = create_basic_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/b", "/t", "auto", "/f", "/nb"]);
+    assert!(output.status.success());
+    assert!(stdout_str(&output).contains("file1.txt"));
+    assert!(stderr_str(&output).is_empty());
+}
+
+/// Denies listing a directory to everyone; returns `false` if `icacls` failed.
+fn deny_listing(dir: &Path) -> bool {
+    Command::new("icacls")
+        .arg(dir)
+        .args(["/deny", "*S-1-1-0:(RD)"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Removes the deny entry added by `deny_listing`.
+fn allow_listing(dir: &Path) {
+    let _ = Command::new("icacls")
+        .arg(dir)
+        .args(["/remove:d", "*S-1-1-0"])
+        .output();
+}
+
+#[test]
+fn should_exit_with_partial_code_for_unreadable_directory() {
+    let dir = create_basic_test_dir();
+    let locked = dir.path().join("locked");
+    fs::create_dir(&locked).unwrap();
+    File::create(locked.join("secret.txt")).unwrap();
+    if !deny_listing(&locked) {
+        return;
+    }
+
+    let plain = run_treepp_in_dir(dir.path(), &["/f", "/nb"]);
+    let listed = run_treepp_in_dir(dir.path(), &["/b", "/f", "/nb", "/er"]);
+    allow_listing(&locked);
+
+    assert_eq!(plain.status.code(), Some(4), "部分目录无法读取时应返回 4");
+    assert!(stdout_str(&plain).contains("file1.txt"), "其余目录仍应输出");
+    assert!(stderr_str(&plain).contains("1 directory could not be read"));
+    assert!(
+        !stderr_str(&plain).contains("locked"),
+        "未指定 /ER 时不应列出目录"
+    );
+
+    assert_eq!(listed.status.code(), Some(4));
+    assert!(
+        stderr_str(&listed).contains("locked"),
+        "/ER 应列出无法读取的目录"
+    );
+}
+
+#[test]
+fn should_annotate_unreadable_directory_in_all_modes() {
+    let dir = create_basic_test_dir();
+    let locked = dir.path().join("locked");
+    fs::create_dir(&locked).unwrap();
+    File::create(locked.join("secret.txt")).unwrap();
+    if !deny_listing(&locked) {
+        return;
+    }
+
+    let streamed = run_treepp_in_dir(dir.path(), &["/f", "/nb"]);
+    let batched = run_treepp_in_dir(dir.path(), &["/b", "/f", "/nb"]);
+    let output_file = dir.path().join("tree.json");
+    let structured = run_treepp_in_dir(dir.path(), &["/b", "/o", output_file.to_str().unwrap()]);
+    allow_listing(&locked);
+
+    for output in [&streamed, &batched] {
+        assert!(
+            stdout_str(output).contains("locked [access denied]"),
+            "无法读取的目录应带有注记"
+        );
+        assert!(!stdout_str(output).contains("secret.txt"));
+    }
+    assert_eq!(structured.status.code(), Some(4));
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output_file).unwrap())
+        .expect("Should be valid JSON");
+    assert_eq!(
+        json["root"]["dirs"]["locked"]["error"].as_str(),
+        Some("access denied"),
+        "结构化输出应包含 error 字段"
+    );
+}
+
+#[test]
+fn should_scan_normally_within_time_limits() {
+    let dir = create_basic_test_dir();
+    for args in [
+        &["/f", "/nb", "/rt", "30s", "/to", "5m"][..],
+        &["/b", "/f", "/nb", "--read-timeout=30s"][..],
+    ] {
+        let output = run_treepp_in_dir(dir.path(), args);
+        assert!(output.status.success(), "{:?} 应成功", args);
+        assert!(stdout_str(&output).contains("file1.txt"));
+        assert!(!stdout_str(&output).contains("[timed out]"));
+    }
+}
+
+#[test]
+fn should_reject_invalid_timeout() {
+    let dir = create_basic_test_dir();
+    let output = run_treepp_in_dir(dir.path(), &["/to", "0"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr_str(&output).contains("positive duration"));
+}
+
+#[test]
+fn should_produce_stable_plain_output() {
+    let dir = create_basic_test_dir();
+    for args in [&["/f", "/pl"][..], &["/b", "/f", "--plain", "/rp"][..]] {
+        let output = run_treepp_in_dir(dir.path(), args);
+        assert!(output.status.success());
+        let stdout = stdout_str(&output);
+        assert!(!stdout.contains("Folder PATH"), "--plain 不应输出样板信息");
+        assert!(
+            !stdout.contains('├') && stdout.contains("+---"),
+            "--plain 应使用 ASCII 字符"
+        );
+        assert!(!stdout.contains('\r'));
+        for line in stdout.lines() {
+            assert_eq!(line, line.trim_end(), "行尾不应有空白: {:?}", line);
+        }
+    }
+
+    let with_report = run_treepp_in_dir(dir.path(), &["/b", "/f", "--plain", "/rp"]);
+    let without_report = run_treepp_in_dir(dir.path(), &["/b", "/f", "/rp", "--plain"]);
+    assert!(
+        stdout_str(&with_report).contains("files"),
+        "--plain 之后的选项应生效"
+    );
+    assert!(!stdout_str(&without_report).contains("files"));
+}
+
+#[test]
+fn should_apply_line_ending_to_stdout_and_file() {
+    let dir = create_basic_test_dir();
+    let output_file = dir.path().join("tree.txt");
+    let output = run_treepp_in_dir(
+        dir.path(),
+        &["/f", "/nb", "/crlf", "/o", output_file.to_str().unwrap()],
+    );
+    assert!(output.status.success());
+    let stdout = stdout_str(&output);
+    assert!(stdout.contains("file1.txt\r\n"), "标准输出应使用 CRLF");
+    let content = fs::read_to_string(&output_file).unwrap();
+    assert!(content.contains("file1.txt\r\n"), "输出文件应使用 CRLF");
+    assert_eq!(
+        content.matches('\n').count(),
+        content.matches("\r\n").count()
+    );
+
+    let lf = run_treepp_in_dir(dir.path(), &["/b", "/f", "/nb", "/lf"]);
+    assert!(!stdout_str(&lf).contains('\r'));
+}
+
+// ============================================================================
+// Settings Tests (treepp config)
+// ============================================================================
+
+/// Executes treepp with `APPDATA` pointing at `appdata`, so the settings
+/// file of the user running the tests is left alone.
+fn run_treepp_with_appdata(dir: &Path, appdata: &Path, args: &[&str]) -> Output {
+    Command::new(get_treepp_path())
+        .current_dir(dir)
+        .env("APPDATA", appdata)
+        .args(args)
+        .output()
+        .expect("Failed to execute treepp")
+}
+
+#[test]
+fn should_apply_default_excludes_from_settings() {
+    let dir = create_basic_test_dir();
+    let appdata = TempDir::new().unwrap();
+    let run = |args: &[&str]| run_treepp_with_appdata(dir.path(), appdata.path(), args);
+
+    let output = run(&["config", "add-exclude", "src", "*.md"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(appdata.path().join("treepp").join("config.toml").is_file());
+    let list = stdout_str(&run(&["config", "list"]));
+    assert_eq!(list.lines().collect::<Vec<_>>(), ["src", "*.md"]);
+
+    let tree = stdout_str(&run(&["/f", "/nb"]));
+    assert!(
+        !tree.contains("main.rs"),
+        "default excludes should apply: {tree}"
+    );
+    assert!(!tree.contains("file2.md"));
+    assert!(tree.contains("file1.txt"));
+
+    let output = run(&["config", "remove", "src"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert_eq!(stdout_str(&run(&["config", "list"])).trim(), "*.md");
+    assert!(stdout_str(&run(&["/f", "/nb"])).contains("main.rs"));
+}
+
+#[test]
+fn should_expand_presets_from_settings() {
+    let dir = create_basic_test_dir();
+    let appdata = TempDir::new().unwrap();
+    fs::create_dir(appdata.path().join("treepp")).unwrap();
+    fs::write(
+        appdata.path().join("treepp").join("config.toml"),
+        "[preset]\nfiles = \"/F /NB\"\nloop = \"@loop\"\n",
+    )
+    .unwrap();
+    let run = |args: &[&str]| run_treepp_with_appdata(dir.path(), appdata.path(), args);
+
+    let preset = run(&["@files", "/A"]);
+    let spelled_out = run(&["/F", "/NB", "/A"]);
+    assert!(preset.status.success(), "stderr: {}", stderr_str(&preset));
+    assert_eq!(stdout_str(&preset), stdout_str(&spelled_out));
+    assert!(stdout_str(&preset).contains("main.rs"));
+
+    let output = run(&["/je", "@loop"]);
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_str(stderr_str(&output).trim()).unwrap();
+    assert_eq!(report["code"], "E110");
+}
+
+#[test]
+fn should_scan_bookmarked_directory() {
+    let dir = create_basic_test_dir();
+    let appdata = TempDir::new().unwrap();
+
+    let in_dir = |args: &[&str]| run_treepp_with_appdata(dir.path(), appdata.path(), args);
+    let elsewhere = |args: &[&str]| run_treepp_with_appdata(appdata.path(), appdata.path(), args);
+
+    let output = in_dir(&["bookmark", "add", "code", "src"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    let list = stdout_str(&elsewhere(&["bookmark", "list"]));
+    assert!(list.starts_with("code  "), "unexpected list: {list}");
+
+    // Relative paths are stored as absolute ones, so the bookmark works anywhere.
+    let output = elsewhere(&["@code", "/F", "/NB"]);
+    assert!(output.status.success(), "stderr: {}", stderr_str(&output));
+    assert!(stdout_str(&output).contains("main.rs"));
+
+    assert_eq!(
+        in_dir(&["bookmark", "add", "x", "missing"]).status.code(),
+        Some(1)
+    );
+}
@@ -0,0 +1,308 @@
+//! Randomized golden-file compatibility tests against the native tree command.
+//!
+//! Each case generates a pseudo-random directory structure from a seed, runs
+//! both native `tree /F /A` and treepp on it, and asserts that the outputs
+//! are byte-identical once the volume banner and line endings are normalized.
+//!
+//! # Running
+//!
+//! The harness spawns the native `tree` command and is therefore gated behind
+//! the `compat-tests` feature and Windows:
+//!
+//! ```text
+//! cargo build
+//! cargo test --features compat-tests --test golden_compat_test
+//! ```
+//!
+//! # Reproducing Failures
+//!
+//! Every failure reports its seed. Set `TREEPP_COMPAT_SEED` to rerun a single
+//! case, or `TREEPP_COMPAT_CASES` to change how many seeds are generated.
+//!
+//! File: tests/golden_compat_test.rs
+//! Author: WaterRun
+//! Date: 2026-10-16
+
+#![cfg(all(windows, feature = "compat-tests"))]
+
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tempfile::TempDir;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Number of randomized cases generated when `TREEPP_COMPAT_CASES` is unset.
+const DEFAULT_CASES: u64 = 32;
+
+/// Base seed mixed into every case so runs are reproducible.
+const BASE_SEED: u64 = 0x7EE0_0C0F_FEE5_EED5;
+
+/// Maximum directory depth of a generated structure.
+const MAX_DEPTH: usize = 4;
+
+/// Maximum number of files generated in one directory.
+const MAX_FILES_PER_DIR: u64 = 5;
+
+/// Maximum number of subdirectories generated in one directory.
+const MAX_DIRS_PER_DIR: u64 = 4;
+
+/// Characters used for generated names, including some that stress sorting.
+const NAME_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCXYZ0123456789_-";
+
+/// Extensions attached to generated file names.
+const EXTENSIONS: &[&str] = &["", ".txt", ".rs", ".md", ".tar.gz", ".A"];
+
+// ============================================================================
+// Random Generation
+// ============================================================================
+
+/// Minimal xorshift64* generator.
+///
+/// The harness only needs reproducible, well-spread values, so a tiny
+/// generator avoids pulling in an external dependency.
+struct XorShift(u64);
+
+impl XorShift {
+    /// Creates a generator from a seed; zero is remapped to a fixed value.
+    fn new(seed: u64) -> Self {
+        Self(if seed == 0 { BASE_SEED } else { seed })
+    }
+
+    /// Returns the next pseudo-random value.
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a value in `0..bound`.
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            0
+        } else {
+            self.next_u64() % bound
+        }
+    }
+
+    /// Returns `true` with a probability of `1 / n`.
+    fn one_in(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+}
+
+/// Generates a name that is unique (case-insensitively) within `taken`.
+///
+/// Names occasionally start with a dot, contain a space or are a single
+/// character, since those are the cases most likely to break ordering.
+fn random_name(rng: &mut XorShift, taken: &mut HashSet<String>, is_file: bool) -> String {
+    loop {
+        let len = 1 + rng.below(10) as usize;
+        let mut name = String::with_capacity(len + 8);
+        if rng.one_in(8) {
+            name.push('.');
+        }
+        for i in 0..len {
+            if i > 0 && i + 1 < len && rng.one_in(12) {
+                name.push(' ');
+            } else {
+                let c = NAME_CHARS[rng.below(NAME_CHARS.len() as u64) as usize];
+                name.push(c as char);
+            }
+        }
+        if is_file {
+            name.push_str(EXTENSIONS[rng.below(EXTENSIONS.len() as u64) as usize]);
+        }
+        if taken.insert(name.to_lowercase()) {
+            return name;
+        }
+    }
+}
+
+/// Populates `dir` with a random structure of files and subdirectories.
+fn populate(rng: &mut XorShift, dir: &Path, depth: usize) {
+    let mut taken = HashSet::new();
+
+    for _ in 0..rng.below(MAX_FILES_PER_DIR + 1) {
+        let name = random_name(rng, &mut taken, true);
+        let mut file = File::create(dir.join(&name)).expect("Failed to create file");
+        let size = rng.below(64) as usize;
+        file.write_all(&vec![b'x'; size])
+            .expect("Failed to write file");
+    }
+
+    if depth >= MAX_DEPTH {
+        return;
+    }
+
+    let dir_count = if depth == 0 {
+        rng.below(MAX_DIRS_PER_DIR) + 1
+    } else {
+        rng.below(MAX_DIRS_PER_DIR)
+    };
+    for _ in 0..dir_count {
+        let name = random_name(rng, &mut taken, false);
+        let sub = dir.join(&name);
+        fs::create_dir(&sub).expect("Failed to create directory");
+        if !rng.one_in(4) {
+            populate(rng, &sub, depth + 1);
+        }
+    }
+}
+
+/// Creates a temporary directory holding the structure for `seed`.
+fn create_random_tree(seed: u64) -> TempDir {
+    let dir = TempDir::new().expect("Failed to create temporary directory");
+    let mut rng = XorShift::new(seed ^ BASE_SEED);
+    populate(&mut rng, dir.path(), 0);
+    dir
+}
+
+/// Returns the seeds to run, honoring `TREEPP_COMPAT_SEED` and `TREEPP_COMPAT_CASES`.
+fn seeds() -> Vec<u64> {
+    if let Ok(seed) = env::var("TREEPP_COMPAT_SEED") {
+        let seed = seed
+            .trim()
+            .parse()
+            .expect("TREEPP_COMPAT_SEED must be an integer");
+        return vec![seed];
+    }
+    let cases = env::var("TREEPP_COMPAT_CASES")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_CASES);
+    (1..=cases).collect()
+}
+
+// ============================================================================
+// Command Execution
+// ============================================================================
+
+/// Locates the treepp executable in the debug or release target directory.
+///
+/// # Panics
+///
+/// Panics if treepp.exe is not found in either location.
+fn get_treepp_path() -> PathBuf {
+    let debug_path = PathBuf::from("target/debug/treepp.exe");
+    if debug_path.exists() {
+        return debug_path;
+    }
+    let release_path = PathBuf::from("target/release/treepp.exe");
+    if release_path.exists() {
+        return release_path;
+    }
+    panic!("treepp not built, please run cargo build first");
+}
+
+/// Runs native `tree /F /A` on `path` and returns its decoded stdout.
+fn run_native_tree(path: &Path) -> String {
+    let output = Command::new("cmd")
+        .args(["/C", "tree", "/F", "/A"])
+        .arg(path)
+        .output()
+        .expect("Failed to execute native tree command");
+    let (stdout, _, _) = encoding_rs::GBK.decode(&output.stdout);
+    stdout.into_owned()
+}
+
+/// Runs `treepp /F /A` on `path` and returns its stdout.
+fn run_treepp(path: &Path) -> String {
+    let output = Command::new(get_treepp_path())
+        .args(["/F", "/A"])
+        .arg(path)
+        .output()
+        .expect("Failed to execute treepp command");
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+// ============================================================================
+// Normalization and Comparison
+// ============================================================================
+
+/// Normalizes output for byte-level comparison.
+///
+/// Converts CRLF to LF and drops the two volume banner lines, which depend on
+/// the volume label and may come from treepp's banner cache. Everything else,
+/// including trailing whitespace, is kept as-is.
+fn normalize(output: &str) -> String {
+    let unified = output.replace("\r\n", "\n");
+    let mut lines = unified.split('\n');
+    lines.next();
+    lines.next();
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+/// Returns the first differing line as `(line number, native, treepp)`.
+fn first_difference<'a>(native: &'a str, treepp: &'a str) -> Option<(usize, &'a str, &'a str)> {
+    let mut native_lines = native.split('\n');
+    let mut treepp_lines = treepp.split('\n');
+    let mut index = 0;
+    loop {
+        index += 1;
+        match (native_lines.next(), treepp_lines.next()) {
+            (None, None) => return None,
+            (n, t) if n != t => {
+                return Some((index, n.unwrap_or("<missing>"), t.unwrap_or("<missing>")));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Runs one seed and returns a failure report if the outputs differ.
+fn check_seed(seed: u64) -> Option<String> {
+    let dir = create_random_tree(seed);
+    let native = normalize(&run_native_tree(dir.path()));
+    let treepp = normalize(&run_treepp(dir.path()));
+
+    let (line, n, t) = first_difference(&native, &treepp)?;
+    Some(format!(
+        "seed {seed}: first difference at L{line}\n  N={n:?}\n  T={t:?}\n\
+         --- native ---\n{native}\n--- treepp ---\n{treepp}"
+    ))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_generate_identical_structures_for_same_seed() {
+        let mut a = XorShift::new(42);
+        let mut b = XorShift::new(42);
+        for _ in 0..16 {
+            assert_eq!(a.next_u64(), b.next_u64(), "相同种子应生成相同序列");
+        }
+    }
+
+    #[test]
+    fn should_drop_banner_and_unify_line_endings() {
+        let raw = "Folder PATH listing\r\nVolume serial\r\nC:\\TMP\r\n|   a.txt\r\n|   \r\n";
+        assert_eq!(normalize(raw), "C:\\TMP\n|   a.txt\n|   \n");
+    }
+
+    #[test]
+    fn should_match_native_tree_for_random_structures() {
+        let failures: Vec<String> = seeds().into_iter().filter_map(check_seed).collect();
+        assert!(
+            failures.is_empty(),
+            "{} 个随机结构与原生 tree 输出不一致:\n{}",
+            failures.len(),
+            failures.join("\n\n")
+        );
+    }
+}
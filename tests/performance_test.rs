@@ -368,13 +368,13 @@ fn create_large_test_dir() -> TempDir {
                     .join("tests/unit")
                     .join(format!("test_unit_{}.rs", j)),
             )
-                .unwrap();
+            .unwrap();
             File::create(
                 module
                     .join("tests/integration")
                     .join(format!("test_int_{}.rs", j)),
             )
-                .unwrap();
+            .unwrap();
         }
 
         File::create(module.join("Cargo.toml")).unwrap();
@@ -416,12 +416,7 @@ fn create_many_files_dir() -> TempDir {
 
     fs::create_dir(dir.path().join("files")).unwrap();
     for i in 0..500 {
-        File::create(
-            dir.path()
-                .join("files")
-                .join(format!("file_{:04}.txt", i)),
-        )
-            .unwrap();
+        File::create(dir.path().join("files").join(format!("file_{:04}.txt", i))).unwrap();
     }
 
     dir
@@ -735,6 +730,49 @@ mod feature_tests {
         );
     }
 
+    /// Tests streaming output throughput against batch mode.
+    #[test]
+    fn benchmark_stream_output_throughput() {
+        let dir = create_large_test_dir();
+
+        let batch = benchmark_treepp(dir.path(), &["/F", "/NB", "/B"], "batch /F /NB /B");
+        let stream = benchmark_treepp(dir.path(), &["/F", "/NB"], "stream /F /NB");
+
+        let ratio = stream.duration_ms / batch.duration_ms;
+        println!(
+            "Streaming output: {:.2} ms vs batch {:.2} ms ({:.2}x)",
+            stream.duration_ms, batch.duration_ms, ratio
+        );
+        assert!(stream.duration_ms > 0.0);
+    }
+
+    /// Tests warm scans with the scan cache against uncached scans.
+    #[test]
+    fn benchmark_scan_cache_warm_start() {
+        let dir = create_large_test_dir();
+
+        let uncached = benchmark_treepp(dir.path(), &["/F", "/NB", "/B"], "uncached /F /NB /B");
+        benchmark_treepp(
+            dir.path(),
+            &["/F", "/NB", "/B", "/CA"],
+            "cold cache /F /NB /B /CA",
+        );
+        let warm = benchmark_treepp(
+            dir.path(),
+            &["/F", "/NB", "/B", "/CA"],
+            "warm cache /F /NB /B /CA",
+        );
+        benchmark_treepp(dir.path(), &["/NB", "/NC"], "discard cache /NB /NC");
+
+        println!(
+            "Warm cache: {:.2} ms vs uncached {:.2} ms ({:.2}x)",
+            warm.duration_ms,
+            uncached.duration_ms,
+            warm.duration_ms / uncached.duration_ms
+        );
+        assert!(warm.duration_ms > 0.0);
+    }
+
     /// Tests performance with different thread counts.
     #[test]
     fn benchmark_thread_counts() {
@@ -773,8 +811,7 @@ mod feature_tests {
             "combined /F /S /DT /HR /RP",
         );
 
-        let change =
-            ((combined.duration_ms - baseline.duration_ms) / baseline.duration_ms) * 100.0;
+        let change = ((combined.duration_ms - baseline.duration_ms) / baseline.duration_ms) * 100.0;
         println!(
             "Combined features impact: {:.2} ms -> {:.2} ms ({:+.1}%)",
             baseline.duration_ms, combined.duration_ms, change
@@ -1194,10 +1231,7 @@ mod full_benchmark {
                 "/F /S /DT /HR /RP /FP",
             ),
             (&["/F", "/NB", "/B"][..], "/F /NB /B"),
-            (
-                &["/F", "/NB", "/B", "/S", "/DT"][..],
-                "/F /NB /B /S /DT",
-            ),
+            (&["/F", "/NB", "/B", "/S", "/DT"][..], "/F /NB /B /S /DT"),
         ];
 
         let mut report = BenchmarkReport::new("Combined Features Performance");
@@ -1294,7 +1328,7 @@ mod stress_tests {
                         .join("tests/integration")
                         .join(format!("int_test_{}.rs", j)),
                 )
-                    .unwrap();
+                .unwrap();
             }
 
             File::create(module.join("Cargo.toml")).unwrap();